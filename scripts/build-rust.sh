@@ -1,3 +1,3 @@
 #!/bin/bash
-cd "$PROJECT_DIR/synth-core"
-~/.cargo/bin/cargo build --release
+cd "$PROJECT_DIR"
+~/.cargo/bin/cargo build --release -p synth-ffi
@@ -8,7 +8,7 @@ swiftc \
   AIPanel.swift \
   main.swift \
   -import-objc-header BridgingHeader.h \
-  -L ../synth-core/target/release \
-  -I ../synth-core \
-  -lsynth_core \
+  -L ../target/release \
+  -I ../synth-ffi \
+  -lsynth_ffi \
   -o Synth
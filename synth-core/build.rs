@@ -0,0 +1,20 @@
+//! Generates the gRPC messages and service from proto/synth.proto when
+//! the `grpc` feature is on, with `protox` so no `protoc` is needed.
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    #[cfg(feature = "grpc")]
+    grpc();
+}
+
+#[cfg(feature = "grpc")]
+fn grpc() {
+    println!("cargo:rerun-if-changed=proto/synth.proto");
+    let files = match protox::compile(["proto/synth.proto"], ["proto"]) {
+        Ok(files) => files,
+        Err(e) => panic!("proto/synth.proto: {e}"),
+    };
+    if let Err(e) = tonic_prost_build::configure().compile_fds(files) {
+        panic!("generating the gRPC service: {e}");
+    }
+}
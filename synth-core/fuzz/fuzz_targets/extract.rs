@@ -0,0 +1,26 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use synth_core::code::Language;
+use synth_core::extract::{extract_bytes, Format};
+
+const FORMATS: [Format; 10] = [
+    Format::Text,
+    Format::Csv,
+    Format::Tsv,
+    Format::Json,
+    Format::Xml,
+    Format::Yaml,
+    Format::Html,
+    Format::Code(Language::Rust),
+    Format::Pptx,
+    Format::Pdf,
+];
+
+// The first byte picks the format. This calls `extract_bytes` directly, not
+// the fallible wrapper, so that panics are reported.
+fuzz_target!(|data: &[u8]| {
+    if let Some((&selector, bytes)) = data.split_first() {
+        let _ = extract_bytes(bytes, FORMATS[usize::from(selector) % FORMATS.len()]);
+    }
+});
@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use synth_core::json;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(text) = std::str::from_utf8(data) {
+        if let Ok(value) = json::parse(text) {
+            // Whatever parses must serialize to valid JSON.
+            json::parse(&value.to_string()).unwrap();
+        }
+    }
+});
@@ -0,0 +1,244 @@
+//! The passages of a document a question about it is answered from.
+//!
+//! A document that fits the prompt goes in whole. A longer one is split
+//! into chunks, ranked against the question by the cosine similarity of
+//! their embeddings when an [embedding model](crate::embeddings) is
+//! configured, or by shared content words without one, and the best
+//! chunks that fit go in, in document order. Chunks and their embeddings
+//! are indexed once per document text and the last few indexes kept, so a
+//! follow-up question only embeds itself; [`prefetch`](crate::prefetch)
+//! builds the index when a document is opened.
+
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::chunk::{chunk_text, Chunk};
+use crate::embeddings;
+use crate::error::Result;
+use crate::extract::extract_text;
+use crate::hash::{hex, Sha256};
+use crate::progress::{Progress, Stage};
+use crate::words::{cosine, term_counts};
+
+/// Documents longer than this many characters are excerpted.
+pub const BUDGET: usize = 48_000;
+
+/// Characters per indexed chunk.
+const CHUNK_CHARS: usize = 2_000;
+
+/// Indexes kept; the least recently built goes first.
+const CACHED_INDEXES: usize = 8;
+
+/// Marks where chunks were left out between two excerpts.
+const GAP: &str = "\n\n[...]\n\n";
+
+/// A long document's chunks, with their embeddings when a model was
+/// configured.
+pub struct Index {
+    /// SHA-256 of the document text.
+    digest: String,
+    /// The [`embeddings::generation`] of the vectors.
+    generation: Option<u64>,
+    pub chunks: Vec<Chunk>,
+    terms: Vec<HashMap<String, f64>>,
+    vectors: Option<Vec<Vec<f32>>>,
+}
+
+impl Index {
+    /// Whether the chunks were embedded.
+    pub fn is_embedded(&self) -> bool {
+        self.vectors.is_some()
+    }
+
+    /// Each chunk's similarity to `question`.
+    fn scores(&self, question: &str) -> Vec<f64> {
+        let embedded = self.vectors.as_ref().and_then(|vectors| {
+            let query = embeddings::embed(&[question]).ok()?.pop()?;
+            Some(
+                vectors
+                    .iter()
+                    .map(|v| embeddings::similarity(&query, v) as f64)
+                    .collect(),
+            )
+        });
+        embedded.unwrap_or_else(|| {
+            let terms = term_counts(question);
+            self.terms.iter().map(|t| cosine(&terms, t)).collect()
+        })
+    }
+}
+
+fn lock() -> MutexGuard<'static, VecDeque<Arc<Index>>> {
+    static INDEXES: Mutex<VecDeque<Arc<Index>>> = Mutex::new(VecDeque::new());
+    INDEXES.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn digest(document: &str) -> String {
+    let mut hasher = Sha256::default();
+    hasher.update(document.as_bytes());
+    hex(&hasher.finish())
+}
+
+/// The kept index of `document` under the configured embedding model,
+/// if there is one.
+pub fn indexed(document: &str) -> Option<Arc<Index>> {
+    find(&digest(document))
+}
+
+fn find(digest: &str) -> Option<Arc<Index>> {
+    let generation = embeddings::is_configured().then(embeddings::generation);
+    lock()
+        .iter()
+        .find(|index| index.digest == digest && index.generation == generation)
+        .cloned()
+}
+
+/// The index of `document`, built unless one for the same text and
+/// embedding model is kept. Chunks are embedded with the configured
+/// model; if that fails they are ranked by words instead.
+pub fn index(document: &str) -> Arc<Index> {
+    build(document, |_| {}, 0.0)
+}
+
+/// Extract the document at `path` and [`index`] it. `progress` hears
+/// about extracting, chunking and embedding each chunk.
+pub fn index_document(path: &Path, mut progress: impl FnMut(&Progress)) -> Result<Arc<Index>> {
+    let name = path.display().to_string();
+    progress(&Progress {
+        stage: Stage::Extracting,
+        completed: 0,
+        total: 1,
+        percent: 0.0,
+        item: Some(&name),
+    });
+    let text = extract_text(path)?;
+    Ok(build(&text, progress, 20.0))
+}
+
+fn build(document: &str, mut progress: impl FnMut(&Progress), start: f64) -> Arc<Index> {
+    let digest = digest(document);
+    if let Some(index) = find(&digest) {
+        progress(&Progress {
+            stage: Stage::Embedding,
+            completed: index.chunks.len(),
+            total: index.chunks.len(),
+            percent: 100.0,
+            item: None,
+        });
+        return index;
+    }
+    progress(&Progress {
+        stage: Stage::Chunking,
+        completed: 0,
+        total: 1,
+        percent: start,
+        item: None,
+    });
+    let generation = embeddings::is_configured().then(embeddings::generation);
+    let chunks = chunk_text(document, CHUNK_CHARS);
+    let terms = chunks.iter().map(|c| term_counts(&c.text)).collect();
+    let vectors = match generation {
+        Some(_) => embed_chunks(&chunks, &mut progress, start),
+        // Without a model there is nothing to embed.
+        None => {
+            progress(&Progress {
+                stage: Stage::Chunking,
+                completed: 1,
+                total: 1,
+                percent: 100.0,
+                item: None,
+            });
+            None
+        }
+    };
+    let index = Arc::new(Index {
+        digest,
+        generation: vectors.as_ref().and(generation),
+        chunks,
+        terms,
+        vectors,
+    });
+    let mut indexes = lock();
+    indexes.retain(|kept| kept.digest != index.digest);
+    if indexes.len() >= CACHED_INDEXES {
+        indexes.pop_front();
+    }
+    indexes.push_back(index.clone());
+    index
+}
+
+/// Each chunk's embedding, reported from `start` percent on; `None` if
+/// one fails.
+fn embed_chunks(
+    chunks: &[Chunk],
+    progress: &mut impl FnMut(&Progress),
+    start: f64,
+) -> Option<Vec<Vec<f32>>> {
+    let mut report = |completed: usize| {
+        progress(&Progress {
+            stage: Stage::Embedding,
+            completed,
+            total: chunks.len(),
+            percent: start + (100.0 - start) * completed as f64 / chunks.len().max(1) as f64,
+            item: None,
+        })
+    };
+    let mut vectors = Vec::new();
+    for (index, chunk) in chunks.iter().enumerate() {
+        report(index);
+        vectors.push(embeddings::embed(&[&chunk.text]).ok()?.pop()?);
+    }
+    report(chunks.len());
+    Some(vectors)
+}
+
+/// Build the index a question about `document` will use, if it is long
+/// enough to be excerpted.
+pub(crate) fn prime(document: &str) {
+    if document.chars().nth(BUDGET).is_some() {
+        index(document);
+    }
+}
+
+/// Forget the indexes.
+pub(crate) fn clear() {
+    lock().clear();
+}
+
+/// `document`, or for one over [`BUDGET`] the chunks most similar to
+/// `question` that fit it, in document order with gaps marked `[...]`.
+pub fn excerpt(document: &str, question: &str) -> String {
+    if document.chars().nth(BUDGET).is_none() {
+        return document.to_string();
+    }
+    let index = index(document);
+    let scores = index.scores(question);
+    let mut ranked: Vec<usize> = (0..index.chunks.len()).collect();
+    ranked.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]).then(a.cmp(&b)));
+    let mut chosen = Vec::new();
+    let mut used = 0;
+    for i in ranked {
+        let chars = index.chunks[i].end - index.chunks[i].start + GAP.len();
+        if used + chars > BUDGET {
+            continue;
+        }
+        used += chars;
+        chosen.push(i);
+    }
+    chosen.sort_unstable();
+    let mut excerpt = String::new();
+    let mut next = 0;
+    for i in chosen {
+        let chunk = &index.chunks[i];
+        if chunk.start != next {
+            excerpt.push_str(GAP);
+        }
+        excerpt.push_str(&chunk.text);
+        next = chunk.end;
+    }
+    if next != document.chars().count() {
+        excerpt.push_str(GAP);
+    }
+    excerpt
+}
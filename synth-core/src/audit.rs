@@ -0,0 +1,302 @@
+//! Optional append-only audit log of chat calls, one JSON object per line.
+//!
+//! Each record holds the time, the profile and model that answered (or the
+//! error), the caller's user and tenant tags, and the prompt and reply, either in full
+//! or as SHA-256 hashes. The log rotates to `audit.1.jsonl`,
+//! `audit.2.jsonl`, ... once it reaches the configured size. With
+//! [`crate::encryption`] configured, each line is sealed and written as hex;
+//! [`seal_existing`] seals the lines written before the key.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::encryption;
+use crate::error::{Error, Result};
+use crate::hash::{self, sha256_hex};
+use crate::json::{self, Value};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    pub dir: PathBuf,
+    /// Store SHA-256 hashes instead of prompt and reply text.
+    pub hash_content: bool,
+    /// Rotate once the current file reaches this size.
+    pub max_bytes: u64,
+    /// Rotated files kept besides the current one.
+    pub keep: usize,
+}
+
+impl Config {
+    /// Read `{"dir": "...", "content": "full" | "hash", "max_bytes":
+    /// 10485760, "keep": 5}`; only `dir` is required.
+    pub fn from_json(value: &Value) -> Result<Self> {
+        let dir = value
+            .get("dir")
+            .and_then(Value::as_str)
+            .ok_or_else(|| Error::Parse("audit config needs a dir".into()))?;
+        let hash_content = match value.get("content").and_then(Value::as_str) {
+            Some("full") | None => false,
+            Some("hash") => true,
+            Some(other) => return Err(Error::Parse(format!("unknown content mode '{other}'"))),
+        };
+        let number = |key| value.get(key).and_then(Value::as_f64).filter(|n| *n >= 0.0);
+        Ok(Config {
+            dir: PathBuf::from(dir),
+            hash_content,
+            max_bytes: number("max_bytes").map_or(10 << 20, |n| (n as u64).max(4096)),
+            keep: number("keep").map_or(5, |n| n as usize),
+        })
+    }
+
+    fn file(&self, generation: usize) -> PathBuf {
+        match generation {
+            0 => self.dir.join("audit.jsonl"),
+            n => self.dir.join(format!("audit.{n}.jsonl")),
+        }
+    }
+}
+
+/// What one chat call did, as recorded.
+#[derive(Debug, Clone)]
+pub struct Entry<'a> {
+    pub prompt: &'a str,
+    /// The raw backend reply, or the error that ended the call.
+    pub outcome: std::result::Result<&'a str, &'a Error>,
+    pub backend: Option<&'a str>,
+    pub model: Option<&'a str>,
+    pub user_id: Option<&'a str>,
+    pub tenant_id: Option<&'a str>,
+    pub duration: Duration,
+}
+
+static CONFIG: Mutex<Option<Config>> = Mutex::new(None);
+
+/// The name every line is sealed under: lines move between files as the
+/// log rotates, but not between logs.
+const SEALED_NAME: &str = "audit log";
+
+fn lock() -> MutexGuard<'static, Option<Config>> {
+    CONFIG.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Start (or with `None`, stop) audit logging.
+pub fn configure(config: Option<Config>) -> Result<()> {
+    if let Some(config) = &config {
+        fs::create_dir_all(&config.dir)?;
+    }
+    *lock() = config;
+    Ok(())
+}
+
+fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |d| (d.as_millis() as f64) / 1000.0)
+}
+
+/// Append a record if logging is on. Failures to write are ignored so an
+/// unwritable log never breaks chat.
+pub fn record(entry: &Entry) {
+    let guard = lock();
+    let Some(config) = guard.as_ref() else {
+        return;
+    };
+    let optional = |s: Option<&str>| s.map_or(Value::Null, Value::from);
+    let mut pairs = vec![
+        ("time", Value::from(now())),
+        ("backend", optional(entry.backend)),
+        ("model", optional(entry.model)),
+        ("user_id", optional(entry.user_id)),
+        ("tenant_id", optional(entry.tenant_id)),
+        (
+            "duration_ms",
+            Value::from(entry.duration.as_millis() as f64),
+        ),
+        ("ok", Value::from(entry.outcome.is_ok())),
+    ];
+    let mut content = |key: &'static str, text: &str| {
+        if config.hash_content {
+            let key = if key == "prompt" {
+                "prompt_sha256"
+            } else {
+                "response_sha256"
+            };
+            pairs.push((key, Value::from(sha256_hex(text.as_bytes()))));
+        } else {
+            pairs.push((key, Value::from(text)));
+        }
+    };
+    content("prompt", entry.prompt);
+    match entry.outcome {
+        Ok(reply) => content("response", reply),
+        Err(error) => {
+            pairs.push(("error", Value::from(error.kind())));
+            pairs.push(("message", Value::from(error.to_string())));
+        }
+    }
+    let Ok(mut line) = encryption::seal_line(&Value::object(pairs).to_string(), SEALED_NAME) else {
+        return;
+    };
+    line.push('\n');
+    let _ = append(config, &line);
+}
+
+fn append(config: &Config, line: &str) -> Result<()> {
+    let current = config.file(0);
+    let size = fs::metadata(&current).map_or(0, |m| m.len());
+    if size > 0 && size + line.len() as u64 > config.max_bytes {
+        rotate(config);
+    }
+    // One write per line in append mode, so lines from several processes
+    // never interleave.
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&current)?
+        .write_all(line.as_bytes())?;
+    Ok(())
+}
+
+fn rotate(config: &Config) {
+    if config.keep == 0 {
+        let _ = fs::remove_file(config.file(0));
+        return;
+    }
+    let _ = fs::remove_file(config.file(config.keep));
+    for generation in (0..config.keep).rev() {
+        let _ = fs::rename(config.file(generation), config.file(generation + 1));
+    }
+}
+
+/// Which records [`query`] returns.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Filter {
+    /// Seconds since the Unix epoch, inclusive.
+    pub since: Option<f64>,
+    pub until: Option<f64>,
+    pub backend: Option<String>,
+    pub user_id: Option<String>,
+    pub tenant_id: Option<String>,
+    /// Only the most recent this many matches (0 for all).
+    pub limit: usize,
+}
+
+impl Filter {
+    /// Read `{"since": 1700000000, "until": ..., "backend": "...",
+    /// "user_id": "...", "tenant_id": "...", "limit": 100}`; every key is
+    /// optional.
+    pub fn from_json(value: &Value) -> Self {
+        let text = |key| value.get(key).and_then(Value::as_str).map(String::from);
+        Filter {
+            since: value.get("since").and_then(Value::as_f64),
+            until: value.get("until").and_then(Value::as_f64),
+            backend: text("backend"),
+            user_id: text("user_id"),
+            tenant_id: text("tenant_id"),
+            limit: value.get("limit").and_then(Value::as_f64).unwrap_or(0.0) as usize,
+        }
+    }
+
+    fn matches(&self, record: &Value) -> bool {
+        let time = record.get("time").and_then(Value::as_f64).unwrap_or(0.0);
+        let same = |key, wanted: &Option<String>| {
+            wanted
+                .as_deref()
+                .is_none_or(|w| record.get(key).and_then(Value::as_str) == Some(w))
+        };
+        self.since.is_none_or(|since| time >= since)
+            && self.until.is_none_or(|until| time <= until)
+            && same("backend", &self.backend)
+            && same("user_id", &self.user_id)
+            && same("tenant_id", &self.tenant_id)
+    }
+}
+
+/// Matching records from the current and rotated logs, oldest first.
+pub fn query(filter: &Filter) -> Result<Vec<Value>> {
+    let config = lock()
+        .clone()
+        .ok_or_else(|| Error::Unsupported("audit logging is not configured".into()))?;
+    let mut records = Vec::new();
+    for generation in (0..=config.keep).rev() {
+        records.extend(
+            read(&config.file(generation))?
+                .into_iter()
+                .filter(|r| filter.matches(r)),
+        );
+    }
+    if filter.limit > 0 && records.len() > filter.limit {
+        records.drain(..records.len() - filter.limit);
+    }
+    Ok(records)
+}
+
+/// Seal the lines of the current and rotated logs written plain before an
+/// encryption key was configured, which [`query`] refuses once it is. Run
+/// once, after configuring the key. Returns how many lines were sealed,
+/// none while logging is off.
+pub fn seal_existing() -> Result<usize> {
+    if !encryption::is_enabled() {
+        return Err(Error::Unsupported(
+            "lines are sealed under a key, and none is configured".into(),
+        ));
+    }
+    // Held throughout, so no line is appended to a file being rewritten.
+    let guard = lock();
+    let Some(config) = guard.as_ref() else {
+        return Ok(0);
+    };
+    let mut sealed = 0;
+    for generation in 0..=config.keep {
+        let path = config.file(generation);
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+        let mut lines = String::new();
+        let mut changed = false;
+        for line in text.lines() {
+            let is_sealed = hash::unhex(line).is_some_and(|data| encryption::is_sealed(&data));
+            match is_sealed {
+                true => lines.push_str(line),
+                false => {
+                    lines.push_str(&encryption::seal_line(line, SEALED_NAME)?);
+                    sealed += 1;
+                    changed = true;
+                }
+            }
+            lines.push('\n');
+        }
+        if changed {
+            let tmp = path.with_extension("jsonl.tmp");
+            fs::write(&tmp, lines)?;
+            fs::rename(&tmp, &path)?;
+        }
+    }
+    Ok(sealed)
+}
+
+fn read(path: &Path) -> Result<Vec<Value>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    // A torn final line from a crash is skipped rather than failing.
+    let lines: Vec<&str> = text.lines().collect();
+    let mut records = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        let what = format!("{} line {}", path.display(), index + 1);
+        let line = match encryption::open_line(line, SEALED_NAME, &what) {
+            Ok(line) => line,
+            Err(_) if index + 1 == lines.len() => continue,
+            Err(e) => return Err(e),
+        };
+        records.extend(json::parse(&line).ok());
+    }
+    Ok(records)
+}
@@ -0,0 +1,562 @@
+//! Named chat backend profiles.
+//!
+//! Profiles are configured once from JSON and selected per call by name;
+//! calls without a name use the default profile. Until configured, the
+//! only profile is "kiro" (the `kiro-cli` subprocess). When the chosen
+//! profile fails or times out, the profiles in the fallback chain are tried
+//! in order.
+//!
+//! A call may carry a sampling seed. Ollama and llama.cpp honour it, so the
+//! same prompt, model and seed give the same reply; kiro has no seed and
+//! ignores it, and mock replies are deterministic anyway. Ollama takes the
+//! seed as a JSON number, so it refuses seeds above 2^53.
+
+use std::cell::RefCell;
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::{Error, Result};
+use crate::http::{self, RequestOptions, Url};
+use crate::json::{self, Value};
+use crate::shutdown::{self, Process};
+use crate::{chat, mock, policy};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Kind {
+    /// `<command> chat --no-interactive -a <prompt>`.
+    Kiro { command: String },
+    /// An Ollama server's `/api/generate` endpoint, possibly behind an
+    /// authenticating gateway.
+    Ollama {
+        url: Url,
+        model: String,
+        http: HttpSettings,
+    },
+    /// Canned or scripted replies, for host test suites.
+    Mock { script: mock::Script },
+    /// A GGUF model run fully offline by llama.cpp's `llama-cli`.
+    #[cfg(feature = "local-llm")]
+    LlamaCpp {
+        command: String,
+        model: std::path::PathBuf,
+        max_tokens: u32,
+    },
+}
+
+impl Kind {
+    /// The `"type"` the profile was configured with.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Kind::Kiro { .. } => "kiro",
+            Kind::Ollama { .. } => "ollama",
+            Kind::Mock { .. } => "mock",
+            #[cfg(feature = "local-llm")]
+            Kind::LlamaCpp { .. } => "llama.cpp",
+        }
+    }
+}
+
+/// Where an API key comes from. Keys are resolved on every call, so
+/// rotated environment variables and host secrets take effect at once.
+/// A literal key is redacted from `Debug` output.
+#[derive(Clone, PartialEq)]
+pub enum Secret {
+    Literal(String),
+    /// An environment variable name.
+    Env(String),
+    /// A name passed to the host's secret provider.
+    Host(String),
+}
+
+impl Secret {
+    /// The current value; `None` when unset or empty.
+    pub fn resolve(&self) -> Option<String> {
+        match self {
+            Secret::Literal(key) => Some(key.clone()),
+            Secret::Env(var) => std::env::var(var).ok(),
+            Secret::Host(name) => secret_provider().and_then(|provider| provider(name)),
+        }
+        .filter(|key| !key.is_empty())
+    }
+}
+
+impl std::fmt::Debug for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Secret::Literal(_) => f.write_str("Literal(<redacted>)"),
+            Secret::Env(var) => f.debug_tuple("Env").field(var).finish(),
+            Secret::Host(name) => f.debug_tuple("Host").field(name).finish(),
+        }
+    }
+}
+
+/// Authentication and routing for HTTP profiles. Requests go out as
+/// plain HTTP, so an API key is only sent to a server, and through a
+/// proxy, on this machine.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct HttpSettings {
+    /// Sent as `Authorization: Bearer <key>`.
+    pub api_key: Option<Secret>,
+    pub headers: Vec<(String, String)>,
+    pub proxy: Option<Url>,
+}
+
+impl HttpSettings {
+    /// Read `"api_key"`, `"api_key_env"` or `"api_key_secret"`, plus
+    /// `"headers": {"X-Team": "docs"}` and `"proxy": "http://proxy:3128"`.
+    fn from_json(value: &Value) -> Result<Self> {
+        let text = |key| value.get(key).and_then(Value::as_str).map(String::from);
+        let api_key = text("api_key")
+            .map(Secret::Literal)
+            .or_else(|| text("api_key_env").map(Secret::Env))
+            .or_else(|| text("api_key_secret").map(Secret::Host));
+        let headers = match value.get("headers") {
+            Some(Value::Object(pairs)) => pairs
+                .iter()
+                .map(|(name, v)| {
+                    let v = v.as_str().unwrap_or_default();
+                    if http::valid_header(name, v) {
+                        Ok((name.clone(), v.to_string()))
+                    } else {
+                        Err(Error::Parse(format!("invalid header '{name}'")))
+                    }
+                })
+                .collect::<Result<Vec<_>>>()?,
+            _ => Vec::new(),
+        };
+        Ok(HttpSettings {
+            api_key,
+            headers,
+            proxy: text("proxy").map(|p| Url::parse(&p)).transpose()?,
+        })
+    }
+
+    /// Refuse to send the API key over a network.
+    fn check(&self, url: &Url) -> Result<()> {
+        let remote = !url.is_loopback() || self.proxy.as_ref().is_some_and(|p| !p.is_loopback());
+        if self.api_key.is_some() && remote {
+            return Err(Error::Unsupported(format!(
+                "an api_key would be sent unencrypted to {}; keys are only sent to localhost",
+                url.host
+            )));
+        }
+        Ok(())
+    }
+
+    fn request_options(&self, url: &Url, timeout: Option<Duration>) -> Result<RequestOptions> {
+        self.check(url)?;
+        let mut headers = self.headers.clone();
+        if let Some(secret) = &self.api_key {
+            let key = secret
+                .resolve()
+                .ok_or_else(|| Error::Backend("API key is not available".into()))?;
+            headers.push(("Authorization".into(), format!("Bearer {key}")));
+        }
+        Ok(RequestOptions {
+            headers,
+            proxy: self.proxy.clone(),
+            timeout,
+        })
+    }
+}
+
+/// Looks up a named secret, such as an API key held in the Keychain.
+pub type SecretProvider = Arc<dyn Fn(&str) -> Option<String> + Send + Sync>;
+
+static SECRET_PROVIDER: RwLock<Option<SecretProvider>> = RwLock::new(None);
+
+/// Install (or with `None`, remove) the host's secret provider.
+pub fn set_secret_provider(provider: Option<SecretProvider>) {
+    *SECRET_PROVIDER.write().unwrap_or_else(|e| e.into_inner()) = provider;
+}
+
+fn secret_provider() -> Option<SecretProvider> {
+    SECRET_PROVIDER
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profile {
+    pub name: String,
+    pub kind: Kind,
+    /// Give up on a call after this long.
+    pub timeout: Option<Duration>,
+}
+
+impl Profile {
+    /// The model this profile runs, where the backend names one.
+    pub fn model(&self) -> Option<String> {
+        match &self.kind {
+            Kind::Kiro { .. } | Kind::Mock { .. } => None,
+            Kind::Ollama { model, .. } => Some(model.clone()),
+            #[cfg(feature = "local-llm")]
+            Kind::LlamaCpp { model, .. } => model
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned()),
+        }
+    }
+
+    fn kiro() -> Profile {
+        Profile {
+            name: "kiro".into(),
+            kind: Kind::Kiro {
+                command: "kiro-cli".into(),
+            },
+            timeout: None,
+        }
+    }
+
+    /// Parse one profile: `{"type": "kiro", "command": "kiro-cli"}`,
+    /// `{"type": "ollama", "model": "llama3", "url":
+    /// "http://localhost:11434"}` (plus the auth and proxy keys read by
+    /// [`HttpSettings`]), `{"type": "mock", ...}` (see [`mock::Script`]) or,
+    /// with the `local-llm` feature,
+    /// `{"type": "llama.cpp", "model": "/path/model.gguf", "command":
+    /// "llama-cli", "max_tokens": 1024}`; each with optional
+    /// `"timeout_secs"`.
+    pub fn from_json(name: &str, value: &Value) -> Result<Profile> {
+        let text = |key| value.get(key).and_then(Value::as_str);
+        let kind = match text("type") {
+            Some("kiro") => Kind::Kiro {
+                command: text("command").unwrap_or("kiro-cli").to_string(),
+            },
+            Some("ollama") => {
+                let url = Url::parse(text("url").unwrap_or("http://localhost:11434"))?;
+                let http = HttpSettings::from_json(value)?;
+                http.check(&url)?;
+                Kind::Ollama {
+                    url,
+                    model: text("model")
+                        .ok_or_else(|| Error::Parse(format!("profile '{name}' needs a model")))?
+                        .to_string(),
+                    http,
+                }
+            }
+            Some("mock") => Kind::Mock {
+                script: mock::Script::from_json(value)?,
+            },
+            #[cfg(feature = "local-llm")]
+            Some("llama.cpp") => Kind::LlamaCpp {
+                command: text("command").unwrap_or("llama-cli").to_string(),
+                model: text("model")
+                    .ok_or_else(|| Error::Parse(format!("profile '{name}' needs a model")))?
+                    .into(),
+                max_tokens: value
+                    .get("max_tokens")
+                    .and_then(Value::as_f64)
+                    .map_or(1024, |n| n as u32),
+            },
+            #[cfg(not(feature = "local-llm"))]
+            Some("llama.cpp") => {
+                return Err(Error::Unsupported(
+                    "llama.cpp profiles need the local-llm feature".into(),
+                ))
+            }
+            Some(other) => return Err(Error::Unsupported(format!("backend type '{other}'"))),
+            None => return Err(Error::Parse(format!("profile '{name}' needs a type"))),
+        };
+        Ok(Profile {
+            name: name.to_string(),
+            kind,
+            timeout: value
+                .get("timeout_secs")
+                .and_then(Value::as_f64)
+                .filter(|secs| *secs > 0.0)
+                .map(Duration::from_secs_f64),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    pub profiles: Vec<Profile>,
+    pub default: String,
+    /// Profiles to try, in order, after the selected one fails.
+    pub fallback: Vec<String>,
+}
+
+impl Config {
+    /// Parse `{"default": "smart", "profiles": {"smart": {...},
+    /// "fast-local": {...}}, "fallback": ["smart", "fast-local"]}`. Without
+    /// `"default"` the first profile is the default.
+    pub fn from_json(value: &Value) -> Result<Config> {
+        let Some(Value::Object(entries)) = value.get("profiles") else {
+            return Err(Error::Parse("\"profiles\" must be an object".into()));
+        };
+        let profiles = entries
+            .iter()
+            .map(|(name, profile)| Profile::from_json(name, profile))
+            .collect::<Result<Vec<_>>>()?;
+        let default = match value.get("default").and_then(Value::as_str) {
+            Some(name) => name.to_string(),
+            None => profiles
+                .first()
+                .map(|p| p.name.clone())
+                .ok_or_else(|| Error::Parse("no profiles configured".into()))?,
+        };
+        let fallback: Vec<String> = value
+            .get("fallback")
+            .and_then(Value::as_array)
+            .unwrap_or(&[])
+            .iter()
+            .filter_map(Value::as_str)
+            .map(String::from)
+            .collect();
+        for name in fallback.iter().chain([&default]) {
+            if !profiles.iter().any(|p| &p.name == name) {
+                return Err(Error::Parse(format!("profile '{name}' is not defined")));
+            }
+        }
+        Ok(Config {
+            profiles,
+            default,
+            fallback,
+        })
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            profiles: vec![Profile::kiro()],
+            default: "kiro".into(),
+            fallback: Vec::new(),
+        }
+    }
+}
+
+static CONFIG: RwLock<Option<Config>> = RwLock::new(None);
+
+/// Replace the configured profiles.
+pub fn configure(config: Config) {
+    *CONFIG.write().unwrap_or_else(|e| e.into_inner()) = Some(config);
+    mock::reset();
+}
+
+/// The current configuration.
+pub fn config() -> Config {
+    CONFIG
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .unwrap_or_default()
+}
+
+/// The named profile (or the default for `None`) followed by the rest of
+/// the fallback chain.
+pub fn chain(name: Option<&str>) -> Result<Vec<Profile>> {
+    let config = config();
+    let name = name.unwrap_or(&config.default);
+    let find = |name: &str| config.profiles.iter().find(|p| p.name == name).cloned();
+    let first =
+        find(name).ok_or_else(|| Error::Parse(format!("unknown backend profile '{name}'")))?;
+    policy::check_backend(&first)?;
+    let mut chain = vec![first];
+    for next in &config.fallback {
+        if !chain.iter().any(|p| &p.name == next) {
+            chain.extend(find(next).filter(|p| policy::check_backend(p).is_ok()));
+        }
+    }
+    Ok(chain)
+}
+
+thread_local! {
+    static LAST_ANSWERED: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Send `prompt` down `chain` until a backend answers. Returns the reply
+/// and the name of the profile that produced it, or the last error.
+pub fn send_with_fallback(
+    chain: &[Profile],
+    prompt: &str,
+    seed: Option<u64>,
+) -> Result<(String, String)> {
+    let mut last = Error::Backend("no backend profiles".into());
+    for profile in chain {
+        match send(profile, prompt, seed) {
+            Ok(reply) => {
+                answered(&profile.name);
+                return Ok((reply, profile.name.clone()));
+            }
+            Err(err) => last = err,
+        }
+    }
+    Err(last)
+}
+
+/// Record that `name` answered this thread's latest call.
+pub(crate) fn answered(name: &str) {
+    LAST_ANSWERED.with(|cell| *cell.borrow_mut() = Some(name.to_string()));
+}
+
+/// The profile that answered this thread's most recent chat call.
+pub fn last_answered() -> Option<String> {
+    LAST_ANSWERED.with(|cell| cell.borrow().clone())
+}
+
+/// Send `prompt` to one backend and return its raw reply, sampling with
+/// `seed` where the backend takes one.
+pub fn send(profile: &Profile, prompt: &str, seed: Option<u64>) -> Result<String> {
+    policy::check_backend(profile)?;
+    match &profile.kind {
+        Kind::Mock { script } => script.reply(&profile.name, prompt),
+        Kind::Kiro { command } => {
+            let child = Process::spawn(
+                Command::new(command)
+                    .args(["chat", "--no-interactive", "-a", prompt])
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::piped()),
+            )?;
+            let (success, stdout) = wait_with_timeout(child, profile.timeout)?;
+            if !success && stdout.trim().is_empty() {
+                return Err(Error::Backend(format!("{command} exited with an error")));
+            }
+            Ok(crate::chat::strip_ansi(&stdout))
+        }
+        #[cfg(feature = "local-llm")]
+        Kind::LlamaCpp {
+            command,
+            model,
+            max_tokens,
+        } => {
+            if !model.is_file() {
+                return Err(Error::Backend(format!(
+                    "model not found: {}",
+                    model.display()
+                )));
+            }
+            let mut command_line = Command::new(command);
+            command_line
+                .arg("-m")
+                .arg(model)
+                .args(["-p", prompt, "-n", &max_tokens.to_string()])
+                .args(["--no-display-prompt", "-no-cnv", "--log-disable"]);
+            if let Some(seed) = seed {
+                command_line.arg("--seed").arg(seed.to_string());
+            }
+            let child = Process::spawn(
+                command_line
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::piped()),
+            )?;
+            let (success, stdout) = wait_with_timeout(child, profile.timeout)?;
+            if !success {
+                return Err(Error::Backend(format!("{command} exited with an error")));
+            }
+            Ok(stdout.trim().to_string())
+        }
+        Kind::Ollama { url, model, http } => {
+            let mut body = vec![
+                ("model", Value::from(model.as_str())),
+                ("prompt", Value::from(prompt)),
+                ("stream", Value::from(false)),
+            ];
+            if let Some(seed) = seed {
+                // Ollama takes a JSON number, which cannot carry larger
+                // seeds exactly.
+                if seed > chat::MAX_NUMBER_SEED {
+                    return Err(Error::Backend(format!(
+                        "seed {seed} is above 2^53, the largest Ollama receives exactly"
+                    )));
+                }
+                body.push((
+                    "options",
+                    Value::object([("seed", Value::from(seed as f64))]),
+                ));
+            }
+            let body = Value::object(body);
+            let response = http::post_json(
+                &url.join("/api/generate"),
+                &body.to_string(),
+                &http.request_options(url, profile.timeout)?,
+            )?;
+            let value = json::parse(&response.body)
+                .map_err(|_| Error::Backend(format!("ollama returned HTTP {}", response.status)))?;
+            if let Some(error) = value.get("error").and_then(Value::as_str) {
+                return Err(Error::Backend(format!("ollama: {error}")));
+            }
+            value
+                .get("response")
+                .and_then(Value::as_str)
+                .map(String::from)
+                .ok_or_else(|| Error::Backend("ollama reply had no \"response\"".into()))
+        }
+    }
+}
+
+/// Have the backend load its model ahead of the first prompt. Only Ollama
+/// loads models on demand; for the others this does nothing.
+pub fn warm(profile: &Profile) -> Result<()> {
+    policy::check_backend(profile)?;
+    if let Kind::Ollama { url, model, http } = &profile.kind {
+        // A generate request without a prompt only loads the model.
+        let body = Value::object([("model", Value::from(model.as_str()))]);
+        let response = http::post_json(
+            &url.join("/api/generate"),
+            &body.to_string(),
+            &http.request_options(url, profile.timeout)?,
+        )?;
+        if response.status >= 400 {
+            return Err(Error::Backend(format!(
+                "ollama returned HTTP {} loading {model}",
+                response.status
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Collect a child's exit success and stdout, killing it if it outlives
+/// `timeout`.
+pub(crate) fn wait_with_timeout(
+    child: Process,
+    timeout: Option<Duration>,
+) -> Result<(bool, String)> {
+    // Drain pipes on threads so a chatty child cannot block on a full pipe.
+    let mut stdout = child.take_stdout();
+    let reader = thread::spawn(move || {
+        let mut out = Vec::new();
+        if let Some(pipe) = stdout.as_mut() {
+            let _ = pipe.read_to_end(&mut out);
+        }
+        out
+    });
+    let mut stderr = child.take_stderr();
+    thread::spawn(move || {
+        if let Some(pipe) = stderr.as_mut() {
+            let _ = std::io::copy(pipe, &mut std::io::sink());
+        }
+    });
+    let started = Instant::now();
+    let status = loop {
+        // On shutdown, return at once rather than wait for output the
+        // child's own children may still hold open.
+        if shutdown::stopping() {
+            child.kill();
+            return Err(Error::Backend("shutting down".into()));
+        }
+        if shutdown::cancelled() {
+            child.kill();
+            return Err(Error::Backend("cancelled".into()));
+        }
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if timeout.is_some_and(|limit| started.elapsed() >= limit) {
+            child.kill();
+            return Err(Error::Backend("backend timed out".into()));
+        }
+        thread::sleep(Duration::from_millis(20));
+    };
+    let out = reader
+        .join()
+        .map_err(|_| Error::Backend("reading backend output failed".into()))?;
+    Ok((status.success(), String::from_utf8_lossy(&out).into_owned()))
+}
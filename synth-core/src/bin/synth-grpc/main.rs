@@ -0,0 +1,231 @@
+//! Extraction and chat over gRPC, for Go and Java backends: the `Synth`
+//! service of `proto/synth.proto`, the routes of `synth-server` (built
+//! with the `grpc` feature).
+//!
+//! ```text
+//! synth-grpc [--addr 127.0.0.1:50051] [--backends backends.json]
+//! ```
+//!
+//! `Ask` and `Chat` stream their reply as `ChatEvent`s: the text, then
+//! `done`, or an `error` if the backend fails. Bad input fails the call
+//! with a status instead. As with `synth-server`, the `x-synth-tenant`
+//! and `x-synth-user` metadata tag calls whose options carry no tags.
+
+use std::path::Path;
+use std::process::ExitCode;
+
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tonic::{Code, Request, Response, Status};
+
+use proto::chat_event::Event;
+use proto::synth_server::{Synth, SynthServer};
+use synth_core::backend::{self, Config};
+use synth_core::chat::{self, ChatOptions};
+use synth_core::chunk::chunk_text;
+use synth_core::error::Error;
+use synth_core::extract::{extract_from_bytes_fallible, Format};
+use synth_core::json;
+use synth_core::usage::Tags;
+
+#[allow(clippy::all)]
+mod proto {
+    tonic::include_proto!("synth.v1");
+}
+
+const USAGE: &str = "usage: synth-grpc [--addr 127.0.0.1:50051] [--backends backends.json]";
+
+/// Characters per chunk when a request asks for 0.
+const DEFAULT_CHUNK_CHARS: usize = 4000;
+
+fn status(error: &Error) -> Status {
+    let code = match error {
+        Error::Unsupported(_) => Code::Unimplemented,
+        Error::Parse(_) | Error::InvalidArgument(_) => Code::InvalidArgument,
+        Error::Backend(_) | Error::Schema(_) | Error::Terminology(_) => Code::Unavailable,
+        Error::Denied(_) => Code::PermissionDenied,
+        Error::Io(_) => Code::Internal,
+    };
+    Status::new(code, error.to_string())
+}
+
+fn extract(document: Option<proto::Document>) -> Result<String, Status> {
+    let document = document.ok_or_else(|| Status::invalid_argument("missing document"))?;
+    let format = Format::from_path(Path::new(&document.name)).ok_or_else(|| {
+        Status::invalid_argument(format!("unsupported document type: {}", document.name))
+    })?;
+    extract_from_bytes_fallible(&document.content, format).map_err(|e| status(&e))
+}
+
+fn required<'a>(value: &'a str, name: &str) -> Result<&'a str, Status> {
+    match value.trim().is_empty() {
+        true => Err(Status::invalid_argument(format!("missing {name}"))),
+        false => Ok(value),
+    }
+}
+
+/// The call's options, tagged from its metadata where they carry no tags.
+fn chat_options<T>(request: &Request<T>, options: Option<proto::ChatOptions>) -> ChatOptions {
+    let options = options.unwrap_or_default();
+    let metadata = |key| {
+        request
+            .metadata()
+            .get(key)
+            .and_then(|value| value.to_str().ok())
+    };
+    let tags = Tags::new(metadata("x-synth-tenant"), metadata("x-synth-user"));
+    let text = |s: String| Some(s).filter(|s| !s.is_empty());
+    ChatOptions {
+        backend: text(options.backend),
+        stop: options.stop,
+        max_chars: Some(options.max_chars as usize).filter(|n| *n > 0),
+        strip_fences: options.strip_fences,
+        memory: options.memory,
+        user_id: text(options.user_id).or(tags.user_id),
+        tenant_id: tags.tenant_id,
+        ..ChatOptions::default()
+    }
+}
+
+type Events = ReceiverStream<Result<proto::ChatEvent, Status>>;
+
+/// Run a chat call on a blocking thread and stream its reply.
+fn reply(prompt: String, options: ChatOptions) -> Response<Events> {
+    let (sender, receiver) = mpsc::channel(2);
+    tokio::task::spawn_blocking(move || {
+        let events = match chat::chat(&prompt, &options) {
+            Ok(text) => vec![Event::Text(text), Event::Done(true)],
+            Err(e) => vec![Event::Error(proto::Error {
+                kind: e.kind().into(),
+                message: e.to_string(),
+            })],
+        };
+        for event in events {
+            let event = proto::ChatEvent { event: Some(event) };
+            if sender.blocking_send(Ok(event)).is_err() {
+                break;
+            }
+        }
+    });
+    Response::new(ReceiverStream::new(receiver))
+}
+
+struct Service;
+
+#[tonic::async_trait]
+impl Synth for Service {
+    async fn extract(
+        &self,
+        request: Request<proto::Document>,
+    ) -> Result<Response<proto::ExtractResponse>, Status> {
+        let document = request.into_inner();
+        let name = document.name.clone();
+        let text = tokio::task::spawn_blocking(move || extract(Some(document)))
+            .await
+            .map_err(|e| Status::internal(e.to_string()))??;
+        Ok(Response::new(proto::ExtractResponse { name, text }))
+    }
+
+    async fn chunk(
+        &self,
+        request: Request<proto::ChunkRequest>,
+    ) -> Result<Response<proto::ChunkResponse>, Status> {
+        let request = request.into_inner();
+        let max_chars = match request.max_chars {
+            0 => DEFAULT_CHUNK_CHARS,
+            n => n as usize,
+        };
+        let chunks = tokio::task::spawn_blocking(move || {
+            let text = extract(request.document)?;
+            Ok::<_, Status>(chunk_text(&text, max_chars))
+        })
+        .await
+        .map_err(|e| Status::internal(e.to_string()))??;
+        let chunks = chunks
+            .into_iter()
+            .map(|chunk| proto::Chunk {
+                start: chunk.start as u64,
+                end: chunk.end as u64,
+                text: chunk.text,
+            })
+            .collect();
+        Ok(Response::new(proto::ChunkResponse { chunks }))
+    }
+
+    type AskStream = Events;
+
+    async fn ask(&self, request: Request<proto::AskRequest>) -> Result<Response<Events>, Status> {
+        let options = chat_options(&request, request.get_ref().options.clone());
+        let asked = request.into_inner();
+        let question = required(&asked.question, "question")?.to_string();
+        let document = asked.document;
+        let text = tokio::task::spawn_blocking(move || extract(document))
+            .await
+            .map_err(|e| Status::internal(e.to_string()))??;
+        Ok(reply(chat::ask_prompt(&text, &question), options))
+    }
+
+    type ChatStream = Events;
+
+    async fn chat(&self, request: Request<proto::ChatRequest>) -> Result<Response<Events>, Status> {
+        let options = chat_options(&request, request.get_ref().options.clone());
+        let prompt = required(&request.get_ref().prompt, "prompt")?.to_string();
+        Ok(reply(prompt, options))
+    }
+}
+
+fn main() -> ExitCode {
+    let mut addr = String::from("127.0.0.1:50051");
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args.next();
+        match (arg.as_str(), value) {
+            ("--addr", Some(value)) => addr = value,
+            ("--backends", Some(path)) => {
+                let config = std::fs::read_to_string(&path)
+                    .map_err(Error::from)
+                    .and_then(|text| json::parse(&text))
+                    .and_then(|value| Config::from_json(&value));
+                match config {
+                    Ok(config) => backend::configure(config),
+                    Err(e) => {
+                        eprintln!("synth-grpc: {path}: {e}");
+                        return ExitCode::from(2);
+                    }
+                }
+            }
+            _ => {
+                eprintln!("{USAGE}");
+                return ExitCode::from(2);
+            }
+        }
+    }
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("synth-grpc: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let served = runtime.block_on(async {
+        let listener = match tokio::net::TcpListener::bind(&addr).await {
+            Ok(listener) => listener,
+            Err(e) => return Err(format!("{addr}: {e}")),
+        };
+        if let Ok(local) = listener.local_addr() {
+            eprintln!("synth-grpc listening on http://{local}");
+        }
+        tonic::transport::Server::builder()
+            .add_service(SynthServer::new(Service))
+            .serve_with_incoming(TcpListenerStream::new(listener))
+            .await
+            .map_err(|e| e.to_string())
+    });
+    match served {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("synth-grpc: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
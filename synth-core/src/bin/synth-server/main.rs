@@ -0,0 +1,405 @@
+//! Extraction and chat over HTTP, for running synth as a service instead
+//! of linking the C library.
+//!
+//! ```text
+//! synth-server [--addr 127.0.0.1:8080] [--backends backends.json] [--max-body-mb 64]
+//! ```
+//!
+//! | Route          | Input                                    | Reply                      |
+//! | -------------- | ---------------------------------------- | -------------------------- |
+//! | `GET /health`  |                                          | `{"ok": true}`             |
+//! | `GET /usage`   | `tenant_id`, `user_id` (both optional)   | `{"usage": [...]}`         |
+//! | `POST /extract`| document                                 | `{"name", "text"}`         |
+//! | `POST /chunks` | document, `max_chars` (default 4000)     | `{"chunks": [{"start", "end", "text"}]}` |
+//! | `POST /ask`    | document, `question`, `options`          | `{"answer"}`               |
+//! | `POST /chat`   | `prompt`, `options`                      | `{"reply"}`                |
+//!
+//! A document is the file part of a `multipart/form-data` upload, a JSON
+//! body `{"text": "...", "name": "notes.md"}`, or a raw body with its file
+//! name in `?name=`. Other inputs are form fields, JSON keys or query
+//! parameters; `options` is the `ChatOptions` JSON. `/ask` and `/chat`
+//! reply as server-sent events (`reply`, then `done` or `error`) when the
+//! client sends `Accept: text/event-stream` or `?stream=1`.
+//!
+//! A gateway in front of the server can tag each call with the end user it
+//! serves through `X-Synth-Tenant` and `X-Synth-User` headers; they fill
+//! `tenant_id` and `user_id` in `options` where those are not set, and so
+//! separate the audit log, memories and `/usage` counters per user.
+
+mod request;
+
+use std::collections::BTreeMap;
+use std::io::{BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use request::{ReadError, Request};
+use synth_core::backend::{self, Config};
+use synth_core::chat::{self, ChatOptions};
+use synth_core::chunk::chunk_text;
+use synth_core::error::{Error, Result};
+use synth_core::extract::{extract_from_bytes_fallible, Format};
+use synth_core::json::{self, Value};
+use synth_core::usage::{self, Tags};
+
+const USAGE: &str =
+    "usage: synth-server [--addr 127.0.0.1:8080] [--backends backends.json] [--max-body-mb 64]";
+
+/// A document to process and the other named inputs of a request.
+#[derive(Default)]
+struct Input {
+    document: Option<(String, Vec<u8>)>,
+    fields: BTreeMap<String, Value>,
+    /// From the `X-Synth-Tenant` and `X-Synth-User` headers.
+    tags: Tags,
+}
+
+impl Input {
+    fn from_request(request: &Request) -> std::result::Result<Input, (u16, String)> {
+        let mut input = Input {
+            tags: Tags::new(
+                request.header("x-synth-tenant"),
+                request.header("x-synth-user"),
+            ),
+            ..Input::default()
+        };
+        for (key, value) in &request.query {
+            input
+                .fields
+                .insert(key.clone(), Value::from(value.as_str()));
+        }
+        match request.content_type().as_str() {
+            "multipart/form-data" => {
+                for part in request::multipart(request).map_err(|e| (400, e.to_string()))? {
+                    match part.filename {
+                        Some(filename) if input.document.is_none() => {
+                            input.document = Some((filename, part.data));
+                        }
+                        _ => {
+                            let text = String::from_utf8_lossy(&part.data).into_owned();
+                            input.fields.insert(part.name, Value::from(text));
+                        }
+                    }
+                }
+            }
+            "application/json" => {
+                let text = String::from_utf8_lossy(&request.body);
+                let Value::Object(pairs) = json::parse(&text).map_err(|e| (400, e.to_string()))?
+                else {
+                    return Err((400, "expected a JSON object".into()));
+                };
+                input.fields.extend(pairs);
+                if let Some(text) = input.text("text") {
+                    let name = input.text("name").unwrap_or("document.txt").to_string();
+                    input.document = Some((name, text.as_bytes().to_vec()));
+                }
+            }
+            _ if !request.body.is_empty() => {
+                let name = input.text("name").unwrap_or("document.txt").to_string();
+                input.document = Some((name, request.body.clone()));
+            }
+            _ => {}
+        }
+        Ok(input)
+    }
+
+    fn text(&self, key: &str) -> Option<&str> {
+        self.fields.get(key).and_then(Value::as_str)
+    }
+
+    fn number(&self, key: &str) -> Option<f64> {
+        match self.fields.get(key)? {
+            Value::String(s) => s.trim().parse().ok(),
+            value => value.as_f64(),
+        }
+    }
+
+    /// `options` as an object, or as JSON text from a form field or query,
+    /// tagged from the headers where it carries no tags of its own.
+    fn chat_options(&self) -> std::result::Result<ChatOptions, (u16, String)> {
+        let options = match self.fields.get("options") {
+            None => ChatOptions::default(),
+            Some(Value::String(text)) => json::parse(text)
+                .map(|value| ChatOptions::from_json(&value))
+                .map_err(|e| (400, format!("options: {e}")))?,
+            Some(value) => ChatOptions::from_json(value),
+        };
+        Ok(ChatOptions {
+            tenant_id: options.tenant_id.or_else(|| self.tags.tenant_id.clone()),
+            user_id: options.user_id.or_else(|| self.tags.user_id.clone()),
+            ..options
+        })
+    }
+
+    fn required(&self, key: &str) -> std::result::Result<&str, (u16, String)> {
+        self.text(key)
+            .filter(|s| !s.trim().is_empty())
+            .ok_or_else(|| (400, format!("missing \"{key}\"")))
+    }
+
+    fn extract(&self) -> std::result::Result<(String, String), (u16, String)> {
+        let (name, bytes) = self
+            .document
+            .as_ref()
+            .ok_or_else(|| (400, "missing document".to_string()))?;
+        let format = Format::from_path(Path::new(name))
+            .ok_or_else(|| (415, format!("unsupported document type: {name}")))?;
+        let text = extract_from_bytes_fallible(bytes, format).map_err(|e| failure(&e))?;
+        Ok((name.clone(), text))
+    }
+}
+
+fn failure(error: &Error) -> (u16, String) {
+    let status = match error {
+        Error::Unsupported(_) => 415,
+        Error::Parse(_) => 422,
+        Error::InvalidArgument(_) => 400,
+        Error::Backend(_) | Error::Schema(_) | Error::Terminology(_) => 502,
+        Error::Denied(_) => 403,
+        Error::Io(_) => 500,
+    };
+    (status, error.to_string())
+}
+
+fn error_body(status: u16, message: &str) -> String {
+    Value::object([
+        ("status", Value::from(status as usize)),
+        ("message", Value::from(message)),
+    ])
+    .to_string()
+}
+
+fn send_json(stream: &mut TcpStream, value: Value) {
+    let _ = request::respond(
+        stream,
+        200,
+        "application/json",
+        value.to_string().as_bytes(),
+    );
+}
+
+fn send_error(stream: &mut TcpStream, (status, message): (u16, String)) {
+    let body = error_body(status, &message);
+    let _ = request::respond(stream, status, "application/json", body.as_bytes());
+}
+
+fn wants_events(request: &Request, input: &Input) -> bool {
+    request
+        .header("accept")
+        .is_some_and(|accept| accept.contains("text/event-stream"))
+        || matches!(input.text("stream"), Some("1" | "true"))
+}
+
+/// Run a chat call and send its reply as `{key: reply}` or as events,
+/// with keep-alive comments while the backend works.
+fn reply(
+    stream: &mut TcpStream,
+    events: bool,
+    key: &'static str,
+    prompt: String,
+    options: ChatOptions,
+) {
+    if !events {
+        match chat::chat(&prompt, &options) {
+            Ok(reply) => send_json(stream, Value::object([(key, Value::from(reply))])),
+            Err(e) => send_error(stream, failure(&e)),
+        }
+        return;
+    }
+    let (sender, receiver) = mpsc::channel::<Result<String>>();
+    thread::spawn(move || {
+        let _ = sender.send(chat::chat(&prompt, &options));
+    });
+    if request::start_events(stream).is_err() {
+        return;
+    }
+    let result = loop {
+        match receiver.recv_timeout(Duration::from_secs(10)) {
+            Ok(result) => break result,
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                if request::keepalive(stream).is_err() {
+                    return;
+                }
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                break Err(Error::Backend("chat worker stopped".into()));
+            }
+        }
+    };
+    let _ = match result {
+        Ok(reply) => request::event(stream, "reply", &reply)
+            .and_then(|()| request::event(stream, "done", "{}")),
+        Err(e) => {
+            let (status, message) = failure(&e);
+            request::event(stream, "error", &error_body(status, &message))
+        }
+    };
+}
+
+fn route(stream: &mut TcpStream, request: &Request) {
+    let post = request.method == "POST";
+    match (request.path.as_str(), post) {
+        ("/health", _) if request.method == "GET" => {
+            send_json(stream, Value::object([("ok", Value::from(true))]));
+            return;
+        }
+        ("/usage", _) if request.method == "GET" => {
+            let query = |key| {
+                request
+                    .query
+                    .iter()
+                    .find(|(name, _)| name == key)
+                    .map(|(_, value)| value.as_str())
+            };
+            let filter = Tags::new(query("tenant_id"), query("user_id"));
+            send_json(
+                stream,
+                Value::object([("usage", usage::report_json(&filter))]),
+            );
+            return;
+        }
+        ("/extract" | "/chunks" | "/ask" | "/chat", true) => {}
+        ("/extract" | "/chunks" | "/ask" | "/chat" | "/health" | "/usage", _) => {
+            send_error(stream, (405, format!("{} not allowed", request.method)));
+            return;
+        }
+        _ => {
+            send_error(stream, (404, format!("no route {}", request.path)));
+            return;
+        }
+    }
+    let input = match Input::from_request(request) {
+        Ok(input) => input,
+        Err(e) => return send_error(stream, e),
+    };
+    let result = match request.path.as_str() {
+        "/extract" => input.extract().map(|(name, text)| {
+            Value::object([("name", Value::from(name)), ("text", Value::from(text))])
+        }),
+        "/chunks" => input.extract().map(|(_, text)| {
+            let max_chars = input.number("max_chars").unwrap_or(4000.0).max(1.0) as usize;
+            let chunks = chunk_text(&text, max_chars)
+                .into_iter()
+                .map(|chunk| {
+                    Value::object([
+                        ("start", Value::from(chunk.start)),
+                        ("end", Value::from(chunk.end)),
+                        ("start_utf16", Value::from(chunk.start_utf16)),
+                        ("end_utf16", Value::from(chunk.end_utf16)),
+                        ("text", Value::from(chunk.text)),
+                    ])
+                })
+                .collect();
+            Value::object([("chunks", Value::Array(chunks))])
+        }),
+        "/ask" => {
+            let asked = input.extract().and_then(|(_, text)| {
+                let question = input.required("question")?;
+                Ok((text, question.to_string(), input.chat_options()?))
+            });
+            return match asked {
+                Ok((text, question, options)) => {
+                    let prompt = chat::ask_prompt(&text, &question);
+                    reply(
+                        stream,
+                        wants_events(request, &input),
+                        "answer",
+                        prompt,
+                        options,
+                    );
+                }
+                Err(e) => send_error(stream, e),
+            };
+        }
+        _ => {
+            let asked = input
+                .required("prompt")
+                .and_then(|prompt| Ok((prompt.to_string(), input.chat_options()?)));
+            return match asked {
+                Ok((prompt, options)) => {
+                    reply(
+                        stream,
+                        wants_events(request, &input),
+                        "reply",
+                        prompt,
+                        options,
+                    );
+                }
+                Err(e) => send_error(stream, e),
+            };
+        }
+    };
+    match result {
+        Ok(value) => send_json(stream, value),
+        Err(e) => send_error(stream, e),
+    }
+}
+
+fn serve(mut stream: TcpStream, max_body: usize) {
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(60)));
+    let Ok(reader) = stream.try_clone() else {
+        return;
+    };
+    let mut reader = BufReader::new(reader);
+    match request::read(&mut reader, &mut stream, max_body) {
+        Ok(request) => route(&mut stream, &request),
+        Err(ReadError::Bad(message)) => send_error(&mut stream, (400, message)),
+        Err(ReadError::TooLarge) => send_error(&mut stream, (413, "request body too large".into())),
+        Err(ReadError::Closed) => {}
+    }
+    let _ = stream.flush();
+}
+
+fn main() -> ExitCode {
+    let mut addr = String::from("127.0.0.1:8080");
+    let mut max_body = 64usize << 20;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args.next();
+        match (arg.as_str(), value) {
+            ("--addr", Some(value)) => addr = value,
+            ("--max-body-mb", Some(value)) => match value.parse::<usize>() {
+                Ok(mb) => max_body = mb << 20,
+                Err(_) => {
+                    eprintln!("{USAGE}");
+                    return ExitCode::from(2);
+                }
+            },
+            ("--backends", Some(path)) => {
+                let config = std::fs::read_to_string(&path)
+                    .map_err(Error::from)
+                    .and_then(|text| json::parse(&text))
+                    .and_then(|value| Config::from_json(&value));
+                match config {
+                    Ok(config) => backend::configure(config),
+                    Err(e) => {
+                        eprintln!("synth-server: {path}: {e}");
+                        return ExitCode::from(2);
+                    }
+                }
+            }
+            _ => {
+                eprintln!("{USAGE}");
+                return ExitCode::from(2);
+            }
+        }
+    }
+    let listener = match TcpListener::bind(&addr) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("synth-server: {addr}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Ok(local) = listener.local_addr() {
+        eprintln!("synth-server listening on http://{local}");
+    }
+    for stream in listener.incoming().flatten() {
+        thread::spawn(move || serve(stream, max_body));
+    }
+    ExitCode::SUCCESS
+}
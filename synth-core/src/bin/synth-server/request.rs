@@ -0,0 +1,312 @@
+//! Just enough HTTP/1.1 to serve the API: request parsing (including
+//! chunked bodies and multipart forms) and responses. Each connection
+//! carries one request and is closed after the response.
+
+use std::io::{self, BufRead, Read, Write};
+
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub query: Vec<(String, String)>,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The media type, lowercased and without parameters.
+    pub fn content_type(&self) -> String {
+        self.header("content-type")
+            .and_then(|value| value.split(';').next())
+            .unwrap_or("")
+            .trim()
+            .to_ascii_lowercase()
+    }
+}
+
+/// Why a request could not be read.
+pub enum ReadError {
+    /// The connection failed or closed: there is no one to answer.
+    Closed,
+    /// Malformed request: answer 400.
+    Bad(String),
+    /// Body over the limit: answer 413.
+    TooLarge,
+}
+
+impl From<io::Error> for ReadError {
+    fn from(_: io::Error) -> Self {
+        ReadError::Closed
+    }
+}
+
+fn line(reader: &mut impl BufRead) -> Result<String, ReadError> {
+    let mut line = String::new();
+    // Header lines are short; a missing newline within 16 KiB is an abuse.
+    reader.take(16 * 1024).read_line(&mut line)?;
+    if !line.ends_with('\n') {
+        return Err(ReadError::Bad("truncated request head".into()));
+    }
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Read one request. `Expect: 100-continue` is answered on `writer`
+/// before the body is read.
+pub fn read(
+    reader: &mut impl BufRead,
+    writer: &mut impl Write,
+    max_body: usize,
+) -> Result<Request, ReadError> {
+    let request_line = line(reader)?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target), Some(_version)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(ReadError::Bad(format!("bad request line: {request_line}")));
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let mut request = Request {
+        method: method.to_string(),
+        path: percent_decode(path),
+        query: parse_query(query),
+        headers: Vec::new(),
+        body: Vec::new(),
+    };
+    loop {
+        let header = line(reader)?;
+        if header.is_empty() {
+            break;
+        }
+        if request.headers.len() >= 100 {
+            return Err(ReadError::Bad("too many headers".into()));
+        }
+        let (name, value) = header
+            .split_once(':')
+            .ok_or_else(|| ReadError::Bad(format!("bad header: {header}")))?;
+        request
+            .headers
+            .push((name.trim().to_string(), value.trim().to_string()));
+    }
+
+    let chunked = request
+        .header("transfer-encoding")
+        .is_some_and(|v| v.eq_ignore_ascii_case("chunked"));
+    let length = match request.header("content-length") {
+        Some(value) => Some(
+            value
+                .parse::<usize>()
+                .map_err(|_| ReadError::Bad("bad Content-Length".into()))?,
+        ),
+        None => None,
+    };
+    if length.is_some_and(|n| n > max_body) {
+        return Err(ReadError::TooLarge);
+    }
+    if (chunked || length.is_some_and(|n| n > 0))
+        && request
+            .header("expect")
+            .is_some_and(|v| v.eq_ignore_ascii_case("100-continue"))
+    {
+        writer.write_all(b"HTTP/1.1 100 Continue\r\n\r\n")?;
+        writer.flush()?;
+    }
+    if chunked {
+        loop {
+            let size_line = line(reader)?;
+            let size = usize::from_str_radix(size_line.split(';').next().unwrap_or("").trim(), 16)
+                .map_err(|_| ReadError::Bad("bad chunk size".into()))?;
+            if size == 0 {
+                // Skip any trailers.
+                while !line(reader)?.is_empty() {}
+                break;
+            }
+            // The size is the client's, so it may overflow.
+            if request
+                .body
+                .len()
+                .checked_add(size)
+                .is_none_or(|total| total > max_body)
+            {
+                return Err(ReadError::TooLarge);
+            }
+            let start = request.body.len();
+            request.body.resize(start + size, 0);
+            reader.read_exact(&mut request.body[start..])?;
+            line(reader)?;
+        }
+    } else if let Some(length) = length {
+        request.body.resize(length, 0);
+        reader.read_exact(&mut request.body)?;
+    }
+    Ok(request)
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = |offset: usize| {
+            bytes
+                .get(i + offset)
+                .and_then(|&b| (b as char).to_digit(16))
+        };
+        match (bytes[i], hex(1), hex(2)) {
+            (b'%', Some(high), Some(low)) => {
+                out.push((high * 16 + low) as u8);
+                i += 3;
+            }
+            (b'+', _, _) => {
+                out.push(b' ');
+                i += 1;
+            }
+            (b, _, _) => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn parse_query(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(key), percent_decode(value))
+        })
+        .collect()
+}
+
+/// One part of a `multipart/form-data` body.
+pub struct Part {
+    pub name: String,
+    pub filename: Option<String>,
+    pub data: Vec<u8>,
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// A quoted or bare `key=value` parameter from a header value.
+fn parameter(value: &str, key: &str) -> Option<String> {
+    value.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case(key)
+            .then(|| value.trim().trim_matches('"').to_string())
+    })
+}
+
+/// Split a multipart body into its parts.
+pub fn multipart(request: &Request) -> Result<Vec<Part>, String> {
+    let boundary = request
+        .header("content-type")
+        .and_then(|value| parameter(value, "boundary"))
+        .ok_or("multipart body without a boundary")?;
+    let delimiter = format!("--{boundary}").into_bytes();
+    let mut rest = &request.body[..];
+    let start = find(rest, &delimiter).ok_or("multipart boundary not found")?;
+    rest = &rest[start + delimiter.len()..];
+    let mut parts = Vec::new();
+    // Each part follows a delimiter line; "--" after one ends the body.
+    while !rest.starts_with(b"--") {
+        rest = rest
+            .strip_prefix(b"\r\n")
+            .ok_or("malformed multipart body")?;
+        let head_end = find(rest, b"\r\n\r\n").ok_or("malformed part headers")?;
+        let head = String::from_utf8_lossy(&rest[..head_end]).into_owned();
+        rest = &rest[head_end + 4..];
+        let mut separator = b"\r\n".to_vec();
+        separator.extend_from_slice(&delimiter);
+        let end = find(rest, &separator).ok_or("unterminated multipart part")?;
+        let disposition = head
+            .lines()
+            .find_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                name.trim()
+                    .eq_ignore_ascii_case("content-disposition")
+                    .then(|| value.trim().to_string())
+            })
+            .unwrap_or_default();
+        parts.push(Part {
+            name: parameter(&disposition, "name").unwrap_or_default(),
+            filename: parameter(&disposition, "filename"),
+            data: rest[..end].to_vec(),
+        });
+        rest = &rest[end + separator.len()..];
+    }
+    Ok(parts)
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        415 => "Unsupported Media Type",
+        422 => "Unprocessable Entity",
+        502 => "Bad Gateway",
+        _ => "Internal Server Error",
+    }
+}
+
+pub fn respond(
+    out: &mut impl Write,
+    status: u16,
+    content_type: &str,
+    body: &[u8],
+) -> io::Result<()> {
+    write!(
+        out,
+        "HTTP/1.1 {status} {}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n",
+        reason(status),
+        body.len()
+    )?;
+    out.write_all(body)?;
+    out.flush()
+}
+
+/// Start a `text/event-stream` response; follow with [`event`] calls.
+pub fn start_events(out: &mut impl Write) -> io::Result<()> {
+    out.write_all(
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\
+          Connection: close\r\n\r\n",
+    )?;
+    out.flush()
+}
+
+/// Send one server-sent event; multi-line data is split across `data:`
+/// lines as the format requires.
+pub fn event(out: &mut impl Write, name: &str, data: &str) -> io::Result<()> {
+    let mut text = format!("event: {name}\n");
+    for line in data.split('\n') {
+        text.push_str("data: ");
+        text.push_str(line);
+        text.push('\n');
+    }
+    text.push('\n');
+    out.write_all(text.as_bytes())?;
+    out.flush()
+}
+
+/// An SSE comment, which clients ignore; keeps idle proxies from closing
+/// the stream while a reply is pending.
+pub fn keepalive(out: &mut impl Write) -> io::Result<()> {
+    out.write_all(b": waiting\n\n")?;
+    out.flush()
+}
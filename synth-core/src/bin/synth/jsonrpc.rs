@@ -0,0 +1,337 @@
+//! `synth --jsonrpc`: JSON-RPC 2.0 over stdio, one message per line, for
+//! editor plugins that keep one process running.
+//!
+//! Methods (params are objects):
+//!
+//! - `extract` `{"path"}`, `{"url"}` or `{"text", "name"}` → `{"text"}`;
+//!   with `{"path", "live": true}` each extraction pass is first sent as an
+//!   `extract/delta` notification `{"path", "pass", ...}` (see
+//!   `synth_core::live`); with `{"normalize": ["llm", "search"]}` the
+//!   result also has `"normalized": {"llm": "...", "search": "..."}`
+//! - `ask` same document params plus `{"question", "options"}` → `{"answer"}`
+//! - `chat` `{"prompt", "options"}` → `{"reply"}`
+//! - `watch` `{"path"}` → `{"watch": id}`; each change to the file is then
+//!   sent as a `watch/changed` notification `{"watch", "path", "text"}`
+//!   (or `"error"` instead of `"text"`)
+//! - `unwatch` `{"watch": id}` → `true`
+//! - `shutdown` → `null`, then the process exits
+//!
+//! `options` is the `ChatOptions` JSON. Requests run concurrently, so
+//! replies may arrive out of order; match them by `id`. With `--webhook`,
+//! watch changes are also sent to the webhook as `watch.changed`.
+
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use synth_core::chat::{self, ChatOptions};
+use synth_core::error::Error;
+use synth_core::extract::{self, extract_from_bytes_fallible, Format};
+use synth_core::ingest;
+use synth_core::json::{self, Value};
+use synth_core::live;
+use synth_core::normalize;
+use synth_core::webhook;
+
+const PARSE_ERROR: f64 = -32700.0;
+const INVALID_REQUEST: f64 = -32600.0;
+const METHOD_NOT_FOUND: f64 = -32601.0;
+const INVALID_PARAMS: f64 = -32602.0;
+/// Failures from synth itself; `data.kind` is the error kind.
+const SYNTH_ERROR: f64 = -32000.0;
+
+/// How often watched files are checked for changes.
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+struct Failure {
+    code: f64,
+    message: String,
+    kind: Option<&'static str>,
+}
+
+impl Failure {
+    fn params(message: impl Into<String>) -> Failure {
+        Failure {
+            code: INVALID_PARAMS,
+            message: message.into(),
+            kind: None,
+        }
+    }
+}
+
+impl From<Error> for Failure {
+    fn from(error: Error) -> Failure {
+        Failure {
+            code: SYNTH_ERROR,
+            message: error.to_string(),
+            kind: Some(error.kind()),
+        }
+    }
+}
+
+/// Everything shared between request threads.
+#[derive(Default)]
+struct Server {
+    next_watch: AtomicU64,
+    watches: Mutex<BTreeMap<u64, Arc<AtomicBool>>>,
+}
+
+impl Server {
+    /// Write one message as a line; holding the stdout lock keeps lines whole.
+    fn send(&self, message: Value) {
+        let mut stdout = io::stdout().lock();
+        let _ = writeln!(stdout, "{message}");
+        let _ = stdout.flush();
+    }
+
+    fn respond(&self, id: Value, result: Result<Value, Failure>) {
+        let outcome = match result {
+            Ok(value) => ("result", value),
+            Err(failure) => {
+                let mut error = vec![
+                    ("code", Value::from(failure.code)),
+                    ("message", Value::from(failure.message)),
+                ];
+                if let Some(kind) = failure.kind {
+                    error.push(("data", Value::object([("kind", Value::from(kind))])));
+                }
+                ("error", Value::object(error))
+            }
+        };
+        self.send(Value::object([
+            ("jsonrpc", Value::from("2.0")),
+            ("id", id),
+            outcome,
+        ]))
+    }
+
+    fn notify(&self, method: &str, params: Value) {
+        self.send(Value::object([
+            ("jsonrpc", Value::from("2.0")),
+            ("method", Value::from(method)),
+            ("params", params),
+        ]))
+    }
+}
+
+fn text_param<'a>(params: &'a Value, key: &str) -> Result<&'a str, Failure> {
+    params
+        .get(key)
+        .and_then(Value::as_str)
+        .ok_or_else(|| Failure::params(format!("missing \"{key}\"")))
+}
+
+fn chat_options(params: &Value) -> ChatOptions {
+    params
+        .get("options")
+        .map(ChatOptions::from_json)
+        .unwrap_or_default()
+}
+
+/// The document named by `{"path"}` or `{"url"}`, or given as
+/// `{"text", "name"}`.
+fn document(params: &Value) -> Result<String, Failure> {
+    if let Some(path) = params.get("path").and_then(Value::as_str) {
+        return Ok(extract::extract_text(Path::new(path))?);
+    }
+    if let Some(url) = params.get("url").and_then(Value::as_str) {
+        return Ok(ingest::extract_url(url)?);
+    }
+    let text = text_param(params, "text")
+        .map_err(|_| Failure::params("expected \"path\", \"url\" or \"text\""))?;
+    let name = params
+        .get("name")
+        .and_then(Value::as_str)
+        .unwrap_or("document.txt");
+    let format =
+        Format::from_path(Path::new(name)).ok_or_else(|| Error::Unsupported(name.to_string()))?;
+    Ok(extract_from_bytes_fallible(text.as_bytes(), format)?)
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn watch(server: &Arc<Server>, params: &Value) -> Result<Value, Failure> {
+    let path = PathBuf::from(text_param(params, "path")?);
+    if !path.is_file() {
+        return Err(Failure::params(format!("not a file: {}", path.display())));
+    }
+    let id = server.next_watch.fetch_add(1, Ordering::Relaxed) + 1;
+    let stop = Arc::new(AtomicBool::new(false));
+    server
+        .watches
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(id, Arc::clone(&stop));
+    let server = Arc::clone(server);
+    thread::spawn(move || {
+        let mut seen = modified(&path);
+        while !stop.load(Ordering::Relaxed) {
+            thread::sleep(WATCH_INTERVAL);
+            let now = modified(&path);
+            if now == seen {
+                continue;
+            }
+            seen = now;
+            let extracted = extract::extract_text(&path);
+            let watch = Value::from(id as f64);
+            let path_value = Value::from(path.display().to_string());
+            // The webhook learns what changed, not the document text.
+            webhook::notify(
+                "watch.changed",
+                Value::object([
+                    ("watch", watch.clone()),
+                    ("path", path_value.clone()),
+                    ("ok", Value::from(extracted.is_ok())),
+                ]),
+            );
+            let outcome = match extracted {
+                Ok(text) => ("text", Value::from(text)),
+                Err(e) => ("error", Value::from(e.to_string())),
+            };
+            server.notify(
+                "watch/changed",
+                Value::object([("watch", watch), ("path", path_value), outcome]),
+            );
+        }
+    });
+    Ok(Value::object([("watch", Value::from(id as f64))]))
+}
+
+fn unwatch(server: &Server, params: &Value) -> Result<Value, Failure> {
+    let id = params
+        .get("watch")
+        .and_then(Value::as_f64)
+        .ok_or_else(|| Failure::params("missing \"watch\""))? as u64;
+    let stop = server
+        .watches
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(&id);
+    if let Some(stop) = &stop {
+        stop.store(true, Ordering::Relaxed);
+    }
+    Ok(Value::from(stop.is_some()))
+}
+
+fn call(server: &Arc<Server>, method: &str, params: &Value) -> Result<Value, Failure> {
+    match method {
+        "extract" => {
+            let live_path = params
+                .get("path")
+                .and_then(Value::as_str)
+                .filter(|_| params.get("live").and_then(Value::as_bool) == Some(true));
+            let text = match live_path {
+                Some(path) => live::extract_live(Path::new(path), |delta| {
+                    let mut pairs = vec![("path".to_string(), Value::from(path))];
+                    if let Value::Object(rest) = delta.to_json() {
+                        pairs.extend(rest);
+                    }
+                    server.notify("extract/delta", Value::Object(pairs));
+                })?,
+                None => document(params)?,
+            };
+            let mut result = vec![("text", Value::from(text.as_str()))];
+            if let Some(Value::Array(names)) = params.get("normalize") {
+                let names: Vec<&str> = names.iter().filter_map(Value::as_str).collect();
+                let normalized = normalize::normalize_all(&text, &names)?;
+                result.push((
+                    "normalized",
+                    Value::Object(
+                        normalized
+                            .into_iter()
+                            .map(|(name, text)| (name, Value::from(text)))
+                            .collect(),
+                    ),
+                ));
+            }
+            Ok(Value::object(result))
+        }
+        "ask" => {
+            let text = document(params)?;
+            let prompt = chat::ask_prompt(&text, text_param(params, "question")?);
+            let answer = chat::chat(&prompt, &chat_options(params))?;
+            Ok(Value::object([("answer", Value::from(answer))]))
+        }
+        "chat" => {
+            let reply = chat::chat(text_param(params, "prompt")?, &chat_options(params))?;
+            Ok(Value::object([("reply", Value::from(reply))]))
+        }
+        "watch" => watch(server, params),
+        "unwatch" => unwatch(server, params),
+        _ => Err(Failure {
+            code: METHOD_NOT_FOUND,
+            message: format!("unknown method '{method}'"),
+            kind: None,
+        }),
+    }
+}
+
+/// Serve requests from stdin until it closes or `shutdown` is called.
+pub fn run() {
+    let server = Arc::new(Server::default());
+    let mut workers: Vec<thread::JoinHandle<()>> = Vec::new();
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let message = match json::parse(&line) {
+            Ok(message) => message,
+            Err(e) => {
+                server.respond(
+                    Value::Null,
+                    Err(Failure {
+                        code: PARSE_ERROR,
+                        message: e.to_string(),
+                        kind: None,
+                    }),
+                );
+                continue;
+            }
+        };
+        let id = message.get("id").cloned();
+        let Some(method) = message.get("method").and_then(Value::as_str) else {
+            server.respond(
+                id.unwrap_or(Value::Null),
+                Err(Failure {
+                    code: INVALID_REQUEST,
+                    message: "missing method".into(),
+                    kind: None,
+                }),
+            );
+            continue;
+        };
+        if method == "shutdown" {
+            // Let in-flight requests answer before the reply that ends the session.
+            for worker in workers.drain(..) {
+                let _ = worker.join();
+            }
+            if let Some(id) = id {
+                server.respond(id, Ok(Value::Null));
+            }
+            return;
+        }
+        let method = method.to_string();
+        let params = message
+            .get("params")
+            .cloned()
+            .unwrap_or_else(|| Value::object(Vec::<(String, Value)>::new()));
+        let server = Arc::clone(&server);
+        workers.retain(|worker| !worker.is_finished());
+        workers.push(thread::spawn(move || {
+            let result = call(&server, &method, &params);
+            // Notifications (no id) get no reply, even on failure.
+            if let Some(id) = id {
+                server.respond(id, result);
+            }
+        }));
+    }
+}
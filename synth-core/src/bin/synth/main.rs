@@ -0,0 +1,100 @@
+//! Command-line tools for the Rust core.
+//!
+//! ```text
+//! synth verify-corpus <dir> [--update] [--json]
+//! synth --jsonrpc [--backends backends.json] [--webhook webhook.json]
+//! ```
+
+mod jsonrpc;
+
+use std::path::Path;
+use std::process::ExitCode;
+
+use synth_core::backend::{self, Config};
+use synth_core::error::Error;
+use synth_core::json::Value;
+use synth_core::webhook::{self, Webhook};
+use synth_core::{corpus, json};
+
+const USAGE: &str = "usage: synth verify-corpus <dir> [--update] [--json]\n       \
+                     synth --jsonrpc [--backends backends.json] [--webhook webhook.json]";
+
+fn verify_corpus(args: &[String]) -> ExitCode {
+    let mut dir = None;
+    let mut update = false;
+    let mut json = false;
+    for arg in args {
+        match arg.as_str() {
+            "--update" => update = true,
+            "--json" => json = true,
+            _ if dir.is_none() && !arg.starts_with("--") => dir = Some(arg),
+            _ => {
+                eprintln!("{USAGE}");
+                return ExitCode::from(2);
+            }
+        }
+    }
+    let Some(dir) = dir else {
+        eprintln!("{USAGE}");
+        return ExitCode::from(2);
+    };
+    match corpus::verify(Path::new(dir), update) {
+        Ok(report) => {
+            if json {
+                println!("{}", report.to_json());
+            } else {
+                print!("{}", report.to_text());
+            }
+            if report.ok() {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+        Err(e) => {
+            eprintln!("synth: {dir}: {e}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn read_json(path: &str) -> Result<Value, Error> {
+    std::fs::read_to_string(path)
+        .map_err(Error::from)
+        .and_then(|text| json::parse(&text))
+}
+
+fn serve_jsonrpc(args: &[String]) -> ExitCode {
+    for pair in args.chunks(2) {
+        let result = match pair {
+            [flag, path] if flag == "--backends" => read_json(path)
+                .and_then(|value| Config::from_json(&value))
+                .map(backend::configure),
+            [flag, path] if flag == "--webhook" => read_json(path)
+                .and_then(|value| Webhook::from_json(&value))
+                .map(|hook| webhook::configure(Some(hook))),
+            _ => {
+                eprintln!("{USAGE}");
+                return ExitCode::from(2);
+            }
+        };
+        if let Err(e) = result {
+            eprintln!("synth: {}: {e}", pair[1]);
+            return ExitCode::from(2);
+        }
+    }
+    jsonrpc::run();
+    ExitCode::SUCCESS
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("verify-corpus") => verify_corpus(&args[1..]),
+        Some("--jsonrpc") => serve_jsonrpc(&args[1..]),
+        _ => {
+            eprintln!("{USAGE}");
+            ExitCode::from(2)
+        }
+    }
+}
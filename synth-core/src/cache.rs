@@ -0,0 +1,107 @@
+//! Replies to seeded chat calls, kept so an exact request is answered the
+//! same way every time.
+//!
+//! A seeded call is keyed by the SHA-256 of everything that decides its
+//! reply: the profile chain with its models, the seed, the caller's tags
+//! and the final prompt. Repeating it returns the stored reply without
+//! asking the backend, which makes test suites stable even against
+//! backends that ignore seeds. Replies are held in memory; with a
+//! directory configured they are also stored there as `<key>.json`, so a
+//! suite can check its recorded replies in.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use crate::backend::Profile;
+use crate::error::Result;
+use crate::hash::sha256_hex;
+use crate::json::{self, Value};
+use crate::usage::Tags;
+
+#[derive(Default)]
+struct Store {
+    dir: Option<PathBuf>,
+    replies: HashMap<String, (String, String)>,
+}
+
+fn lock() -> MutexGuard<'static, Option<Store>> {
+    static STORE: Mutex<Option<Store>> = Mutex::new(None);
+    STORE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Store replies under `dir` as well as in memory, or with `None` in
+/// memory only.
+pub fn set_dir(dir: Option<&Path>) -> Result<()> {
+    if let Some(dir) = dir {
+        fs::create_dir_all(dir)?;
+    }
+    lock().get_or_insert_with(Store::default).dir = dir.map(Path::to_path_buf);
+    Ok(())
+}
+
+/// Forget the replies held in memory. Files already written stay.
+pub fn clear() {
+    if let Some(store) = lock().as_mut() {
+        store.replies.clear();
+    }
+}
+
+/// The cache key for a seeded call.
+pub fn key(chain: &[Profile], seed: u64, tags: &Tags, prompt: &str) -> String {
+    let profiles: Vec<Value> = chain
+        .iter()
+        .map(|profile| {
+            Value::object([
+                ("name", Value::from(profile.name.as_str())),
+                ("type", Value::from(profile.kind.name())),
+                ("model", profile.model().map_or(Value::Null, Value::from)),
+            ])
+        })
+        .collect();
+    let mut request = vec![
+        ("profiles", Value::Array(profiles)),
+        ("seed", Value::from(seed.to_string())),
+        ("prompt", Value::from(prompt)),
+    ];
+    request.extend(tags.pairs());
+    sha256_hex(Value::object(request).to_string().as_bytes())
+}
+
+/// The stored reply and the profile that gave it.
+pub fn get(key: &str) -> Option<(String, String)> {
+    let mut guard = lock();
+    let store = guard.get_or_insert_with(Store::default);
+    if let Some(hit) = store.replies.get(key) {
+        return Some(hit.clone());
+    }
+    let path = store.dir.as_ref()?.join(format!("{key}.json"));
+    let value = json::parse(&crate::store::read_string(&path).ok()??).ok()?;
+    let hit = (
+        value.get("reply")?.as_str()?.to_string(),
+        value.get("backend")?.as_str()?.to_string(),
+    );
+    store.replies.insert(key.to_string(), hit.clone());
+    Some(hit)
+}
+
+/// Remember a reply. Failures to write the file are ignored; the reply is
+/// still kept in memory.
+pub fn put(key: &str, reply: &str, backend: &str) {
+    let mut guard = lock();
+    let store = guard.get_or_insert_with(Store::default);
+    store
+        .replies
+        .insert(key.to_string(), (reply.to_string(), backend.to_string()));
+    if let Some(dir) = &store.dir {
+        let record = Value::object([
+            ("backend", Value::from(backend)),
+            ("reply", Value::from(reply)),
+        ]);
+        let _ = crate::store::write(
+            &dir.join(format!("{key}.json")),
+            record.to_string().as_bytes(),
+        );
+    }
+}
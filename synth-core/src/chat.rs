@@ -0,0 +1,318 @@
+//! Chat entry points: per-call options, guardrail hooks and backend
+//! dispatch.
+
+use std::time::Instant;
+
+use crate::ask;
+use crate::audit;
+use crate::backend;
+use crate::cache;
+use crate::error::{Error, Result};
+use crate::glossary;
+use crate::hooks;
+use crate::json::{self, Value};
+use crate::memory;
+use crate::schema;
+use crate::telemetry;
+use crate::usage::{self, Tags};
+
+/// Per-call chat settings.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ChatOptions {
+    /// Backend profile name; `None` uses the default profile.
+    pub backend: Option<String>,
+    /// The reply is cut at the first occurrence of any of these.
+    pub stop: Vec<String>,
+    /// Replies longer than this many characters are truncated.
+    pub max_chars: Option<usize>,
+    /// Remove markdown code fence lines, keeping the fenced content.
+    pub strip_fences: bool,
+    /// Prepend relevant long-term memories to the prompt.
+    pub memory: bool,
+    /// Sampling seed for backends that take one. Seeded calls are also
+    /// answered from the [`cache`] when the exact request repeats.
+    pub seed: Option<u64>,
+    /// Name of a [defined](glossary::define) glossary the reply must
+    /// follow.
+    pub glossary: Option<String>,
+    /// Caller-supplied user and tenant tags; see [`usage`].
+    pub user_id: Option<String>,
+    pub tenant_id: Option<String>,
+}
+
+/// The largest seed a JSON number carries exactly, 2^53.
+pub const MAX_NUMBER_SEED: u64 = 1 << 53;
+
+/// A seed given as a decimal string, or as a whole JSON number no larger
+/// than [`MAX_NUMBER_SEED`].
+fn seed_from_json(value: &Value) -> Option<u64> {
+    match value {
+        Value::String(s) => s.trim().parse().ok(),
+        Value::Number(n) if *n >= 0.0 && n.fract() == 0.0 && *n <= MAX_NUMBER_SEED as f64 => {
+            Some(*n as u64)
+        }
+        _ => None,
+    }
+}
+
+impl ChatOptions {
+    /// Read `{"backend": "fast-local", "stop": ["\n\n"], "max_chars": 2000,
+    /// "strip_fences": true, "memory": true, "seed": 42, "glossary":
+    /// "contracts-de", "user_id": "...", "tenant_id": "..."}`; other keys are ignored so the same object can
+    /// carry pipeline options. A seed may also be a decimal string, which
+    /// is how seeds above [`MAX_NUMBER_SEED`] must be given: a JSON number
+    /// that large has already been rounded, so it is ignored.
+    pub fn from_json(value: &Value) -> Self {
+        ChatOptions {
+            backend: value
+                .get("backend")
+                .and_then(Value::as_str)
+                .map(String::from),
+            stop: match value.get("stop") {
+                Some(Value::String(s)) => vec![s.clone()],
+                Some(Value::Array(items)) => items
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .filter(|s| !s.is_empty())
+                    .map(String::from)
+                    .collect(),
+                _ => Vec::new(),
+            },
+            max_chars: value
+                .get("max_chars")
+                .and_then(Value::as_f64)
+                .filter(|n| *n >= 1.0)
+                .map(|n| n as usize),
+            strip_fences: value.get("strip_fences").and_then(Value::as_bool) == Some(true),
+            memory: value.get("memory").and_then(Value::as_bool) == Some(true),
+            seed: value.get("seed").and_then(seed_from_json),
+            glossary: value
+                .get("glossary")
+                .and_then(Value::as_str)
+                .map(String::from),
+            user_id: value
+                .get("user_id")
+                .and_then(Value::as_str)
+                .map(String::from),
+            tenant_id: value
+                .get("tenant_id")
+                .and_then(Value::as_str)
+                .map(String::from),
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut pairs = Vec::new();
+        if let Some(backend) = &self.backend {
+            pairs.push(("backend", Value::from(backend.as_str())));
+        }
+        if !self.stop.is_empty() {
+            pairs.push((
+                "stop",
+                Value::Array(self.stop.iter().map(|s| Value::from(s.as_str())).collect()),
+            ));
+        }
+        if let Some(max_chars) = self.max_chars {
+            pairs.push(("max_chars", Value::from(max_chars)));
+        }
+        pairs.push(("strip_fences", Value::from(self.strip_fences)));
+        pairs.push(("memory", Value::from(self.memory)));
+        if let Some(seed) = self.seed {
+            // As a string: JSON numbers are doubles and would round it.
+            pairs.push(("seed", Value::from(seed.to_string())));
+        }
+        if let Some(glossary) = &self.glossary {
+            pairs.push(("glossary", Value::from(glossary.as_str())));
+        }
+        pairs.extend(self.tags().pairs());
+        Value::object(pairs)
+    }
+
+    pub fn tags(&self) -> Tags {
+        Tags::new(self.tenant_id.as_deref(), self.user_id.as_deref())
+    }
+
+    /// Apply the stop sequences, fence stripping and length limit to a
+    /// backend reply.
+    pub fn filter(&self, reply: &str) -> String {
+        let mut text = reply;
+        if let Some(cut) = self.stop.iter().filter_map(|s| text.find(s.as_str())).min() {
+            text = &text[..cut];
+        }
+        let mut text = if self.strip_fences {
+            strip_fences(text)
+        } else {
+            text.to_string()
+        };
+        if let Some((cut, _)) = self.max_chars.and_then(|n| text.char_indices().nth(n)) {
+            text.truncate(cut);
+        }
+        text
+    }
+}
+
+/// Drop the ```` ``` ```` and `~~~` delimiter lines (with any language tag)
+/// from a reply, keeping what they enclosed.
+pub fn strip_fences(text: &str) -> String {
+    let kept: Vec<&str> = text
+        .lines()
+        .filter(|line| {
+            let line = line.trim_start();
+            !(line.starts_with("```") || line.starts_with("~~~"))
+        })
+        .collect();
+    kept.join("\n").trim().to_string()
+}
+
+/// Send a prompt through the selected backend profile, falling back along
+/// the configured chain on failure. The reply is filtered per `options`
+/// whichever backend answered; registered guardrail hooks then run on the
+/// prompt (after any recalled memories are added) and the reply. A seeded
+/// call whose exact request was answered before is answered from the
+/// [`cache`]. With a [`glossary`] selected, its relevant entries are added
+/// to the prompt and a reply that breaks it is sent back before failing
+/// with [`Error::Terminology`]. Each exchange is counted against the
+/// caller's tags and recorded in the audit log when one is configured.
+pub fn chat(prompt: &str, options: &ChatOptions) -> Result<String> {
+    let chain = backend::chain(options.backend.as_deref())?;
+    let tags = options.tags();
+    let prompt = if options.memory {
+        memory::recall(prompt, &tags)
+    } else {
+        prompt.to_string()
+    };
+    let prompt = hooks::apply(hooks::Stage::Pre, &prompt);
+    let reply = match &options.glossary {
+        Some(name) => glossary::enforce(
+            &glossary::glossary(name)?,
+            &prompt,
+            glossary::RETRIES,
+            |request| exchange(&chain, request, options, &tags),
+        )?,
+        None => exchange(&chain, &prompt, options, &tags)?,
+    };
+    Ok(hooks::apply(hooks::Stage::Post, &reply))
+}
+
+/// One round trip for [`chat`]: the filtered reply to `prompt`.
+fn exchange(
+    chain: &[backend::Profile],
+    prompt: &str,
+    options: &ChatOptions,
+    tags: &Tags,
+) -> Result<String> {
+    let started = Instant::now();
+    let cache_key = options
+        .seed
+        .map(|seed| cache::key(chain, seed, tags, prompt));
+    let result = match cache_key.as_deref().and_then(cache::get) {
+        Some(hit) => {
+            backend::answered(&hit.1);
+            Ok(hit)
+        }
+        None => {
+            let result = telemetry::measure("chat", || {
+                backend::send_with_fallback(chain, prompt, options.seed)
+            });
+            if let (Some(key), Ok((reply, answered_by))) = (&cache_key, &result) {
+                cache::put(key, reply, answered_by);
+            }
+            result
+        }
+    };
+    let answered = result
+        .as_ref()
+        .ok()
+        .and_then(|(_, name)| chain.iter().find(|profile| &profile.name == name));
+    let outcome = result.as_ref().map(|(reply, _)| reply.as_str());
+    usage::record(tags, prompt, outcome);
+    audit::record(&audit::Entry {
+        prompt,
+        outcome,
+        backend: answered.map(|profile| profile.name.as_str()),
+        model: answered.and_then(|profile| profile.model()).as_deref(),
+        user_id: options.user_id.as_deref(),
+        tenant_id: options.tenant_id.as_deref(),
+        duration: started.elapsed(),
+    });
+    let (reply, _answered_by) = result?;
+    Ok(options.filter(&reply))
+}
+
+/// A prompt asking `question` about `document`, to be answered from the
+/// document alone. A long document is [excerpted](ask::excerpt) to the
+/// passages most relevant to the question.
+pub fn ask_prompt(document: &str, question: &str) -> String {
+    let document = ask::excerpt(document, question);
+    format!(
+        "Answer the question using only the document below. If the document does not \
+         contain the answer, say so.\n\nQuestion: {question}\n\nDocument:\n{document}"
+    )
+}
+
+/// Send a prompt to the default backend profile (`kiro-cli chat` unless
+/// configured otherwise).
+pub fn kiro_chat(prompt: &str) -> Result<String> {
+    chat(prompt, &ChatOptions::default())
+}
+
+/// Ask `chat` for JSON matching `schema` and validate the reply. Invalid
+/// replies are sent back with the violations, up to `max_retries` times,
+/// before failing with [`Error::Schema`] (or [`Error::Backend`] when no
+/// JSON could be found at all).
+pub fn chat_json(
+    prompt: &str,
+    schema: &Value,
+    max_retries: usize,
+    mut chat: impl FnMut(&str) -> Result<String>,
+) -> Result<Value> {
+    let mut request = format!(
+        "{prompt}\n\nRespond with only a JSON value, no prose or code fences, \
+         that conforms to this JSON Schema:\n{schema}"
+    );
+    let mut last = Error::Backend("no reply".into());
+    for _ in 0..=max_retries {
+        let reply = chat(&request)?;
+        let problems = match json::parse_embedded(&reply) {
+            Ok(value) => {
+                let violations = schema::validate(&value, schema);
+                if violations.is_empty() {
+                    return Ok(value);
+                }
+                last = Error::Schema(violations.clone());
+                violations
+            }
+            Err(_) => {
+                last = Error::Backend("reply contained no JSON".into());
+                vec!["the reply was not valid JSON".into()]
+            }
+        };
+        request = format!(
+            "{prompt}\n\nYour previous reply was rejected:\n- {}\n\n\
+             Respond again with only a JSON value that conforms to this JSON Schema:\n{schema}",
+            problems.join("\n- ")
+        );
+    }
+    Err(last)
+}
+
+pub fn strip_ansi(s: &str) -> String {
+    let mut result = String::new();
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            if chars.peek() == Some(&'[') {
+                chars.next();
+                while let Some(&nc) = chars.peek() {
+                    chars.next();
+                    if nc.is_ascii_alphabetic() {
+                        break;
+                    }
+                }
+            }
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
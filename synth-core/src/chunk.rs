@@ -0,0 +1,67 @@
+//! Splitting long text into prompt-sized pieces.
+
+use crate::segment;
+
+/// A contiguous slice of the source text. Offsets are in characters, and
+/// consecutive chunks cover the text exactly with no gaps or overlap.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    pub start: usize,
+    pub end: usize,
+    /// `start` and `end` in UTF-16 code units.
+    pub start_utf16: usize,
+    pub end_utf16: usize,
+    pub text: String,
+}
+
+/// Split `text` into chunks of at most `max_chars` characters, preferring
+/// to break after a paragraph, then a line break, then a sentence end (as
+/// found by [`segment::sentences`]), then any whitespace.
+pub fn chunk_text(text: &str, max_chars: usize) -> Vec<Chunk> {
+    let chars: Vec<char> = text.chars().collect();
+    let max_chars = max_chars.max(1);
+    let sentence_ends: Vec<usize> = segment::sentences(text).iter().map(|r| r.end).collect();
+    let utf16 = segment::utf16_offsets(text);
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < chars.len() {
+        let end = if chars.len() - start <= max_chars {
+            chars.len()
+        } else {
+            break_point(&chars[start..start + max_chars], start, &sentence_ends)
+                .map_or(start + max_chars, |n| start + n)
+        };
+        chunks.push(Chunk {
+            start,
+            end,
+            start_utf16: utf16[start],
+            end_utf16: utf16[end],
+            text: chars[start..end].iter().collect(),
+        });
+        start = end;
+    }
+    chunks
+}
+
+/// Length of the best prefix of `window` (starting at char `offset`) to
+/// cut at, if there is a natural break in its second half.
+fn break_point(window: &[char], offset: usize, sentence_ends: &[usize]) -> Option<usize> {
+    let floor = window.len() / 2;
+    let after = |pred: &dyn Fn(usize) -> bool| {
+        (floor..window.len())
+            .rev()
+            .find(|&i| pred(i))
+            .map(|i| i + 1)
+    };
+    // Keep the space after a sentence with it, when there is one.
+    let ends_at = |n: usize| sentence_ends.binary_search(&(offset + n)).is_ok();
+    after(&|i| window[i] == '\n' && i > 0 && window[i - 1] == '\n')
+        .or_else(|| after(&|i| window[i] == '\n'))
+        .or_else(|| {
+            after(&|i| {
+                (window[i].is_whitespace() && ends_at(i))
+                    || (ends_at(i + 1) && !window.get(i + 1).is_some_and(|c| c.is_whitespace()))
+            })
+        })
+        .or_else(|| after(&|i| window[i].is_whitespace()))
+}
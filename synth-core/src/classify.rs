@@ -0,0 +1,161 @@
+//! Document classification against caller-defined labels.
+
+use std::collections::HashMap;
+
+use crate::error::{Error, Result};
+use crate::json::{self, Value};
+use crate::words::{cosine, term_counts};
+
+/// Document text sent to the backend is capped at this many characters.
+const PROMPT_CHARS: usize = 24_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub name: String,
+    /// Optional longer description used to match and to brief the backend.
+    pub description: Option<String>,
+}
+
+impl Label {
+    /// Parse `["invoice", {"label": "contract", "description": "..."}]`.
+    pub fn list_from_json(value: &Value) -> Result<Vec<Label>> {
+        let items = value
+            .as_array()
+            .ok_or_else(|| Error::Parse("labels must be an array".into()))?;
+        let labels: Vec<Label> = items
+            .iter()
+            .filter_map(|item| match item {
+                Value::String(name) => Some(Label {
+                    name: name.clone(),
+                    description: None,
+                }),
+                other => Some(Label {
+                    name: other.get("label")?.as_str()?.to_string(),
+                    description: other
+                        .get("description")
+                        .and_then(Value::as_str)
+                        .map(String::from),
+                }),
+            })
+            .collect();
+        if labels.is_empty() {
+            return Err(Error::Parse("no labels given".into()));
+        }
+        Ok(labels)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    /// Ask the chat backend to pick labels.
+    Backend,
+    /// Term-overlap similarity between the text and each label, computed
+    /// locally with no backend call.
+    Lexical,
+}
+
+impl Method {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "backend" => Some(Method::Backend),
+            "lexical" => Some(Method::Lexical),
+            _ => None,
+        }
+    }
+}
+
+/// Labels with a confidence in `0.0..=1.0`, best first.
+pub type Ranking = Vec<(String, f64)>;
+
+pub fn ranking_to_json(ranking: &Ranking) -> Value {
+    Value::Array(
+        ranking
+            .iter()
+            .map(|(label, confidence)| {
+                Value::object([
+                    ("label", Value::from(label.as_str())),
+                    (
+                        "confidence",
+                        Value::from((confidence * 1000.0).round() / 1000.0),
+                    ),
+                ])
+            })
+            .collect(),
+    )
+}
+
+/// Score each label by cosine similarity of content-word counts between
+/// the text and the label name plus description. Scores are normalized to
+/// sum to 1 so they read as relative confidence.
+pub fn classify_lexical(text: &str, labels: &[Label]) -> Ranking {
+    let doc = term_counts(text);
+    let mut scores: Vec<(String, f64)> = labels
+        .iter()
+        .map(|label| {
+            let description = label.description.as_deref().unwrap_or("");
+            let probe = term_counts(&format!("{} {description}", label.name));
+            (label.name.clone(), cosine(&doc, &probe))
+        })
+        .collect();
+    let total: f64 = scores.iter().map(|(_, s)| s).sum();
+    if total > 0.0 {
+        for (_, score) in &mut scores {
+            *score /= total;
+        }
+    }
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scores
+}
+
+/// Ask `chat` which labels fit. Labels the backend invents are dropped and
+/// confidences are clamped to `0.0..=1.0`.
+pub fn classify_with_backend(
+    text: &str,
+    labels: &[Label],
+    mut chat: impl FnMut(&str) -> Result<String>,
+) -> Result<Ranking> {
+    let mut prompt = String::from(
+        "Classify the document below using only these labels. A document may fit \
+         more than one. Respond with only a JSON object of the form \
+         {\"labels\": [{\"label\": \"...\", \"confidence\": 0.0}]} listing the labels \
+         that apply, with confidence between 0 and 1.\n\nLabels:\n",
+    );
+    for label in labels {
+        match &label.description {
+            Some(description) => prompt.push_str(&format!("- {}: {description}\n", label.name)),
+            None => prompt.push_str(&format!("- {}\n", label.name)),
+        }
+    }
+    let excerpt = text
+        .char_indices()
+        .nth(PROMPT_CHARS)
+        .map_or(text, |(cut, _)| &text[..cut]);
+    prompt.push_str("\nDocument:\n");
+    prompt.push_str(excerpt);
+
+    let reply = json::parse_embedded(&chat(&prompt)?)
+        .map_err(|_| Error::Backend("classification reply was not JSON".into()))?;
+    // A label the backend repeats keeps its highest confidence.
+    let mut best: HashMap<String, f64> = HashMap::new();
+    let found = reply
+        .get("labels")
+        .and_then(Value::as_array)
+        .unwrap_or(&[])
+        .iter()
+        .filter_map(|item| {
+            let name = item.get("label")?.as_str()?;
+            let label = labels.iter().find(|l| l.name.eq_ignore_ascii_case(name))?;
+            let confidence = item
+                .get("confidence")
+                .and_then(Value::as_f64)
+                .unwrap_or(0.5);
+            Some((label.name.clone(), confidence.clamp(0.0, 1.0)))
+        });
+    for (name, confidence) in found {
+        let slot = best.entry(name).or_insert(confidence);
+        *slot = slot.max(confidence);
+    }
+    let mut ranking: Ranking = best.into_iter().collect();
+    ranking.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    Ok(ranking)
+}
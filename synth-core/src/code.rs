@@ -0,0 +1,599 @@
+//! Source-code ingestion: language detection and declaration-level chunks.
+//!
+//! Boundaries come from a lightweight scanner (bracket depth for brace
+//! languages, indentation for Python) rather than a full parser, which is
+//! enough to keep functions, types and impl blocks intact.
+
+use std::path::Path;
+
+use crate::json::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Rust,
+    Swift,
+    Python,
+    JavaScript,
+    TypeScript,
+    Go,
+    Java,
+    Kotlin,
+    CSharp,
+    C,
+    Cpp,
+}
+
+impl Language {
+    pub fn from_path(path: &Path) -> Option<Language> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        let language = match ext.as_str() {
+            "rs" => Language::Rust,
+            "swift" => Language::Swift,
+            "py" | "pyi" => Language::Python,
+            "js" | "jsx" | "mjs" | "cjs" => Language::JavaScript,
+            "ts" | "tsx" => Language::TypeScript,
+            "go" => Language::Go,
+            "java" => Language::Java,
+            "kt" | "kts" => Language::Kotlin,
+            "cs" => Language::CSharp,
+            "c" | "h" => Language::C,
+            "cc" | "cpp" | "cxx" | "hpp" | "hh" | "m" | "mm" => Language::Cpp,
+            _ => return None,
+        };
+        Some(language)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::Rust => "rust",
+            Language::Swift => "swift",
+            Language::Python => "python",
+            Language::JavaScript => "javascript",
+            Language::TypeScript => "typescript",
+            Language::Go => "go",
+            Language::Java => "java",
+            Language::Kotlin => "kotlin",
+            Language::CSharp => "csharp",
+            Language::C => "c",
+            Language::Cpp => "cpp",
+        }
+    }
+
+    fn line_comment(self) -> &'static str {
+        match self {
+            Language::Python => "#",
+            _ => "//",
+        }
+    }
+
+    fn is_attribute(self, trimmed: &str) -> bool {
+        match self {
+            Language::Rust => trimmed.starts_with("#["),
+            Language::C | Language::Cpp => trimmed.starts_with("[["),
+            Language::CSharp => trimmed.starts_with('['),
+            _ => trimmed.starts_with('@'),
+        }
+    }
+
+    fn keywords(self) -> &'static [&'static str] {
+        match self {
+            Language::Rust => &[
+                "fn",
+                "struct",
+                "enum",
+                "trait",
+                "impl",
+                "mod",
+                "union",
+                "macro_rules!",
+            ],
+            Language::Swift => &[
+                "func",
+                "class",
+                "struct",
+                "enum",
+                "protocol",
+                "extension",
+                "actor",
+            ],
+            Language::Python => &["def", "class"],
+            Language::JavaScript => &["function", "class"],
+            Language::TypeScript => &["function", "class", "interface", "enum", "namespace"],
+            Language::Go => &["func", "type"],
+            Language::Java => &["class", "interface", "enum", "record"],
+            Language::Kotlin => &["fun", "class", "interface", "object"],
+            Language::CSharp => &[
+                "class",
+                "interface",
+                "enum",
+                "struct",
+                "record",
+                "namespace",
+            ],
+            Language::C | Language::Cpp => &["struct", "class", "enum", "union", "namespace"],
+        }
+    }
+}
+
+const MODIFIERS: &[&str] = &[
+    "pub",
+    "export",
+    "default",
+    "async",
+    "static",
+    "public",
+    "private",
+    "protected",
+    "internal",
+    "fileprivate",
+    "open",
+    "final",
+    "abstract",
+    "override",
+    "unsafe",
+    "extern",
+    "\"C\"",
+    "const",
+    "inline",
+    "virtual",
+    "sealed",
+    "data",
+    "suspend",
+    "mutating",
+    "partial",
+];
+
+/// A contiguous range of source lines.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeChunk {
+    /// Declaration keyword (`fn`, `class`, ...), `block` for an unnamed
+    /// braced item, or `statements` for imports and other loose code.
+    pub kind: String,
+    pub name: Option<String>,
+    /// 1-based, inclusive.
+    pub start_line: usize,
+    pub end_line: usize,
+    pub text: String,
+}
+
+impl CodeChunk {
+    pub fn to_json(&self) -> Value {
+        Value::object([
+            ("kind", Value::from(self.kind.as_str())),
+            (
+                "name",
+                self.name.as_deref().map_or(Value::Null, Value::from),
+            ),
+            ("start_line", Value::from(self.start_line)),
+            ("end_line", Value::from(self.end_line)),
+            ("text", Value::from(self.text.as_str())),
+        ])
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LineKind {
+    Blank,
+    Comment,
+    Attribute,
+    Code,
+}
+
+struct Item {
+    start: usize,
+    end: usize,
+    decl: Option<(String, String)>,
+    had_block: bool,
+}
+
+/// Split `source` into declaration-level chunks. Chunks longer than
+/// `max_lines` are split further, preferring blank lines as cut points.
+pub fn chunk_code(source: &str, language: Language, max_lines: Option<usize>) -> Vec<CodeChunk> {
+    let lines: Vec<&str> = source.lines().collect();
+    let scan = scan_lines(&lines, language);
+    let items = if language == Language::Python {
+        indent_items(&lines, &scan, language)
+    } else {
+        brace_items(&lines, &scan, language)
+    };
+
+    let mut chunks = Vec::new();
+    let mut loose_start: Option<usize> = None;
+    let mut cursor = 0;
+    for item in &items {
+        let named =
+            item.decl.is_some() || (item.had_block && !lines[item.end].trim().ends_with(';'));
+        if !named {
+            loose_start.get_or_insert(cursor);
+            cursor = item.end + 1;
+            continue;
+        }
+        let lead = lead_start(&scan, item.start, cursor);
+        if let Some(start) = loose_start.take() {
+            push_chunk(&mut chunks, &lines, "statements", None, start, lead);
+        } else if cursor < lead {
+            push_chunk(&mut chunks, &lines, "statements", None, cursor, lead);
+        }
+        let (kind, name) = match &item.decl {
+            Some((kind, name)) => (kind.as_str(), Some(name.clone())),
+            None => ("block", None),
+        };
+        push_chunk(&mut chunks, &lines, kind, name, lead, item.end + 1);
+        cursor = item.end + 1;
+    }
+    push_chunk(
+        &mut chunks,
+        &lines,
+        "statements",
+        None,
+        loose_start.unwrap_or(cursor),
+        lines.len(),
+    );
+
+    match max_lines {
+        Some(limit) if limit > 0 => chunks
+            .into_iter()
+            .flat_map(|chunk| split_chunk(chunk, limit))
+            .collect(),
+        _ => chunks,
+    }
+}
+
+/// First line of the comments and attributes directly above `start`.
+fn lead_start(scan: &[(LineKind, bool)], start: usize, floor: usize) -> usize {
+    let mut lead = start;
+    while lead > floor
+        && scan[lead - 1].1
+        && matches!(scan[lead - 1].0, LineKind::Comment | LineKind::Attribute)
+    {
+        lead -= 1;
+    }
+    lead
+}
+
+fn push_chunk(
+    chunks: &mut Vec<CodeChunk>,
+    lines: &[&str],
+    kind: &str,
+    name: Option<String>,
+    start: usize,
+    end: usize,
+) {
+    let mut start = start;
+    let mut end = end.min(lines.len());
+    while start < end && lines[start].trim().is_empty() {
+        start += 1;
+    }
+    while end > start && lines[end - 1].trim().is_empty() {
+        end -= 1;
+    }
+    if start >= end {
+        return;
+    }
+    chunks.push(CodeChunk {
+        kind: kind.to_string(),
+        name,
+        start_line: start + 1,
+        end_line: end,
+        text: lines[start..end].join("\n"),
+    });
+}
+
+fn split_chunk(chunk: CodeChunk, limit: usize) -> Vec<CodeChunk> {
+    let lines: Vec<&str> = chunk.text.split('\n').collect();
+    if lines.len() <= limit {
+        return vec![chunk];
+    }
+    let mut parts = Vec::new();
+    let mut start = 0;
+    while start < lines.len() {
+        let hard_end = (start + limit).min(lines.len());
+        let end = if hard_end == lines.len() {
+            hard_end
+        } else {
+            (start + limit / 2..hard_end)
+                .rev()
+                .find(|&i| lines[i].trim().is_empty())
+                .map_or(hard_end, |i| i + 1)
+        };
+        parts.push(CodeChunk {
+            kind: chunk.kind.clone(),
+            name: chunk.name.clone(),
+            start_line: chunk.start_line + start,
+            end_line: chunk.start_line + end - 1,
+            text: lines[start..end].join("\n"),
+        });
+        start = end;
+    }
+    parts
+}
+
+/// Per line: what it contains and whether it starts at nesting depth 0.
+fn scan_lines(lines: &[&str], language: Language) -> Vec<(LineKind, bool)> {
+    let mut scanner = Scanner::new(language);
+    lines
+        .iter()
+        .map(|line| {
+            let top = scanner.depth == 0 && scanner.string.is_none();
+            let was_in_comment = scanner.in_block_comment;
+            scanner.scan(line);
+            let trimmed = line.trim();
+            let kind = if trimmed.is_empty() {
+                LineKind::Blank
+            } else if was_in_comment
+                || trimmed.starts_with(language.line_comment())
+                || trimmed.starts_with("/*")
+            {
+                LineKind::Comment
+            } else if language.is_attribute(trimmed) {
+                LineKind::Attribute
+            } else {
+                LineKind::Code
+            };
+            let top = top && (language != Language::Python || !line.starts_with([' ', '\t']));
+            (kind, top)
+        })
+        .collect()
+}
+
+fn brace_items(lines: &[&str], scan: &[(LineKind, bool)], language: Language) -> Vec<Item> {
+    let mut scanner = Scanner::new(language);
+    let mut items = Vec::new();
+    let mut current: Option<Item> = None;
+    for (i, line) in lines.iter().enumerate() {
+        if current.is_none() && scan[i].1 && scan[i].0 == LineKind::Code {
+            current = Some(Item {
+                start: i,
+                end: i,
+                decl: declaration(line, language),
+                had_block: false,
+            });
+        }
+        let opened = scanner.scan(line);
+        let Some(item) = current.as_mut() else {
+            continue;
+        };
+        item.had_block |= opened;
+        item.end = i;
+        let next = lines[i + 1..]
+            .iter()
+            .map(|l| l.trim())
+            .find(|l| !l.is_empty());
+        if scanner.depth == 0 && !scanner.in_multiline() && !continues(line.trim(), next) {
+            items.extend(current.take());
+        }
+    }
+    items.extend(current);
+    items
+}
+
+fn continues(line: &str, next: Option<&str>) -> bool {
+    const TRAILING: [&str; 8] = [",", "(", "=", "->", "&&", "||", "+", "."];
+    const LEADING: [&str; 6] = ["{", "where", "->", ":", ".", "?"];
+    if line.ends_with(';') || line.ends_with('}') {
+        return next.is_some_and(|n| n.starts_with("else") || n.starts_with("where"));
+    }
+    TRAILING.iter().any(|t| line.ends_with(t))
+        || next.is_some_and(|n| LEADING.iter().any(|l| n.starts_with(l)))
+}
+
+fn indent_items(lines: &[&str], scan: &[(LineKind, bool)], language: Language) -> Vec<Item> {
+    let mut items: Vec<Item> = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let (kind, top) = scan[i];
+        if kind == LineKind::Blank {
+            continue;
+        }
+        if top && kind == LineKind::Code {
+            items.push(Item {
+                start: i,
+                end: i,
+                decl: declaration(line, language),
+                had_block: false,
+            });
+        } else if !top {
+            // Indented lines extend the item above unless a top-level
+            // comment or decorator came in between.
+            if let Some(last) = items.last_mut() {
+                if (last.end + 1..i).all(|j| scan[j].0 == LineKind::Blank || !scan[j].1) {
+                    last.end = i;
+                }
+            }
+        }
+    }
+    items
+}
+
+fn declaration(line: &str, language: Language) -> Option<(String, String)> {
+    let mut rest = line.trim();
+    loop {
+        let word_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let word = &rest[..word_end];
+        if MODIFIERS.contains(&word) || word.starts_with("pub(") {
+            rest = rest[word_end..].trim_start();
+        } else {
+            break;
+        }
+    }
+    let keyword = language
+        .keywords()
+        .iter()
+        .find(|kw| {
+            rest.starts_with(**kw)
+                && rest[kw.len()..]
+                    .chars()
+                    .next()
+                    .is_none_or(|c| !c.is_alphanumeric() && c != '_')
+        })?
+        .to_string();
+    let mut after = rest[keyword.len()..].trim_start();
+    if matches!(keyword.as_str(), "impl" | "extension") {
+        if after.starts_with('<') {
+            after = skip_generics(after);
+        }
+        let end = after.find(['{', ';']).unwrap_or(after.len());
+        let end = after[..end].find(" where").unwrap_or(end);
+        let name = after[..end].trim().to_string();
+        return Some((keyword, name));
+    }
+    if language == Language::Go && after.starts_with('(') {
+        after = after
+            .find(')')
+            .map_or(after, |close| after[close + 1..].trim_start());
+    }
+    let name: String = after
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == '$')
+        .collect();
+    Some((keyword, name))
+}
+
+fn skip_generics(text: &str) -> &str {
+    let mut depth = 0usize;
+    for (i, c) in text.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    return text[i + 1..].trim_start();
+                }
+            }
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Tracks bracket depth, block comments and multi-line strings across lines.
+struct Scanner {
+    language: Language,
+    depth: usize,
+    in_block_comment: bool,
+    string: Option<&'static str>,
+}
+
+impl Scanner {
+    fn new(language: Language) -> Self {
+        Scanner {
+            language,
+            depth: 0,
+            in_block_comment: false,
+            string: None,
+        }
+    }
+
+    fn in_multiline(&self) -> bool {
+        self.in_block_comment || self.string.is_some()
+    }
+
+    /// Advance over one line; returns true if it opened a `{` block.
+    fn scan(&mut self, line: &str) -> bool {
+        let bytes = line.as_bytes();
+        let mut opened = false;
+        let mut i = 0;
+        while i < bytes.len() {
+            let rest = &line[i..];
+            if self.in_block_comment {
+                match rest.find("*/") {
+                    Some(end) => {
+                        self.in_block_comment = false;
+                        i += end + 2;
+                        continue;
+                    }
+                    None => return opened,
+                }
+            }
+            if let Some(delim) = self.string {
+                match find_unescaped(rest, delim) {
+                    Some(end) => {
+                        self.string = None;
+                        i += end + delim.len();
+                        continue;
+                    }
+                    None => return opened,
+                }
+            }
+            if rest.starts_with(self.language.line_comment()) {
+                return opened;
+            }
+            if self.language != Language::Python && rest.starts_with("/*") {
+                self.in_block_comment = true;
+                i += 2;
+                continue;
+            }
+            if let Some(delim) = self.string_start(rest) {
+                let body = &rest[delim.len()..];
+                match find_unescaped(body, delim) {
+                    Some(end) => i += delim.len() + end + delim.len(),
+                    None if delim.len() == 3 || delim == "`" => {
+                        self.string = Some(delim);
+                        return opened;
+                    }
+                    None => return opened,
+                }
+                continue;
+            }
+            match bytes[i] {
+                b'{' => {
+                    self.depth += 1;
+                    opened = true;
+                }
+                b'(' | b'[' => self.depth += 1,
+                b'}' | b')' | b']' => self.depth = self.depth.saturating_sub(1),
+                _ => {}
+            }
+            i += 1;
+        }
+        opened
+    }
+
+    fn string_start(&self, rest: &str) -> Option<&'static str> {
+        if rest.starts_with("\"\"\"") {
+            return Some("\"\"\"");
+        }
+        if self.language == Language::Python && rest.starts_with("'''") {
+            return Some("'''");
+        }
+        if rest.starts_with('"') {
+            return Some("\"");
+        }
+        if matches!(
+            self.language,
+            Language::JavaScript | Language::TypeScript | Language::Go
+        ) && rest.starts_with('`')
+        {
+            return Some("`");
+        }
+        // Single quotes are strings in Python/JS and char literals elsewhere;
+        // Rust lifetimes (`'a`) have no closing quote right after one char.
+        if let Some(body) = rest.strip_prefix('\'') {
+            let quoted_char = match body.strip_prefix('\\') {
+                Some(escape) => escape.find('\'').is_some_and(|end| end < 10),
+                None => body.chars().nth(1) == Some('\''),
+            };
+            let is_string = matches!(
+                self.language,
+                Language::Python | Language::JavaScript | Language::TypeScript
+            );
+            if is_string || quoted_char {
+                return Some("'");
+            }
+        }
+        None
+    }
+}
+
+fn find_unescaped(text: &str, delim: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if text[i..].starts_with(delim) {
+            return Some(i);
+        }
+    }
+    None
+}
@@ -0,0 +1,195 @@
+//! Side-by-side comparison of several documents through the chat backend.
+
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+use crate::extract::extract_text;
+use crate::json::{self, Value};
+use crate::progress::{Progress, Stage};
+use crate::table::markdown_table;
+
+/// Total document text sent in one comparison prompt. Each document gets
+/// an equal share and longer ones are truncated.
+const PROMPT_BUDGET: usize = 48_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    pub columns: Vec<String>,
+    /// `(document path, one value per column)`.
+    pub rows: Vec<(PathBuf, Vec<String>)>,
+}
+
+impl Comparison {
+    pub fn to_markdown(&self) -> String {
+        let mut columns = vec!["Document".to_string()];
+        columns.extend(self.columns.iter().cloned());
+        let rows: Vec<Vec<String>> = self
+            .rows
+            .iter()
+            .map(|(path, values)| {
+                let mut row = vec![display_name(path)];
+                row.extend(values.iter().cloned());
+                row
+            })
+            .collect();
+        markdown_table(&columns, &rows)
+    }
+
+    pub fn to_json(&self) -> Value {
+        Value::object([
+            (
+                "columns",
+                Value::Array(
+                    self.columns
+                        .iter()
+                        .map(|c| Value::from(c.as_str()))
+                        .collect(),
+                ),
+            ),
+            (
+                "rows",
+                Value::Array(
+                    self.rows
+                        .iter()
+                        .map(|(path, values)| {
+                            Value::object([
+                                ("document", Value::from(path.display().to_string())),
+                                (
+                                    "values",
+                                    Value::Array(
+                                        values.iter().map(|v| Value::from(v.as_str())).collect(),
+                                    ),
+                                ),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            ("markdown", Value::from(self.to_markdown())),
+        ])
+    }
+}
+
+fn display_name(path: &Path) -> String {
+    path.file_name().map_or_else(
+        || path.display().to_string(),
+        |n| n.to_string_lossy().into_owned(),
+    )
+}
+
+/// Extract each document and ask `chat` for a comparison table. `criteria`
+/// is either a JSON array of column names or a free-text description, in
+/// which case the backend picks the columns. `progress` hears about each
+/// document extracted and the backend call.
+pub fn compare_corpus(
+    paths: &[PathBuf],
+    criteria: &str,
+    mut chat: impl FnMut(&str) -> Result<String>,
+    mut progress: impl FnMut(&Progress),
+) -> Result<Comparison> {
+    if paths.is_empty() {
+        return Err(Error::Parse("no documents to compare".into()));
+    }
+    let fixed_columns: Option<Vec<String>> = json::parse(criteria).ok().and_then(|value| {
+        value.as_array().map(|items| {
+            items
+                .iter()
+                .filter_map(Value::as_str)
+                .map(String::from)
+                .collect()
+        })
+    });
+
+    let share = PROMPT_BUDGET / paths.len();
+    let mut prompt = String::from("Compare the following documents ");
+    match &fixed_columns {
+        Some(columns) => prompt.push_str(&format!(
+            "on exactly these criteria, in this order: {}.\n",
+            columns.join("; ")
+        )),
+        None => prompt.push_str(&format!("on these criteria: {}\n", criteria.trim())),
+    }
+    prompt.push_str(
+        "Respond with only a JSON object of the form \
+         {\"columns\": [\"criterion\", ...], \
+         \"rows\": [{\"document\": 1, \"values\": [\"...\", ...]}]} \
+         with one row per document, using its number, and one value per column. \
+         Keep values short; use \"not stated\" when a document does not cover a criterion.\n",
+    );
+    for (index, path) in paths.iter().enumerate() {
+        let name = display_name(path);
+        progress(&Progress {
+            stage: Stage::Extracting,
+            completed: index,
+            total: paths.len(),
+            percent: 50.0 * index as f64 / paths.len() as f64,
+            item: Some(&name),
+        });
+        let text = extract_text(path)?;
+        let (excerpt, truncated) = match text.char_indices().nth(share) {
+            Some((cut, _)) => (&text[..cut], true),
+            None => (text.as_str(), false),
+        };
+        prompt.push_str(&format!("\n--- Document {}: {} ---\n", index + 1, name));
+        prompt.push_str(excerpt);
+        if truncated {
+            prompt.push_str("\n[remainder truncated]");
+        }
+        prompt.push('\n');
+    }
+
+    progress(&Progress {
+        stage: Stage::Generating,
+        completed: 0,
+        total: 1,
+        percent: 50.0,
+        item: None,
+    });
+    let reply = json::parse_embedded(&chat(&prompt)?)
+        .map_err(|_| Error::Backend("comparison reply was not JSON".into()))?;
+    let columns = match fixed_columns {
+        Some(columns) => columns,
+        None => reply
+            .get("columns")
+            .and_then(Value::as_array)
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(String::from)
+                    .collect()
+            })
+            .ok_or_else(|| Error::Backend("comparison reply has no columns".into()))?,
+    };
+    let replied_rows = reply.get("rows").and_then(Value::as_array).unwrap_or(&[]);
+    let rows = paths
+        .iter()
+        .enumerate()
+        .map(|(index, path)| {
+            let row = replied_rows
+                .iter()
+                .find(|row| row.get("document").and_then(Value::as_f64) == Some((index + 1) as f64))
+                .or_else(|| replied_rows.get(index));
+            let mut values: Vec<String> = row
+                .and_then(|row| row.get("values"))
+                .and_then(Value::as_array)
+                .map(|items| {
+                    items
+                        .iter()
+                        .map(|v| v.as_str().map_or_else(|| v.to_string(), String::from))
+                        .collect()
+                })
+                .unwrap_or_default();
+            values.resize(columns.len(), String::new());
+            (path.clone(), values)
+        })
+        .collect();
+    progress(&Progress {
+        stage: Stage::Generating,
+        completed: 1,
+        total: 1,
+        percent: 100.0,
+        item: None,
+    });
+    Ok(Comparison { columns, rows })
+}
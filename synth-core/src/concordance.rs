@@ -0,0 +1,159 @@
+//! Term usage across a document: every occurrence of a word or phrase in
+//! context (a keyword-in-context concordance), and word frequencies.
+//!
+//! Matching uses the tokenization of [`words`](crate::words), so it is
+//! case-insensitive and whole-word: "term" does not match "terminate".
+
+use std::collections::{HashMap, HashSet};
+
+use crate::json::Value;
+use crate::words::{is_content_word, word_spans, words};
+
+/// One occurrence of the term. Offsets are in characters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Occurrence {
+    pub start: usize,
+    pub end: usize,
+    /// 1-based line and column of `start`.
+    pub line: usize,
+    pub column: usize,
+    /// The match as written, and up to `window` words each side with
+    /// their whitespace collapsed.
+    pub before: String,
+    pub matched: String,
+    pub after: String,
+}
+
+impl Occurrence {
+    pub fn to_json(&self) -> Value {
+        Value::object([
+            ("start", Value::from(self.start)),
+            ("end", Value::from(self.end)),
+            ("line", Value::from(self.line)),
+            ("column", Value::from(self.column)),
+            ("before", Value::from(self.before.as_str())),
+            ("match", Value::from(self.matched.as_str())),
+            ("after", Value::from(self.after.as_str())),
+        ])
+    }
+}
+
+pub fn occurrences_to_json(occurrences: &[Occurrence]) -> Value {
+    Value::Array(occurrences.iter().map(Occurrence::to_json).collect())
+}
+
+/// Every occurrence of `term` (one or more words) in `text`, in order,
+/// with `window` words of context on each side. Context does not run past
+/// a blank line.
+pub fn concordance(text: &str, term: &str, window: usize) -> Vec<Occurrence> {
+    let term = words(term);
+    if term.is_empty() {
+        return Vec::new();
+    }
+    let chars: Vec<char> = text.chars().collect();
+    let slice = |start: usize, end: usize| chars[start..end].iter().collect::<String>();
+    let spans = word_spans(text);
+    let mut line_starts = vec![0];
+    line_starts.extend(
+        chars
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| **c == '\n')
+            .map(|(i, _)| i + 1),
+    );
+    // Two line breaks between words means a blank line.
+    let paragraph_break =
+        |from: usize, to: usize| chars[from..to].iter().filter(|c| **c == '\n').count() >= 2;
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i + term.len() <= spans.len() {
+        if !spans[i..i + term.len()]
+            .iter()
+            .zip(&term)
+            .all(|((_, word), wanted)| word == wanted)
+        {
+            i += 1;
+            continue;
+        }
+        let last = i + term.len() - 1;
+        let (start, end) = (spans[i].0.start, spans[last].0.end);
+        let mut first = i;
+        while first > 0
+            && i - first < window
+            && !paragraph_break(spans[first - 1].0.end, spans[first].0.start)
+        {
+            first -= 1;
+        }
+        let mut after = last;
+        while after + 1 < spans.len()
+            && after - last < window
+            && !paragraph_break(spans[after].0.end, spans[after + 1].0.start)
+        {
+            after += 1;
+        }
+        let line = line_starts.partition_point(|&s| s <= start);
+        found.push(Occurrence {
+            start,
+            end,
+            line,
+            column: start - line_starts[line - 1] + 1,
+            before: slice(spans[first].0.start, start)
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" "),
+            matched: slice(start, end),
+            after: slice(end, spans[after].0.end)
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" "),
+        });
+        i = last + 1;
+    }
+    found
+}
+
+/// Word counts of `text`, most frequent first (ties alphabetically). With
+/// `content_only`, stopwords, bare numbers and single letters are left
+/// out. `top_n` caps the list (0 for all).
+pub fn word_frequencies(text: &str, content_only: bool, top_n: usize) -> Vec<(String, usize)> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for word in words(text) {
+        if !content_only || is_content_word(&word) {
+            *counts.entry(word).or_default() += 1;
+        }
+    }
+    let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    if top_n > 0 {
+        counts.truncate(top_n);
+    }
+    counts
+}
+
+/// `{"total_words": 1200, "distinct": 2, "words": [{"word": "term",
+/// "count": 40, "per_thousand": 33.3}]}` for the whole text.
+pub fn frequencies_to_json(text: &str, frequencies: &[(String, usize)]) -> Value {
+    let all = words(text);
+    let total = all.len();
+    let distinct = all.iter().collect::<HashSet<_>>().len();
+    let rows = frequencies
+        .iter()
+        .map(|(word, count)| {
+            let per_thousand = if total == 0 {
+                0.0
+            } else {
+                (*count as f64 * 10_000.0 / total as f64).round() / 10.0
+            };
+            Value::object([
+                ("word", Value::from(word.as_str())),
+                ("count", Value::from(*count)),
+                ("per_thousand", Value::from(per_thousand)),
+            ])
+        })
+        .collect();
+    Value::object([
+        ("total_words", Value::from(total)),
+        ("distinct", Value::from(distinct)),
+        ("words", Value::Array(rows)),
+    ])
+}
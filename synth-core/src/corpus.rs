@@ -0,0 +1,248 @@
+//! Golden-corpus regression checks for extraction.
+//!
+//! Every supported document under a directory is extracted with
+//! [`extract_text`] and compared to the `<file>.golden` stored beside it.
+//! Running with `update` rewrites the goldens from the current output.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::Result;
+use crate::extract::{extract_text, Format};
+use crate::json::Value;
+
+const GOLDEN_EXTENSION: &str = "golden";
+
+/// Diffs list at most this many changed lines per document.
+const DIFF_LINES: usize = 40;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Passed,
+    /// Output differs; a unified-style diff of golden against actual.
+    Failed(String),
+    /// No golden file yet.
+    Missing,
+    /// The golden was written (update mode).
+    Updated,
+    /// Extraction itself failed.
+    Error(String),
+}
+
+impl Outcome {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Outcome::Passed => "passed",
+            Outcome::Failed(_) => "failed",
+            Outcome::Missing => "missing",
+            Outcome::Updated => "updated",
+            Outcome::Error(_) => "error",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Report {
+    /// `(document path relative to the corpus, outcome)`, sorted by path.
+    pub documents: Vec<(PathBuf, Outcome)>,
+}
+
+impl Report {
+    pub fn count(&self, name: &str) -> usize {
+        self.documents
+            .iter()
+            .filter(|(_, outcome)| outcome.name() == name)
+            .count()
+    }
+
+    /// No failures, missing goldens or extraction errors.
+    pub fn ok(&self) -> bool {
+        self.documents
+            .iter()
+            .all(|(_, outcome)| matches!(outcome, Outcome::Passed | Outcome::Updated))
+    }
+
+    /// A human-readable report: one line per document that did not pass,
+    /// with diffs, then totals.
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        for (path, outcome) in &self.documents {
+            match outcome {
+                Outcome::Passed => {}
+                Outcome::Failed(diff) => {
+                    out.push_str(&format!("FAIL {}\n{diff}", path.display()));
+                }
+                Outcome::Missing => out.push_str(&format!("MISSING {}\n", path.display())),
+                Outcome::Updated => out.push_str(&format!("UPDATED {}\n", path.display())),
+                Outcome::Error(message) => {
+                    out.push_str(&format!("ERROR {}: {message}\n", path.display()));
+                }
+            }
+        }
+        out.push_str(&format!(
+            "{} documents: {} passed, {} failed, {} missing, {} updated, {} errors\n",
+            self.documents.len(),
+            self.count("passed"),
+            self.count("failed"),
+            self.count("missing"),
+            self.count("updated"),
+            self.count("error"),
+        ));
+        out
+    }
+
+    pub fn to_json(&self) -> Value {
+        Value::object([
+            ("ok", Value::from(self.ok())),
+            (
+                "documents",
+                Value::Array(
+                    self.documents
+                        .iter()
+                        .map(|(path, outcome)| {
+                            let mut pairs = vec![
+                                ("path", Value::from(path.display().to_string())),
+                                ("outcome", Value::from(outcome.name())),
+                            ];
+                            match outcome {
+                                Outcome::Failed(diff) => {
+                                    pairs.push(("diff", Value::from(diff.as_str())));
+                                }
+                                Outcome::Error(message) => {
+                                    pairs.push(("error", Value::from(message.as_str())));
+                                }
+                                _ => {}
+                            }
+                            Value::object(pairs)
+                        })
+                        .collect(),
+                ),
+            ),
+        ])
+    }
+}
+
+fn documents(dir: &Path, found: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            documents(&path, found)?;
+        } else if path.extension().and_then(|e| e.to_str()) != Some(GOLDEN_EXTENSION)
+            && Format::from_path(&path).is_some()
+        {
+            found.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn golden_path(document: &Path) -> PathBuf {
+    let mut name = document.as_os_str().to_owned();
+    name.push(".");
+    name.push(GOLDEN_EXTENSION);
+    PathBuf::from(name)
+}
+
+/// Check every document under `dir` against its golden, or with `update`
+/// write the goldens from the current output.
+pub fn verify(dir: &Path, update: bool) -> Result<Report> {
+    let mut found = Vec::new();
+    documents(dir, &mut found)?;
+    found.sort();
+    let mut report = Report::default();
+    for path in found {
+        let relative = path.strip_prefix(dir).unwrap_or(&path).to_path_buf();
+        let golden = golden_path(&path);
+        let outcome = match extract_text(&path) {
+            Err(e) => Outcome::Error(e.to_string()),
+            Ok(actual) if update => match fs::read_to_string(&golden) {
+                Ok(expected) if expected == actual => Outcome::Passed,
+                _ => {
+                    fs::write(&golden, &actual)?;
+                    Outcome::Updated
+                }
+            },
+            Ok(actual) => match fs::read_to_string(&golden) {
+                Ok(expected) if expected == actual => Outcome::Passed,
+                Ok(expected) => Outcome::Failed(diff(&expected, &actual)),
+                Err(_) => Outcome::Missing,
+            },
+        };
+        report.documents.push((relative, outcome));
+    }
+    Ok(report)
+}
+
+/// Line diff of `expected` against `actual`: `-` lines only in the golden,
+/// `+` lines only in the output, with the line numbers of each hunk.
+pub fn diff(expected: &str, actual: &str) -> String {
+    let old: Vec<&str> = expected.lines().collect();
+    let new: Vec<&str> = actual.lines().collect();
+    // Trim the common head and tail so the table below stays small.
+    let head = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let tail = old[head..]
+        .iter()
+        .rev()
+        .zip(new[head..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[head..old.len() - tail];
+    let new_mid = &new[head..new.len() - tail];
+
+    let mut out = format!(
+        "@@ -{},{} +{},{} @@\n",
+        head + 1,
+        old_mid.len(),
+        head + 1,
+        new_mid.len()
+    );
+    let mut lines = 0;
+    let mut push = |out: &mut String, sign: char, line: &str| {
+        if lines < DIFF_LINES {
+            out.push_str(&format!("{sign} {line}\n"));
+        }
+        lines += 1;
+    };
+    // Longest common subsequence; very long differences fall back to
+    // listing both sides in full.
+    if old_mid.len() * new_mid.len() > 4_000_000 {
+        for line in old_mid {
+            push(&mut out, '-', line);
+        }
+        for line in new_mid {
+            push(&mut out, '+', line);
+        }
+    } else {
+        let width = new_mid.len() + 1;
+        let mut table = vec![0u32; (old_mid.len() + 1) * width];
+        for i in (0..old_mid.len()).rev() {
+            for j in (0..new_mid.len()).rev() {
+                table[i * width + j] = if old_mid[i] == new_mid[j] {
+                    table[(i + 1) * width + j + 1] + 1
+                } else {
+                    table[(i + 1) * width + j].max(table[i * width + j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < old_mid.len() || j < new_mid.len() {
+            if i < old_mid.len() && j < new_mid.len() && old_mid[i] == new_mid[j] {
+                push(&mut out, ' ', old_mid[i]);
+                i += 1;
+                j += 1;
+            } else if i < old_mid.len()
+                && (j == new_mid.len() || table[(i + 1) * width + j] >= table[i * width + j + 1])
+            {
+                push(&mut out, '-', old_mid[i]);
+                i += 1;
+            } else {
+                push(&mut out, '+', new_mid[j]);
+                j += 1;
+            }
+        }
+    }
+    if lines > DIFF_LINES {
+        out.push_str(&format!("... {} more lines\n", lines - DIFF_LINES));
+    }
+    out
+}
@@ -0,0 +1,171 @@
+//! Exact and near-duplicate detection across documents.
+//!
+//! Exact copies share a content hash. Near duplicates are found with
+//! MinHash signatures over three-word shingles, which estimate the Jaccard
+//! similarity of two texts' shingle sets.
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use crate::extract::extract_text;
+use crate::hash::{fnv1a64, mix64};
+use crate::json::Value;
+use crate::words::words;
+
+const PERMUTATIONS: usize = 128;
+const SHINGLE_WORDS: usize = 3;
+
+/// MinHash signature of a text.
+pub fn signature(text: &str) -> Vec<u64> {
+    let words = words(text);
+    let mut shingles: HashSet<u64> = words
+        .windows(SHINGLE_WORDS)
+        .map(|window| fnv1a64(window.join(" ").as_bytes()))
+        .collect();
+    if shingles.is_empty() && !words.is_empty() {
+        shingles.insert(fnv1a64(words.join(" ").as_bytes()));
+    }
+    (0..PERMUTATIONS as u64)
+        .map(|seed| {
+            shingles
+                .iter()
+                .map(|&shingle| mix64(shingle ^ mix64(seed)))
+                .min()
+                .unwrap_or(u64::MAX)
+        })
+        .collect()
+}
+
+/// Estimated Jaccard similarity of two signatures.
+pub fn similarity(a: &[u64], b: &[u64]) -> f64 {
+    if a.is_empty() || a.len() != b.len() {
+        return 0.0;
+    }
+    let same = a.iter().zip(b).filter(|(x, y)| x == y).count();
+    same as f64 / a.len() as f64
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cluster {
+    /// Members with their similarity to the first member.
+    pub documents: Vec<(PathBuf, f64)>,
+    /// Every member has byte-identical content.
+    pub exact: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Duplicates {
+    pub clusters: Vec<Cluster>,
+    /// Files that could not be read or have no extractor.
+    pub skipped: Vec<PathBuf>,
+}
+
+impl Duplicates {
+    pub fn to_json(&self) -> Value {
+        let paths = |items: &[PathBuf]| {
+            Value::Array(
+                items
+                    .iter()
+                    .map(|p| Value::from(p.display().to_string()))
+                    .collect(),
+            )
+        };
+        Value::object([
+            (
+                "clusters",
+                Value::Array(
+                    self.clusters
+                        .iter()
+                        .map(|cluster| {
+                            Value::object([
+                                (
+                                    "documents",
+                                    Value::Array(
+                                        cluster
+                                            .documents
+                                            .iter()
+                                            .map(|(path, score)| {
+                                                Value::object([
+                                                    (
+                                                        "path",
+                                                        Value::from(path.display().to_string()),
+                                                    ),
+                                                    (
+                                                        "similarity",
+                                                        Value::from(
+                                                            (score * 100.0).round() / 100.0,
+                                                        ),
+                                                    ),
+                                                ])
+                                            })
+                                            .collect(),
+                                    ),
+                                ),
+                                ("exact", Value::from(cluster.exact)),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            ("skipped", paths(&self.skipped)),
+        ])
+    }
+}
+
+/// Group documents whose estimated similarity is at least `threshold`
+/// (0.0-1.0). Only groups with two or more members are returned.
+pub fn find_duplicates(paths: &[PathBuf], threshold: f64) -> Duplicates {
+    let mut result = Duplicates::default();
+    let mut docs: Vec<(PathBuf, u64, Vec<u64>)> = Vec::new();
+    for path in paths {
+        match (std::fs::read(path), extract_text(path)) {
+            (Ok(bytes), Ok(text)) => docs.push((path.clone(), fnv1a64(&bytes), signature(&text))),
+            _ => result.skipped.push(path.clone()),
+        }
+    }
+
+    // Union-find over every pair; fine for the few thousand files in a folder.
+    let mut parent: Vec<usize> = (0..docs.len()).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    for i in 0..docs.len() {
+        for j in i + 1..docs.len() {
+            let matched = docs[i].1 == docs[j].1 || similarity(&docs[i].2, &docs[j].2) >= threshold;
+            if matched {
+                let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+                parent[b.max(a)] = a.min(b);
+            }
+        }
+    }
+
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for i in 0..docs.len() {
+        let r = root(&mut parent, i);
+        match groups.iter_mut().find(|g| g[0] == r) {
+            Some(group) => group.push(i),
+            None => groups.push(vec![i]),
+        }
+    }
+    for group in groups.into_iter().filter(|g| g.len() > 1) {
+        let first = &docs[group[0]];
+        let exact = group.iter().all(|&i| docs[i].1 == first.1);
+        let documents = group
+            .iter()
+            .map(|&i| {
+                let score = if docs[i].1 == first.1 {
+                    1.0
+                } else {
+                    similarity(&first.2, &docs[i].2)
+                };
+                (docs[i].0.clone(), score)
+            })
+            .collect();
+        result.clusters.push(Cluster { documents, exact });
+    }
+    result
+}
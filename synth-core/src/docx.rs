@@ -0,0 +1,920 @@
+//! Editing an existing Word (.docx) document: appending paragraphs,
+//! inserting text at a bookmark and replacing the section under a
+//! heading, so generated clauses land in the user's own file, or, as
+//! tracked changes, suggesting replacements Word can accept or reject;
+//! highlighting the runs a span of the extracted text came from; and
+//! concatenating several documents into one.
+//!
+//! Edits are spliced into `word/document.xml`, leaving everything around
+//! them as it was; the other parts are copied unchanged. New blocks are
+//! markdown, turned into paragraphs as [`draft`](crate::draft) does.
+
+use std::ops::Range;
+use std::path::Path;
+
+use crate::docx_text::{self, Reading};
+use crate::draft::Draft;
+use crate::error::{Error, Result};
+use crate::json::Value;
+use crate::opc;
+use crate::xml::{self, escape, Element};
+use crate::zip::{Archive, Writer};
+
+const DOCUMENT: &str = "word/document.xml";
+const STYLES: &str = "word/styles.xml";
+
+/// One change to a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Edit {
+    /// Markdown paragraphs added at the end of the body.
+    Append(String),
+    /// Text placed where the bookmark starts: a run inside a paragraph,
+    /// or markdown paragraphs between them.
+    InsertAtBookmark { bookmark: String, text: String },
+    /// Markdown paragraphs in place of everything under a heading, up to
+    /// the next heading of the same or a higher level.
+    ReplaceSection { heading: String, text: String },
+    /// The first `find` replaced by `replace` as a tracked change: a
+    /// deletion and an insertion by `author`. An empty `replace` only
+    /// deletes.
+    Suggest {
+        find: String,
+        replace: String,
+        author: String,
+        /// When the change was made, ISO 8601; left out when unset.
+        date: Option<String>,
+    },
+    /// The runs characters `start..end` of the extracted text came from
+    /// marked with a highlight color, e.g. "yellow".
+    Highlight {
+        start: usize,
+        end: usize,
+        color: String,
+    },
+}
+
+impl Edit {
+    /// Read `{"op": "append", "text": "..."}`, `{"op":
+    /// "insert_at_bookmark", "bookmark": "...", "text": "..."}` or `{"op":
+    /// "replace_section", "heading": "...", "text": "..."}` or `{"op":
+    /// "suggest", "find": "...", "replace": "...", "author": "Synth",
+    /// "date": "2026-01-31T09:00:00Z"}` (author and date optional) or
+    /// `{"op": "highlight", "start": 120, "end": 180, "color": "yellow"}`
+    /// (color optional).
+    pub fn from_json(value: &Value) -> Result<Edit> {
+        let text = |key| {
+            value
+                .get(key)
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .ok_or_else(|| Error::Parse(format!("edit needs a {key}")))
+        };
+        match value.get("op").and_then(Value::as_str) {
+            Some("append") => Ok(Edit::Append(text("text")?)),
+            Some("insert_at_bookmark") => Ok(Edit::InsertAtBookmark {
+                bookmark: text("bookmark")?,
+                text: text("text")?,
+            }),
+            Some("replace_section") => Ok(Edit::ReplaceSection {
+                heading: text("heading")?,
+                text: text("text")?,
+            }),
+            Some("suggest") => Ok(Edit::Suggest {
+                find: text("find")?,
+                replace: text("replace")?,
+                author: text("author").unwrap_or_else(|_| "Synth".into()),
+                date: text("date").ok(),
+            }),
+            Some("highlight") => {
+                let offset = |key| {
+                    value
+                        .get(key)
+                        .and_then(Value::as_f64)
+                        .filter(|n| *n >= 0.0)
+                        .map(|n| n as usize)
+                        .ok_or_else(|| Error::Parse(format!("highlight needs a {key}")))
+                };
+                Ok(Edit::Highlight {
+                    start: offset("start")?,
+                    end: offset("end")?,
+                    color: text("color").unwrap_or_else(|_| "yellow".into()),
+                })
+            }
+            Some(other) => Err(Error::Parse(format!("unknown edit '{other}'"))),
+            None => Err(Error::Parse("edit needs an op".into())),
+        }
+    }
+}
+
+/// An open document and its main part as edited so far.
+pub struct Document {
+    archive: Archive,
+    xml: String,
+}
+
+impl Document {
+    pub fn open(path: &Path) -> Result<Document> {
+        Document::from_archive(Archive::open(path)?)
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Document> {
+        Document::from_archive(Archive::from_bytes(bytes)?)
+    }
+
+    fn from_archive(archive: Archive) -> Result<Document> {
+        let xml = archive.read_string(DOCUMENT)?;
+        Ok(Document { archive, xml })
+    }
+
+    /// `word/document.xml` with the edits made so far.
+    pub fn document_xml(&self) -> &str {
+        &self.xml
+    }
+
+    fn root(&self) -> Result<Element> {
+        xml::parse(&self.xml, true)
+    }
+
+    pub fn apply(&mut self, edit: &Edit) -> Result<()> {
+        match edit {
+            Edit::Append(text) => self.append(text),
+            Edit::InsertAtBookmark { bookmark, text } => self.insert_at_bookmark(bookmark, text),
+            Edit::ReplaceSection { heading, text } => self.replace_section(heading, text),
+            Edit::Suggest {
+                find,
+                replace,
+                author,
+                date,
+            } => self.suggest(find, replace, author, date.as_deref()),
+            Edit::Highlight { start, end, color } => self.highlight(*start..*end, color),
+        }
+    }
+
+    /// Add markdown paragraphs after the last block, before the final
+    /// section properties.
+    pub fn append(&mut self, markdown: &str) -> Result<()> {
+        let wordml = Draft::from_markdown(markdown).to_wordml();
+        let root = self.root()?;
+        let body = body(&root)?;
+        if body.inner.is_empty() && self.xml[..body.span.end].ends_with("/>") {
+            self.xml
+                .replace_range(body.span.clone(), &format!("<w:body>{wordml}</w:body>"));
+            return Ok(());
+        }
+        let at = match body.elements().last() {
+            Some(last) if last.name == "w:sectPr" => last.span.start,
+            _ => body.inner.end,
+        };
+        self.xml.insert_str(at, &wordml);
+        Ok(())
+    }
+
+    /// Put `text` where the bookmark named `bookmark` starts. Inside a
+    /// paragraph it becomes a run formatted like the one after it, line
+    /// breaks kept; between blocks it is markdown paragraphs.
+    pub fn insert_at_bookmark(&mut self, bookmark: &str, text: &str) -> Result<()> {
+        let root = self.root()?;
+        let (parent, index, inline) = find_bookmark(body(&root)?, bookmark, false)
+            .ok_or_else(|| Error::InvalidArgument(format!("no bookmark named '{bookmark}'")))?;
+        let siblings: Vec<&Element> = parent.elements().collect();
+        let wordml = if inline {
+            let props = siblings[index + 1..]
+                .iter()
+                .find(|el| el.name == "w:r")
+                .and_then(|run| run.child("w:rPr"))
+                .map_or("", |props| &self.xml[props.span.clone()]);
+            text_run(text, props)
+        } else {
+            Draft::from_markdown(text).to_wordml()
+        };
+        self.xml.insert_str(siblings[index].span.end, &wordml);
+        Ok(())
+    }
+
+    /// Replace what follows the heading whose text is `heading` (ignoring
+    /// case and surrounding space) with markdown paragraphs. The heading
+    /// itself stays.
+    pub fn replace_section(&mut self, heading: &str, markdown: &str) -> Result<()> {
+        let styles = heading_styles(&self.archive)?;
+        let root = self.root()?;
+        let body = body(&root)?;
+        let blocks: Vec<&Element> = body.elements().collect();
+        let (start, level) = blocks
+            .iter()
+            .enumerate()
+            .find_map(|(i, block)| {
+                let level = heading_level(block, &styles)?;
+                paragraph_text(block)
+                    .trim()
+                    .eq_ignore_ascii_case(heading.trim())
+                    .then_some((i, level))
+            })
+            .ok_or_else(|| Error::InvalidArgument(format!("no heading '{heading}'")))?;
+        let end = blocks[start + 1..]
+            .iter()
+            .find(|block| {
+                block.name == "w:sectPr"
+                    || heading_level(block, &styles).is_some_and(|next| next <= level)
+            })
+            .map_or(body.inner.end, |block| block.span.start);
+        let wordml = Draft::from_markdown(markdown).to_wordml();
+        self.xml.replace_range(blocks[start].span.end..end, &wordml);
+        Ok(())
+    }
+
+    /// Replace the first `find` with `replace` as a tracked change. The
+    /// text has to lie within plain runs of one paragraph: runs of text
+    /// with properties, not broken by fields, bookmarks or other markup.
+    /// The deleted runs keep their formatting and the insertion takes
+    /// that of the first.
+    pub fn suggest(
+        &mut self,
+        find: &str,
+        replace: &str,
+        author: &str,
+        date: Option<&str>,
+    ) -> Result<()> {
+        if find.is_empty() {
+            return Err(Error::InvalidArgument("nothing to find".into()));
+        }
+        let root = self.root()?;
+        let mut paragraphs = Vec::new();
+        body(&root)?.find_all("w:p", &mut paragraphs);
+        let id = next_revision_id(&root);
+        for paragraph in paragraphs {
+            for runs in plain_runs(paragraph) {
+                let text: String = runs.iter().map(|run| run.text.as_str()).collect();
+                let Some(start) = text.find(find) else {
+                    continue;
+                };
+                let revision = Revision { author, date, id };
+                let (span, wordml) =
+                    revision.replace(&self.xml, &runs, start..start + find.len(), replace);
+                self.xml.replace_range(span, &wordml);
+                return Ok(());
+            }
+        }
+        Err(Error::InvalidArgument(format!(
+            "'{find}' is not in the document's text"
+        )))
+    }
+
+    /// Highlight the runs characters `range` of the document's extracted
+    /// text (as [`docx_text`] reads it by default) came from in
+    /// `color`, one of Word's highlight colors. Runs of plain text are
+    /// split where the range starts and ends; others are highlighted
+    /// whole.
+    pub fn highlight(&mut self, range: Range<usize>, color: &str) -> Result<()> {
+        if !HIGHLIGHTS.contains(&color) {
+            return Err(Error::InvalidArgument(format!(
+                "unknown highlight color '{color}'"
+            )));
+        }
+        let reading = Reading::read(
+            &self.archive,
+            &self.xml,
+            &docx_text::Options::default(),
+            None,
+        )?;
+        let pieces = reading.pieces(range.clone());
+        if pieces.is_empty() {
+            return Err(Error::InvalidArgument(format!(
+                "no text at {}..{}",
+                range.start, range.end
+            )));
+        }
+        // Back to front, so the spans before each edit stay valid.
+        for (piece, part) in pieces.into_iter().rev() {
+            let span = piece.span.clone();
+            let wordml = highlight_run(&self.xml[span.clone()], part, color)?;
+            self.xml.replace_range(span, &wordml);
+        }
+        Ok(())
+    }
+
+    fn writer(&self) -> Result<Writer> {
+        let mut writer = Writer::default();
+        for name in self.archive.names() {
+            if name == DOCUMENT {
+                writer.add(name, self.xml.as_bytes())?;
+            } else {
+                writer.copy(&self.archive, name)?;
+            }
+        }
+        Ok(writer)
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        Ok(self.writer()?.finish())
+    }
+
+    /// Write the edited document to `path`, which may be the one it was
+    /// opened from.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        self.writer()?.save(path)
+    }
+}
+
+/// Open `path`, make `edits` in order and save the result to `out`. No
+/// file is written if any edit fails.
+pub fn edit(path: &Path, edits: &[Edit], out: &Path) -> Result<()> {
+    let mut document = Document::open(path)?;
+    for edit in edits {
+        document.apply(edit)?;
+    }
+    document.save(out)
+}
+
+const RELATIONSHIPS: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+
+/// Concatenate the documents at `paths` into `out`, each starting a new
+/// section with its own page layout. The first document is the base: its
+/// styles, headers, footers and settings are kept, and the others add
+/// the styles it lacks (by style id; the base's definition wins), their
+/// images and their hyperlinks. Headers and footers of the later
+/// documents are not carried over, so their sections continue the ones
+/// before. Documents referencing other parts from their body (charts,
+/// embedded objects) are unsupported.
+pub fn merge(paths: &[impl AsRef<Path>], out: &Path) -> Result<()> {
+    let (first, rest) = paths
+        .split_first()
+        .ok_or_else(|| Error::InvalidArgument("no documents to merge".into()))?;
+    let base = Document::open(first.as_ref())?;
+    let mut merge = Merge::new(&base)?;
+    for (i, path) in rest.iter().enumerate() {
+        merge.add(&Document::open(path.as_ref())?, i + 1)?;
+    }
+    merge.finish(&base)?.save(out)
+}
+
+/// A merge in progress.
+struct Merge {
+    /// The body so far, without the last section's properties.
+    content: String,
+    /// The properties of the section the last document started.
+    section: String,
+    style_ids: Vec<String>,
+    /// Style definitions to add to the base's.
+    styles: String,
+    /// Relationships to add to the base document's.
+    relationships: String,
+    /// Default content types to add, by extension.
+    content_types: Vec<(String, String)>,
+    /// Parts copied from the later documents.
+    parts: Vec<(String, Vec<u8>)>,
+}
+
+impl Merge {
+    fn new(base: &Document) -> Result<Merge> {
+        let root = base.root()?;
+        let (content, section) = split_body(&base.xml, body(&root)?);
+        let style_ids = if base.archive.contains(STYLES) {
+            xml::parse(&base.archive.read_string(STYLES)?, false)?
+                .elements()
+                .filter_map(|style| style.attr("w:styleId").map(str::to_string))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        Ok(Merge {
+            content: content.to_string(),
+            section: section.to_string(),
+            style_ids,
+            styles: String::new(),
+            relationships: String::new(),
+            content_types: Vec::new(),
+            parts: Vec::new(),
+        })
+    }
+
+    /// Append `document`, the `n`th merged, after a section break.
+    fn add(&mut self, document: &Document, n: usize) -> Result<()> {
+        let root = document.root()?;
+        let body = body(&root)?;
+        let (content, section) = split_body(&document.xml, body);
+        let mut content = without_headers(content)?;
+        let relationships = opc::relationships(&document.archive, DOCUMENT)?;
+        let mut used = Vec::new();
+        for block in body.elements() {
+            relationship_refs(block, &mut used);
+        }
+        // Merged parts here; header and footer references are dropped.
+        used.retain(|(_, id)| content.contains(&format!("=\"{id}\"")));
+        for (attr, id) in used {
+            let rel = relationships
+                .iter()
+                .find(|rel| rel.id == id)
+                .ok_or_else(|| Error::Parse(format!("missing relationship {id}")))?;
+            let new_id = format!("rIdMerged{n}_{id}");
+            let target = if rel.external {
+                format!("{}\" TargetMode=\"External", escape(&rel.target))
+            } else if rel.kind == "image" {
+                let file = rel.target.rsplit('/').next().unwrap_or(&rel.target);
+                let name = format!("word/media/merged{n}-{file}");
+                self.copy_part(document, &rel.target, &name)?;
+                name["word/".len()..].to_string()
+            } else {
+                return Err(Error::Unsupported(format!(
+                    "merging documents with {} parts",
+                    rel.kind
+                )));
+            };
+            let entry = format!(
+                "<Relationship Id=\"{new_id}\" Type=\"{RELATIONSHIPS}/{}\" Target=\"{target}\"/>",
+                rel.kind
+            );
+            if !self.relationships.contains(&entry) {
+                self.relationships.push_str(&entry);
+            }
+            content = content.replace(&format!("{attr}=\"{id}\""), &format!("{attr}=\"{new_id}\""));
+        }
+        if document.archive.contains(STYLES) {
+            let text = document.archive.read_string(STYLES)?;
+            for style in xml::parse(&text, false)?.elements() {
+                let Some(id) = style.attr("w:styleId") else {
+                    continue;
+                };
+                if style.name == "w:style" && !self.style_ids.iter().any(|known| known == id) {
+                    self.style_ids.push(id.to_string());
+                    self.styles.push_str(&text[style.span.clone()]);
+                }
+            }
+        }
+        // The section so far ends here, with its own properties.
+        self.content
+            .push_str(&format!("<w:p><w:pPr>{}</w:pPr></w:p>", self.section));
+        self.content.push_str(&content);
+        self.section = without_headers(section)?;
+        Ok(())
+    }
+
+    fn copy_part(&mut self, document: &Document, part: &str, name: &str) -> Result<()> {
+        if self.parts.iter().any(|(copied, _)| copied == name) {
+            return Ok(());
+        }
+        self.parts
+            .push((name.to_string(), document.archive.read(part)?));
+        let Some((_, ext)) = name.rsplit_once('.') else {
+            return Ok(());
+        };
+        let types = document.archive.read_string("[Content_Types].xml")?;
+        let default = xml::parse(&types, false)?
+            .elements()
+            .find(|el| {
+                el.name == "Default"
+                    && el
+                        .attr("Extension")
+                        .is_some_and(|e| e.eq_ignore_ascii_case(ext))
+            })
+            .and_then(|el| el.attr("ContentType").map(str::to_string));
+        if let Some(content_type) = default {
+            self.content_types
+                .push((ext.to_ascii_lowercase(), content_type));
+        }
+        Ok(())
+    }
+
+    /// The base with everything merged into it.
+    fn finish(self, base: &Document) -> Result<Writer> {
+        let root = base.root()?;
+        let body = body(&root)?;
+        let mut document = base.xml.clone();
+        document.replace_range(body.inner.clone(), &(self.content + &self.section));
+
+        let mut changed = vec![(DOCUMENT.to_string(), document)];
+        let rels = opc::rels_name(DOCUMENT);
+        let text = if base.archive.contains(&rels) {
+            base.archive.read_string(&rels)?
+        } else {
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+             <Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\"/>"
+                .to_string()
+        };
+        changed.push((rels, insert_at_end(&text, &self.relationships)?));
+        if !self.styles.is_empty() && base.archive.contains(STYLES) {
+            let text = base.archive.read_string(STYLES)?;
+            changed.push((STYLES.to_string(), insert_at_end(&text, &self.styles)?));
+        }
+        let types = "[Content_Types].xml";
+        let text = base.archive.read_string(types)?;
+        let known: Vec<String> = xml::parse(&text, false)?
+            .elements()
+            .filter_map(|el| el.attr("Extension").map(str::to_ascii_lowercase))
+            .collect();
+        let mut defaults = String::new();
+        for (ext, content_type) in &self.content_types {
+            let entry = format!(
+                "<Default Extension=\"{ext}\" ContentType=\"{}\"/>",
+                escape(content_type)
+            );
+            if !known.contains(ext) && !defaults.contains(&entry) {
+                defaults.push_str(&entry);
+            }
+        }
+        changed.push((types.to_string(), insert_at_end(&text, &defaults)?));
+
+        let mut writer = Writer::default();
+        for name in base.archive.names() {
+            match changed.iter().position(|(part, _)| part == name) {
+                Some(i) => {
+                    let (part, text) = changed.remove(i);
+                    writer.add(&part, text.as_bytes())?;
+                }
+                None => writer.copy(&base.archive, name)?,
+            }
+        }
+        for (part, text) in changed {
+            writer.add(&part, text.as_bytes())?;
+        }
+        for (part, data) in &self.parts {
+            writer.add(part, data)?;
+        }
+        Ok(writer)
+    }
+}
+
+/// A body's blocks and its closing section properties, as written.
+fn split_body<'a>(xml: &'a str, body: &Element) -> (&'a str, &'a str) {
+    match body.elements().last() {
+        Some(last) if last.name == "w:sectPr" => (
+            &xml[body.inner.start..last.span.start],
+            &xml[last.span.clone()],
+        ),
+        _ => (&xml[body.inner.clone()], ""),
+    }
+}
+
+/// Body content without header and footer references in its section
+/// properties, as those parts are not merged.
+fn without_headers(content: &str) -> Result<String> {
+    // Parse the blocks under a stand-in root for their spans.
+    let open = "<w:body>";
+    let root = xml::parse(&format!("{open}{content}</w:body>"), true)?;
+    let mut references = Vec::new();
+    root.find_all("w:headerReference", &mut references);
+    root.find_all("w:footerReference", &mut references);
+    references.sort_by_key(|el| std::cmp::Reverse(el.span.start));
+    let mut out = content.to_string();
+    for el in references {
+        out.replace_range(el.span.start - open.len()..el.span.end - open.len(), "");
+    }
+    Ok(out)
+}
+
+/// Every `r:` attribute naming a relationship, with its value.
+fn relationship_refs(el: &Element, out: &mut Vec<(String, String)>) {
+    for (key, value) in &el.attrs {
+        let pair = (key.clone(), value.clone());
+        if key.starts_with("r:") && !out.contains(&pair) {
+            out.push(pair);
+        }
+    }
+    for child in el.elements() {
+        relationship_refs(child, out);
+    }
+}
+
+/// `text` with `addition` before the root element's end tag.
+fn insert_at_end(text: &str, addition: &str) -> Result<String> {
+    let root = xml::parse(text, true)?;
+    let mut out = text.to_string();
+    if root.inner.is_empty() && text[..root.span.end].ends_with("/>") {
+        let open = &text[root.span.start..root.span.end - 2];
+        out.replace_range(
+            root.span.clone(),
+            &format!("{open}>{addition}</{}>", root.name),
+        );
+    } else {
+        out.insert_str(root.inner.end, addition);
+    }
+    Ok(out)
+}
+
+pub(crate) fn body(root: &Element) -> Result<&Element> {
+    root.child("w:body")
+        .ok_or_else(|| Error::Parse("document has no body".into()))
+}
+
+/// The element holding the start of bookmark `name`, the start's index
+/// among its elements, and whether it is inside a paragraph.
+fn find_bookmark<'a>(
+    parent: &'a Element,
+    name: &str,
+    inline: bool,
+) -> Option<(&'a Element, usize, bool)> {
+    for (i, el) in parent.elements().enumerate() {
+        if el.name == "w:bookmarkStart" && el.attr("w:name") == Some(name) {
+            return Some((parent, i, inline));
+        }
+        if let Some(found) = find_bookmark(el, name, inline || el.name == "w:p") {
+            return Some(found);
+        }
+    }
+    None
+}
+
+/// A run holding only properties and text.
+struct PlainRun {
+    span: Range<usize>,
+    /// The `w:rPr` element, or an empty range.
+    props: Range<usize>,
+    text: String,
+}
+
+/// The sequences of plain runs in a paragraph, split wherever anything
+/// else comes between them.
+fn plain_runs(paragraph: &Element) -> Vec<Vec<PlainRun>> {
+    let mut groups = vec![Vec::new()];
+    for el in paragraph.elements() {
+        let plain = el.name == "w:r"
+            && el
+                .elements()
+                .all(|child| child.name == "w:rPr" || child.name == "w:t");
+        if plain {
+            groups.last_mut().unwrap().push(PlainRun {
+                span: el.span.clone(),
+                props: el.child("w:rPr").map_or(0..0, |props| props.span.clone()),
+                text: el
+                    .elements()
+                    .filter(|c| c.name == "w:t")
+                    .map(Element::text)
+                    .collect(),
+            });
+        } else if el.name != "w:pPr" {
+            groups.push(Vec::new());
+        }
+    }
+    groups.retain(|runs| !runs.is_empty());
+    groups
+}
+
+/// Revision ids have to be unique, so start past every `w:id` in use.
+fn next_revision_id(root: &Element) -> u64 {
+    let own = root
+        .attr("w:id")
+        .and_then(|id| id.parse::<u64>().ok())
+        .map_or(0, |id| id + 1);
+    root.elements().map(next_revision_id).fold(own, u64::max)
+}
+
+/// Who made a tracked change and when.
+struct Revision<'a> {
+    author: &'a str,
+    date: Option<&'a str>,
+    id: u64,
+}
+
+impl Revision<'_> {
+    fn attrs(&self, id: u64) -> String {
+        let mut attrs = format!(" w:id=\"{id}\" w:author=\"{}\"", escape(self.author));
+        if let Some(date) = self.date {
+            attrs.push_str(&format!(" w:date=\"{}\"", escape(date)));
+        }
+        attrs
+    }
+
+    /// The span of `runs` to replace and the WordprocessingML for it:
+    /// text before and after `matched` kept as it was, the matched text
+    /// deleted and `replace` inserted.
+    fn replace(
+        &self,
+        xml: &str,
+        runs: &[PlainRun],
+        matched: Range<usize>,
+        replace: &str,
+    ) -> (Range<usize>, String) {
+        let mut starts = Vec::with_capacity(runs.len());
+        let mut at = 0;
+        for run in runs {
+            starts.push(at);
+            at += run.text.len();
+        }
+        let first = (0..runs.len())
+            .find(|&i| starts[i] + runs[i].text.len() > matched.start)
+            .unwrap_or(0);
+        let last = (first..runs.len())
+            .rev()
+            .find(|&i| starts[i] < matched.end)
+            .unwrap_or(first);
+        let props = |i: usize| &xml[runs[i].props.clone()];
+        let mut out = String::new();
+        let before = &runs[first].text[..matched.start - starts[first]];
+        if !before.is_empty() {
+            out.push_str(&text_run(before, props(first)));
+        }
+        out.push_str(&format!("<w:del{}>", self.attrs(self.id)));
+        for i in first..=last {
+            let from = matched.start.max(starts[i]) - starts[i];
+            let to = matched.end.min(starts[i] + runs[i].text.len()) - starts[i];
+            out.push_str(&format!(
+                "<w:r>{}<w:delText xml:space=\"preserve\">{}</w:delText></w:r>",
+                props(i),
+                escape(&runs[i].text[from..to])
+            ));
+        }
+        out.push_str("</w:del>");
+        if !replace.is_empty() {
+            out.push_str(&format!(
+                "<w:ins{}>{}</w:ins>",
+                self.attrs(self.id + 1),
+                text_run(replace, props(first))
+            ));
+        }
+        let after = &runs[last].text[matched.end - starts[last]..];
+        if !after.is_empty() {
+            out.push_str(&text_run(after, props(last)));
+        }
+        (runs[first].span.start..runs[last].span.end, out)
+    }
+}
+
+/// Word's highlight colors.
+const HIGHLIGHTS: [&str; 16] = [
+    "yellow",
+    "green",
+    "cyan",
+    "magenta",
+    "blue",
+    "red",
+    "darkBlue",
+    "darkCyan",
+    "darkGreen",
+    "darkMagenta",
+    "darkRed",
+    "darkYellow",
+    "darkGray",
+    "lightGray",
+    "black",
+    "white",
+];
+
+/// Run properties that come after `w:highlight`, which Word wants in
+/// schema order.
+const AFTER_HIGHLIGHT: [&str; 14] = [
+    "w:u",
+    "w:effect",
+    "w:bdr",
+    "w:shd",
+    "w:fitText",
+    "w:vertAlign",
+    "w:rtl",
+    "w:cs",
+    "w:em",
+    "w:lang",
+    "w:eastAsianLayout",
+    "w:specVanish",
+    "w:oMath",
+    "w:rPrChange",
+];
+
+/// The run `run` (its WordprocessingML) with characters `part` of its
+/// text highlighted.
+fn highlight_run(run: &str, part: Range<usize>, color: &str) -> Result<String> {
+    let el = xml::parse(run, true)?;
+    let props = el.child("w:rPr");
+    let mut marked = String::from("<w:rPr>");
+    let mark = format!("<w:highlight w:val=\"{color}\"/>");
+    let mut placed = false;
+    for child in props.into_iter().flat_map(Element::elements) {
+        if child.name == "w:highlight" {
+            continue;
+        }
+        if !placed && AFTER_HIGHLIGHT.contains(&child.name.as_str()) {
+            marked.push_str(&mark);
+            placed = true;
+        }
+        marked.push_str(&run[child.span.clone()]);
+    }
+    if !placed {
+        marked.push_str(&mark);
+    }
+    marked.push_str("</w:rPr>");
+
+    let plain = el
+        .elements()
+        .all(|child| child.name == "w:rPr" || child.name == "w:t");
+    let text: String = el
+        .elements()
+        .filter(|c| c.name == "w:t")
+        .map(Element::text)
+        .collect();
+    let chars = text.chars().count();
+    if plain && part.len() < chars {
+        let props = props.map_or("", |props| &run[props.span.clone()]);
+        let split = |from: usize, to: usize| -> String {
+            text.chars().skip(from).take(to - from).collect()
+        };
+        let mut out = String::new();
+        for (range, props) in [
+            (0..part.start, props),
+            (part.clone(), marked.as_str()),
+            (part.end..chars, props),
+        ] {
+            if !range.is_empty() {
+                out.push_str(&text_run(&split(range.start, range.end), props));
+            }
+        }
+        return Ok(out);
+    }
+    Ok(match props {
+        Some(props) => format!(
+            "{}{marked}{}",
+            &run[..props.span.start],
+            &run[props.span.end..]
+        ),
+        None => format!(
+            "{}{marked}{}",
+            &run[..el.inner.start],
+            &run[el.inner.start..]
+        ),
+    })
+}
+
+/// A run of `text` with the run properties `props`, as written.
+fn text_run(text: &str, props: &str) -> String {
+    let lines: Vec<String> = text
+        .lines()
+        .map(|line| format!("<w:t xml:space=\"preserve\">{}</w:t>", escape(line)))
+        .collect();
+    format!("<w:r>{props}{}</w:r>", lines.join("<w:br/>"))
+}
+
+/// The text a paragraph shows: its `w:t` runs and tabs, without deleted
+/// text or field codes.
+pub(crate) fn paragraph_text(p: &Element) -> String {
+    let mut out = String::new();
+    push_text(p, &mut out);
+    out
+}
+
+fn push_text(el: &Element, out: &mut String) {
+    for child in el.elements() {
+        match child.name.as_str() {
+            "w:t" => out.push_str(&child.text()),
+            "w:tab" => out.push('\t'),
+            "w:br" | "w:cr" => out.push('\n'),
+            "w:del" | "w:instrText" | "w:delText" => {}
+            _ => push_text(child, out),
+        }
+    }
+}
+
+/// Paragraph style ids that are headings, with their level: 0 for the
+/// title, 1 for "heading 1" and so on.
+pub(crate) fn heading_styles(archive: &Archive) -> Result<Vec<(String, u8)>> {
+    if !archive.contains(STYLES) {
+        return Ok(Vec::new());
+    }
+    let root = xml::parse(&archive.read_string(STYLES)?, false)?;
+    Ok(root
+        .elements()
+        .filter(|el| el.name == "w:style" && el.attr("w:type") == Some("paragraph"))
+        .filter_map(|style| {
+            let id = style.attr("w:styleId")?;
+            let name = style
+                .child("w:name")
+                .and_then(|n| n.attr("w:val"))
+                .unwrap_or("")
+                .to_ascii_lowercase();
+            let level = match name.strip_prefix("heading ") {
+                _ if name == "title" => Some(0),
+                Some(n) => n.parse().ok(),
+                None => outline_level(style),
+            }?;
+            Some((id.to_string(), level))
+        })
+        .collect())
+}
+
+/// A `w:outlineLvl` in an element's paragraph properties, counted from 1.
+fn outline_level(el: &Element) -> Option<u8> {
+    let level: u8 = el
+        .child("w:pPr")?
+        .child("w:outlineLvl")?
+        .attr("w:val")?
+        .parse()
+        .ok()?;
+    // Level 9 is body text.
+    (level < 9).then_some(level + 1)
+}
+
+/// The heading level of a block, if it is a heading paragraph.
+pub(crate) fn heading_level(block: &Element, styles: &[(String, u8)]) -> Option<u8> {
+    if block.name != "w:p" {
+        return None;
+    }
+    if let Some(level) = outline_level(block) {
+        return Some(level);
+    }
+    let id = block.child("w:pPr")?.child("w:pStyle")?.attr("w:val")?;
+    match styles.iter().find(|(style, _)| style == id) {
+        Some((_, level)) => Some(*level),
+        // Without a styles part, go by the built-in ids.
+        None if styles.is_empty() => match id.strip_prefix("Heading") {
+            _ if id == "Title" => Some(0),
+            Some(n) => n.parse().ok(),
+            None => None,
+        },
+        None => None,
+    }
+}
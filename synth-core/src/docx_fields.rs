@@ -0,0 +1,402 @@
+//! Word field codes (`DATE \@ "d MMMM yyyy"`, `SEQ Figure`, ...) and the
+//! value of the simple ones worked out here instead of the result Word
+//! last saved, which is stale for dates and missing when a document was
+//! generated without Word: `DATE`, `SEQ`, `AUTONUM` and `FILENAME`.
+
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::{Error, Result};
+use crate::json::Value;
+
+/// What a field's text is in the extracted text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldMode {
+    /// The result saved in the document, for every field.
+    Cached,
+    /// The value of the fields in [this module](self) that can be worked
+    /// out; the saved result for the others.
+    Evaluate,
+}
+
+impl FieldMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "cached" => Some(FieldMode::Cached),
+            "evaluate" => Some(FieldMode::Evaluate),
+            _ => None,
+        }
+    }
+}
+
+/// A day of the (proleptic Gregorian) calendar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+const WEEKDAYS: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+impl Date {
+    /// Today in UTC.
+    pub fn today() -> Date {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        Date::from_days((secs / 86_400) as i64)
+    }
+
+    /// Read `2026-10-14`.
+    pub fn parse(text: &str) -> Result<Date> {
+        let bad = || Error::Parse(format!("'{text}' is not a YYYY-MM-DD date"));
+        let mut parts = text.splitn(3, '-');
+        let mut part = || {
+            parts
+                .next()
+                .and_then(|p| p.parse::<i64>().ok())
+                .ok_or_else(bad)
+        };
+        let (year, month, day) = (part()?, part()?, part()?);
+        let date = Date {
+            year: year as i32,
+            month: month as u32,
+            day: day as u32,
+        };
+        if !(1..=12).contains(&date.month) || date.day == 0 || date.day > date.days_in_month() {
+            return Err(bad());
+        }
+        Ok(date)
+    }
+
+    fn days_in_month(self) -> u32 {
+        match self.month {
+            2 if self.year % 4 == 0 && (self.year % 100 != 0 || self.year % 400 == 0) => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        }
+    }
+
+    /// The date `days` after 1970-01-01.
+    fn from_days(days: i64) -> Date {
+        // Howard Hinnant's civil_from_days.
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = (yoe + era * 400 + i64::from(month <= 2)) as i32;
+        Date { year, month, day }
+    }
+
+    fn days(self) -> i64 {
+        let year = i64::from(self.year) - i64::from(self.month <= 2);
+        let era = year.div_euclid(400);
+        let yoe = year.rem_euclid(400);
+        let m = i64::from(self.month);
+        let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + i64::from(self.day) - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        era * 146_097 + doe - 719_468
+    }
+
+    /// Monday is 0.
+    fn weekday(self) -> usize {
+        // 1970-01-01 was a Thursday.
+        (self.days() + 3).rem_euclid(7) as usize
+    }
+
+    /// The date in a Word date picture: `d`, `dd`, `ddd`, `dddd`, `M` to
+    /// `MMMM`, `yy` and `yyyy`, with text in single quotes kept as it is.
+    /// Time of day is not known, so the other letters are left as they
+    /// are.
+    pub fn format(self, picture: &str) -> String {
+        let chars: Vec<char> = picture.chars().collect();
+        let mut out = String::new();
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            if c == '\'' {
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|&q| q == '\'')
+                    .map_or(chars.len(), |n| i + 1 + n);
+                out.extend(&chars[i + 1..end]);
+                i = end + 1;
+                continue;
+            }
+            let n = chars[i..].iter().take_while(|&&same| same == c).count();
+            i += n;
+            let month = MONTHS[self.month as usize - 1];
+            let weekday = WEEKDAYS[self.weekday()];
+            match (c, n) {
+                ('d', 1) => out.push_str(&self.day.to_string()),
+                ('d', 2) => out.push_str(&format!("{:02}", self.day)),
+                ('d', 3) => out.push_str(&weekday[..3]),
+                ('d', _) => out.push_str(weekday),
+                ('M', 1) => out.push_str(&self.month.to_string()),
+                ('M', 2) => out.push_str(&format!("{:02}", self.month)),
+                ('M', 3) => out.push_str(&month[..3]),
+                ('M', _) => out.push_str(month),
+                ('y', 1 | 2) => out.push_str(&format!("{:02}", self.year.rem_euclid(100))),
+                ('y', _) => out.push_str(&self.year.to_string()),
+                _ => out.extend(std::iter::repeat_n(c, n)),
+            }
+        }
+        out
+    }
+}
+
+/// A field in the document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    /// The field's type, upper-cased: "DATE", "REF", "MERGEFIELD" ...
+    pub code: String,
+    /// The whole field code, e.g. `SEQ Figure \* ARABIC`.
+    pub instruction: String,
+    /// The paragraph the field is in, as the text counts them.
+    pub paragraph: usize,
+    /// The text Word last showed for it; empty when none was saved.
+    pub result: String,
+    /// What it evaluated to, with [`FieldMode::Evaluate`] and a field
+    /// that can be worked out.
+    pub value: Option<String>,
+}
+
+impl Field {
+    pub(crate) fn new(instruction: &str, paragraph: usize) -> Field {
+        let mut field = Field {
+            code: String::new(),
+            instruction: String::new(),
+            paragraph,
+            result: String::new(),
+            value: None,
+        };
+        field.set_instruction(instruction);
+        field
+    }
+
+    pub(crate) fn set_instruction(&mut self, instruction: &str) {
+        self.instruction = instruction.trim().to_string();
+        self.code = words(&self.instruction)
+            .first()
+            .map_or_else(String::new, |word| word.to_ascii_uppercase());
+    }
+
+    pub fn to_json(&self) -> Value {
+        Value::object(vec![
+            ("code", Value::from(self.code.as_str())),
+            ("instruction", Value::from(self.instruction.as_str())),
+            ("paragraph", Value::from(self.paragraph)),
+            ("result", Value::from(self.result.as_str())),
+            (
+                "value",
+                self.value.as_deref().map_or(Value::Null, Value::from),
+            ),
+        ])
+    }
+}
+
+/// Works out field values in document order, keeping the counters that
+/// `SEQ` and `AUTONUM` number by.
+pub(crate) struct Evaluator {
+    today: Date,
+    /// The document's path, for `FILENAME`.
+    file: Option<PathBuf>,
+    sequences: Vec<(String, u64)>,
+    autonum: u64,
+}
+
+impl Evaluator {
+    pub fn new(today: Date, file: Option<PathBuf>) -> Evaluator {
+        Evaluator {
+            today,
+            file,
+            sequences: Vec::new(),
+            autonum: 0,
+        }
+    }
+
+    /// The value of the field with code `instruction`, if it is one that
+    /// can be worked out.
+    pub fn evaluate(&mut self, instruction: &str) -> Option<String> {
+        let words = words(instruction);
+        let has = |switch: &str| words.iter().any(|w| w.eq_ignore_ascii_case(switch));
+        let arg = |switch: &str| {
+            let at = words.iter().position(|w| w.eq_ignore_ascii_case(switch))?;
+            words.get(at + 1).map(String::as_str)
+        };
+        // Every `\*` format; a field can have a number format and a case.
+        let formats: Vec<&str> = words
+            .windows(2)
+            .filter(|pair| pair[0] == "\\*")
+            .map(|pair| pair[1].as_str())
+            .collect();
+        let value = match words.first()?.to_ascii_uppercase().as_str() {
+            "DATE" => self.today.format(arg("\\@").unwrap_or("M/d/yyyy")),
+            "SEQ" => {
+                let name = words.get(1).filter(|w| !w.starts_with('\\'))?;
+                let at = match self.sequences.iter().position(|(seq, _)| seq == name) {
+                    Some(at) => at,
+                    None => {
+                        self.sequences.push((name.clone(), 0));
+                        self.sequences.len() - 1
+                    }
+                };
+                let counter = &mut self.sequences[at].1;
+                match arg("\\r").and_then(|n| n.parse().ok()) {
+                    Some(reset) => *counter = reset,
+                    None if has("\\c") => {}
+                    None => *counter += 1,
+                }
+                if has("\\h") {
+                    return Some(String::new());
+                }
+                number(*counter, &formats)
+            }
+            "AUTONUM" => {
+                self.autonum += 1;
+                format!("{}{}", self.autonum, arg("\\s").unwrap_or("."))
+            }
+            "FILENAME" => {
+                let file = self.file.as_ref()?;
+                if has("\\p") {
+                    file.display().to_string()
+                } else {
+                    file.file_name()?.to_string_lossy().into_owned()
+                }
+            }
+            _ => return None,
+        };
+        Some(
+            formats
+                .iter()
+                .fold(value, |value, format| case(&value, format)),
+        )
+    }
+}
+
+/// `n` in the first number format among `formats`: ARABIC (the default),
+/// ROMAN, roman, ALPHABETIC or alphabetic.
+fn number(n: u64, formats: &[&str]) -> String {
+    for format in formats {
+        match *format {
+            "ROMAN" => return roman(n),
+            "roman" => return roman(n).to_lowercase(),
+            "ALPHABETIC" => return alphabetic(n),
+            "alphabetic" => return alphabetic(n).to_lowercase(),
+            _ => {}
+        }
+    }
+    n.to_string()
+}
+
+fn roman(mut n: u64) -> String {
+    const NUMERALS: [(u64, &str); 13] = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+    let mut out = String::new();
+    for (value, numeral) in NUMERALS {
+        while n >= value {
+            out.push_str(numeral);
+            n -= value;
+        }
+    }
+    out
+}
+
+/// A, B, ... Z, AA, BB, as Word counts.
+fn alphabetic(n: u64) -> String {
+    if n == 0 {
+        return String::new();
+    }
+    let letter = char::from(b'A' + ((n - 1) % 26) as u8);
+    std::iter::repeat_n(letter, ((n - 1) / 26 + 1) as usize).collect()
+}
+
+/// `text` in a case format: Upper, Lower, Caps or FirstCap. Other formats
+/// leave it as it is.
+fn case(text: &str, format: &str) -> String {
+    match format.to_ascii_lowercase().as_str() {
+        "upper" => text.to_uppercase(),
+        "lower" => text.to_lowercase(),
+        "caps" => text
+            .split(' ')
+            .map(capitalize)
+            .collect::<Vec<_>>()
+            .join(" "),
+        "firstcap" => capitalize(text),
+        _ => text.to_string(),
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// The words of a field code, with quoted ones unquoted.
+pub(crate) fn words(instruction: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut chars = instruction.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            words.push(chars.by_ref().take_while(|&c| c != '"').collect());
+        } else {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+            words.push(word);
+        }
+    }
+    words
+}
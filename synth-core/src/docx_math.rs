@@ -0,0 +1,513 @@
+//! Word equations (Office Math, `m:oMath`) as LaTeX, or in a linear form
+//! that reads as plain text: `(a+b)/2`, `x^2`, `√(x)`.
+//!
+//! The structures Word's equation editor builds are covered: fractions,
+//! scripts, radicals, delimiters, n-ary operators, functions, accents,
+//! bars, limits, matrices and equation arrays. Anything else is read as
+//! the math inside it.
+
+use crate::xml::Element;
+
+/// How equations appear in the extracted text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MathMode {
+    /// `$\frac{a}{b}$`, and `$$...$$` for equations on their own line.
+    Latex,
+    /// `a/b`.
+    Linear,
+    /// Left out.
+    Omit,
+}
+
+impl MathMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "latex" => Some(MathMode::Latex),
+            "linear" => Some(MathMode::Linear),
+            "omit" => Some(MathMode::Omit),
+            _ => None,
+        }
+    }
+}
+
+/// Characters LaTeX writes as a command.
+const SYMBOLS: &[(char, &str)] = &[
+    ('α', "\\alpha"),
+    ('β', "\\beta"),
+    ('γ', "\\gamma"),
+    ('δ', "\\delta"),
+    ('ε', "\\epsilon"),
+    ('ζ', "\\zeta"),
+    ('η', "\\eta"),
+    ('θ', "\\theta"),
+    ('ι', "\\iota"),
+    ('κ', "\\kappa"),
+    ('λ', "\\lambda"),
+    ('μ', "\\mu"),
+    ('ν', "\\nu"),
+    ('ξ', "\\xi"),
+    ('π', "\\pi"),
+    ('ρ', "\\rho"),
+    ('σ', "\\sigma"),
+    ('τ', "\\tau"),
+    ('υ', "\\upsilon"),
+    ('φ', "\\phi"),
+    ('χ', "\\chi"),
+    ('ψ', "\\psi"),
+    ('ω', "\\omega"),
+    ('Γ', "\\Gamma"),
+    ('Δ', "\\Delta"),
+    ('Θ', "\\Theta"),
+    ('Λ', "\\Lambda"),
+    ('Ξ', "\\Xi"),
+    ('Π', "\\Pi"),
+    ('Σ', "\\Sigma"),
+    ('Υ', "\\Upsilon"),
+    ('Φ', "\\Phi"),
+    ('Ψ', "\\Psi"),
+    ('Ω', "\\Omega"),
+    ('±', "\\pm"),
+    ('∓', "\\mp"),
+    ('×', "\\times"),
+    ('÷', "\\div"),
+    ('·', "\\cdot"),
+    ('⋅', "\\cdot"),
+    ('≤', "\\leq"),
+    ('≥', "\\geq"),
+    ('≠', "\\neq"),
+    ('≈', "\\approx"),
+    ('≡', "\\equiv"),
+    ('∼', "\\sim"),
+    ('∝', "\\propto"),
+    ('∞', "\\infty"),
+    ('∂', "\\partial"),
+    ('∇', "\\nabla"),
+    ('∈', "\\in"),
+    ('∉', "\\notin"),
+    ('⊂', "\\subset"),
+    ('⊆', "\\subseteq"),
+    ('∪', "\\cup"),
+    ('∩', "\\cap"),
+    ('∅', "\\emptyset"),
+    ('∀', "\\forall"),
+    ('∃', "\\exists"),
+    ('¬', "\\neg"),
+    ('∧', "\\wedge"),
+    ('∨', "\\vee"),
+    ('→', "\\to"),
+    ('←', "\\leftarrow"),
+    ('↔', "\\leftrightarrow"),
+    ('⇒', "\\Rightarrow"),
+    ('⇔', "\\Leftrightarrow"),
+    ('…', "\\ldots"),
+    ('⋯', "\\cdots"),
+    ('⟨', "\\langle"),
+    ('⟩', "\\rangle"),
+    ('⌊', "\\lfloor"),
+    ('⌋', "\\rfloor"),
+    ('⌈', "\\lceil"),
+    ('⌉', "\\rceil"),
+    ('‖', "\\|"),
+    ('∑', "\\sum"),
+    ('∏', "\\prod"),
+    ('∐', "\\coprod"),
+    ('∫', "\\int"),
+    ('∬', "\\iint"),
+    ('∭', "\\iiint"),
+    ('∮', "\\oint"),
+    ('⋃', "\\bigcup"),
+    ('⋂', "\\bigcap"),
+    ('⋁', "\\bigvee"),
+    ('⋀', "\\bigwedge"),
+    ('⨁', "\\bigoplus"),
+    ('⨂', "\\bigotimes"),
+    ('{', "\\{"),
+    ('}', "\\}"),
+    ('%', "\\%"),
+    ('#', "\\#"),
+    ('&', "\\&"),
+    ('$', "\\$"),
+    ('_', "\\_"),
+    ('\\', "\\backslash"),
+    ('~', "\\sim"),
+    ('′', "'"),
+    ('″', "''"),
+    ('°', "^\\circ"),
+    ('\u{2212}', "-"),
+];
+
+/// Accents, by their combining character or the character Word uses.
+const ACCENTS: &[(char, &str)] = &[
+    ('\u{0300}', "\\grave"),
+    ('\u{0301}', "\\acute"),
+    ('\u{0302}', "\\hat"),
+    ('\u{0303}', "\\tilde"),
+    ('\u{0304}', "\\bar"),
+    ('\u{0305}', "\\bar"),
+    ('\u{0306}', "\\breve"),
+    ('\u{0307}', "\\dot"),
+    ('\u{0308}', "\\ddot"),
+    ('\u{030C}', "\\check"),
+    ('\u{20D1}', "\\vec"),
+    ('\u{20D7}', "\\vec"),
+    ('^', "\\hat"),
+    ('~', "\\tilde"),
+    ('¯', "\\bar"),
+    ('→', "\\vec"),
+];
+
+/// Function names LaTeX has a command for.
+const FUNCTIONS: &[&str] = &[
+    "arccos", "arcsin", "arctan", "arg", "cos", "cosh", "cot", "coth", "csc", "deg", "det", "dim",
+    "exp", "gcd", "hom", "inf", "ker", "lg", "lim", "liminf", "limsup", "ln", "log", "max", "min",
+    "Pr", "sec", "sin", "sinh", "sup", "tan", "tanh",
+];
+
+/// `el`, an equation (`m:oMath`) or a display of them (`m:oMathPara`),
+/// as it reads in the extracted text: empty with [`MathMode::Omit`].
+pub fn equation(el: &Element, mode: MathMode) -> String {
+    let latex = match mode {
+        MathMode::Omit => return String::new(),
+        MathMode::Latex => true,
+        MathMode::Linear => false,
+    };
+    let math = Math { latex };
+    if el.name != "m:oMathPara" {
+        let text = math.children(el);
+        return match text.trim() {
+            "" => String::new(),
+            text if latex => format!("${text}$"),
+            text => text.to_string(),
+        };
+    }
+    let lines: Vec<String> = el
+        .elements()
+        .filter(|el| el.name == "m:oMath")
+        .map(|el| math.children(el).trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect();
+    match (lines.is_empty(), latex) {
+        (true, _) => String::new(),
+        (false, true) => format!("$${}$$", lines.join(" \\\\ ")),
+        (false, false) => lines.join("\n"),
+    }
+}
+
+struct Math {
+    latex: bool,
+}
+
+impl Math {
+    fn children(&self, el: &Element) -> String {
+        let mut out = String::new();
+        for child in el.elements() {
+            push(&mut out, &self.node(child));
+        }
+        out
+    }
+
+    /// The child `name` of `el`, rendered.
+    fn arg(&self, el: &Element, name: &str) -> String {
+        el.child(name)
+            .map(|child| self.children(child).trim().to_string())
+            .unwrap_or_default()
+    }
+
+    fn node(&self, el: &Element) -> String {
+        let e = || self.arg(el, "m:e");
+        let latex = self.latex;
+        match el.name.as_str() {
+            "m:r" => self.run(el),
+            "m:f" => {
+                let (num, den) = (self.arg(el, "m:num"), self.arg(el, "m:den"));
+                match (prop(el, "m:fPr", "m:type"), latex) {
+                    (Some("lin" | "skw"), true) => format!("{num}/{den}"),
+                    (Some("noBar"), true) => {
+                        format!("\\genfrac{{}}{{}}{{0pt}}{{}}{{{num}}}{{{den}}}")
+                    }
+                    (_, true) => format!("\\frac{{{num}}}{{{den}}}"),
+                    (_, false) => format!("{}/{}", group(&num), group(&den)),
+                }
+            }
+            "m:sSup" => format!(
+                "{}^{}",
+                self.base(&e()),
+                self.script(&self.arg(el, "m:sup"))
+            ),
+            "m:sSub" => format!(
+                "{}_{}",
+                self.base(&e()),
+                self.script(&self.arg(el, "m:sub"))
+            ),
+            "m:sSubSup" => format!(
+                "{}_{}^{}",
+                self.base(&e()),
+                self.script(&self.arg(el, "m:sub")),
+                self.script(&self.arg(el, "m:sup"))
+            ),
+            "m:sPre" => {
+                let scripts = format!(
+                    "_{}^{}",
+                    self.script(&self.arg(el, "m:sub")),
+                    self.script(&self.arg(el, "m:sup"))
+                );
+                match latex {
+                    true => format!("{{}}{scripts}{}", e()),
+                    false => format!("{scripts}{}", self.base(&e())),
+                }
+            }
+            "m:rad" => {
+                let degree = match prop(el, "m:radPr", "m:degHide") {
+                    Some("1" | "on" | "true") => String::new(),
+                    _ => self.arg(el, "m:deg"),
+                };
+                match (degree.is_empty(), latex) {
+                    (true, true) => format!("\\sqrt{{{}}}", e()),
+                    (false, true) => format!("\\sqrt[{degree}]{{{}}}", e()),
+                    (true, false) => format!("√{}", group(&e())),
+                    (false, false) => format!("{}^(1/{degree})", group(&e())),
+                }
+            }
+            "m:d" => self.delimited(el),
+            "m:nary" => {
+                let op = prop(el, "m:naryPr", "m:chr")
+                    .and_then(|c| c.chars().next())
+                    .unwrap_or('∫');
+                let hidden = |name| matches!(prop(el, "m:naryPr", name), Some("1" | "on" | "true"));
+                let mut out = match latex {
+                    true => symbol(op).map_or(op.to_string(), str::to_string),
+                    false => op.to_string(),
+                };
+                for (mark, name, hide) in [('_', "m:sub", "m:subHide"), ('^', "m:sup", "m:supHide")]
+                {
+                    let limit = self.arg(el, name);
+                    if !hidden(hide) && !limit.is_empty() {
+                        out.push(mark);
+                        out.push_str(&self.script(&limit));
+                    }
+                }
+                out.push(' ');
+                out.push_str(&e());
+                out
+            }
+            "m:func" => {
+                let name = self.arg(el, "m:fName");
+                let mut out = match latex {
+                    true if FUNCTIONS.contains(&name.as_str()) => format!("\\{name}"),
+                    true if name.starts_with('\\') => name,
+                    true => format!("\\operatorname{{{name}}}"),
+                    false => name,
+                };
+                let arg = e();
+                if !latex && !arg.starts_with('(') {
+                    out.push(' ');
+                }
+                push(&mut out, &arg);
+                out
+            }
+            "m:acc" => {
+                let accent = prop(el, "m:accPr", "m:chr")
+                    .and_then(|c| c.chars().next())
+                    .unwrap_or('\u{0302}');
+                match latex {
+                    true => match ACCENTS.iter().find(|(c, _)| *c == accent) {
+                        Some((_, command)) => format!("{command}{{{}}}", e()),
+                        None => format!("\\overset{{{accent}}}{{{}}}", e()),
+                    },
+                    false => format!("{}{accent}", group(&e())),
+                }
+            }
+            "m:bar" => {
+                let top = prop(el, "m:barPr", "m:pos") == Some("top");
+                match (top, latex) {
+                    (true, true) => format!("\\overline{{{}}}", e()),
+                    (false, true) => format!("\\underline{{{}}}", e()),
+                    (true, false) => format!("{}\u{0305}", group(&e())),
+                    (false, false) => format!("{}\u{0332}", group(&e())),
+                }
+            }
+            "m:limLow" | "m:limUpp" => {
+                let (base, limit) = (e(), self.arg(el, "m:lim"));
+                let low = el.name == "m:limLow";
+                match latex {
+                    true if low && FUNCTIONS.contains(&base.as_str()) => {
+                        format!("\\{base}_{}", self.script(&limit))
+                    }
+                    true if low => format!("\\underset{{{limit}}}{{{base}}}"),
+                    true => format!("\\overset{{{limit}}}{{{base}}}"),
+                    false => format!("{base}{}{}", if low { '_' } else { '^' }, group(&limit)),
+                }
+            }
+            "m:groupChr" => {
+                let chr = prop(el, "m:groupChrPr", "m:chr");
+                match (chr, latex) {
+                    (Some("⏞"), true) => format!("\\overbrace{{{}}}", e()),
+                    (None | Some("⏟"), true) => format!("\\underbrace{{{}}}", e()),
+                    _ => e(),
+                }
+            }
+            "m:m" => {
+                let rows: Vec<Vec<String>> = el
+                    .elements()
+                    .filter(|row| row.name == "m:mr")
+                    .map(|row| self.cells(row))
+                    .collect();
+                let (cell, row) = if latex {
+                    (" & ", " \\\\ ")
+                } else {
+                    (" ", "; ")
+                };
+                let body = rows
+                    .iter()
+                    .map(|cells| cells.join(cell))
+                    .collect::<Vec<_>>()
+                    .join(row);
+                match latex {
+                    true => format!("\\begin{{matrix}}{body}\\end{{matrix}}"),
+                    false => format!("[{body}]"),
+                }
+            }
+            "m:eqArr" => match latex {
+                true => format!(
+                    "\\begin{{aligned}}{}\\end{{aligned}}",
+                    self.cells(el).join(" \\\\ ")
+                ),
+                false => self.cells(el).join("; "),
+            },
+            name if name.ends_with("Pr") => String::new(),
+            _ => self.children(el),
+        }
+    }
+
+    /// The rendered `m:e` children of `el`.
+    fn cells(&self, el: &Element) -> Vec<String> {
+        el.elements()
+            .filter(|e| e.name == "m:e")
+            .map(|e| self.children(e).trim().to_string())
+            .collect()
+    }
+
+    /// The text of a math run.
+    fn run(&self, run: &Element) -> String {
+        let mut text = String::new();
+        for t in run.elements().filter(|el| el.name == "m:t") {
+            text.push_str(&t.text());
+        }
+        if !self.latex {
+            return text.replace('\u{2212}', "-");
+        }
+        let plain = run
+            .child("m:rPr")
+            .is_some_and(|props| props.child("m:nor").is_some());
+        let mut out = String::new();
+        for c in text.chars() {
+            match symbol(c) {
+                Some(command) => push(&mut out, command),
+                None => out.push(c),
+            }
+        }
+        match plain {
+            true => format!("\\text{{{out}}}"),
+            false => out,
+        }
+    }
+
+    /// An open/close pair of delimiters around the `m:e` children, split
+    /// by a separator.
+    fn delimited(&self, el: &Element) -> String {
+        let chr = |name, default| prop(el, "m:dPr", name).unwrap_or(default).to_string();
+        let (open, close, sep) = (
+            chr("m:begChr", "("),
+            chr("m:endChr", ")"),
+            chr("m:sepChr", "|"),
+        );
+        let cells = self.cells(el);
+        if !self.latex {
+            return format!("{open}{}{close}", cells.join(&sep));
+        }
+        let delimiter = |d: &str| match d.chars().next() {
+            None => ".".to_string(),
+            Some(c) => symbol(c).map_or(d.to_string(), str::to_string),
+        };
+        let sep = match sep.as_str() {
+            "|" => " \\mid ".to_string(),
+            sep => delimiter(sep),
+        };
+        format!(
+            "\\left{}{}\\right{}",
+            delimiter(&open),
+            cells.join(&sep),
+            delimiter(&close)
+        )
+    }
+
+    /// The base of a script.
+    fn base(&self, base: &str) -> String {
+        match self.latex {
+            true => base.to_string(),
+            false => group(base),
+        }
+    }
+
+    /// A sub- or superscript.
+    fn script(&self, script: &str) -> String {
+        match (self.latex, script.chars().count()) {
+            (true, 1) => script.to_string(),
+            (true, _) => format!("{{{script}}}"),
+            (false, _) => group(script),
+        }
+    }
+}
+
+/// The `m:val` of `el`'s property `name` in its properties `props`.
+fn prop<'a>(el: &'a Element, props: &str, name: &str) -> Option<&'a str> {
+    el.child(props)?.child(name)?.attr("m:val")
+}
+
+fn symbol(c: char) -> Option<&'static str> {
+    SYMBOLS
+        .iter()
+        .find(|(s, _)| *s == c)
+        .map(|(_, command)| *command)
+}
+
+/// Append `text`, keeping it apart from a LaTeX command it would run into.
+fn push(out: &mut String, text: &str) {
+    let letters = out.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    if letters.len() < out.len()
+        && letters.ends_with('\\')
+        && text.starts_with(|c: char| c.is_ascii_alphabetic())
+    {
+        out.push(' ');
+    }
+    out.push_str(text);
+}
+
+/// `text` as one operand of linear math: bracketed unless it is a single
+/// character, a word or a number or is bracketed already.
+fn group(text: &str) -> String {
+    let atom = text.chars().count() == 1 || text.chars().all(|c| c.is_alphanumeric() || c == '.');
+    if !text.is_empty() && (atom || bracketed(text)) {
+        return text.to_string();
+    }
+    format!("({text})")
+}
+
+/// Whether `text` is one bracketed group, `(a+b)` but not `(a)+(b)`.
+fn bracketed(text: &str) -> bool {
+    if !text.starts_with('(') {
+        return false;
+    }
+    let mut depth = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 {
+            return i == text.len() - 1;
+        }
+    }
+    false
+}
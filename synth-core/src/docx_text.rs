@@ -0,0 +1,1193 @@
+//! Text of a Word (.docx) document for extraction, and where each piece
+//! of it came from, so a span of the extracted text can be traced back to
+//! the paragraph and run it was read from ("show me where this answer
+//! came from").
+//!
+//! Headings become markdown headings and table rows one line each, their
+//! cells separated by ` | `; blocks are separated by a blank line.
+//! Deleted text, field codes and empty paragraphs are left out.
+//!
+//! Text Word does not show is reported apart from the text and, unless
+//! asked for, left out of it: hidden (`w:vanish`) runs, directly or by
+//! style, and white text on no background. What is folded under a heading
+//! saved collapsed is shown by Word once expanded, so it is kept unless
+//! asked otherwise, and reported as well.
+//!
+//! The values of forms built in Word are read into [`FormField`]s: content
+//! controls (`w:sdt`) with their tag or title, and the legacy text, check
+//! box and drop-down form fields.
+//!
+//! Bookmarks are reported with the text they enclose, and cross-reference
+//! fields (`REF`, `PAGEREF`) with the bookmark they point to, its text,
+//! and the result Word last showed for them.
+//!
+//! Every field is reported with its code and saved result; with
+//! [`FieldMode::Evaluate`], the simple ones [`docx_fields`](crate::docx_fields)
+//! can work out are shown with their value instead.
+//!
+//! Text boxes, shapes with text and SmartArt are read after the paragraph
+//! they are anchored in, as blocks of their own; of content saved two
+//! ways for older versions of Word (`mc:AlternateContent`), only the
+//! first is read.
+//!
+//! Equations are shown in LaTeX unless asked for in a linear form (or
+//! not at all); see [`docx_math`](crate::docx_math).
+
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::docx::{self, heading_level, heading_styles};
+use crate::docx_fields::{self, Date, Evaluator, Field, FieldMode};
+use crate::docx_math::{self, MathMode};
+use crate::error::{Error, Result};
+use crate::json::Value;
+use crate::opc::{self, Relationship};
+use crate::xml::{self, Element};
+use crate::zip::Archive;
+
+const DOCUMENT: &str = "word/document.xml";
+const STYLES: &str = "word/styles.xml";
+const DIAGRAM: &str = "http://schemas.openxmlformats.org/drawingml/2006/diagram";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// Keep text Word does not show.
+    pub hidden: bool,
+    /// Keep what is under collapsed headings.
+    pub collapsed: bool,
+    pub fields: FieldMode,
+    /// The date `DATE` fields evaluate to; today (UTC) when unset.
+    pub today: Option<Date>,
+    pub math: MathMode,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            hidden: false,
+            collapsed: true,
+            fields: FieldMode::Cached,
+            today: None,
+            math: MathMode::Latex,
+        }
+    }
+}
+
+impl Options {
+    /// Read `{"hidden": false, "collapsed": true, "fields": "cached" |
+    /// "evaluate", "today": "2026-10-14", "math": "latex" | "linear" |
+    /// "omit"}`; every key is optional.
+    pub fn from_json(value: &Value) -> Result<Options> {
+        let defaults = Options::default();
+        let flag = |key, default| match value.get(key) {
+            None => Ok(default),
+            Some(v) => v
+                .as_bool()
+                .ok_or_else(|| Error::Parse(format!("{key} should be true or false"))),
+        };
+        Ok(Options {
+            hidden: flag("hidden", defaults.hidden)?,
+            collapsed: flag("collapsed", defaults.collapsed)?,
+            fields: match value.get("fields") {
+                None => defaults.fields,
+                Some(v) => v.as_str().and_then(FieldMode::from_name).ok_or_else(|| {
+                    Error::Parse("fields should be \"cached\" or \"evaluate\"".into())
+                })?,
+            },
+            today: match value.get("today") {
+                None => defaults.today,
+                Some(v) => Some(Date::parse(v.as_str().unwrap_or(""))?),
+            },
+            math: match value.get("math") {
+                None => defaults.math,
+                Some(v) => v.as_str().and_then(MathMode::from_name).ok_or_else(|| {
+                    Error::Parse("math should be \"latex\", \"linear\" or \"omit\"".into())
+                })?,
+            },
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HiddenKind {
+    /// Runs formatted hidden.
+    Vanish,
+    /// White text on no background.
+    WhiteText,
+    /// A block under a collapsed heading.
+    Collapsed,
+}
+
+impl HiddenKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            HiddenKind::Vanish => "vanish",
+            HiddenKind::WhiteText => "white_text",
+            HiddenKind::Collapsed => "collapsed",
+        }
+    }
+}
+
+/// Text Word does not show, or shows only on request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hidden {
+    pub kind: HiddenKind,
+    /// The paragraph it is in (or, for a collapsed table, starts at), as
+    /// counted in [`Location`].
+    pub paragraph: usize,
+    pub text: String,
+}
+
+impl Hidden {
+    pub fn to_json(&self) -> Value {
+        Value::object(vec![
+            ("kind", Value::from(self.kind.name())),
+            ("paragraph", Value::from(self.paragraph)),
+            ("text", Value::from(self.text.as_str())),
+        ])
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormKind {
+    Text,
+    Checkbox,
+    /// A drop-down list or combo box.
+    DropDown,
+    Date,
+    /// A PDF group of radio buttons; its value is the chosen button's
+    /// export value.
+    Radio,
+}
+
+impl FormKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            FormKind::Text => "text",
+            FormKind::Checkbox => "checkbox",
+            FormKind::DropDown => "drop_down",
+            FormKind::Date => "date",
+            FormKind::Radio => "radio",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormValue {
+    Text(String),
+    Checked(bool),
+    /// Not filled in: the control shows its placeholder, or the field is
+    /// blank.
+    Empty,
+}
+
+impl FormValue {
+    pub fn to_json(&self) -> Value {
+        match self {
+            FormValue::Text(text) => Value::from(text.as_str()),
+            FormValue::Checked(checked) => Value::from(*checked),
+            FormValue::Empty => Value::Null,
+        }
+    }
+}
+
+/// A content control or legacy form field and what it holds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormField {
+    /// The control's tag, else its title; the legacy field's bookmark
+    /// name; or "field 3" for the third field when it has none.
+    pub name: String,
+    pub kind: FormKind,
+    /// Text as shown; for dates, the date as formatted in the document.
+    pub value: FormValue,
+}
+
+/// `{"name": value}` for each field, keeping the first of fields sharing
+/// a name (repeated controls bound to the same data).
+pub fn form_to_json(fields: &[FormField]) -> Value {
+    let mut pairs: Vec<(&str, Value)> = Vec::new();
+    for field in fields {
+        if !pairs.iter().any(|(name, _)| *name == field.name) {
+            pairs.push((&field.name, field.value.to_json()));
+        }
+    }
+    Value::object(pairs)
+}
+
+/// A named span of the document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bookmark {
+    pub name: String,
+    /// The paragraph it starts in, as counted in [`Location`].
+    pub paragraph: usize,
+    /// The text inside it, its paragraphs on lines of their own; empty
+    /// for a bookmark marking a point.
+    pub text: String,
+}
+
+impl Bookmark {
+    pub fn to_json(&self) -> Value {
+        Value::object(vec![
+            ("name", Value::from(self.name.as_str())),
+            ("paragraph", Value::from(self.paragraph)),
+            ("text", Value::from(self.text.as_str())),
+        ])
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReferenceKind {
+    /// The bookmark's text (or its number, with switches).
+    Ref,
+    /// The page the bookmark is on.
+    PageRef,
+}
+
+impl ReferenceKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ReferenceKind::Ref => "ref",
+            ReferenceKind::PageRef => "page_ref",
+        }
+    }
+}
+
+/// A field referring to a bookmark in the same document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrossReference {
+    pub kind: ReferenceKind,
+    pub bookmark: String,
+    /// The paragraph the field is in.
+    pub paragraph: usize,
+    /// What the field showed when the document was saved.
+    pub text: String,
+    /// The bookmark's text; `None` when there is no such bookmark.
+    pub target: Option<String>,
+}
+
+impl CrossReference {
+    pub fn to_json(&self) -> Value {
+        Value::object(vec![
+            ("kind", Value::from(self.kind.name())),
+            ("bookmark", Value::from(self.bookmark.as_str())),
+            ("paragraph", Value::from(self.paragraph)),
+            ("text", Value::from(self.text.as_str())),
+            (
+                "target",
+                self.target.as_deref().map_or(Value::Null, Value::from),
+            ),
+        ])
+    }
+}
+
+/// A document's text and what was found reading it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Extraction {
+    pub text: String,
+    /// Hidden text, whether or not it was kept.
+    pub hidden: Vec<Hidden>,
+    pub form: Vec<FormField>,
+    /// Bookmarks in document order, leaving out Word's own `_GoBack`.
+    pub bookmarks: Vec<Bookmark>,
+    pub references: Vec<CrossReference>,
+    /// Every field, in the order they end (an inner field before the one
+    /// around it).
+    pub fields: Vec<Field>,
+}
+
+impl Extraction {
+    pub fn to_json(&self) -> Value {
+        Value::object(vec![
+            ("text", Value::from(self.text.as_str())),
+            (
+                "hidden",
+                Value::Array(self.hidden.iter().map(Hidden::to_json).collect()),
+            ),
+            ("form", form_to_json(&self.form)),
+            (
+                "bookmarks",
+                Value::Array(self.bookmarks.iter().map(Bookmark::to_json).collect()),
+            ),
+            (
+                "references",
+                Value::Array(
+                    self.references
+                        .iter()
+                        .map(CrossReference::to_json)
+                        .collect(),
+                ),
+            ),
+            (
+                "fields",
+                Value::Array(self.fields.iter().map(Field::to_json).collect()),
+            ),
+        ])
+    }
+}
+
+/// Where a span of the extracted text is in the document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    /// The paragraph's position among the document's paragraphs, in
+    /// reading order, from 0.
+    pub paragraph: usize,
+    /// The run's position among the paragraph's `w:r` elements, deleted
+    /// ones included, from 0.
+    pub run: usize,
+    /// The characters of the run's text the span covers.
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+}
+
+impl Location {
+    pub fn to_json(&self) -> Value {
+        Value::object(vec![
+            ("paragraph", Value::from(self.paragraph)),
+            ("run", Value::from(self.run)),
+            ("start", Value::from(self.start)),
+            ("end", Value::from(self.end)),
+            ("text", Value::from(self.text.as_str())),
+        ])
+    }
+}
+
+pub fn locations_to_json(locations: &[Location]) -> Value {
+    Value::Array(locations.iter().map(Location::to_json).collect())
+}
+
+/// The text of the document at `path`, with the default options.
+pub fn text(path: &Path) -> Result<String> {
+    Ok(extract(path, &Options::default())?.text)
+}
+
+pub fn text_from_bytes(bytes: &[u8]) -> Result<String> {
+    let archive = Archive::from_bytes(bytes.to_vec())?;
+    Ok(Reading::open(&archive, &Options::default(), None)?.text)
+}
+
+/// The text of the document at `path` and what it holds besides.
+pub fn extract(path: &Path, options: &Options) -> Result<Extraction> {
+    let reading = Reading::open(&Archive::open(path)?, options, Some(path))?;
+    Ok(Extraction {
+        references: reading.references(),
+        text: reading.text,
+        hidden: reading.hidden,
+        form: reading.form,
+        bookmarks: reading.bookmarks,
+        fields: reading.fields,
+    })
+}
+
+/// The runs characters `range` of the document's text (with the default
+/// options) came from, in order. Characters between runs (heading marks,
+/// cell separators, line breaks) have no location.
+pub fn locate(path: &Path, range: Range<usize>) -> Result<Vec<Location>> {
+    let reading = Reading::open(&Archive::open(path)?, &Options::default(), Some(path))?;
+    Ok(reading.locate(range))
+}
+
+/// The text of a document and the run behind each piece of it.
+pub(crate) struct Reading {
+    pub text: String,
+    pieces: Vec<Piece>,
+    hidden: Vec<Hidden>,
+    form: Vec<FormField>,
+    bookmarks: Vec<Bookmark>,
+    fields: Vec<Field>,
+}
+
+/// The text of one run.
+pub(crate) struct Piece {
+    /// Characters of the extracted text.
+    pub chars: Range<usize>,
+    pub paragraph: usize,
+    pub run: usize,
+    /// The run's element in `word/document.xml`.
+    pub span: Range<usize>,
+}
+
+impl Reading {
+    fn open(archive: &Archive, options: &Options, path: Option<&Path>) -> Result<Reading> {
+        Reading::read(archive, &archive.read_string(DOCUMENT)?, options, path)
+    }
+
+    /// Read `xml`, the main part of the document in `archive`, which is
+    /// at `path` if that is known.
+    pub fn read(
+        archive: &Archive,
+        xml: &str,
+        options: &Options,
+        path: Option<&Path>,
+    ) -> Result<Reading> {
+        let root = xml::parse(xml, true)?;
+        let mut reader = Reader {
+            options,
+            archive,
+            relationships: opc::relationships(archive, DOCUMENT)?,
+            styles: heading_styles(archive)?,
+            hidden_styles: hidden_styles(archive)?,
+            paragraphs: 0,
+            hidden: Vec::new(),
+            collapsed: None,
+            shaded: false,
+            bookmarks: Vec::new(),
+            open_bookmarks: Vec::new(),
+            fields: Vec::new(),
+            open_fields: Vec::new(),
+            drawings: Vec::new(),
+            evaluator: (options.fields == FieldMode::Evaluate).then(|| {
+                Evaluator::new(
+                    options.today.unwrap_or_else(Date::today),
+                    path.map(PathBuf::from),
+                )
+            }),
+        };
+        let body = docx::body(&root)?;
+        let blocks = reader.blocks(body);
+        let mut form = Vec::new();
+        form_fields(body, &mut form);
+        let mut text = Line::default();
+        for (i, block) in blocks.into_iter().enumerate() {
+            if i > 0 {
+                text.push("\n\n");
+            }
+            text.extend(block);
+        }
+        if !text.text.is_empty() {
+            text.push("\n");
+        }
+        Ok(Reading {
+            text: text.text,
+            pieces: text.pieces,
+            hidden: reader.hidden,
+            form,
+            bookmarks: reader.bookmarks,
+            fields: reader.fields,
+        })
+    }
+
+    fn references(&self) -> Vec<CrossReference> {
+        self.fields
+            .iter()
+            .filter_map(|field| {
+                let words = docx_fields::words(&field.instruction);
+                let kind = match words.first()?.to_ascii_uppercase().as_str() {
+                    "REF" => ReferenceKind::Ref,
+                    "PAGEREF" => ReferenceKind::PageRef,
+                    _ => return None,
+                };
+                let bookmark = words.get(1)?.clone();
+                Some(CrossReference {
+                    kind,
+                    target: self
+                        .bookmarks
+                        .iter()
+                        .find(|b| b.name == bookmark)
+                        .map(|b| b.text.clone()),
+                    bookmark,
+                    paragraph: field.paragraph,
+                    text: field.result.trim().to_string(),
+                })
+            })
+            .collect()
+    }
+
+    /// The pieces overlapping `range`, each with the characters of its
+    /// run's text that are inside it.
+    pub fn pieces(&self, range: Range<usize>) -> Vec<(&Piece, Range<usize>)> {
+        self.pieces
+            .iter()
+            .filter(|piece| piece.chars.start < range.end && range.start < piece.chars.end)
+            .map(|piece| {
+                let start = range.start.max(piece.chars.start) - piece.chars.start;
+                let end = range.end.min(piece.chars.end) - piece.chars.start;
+                (piece, start..end)
+            })
+            .collect()
+    }
+
+    pub fn locate(&self, range: Range<usize>) -> Vec<Location> {
+        self.pieces(range)
+            .into_iter()
+            .map(|(piece, part)| Location {
+                paragraph: piece.paragraph,
+                run: piece.run,
+                text: self
+                    .text
+                    .chars()
+                    .skip(piece.chars.start + part.start)
+                    .take(part.len())
+                    .collect(),
+                start: part.start,
+                end: part.end,
+            })
+            .collect()
+    }
+}
+
+/// Text being put together, with the pieces in it.
+#[derive(Default)]
+struct Line {
+    text: String,
+    chars: usize,
+    pieces: Vec<Piece>,
+}
+
+impl Line {
+    fn push(&mut self, text: &str) {
+        self.text.push_str(text);
+        self.chars += text.chars().count();
+    }
+
+    fn push_run(&mut self, text: &str, paragraph: usize, run: usize, span: Range<usize>) {
+        let start = self.chars;
+        self.push(text);
+        self.pieces.push(Piece {
+            chars: start..self.chars,
+            paragraph,
+            run,
+            span,
+        });
+    }
+
+    fn extend(&mut self, other: Line) {
+        let shift = self.chars;
+        self.pieces
+            .extend(other.pieces.into_iter().map(|piece| Piece {
+                chars: piece.chars.start + shift..piece.chars.end + shift,
+                ..piece
+            }));
+        self.push(&other.text);
+    }
+
+    fn is_blank(&self) -> bool {
+        self.text.trim().is_empty()
+    }
+}
+
+struct Reader<'a> {
+    options: &'a Options,
+    archive: &'a Archive,
+    /// Of the main part.
+    relationships: Vec<Relationship>,
+    styles: Vec<(String, u8)>,
+    /// Style ids whose text is hidden.
+    hidden_styles: Vec<String>,
+    /// Paragraphs read so far.
+    paragraphs: usize,
+    hidden: Vec<Hidden>,
+    /// The level of the collapsed heading whose section is being read.
+    collapsed: Option<u8>,
+    /// Whether the table cell being read has a background.
+    shaded: bool,
+    bookmarks: Vec<Bookmark>,
+    /// Bookmarks started and not yet ended, by id, in `bookmarks`.
+    open_bookmarks: Vec<(String, usize)>,
+    fields: Vec<Field>,
+    /// Innermost last.
+    open_fields: Vec<OpenField>,
+    /// Blocks read from the drawings in the paragraph being read.
+    drawings: Vec<Line>,
+    /// With [`FieldMode::Evaluate`].
+    evaluator: Option<Evaluator>,
+}
+
+/// A field begun and not yet ended.
+struct OpenField {
+    field: Field,
+    /// Whether its result has started.
+    result: bool,
+}
+
+impl OpenField {
+    /// Whether the saved result is replaced by the field's value.
+    fn replaced(&self) -> bool {
+        self.result && self.field.value.is_some()
+    }
+}
+
+/// The paragraph whose runs are being read.
+struct Paragraph {
+    index: usize,
+    /// Whether its style hides its text.
+    hidden: bool,
+    shaded: bool,
+    runs: usize,
+}
+
+impl Reader<'_> {
+    /// The non-empty blocks among `parent`'s children.
+    fn blocks(&mut self, parent: &Element) -> Vec<Line> {
+        let mut blocks = Vec::new();
+        for el in parent.elements() {
+            let paragraph = self.paragraphs;
+            let block = match el.name.as_str() {
+                "w:p" => {
+                    let level = heading_level(el, &self.styles);
+                    if level.zip(self.collapsed).is_some_and(|(l, c)| l <= c) {
+                        self.collapsed = None;
+                    }
+                    let folded = self.collapsed.is_some();
+                    if on(el.child("w:pPr").and_then(|p| p.child("w15:collapsed"))) {
+                        self.collapsed = self.collapsed.or(level);
+                    }
+                    (self.paragraph(el), folded)
+                }
+                "w:tbl" => (self.table(el), self.collapsed.is_some()),
+                "w:sdt" | "w:sdtContent" | "w:customXml" => {
+                    blocks.extend(self.blocks(el));
+                    continue;
+                }
+                "w:bookmarkStart" | "w:bookmarkEnd" => {
+                    self.bookmark(el, self.paragraphs);
+                    continue;
+                }
+                _ => continue,
+            };
+            let (line, folded) = block;
+            let drawings = std::mem::take(&mut self.drawings);
+            for line in std::iter::once(line).chain(drawings) {
+                if folded && !line.is_blank() {
+                    self.hidden.push(Hidden {
+                        kind: HiddenKind::Collapsed,
+                        paragraph,
+                        text: line.text.clone(),
+                    });
+                    if !self.options.collapsed {
+                        continue;
+                    }
+                }
+                blocks.push(line);
+            }
+        }
+        blocks.retain(|block| !block.is_blank());
+        blocks
+    }
+
+    fn paragraph(&mut self, p: &Element) -> Line {
+        let props = p.child("w:pPr");
+        let mut paragraph = Paragraph {
+            index: self.paragraphs,
+            hidden: props
+                .and_then(|props| props.child("w:pStyle"))
+                .and_then(|style| style.attr("w:val"))
+                .is_some_and(|id| self.hidden_styles.iter().any(|s| s == id)),
+            shaded: shaded(props),
+            runs: 0,
+        };
+        self.paragraphs += 1;
+        let mut runs = Line::default();
+        self.push_runs(p, &mut paragraph, &mut runs);
+        for &(_, i) in &self.open_bookmarks {
+            let text = &mut self.bookmarks[i].text;
+            if !text.is_empty() && !text.ends_with('\n') {
+                text.push('\n');
+            }
+        }
+        let mut line = Line::default();
+        match heading_level(p, &self.styles) {
+            _ if runs.is_blank() => {}
+            Some(level) => line.push(&format!("{} ", "#".repeat(usize::from(level.max(1))))),
+            None => {}
+        }
+        line.extend(runs);
+        line
+    }
+
+    /// One line per row, cells separated by ` | `.
+    fn table(&mut self, table: &Element) -> Line {
+        let mut out = Line::default();
+        for row in table.elements().filter(|el| el.name == "w:tr") {
+            let mut line = Line::default();
+            for (n, cell) in row.elements().filter(|el| el.name == "w:tc").enumerate() {
+                if n > 0 {
+                    line.push(" | ");
+                }
+                let outer = self.shaded;
+                self.shaded |= shaded(cell.child("w:tcPr"));
+                for (i, block) in self.blocks(cell).into_iter().enumerate() {
+                    if i > 0 {
+                        line.push(" ");
+                    }
+                    line.extend(block);
+                }
+                self.shaded = outer;
+            }
+            if line
+                .text
+                .trim_matches(|c: char| c.is_whitespace() || c == '|')
+                .is_empty()
+            {
+                continue;
+            }
+            if !out.text.is_empty() {
+                out.push("\n");
+            }
+            out.extend(line);
+        }
+        out
+    }
+
+    /// The runs inside `el`, looking inside hyperlinks, insertions and
+    /// other inline wrappers.
+    fn push_runs(&mut self, el: &Element, paragraph: &mut Paragraph, line: &mut Line) {
+        for child in el.elements() {
+            match child.name.as_str() {
+                "w:r" => {
+                    let text = run_text(child);
+                    let hidden = self.hidden_kind(child, paragraph);
+                    if let (Some(kind), false) = (hidden, text.trim().is_empty()) {
+                        match self.hidden.last_mut() {
+                            Some(last)
+                                if last.kind == kind && last.paragraph == paragraph.index =>
+                            {
+                                last.text.push_str(&text)
+                            }
+                            _ => self.hidden.push(Hidden {
+                                kind,
+                                paragraph: paragraph.index,
+                                text: text.clone(),
+                            }),
+                        }
+                    }
+                    self.field_chars(child, paragraph.index, line);
+                    if !text.is_empty() && (hidden.is_none() || self.options.hidden) {
+                        if !self.open_fields.iter().any(OpenField::replaced) {
+                            line.push_run(
+                                &text,
+                                paragraph.index,
+                                paragraph.runs,
+                                child.span.clone(),
+                            );
+                        }
+                        self.shown(&text);
+                    }
+                    paragraph.runs += 1;
+                    self.drawings(child);
+                }
+                "w:fldSimple" => {
+                    let mut field =
+                        Field::new(child.attr("w:instr").unwrap_or(""), paragraph.index);
+                    self.evaluate(&mut field);
+                    self.open_fields.push(OpenField {
+                        field,
+                        result: true,
+                    });
+                    self.push_runs(child, paragraph, line);
+                    self.end_field(line);
+                }
+                "w:bookmarkStart" | "w:bookmarkEnd" => self.bookmark(child, paragraph.index),
+                "m:oMathPara" | "m:oMath" => {
+                    let text = docx_math::equation(child, self.options.math);
+                    if !text.is_empty() {
+                        line.push(&text);
+                        self.shown(&text);
+                    }
+                }
+                "w:del" | "w:moveFrom" => {
+                    let mut deleted = Vec::new();
+                    child.find_all("w:r", &mut deleted);
+                    paragraph.runs += deleted.len();
+                }
+                "w:pPr" | "mc:Fallback" => {}
+                _ => self.push_runs(child, paragraph, line),
+            }
+        }
+    }
+
+    /// Read the text boxes and SmartArt in `el` into `drawings`.
+    fn drawings(&mut self, el: &Element) {
+        for child in el.elements() {
+            match child.name.as_str() {
+                "w:txbxContent" => {
+                    let outer = std::mem::take(&mut self.drawings);
+                    let blocks = self.blocks(child);
+                    self.drawings = outer;
+                    self.drawings.extend(blocks);
+                }
+                "a:graphicData" if child.attr("uri") == Some(DIAGRAM) => {
+                    let line = self.diagram(child);
+                    if !line.is_blank() {
+                        self.drawings.push(line);
+                    }
+                }
+                "mc:Fallback" => {}
+                _ => self.drawings(child),
+            }
+        }
+    }
+
+    /// The text of a SmartArt diagram's nodes, one per line, from its
+    /// data part; none if that is missing.
+    fn diagram(&self, data: &Element) -> Line {
+        let mut line = Line::default();
+        let root = data
+            .find("dgm:relIds")
+            .and_then(|ids| ids.attr("r:dm"))
+            .and_then(|id| self.relationships.iter().find(|rel| rel.id == id))
+            .and_then(|rel| self.archive.read_string(&rel.target).ok())
+            .and_then(|xml| xml::parse(&xml, true).ok());
+        let Some(root) = root else {
+            return line;
+        };
+        let mut points = Vec::new();
+        root.find_all("dgm:pt", &mut points);
+        for point in points {
+            if !matches!(point.attr("type"), None | Some("node")) {
+                continue;
+            }
+            let mut paragraphs = Vec::new();
+            point.find_all("a:p", &mut paragraphs);
+            let text = paragraphs
+                .iter()
+                .map(|p| {
+                    let mut runs = Vec::new();
+                    p.find_all("a:t", &mut runs);
+                    runs.iter().map(|t| t.text()).collect::<String>()
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            let text = text.trim();
+            if !text.is_empty() {
+                if !line.text.is_empty() {
+                    line.push("\n");
+                }
+                line.push(text);
+            }
+        }
+        line
+    }
+
+    /// Text shown inside the open bookmarks and field results.
+    fn shown(&mut self, text: &str) {
+        for &(_, i) in &self.open_bookmarks {
+            self.bookmarks[i].text.push_str(text);
+        }
+        for open in &mut self.open_fields {
+            if open.result {
+                open.field.result.push_str(text);
+            }
+        }
+    }
+
+    /// A bookmark's start or end, in or before `paragraph`.
+    fn bookmark(&mut self, el: &Element, paragraph: usize) {
+        let id = el.attr("w:id").unwrap_or("").to_string();
+        if el.name == "w:bookmarkEnd" {
+            if let Some(at) = self.open_bookmarks.iter().position(|(open, _)| *open == id) {
+                let (_, i) = self.open_bookmarks.remove(at);
+                let text = &mut self.bookmarks[i].text;
+                *text = text.trim().to_string();
+            }
+            return;
+        }
+        match el.attr("w:name") {
+            Some("_GoBack") | None => {}
+            Some(name) => {
+                self.open_bookmarks.push((id, self.bookmarks.len()));
+                self.bookmarks.push(Bookmark {
+                    name: name.to_string(),
+                    paragraph,
+                    text: String::new(),
+                });
+            }
+        }
+    }
+
+    /// Follow the field characters and codes in `run`.
+    fn field_chars(&mut self, run: &Element, paragraph: usize, line: &mut Line) {
+        for child in run.elements() {
+            match (child.name.as_str(), child.attr("w:fldCharType")) {
+                ("w:fldChar", Some("begin")) => self.open_fields.push(OpenField {
+                    field: Field::new("", paragraph),
+                    result: false,
+                }),
+                ("w:fldChar", Some("separate")) => {
+                    if let Some(mut open) = self.open_fields.pop() {
+                        open.result = true;
+                        open.field.set_instruction(&open.field.instruction.clone());
+                        self.evaluate(&mut open.field);
+                        self.open_fields.push(open);
+                    }
+                }
+                ("w:fldChar", Some("end")) => self.end_field(line),
+                ("w:instrText", _) => {
+                    if let Some(open) = self.open_fields.last_mut().filter(|open| !open.result) {
+                        open.field.instruction.push_str(&child.text());
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Work out `field`'s value when evaluating fields.
+    fn evaluate(&mut self, field: &mut Field) {
+        if let Some(evaluator) = &mut self.evaluator {
+            field.value = evaluator.evaluate(&field.instruction);
+        }
+    }
+
+    /// End the innermost field, showing its value if it has one.
+    fn end_field(&mut self, line: &mut Line) {
+        let Some(mut open) = self.open_fields.pop() else {
+            return;
+        };
+        if !open.result {
+            // A field without a saved result.
+            open.field.set_instruction(&open.field.instruction.clone());
+            self.evaluate(&mut open.field);
+        }
+        open.field.result = open.field.result.trim().to_string();
+        if let Some(value) = &open.field.value {
+            line.push(value);
+        }
+        self.fields.push(open.field);
+    }
+
+    /// Why Word does not show `run`, if it does not.
+    fn hidden_kind(&self, run: &Element, paragraph: &Paragraph) -> Option<HiddenKind> {
+        let props = run.child("w:rPr");
+        let prop = |name| props.and_then(|props| props.child(name));
+        let vanish = match prop("w:vanish") {
+            Some(_) => on(prop("w:vanish")),
+            None => {
+                paragraph.hidden
+                    || prop("w:rStyle")
+                        .and_then(|style| style.attr("w:val"))
+                        .is_some_and(|id| self.hidden_styles.iter().any(|s| s == id))
+            }
+        };
+        let white = prop("w:color")
+            .and_then(|color| color.attr("w:val"))
+            .is_some_and(|color| color.eq_ignore_ascii_case("FFFFFF"))
+            && !shaded(props)
+            && !prop("w:highlight")
+                .and_then(|h| h.attr("w:val"))
+                .is_some_and(|h| !matches!(h, "none" | "white"))
+            && !paragraph.shaded
+            && !self.shaded;
+        if vanish {
+            Some(HiddenKind::Vanish)
+        } else if white {
+            Some(HiddenKind::WhiteText)
+        } else {
+            None
+        }
+    }
+}
+
+/// The content controls and legacy form fields in `el`, in order.
+fn form_fields(el: &Element, out: &mut Vec<FormField>) {
+    for child in el.elements() {
+        match child.name.as_str() {
+            "w:sdt" => {
+                let content = child.child("w:sdtContent");
+                match content {
+                    // Groups and repeating sections hold other controls.
+                    Some(content) if content.find("w:sdt").is_some() => form_fields(content, out),
+                    _ => {
+                        let field = content_control(child, out.len());
+                        out.push(field);
+                    }
+                }
+            }
+            "w:p" => {
+                legacy_fields(child, out);
+                form_fields(child, out);
+            }
+            _ => form_fields(child, out),
+        }
+    }
+}
+
+fn content_control(sdt: &Element, n: usize) -> FormField {
+    let props = sdt.child("w:sdtPr");
+    let prop = |name| props.and_then(|props| props.child(name));
+    let val = |name| prop(name).and_then(|el| el.attr("w:val"));
+    let name = val("w:tag")
+        .filter(|tag| !tag.is_empty())
+        .or(val("w:alias"))
+        .map_or_else(|| format!("field {}", n + 1), str::to_string);
+    let text = sdt
+        .child("w:sdtContent")
+        .map(shown_text)
+        .unwrap_or_default();
+    if let Some(checkbox) = prop("w14:checkbox") {
+        let checked = checkbox
+            .child("w14:checked")
+            .and_then(|el| el.attr("w14:val"))
+            .is_some_and(|val| matches!(val, "1" | "true"));
+        return FormField {
+            name,
+            kind: FormKind::Checkbox,
+            value: FormValue::Checked(checked),
+        };
+    }
+    let kind = if prop("w:dropDownList").or(prop("w:comboBox")).is_some() {
+        FormKind::DropDown
+    } else if prop("w:date").is_some() {
+        FormKind::Date
+    } else {
+        FormKind::Text
+    };
+    let value = if on(prop("w:showingPlcHdr")) || text.is_empty() {
+        FormValue::Empty
+    } else {
+        FormValue::Text(text)
+    };
+    FormField { name, kind, value }
+}
+
+/// The legacy form fields in a paragraph: fields whose start carries
+/// `w:ffData`, with a text field's value the field's result.
+fn legacy_fields(p: &Element, out: &mut Vec<FormField>) {
+    let mut runs = Vec::new();
+    p.find_all("w:r", &mut runs);
+    // The field being read, and whether its result has started.
+    let mut current: Option<(FormField, bool)> = None;
+    for run in runs {
+        let Some(fld) = run.child("w:fldChar") else {
+            if let Some((field, true)) = &mut current {
+                if let FormValue::Text(text) = &mut field.value {
+                    text.push_str(&run_text(run));
+                }
+            }
+            continue;
+        };
+        match fld.attr("w:fldCharType") {
+            Some("begin") => {
+                current = fld
+                    .child("w:ffData")
+                    .map(|data| (legacy_field(data, out.len()), false));
+            }
+            Some("separate") => {
+                if let Some((_, result)) = &mut current {
+                    *result = true;
+                }
+            }
+            Some("end") => {
+                if let Some((mut field, _)) = current.take() {
+                    if field.value == FormValue::Text(String::new()) {
+                        field.value = FormValue::Empty;
+                    }
+                    out.push(field);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn legacy_field(data: &Element, n: usize) -> FormField {
+    let name = data
+        .child("w:name")
+        .and_then(|el| el.attr("w:val"))
+        .filter(|name| !name.is_empty())
+        .map_or_else(|| format!("field {}", n + 1), str::to_string);
+    if let Some(checkbox) = data.child("w:checkBox") {
+        let checked = match checkbox.child("w:checked") {
+            Some(checked) => on(Some(checked)),
+            None => on(checkbox.child("w:default")),
+        };
+        return FormField {
+            name,
+            kind: FormKind::Checkbox,
+            value: FormValue::Checked(checked),
+        };
+    }
+    if let Some(list) = data.child("w:ddList") {
+        let index = list
+            .child("w:result")
+            .and_then(|el| el.attr("w:val"))
+            .and_then(|val| val.parse().ok())
+            .unwrap_or(0);
+        let value = list
+            .elements()
+            .filter(|el| el.name == "w:listEntry")
+            .nth(index)
+            .and_then(|el| el.attr("w:val"))
+            .map_or(FormValue::Empty, |entry| FormValue::Text(entry.to_string()));
+        return FormField {
+            name,
+            kind: FormKind::DropDown,
+            value,
+        };
+    }
+    FormField {
+        name,
+        kind: FormKind::Text,
+        value: FormValue::Text(String::new()),
+    }
+}
+
+/// The text shown inside `el`, paragraphs on lines of their own.
+fn shown_text(el: &Element) -> String {
+    fn walk(el: &Element, out: &mut String) {
+        for child in el.elements() {
+            match child.name.as_str() {
+                "w:r" => out.push_str(&run_text(child)),
+                "w:del" | "w:moveFrom" | "w:sdtPr" => {}
+                "w:p" => {
+                    if !out.is_empty() {
+                        out.push('\n');
+                    }
+                    walk(child, out);
+                }
+                _ => walk(child, out),
+            }
+        }
+    }
+    let mut out = String::new();
+    walk(el, &mut out);
+    out.trim().to_string()
+}
+
+/// Whether a toggle property is set: present, and not turned off.
+fn on(el: Option<&Element>) -> bool {
+    el.is_some_and(|el| !matches!(el.attr("w:val"), Some("0" | "false" | "off")))
+}
+
+/// Whether properties `props` give a background other than white.
+fn shaded(props: Option<&Element>) -> bool {
+    props
+        .and_then(|props| props.child("w:shd"))
+        .and_then(|shd| shd.attr("w:fill"))
+        .is_some_and(|fill| {
+            !fill.eq_ignore_ascii_case("auto") && !fill.eq_ignore_ascii_case("FFFFFF")
+        })
+}
+
+/// Ids of the styles that hide their text.
+fn hidden_styles(archive: &Archive) -> Result<Vec<String>> {
+    if !archive.contains(STYLES) {
+        return Ok(Vec::new());
+    }
+    let root = xml::parse(&archive.read_string(STYLES)?, false)?;
+    Ok(root
+        .elements()
+        .filter(|style| style.name == "w:style")
+        .filter(|style| {
+            on(style
+                .child("w:rPr")
+                .and_then(|props| props.child("w:vanish")))
+        })
+        .filter_map(|style| style.attr("w:styleId").map(str::to_string))
+        .collect())
+}
+
+/// The text a run shows: its text, tabs and line breaks.
+fn run_text(run: &Element) -> String {
+    let mut out = String::new();
+    for child in run.elements() {
+        match child.name.as_str() {
+            "w:t" => out.push_str(&child.text()),
+            "w:tab" => out.push('\t'),
+            "w:br" | "w:cr" => out.push('\n'),
+            "w:noBreakHyphen" => out.push('-'),
+            _ => {}
+        }
+    }
+    out
+}
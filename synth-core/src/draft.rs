@@ -0,0 +1,331 @@
+//! New clauses drafted in the style of an existing document.
+//!
+//! [`draft_in_style`] samples the document's paragraphs of typical
+//! length, with its first heading and first list as examples of its
+//! formatting, asks the backend to follow an instruction in that style,
+//! and parses the markdown reply into styled paragraphs: headings, bullet
+//! items and body text with bold and italic runs. A [`Draft`] renders as
+//! WordprocessingML paragraphs, ready to insert into the body of a
+//! `.docx`. Clause numbering is kept as written, so it reads like the
+//! source rather than being left to Word's list numbering.
+
+use std::path::Path;
+
+use crate::chat::strip_fences;
+use crate::error::{Error, Result};
+use crate::extract;
+use crate::json::Value;
+use crate::segment::paragraphs;
+use crate::words::words;
+use crate::xml::escape;
+
+/// How many body paragraphs are sampled.
+pub const SAMPLES: usize = 4;
+
+/// Longer passages are cut to this many characters in the prompt.
+const SAMPLE_CHARS: usize = 1_500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Heading,
+    List,
+    Body,
+}
+
+fn kind(block: &str) -> Kind {
+    let lines: Vec<&str> = block.lines().map(str::trim).collect();
+    if lines.len() == 1 {
+        let line = lines[0];
+        if line.starts_with('#')
+            || (words(line).len() <= 12 && !line.ends_with(['.', ',', ';', ':', '?', '!']))
+        {
+            return Kind::Heading;
+        }
+    }
+    // A lone numbered line is a clause, not a list.
+    if lines.iter().all(|line| bullet(line).is_some())
+        || (lines.len() > 1 && lines.iter().all(|line| numbered(line)))
+    {
+        Kind::List
+    } else {
+        Kind::Body
+    }
+}
+
+/// The text of a `-`, `*`, `+` or `•` bullet line.
+fn bullet(line: &str) -> Option<&str> {
+    ["- ", "* ", "+ ", "• "]
+        .iter()
+        .find_map(|marker| line.strip_prefix(marker))
+}
+
+/// Whether `line` starts with a clause number: `1.`, `2.3`, `4)`, `(a)`.
+fn numbered(line: &str) -> bool {
+    let Some(first) = line.split_whitespace().next() else {
+        return false;
+    };
+    let inner = first.trim_start_matches('(').trim_end_matches(['.', ')']);
+    let digits = |part: &str| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit());
+    let marked = first.len() > inner.len();
+    if inner.split('.').all(digits) {
+        return marked || inner.contains('.');
+    }
+    marked && inner.chars().count() == 1 && inner.chars().all(char::is_alphabetic)
+}
+
+/// The document's body paragraphs closest to its typical length, at most
+/// `count`, with its first heading and first list, in document order.
+pub fn sample_paragraphs(text: &str, count: usize) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let blocks: Vec<(Kind, String)> = paragraphs(text)
+        .into_iter()
+        .map(|range| chars[range].iter().collect::<String>())
+        .map(|block| (kind(&block), block))
+        .collect();
+    let mut lengths: Vec<usize> = blocks
+        .iter()
+        .filter(|(kind, _)| *kind == Kind::Body)
+        .map(|(_, block)| words(block).len())
+        .collect();
+    lengths.sort_unstable();
+    let typical = lengths.get(lengths.len() / 2).copied().unwrap_or(0);
+    let mut body: Vec<(usize, usize)> = blocks
+        .iter()
+        .enumerate()
+        .filter(|(_, (kind, _))| *kind == Kind::Body)
+        .map(|(i, (_, block))| (words(block).len().abs_diff(typical), i))
+        .collect();
+    body.sort_unstable();
+    let mut chosen: Vec<usize> = body.into_iter().take(count).map(|(_, i)| i).collect();
+    for wanted in [Kind::Heading, Kind::List] {
+        if let Some(i) = blocks.iter().position(|(kind, _)| *kind == wanted) {
+            chosen.push(i);
+        }
+    }
+    chosen.sort_unstable();
+    chosen.dedup();
+    chosen
+        .into_iter()
+        .map(|i| blocks[i].1.chars().take(SAMPLE_CHARS).collect())
+        .collect()
+}
+
+/// A prompt asking for text that follows `instruction` in the style of
+/// `samples`.
+pub fn style_prompt(samples: &[String], instruction: &str) -> String {
+    format!(
+        "Below are passages from a document. Write new text for it that follows the \
+         instruction, matching the passages' tone, vocabulary, sentence length and \
+         formatting conventions: how headings and clauses are numbered, how lists are \
+         marked and how defined terms are capitalised or emphasised.\n\n\
+         Reply with only the new text. Mark headings with # and emphasis with ** or * \
+         as in markdown.\n\nInstruction: {instruction}\n\nPassages:\n\n{}",
+        samples.join("\n\n---\n\n")
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// Level 1 to 6.
+    Heading(u8),
+    Bullet,
+    Body,
+}
+
+impl Style {
+    /// The built-in Word style id: `Heading1`, `ListBullet` or `Normal`.
+    pub fn word_id(&self) -> String {
+        match self {
+            Style::Heading(level) => format!("Heading{level}"),
+            Style::Bullet => "ListBullet".into(),
+            Style::Body => "Normal".into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Run {
+    pub text: String,
+    pub bold: bool,
+    pub italic: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Paragraph {
+    pub style: Style,
+    pub runs: Vec<Run>,
+}
+
+impl Paragraph {
+    pub fn text(&self) -> String {
+        self.runs.iter().map(|run| run.text.as_str()).collect()
+    }
+
+    pub fn to_json(&self) -> Value {
+        let runs = self
+            .runs
+            .iter()
+            .map(|run| {
+                Value::object([
+                    ("text", Value::from(run.text.as_str())),
+                    ("bold", Value::from(run.bold)),
+                    ("italic", Value::from(run.italic)),
+                ])
+            })
+            .collect();
+        Value::object([
+            ("style", Value::from(self.style.word_id())),
+            ("text", Value::from(self.text())),
+            ("runs", Value::Array(runs)),
+        ])
+    }
+
+    /// One `<w:p>` element.
+    pub fn to_wordml(&self) -> String {
+        let mut xml = String::from("<w:p>");
+        if self.style != Style::Body {
+            xml.push_str(&format!(
+                "<w:pPr><w:pStyle w:val=\"{}\"/></w:pPr>",
+                self.style.word_id()
+            ));
+        }
+        for run in &self.runs {
+            xml.push_str("<w:r>");
+            if run.bold || run.italic {
+                xml.push_str("<w:rPr>");
+                if run.bold {
+                    xml.push_str("<w:b/>");
+                }
+                if run.italic {
+                    xml.push_str("<w:i/>");
+                }
+                xml.push_str("</w:rPr>");
+            }
+            xml.push_str(&format!(
+                "<w:t xml:space=\"preserve\">{}</w:t></w:r>",
+                escape(&run.text)
+            ));
+        }
+        xml.push_str("</w:p>");
+        xml
+    }
+}
+
+/// Split `text` at `marker` pairs, flagging the enclosed pieces. An
+/// unpaired marker is kept as text.
+fn toggled(text: &str, marker: &str) -> Vec<(String, bool)> {
+    let pieces: Vec<&str> = text.split(marker).collect();
+    if pieces.len().is_multiple_of(2) {
+        return vec![(text.to_string(), false)];
+    }
+    pieces
+        .into_iter()
+        .enumerate()
+        .filter(|(_, piece)| !piece.is_empty())
+        .map(|(i, piece)| (piece.to_string(), i % 2 == 1))
+        .collect()
+}
+
+fn runs(text: &str) -> Vec<Run> {
+    toggled(text, "**")
+        .into_iter()
+        .flat_map(|(piece, bold)| {
+            toggled(&piece, "*")
+                .into_iter()
+                .map(move |(text, italic)| Run { text, bold, italic })
+        })
+        .collect()
+}
+
+/// A drafted passage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Draft {
+    /// The reply as markdown, without code fences.
+    pub text: String,
+    pub paragraphs: Vec<Paragraph>,
+}
+
+impl Draft {
+    /// Parse a markdown reply. Each heading, bullet and numbered line
+    /// starts a paragraph; other lines join the one before until a blank
+    /// line.
+    pub fn from_markdown(reply: &str) -> Draft {
+        let text = strip_fences(reply);
+        let mut paragraphs: Vec<(Style, String)> = Vec::new();
+        let mut open = false;
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() {
+                open = false;
+                continue;
+            }
+            let hashes = line.len() - line.trim_start_matches('#').len();
+            let (style, content) = if (1..=6).contains(&hashes) && line[hashes..].starts_with(' ') {
+                (Style::Heading(hashes as u8), line[hashes..].trim())
+            } else if let Some(item) = bullet(line) {
+                (Style::Bullet, item.trim())
+            } else {
+                (Style::Body, line)
+            };
+            match paragraphs.last_mut() {
+                Some((Style::Body, joined)) if open && style == Style::Body && !numbered(line) => {
+                    joined.push(' ');
+                    joined.push_str(content);
+                }
+                _ => paragraphs.push((style, content.to_string())),
+            }
+            open = style == Style::Body;
+        }
+        Draft {
+            paragraphs: paragraphs
+                .into_iter()
+                .map(|(style, content)| Paragraph {
+                    style,
+                    runs: runs(&content),
+                })
+                .collect(),
+            text,
+        }
+    }
+
+    /// The paragraphs as `<w:p>` elements, for the `<w:body>` of
+    /// `word/document.xml`. Heading and bullet styles are Word's built-in
+    /// ones.
+    pub fn to_wordml(&self) -> String {
+        self.paragraphs.iter().map(Paragraph::to_wordml).collect()
+    }
+
+    /// `{"text": "...", "paragraphs": [{"style": "Heading2", "text": "...",
+    /// "runs": [{"text", "bold", "italic"}]}], "wordml": "<w:p>..."}`.
+    pub fn to_json(&self) -> Value {
+        Value::object([
+            ("text", Value::from(self.text.as_str())),
+            (
+                "paragraphs",
+                Value::Array(self.paragraphs.iter().map(Paragraph::to_json).collect()),
+            ),
+            ("wordml", Value::from(self.to_wordml())),
+        ])
+    }
+}
+
+/// Draft text following `instruction` in the style of the document at
+/// `path`, asking `chat`.
+pub fn draft_in_style(
+    path: &Path,
+    instruction: &str,
+    mut chat: impl FnMut(&str) -> Result<String>,
+) -> Result<Draft> {
+    let text = extract::extract_text(path)?;
+    let samples = sample_paragraphs(&text, SAMPLES);
+    if samples.is_empty() {
+        return Err(Error::Parse(format!(
+            "{} has no paragraphs to take a style from",
+            path.display()
+        )));
+    }
+    let draft = Draft::from_markdown(&chat(&style_prompt(&samples, instruction))?);
+    if draft.paragraphs.is_empty() {
+        return Err(Error::Backend("the draft was empty".into()));
+    }
+    Ok(draft)
+}
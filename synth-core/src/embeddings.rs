@@ -0,0 +1,427 @@
+//! Sentence embeddings from a small on-device ONNX model, such as
+//! all-MiniLM-L6-v2, run in-process through tract with the `embeddings`
+//! feature.
+//!
+//! A model is a BERT-style encoder and the WordPiece `vocab.txt` it was
+//! trained with, both installed by the [model manager](crate::models) and
+//! checked against their SHA-256 when loaded, or given as paths. Text is
+//! tokenized here, padded to `max_tokens`, and the model's last hidden
+//! state averaged over the real tokens and scaled to unit length, so the
+//! dot product of two embeddings is their cosine similarity. Nothing is
+//! embedded until a model is configured.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::error::{Error, Result};
+use crate::json::Value;
+use crate::models;
+use crate::normalize;
+
+/// Words longer than this many characters are one unknown token, as in
+/// BERT's own tokenizer.
+const MAX_WORD_CHARS: usize = 100;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    /// The ONNX model file.
+    pub model: PathBuf,
+    /// Its WordPiece vocabulary, one token per line.
+    pub vocab: PathBuf,
+    /// Tokens per text, counting `[CLS]` and `[SEP]`; longer texts are
+    /// cut off.
+    pub max_tokens: usize,
+    /// Lowercase and strip accents first, for uncased models.
+    pub lowercase: bool,
+}
+
+impl Config {
+    /// Read `{"models_dir": "...", "model": "minilm", "vocab":
+    /// "minilm-vocab"}`, naming models installed there, or `{"model_path":
+    /// "/path/model.onnx", "vocab_path": "/path/vocab.txt"}`; also
+    /// `"max_tokens": 128` and `"lowercase": true`.
+    pub fn from_json(value: &Value) -> Result<Config> {
+        let text = |key| value.get(key).and_then(Value::as_str);
+        let (model, vocab) = match text("models_dir") {
+            Some(dir) => {
+                let dir = Path::new(dir);
+                let name = |key| {
+                    text(key).ok_or_else(|| {
+                        Error::Parse(format!("embeddings config needs a \"{key}\" name"))
+                    })
+                };
+                (
+                    models::installed(dir, name("model")?)?,
+                    models::installed(dir, name("vocab")?)?,
+                )
+            }
+            None => {
+                let path = |key| {
+                    text(key).map(PathBuf::from).ok_or_else(|| {
+                        Error::Parse(format!("embeddings config needs \"{key}\" or models_dir"))
+                    })
+                };
+                (path("model_path")?, path("vocab_path")?)
+            }
+        };
+        let max_tokens = match value.get("max_tokens").and_then(Value::as_f64) {
+            Some(n) if (3.0..=8192.0).contains(&n) => n as usize,
+            Some(_) => return Err(Error::Parse("max_tokens must be between 3 and 8192".into())),
+            None => 128,
+        };
+        Ok(Config {
+            model,
+            vocab,
+            max_tokens,
+            lowercase: value
+                .get("lowercase")
+                .and_then(Value::as_bool)
+                .unwrap_or(true),
+        })
+    }
+}
+
+/// BERT's WordPiece tokenizer: text split into words and punctuation,
+/// then each word into the longest pieces the vocabulary has.
+#[derive(Debug, Clone)]
+pub struct Tokenizer {
+    ids: HashMap<String, i64>,
+    lowercase: bool,
+    cls: i64,
+    sep: i64,
+    unk: i64,
+    pad: i64,
+}
+
+impl Tokenizer {
+    /// From the text of a `vocab.txt`, whose line numbers are the ids.
+    pub fn from_vocab(vocab: &str, lowercase: bool) -> Result<Tokenizer> {
+        let ids: HashMap<String, i64> = vocab
+            .lines()
+            .enumerate()
+            .map(|(id, token)| (token.trim_end_matches('\r').to_string(), id as i64))
+            .collect();
+        let special = |token: &str| {
+            ids.get(token)
+                .copied()
+                .ok_or_else(|| Error::Parse(format!("vocabulary has no {token} token")))
+        };
+        Ok(Tokenizer {
+            cls: special("[CLS]")?,
+            sep: special("[SEP]")?,
+            unk: special("[UNK]")?,
+            pad: special("[PAD]")?,
+            ids,
+            lowercase,
+        })
+    }
+
+    /// The ids of `text` between `[CLS]` and `[SEP]`, at most `max_tokens`
+    /// of them in all.
+    pub fn encode(&self, text: &str, max_tokens: usize) -> Vec<i64> {
+        let mut ids = vec![self.cls];
+        let room = max_tokens.saturating_sub(1);
+        for word in self.words(text) {
+            for id in self.pieces(&word) {
+                if ids.len() == room {
+                    ids.push(self.sep);
+                    return ids;
+                }
+                ids.push(id);
+            }
+        }
+        ids.push(self.sep);
+        ids
+    }
+
+    /// The id that pads a short text.
+    pub fn pad(&self) -> i64 {
+        self.pad
+    }
+
+    fn words(&self, text: &str) -> Vec<String> {
+        let mut words = Vec::new();
+        let mut word = String::new();
+        for c in text.chars() {
+            if c == '\0' || c == '\u{fffd}' || (c.is_control() && !c.is_whitespace()) {
+                continue;
+            }
+            if c.is_whitespace() {
+                words.extend((!word.is_empty()).then(|| std::mem::take(&mut word)));
+                continue;
+            }
+            // Punctuation and CJK ideographs are words of their own.
+            if !c.is_alphanumeric() || is_cjk(c) {
+                words.extend((!word.is_empty()).then(|| std::mem::take(&mut word)));
+                words.push(self.fold(c));
+                continue;
+            }
+            word.push_str(&self.fold(c));
+        }
+        words.extend((!word.is_empty()).then_some(word));
+        words
+    }
+
+    fn fold(&self, c: char) -> String {
+        if !self.lowercase {
+            return c.to_string();
+        }
+        c.to_lowercase()
+            .map(|c| normalize::fold(c).map_or_else(|| c.to_string(), str::to_string))
+            .collect()
+    }
+
+    /// Greedy longest-match-first, later pieces prefixed with `##`; a word
+    /// with a part the vocabulary lacks is one unknown token.
+    fn pieces(&self, word: &str) -> Vec<i64> {
+        let chars: Vec<char> = word.chars().collect();
+        if chars.len() > MAX_WORD_CHARS {
+            return vec![self.unk];
+        }
+        let mut pieces = Vec::new();
+        let mut start = 0;
+        while start < chars.len() {
+            let found = (start + 1..=chars.len()).rev().find_map(|end| {
+                let piece: String = chars[start..end].iter().collect();
+                let piece = match start {
+                    0 => piece,
+                    _ => format!("##{piece}"),
+                };
+                self.ids.get(&piece).map(|&id| (id, end))
+            });
+            match found {
+                Some((id, end)) => {
+                    pieces.push(id);
+                    start = end;
+                }
+                None => return vec![self.unk],
+            }
+        }
+        pieces
+    }
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x4e00..=0x9fff
+        | 0x3400..=0x4dbf
+        | 0x20000..=0x2a6df
+        | 0x2a700..=0x2b73f
+        | 0x2b740..=0x2b81f
+        | 0x2b820..=0x2ceaf
+        | 0xf900..=0xfaff
+        | 0x2f800..=0x2fa1f)
+}
+
+/// A loaded model.
+pub struct Embedder {
+    tokenizer: Tokenizer,
+    max_tokens: usize,
+    #[cfg(feature = "embeddings")]
+    model: onnx::Model,
+}
+
+impl Embedder {
+    /// Read the vocabulary and load the model.
+    pub fn load(config: &Config) -> Result<Embedder> {
+        let vocab = fs::read_to_string(&config.vocab)?;
+        let tokenizer = Tokenizer::from_vocab(&vocab, config.lowercase)?;
+        #[cfg(feature = "embeddings")]
+        {
+            Ok(Embedder {
+                model: onnx::Model::load(&config.model, config.max_tokens)?,
+                tokenizer,
+                max_tokens: config.max_tokens,
+            })
+        }
+        #[cfg(not(feature = "embeddings"))]
+        {
+            let _ = (tokenizer, config.max_tokens);
+            Err(Error::Unsupported(
+                "embeddings need the embeddings feature".into(),
+            ))
+        }
+    }
+
+    /// The unit-length embedding of `text`.
+    pub fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let ids = self.tokenizer.encode(text, self.max_tokens);
+        #[cfg(feature = "embeddings")]
+        {
+            let mut vector = self.model.run(&ids, self.tokenizer.pad())?;
+            let length = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+            if length > 0.0 {
+                vector.iter_mut().for_each(|x| *x /= length);
+            }
+            Ok(vector)
+        }
+        #[cfg(not(feature = "embeddings"))]
+        {
+            let _ = ids;
+            Err(Error::Unsupported(
+                "embeddings need the embeddings feature".into(),
+            ))
+        }
+    }
+}
+
+#[cfg(feature = "embeddings")]
+mod onnx {
+    use std::path::Path;
+
+    use tract_onnx::prelude::*;
+
+    use crate::error::{Error, Result};
+
+    /// What a model input named so is fed.
+    enum Input {
+        Ids,
+        Mask,
+        TokenTypes,
+    }
+
+    pub(super) struct Model {
+        plan: TypedRunnableModel<TypedModel>,
+        inputs: Vec<Input>,
+        length: usize,
+    }
+
+    fn error(path: &Path) -> impl Fn(TractError) -> Error + '_ {
+        move |e| Error::Parse(format!("cannot load {}: {e}", path.display()))
+    }
+
+    impl Model {
+        /// Loaded for one shape, `[1, length]`, so it is optimized once.
+        pub(super) fn load(path: &Path, length: usize) -> Result<Model> {
+            let mut model = tract_onnx::onnx()
+                .model_for_path(path)
+                .map_err(error(path))?;
+            let mut inputs = Vec::new();
+            for (index, outlet) in model
+                .input_outlets()
+                .map_err(error(path))?
+                .to_vec()
+                .iter()
+                .enumerate()
+            {
+                inputs.push(match model.node(outlet.node).name.as_str() {
+                    "input_ids" => Input::Ids,
+                    "attention_mask" => Input::Mask,
+                    "token_type_ids" => Input::TokenTypes,
+                    other => {
+                        return Err(Error::Unsupported(format!(
+                            "embedding model input '{other}' is not one BERT has"
+                        )))
+                    }
+                });
+                model
+                    .set_input_fact(index, i64::fact([1, length]).into())
+                    .map_err(error(path))?;
+            }
+            let plan = model
+                .into_optimized()
+                .and_then(|model| model.into_runnable())
+                .map_err(error(path))?;
+            Ok(Model {
+                plan,
+                inputs,
+                length,
+            })
+        }
+
+        /// The last hidden state averaged over the real tokens, or the
+        /// pooled output of a model that gives one.
+        pub(super) fn run(&self, ids: &[i64], pad: i64) -> Result<Vec<f32>> {
+            let real = ids.len().min(self.length);
+            let row = |f: &dyn Fn(usize) -> i64| -> Result<TValue> {
+                let values: Vec<i64> = (0..self.length).map(f).collect();
+                Ok(
+                    tract_ndarray::Array2::from_shape_vec((1, self.length), values)
+                        .map_err(|e| Error::Parse(e.to_string()))?
+                        .into_tensor()
+                        .into(),
+                )
+            };
+            let mut inputs = TVec::new();
+            for input in &self.inputs {
+                inputs.push(match input {
+                    Input::Ids => row(&|i| ids.get(i).copied().unwrap_or(pad))?,
+                    Input::Mask => row(&|i| (i < real) as i64)?,
+                    Input::TokenTypes => row(&|_| 0)?,
+                });
+            }
+            let outputs = self
+                .plan
+                .run(inputs)
+                .map_err(|e| Error::Backend(format!("embedding model failed: {e}")))?;
+            let output = outputs
+                .first()
+                .ok_or_else(|| Error::Backend("embedding model gave no output".into()))?
+                .to_array_view::<f32>()
+                .map_err(|e| Error::Backend(format!("embedding model output: {e}")))?;
+            match *output.shape() {
+                [1, tokens, width] if tokens >= real && real > 0 => {
+                    let mut mean = vec![0f32; width];
+                    for token in 0..real {
+                        for (d, sum) in mean.iter_mut().enumerate() {
+                            *sum += output[[0, token, d]];
+                        }
+                    }
+                    mean.iter_mut().for_each(|x| *x /= real as f32);
+                    Ok(mean)
+                }
+                [1, width] => Ok((0..width).map(|d| output[[0, d]]).collect()),
+                ref shape => Err(Error::Backend(format!(
+                    "embedding model output has shape {shape:?}"
+                ))),
+            }
+        }
+    }
+}
+
+static EMBEDDER: Mutex<Option<Arc<Embedder>>> = Mutex::new(None);
+
+/// Counts models configured, so vectors kept from another are told apart.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+fn lock() -> MutexGuard<'static, Option<Arc<Embedder>>> {
+    EMBEDDER.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Load the model `config` names for [`embed`], or with `None` unload it.
+pub fn configure(config: Option<Config>) -> Result<()> {
+    let embedder = match config {
+        Some(config) => Some(Arc::new(Embedder::load(&config)?)),
+        None => None,
+    };
+    *lock() = embedder;
+    GENERATION.fetch_add(1, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Changes whenever [`configure`] does: embeddings made under another
+/// generation are not comparable with new ones.
+pub fn generation() -> u64 {
+    GENERATION.load(Ordering::Relaxed)
+}
+
+/// Whether a model is loaded.
+pub fn is_configured() -> bool {
+    lock().is_some()
+}
+
+/// The embeddings of `texts` under the configured model.
+pub fn embed(texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+    let embedder = lock()
+        .clone()
+        .ok_or_else(|| Error::Unsupported("no embedding model is configured".into()))?;
+    texts.iter().map(|text| embedder.embed(text)).collect()
+}
+
+/// The cosine similarity of two embeddings Synth made: their dot product.
+pub fn similarity(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
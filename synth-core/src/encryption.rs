@@ -0,0 +1,260 @@
+//! Optional encryption at rest of what synth persists: the reply cache,
+//! jobs, memories, the registry, projects' manifests, text caches and
+//! sessions, in files or in the database, and the audit log.
+//!
+//! With a key configured, supplied by the host or derived from a
+//! passphrase with PBKDF2-HMAC-SHA256, each record is sealed with
+//! AES-256-GCM (RustCrypto's `aes-gcm`, with the `encryption` feature)
+//! under a fresh random nonce and starts with a header naming the format.
+//! The header and the record's name, its path for a store record, are
+//! authenticated with it, so a sealed record copied over another is
+//! refused like a changed one. Once a key is configured plain records are
+//! refused too, since anyone able to write them could plant them;
+//! records written before the key are sealed once, explicitly, by
+//! [`store::seal_existing`](crate::store::seal_existing) and
+//! [`audit::seal_existing`](crate::audit::seal_existing). A sealed record
+//! cannot be read without the key. File and session names, ingested
+//! documents, temporary files handed to `tesseract` or `whisper-cli` and
+//! project exports stay plain.
+
+use std::fmt;
+use std::sync::{Mutex, MutexGuard};
+
+use crate::error::{Error, Result};
+use crate::hash::{hex, pbkdf2_hmac_sha256, unhex};
+use crate::json::Value;
+
+/// The start of every sealed record: the format and its version, also
+/// authenticated with the record.
+const HEADER: &[u8] = b"SYNTHENC\x01";
+
+/// PBKDF2 rounds unless configured (the OWASP recommendation for
+/// HMAC-SHA256).
+pub const DEFAULT_ITERATIONS: u32 = 600_000;
+
+/// The salt for a passphrase configured without one.
+const DEFAULT_SALT: &[u8] = b"synth-encryption-at-rest";
+
+static CONFIG: Mutex<Option<Config>> = Mutex::new(None);
+
+#[derive(Clone, PartialEq)]
+pub struct Config {
+    key: [u8; 32],
+}
+
+impl fmt::Debug for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Config { key: <redacted> }")
+    }
+}
+
+impl Config {
+    /// Encrypt with a 256-bit key the host keeps, e.g. in the Keychain.
+    pub fn from_key(key: [u8; 32]) -> Config {
+        Config { key }
+    }
+
+    /// Encrypt with the key `passphrase` derives. The same passphrase,
+    /// salt and iterations give the same key on every launch.
+    pub fn from_passphrase(passphrase: &str, salt: &[u8], iterations: u32) -> Config {
+        Config {
+            key: derive_key(passphrase, salt, iterations),
+        }
+    }
+
+    /// Read `{"key": "<64 hex digits>"}` or `{"passphrase": "...", "salt":
+    /// "...", "iterations": 600000}`; salt and iterations are optional.
+    pub fn from_json(value: &Value) -> Result<Config> {
+        let text = |key| value.get(key).and_then(Value::as_str);
+        if let Some(key) = text("key") {
+            let key: [u8; 32] = unhex(key.trim())
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or_else(|| Error::Parse("encryption key must be 64 hex digits".into()))?;
+            return Ok(Config::from_key(key));
+        }
+        let passphrase = text("passphrase")
+            .filter(|p| !p.is_empty())
+            .ok_or_else(|| Error::Parse("encryption config needs a key or a passphrase".into()))?;
+        let salt = text("salt").map_or(DEFAULT_SALT, str::as_bytes);
+        let iterations = match value.get("iterations").and_then(Value::as_f64) {
+            Some(n) if n >= 1.0 && n <= u32::MAX as f64 => n as u32,
+            Some(_) => return Err(Error::Parse("iterations must be a positive count".into())),
+            None => DEFAULT_ITERATIONS,
+        };
+        Ok(Config::from_passphrase(passphrase, salt, iterations))
+    }
+}
+
+/// The AES-256 key PBKDF2-HMAC-SHA256 derives from `passphrase`.
+pub fn derive_key(passphrase: &str, salt: &[u8], iterations: u32) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2_hmac_sha256(passphrase.as_bytes(), salt, iterations.max(1), &mut key);
+    key
+}
+
+fn lock() -> MutexGuard<'static, Option<Config>> {
+    CONFIG.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Seal what is persisted from now on with `config`'s key, or with `None`
+/// write it plain again. Records already written are left as they are.
+/// A key needs the `encryption` feature.
+pub fn configure(config: Option<Config>) -> Result<()> {
+    if config.is_some() && !cfg!(feature = "encryption") {
+        return Err(Error::Unsupported(
+            "encryption at rest needs the encryption feature".into(),
+        ));
+    }
+    *lock() = config;
+    Ok(())
+}
+
+pub fn is_enabled() -> bool {
+    lock().is_some()
+}
+
+/// Whether `data` is a sealed record.
+pub fn is_sealed(data: &[u8]) -> bool {
+    data.starts_with(HEADER)
+}
+
+/// What is authenticated besides a record's contents: the header and the
+/// record's name.
+fn associated_data(name: &str) -> Vec<u8> {
+    [HEADER, name.as_bytes()].concat()
+}
+
+/// `data`, the record called `name`, sealed under the configured key, or
+/// as it is without one.
+pub fn seal(data: &[u8], name: &str) -> Result<Vec<u8>> {
+    let Some(config) = lock().clone() else {
+        return Ok(data.to_vec());
+    };
+    let nonce = cipher::nonce()?;
+    let mut out = HEADER.to_vec();
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&cipher::seal(
+        &config.key,
+        &nonce,
+        &associated_data(name),
+        data,
+    )?);
+    Ok(out)
+}
+
+/// The plain contents of the record called `name`, read as `data`.
+pub fn open(data: Vec<u8>, name: &str) -> Result<Vec<u8>> {
+    unseal(data, name, name)
+}
+
+/// [`open`], naming the record `what` in errors.
+fn unseal(data: Vec<u8>, name: &str, what: &str) -> Result<Vec<u8>> {
+    let config = lock().clone();
+    if !is_sealed(&data) {
+        return match config {
+            Some(_) => Err(Error::Unsupported(format!(
+                "{what} is not encrypted; records written before the key must be sealed first"
+            ))),
+            None => Ok(data),
+        };
+    }
+    let config = config.ok_or_else(|| {
+        Error::Unsupported(format!("{what} is encrypted and no key is configured"))
+    })?;
+    let opened = data[HEADER.len()..]
+        .split_first_chunk::<{ cipher::NONCE_LEN }>()
+        .and_then(|(nonce, sealed)| {
+            cipher::open(&config.key, nonce, &associated_data(name), sealed)
+        });
+    opened.ok_or_else(|| {
+        Error::Parse(format!(
+            "{what} cannot be decrypted: the key is wrong or it was changed"
+        ))
+    })
+}
+
+/// A line of text sealed as [`seal`] does, as a line: hex digits after
+/// the header.
+pub(crate) fn seal_line(line: &str, name: &str) -> Result<String> {
+    match is_enabled() {
+        true => Ok(hex(&seal(line.as_bytes(), name)?)),
+        false => Ok(line.to_string()),
+    }
+}
+
+/// The text of a line [`seal_line`] wrote for the record `name`; `what`
+/// says where it was in errors.
+pub(crate) fn open_line(line: &str, name: &str, what: &str) -> Result<String> {
+    let data = match unhex(line) {
+        Some(data) if is_sealed(&data) => data,
+        _ => line.as_bytes().to_vec(),
+    };
+    String::from_utf8(unseal(data, name, what)?)
+        .map_err(|_| Error::Parse(format!("{what} is not UTF-8")))
+}
+
+#[cfg(feature = "encryption")]
+mod cipher {
+    use aes_gcm::aead::{Aead, KeyInit, Payload};
+    use aes_gcm::Aes256Gcm;
+
+    use crate::error::{Error, Result};
+
+    /// Bytes of a nonce.
+    pub(super) const NONCE_LEN: usize = 12;
+
+    pub(super) fn seal(
+        key: &[u8; 32],
+        nonce: &[u8; NONCE_LEN],
+        aad: &[u8],
+        data: &[u8],
+    ) -> Result<Vec<u8>> {
+        Aes256Gcm::new(key.into())
+            .encrypt(nonce.into(), Payload { msg: data, aad })
+            .map_err(|_| Error::Backend("AES-256-GCM could not encrypt".into()))
+    }
+
+    /// The plain text, or `None` if the tag does not match.
+    pub(super) fn open(
+        key: &[u8; 32],
+        nonce: &[u8; NONCE_LEN],
+        aad: &[u8],
+        sealed: &[u8],
+    ) -> Option<Vec<u8>> {
+        Aes256Gcm::new(key.into())
+            .decrypt(nonce.into(), Payload { msg: sealed, aad })
+            .ok()
+    }
+
+    pub(super) fn nonce() -> Result<[u8; NONCE_LEN]> {
+        let mut nonce = [0u8; NONCE_LEN];
+        getrandom::fill(&mut nonce)
+            .map_err(|e| Error::Unsupported(format!("no random source for encryption: {e}")))?;
+        Ok(nonce)
+    }
+}
+
+/// Without the feature no key can be configured, so nothing is sealed and
+/// a sealed record is never opened.
+#[cfg(not(feature = "encryption"))]
+mod cipher {
+    use crate::error::{Error, Result};
+
+    pub(super) const NONCE_LEN: usize = 12;
+
+    fn unsupported() -> Error {
+        Error::Unsupported("encryption at rest needs the encryption feature".into())
+    }
+
+    pub(super) fn seal(_: &[u8; 32], _: &[u8; NONCE_LEN], _: &[u8], _: &[u8]) -> Result<Vec<u8>> {
+        Err(unsupported())
+    }
+
+    pub(super) fn open(_: &[u8; 32], _: &[u8; NONCE_LEN], _: &[u8], _: &[u8]) -> Option<Vec<u8>> {
+        None
+    }
+
+    pub(super) fn nonce() -> Result<[u8; NONCE_LEN]> {
+        Err(unsupported())
+    }
+}
@@ -0,0 +1,78 @@
+use std::fmt;
+use std::io;
+
+/// Errors raised while reading or converting documents.
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// The file type has no extractor.
+    Unsupported(String),
+    /// The content could not be parsed.
+    Parse(String),
+    /// The chat backend failed or returned an unusable reply.
+    Backend(String),
+    /// The backend's JSON reply did not match the requested schema, after
+    /// all retries. Holds the violations from the last attempt.
+    Schema(Vec<String>),
+    /// The reply broke the [glossary](crate::glossary) in force, after all
+    /// retries. Holds the violations from the last attempt.
+    Terminology(Vec<String>),
+    /// Refused by the [policy](crate::policy) in force.
+    Denied(String),
+    /// The options asked for something the input cannot give.
+    InvalidArgument(String),
+}
+
+impl Error {
+    /// Short machine-readable name of the variant.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::Io(_) => "io",
+            Error::Unsupported(_) => "unsupported",
+            Error::Parse(_) => "parse",
+            Error::Backend(_) => "backend",
+            Error::Schema(_) => "schema",
+            Error::Terminology(_) => "terminology",
+            Error::Denied(_) => "denied",
+            Error::InvalidArgument(_) => "invalid_argument",
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "io error: {err}"),
+            Error::Unsupported(what) => write!(f, "unsupported input: {what}"),
+            Error::Parse(msg) => write!(f, "parse error: {msg}"),
+            Error::Backend(msg) => write!(f, "backend error: {msg}"),
+            Error::Schema(violations) => {
+                write!(f, "schema violation: {}", violations.join("; "))
+            }
+            Error::Terminology(violations) => {
+                write!(f, "terminology violation: {}", violations.join("; "))
+            }
+            Error::Denied(msg) => write!(f, "denied by policy: {msg}"),
+            Error::InvalidArgument(msg) => write!(f, "invalid argument: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// The message a caught panic was raised with.
+pub(crate) fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_default()
+}
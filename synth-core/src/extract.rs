@@ -0,0 +1,150 @@
+//! Text extraction, dispatching on file extension.
+
+use std::io::BufReader;
+use std::panic;
+use std::path::Path;
+
+use crate::code::Language;
+use crate::docx_text;
+use crate::error::{panic_message, Error, Result};
+use crate::html;
+use crate::ocr;
+use crate::pdf_text;
+use crate::policy;
+use crate::pptx;
+use crate::prefetch;
+use crate::structured::{self, StructuredOptions};
+use crate::table::{self, TableOptions};
+use crate::telemetry;
+use crate::transcribe;
+
+/// Input formats that have an extractor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Plain text and markdown, returned as-is.
+    Text,
+    Csv,
+    Tsv,
+    Json,
+    Xml,
+    Yaml,
+    /// Web pages, reduced to their article text.
+    Html,
+    /// Source code, returned as-is.
+    Code(Language),
+    /// Word documents, headings and tables as markdown.
+    Docx,
+    /// PowerPoint slides with their speaker notes.
+    Pptx,
+    /// PDF text layers, pages separated by a form feed.
+    Pdf,
+    /// Recorded audio, transcribed with the `transcribe` feature.
+    Audio,
+    /// Screenshots and scans, read with OCR.
+    Image,
+}
+
+impl Format {
+    pub fn from_path(path: &Path) -> Option<Format> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "txt" | "text" | "md" | "markdown" => Some(Format::Text),
+            "csv" => Some(Format::Csv),
+            "tsv" | "tab" => Some(Format::Tsv),
+            "json" => Some(Format::Json),
+            "xml" | "plist" => Some(Format::Xml),
+            "yaml" | "yml" => Some(Format::Yaml),
+            "html" | "htm" | "xhtml" => Some(Format::Html),
+            "docx" => Some(Format::Docx),
+            "pptx" => Some(Format::Pptx),
+            "pdf" => Some(Format::Pdf),
+            _ if transcribe::is_audio(path) => Some(Format::Audio),
+            _ if ocr::is_image(path) => Some(Format::Image),
+            _ => Language::from_path(path).map(Format::Code),
+        }
+    }
+
+    /// Whether files of this format are text, so a prefix of one can be
+    /// shown as-is.
+    pub fn is_text(self) -> bool {
+        !matches!(
+            self,
+            Format::Docx | Format::Pptx | Format::Pdf | Format::Audio | Format::Image
+        )
+    }
+}
+
+/// Extract prompt-ready text from a supported file.
+/// Documents warmed up by [`prefetch`](crate::prefetch) come from its cache.
+pub fn extract_text(path: &Path) -> Result<String> {
+    telemetry::measure("extract", || {
+        policy::check_read(path)?;
+        if let Some(text) = prefetch::cached(path) {
+            return Ok(text);
+        }
+        match Format::from_path(path) {
+            Some(Format::Csv) => extract_table(path, &TableOptions::default()),
+            Some(Format::Tsv) => extract_table(path, &tsv_options()),
+            Some(Format::Audio) => transcribe::transcribe_file(path),
+            Some(Format::Image) => ocr::recognize(path),
+            Some(format) => extract_from_bytes_fallible(&policy::read_file(path)?, format),
+            None => Err(Error::Unsupported(path.display().to_string())),
+        }
+    })
+}
+
+/// Extract prompt-ready text from file contents already in memory, e.g.
+/// read from a descriptor the host opened.
+pub fn extract_bytes(bytes: &[u8], format: Format) -> Result<String> {
+    let text = || String::from_utf8_lossy(bytes);
+    let options = StructuredOptions::default();
+    match format {
+        Format::Text | Format::Code(_) => Ok(text().into_owned()),
+        Format::Csv => table::render_table(bytes, &TableOptions::default()),
+        Format::Tsv => table::render_table(bytes, &tsv_options()),
+        Format::Json => structured::render_json(&text(), &options),
+        Format::Xml => structured::render_xml(&text(), &options),
+        Format::Yaml => structured::render_yaml(&text(), &options),
+        Format::Html => Ok(html::article(&text()).to_markdown()),
+        Format::Docx => docx_text::text_from_bytes(bytes),
+        Format::Pptx => Ok(pptx::render(&pptx::slides_from_bytes(bytes)?)),
+        Format::Pdf => pdf_text::text_from_bytes(bytes),
+        Format::Audio => transcribe::transcribe_bytes(bytes),
+        Format::Image => ocr::recognize_bytes(bytes),
+    }
+}
+
+/// [`extract_bytes`] for untrusted input: a panic in any extractor is
+/// returned as a parse error instead of unwinding into the caller.
+pub fn extract_from_bytes_fallible(bytes: &[u8], format: Format) -> Result<String> {
+    panic::catch_unwind(|| extract_bytes(bytes, format)).unwrap_or_else(|payload| {
+        Err(Error::Parse(format!(
+            "extractor failed: {}",
+            panic_message(payload.as_ref())
+        )))
+    })
+}
+
+fn tsv_options() -> TableOptions {
+    TableOptions {
+        delimiter: Some('\t'),
+        ..TableOptions::default()
+    }
+}
+
+/// Render a CSV/TSV file with explicit table options.
+pub fn extract_table(path: &Path, options: &TableOptions) -> Result<String> {
+    let reader = BufReader::new(policy::open_file(path)?);
+    table::render_table(reader, options)
+}
+
+/// Render a JSON, XML or YAML file with explicit options.
+pub fn extract_structured(path: &Path, options: &StructuredOptions) -> Result<String> {
+    let text = String::from_utf8_lossy(&policy::read_file(path)?).into_owned();
+    match Format::from_path(path) {
+        Some(Format::Json) => structured::render_json(&text, options),
+        Some(Format::Xml) => structured::render_xml(&text, options),
+        Some(Format::Yaml) => structured::render_yaml(&text, options),
+        _ => Err(Error::Unsupported(path.display().to_string())),
+    }
+}
@@ -0,0 +1,257 @@
+//! Required terminology for generated text, so a document says "purchase
+//! order" the same way everywhere.
+//!
+//! A glossary maps each source term to the rendering it must be given (a
+//! translation, or the term itself for same-language drafting), with an
+//! optional definition and variants to avoid. Hosts [`define`] glossaries
+//! by name and select one with
+//! [`ChatOptions::glossary`](crate::chat::ChatOptions::glossary); every
+//! pipeline that chats through those options, translation and drafting
+//! included, then consults it. The entries whose term occurs in the prompt
+//! are added to it, and the reply is [`check`]ed: a reply that drops a
+//! required rendering or uses an avoided variant is sent back with the
+//! violations before the call fails with
+//! [`Error::Terminology`](crate::error::Error::Terminology). The
+//! [translation](crate::translate) pipeline does the same for each part
+//! of a document, with the entries found in that part.
+//!
+//! Terms match as whole words, ignoring case, as in
+//! [`concordance`](crate::concordance).
+
+use std::sync::RwLock;
+
+use crate::error::{Error, Result};
+use crate::json::Value;
+use crate::words::words;
+
+/// How often a reply that breaks the glossary is sent back.
+pub const RETRIES: usize = 1;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub term: String,
+    /// What the term must appear as in the output; `None` only explains
+    /// the term.
+    pub rendering: Option<String>,
+    pub definition: Option<String>,
+    /// Renderings the output must not use.
+    pub avoid: Vec<String>,
+}
+
+impl Entry {
+    /// Read `{"term": "purchase order", "use": "Bestellung", "definition":
+    /// "...", "avoid": ["Kaufauftrag"]}`; only `term` is required.
+    pub fn from_json(value: &Value) -> Result<Entry> {
+        let text = |key| {
+            value
+                .get(key)
+                .and_then(Value::as_str)
+                .filter(|s| !s.trim().is_empty())
+                .map(String::from)
+        };
+        let term =
+            text("term").ok_or_else(|| Error::Parse("a glossary entry needs a term".into()))?;
+        let avoid = match value.get("avoid") {
+            Some(Value::String(s)) => vec![s.clone()],
+            Some(Value::Array(items)) => items
+                .iter()
+                .filter_map(Value::as_str)
+                .filter(|s| !s.trim().is_empty())
+                .map(String::from)
+                .collect(),
+            _ => Vec::new(),
+        };
+        Ok(Entry {
+            term,
+            rendering: text("use"),
+            definition: text("definition"),
+            avoid,
+        })
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut pairs = vec![("term", Value::from(self.term.as_str()))];
+        if let Some(rendering) = &self.rendering {
+            pairs.push(("use", Value::from(rendering.as_str())));
+        }
+        if let Some(definition) = &self.definition {
+            pairs.push(("definition", Value::from(definition.as_str())));
+        }
+        if !self.avoid.is_empty() {
+            pairs.push((
+                "avoid",
+                Value::Array(self.avoid.iter().map(|s| Value::from(s.as_str())).collect()),
+            ));
+        }
+        Value::object(pairs)
+    }
+
+    fn line(&self) -> String {
+        let mut line = match &self.rendering {
+            Some(rendering) => format!("- \"{}\": write \"{rendering}\"", self.term),
+            None => format!("- \"{}\"", self.term),
+        };
+        if let Some(definition) = &self.definition {
+            line.push_str(&format!(" ({definition})"));
+        }
+        if !self.avoid.is_empty() {
+            let avoid: Vec<String> = self.avoid.iter().map(|s| format!("\"{s}\"")).collect();
+            line.push_str(&format!("; never {}", avoid.join(" or ")));
+        }
+        line
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Glossary {
+    pub entries: Vec<Entry>,
+}
+
+impl Glossary {
+    /// Read an array of [`Entry`] objects, or `{"entries": [...]}`.
+    pub fn from_json(value: &Value) -> Result<Glossary> {
+        let items = match value {
+            Value::Array(items) => items,
+            _ => match value.get("entries") {
+                Some(Value::Array(items)) => items,
+                _ => return Err(Error::Parse("a glossary is an array of entries".into())),
+            },
+        };
+        Ok(Glossary {
+            entries: items.iter().map(Entry::from_json).collect::<Result<_>>()?,
+        })
+    }
+
+    pub fn to_json(&self) -> Value {
+        Value::Array(self.entries.iter().map(Entry::to_json).collect())
+    }
+
+    /// The entries whose term occurs in `source`.
+    pub fn relevant(&self, source: &str) -> Vec<&Entry> {
+        let source = words(source);
+        self.entries
+            .iter()
+            .filter(|entry| contains(&source, &entry.term))
+            .collect()
+    }
+
+    /// `prompt` followed by the terminology it must follow, or unchanged
+    /// when no entry applies.
+    pub fn annotate(&self, prompt: &str) -> String {
+        self.annotate_for(prompt, prompt)
+    }
+
+    /// `prompt` followed by the terminology of the entries whose term
+    /// occurs in `source`.
+    pub fn annotate_for(&self, source: &str, prompt: &str) -> String {
+        let relevant = self.relevant(source);
+        if relevant.is_empty() {
+            return prompt.to_string();
+        }
+        let lines: Vec<String> = relevant.iter().map(|entry| entry.line()).collect();
+        format!(
+            "{prompt}\n\nUse this terminology exactly:\n{}",
+            lines.join("\n")
+        )
+    }
+}
+
+/// Whether the words of `phrase` occur, in order and adjacent, in `text`.
+fn contains(text: &[String], phrase: &str) -> bool {
+    let phrase = words(phrase);
+    !phrase.is_empty() && text.windows(phrase.len()).any(|window| window == phrase)
+}
+
+/// How `output`, written from `source`, breaks `glossary`: for each entry
+/// whose term occurs in `source`, a required rendering that is missing and
+/// any avoided variant that is used. Empty when the output complies.
+pub fn check(glossary: &Glossary, source: &str, output: &str) -> Vec<String> {
+    let output = words(output);
+    let mut violations = Vec::new();
+    for entry in glossary.relevant(source) {
+        if let Some(rendering) = &entry.rendering {
+            if !contains(&output, rendering) {
+                violations.push(format!(
+                    "\"{}\" must be rendered as \"{rendering}\"",
+                    entry.term
+                ));
+            }
+        }
+        for variant in &entry.avoid {
+            if contains(&output, variant) {
+                let instead = entry.rendering.as_deref().unwrap_or(&entry.term);
+                violations.push(format!("use \"{instead}\" instead of \"{variant}\""));
+            }
+        }
+    }
+    violations
+}
+
+/// Chat with `prompt` annotated with the relevant entries, sending a
+/// reply that breaks the glossary back with its violations up to
+/// `max_retries` times.
+pub fn enforce(
+    glossary: &Glossary,
+    prompt: &str,
+    max_retries: usize,
+    chat: impl FnMut(&str) -> Result<String>,
+) -> Result<String> {
+    enforce_for(glossary, prompt, prompt, max_retries, chat)
+}
+
+/// [`enforce`] with the entries that apply found in `source`, the text
+/// the reply is written from, rather than in the whole prompt: a
+/// translation is checked against the terms of the passage translated,
+/// not of its instructions.
+pub fn enforce_for(
+    glossary: &Glossary,
+    source: &str,
+    prompt: &str,
+    max_retries: usize,
+    mut chat: impl FnMut(&str) -> Result<String>,
+) -> Result<String> {
+    let annotated = glossary.annotate_for(source, prompt);
+    let mut request = annotated.clone();
+    let mut violations = Vec::new();
+    for _ in 0..=max_retries {
+        let reply = chat(&request)?;
+        violations = check(glossary, source, &reply);
+        if violations.is_empty() {
+            return Ok(reply);
+        }
+        request = format!(
+            "{annotated}\n\nYour previous reply broke the terminology:\n- {}\n\n\
+             Reply again, using the terminology exactly.",
+            violations.join("\n- ")
+        );
+    }
+    Err(Error::Terminology(violations))
+}
+
+static DEFINED: RwLock<Vec<(String, Glossary)>> = RwLock::new(Vec::new());
+
+/// Add or replace the glossary called `name`.
+pub fn define(name: &str, glossary: Glossary) {
+    let mut defined = DEFINED.write().unwrap_or_else(|e| e.into_inner());
+    defined.retain(|(existing, _)| existing != name);
+    defined.push((name.to_string(), glossary));
+}
+
+/// Forget the glossary called `name`. Returns whether there was one.
+pub fn remove(name: &str) -> bool {
+    let mut defined = DEFINED.write().unwrap_or_else(|e| e.into_inner());
+    let before = defined.len();
+    defined.retain(|(existing, _)| existing != name);
+    defined.len() != before
+}
+
+/// The glossary called `name`.
+pub fn glossary(name: &str) -> Result<Glossary> {
+    DEFINED
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .find(|(existing, _)| existing == name)
+        .map(|(_, glossary)| glossary.clone())
+        .ok_or_else(|| Error::Parse(format!("unknown glossary '{name}'")))
+}
@@ -0,0 +1,69 @@
+//! Guardrail hooks run around every chat backend call.
+//!
+//! Pre hooks rewrite the prompt before it is sent (redaction, appended
+//! policy); post hooks rewrite the reply (filters, link stripping). Hooks
+//! run in registration order, each seeing the previous hook's output.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Before the prompt is sent.
+    Pre,
+    /// After the reply arrives.
+    Post,
+}
+
+impl Stage {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "pre" => Some(Stage::Pre),
+            "post" => Some(Stage::Post),
+            _ => None,
+        }
+    }
+}
+
+pub type Hook = Arc<dyn Fn(&str) -> String + Send + Sync>;
+
+struct Registered {
+    id: u64,
+    stage: Stage,
+    hook: Hook,
+}
+
+static HOOKS: RwLock<Vec<Registered>> = RwLock::new(Vec::new());
+/// Ids are never reused, so a stale id cannot remove a newer hook.
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Add a hook and return its id for [`unregister`].
+pub fn register(stage: Stage, hook: Hook) -> u64 {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let mut hooks = HOOKS.write().unwrap_or_else(|e| e.into_inner());
+    hooks.push(Registered { id, stage, hook });
+    id
+}
+
+/// Remove a hook. Returns false if no hook has that id.
+pub fn unregister(id: u64) -> bool {
+    let mut hooks = HOOKS.write().unwrap_or_else(|e| e.into_inner());
+    let before = hooks.len();
+    hooks.retain(|h| h.id != id);
+    hooks.len() != before
+}
+
+/// Pass `text` through every hook registered for `stage`.
+pub fn apply(stage: Stage, text: &str) -> String {
+    // Clone the list so hooks may register or unregister hooks themselves.
+    let hooks: Vec<Hook> = HOOKS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .filter(|h| h.stage == stage)
+        .map(|h| Arc::clone(&h.hook))
+        .collect();
+    hooks
+        .iter()
+        .fold(text.to_string(), |text, hook| hook(&text))
+}
@@ -0,0 +1,634 @@
+//! Readable text from HTML pages, in the spirit of Readability.
+//!
+//! The page is parsed leniently into a flat tree. Navigation, scripts and
+//! elements whose class or id look like chrome (sidebars, comments, share
+//! bars, ...) are dropped. The article is the `<article>` or `<main>`
+//! element when there is one; otherwise the element whose paragraphs
+//! score best on length and commas, discounted by how much of its text
+//! is links. That element is rendered as Markdown-style text.
+
+const MAX_DEPTH: usize = 256;
+
+/// Elements dropped with everything inside them.
+const SKIPPED: &[&str] = &[
+    "script", "style", "noscript", "template", "svg", "iframe", "form", "button", "select", "nav",
+    "header", "footer", "aside", "head",
+];
+
+/// Class or id fragments of page chrome, unless a [`LIKELY`] one also matches.
+const UNLIKELY: &[&str] = &[
+    "comment",
+    "sidebar",
+    "footer",
+    "menu",
+    "share",
+    "social",
+    "advert",
+    "promo",
+    "related",
+    "cookie",
+    "banner",
+    "subscribe",
+    "newsletter",
+    "popup",
+    "breadcrumb",
+];
+const LIKELY: &[&str] = &[
+    "article", "content", "main", "post", "entry", "story", "body",
+];
+
+const VOID: &[&str] = &[
+    "br", "img", "hr", "meta", "link", "input", "area", "base", "col", "embed", "source", "track",
+    "wbr",
+];
+
+/// Elements that end an open `<p>`.
+const BLOCKS: &[&str] = &[
+    "p",
+    "div",
+    "ul",
+    "ol",
+    "table",
+    "pre",
+    "blockquote",
+    "section",
+    "article",
+    "main",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "dl",
+    "figure",
+    "hr",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Article {
+    pub title: Option<String>,
+    pub text: String,
+}
+
+impl Article {
+    /// The text with the title as a `#` heading, unless the page already
+    /// starts with its own.
+    pub fn to_markdown(&self) -> String {
+        match &self.title {
+            Some(title) if !self.text.starts_with("# ") => format!("# {title}\n\n{}", self.text),
+            _ => self.text.clone(),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Kind {
+    Element {
+        tag: String,
+        attrs: Vec<(String, String)>,
+    },
+    Text(String),
+}
+
+#[derive(Debug)]
+struct Node {
+    kind: Kind,
+    parent: usize,
+    children: Vec<usize>,
+}
+
+/// Parsed nodes; index 0 is the document. Children always come after
+/// their parent, so a reverse scan visits every subtree bottom-up.
+struct Document {
+    nodes: Vec<Node>,
+}
+
+impl Document {
+    fn tag(&self, id: usize) -> &str {
+        match &self.nodes[id].kind {
+            Kind::Element { tag, .. } => tag,
+            Kind::Text(_) => "",
+        }
+    }
+
+    fn attr(&self, id: usize, name: &str) -> Option<&str> {
+        match &self.nodes[id].kind {
+            Kind::Element { attrs, .. } => attrs
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str()),
+            Kind::Text(_) => None,
+        }
+    }
+
+    fn elements<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = usize> + 'a {
+        (0..self.nodes.len()).filter(move |&id| self.tag(id) == tag)
+    }
+
+    /// All text under `id`, whitespace collapsed.
+    fn text(&self, id: usize) -> String {
+        let mut out = String::new();
+        let mut pending = vec![id];
+        while let Some(id) = pending.pop() {
+            match &self.nodes[id].kind {
+                Kind::Text(text) => {
+                    out.push_str(text);
+                    out.push(' ');
+                }
+                Kind::Element { .. } => pending.extend(self.nodes[id].children.iter().rev()),
+            }
+        }
+        collapse(&out)
+    }
+}
+
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let decoded = after
+            .char_indices()
+            .take(12)
+            .find(|&(_, c)| c == ';')
+            .and_then(|(end, _)| {
+                let entity = &after[..end];
+                let c = match entity {
+                    "amp" => Some('&'),
+                    "lt" => Some('<'),
+                    "gt" => Some('>'),
+                    "quot" => Some('"'),
+                    "apos" => Some('\''),
+                    "nbsp" => Some('\u{a0}'),
+                    "mdash" => Some('—'),
+                    "ndash" => Some('–'),
+                    "hellip" => Some('…'),
+                    "lsquo" => Some('‘'),
+                    "rsquo" => Some('’'),
+                    "ldquo" => Some('“'),
+                    "rdquo" => Some('”'),
+                    "copy" => Some('©'),
+                    _ => entity
+                        .strip_prefix("#x")
+                        .or_else(|| entity.strip_prefix("#X"))
+                        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                        .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                        .and_then(char::from_u32),
+                };
+                c.map(|c| (c, end))
+            });
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// `name="value"` pairs from the inside of a start tag, after its name.
+fn parse_attrs(mut rest: &str) -> Vec<(String, String)> {
+    let mut attrs = Vec::new();
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+        let name_end = rest
+            .find(|c: char| c.is_whitespace() || c == '=' || c == '/')
+            .unwrap_or(rest.len());
+        if name_end == 0 {
+            return attrs;
+        }
+        let name = rest[..name_end].to_ascii_lowercase();
+        rest = rest[name_end..].trim_start();
+        let value = match rest.strip_prefix('=') {
+            Some(after) => {
+                let after = after.trim_start();
+                match after.chars().next() {
+                    Some(quote @ ('"' | '\'')) => {
+                        let end = after[1..].find(quote).map_or(after.len(), |i| i + 1);
+                        rest = after.get(end + 1..).unwrap_or("");
+                        &after[1..end]
+                    }
+                    _ => {
+                        let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                        rest = &after[end..];
+                        &after[..end]
+                    }
+                }
+            }
+            None => "",
+        };
+        attrs.push((name, decode_entities(value)));
+    }
+}
+
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack
+        .as_bytes()
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+fn parse(html: &str) -> Document {
+    let mut doc = Document {
+        nodes: vec![Node {
+            kind: Kind::Element {
+                tag: "#document".into(),
+                attrs: Vec::new(),
+            },
+            parent: 0,
+            children: Vec::new(),
+        }],
+    };
+    let mut stack = vec![0usize];
+    let add = |doc: &mut Document, parent: usize, kind: Kind| {
+        let id = doc.nodes.len();
+        doc.nodes.push(Node {
+            kind,
+            parent,
+            children: Vec::new(),
+        });
+        doc.nodes[parent].children.push(id);
+        id
+    };
+    let mut rest = html;
+    while !rest.is_empty() {
+        let parent = *stack.last().unwrap_or(&0);
+        if let Some(after) = rest.strip_prefix("<!--") {
+            rest = after.find("-->").map_or("", |end| &after[end + 3..]);
+        } else if rest.starts_with("<!") || rest.starts_with("<?") {
+            rest = rest.find('>').map_or("", |end| &rest[end + 1..]);
+        } else if let Some(after) = rest.strip_prefix("</") {
+            let end = after.find('>').unwrap_or(after.len());
+            let name = after[..end].trim().to_ascii_lowercase();
+            if let Some(depth) = stack.iter().rposition(|&id| doc.tag(id) == name) {
+                stack.truncate(depth.max(1));
+            }
+            rest = after.get(end + 1..).unwrap_or("");
+        } else if rest.starts_with('<') && rest[1..].starts_with(|c: char| c.is_ascii_alphabetic())
+        {
+            // The start tag ends at the first `>` outside a quoted value.
+            let mut quote = None;
+            let end = rest[1..]
+                .char_indices()
+                .find(|&(_, c)| match (quote, c) {
+                    (None, '"' | '\'') => {
+                        quote = Some(c);
+                        false
+                    }
+                    (Some(q), _) if q == c => {
+                        quote = None;
+                        false
+                    }
+                    (None, '>') => true,
+                    _ => false,
+                })
+                .map_or(rest.len() - 1, |(i, _)| i);
+            let body = &rest[1..1 + end];
+            rest = rest.get(end + 2..).unwrap_or("");
+            let name_end = body
+                .find(|c: char| c.is_whitespace() || c == '/')
+                .unwrap_or(body.len());
+            let tag = body[..name_end].to_ascii_lowercase();
+            let attrs = parse_attrs(&body[name_end..]);
+            // Close what an opening tag implies is finished.
+            if doc.tag(parent) == "p" && BLOCKS.contains(&tag.as_str()) && stack.len() > 1 {
+                stack.pop();
+            }
+            let (siblings, container): (&[&str], &[&str]) = match tag.as_str() {
+                "li" => (&["li"], &["ul", "ol"]),
+                "dt" | "dd" => (&["dt", "dd"], &["dl"]),
+                "tr" => (&["tr"], &["table", "tbody", "thead", "tfoot"]),
+                "td" | "th" => (&["td", "th"], &["tr", "table"]),
+                "option" => (&["option"], &["select"]),
+                _ => (&[], &[]),
+            };
+            let open = stack.iter().rposition(|&id| {
+                siblings.contains(&doc.tag(id)) || container.contains(&doc.tag(id))
+            });
+            if let Some(depth) = open.filter(|&depth| siblings.contains(&doc.tag(stack[depth]))) {
+                stack.truncate(depth.max(1));
+            }
+            let parent = *stack.last().unwrap_or(&0);
+            let raw = matches!(tag.as_str(), "script" | "style" | "title" | "textarea");
+            let void = VOID.contains(&tag.as_str()) || body.ends_with('/');
+            let id = add(&mut doc, parent, Kind::Element { tag, attrs });
+            if raw {
+                let close = format!("</{}", doc.tag(id));
+                let end = find_ignore_case(rest, &close).unwrap_or(rest.len());
+                add(&mut doc, id, Kind::Text(decode_entities(&rest[..end])));
+                rest = &rest[end..];
+                rest = rest.find('>').map_or("", |gt| &rest[gt + 1..]);
+            } else if !void && stack.len() < MAX_DEPTH {
+                stack.push(id);
+            }
+        } else {
+            let end = rest
+                .char_indices()
+                .skip(1)
+                .find(|&(_, c)| c == '<')
+                .map_or(rest.len(), |(i, _)| i);
+            let text = decode_entities(&rest[..end]);
+            let parent = *stack.last().unwrap_or(&0);
+            add(&mut doc, parent, Kind::Text(text));
+            rest = &rest[end..];
+        }
+    }
+    doc
+}
+
+fn unlikely(doc: &Document, id: usize) -> bool {
+    if matches!(doc.tag(id), "html" | "body" | "article" | "main") {
+        return false;
+    }
+    let names = format!(
+        "{} {}",
+        doc.attr(id, "class").unwrap_or(""),
+        doc.attr(id, "id").unwrap_or("")
+    )
+    .to_ascii_lowercase();
+    UNLIKELY.iter().any(|word| names.contains(word))
+        && !LIKELY.iter().any(|word| names.contains(word))
+}
+
+/// Which nodes are dropped, alone or through an ancestor.
+fn skipped(doc: &Document) -> Vec<bool> {
+    let mut skipped = vec![false; doc.nodes.len()];
+    for id in 1..doc.nodes.len() {
+        skipped[id] = skipped[doc.nodes[id].parent]
+            || SKIPPED.contains(&doc.tag(id))
+            || doc.attr(id, "hidden").is_some()
+            || doc.attr(id, "aria-hidden") == Some("true")
+            || unlikely(doc, id);
+    }
+    skipped
+}
+
+/// The element holding the article text.
+fn main_element(doc: &Document, skipped: &[bool]) -> usize {
+    // Visible text length and linked text length of every subtree.
+    let mut text_len = vec![0usize; doc.nodes.len()];
+    let mut link_len = vec![0usize; doc.nodes.len()];
+    for id in (0..doc.nodes.len()).rev() {
+        if skipped[id] {
+            text_len[id] = 0;
+            link_len[id] = 0;
+            continue;
+        }
+        if let Kind::Text(text) = &doc.nodes[id].kind {
+            text_len[id] = text.split_whitespace().map(|w| w.chars().count() + 1).sum();
+        }
+        if doc.tag(id) == "a" {
+            link_len[id] = text_len[id];
+        }
+        if id > 0 {
+            let parent = doc.nodes[id].parent;
+            text_len[parent] += text_len[id];
+            link_len[parent] += link_len[id];
+        }
+    }
+    let biggest = |ids: Vec<usize>| ids.into_iter().max_by_key(|&id| text_len[id]);
+    let landmark =
+        biggest(doc.elements("article").filter(|&id| !skipped[id]).collect()).or_else(|| {
+            biggest(
+                (0..doc.nodes.len())
+                    .filter(|&id| {
+                        !skipped[id]
+                            && (doc.tag(id) == "main" || doc.attr(id, "role") == Some("main"))
+                    })
+                    .collect(),
+            )
+        });
+    if let Some(id) = landmark.filter(|&id| text_len[id] >= 200) {
+        return id;
+    }
+
+    let mut scores = vec![0f64; doc.nodes.len()];
+    for id in 0..doc.nodes.len() {
+        if skipped[id] || !matches!(doc.tag(id), "p" | "pre" | "td" | "blockquote") {
+            continue;
+        }
+        if text_len[id] < 25 {
+            continue;
+        }
+        let text = doc.text(id);
+        let score = 1.0 + text.matches(',').count() as f64 + (text_len[id] as f64 / 100.0).min(3.0);
+        let parent = doc.nodes[id].parent;
+        scores[parent] += score;
+        if parent > 0 {
+            scores[doc.nodes[parent].parent] += score / 2.0;
+        }
+    }
+    (1..doc.nodes.len())
+        .filter(|&id| scores[id] > 0.0)
+        .map(|id| {
+            let density = link_len[id] as f64 / text_len[id].max(1) as f64;
+            (id, scores[id] * (1.0 - density))
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(id, _)| id)
+        .or_else(|| doc.elements("body").next())
+        .unwrap_or(0)
+}
+
+/// Builds Markdown-style blocks from a subtree.
+struct Writer<'a> {
+    doc: &'a Document,
+    skipped: &'a [bool],
+    blocks: Vec<String>,
+    current: String,
+    prefix: String,
+    /// The current block is a list item; consecutive items share a block.
+    item: bool,
+    last_item: bool,
+    lists: usize,
+    quotes: usize,
+}
+
+impl Writer<'_> {
+    fn flush(&mut self) {
+        let lines: Vec<String> = self
+            .current
+            .split('\n')
+            .map(collapse)
+            .filter(|line| !line.is_empty())
+            .collect();
+        self.current.clear();
+        let prefix = std::mem::take(&mut self.prefix);
+        let item = std::mem::take(&mut self.item);
+        if lines.is_empty() {
+            return;
+        }
+        let block = format!("{prefix}{}", lines.join("\n"));
+        let quote = "> ".repeat(self.quotes);
+        let block = if quote.is_empty() {
+            block
+        } else {
+            block
+                .lines()
+                .map(|line| format!("{quote}{line}"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        match self.blocks.last_mut() {
+            Some(last) if item && self.last_item => {
+                last.push('\n');
+                last.push_str(&block);
+            }
+            _ => self.blocks.push(block),
+        }
+        self.last_item = item;
+    }
+
+    fn children(&mut self, id: usize) {
+        for &child in &self.doc.nodes[id].children {
+            self.node(child);
+        }
+    }
+
+    fn node(&mut self, id: usize) {
+        if self.skipped[id] {
+            return;
+        }
+        let tag = match &self.doc.nodes[id].kind {
+            Kind::Text(text) => {
+                self.current.push_str(&text.replace('\n', " "));
+                return;
+            }
+            Kind::Element { tag, .. } => tag.as_str(),
+        };
+        match tag {
+            "br" => self.current.push('\n'),
+            "img" | "title" | "textarea" => {}
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.flush();
+                let level = usize::from(tag.as_bytes()[1] - b'0');
+                self.prefix = format!("{} ", "#".repeat(level));
+                self.children(id);
+                self.flush();
+            }
+            "li" => {
+                self.flush();
+                // Set first: a nested list flushes the item's own text.
+                self.prefix = format!("{}- ", "  ".repeat(self.lists.saturating_sub(1)));
+                self.item = true;
+                self.children(id);
+                self.flush();
+            }
+            "ul" | "ol" => {
+                self.flush();
+                self.lists += 1;
+                self.children(id);
+                self.flush();
+                self.lists -= 1;
+            }
+            "pre" => {
+                self.flush();
+                let mut code = String::new();
+                let mut pending = vec![id];
+                while let Some(node) = pending.pop() {
+                    match &self.doc.nodes[node].kind {
+                        Kind::Text(text) => code.push_str(text),
+                        Kind::Element { tag, .. } if tag == "br" => code.push('\n'),
+                        Kind::Element { .. } => {
+                            pending.extend(self.doc.nodes[node].children.iter().rev())
+                        }
+                    }
+                }
+                let code = code.trim_matches('\n');
+                if !code.trim().is_empty() {
+                    self.blocks.push(format!("```\n{code}\n```"));
+                }
+            }
+            "blockquote" => {
+                self.flush();
+                self.quotes += 1;
+                self.children(id);
+                self.flush();
+                self.quotes -= 1;
+            }
+            "td" | "th" => {
+                self.children(id);
+                self.current.push(' ');
+            }
+            // Rows are lines of one block.
+            "tr" => {
+                self.children(id);
+                self.current.push('\n');
+            }
+            "p" | "div" | "section" | "article" | "main" | "table" | "dl" | "dt" | "dd"
+            | "figure" | "figcaption" | "hr" | "body" => {
+                self.flush();
+                self.children(id);
+                self.flush();
+            }
+            _ => self.children(id),
+        }
+    }
+}
+
+fn title(doc: &Document) -> Option<String> {
+    let meta = doc
+        .elements("meta")
+        .find(|&id| doc.attr(id, "property") == Some("og:title"))
+        .and_then(|id| doc.attr(id, "content"))
+        .map(collapse);
+    let title = || {
+        doc.elements("title").next().map(|id| {
+            let text = doc.text(id);
+            // "Headline | Site Name" keeps the headline.
+            match text.split_once(" | ") {
+                Some((headline, _)) if headline.split_whitespace().count() >= 3 => {
+                    headline.to_string()
+                }
+                _ => text,
+            }
+        })
+    };
+    let heading = || doc.elements("h1").next().map(|id| doc.text(id));
+    meta.or_else(title)
+        .or_else(heading)
+        .filter(|title| !title.is_empty())
+}
+
+/// The title and readable text of an HTML page.
+pub fn article(html: &str) -> Article {
+    let doc = parse(html);
+    let skipped = skipped(&doc);
+    let root = main_element(&doc, &skipped);
+    let mut writer = Writer {
+        doc: &doc,
+        skipped: &skipped,
+        blocks: Vec::new(),
+        current: String::new(),
+        prefix: String::new(),
+        item: false,
+        last_item: false,
+        lists: 0,
+        quotes: 0,
+    };
+    writer.node(root);
+    writer.flush();
+    let mut text = writer.blocks.join("\n\n");
+    if !text.is_empty() {
+        text.push('\n');
+    }
+    Article {
+        title: title(&doc),
+        text,
+    }
+}
@@ -0,0 +1,212 @@
+//! Minimal HTTP/1.1 client for local backends, built on `TcpStream`.
+//!
+//! Plain `http://` only; TLS needs a dependency this crate does not take.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use crate::error::{Error, Result};
+
+/// Response bodies larger than this are refused, whatever the server
+/// claims.
+pub const MAX_BODY: usize = 64 * 1024 * 1024;
+
+/// Parsed `http://host[:port]/path` URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Url {
+    pub host: String,
+    pub port: u16,
+    pub path: String,
+}
+
+impl Url {
+    pub fn parse(url: &str) -> Result<Url> {
+        let rest = url
+            .strip_prefix("http://")
+            .ok_or_else(|| Error::Unsupported(format!("only http:// URLs are supported: {url}")))?;
+        let (authority, path) = match rest.find('/') {
+            Some(slash) => (&rest[..slash], &rest[slash..]),
+            None => (rest, "/"),
+        };
+        // Bracketed IPv6 literals contain colons of their own.
+        let split = match authority.strip_prefix('[') {
+            Some(v6) => v6
+                .split_once(']')
+                .map(|(host, rest)| (host, rest.strip_prefix(':'))),
+            None => Some(match authority.split_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (authority, None),
+            }),
+        };
+        let (host, port) = split.ok_or_else(|| Error::Parse(format!("bad host in {url}")))?;
+        let port = match port {
+            Some(port) => port
+                .parse()
+                .map_err(|_| Error::Parse(format!("bad port in {url}")))?,
+            None => 80,
+        };
+        if host.is_empty() {
+            return Err(Error::Parse(format!("no host in {url}")));
+        }
+        Ok(Url {
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+        })
+    }
+
+    /// Whether the host is this machine, so plain HTTP to it never crosses
+    /// a network.
+    pub fn is_loopback(&self) -> bool {
+        self.host.eq_ignore_ascii_case("localhost")
+            || self.host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+    }
+
+    /// Joined with a path relative to the server root.
+    pub fn join(&self, path: &str) -> Url {
+        Url {
+            path: format!(
+                "{}/{}",
+                self.path.trim_end_matches('/'),
+                path.trim_start_matches('/')
+            ),
+            ..self.clone()
+        }
+    }
+}
+
+/// Extra request settings.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RequestOptions {
+    /// Sent after the standard headers, e.g. `Authorization`.
+    pub headers: Vec<(String, String)>,
+    /// Forward proxy to send the request through.
+    pub proxy: Option<Url>,
+    pub timeout: Option<Duration>,
+}
+
+/// Whether `text` is safe to place in a header line.
+pub fn valid_header(name: &str, value: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_!#$%&'*+.^`|~".contains(c))
+        && !value.contains(['\r', '\n'])
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub body: String,
+}
+
+/// POST `body` as JSON to `url` and read the whole response.
+pub fn post_json(url: &Url, body: &str, options: &RequestOptions) -> Result<Response> {
+    let timeout = options.timeout;
+    let server = options.proxy.as_ref().unwrap_or(url);
+    let addr = (server.host.as_str(), server.port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| Error::Backend(format!("cannot resolve {}", server.host)))?;
+    let mut stream = match timeout {
+        Some(timeout) => TcpStream::connect_timeout(&addr, timeout)?,
+        None => TcpStream::connect(addr)?,
+    };
+    stream.set_read_timeout(timeout)?;
+    stream.set_write_timeout(timeout)?;
+    let host = if url.host.contains(':') {
+        format!("[{}]", url.host)
+    } else {
+        url.host.clone()
+    };
+    // Proxies take the absolute URL as the request target.
+    let target = match options.proxy {
+        Some(_) => format!("http://{host}:{}{}", url.port, url.path),
+        None => url.path.clone(),
+    };
+    let mut request = format!(
+        "POST {target} HTTP/1.1\r\nHost: {host}:{}\r\nContent-Type: application/json\r\n\
+         Accept: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+        url.port,
+        body.len()
+    );
+    for (name, value) in &options.headers {
+        if !valid_header(name, value) {
+            return Err(Error::Parse(format!("invalid header '{name}'")));
+        }
+        request.push_str(&format!("{name}: {value}\r\n"));
+    }
+    request.push_str("\r\n");
+    request.push_str(body);
+    stream.write_all(request.as_bytes())?;
+    read_response(BufReader::new(stream))
+}
+
+fn read_response(mut reader: impl BufRead) -> Result<Response> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let status = line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| Error::Backend(format!("bad HTTP status line: {}", line.trim())))?;
+
+    let mut chunked = false;
+    let mut length: Option<usize> = None;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            let value = value.trim();
+            match name.trim().to_ascii_lowercase().as_str() {
+                "transfer-encoding" => chunked = value.eq_ignore_ascii_case("chunked"),
+                "content-length" => length = value.parse().ok(),
+                _ => {}
+            }
+        }
+    }
+
+    let mut body = Vec::new();
+    if chunked {
+        loop {
+            line.clear();
+            reader.read_line(&mut line)?;
+            let size = usize::from_str_radix(line.trim().split(';').next().unwrap_or(""), 16)
+                .map_err(|_| Error::Backend("bad chunk size".into()))?;
+            if size == 0 {
+                break;
+            }
+            let start = body.len();
+            let end = start
+                .checked_add(size)
+                .filter(|end| *end <= MAX_BODY)
+                .ok_or_else(too_large)?;
+            body.resize(end, 0);
+            reader.read_exact(&mut body[start..])?;
+            line.clear();
+            reader.read_line(&mut line)?;
+        }
+    } else if let Some(length) = length {
+        if length > MAX_BODY {
+            return Err(too_large());
+        }
+        body.resize(length, 0);
+        reader.read_exact(&mut body)?;
+    } else {
+        reader.take(MAX_BODY as u64 + 1).read_to_end(&mut body)?;
+        if body.len() > MAX_BODY {
+            return Err(too_large());
+        }
+    }
+    Ok(Response {
+        status,
+        body: String::from_utf8_lossy(&body).into_owned(),
+    })
+}
+
+fn too_large() -> Error {
+    Error::Backend(format!("response body is over {MAX_BODY} bytes"))
+}
@@ -0,0 +1,303 @@
+//! DEFLATE decompression (RFC 1951), for zip entries and PDF streams.
+//!
+//! Output is capped so a small, highly compressed input cannot exhaust
+//! memory; going over the cap is a parse error.
+
+use crate::error::{Error, Result};
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// The order code length code lengths are sent in.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+fn corrupt(what: &str) -> Error {
+    Error::Parse(format!("corrupt deflate data: {what}"))
+}
+
+struct Bits<'a> {
+    data: &'a [u8],
+    pos: usize,
+    buffer: u64,
+    count: u32,
+}
+
+impl Bits<'_> {
+    fn refill(&mut self) {
+        while self.count <= 56 {
+            let byte = match self.data.get(self.pos) {
+                Some(&byte) => byte,
+                None => return,
+            };
+            self.buffer |= u64::from(byte) << self.count;
+            self.pos += 1;
+            self.count += 8;
+        }
+    }
+
+    fn take(&mut self, n: u32) -> Result<u32> {
+        if n == 0 {
+            return Ok(0);
+        }
+        if self.count < n {
+            self.refill();
+            if self.count < n {
+                return Err(corrupt("unexpected end"));
+            }
+        }
+        let value = (self.buffer & ((1u64 << n) - 1)) as u32;
+        self.buffer >>= n;
+        self.count -= n;
+        Ok(value)
+    }
+
+    /// Drop the bits left in the current byte.
+    fn align(&mut self) {
+        let partial = self.count % 8;
+        self.buffer >>= partial;
+        self.count -= partial;
+    }
+
+    fn bytes(&mut self, n: usize) -> Result<&[u8]> {
+        // Hand back buffered whole bytes to the input first.
+        let buffered = (self.count / 8) as usize;
+        self.pos -= buffered;
+        self.buffer = 0;
+        self.count = 0;
+        let end = self
+            .pos
+            .checked_add(n)
+            .filter(|&end| end <= self.data.len())
+            .ok_or_else(|| corrupt("stored block past the end"))?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+}
+
+/// A canonical Huffman code, decoded a bit at a time.
+struct Huffman {
+    /// Codes of each length, 0 to 15.
+    counts: [u16; 16],
+    /// Symbols ordered by code.
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Huffman> {
+        let mut counts = [0u16; 16];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+        counts[0] = 0;
+        // Over-subscribed codes are invalid; incomplete ones are allowed.
+        let mut left = 1i32;
+        for &count in &counts[1..] {
+            left = (left << 1) - i32::from(count);
+            if left < 0 {
+                return Err(corrupt("over-subscribed code"));
+            }
+        }
+        let mut offsets = [0u16; 16];
+        for len in 1..15 {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0u16; lengths.len()];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+        Ok(Huffman { counts, symbols })
+    }
+
+    fn decode(&self, bits: &mut Bits) -> Result<u16> {
+        let mut code = 0i32;
+        let mut first = 0i32;
+        let mut index = 0i32;
+        for len in 1..16 {
+            code |= bits.take(1)? as i32;
+            let count = i32::from(self.counts[len]);
+            if code - count < first {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(corrupt("invalid code"))
+    }
+}
+
+fn fixed_codes() -> Result<(Huffman, Huffman)> {
+    let mut lengths = [0u8; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+    Ok((Huffman::new(&lengths)?, Huffman::new(&[5; 30])?))
+}
+
+fn dynamic_codes(bits: &mut Bits) -> Result<(Huffman, Huffman)> {
+    let literals = bits.take(5)? as usize + 257;
+    let distances = bits.take(5)? as usize + 1;
+    let code_lengths = bits.take(4)? as usize + 4;
+    if literals > 286 || distances > 30 {
+        return Err(corrupt("too many codes"));
+    }
+    let mut lengths = [0u8; 19];
+    for &index in &CODE_LENGTH_ORDER[..code_lengths] {
+        lengths[index] = bits.take(3)? as u8;
+    }
+    let code_length_code = Huffman::new(&lengths)?;
+    let mut lengths = vec![0u8; literals + distances];
+    let mut i = 0;
+    while i < lengths.len() {
+        let symbol = code_length_code.decode(bits)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *lengths[..i]
+                    .last()
+                    .ok_or_else(|| corrupt("repeat with no previous length"))?;
+                (previous, 3 + bits.take(2)? as usize)
+            }
+            17 => (0, 3 + bits.take(3)? as usize),
+            _ => (0, 11 + bits.take(7)? as usize),
+        };
+        if i + repeat > lengths.len() {
+            return Err(corrupt("lengths past the end"));
+        }
+        lengths[i..i + repeat].fill(value);
+        i += repeat;
+    }
+    if lengths[256] == 0 {
+        return Err(corrupt("no end-of-block code"));
+    }
+    Ok((
+        Huffman::new(&lengths[..literals])?,
+        Huffman::new(&lengths[literals..])?,
+    ))
+}
+
+/// Decompress raw DEFLATE `data`, failing past `limit` bytes of output.
+pub fn inflate(data: &[u8], limit: usize) -> Result<Vec<u8>> {
+    let mut bits = Bits {
+        data,
+        pos: 0,
+        buffer: 0,
+        count: 0,
+    };
+    let mut out: Vec<u8> = Vec::with_capacity(data.len().saturating_mul(3).min(limit));
+    loop {
+        let last = bits.take(1)? == 1;
+        match bits.take(2)? {
+            0 => {
+                bits.align();
+                let header = bits.bytes(4)?;
+                let len = u16::from_le_bytes([header[0], header[1]]);
+                let check = u16::from_le_bytes([header[2], header[3]]);
+                if len != !check {
+                    return Err(corrupt("stored block length"));
+                }
+                if out.len() + len as usize > limit {
+                    return Err(too_large(limit));
+                }
+                out.extend_from_slice(bits.bytes(len as usize)?);
+            }
+            kind @ (1 | 2) => {
+                let (literal, distance) = if kind == 1 {
+                    fixed_codes()?
+                } else {
+                    dynamic_codes(&mut bits)?
+                };
+                codes(&mut bits, &literal, &distance, &mut out, limit)?;
+            }
+            _ => return Err(corrupt("reserved block type")),
+        }
+        if last {
+            return Ok(out);
+        }
+    }
+}
+
+fn too_large(limit: usize) -> Error {
+    Error::Parse(format!("decompressed data is larger than {limit} bytes"))
+}
+
+fn codes(
+    bits: &mut Bits,
+    literal: &Huffman,
+    distance: &Huffman,
+    out: &mut Vec<u8>,
+    limit: usize,
+) -> Result<()> {
+    loop {
+        let symbol = literal.decode(bits)?;
+        match symbol {
+            0..=255 => {
+                if out.len() >= limit {
+                    return Err(too_large(limit));
+                }
+                out.push(symbol as u8);
+            }
+            256 => return Ok(()),
+            _ => {
+                let index = usize::from(symbol - 257);
+                let (&base, &extra) = LENGTH_BASE
+                    .get(index)
+                    .zip(LENGTH_EXTRA.get(index))
+                    .ok_or_else(|| corrupt("invalid length"))?;
+                let len = usize::from(base) + bits.take(u32::from(extra))? as usize;
+                let index = usize::from(distance.decode(bits)?);
+                let (&base, &extra) = DISTANCE_BASE
+                    .get(index)
+                    .zip(DISTANCE_EXTRA.get(index))
+                    .ok_or_else(|| corrupt("invalid distance"))?;
+                let back = usize::from(base) + bits.take(u32::from(extra))? as usize;
+                if back > out.len() {
+                    return Err(corrupt("distance before the start"));
+                }
+                if out.len() + len > limit {
+                    return Err(too_large(limit));
+                }
+                let start = out.len() - back;
+                // Copies may overlap their own output, so go byte by byte.
+                for i in 0..len {
+                    out.push(out[start + i]);
+                }
+            }
+        }
+    }
+}
+
+/// Decompress a zlib stream (RFC 1950), as PDF's FlateDecode holds. The
+/// trailing checksum is not verified, since damaged PDFs often lack it.
+pub fn inflate_zlib(data: &[u8], limit: usize) -> Result<Vec<u8>> {
+    match data {
+        [cmf, flg, rest @ ..]
+            if cmf & 0x0f == 8 && (u16::from(*cmf) << 8 | u16::from(*flg)) % 31 == 0 =>
+        {
+            if flg & 0x20 != 0 {
+                return Err(Error::Unsupported("zlib preset dictionary".into()));
+            }
+            inflate(rest, limit)
+        }
+        _ => Err(corrupt("not a zlib stream")),
+    }
+}
@@ -0,0 +1,186 @@
+//! Web pages and pasted text as documents.
+//!
+//! [`extract_url`] fetches a page with the system `curl` (which brings TLS
+//! and redirects) and extracts it like a file of the same type; HTML is
+//! reduced to its article text. [`ingest_text`] saves text as a Markdown
+//! file, so everything that takes document paths — projects, comparison,
+//! minutes — works on it unchanged.
+
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::chat::strip_ansi;
+use crate::error::{Error, Result};
+use crate::extract::{extract_from_bytes_fallible, Format};
+use crate::hash::sha256_hex;
+use crate::shutdown::Process;
+use crate::telemetry;
+
+/// Pages larger than this are refused.
+pub const MAX_FETCH_BYTES: u64 = 50 * 1024 * 1024;
+const FETCH_TIMEOUT_SECS: u32 = 30;
+
+static DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+static FETCHES: AtomicU64 = AtomicU64::new(0);
+
+/// Where [`ingest_text`] writes; defaults to `synth-ingest` in the temp dir.
+pub fn set_dir(dir: Option<PathBuf>) {
+    *DIR.lock().unwrap_or_else(|e| e.into_inner()) = dir;
+}
+
+pub fn dir() -> PathBuf {
+    DIR.lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .unwrap_or_else(|| std::env::temp_dir().join("synth-ingest"))
+}
+
+/// The format for a response, from its media type or else the URL's
+/// extension.
+pub fn format_for(content_type: &str, url: &str) -> Option<Format> {
+    let media = content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase();
+    let format = match media.as_str() {
+        "text/html" | "application/xhtml+xml" => Some(Format::Html),
+        "text/plain" | "text/markdown" | "text/x-markdown" => Some(Format::Text),
+        "text/csv" => Some(Format::Csv),
+        "text/tab-separated-values" => Some(Format::Tsv),
+        "application/json" => Some(Format::Json),
+        "application/xml" | "text/xml" => Some(Format::Xml),
+        "application/yaml" | "application/x-yaml" | "text/yaml" => Some(Format::Yaml),
+        _ if media.ends_with("+json") => Some(Format::Json),
+        _ if media.ends_with("+xml") => Some(Format::Xml),
+        _ => None,
+    };
+    format.or_else(|| {
+        let path = url.split(['?', '#']).next().unwrap_or(url);
+        Format::from_path(Path::new(path))
+    })
+}
+
+/// GET `url` (http or https, following redirects). Returns the body and
+/// the `Content-Type` of the final response.
+pub fn fetch(url: &str) -> Result<(Vec<u8>, String)> {
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        return Err(Error::Unsupported(format!(
+            "only http:// and https:// URLs can be fetched: {url}"
+        )));
+    }
+    let body_path = std::env::temp_dir().join(format!(
+        "synth-fetch-{}-{}",
+        std::process::id(),
+        FETCHES.fetch_add(1, Ordering::Relaxed)
+    ));
+    let child = Process::spawn(
+        Command::new("curl")
+            .args([
+                "--fail",
+                "--location",
+                "--silent",
+                "--show-error",
+                "--compressed",
+            ])
+            .args(["--proto", "=http,https", "--proto-redir", "=http,https"])
+            .args(["--max-redirs", "5", "--max-time"])
+            .arg(FETCH_TIMEOUT_SECS.to_string())
+            .arg("--max-filesize")
+            .arg(MAX_FETCH_BYTES.to_string())
+            .args(["--write-out", "%{content_type}", "-o"])
+            .arg(&body_path)
+            .arg("--")
+            .arg(url)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped()),
+    )?;
+    let mut content_type = String::new();
+    let mut message = String::new();
+    if let Some(mut stdout) = child.take_stdout() {
+        let _ = stdout.read_to_string(&mut content_type);
+    }
+    if let Some(mut stderr) = child.take_stderr() {
+        let _ = stderr.read_to_string(&mut message);
+    }
+    let status = child.wait()?;
+    let body = fs::read(&body_path);
+    let _ = fs::remove_file(&body_path);
+    if !status.success() {
+        return Err(Error::Backend(format!(
+            "fetching {url} failed: {}",
+            message.trim()
+        )));
+    }
+    Ok((body?, content_type.trim().to_string()))
+}
+
+/// Fetch a page and extract its text; HTML pages are reduced to their
+/// article, with the title as a heading.
+pub fn extract_url(url: &str) -> Result<String> {
+    telemetry::measure("extract", || {
+        let (body, content_type) = fetch(url)?;
+        let format = format_for(&content_type, url)
+            .ok_or_else(|| Error::Unsupported(format!("{url} ({content_type})")))?;
+        extract_from_bytes_fallible(&body, format)
+    })
+}
+
+/// Lowercase ASCII words of `title` joined by dashes, at most 60 bytes.
+fn slug(title: &str) -> String {
+    let mut slug = String::new();
+    for word in title
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty())
+    {
+        if slug.len() + word.len() + 1 > 60 {
+            break;
+        }
+        if !slug.is_empty() {
+            slug.push('-');
+        }
+        slug.push_str(&word.to_ascii_lowercase());
+    }
+    if slug.is_empty() {
+        slug.push_str("pasted");
+    }
+    slug
+}
+
+/// Save pasted or fetched text as a Markdown document and return its
+/// path. The title becomes a `#` heading unless the text already starts
+/// with a heading. Terminal escapes and `\r\n` line endings are cleaned
+/// up; ingesting the same title and text again returns the same file.
+pub fn ingest_text(text: &str, title: &str) -> Result<PathBuf> {
+    let title = title.trim();
+    let text = strip_ansi(&text.replace("\r\n", "\n"));
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(Error::Parse("nothing to ingest: the text is empty".into()));
+    }
+    let document = if title.is_empty() || text.starts_with("# ") {
+        format!("{text}\n")
+    } else {
+        format!("# {title}\n\n{text}\n")
+    };
+    let dir = dir();
+    fs::create_dir_all(&dir)?;
+    let hash = sha256_hex(document.as_bytes());
+    let name = match title {
+        "" => text.lines().next().unwrap_or(""),
+        title => title,
+    };
+    let path = dir.join(format!("{}-{}.md", slug(name), &hash[..12]));
+    if !path.is_file() {
+        let tmp = path.with_extension("md.tmp");
+        fs::write(&tmp, &document)?;
+        fs::rename(&tmp, &path)?;
+    }
+    Ok(path)
+}
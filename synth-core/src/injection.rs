@@ -0,0 +1,303 @@
+//! Scanning document text for likely prompt-injection content before it is
+//! pasted into a prompt.
+//!
+//! Plain-text extraction loses formatting, so text hidden by styling is
+//! only caught when the styling survives as markup (HTML comments, inline
+//! `display:none` or white-text styles).
+//!
+//! Phrases are matched with invisible and format characters (zero-width
+//! and bidi controls, soft hyphens, combining marks, variation selectors)
+//! taken out, so `ig\u{200B}nore` still reads as "ignore"; the findings
+//! give offsets in the original text.
+
+use std::ops::Range;
+
+use crate::json::Value;
+use crate::words::word_spans;
+
+/// Instruction-like phrases. `*` matches up to two arbitrary words.
+const PHRASES: &[&str] = &[
+    "ignore * previous instructions",
+    "ignore * prior instructions",
+    "ignore * above instructions",
+    "ignore * instructions above",
+    "disregard * previous instructions",
+    "disregard * prior instructions",
+    "disregard * above",
+    "forget * previous instructions",
+    "forget everything",
+    "override * instructions",
+    "new instructions",
+    "you are now",
+    "from now on you",
+    "pretend to be",
+    "reveal * system prompt",
+    "print * system prompt",
+    "your system prompt",
+    "do not tell the user",
+    "don't tell the user",
+];
+
+/// Inline styles that hide text when rendered.
+const HIDING_STYLES: &[&str] = &[
+    "display:none",
+    "visibility:hidden",
+    "font-size:0",
+    "color:white",
+    "color:#fff",
+    "color:#ffffff",
+    "opacity:0",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// Text addressed to the model rather than the reader.
+    Instruction,
+    /// Zero-width, bidi-control or tag characters.
+    InvisibleCharacter,
+    /// Markup that hides its content when rendered.
+    HiddenMarkup,
+}
+
+impl Kind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::Instruction => "instruction",
+            Kind::InvisibleCharacter => "invisible_character",
+            Kind::HiddenMarkup => "hidden_markup",
+        }
+    }
+}
+
+/// A suspicious span, in characters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub kind: Kind,
+    pub range: Range<usize>,
+}
+
+pub fn findings_to_json(text: &str, findings: &[Finding]) -> Value {
+    let chars: Vec<char> = text.chars().collect();
+    Value::Array(
+        findings
+            .iter()
+            .map(|f| {
+                let excerpt: String = chars[f.range.clone()]
+                    .iter()
+                    .map(|c| if is_invisible(*c) { '\u{FFFD}' } else { *c })
+                    .take(120)
+                    .collect();
+                Value::object([
+                    ("kind", Value::from(f.kind.name())),
+                    ("start", Value::from(f.range.start)),
+                    ("end", Value::from(f.range.end)),
+                    ("excerpt", Value::from(excerpt)),
+                ])
+            })
+            .collect(),
+    )
+}
+
+fn is_invisible(c: char) -> bool {
+    matches!(c,
+        '\u{200B}'..='\u{200F}'
+        | '\u{202A}'..='\u{202E}'
+        | '\u{2060}'..='\u{2064}'
+        | '\u{2066}'..='\u{2069}'
+        | '\u{FEFF}'
+        | '\u{E0000}'..='\u{E007F}')
+}
+
+/// Characters that change nothing a reader sees in a word: the invisible
+/// ones, soft hyphens, combining marks and variation selectors.
+fn is_ignorable(c: char) -> bool {
+    is_invisible(c)
+        || matches!(c,
+            '\u{00AD}'
+            | '\u{034F}'
+            | '\u{061C}'
+            | '\u{180E}'
+            | '\u{0300}'..='\u{036F}'
+            | '\u{1AB0}'..='\u{1AFF}'
+            | '\u{1DC0}'..='\u{1DFF}'
+            | '\u{20D0}'..='\u{20FF}'
+            | '\u{FE00}'..='\u{FE0F}'
+            | '\u{FE20}'..='\u{FE2F}'
+            | '\u{E0100}'..='\u{E01EF}')
+}
+
+/// Suspicious spans in `text`, ordered by start offset. Findings of
+/// different kinds may overlap.
+pub fn scan(text: &str) -> Vec<Finding> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut findings = Vec::new();
+
+    let mut i = 0;
+    while i < chars.len() {
+        if is_invisible(chars[i]) {
+            let start = i;
+            while i < chars.len() && is_invisible(chars[i]) {
+                i += 1;
+            }
+            findings.push(Finding {
+                kind: Kind::InvisibleCharacter,
+                range: start..i,
+            });
+        } else {
+            i += 1;
+        }
+    }
+
+    findings.extend(hidden_markup(&chars));
+
+    // Match against the visible letters, remembering where each one is in
+    // `text`.
+    let (visible, origin): (String, Vec<usize>) = chars
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| !is_ignorable(**c))
+        .map(|(i, c)| (*c, i))
+        .unzip();
+    let spans = word_spans(&visible);
+    let words: Vec<(usize, String)> = spans
+        .iter()
+        .map(|(span, word)| (span.start, word.clone()))
+        .collect();
+    for phrase in PHRASES {
+        let pattern: Vec<&str> = phrase.split(' ').collect();
+        for start in 0..words.len() {
+            if let Some(end) = match_at(&pattern, &words[start..]) {
+                let range =
+                    origin[spans[start].0.start]..origin[spans[start + end - 1].0.end - 1] + 1;
+                // Only another instruction or hidden markup already covers
+                // it; an invisible character inside must not hide it.
+                if !findings
+                    .iter()
+                    .any(|f| f.kind != Kind::InvisibleCharacter && overlaps(&f.range, &range))
+                {
+                    findings.push(Finding {
+                        kind: Kind::Instruction,
+                        range,
+                    });
+                }
+            }
+        }
+    }
+
+    findings.sort_by_key(|f| f.range.start);
+    findings
+}
+
+fn overlaps(a: &Range<usize>, b: &Range<usize>) -> bool {
+    a.start < b.end && b.start < a.end
+}
+
+/// Number of words matched by `pattern` at the start of `words`.
+fn match_at(pattern: &[&str], words: &[(usize, String)]) -> Option<usize> {
+    match pattern.split_first() {
+        None => Some(0),
+        Some((&"*", rest)) => (0..=2.min(words.len()))
+            .find_map(|skip| match_at(rest, &words[skip..]).map(|n| n + skip)),
+        Some((word, rest)) => {
+            let first = words.first()?;
+            let same = first.1 == *word || first.1.replace('’', "'") == *word;
+            if same {
+                match_at(rest, &words[1..]).map(|n| n + 1)
+            } else {
+                None
+            }
+        }
+    }
+}
+
+/// HTML comments, and elements whose inline style hides them.
+fn hidden_markup(chars: &[char]) -> Vec<Finding> {
+    let text: String = chars.iter().collect();
+    // ASCII lowercasing keeps byte offsets valid in `text`.
+    let lower = text.to_ascii_lowercase();
+    let to_char = |byte: usize| text[..byte].chars().count();
+    let mut findings = Vec::new();
+    let mut rest = 0;
+    while let Some(open) = lower[rest..].find("<!--") {
+        let open = rest + open;
+        let close = lower[open..]
+            .find("-->")
+            .map_or(lower.len(), |n| open + n + 3);
+        findings.push(Finding {
+            kind: Kind::HiddenMarkup,
+            range: to_char(open)..to_char(close),
+        });
+        rest = close;
+    }
+    let mut rest = 0;
+    while let Some(style) = lower[rest..].find("style=") {
+        let style = rest + style;
+        let tag_start = lower[..style].rfind('<');
+        let tag_end = lower[style..].find('>').map(|n| style + n);
+        rest = style + 6;
+        let (Some(tag_start), Some(tag_end)) = (tag_start, tag_end) else {
+            continue;
+        };
+        let compact: String = lower[style..tag_end]
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect();
+        if !HIDING_STYLES.iter().any(|s| compact.contains(s)) {
+            continue;
+        }
+        let name: String = lower[tag_start + 1..]
+            .chars()
+            .take_while(|c| c.is_alphanumeric())
+            .collect();
+        let close_tag = format!("</{name}>");
+        let end = lower[tag_end..]
+            .find(&close_tag)
+            .map_or(tag_end + 1, |n| tag_end + n + close_tag.len());
+        findings.push(Finding {
+            kind: Kind::HiddenMarkup,
+            range: to_char(tag_start)..to_char(end),
+        });
+        rest = end;
+    }
+    findings
+}
+
+/// `text` with invisible characters removed and instruction or hidden
+/// spans replaced by a visible marker. The result is scanned again, so a
+/// phrase that only reads as one once characters are stripped is replaced
+/// too, whatever `findings` held.
+pub fn neutralize(text: &str, findings: &[Finding]) -> String {
+    let mut out = apply(text, findings);
+    loop {
+        let left: Vec<Finding> = scan(&out)
+            .into_iter()
+            .filter(|f| f.kind != Kind::InvisibleCharacter)
+            .collect();
+        let next = apply(&out, &left);
+        if left.is_empty() || next == out {
+            return out;
+        }
+        out = next;
+    }
+}
+
+fn apply(text: &str, findings: &[Finding]) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut at = 0;
+    for finding in findings {
+        if finding.range.start < at {
+            continue;
+        }
+        out.extend(&chars[at..finding.range.start]);
+        match finding.kind {
+            Kind::InvisibleCharacter => {}
+            Kind::Instruction | Kind::HiddenMarkup => {
+                out.push_str("[removed: possible prompt injection]")
+            }
+        }
+        at = finding.range.end;
+    }
+    out.extend(&chars[at..]);
+    out
+}
@@ -0,0 +1,535 @@
+//! Background job queue for long-running pipelines.
+//!
+//! Jobs run one at a time on a worker thread and can be polled from any
+//! thread. When a state directory is configured each job is persisted as
+//! `<dir>/<id>.json`; jobs that were queued or running when the process
+//! exited are queued again (from the start) on the next [`init`].
+//!
+//! Cancelling a running job kills the backend process it is waiting on;
+//! a job that panics fails with the panic message and the worker carries
+//! on with the next.
+//!
+//! Finished, failed and cancelled jobs are sent to the configured webhook
+//! as `job.done`, `job.failed` and `job.cancelled` with the job's status.
+//! Jobs cancelled by a [`shutdown`](crate::shutdown) are saved as
+//! cancelled but not sent, since the host is quitting.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::{Condvar, Mutex, MutexGuard, OnceLock};
+
+use crate::error::{panic_message, Error, Result};
+use crate::json::{self, Value};
+use crate::progress::{Progress, Stage};
+use crate::shutdown;
+use crate::store;
+use crate::{
+    chat, classify, compare, dedup, extract, minutes, summarize, telemetry, template, translate,
+    webhook,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    Queued,
+    Running,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+impl State {
+    pub fn name(self) -> &'static str {
+        match self {
+            State::Queued => "queued",
+            State::Running => "running",
+            State::Done => "done",
+            State::Failed => "failed",
+            State::Cancelled => "cancelled",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        [
+            State::Queued,
+            State::Running,
+            State::Done,
+            State::Failed,
+            State::Cancelled,
+        ]
+        .into_iter()
+        .find(|s| s.name() == name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Job {
+    pub id: u64,
+    pub kind: String,
+    pub params: Value,
+    pub state: State,
+    /// Latest stage report: stage, `(completed, total)` within it, and
+    /// overall percent.
+    pub stage: Option<Stage>,
+    pub progress: (usize, usize),
+    pub percent: f64,
+    pub result: Option<String>,
+    pub error: Option<String>,
+}
+
+impl Job {
+    /// Status without the result payload.
+    pub fn status_json(&self) -> Value {
+        let mut pairs = vec![
+            ("id", Value::from(self.id as f64)),
+            ("kind", Value::from(self.kind.as_str())),
+            ("state", Value::from(self.state.name())),
+            (
+                "progress",
+                Value::object([
+                    ("completed", Value::from(self.progress.0)),
+                    ("total", Value::from(self.progress.1)),
+                    ("percent", Value::from(self.percent.round())),
+                ]),
+            ),
+        ];
+        if let Some(stage) = self.stage {
+            pairs.push(("stage", Value::from(stage.name())));
+        }
+        if let Some(error) = &self.error {
+            pairs.push(("error", Value::from(error.as_str())));
+        }
+        Value::object(pairs)
+    }
+
+    fn to_json(&self) -> Value {
+        let mut value = self.status_json();
+        if let Value::Object(pairs) = &mut value {
+            pairs.push(("params".into(), self.params.clone()));
+            if let Some(result) = &self.result {
+                pairs.push(("result".into(), Value::from(result.as_str())));
+            }
+        }
+        value
+    }
+
+    fn from_json(value: &Value) -> Option<Job> {
+        let count = |key| {
+            value
+                .get("progress")
+                .and_then(|p| p.get(key))
+                .and_then(Value::as_f64)
+                .unwrap_or(0.0) as usize
+        };
+        Some(Job {
+            id: value.get("id")?.as_f64()? as u64,
+            kind: value.get("kind")?.as_str()?.to_string(),
+            params: value.get("params").cloned().unwrap_or(Value::Null),
+            state: State::from_name(value.get("state")?.as_str()?)?,
+            stage: None,
+            progress: (count("completed"), count("total")),
+            percent: value
+                .get("progress")
+                .and_then(|p| p.get("percent"))
+                .and_then(Value::as_f64)
+                .unwrap_or(0.0),
+            result: value
+                .get("result")
+                .and_then(Value::as_str)
+                .map(String::from),
+            error: value.get("error").and_then(Value::as_str).map(String::from),
+        })
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    dir: Option<PathBuf>,
+    jobs: BTreeMap<u64, Job>,
+    pending: VecDeque<u64>,
+    /// The id the next job gets; ids are never reused.
+    next_id: u64,
+    worker_started: bool,
+}
+
+struct Queue {
+    inner: Mutex<Inner>,
+    wake: Condvar,
+}
+
+fn queue() -> &'static Queue {
+    static QUEUE: OnceLock<Queue> = OnceLock::new();
+    QUEUE.get_or_init(|| Queue {
+        inner: Mutex::new(Inner::default()),
+        wake: Condvar::new(),
+    })
+}
+
+fn lock() -> MutexGuard<'static, Inner> {
+    // A panic inside a job must not take the whole queue down with it.
+    queue().inner.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Persist jobs under `dir` and reload the ones saved there by an earlier
+/// run. Unfinished jobs are queued again.
+pub fn init(dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir)?;
+    let mut loaded = Vec::new();
+    for name in store::list(dir)? {
+        if !name.ends_with(".json") {
+            continue;
+        }
+        let Some(text) = store::read_string(&dir.join(name))? else {
+            continue;
+        };
+        if let Some(job) = json::parse(&text).ok().as_ref().and_then(Job::from_json) {
+            loaded.push(job);
+        }
+    }
+    let mut inner = lock();
+    inner.dir = Some(dir.to_path_buf());
+    if let Some(last) = loaded.iter().map(|job| job.id).max() {
+        inner.next_id = inner.next_id.max(last + 1);
+    }
+    for mut job in loaded {
+        if inner.jobs.contains_key(&job.id) {
+            continue;
+        }
+        if matches!(job.state, State::Queued | State::Running) {
+            job.state = State::Queued;
+            job.stage = None;
+            job.progress = (0, 0);
+            job.percent = 0.0;
+            inner.pending.push_back(job.id);
+        }
+        inner.jobs.insert(job.id, job);
+    }
+    let snapshot: Vec<Job> = inner.jobs.values().cloned().collect();
+    for job in &snapshot {
+        save(&inner, job);
+    }
+    if !inner.pending.is_empty() {
+        start_worker(&mut inner);
+    }
+    queue().wake.notify_one();
+    Ok(())
+}
+
+/// Queue a job and return its id. See [`run`] for the kinds and params.
+pub fn submit(kind: &str, params: Value) -> Result<u64> {
+    if !KINDS.contains(&kind) {
+        return Err(Error::Unsupported(format!("job kind '{kind}'")));
+    }
+    let mut inner = lock();
+    let id = inner.next_id.max(1);
+    inner.next_id = id + 1;
+    let job = Job {
+        id,
+        kind: kind.to_string(),
+        params,
+        state: State::Queued,
+        stage: None,
+        progress: (0, 0),
+        percent: 0.0,
+        result: None,
+        error: None,
+    };
+    save(&inner, &job);
+    inner.jobs.insert(id, job);
+    inner.pending.push_back(id);
+    start_worker(&mut inner);
+    queue().wake.notify_one();
+    Ok(id)
+}
+
+pub fn status(id: u64) -> Option<Job> {
+    lock().jobs.get(&id).cloned()
+}
+
+/// Cancel a queued or running job. A running job's backend process is
+/// killed, so it stops at its current step, and its result is discarded.
+pub fn cancel(id: u64) -> bool {
+    let mut inner = lock();
+    let Some(job) = inner.jobs.get_mut(&id) else {
+        return false;
+    };
+    if !matches!(job.state, State::Queued | State::Running) {
+        return false;
+    }
+    let running = job.state == State::Running;
+    job.state = State::Cancelled;
+    let job = job.clone();
+    inner.pending.retain(|pending| *pending != id);
+    save(&inner, &job);
+    drop(inner);
+    if running {
+        shutdown::kill_task(id);
+    }
+    notify(&job);
+    true
+}
+
+/// Cancel every queued and running job and wake the worker so it can
+/// exit. Returns how many were cancelled.
+pub(crate) fn cancel_all() -> usize {
+    let mut inner = lock();
+    let cancelled: Vec<Job> = inner
+        .jobs
+        .values_mut()
+        .filter(|job| matches!(job.state, State::Queued | State::Running))
+        .map(|job| {
+            job.state = State::Cancelled;
+            job.clone()
+        })
+        .collect();
+    inner.pending.clear();
+    for job in &cancelled {
+        save(&inner, job);
+    }
+    queue().wake.notify_all();
+    cancelled.len()
+}
+
+/// The result of a finished job.
+pub fn result(id: u64) -> Option<String> {
+    lock()
+        .jobs
+        .get(&id)
+        .filter(|job| job.state == State::Done)
+        .and_then(|job| job.result.clone())
+}
+
+fn notify(job: &Job) {
+    webhook::notify(&format!("job.{}", job.state.name()), job.status_json());
+}
+
+fn save(inner: &Inner, job: &Job) {
+    let Some(dir) = &inner.dir else {
+        return;
+    };
+    let path = dir.join(format!("{}.json", job.id));
+    let _ = store::write(&path, job.to_json().to_string().as_bytes());
+}
+
+fn start_worker(inner: &mut Inner) {
+    if inner.worker_started {
+        return;
+    }
+    inner.worker_started = true;
+    shutdown::spawn_thread(worker);
+}
+
+fn worker() {
+    loop {
+        let (id, kind, params) = {
+            let mut inner = lock();
+            let id = loop {
+                match inner.pending.pop_front() {
+                    Some(id) => break id,
+                    None if shutdown::stopping() => {
+                        inner.worker_started = false;
+                        return;
+                    }
+                    None => inner = queue().wake.wait(inner).unwrap_or_else(|e| e.into_inner()),
+                }
+            };
+            let Some(job) = inner.jobs.get_mut(&id) else {
+                continue;
+            };
+            job.state = State::Running;
+            let job = job.clone();
+            save(&inner, &job);
+            (id, job.kind, job.params)
+        };
+
+        let outcome = match panic::catch_unwind(AssertUnwindSafe(|| {
+            shutdown::in_task(id, || {
+                telemetry::measure(&format!("job.{kind}"), || {
+                    run(&kind, &params, |report| {
+                        let mut inner = lock();
+                        if let Some(job) = inner.jobs.get_mut(&id) {
+                            job.stage = Some(report.stage);
+                            job.progress = (report.completed, report.total);
+                            job.percent = report.percent;
+                        }
+                    })
+                })
+            })
+        })) {
+            Ok(outcome) => outcome.map_err(|err| err.to_string()),
+            Err(payload) => Err(format!("job panicked: {}", panic_message(payload.as_ref()))),
+        };
+
+        let mut inner = lock();
+        let Some(job) = inner.jobs.get_mut(&id) else {
+            continue;
+        };
+        if job.state == State::Cancelled {
+            continue;
+        }
+        match outcome {
+            Ok(result) => {
+                job.state = State::Done;
+                job.result = Some(result);
+            }
+            Err(err) => {
+                job.state = State::Failed;
+                job.error = Some(err);
+            }
+        }
+        let job = job.clone();
+        save(&inner, &job);
+        drop(inner);
+        notify(&job);
+    }
+}
+
+/// Job kinds accepted by [`submit`].
+pub const KINDS: &[&str] = &[
+    "extract_text",
+    "generate_minutes",
+    "compare_corpus",
+    "classify_document",
+    "find_duplicates",
+    "generate_documents",
+    "summarize",
+    "translate",
+];
+
+/// Run one job synchronously. Params mirror the arguments of the FFI
+/// function of the same name, plus an optional `"backend"` profile for
+/// kinds that call the chat backend:
+///
+/// - `extract_text`: `{"path"}`
+/// - `generate_minutes`: `{"path", "options"?}`
+/// - `compare_corpus`: `{"paths", "criteria"}`
+/// - `classify_document`: `{"path", "labels", "method"?}`
+/// - `find_duplicates`: `{"paths", "threshold"?}`
+/// - `generate_documents`: `{"template", "records", "out_dir"}`
+/// - `summarize`: `{"path", "options"?}`, chunked and summarized part by
+///   part through the chat backend
+/// - `translate`: `{"path", "options"}`, with `options.target` required
+pub fn run(kind: &str, params: &Value, mut progress: impl FnMut(&Progress)) -> Result<String> {
+    let text = |key: &str| {
+        params
+            .get(key)
+            .and_then(Value::as_str)
+            .ok_or_else(|| Error::Parse(format!("missing \"{key}\"")))
+    };
+    let paths = || -> Result<Vec<PathBuf>> {
+        params
+            .get("paths")
+            .and_then(Value::as_array)
+            .and_then(|items| {
+                items
+                    .iter()
+                    .map(|v| v.as_str().map(PathBuf::from))
+                    .collect()
+            })
+            .ok_or_else(|| Error::Parse("\"paths\" must be an array of strings".into()))
+    };
+    let chat_options = chat::ChatOptions::from_json(params);
+    let chat = |prompt: &str| chat::chat(prompt, &chat_options);
+    match kind {
+        "extract_text" => extract::extract_text(Path::new(text("path")?)),
+        "generate_minutes" => {
+            let mut options = match params.get("options") {
+                Some(value) => minutes::MinutesOptions::from_json(value)?,
+                None => minutes::MinutesOptions::default(),
+            };
+            if options.chat.backend.is_none() {
+                options.chat = chat_options.clone();
+            }
+            let transcript = extract::extract_text(Path::new(text("path")?))?;
+            let minutes = minutes::generate_minutes(&transcript, &options, chat, progress)?;
+            Ok(match options.format {
+                minutes::MinutesFormat::Json => minutes.to_json().to_string(),
+                minutes::MinutesFormat::Markdown => minutes.to_markdown(),
+            })
+        }
+        "compare_corpus" => {
+            let criteria = match params.get("criteria") {
+                Some(Value::String(s)) => s.clone(),
+                Some(other) => other.to_string(),
+                None => return Err(Error::Parse("missing \"criteria\"".into())),
+            };
+            let comparison = compare::compare_corpus(&paths()?, &criteria, chat, progress)?;
+            Ok(comparison.to_json().to_string())
+        }
+        "classify_document" => {
+            let labels = classify::Label::list_from_json(
+                params
+                    .get("labels")
+                    .ok_or_else(|| Error::Parse("missing \"labels\"".into()))?,
+            )?;
+            let method = match params.get("method").and_then(Value::as_str) {
+                Some(name) => classify::Method::from_name(name)
+                    .ok_or_else(|| Error::Parse(format!("unknown method '{name}'")))?,
+                None => classify::Method::Backend,
+            };
+            let text = extract::extract_text(Path::new(text("path")?))?;
+            let ranking = match method {
+                classify::Method::Lexical => classify::classify_lexical(&text, &labels),
+                classify::Method::Backend => classify::classify_with_backend(&text, &labels, chat)?,
+            };
+            Ok(classify::ranking_to_json(&ranking).to_string())
+        }
+        "find_duplicates" => {
+            let threshold = params
+                .get("threshold")
+                .and_then(Value::as_f64)
+                .unwrap_or(0.8);
+            Ok(dedup::find_duplicates(&paths()?, threshold)
+                .to_json()
+                .to_string())
+        }
+        "generate_documents" => {
+            let records = params
+                .get("records")
+                .and_then(Value::as_array)
+                .ok_or_else(|| Error::Parse("\"records\" must be an array".into()))?;
+            let generated = template::generate_documents(
+                Path::new(text("template")?),
+                records,
+                Path::new(text("out_dir")?),
+                |completed, total| {
+                    progress(&Progress {
+                        stage: Stage::Writing,
+                        completed,
+                        total,
+                        percent: 100.0 * completed as f64 / total.max(1) as f64,
+                        item: None,
+                    })
+                },
+            )?;
+            Ok(generated.to_json().to_string())
+        }
+        "summarize" => {
+            let mut options = match params.get("options") {
+                Some(value) => summarize::SummarizeOptions::from_json(value),
+                None => summarize::SummarizeOptions::default(),
+            };
+            if options.chat.backend.is_none() {
+                options.chat = chat_options.clone();
+            }
+            let chat = |prompt: &str| chat::chat(prompt, &options.chat);
+            summarize::summarize_document(Path::new(text("path")?), &options, chat, progress)
+        }
+        "translate" => {
+            let options = params
+                .get("options")
+                .ok_or_else(|| Error::Parse("missing \"options\"".into()))?;
+            let mut options = translate::TranslateOptions::from_json(options)?;
+            // The pipeline holds each part to the glossary itself.
+            let mut shared = chat_options.clone();
+            options.glossary = options.glossary.or(shared.glossary.take());
+            if options.chat.backend.is_none() {
+                options.chat = shared;
+            }
+            let chat = |prompt: &str| chat::chat(prompt, &options.chat);
+            translate::translate_document(Path::new(text("path")?), &options, chat, progress)
+        }
+        other => Err(Error::Unsupported(format!("job kind '{other}'"))),
+    }
+}
@@ -0,0 +1,93 @@
+//! Local keyphrase extraction with RAKE (Rapid Automatic Keyword
+//! Extraction).
+//!
+//! Candidate phrases are runs of content words broken at stopwords and
+//! punctuation. Each word scores `degree / frequency`, where degree counts
+//! the words it co-occurs with in candidates, and a phrase scores the sum
+//! of its words.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::json::Value;
+use crate::words::{is_content_word, words};
+
+/// Longer candidates are usually sentence fragments rather than keyphrases.
+const MAX_PHRASE_WORDS: usize = 4;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keyword {
+    pub phrase: String,
+    pub score: f64,
+}
+
+pub fn keywords_to_json(keywords: &[Keyword]) -> Value {
+    Value::Array(
+        keywords
+            .iter()
+            .map(|k| {
+                Value::object([
+                    ("keyword", Value::from(k.phrase.as_str())),
+                    ("score", Value::from((k.score * 100.0).round() / 100.0)),
+                ])
+            })
+            .collect(),
+    )
+}
+
+/// The `top_n` highest-scoring keyphrases of `text`, best first
+/// (0 returns all).
+pub fn extract_keywords(text: &str, top_n: usize) -> Vec<Keyword> {
+    let candidates: Vec<Vec<String>> = text
+        .split(|c: char| {
+            !(c.is_alphanumeric() || c.is_whitespace() || matches!(c, '\'' | '’' | '-'))
+        })
+        .flat_map(|fragment| {
+            let mut phrases = Vec::new();
+            let mut current = Vec::new();
+            for word in words(fragment) {
+                if is_content_word(&word) {
+                    current.push(word);
+                } else if !current.is_empty() {
+                    phrases.push(std::mem::take(&mut current));
+                }
+            }
+            if !current.is_empty() {
+                phrases.push(current);
+            }
+            phrases
+        })
+        .filter(|phrase| phrase.len() <= MAX_PHRASE_WORDS)
+        .collect();
+
+    let mut frequency: HashMap<&str, f64> = HashMap::new();
+    let mut degree: HashMap<&str, f64> = HashMap::new();
+    for phrase in &candidates {
+        for word in phrase {
+            *frequency.entry(word).or_default() += 1.0;
+            *degree.entry(word).or_default() += phrase.len() as f64;
+        }
+    }
+
+    let mut keywords: Vec<Keyword> = Vec::new();
+    let mut seen: HashSet<String> = HashSet::new();
+    for phrase in &candidates {
+        let joined = phrase.join(" ");
+        if !seen.insert(joined.clone()) {
+            continue;
+        }
+        let score = phrase
+            .iter()
+            .map(|w| degree[w.as_str()] / frequency[w.as_str()])
+            .sum();
+        keywords.push(Keyword {
+            phrase: joined,
+            score,
+        });
+    }
+    // Stable sort keeps first-occurrence order among ties.
+    keywords.sort_by(|a, b| b.score.total_cmp(&a.score));
+    if top_n > 0 {
+        keywords.truncate(top_n);
+    }
+    keywords
+}
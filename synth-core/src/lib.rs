@@ -1,65 +1,84 @@
-use std::ffi::{CStr, CString};
-use std::os::raw::c_char;
-use std::process::{Command, Stdio};
+//! Document processing and AI chat for Synth, as a safe Rust API.
+//!
+//! The C ABI the Swift app links against lives in the `synth-ffi` crate,
+//! which wraps these modules; nothing here handles raw pointers.
 
-/// Free a string allocated by Rust
-#[no_mangle]
-pub extern "C" fn free_string(s: *mut c_char) {
-    if !s.is_null() {
-        unsafe {
-            drop(CString::from_raw(s));
-        }
-    }
-}
+pub mod ask;
+pub mod audit;
+pub mod backend;
+pub mod cache;
+pub mod chat;
+pub mod chunk;
+pub mod classify;
+pub mod code;
+pub mod compare;
+pub mod concordance;
+pub mod corpus;
+pub mod dedup;
+pub mod docx;
+pub mod docx_fields;
+pub mod docx_math;
+pub mod docx_text;
+pub mod draft;
+pub mod embeddings;
+pub mod encryption;
+pub mod error;
+pub mod extract;
+pub mod glossary;
+pub mod hooks;
+pub mod html;
+pub mod http;
+pub mod ingest;
+pub mod injection;
+pub mod jobs;
+pub mod json;
+pub mod keywords;
+pub mod live;
+pub mod memory;
+pub mod minutes;
+pub mod mock;
+pub mod models;
+#[cfg(feature = "async")]
+pub mod nonblocking;
+pub mod normalize;
+pub mod ocr;
+pub mod outline;
+pub mod pdf;
+pub mod pdf_annotations;
+pub mod pdf_forms;
+pub mod pdf_markup;
+pub mod pdf_objects;
+pub mod pdf_text;
+pub mod policy;
+pub mod pptx;
+pub mod prefetch;
+pub mod progress;
+pub mod project;
+pub mod reanchor;
+pub mod registry;
+pub mod schema;
+pub mod segment;
+pub mod select;
+pub mod selftest;
+pub mod session;
+pub mod shutdown;
+pub mod stats;
+pub mod store;
+pub mod structured;
+pub mod summarize;
+pub mod table;
+pub mod telemetry;
+pub mod template;
+pub mod transcribe;
+pub mod translate;
+pub mod usage;
+pub mod webhook;
+pub mod words;
 
-/// Send a prompt to kiro-cli and get the response
-#[no_mangle]
-pub extern "C" fn kiro_chat(prompt: *const c_char) -> *mut c_char {
-    if prompt.is_null() {
-        return std::ptr::null_mut();
-    }
-
-    let c_str = unsafe { CStr::from_ptr(prompt) };
-    let prompt_str = match c_str.to_str() {
-        Ok(s) => s,
-        Err(_) => return std::ptr::null_mut(),
-    };
-
-    let output = Command::new("kiro-cli")
-        .args(["chat", "--no-interactive", "-a", prompt_str])
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .output();
-
-    match output {
-        Ok(out) => {
-            let stdout = String::from_utf8_lossy(&out.stdout);
-            let cleaned = strip_ansi(&stdout);
-            CString::new(cleaned)
-                .map(|s| s.into_raw())
-                .unwrap_or(std::ptr::null_mut())
-        }
-        Err(_) => std::ptr::null_mut(),
-    }
-}
-
-fn strip_ansi(s: &str) -> String {
-    let mut result = String::new();
-    let mut chars = s.chars().peekable();
-    while let Some(c) = chars.next() {
-        if c == '\x1b' {
-            if chars.peek() == Some(&'[') {
-                chars.next();
-                while let Some(&nc) = chars.peek() {
-                    chars.next();
-                    if nc.is_ascii_alphabetic() {
-                        break;
-                    }
-                }
-            }
-        } else {
-            result.push(c);
-        }
-    }
-    result
-}
+mod hash;
+mod inflate;
+mod opc;
+mod tar;
+mod toml;
+mod xml;
+mod zip;
@@ -0,0 +1,100 @@
+//! Extraction in passes, for live-preview UIs that want to show something
+//! before the whole document is processed.
+//!
+//! [`extract_live`] reports each pass as a [`Delta`]: for files larger
+//! than [`PREVIEW_BYTES`], first a preview of their beginning decoded as
+//! plain text (web pages reduced to their article text), then the full
+//! extraction, which replaces the preview, then the heading outline and
+//! reading statistics of that text. Smaller files skip the preview.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use crate::error::Result;
+use crate::extract::{self, Format};
+use crate::html;
+use crate::json::Value;
+use crate::outline::{self, Heading};
+use crate::policy;
+use crate::stats::{self, Stats};
+
+/// How much of a file the preview pass reads.
+pub const PREVIEW_BYTES: usize = 64 * 1024;
+
+/// One pass over the document.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Delta {
+    /// The start of the file, decoded without parsing.
+    Preview(String),
+    /// The complete extracted text.
+    Text(String),
+    Outline(Vec<Heading>),
+    Stats(Stats),
+}
+
+impl Delta {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Delta::Preview(_) => "preview",
+            Delta::Text(_) => "text",
+            Delta::Outline(_) => "outline",
+            Delta::Stats(_) => "stats",
+        }
+    }
+
+    /// `{"pass": "preview" | "text", "text": "..."}`, `{"pass": "outline",
+    /// "outline": [...]}` or `{"pass": "stats", "stats": {...}}`.
+    pub fn to_json(&self) -> Value {
+        let data = match self {
+            Delta::Preview(text) | Delta::Text(text) => ("text", Value::from(text.as_str())),
+            Delta::Outline(headings) => (
+                "outline",
+                Value::Array(headings.iter().map(Heading::to_json).collect()),
+            ),
+            Delta::Stats(stats) => ("stats", stats.to_json()),
+        };
+        Value::object([("pass", Value::from(self.name())), data])
+    }
+}
+
+/// The first [`PREVIEW_BYTES`] of `path` as text, or `None` if the whole
+/// file is shorter than that.
+fn preview(path: &Path) -> Result<Option<String>> {
+    let mut head = Vec::with_capacity(PREVIEW_BYTES + 1);
+    File::open(path)?
+        .take(PREVIEW_BYTES as u64 + 1)
+        .read_to_end(&mut head)?;
+    if head.len() <= PREVIEW_BYTES {
+        return Ok(None);
+    }
+    head.truncate(PREVIEW_BYTES);
+    // Drop a character cut in half at the end.
+    let text = match std::str::from_utf8(&head) {
+        Ok(text) => text.to_string(),
+        Err(e) if e.error_len().is_none() => {
+            String::from_utf8_lossy(&head[..e.valid_up_to()]).into_owned()
+        }
+        Err(_) => String::from_utf8_lossy(&head).into_owned(),
+    };
+    Ok(Some(match Format::from_path(path) {
+        Some(Format::Html) => html::article(&text).to_markdown(),
+        _ => text,
+    }))
+}
+
+/// Extract `path` as [`extract::extract_text`] does, reporting each pass
+/// to `on_delta` as it completes. Returns the full text.
+pub fn extract_live(path: &Path, mut on_delta: impl FnMut(&Delta)) -> Result<String> {
+    policy::check_read(path)?;
+    if Format::from_path(path).is_some_and(Format::is_text) {
+        if let Some(text) = preview(path)? {
+            on_delta(&Delta::Preview(text));
+        }
+    }
+    let text = extract::extract_text(path)?;
+    on_delta(&Delta::Text(text.clone()));
+    on_delta(&Delta::Outline(outline::outline(&text)));
+    on_delta(&Delta::Stats(stats::document_stats(&text)));
+    Ok(text)
+}
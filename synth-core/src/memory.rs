@@ -0,0 +1,366 @@
+//! Long-term memory: short facts kept across conversations and recalled
+//! into later prompts.
+//!
+//! Memories are searched and recalled by the cosine similarity of their
+//! embeddings when an [embedding model](crate::embeddings) is configured,
+//! each memory embedded once and the vector kept while the model is. Without
+//! one, or if embedding fails, retrieval is lexical only: content-word
+//! similarity, the same local measure the lexical classifier uses, which
+//! misses paraphrases sharing no words. Repeats are always caught
+//! lexically. When a directory is configured memories persist in
+//! `<dir>/memories.json`; their vectors are not saved.
+//!
+//! A memory stored with a user or tenant tag is only recalled into chat
+//! calls carrying the same tags, so one user's facts never reach another's
+//! prompts; untagged memories are recalled for everyone. Listing and
+//! searching are scoped the same way, and a memory is only updated or
+//! deleted by a caller with exactly the tags it was stored with.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::embeddings;
+use crate::error::{Error, Result};
+use crate::json::{self, Value};
+use crate::store;
+use crate::usage::Tags;
+use crate::words::{cosine, term_counts};
+
+/// Text sent to the backend when extracting facts is capped at this many
+/// characters.
+const EXTRACT_CHARS: usize = 24_000;
+
+/// New facts at least this similar to an existing memory are not stored
+/// again.
+const DUPLICATE_SIMILARITY: f64 = 0.9;
+
+/// Memories recalled into a prompt, and the similarity they need by
+/// shared words or by embedding.
+const RECALL_LIMIT: usize = 5;
+const RECALL_THRESHOLD: f64 = 0.15;
+const EMBEDDING_RECALL_THRESHOLD: f64 = 0.35;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Memory {
+    pub id: u64,
+    pub text: String,
+    /// Where the fact came from, e.g. a document path or session name.
+    pub source: Option<String>,
+    /// Seconds since the Unix epoch.
+    pub created: u64,
+    /// Who may recall it.
+    pub tags: Tags,
+}
+
+impl Memory {
+    pub fn to_json(&self) -> Value {
+        let mut pairs = vec![
+            ("id", Value::from(self.id as f64)),
+            ("text", Value::from(self.text.as_str())),
+            (
+                "source",
+                self.source.as_deref().map_or(Value::Null, Value::from),
+            ),
+            ("created", Value::from(self.created as f64)),
+        ];
+        pairs.extend(self.tags.pairs());
+        Value::object(pairs)
+    }
+
+    fn from_json(value: &Value) -> Option<Memory> {
+        Some(Memory {
+            id: value.get("id")?.as_f64()? as u64,
+            text: value.get("text")?.as_str()?.to_string(),
+            source: value
+                .get("source")
+                .and_then(Value::as_str)
+                .map(String::from),
+            created: value.get("created").and_then(Value::as_f64).unwrap_or(0.0) as u64,
+            tags: Tags::from_json(value),
+        })
+    }
+}
+
+pub fn memories_to_json(memories: &[Memory]) -> Value {
+    Value::Array(memories.iter().map(Memory::to_json).collect())
+}
+
+#[derive(Default)]
+struct Store {
+    dir: Option<PathBuf>,
+    memories: BTreeMap<u64, Memory>,
+    /// Embeddings of memories under model `generation`, with the text
+    /// embedded, so an updated memory is embedded again.
+    vectors: BTreeMap<u64, (String, Vec<f32>)>,
+    generation: u64,
+}
+
+impl Store {
+    fn save(&self) {
+        let Some(dir) = &self.dir else {
+            return;
+        };
+        let memories: Vec<Memory> = self.memories.values().cloned().collect();
+        let path = dir.join("memories.json");
+        let _ = store::write(&path, memories_to_json(&memories).to_string().as_bytes());
+    }
+
+    fn insert(&mut self, text: &str, source: Option<&str>, tags: &Tags) -> Option<Memory> {
+        let text = text.trim();
+        if text.is_empty() {
+            return None;
+        }
+        let terms = term_counts(text);
+        let duplicate = self.memories.values().filter(|m| m.tags == *tags).any(|m| {
+            m.text.eq_ignore_ascii_case(text)
+                || cosine(&terms, &term_counts(&m.text)) >= DUPLICATE_SIMILARITY
+        });
+        if duplicate {
+            return None;
+        }
+        let id = self.memories.keys().next_back().map_or(1, |last| last + 1);
+        let memory = Memory {
+            id,
+            text: text.to_string(),
+            source: source.map(String::from),
+            created: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            tags: tags.clone(),
+        };
+        self.memories.insert(id, memory.clone());
+        Some(memory)
+    }
+}
+
+fn lock() -> MutexGuard<'static, Store> {
+    static STORE: Mutex<Store> = Mutex::new(Store {
+        dir: None,
+        memories: BTreeMap::new(),
+        vectors: BTreeMap::new(),
+        generation: 0,
+    });
+    STORE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Persist memories under `dir`, loading any saved there earlier. Without
+/// it memories live in memory only.
+pub fn init(dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir)?;
+    let path = dir.join("memories.json");
+    let loaded: Vec<Memory> = match store::read_string(&path)? {
+        Some(text) => json::parse(&text)?
+            .as_array()
+            .unwrap_or(&[])
+            .iter()
+            .filter_map(Memory::from_json)
+            .collect(),
+        None => Vec::new(),
+    };
+    let mut store = lock();
+    store.dir = Some(dir.to_path_buf());
+    for memory in loaded {
+        store.memories.entry(memory.id).or_insert(memory);
+    }
+    store.save();
+    Ok(())
+}
+
+/// Store a fact for the callers `tags` match. Returns `None` if it is
+/// empty or repeats an existing memory with the same tags.
+pub fn add(text: &str, source: Option<&str>, tags: &Tags) -> Option<Memory> {
+    let mut store = lock();
+    let memory = store.insert(text, source, tags)?;
+    store.save();
+    Some(memory)
+}
+
+/// The memories a caller with `scope` may recall.
+pub fn list(scope: &Tags) -> Vec<Memory> {
+    lock()
+        .memories
+        .values()
+        .filter(|m| m.tags.matches(scope))
+        .cloned()
+        .collect()
+}
+
+/// Replace a memory's text. Returns false if no memory with the id was
+/// stored with the tags `scope`.
+pub fn update(id: u64, text: &str, scope: &Tags) -> bool {
+    let mut store = lock();
+    let Some(memory) = store.memories.get_mut(&id).filter(|m| m.tags == *scope) else {
+        return false;
+    };
+    memory.text = text.trim().to_string();
+    store.save();
+    true
+}
+
+/// Forget a memory. Returns false if no memory with the id was stored with
+/// the tags `scope`.
+pub fn remove(id: u64, scope: &Tags) -> bool {
+    let mut store = lock();
+    if store.memories.get(&id).is_none_or(|m| m.tags != *scope) {
+        return false;
+    }
+    store.memories.remove(&id);
+    store.vectors.remove(&id);
+    store.save();
+    true
+}
+
+/// Up to `limit` memories a caller with `scope` may recall most similar to
+/// `query` (0 for no limit), best first, with their similarity.
+pub fn search(query: &str, limit: usize, scope: &Tags) -> Vec<(Memory, f64)> {
+    scored(query, limit, |m| m.tags.matches(scope)).0
+}
+
+/// The memories `visible` lets through scored against `query`, and the
+/// score recall needs under the measure used.
+fn scored(
+    query: &str,
+    limit: usize,
+    visible: impl Fn(&Memory) -> bool,
+) -> (Vec<(Memory, f64)>, f64) {
+    let memories: Vec<Memory> = lock()
+        .memories
+        .values()
+        .filter(|m| visible(m))
+        .cloned()
+        .collect();
+    let embedded = match embeddings::is_configured() {
+        true => embedded_scores(query, &memories).ok(),
+        false => None,
+    };
+    let (scores, threshold) = match embedded {
+        Some(scores) => (scores, EMBEDDING_RECALL_THRESHOLD),
+        None => {
+            let terms = term_counts(query);
+            let scores = memories
+                .iter()
+                .map(|m| cosine(&terms, &term_counts(&m.text)))
+                .collect();
+            (scores, RECALL_THRESHOLD)
+        }
+    };
+    let mut scored: Vec<(Memory, f64)> = memories
+        .into_iter()
+        .zip(scores)
+        .filter(|(_, score)| *score > 0.0)
+        .collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    if limit > 0 {
+        scored.truncate(limit);
+    }
+    (scored, threshold)
+}
+
+/// The cosine similarity of each memory's embedding to `query`'s,
+/// embedding the memories not embedded yet under the current model. The
+/// store is not locked while the model runs.
+fn embedded_scores(query: &str, memories: &[Memory]) -> Result<Vec<f64>> {
+    let generation = embeddings::generation();
+    let mut vectors: Vec<Option<Vec<f32>>> = {
+        let mut store = lock();
+        if store.generation != generation {
+            store.vectors.clear();
+            store.generation = generation;
+        }
+        memories
+            .iter()
+            .map(|m| {
+                store
+                    .vectors
+                    .get(&m.id)
+                    .filter(|(text, _)| *text == m.text)
+                    .map(|(_, vector)| vector.clone())
+            })
+            .collect()
+    };
+    let missing: Vec<usize> = (0..memories.len())
+        .filter(|&i| vectors[i].is_none())
+        .collect();
+    let mut texts = vec![query];
+    texts.extend(missing.iter().map(|&i| memories[i].text.as_str()));
+    let mut embedded = embeddings::embed(&texts)?.into_iter();
+    let query = embedded
+        .next()
+        .ok_or_else(|| Error::Backend("no embedding for the query".into()))?;
+    let mut store = lock();
+    for (&i, vector) in missing.iter().zip(embedded) {
+        if store.generation == generation {
+            let memory = &memories[i];
+            store
+                .vectors
+                .insert(memory.id, (memory.text.clone(), vector.clone()));
+        }
+        vectors[i] = Some(vector);
+    }
+    Ok(vectors
+        .iter()
+        .map(|vector| {
+            vector
+                .as_deref()
+                .map_or(0.0, |v| embeddings::similarity(&query, v) as f64)
+        })
+        .collect())
+}
+
+/// `prompt` preceded by the memories relevant to it that a caller with
+/// `tags` may recall, if any.
+pub fn recall(prompt: &str, tags: &Tags) -> String {
+    let (scored, threshold) = scored(prompt, RECALL_LIMIT, |m| m.tags.matches(tags));
+    let relevant: Vec<String> = scored
+        .into_iter()
+        .filter(|(_, score)| *score >= threshold)
+        .map(|(memory, _)| format!("- {}", memory.text))
+        .collect();
+    if relevant.is_empty() {
+        return prompt.to_string();
+    }
+    format!(
+        "Facts remembered from earlier conversations (use them only where \
+         relevant):\n{}\n\n{prompt}",
+        relevant.join("\n")
+    )
+}
+
+/// Ask `chat` for the durable facts in `text` (a conversation or document)
+/// and store the new ones under `tags`. Returns the memories added.
+pub fn extract(
+    text: &str,
+    source: Option<&str>,
+    tags: &Tags,
+    mut chat: impl FnMut(&str) -> Result<String>,
+) -> Result<Vec<Memory>> {
+    let excerpt = match text.char_indices().nth(EXTRACT_CHARS) {
+        Some((cut, _)) => &text[..cut],
+        None => text,
+    };
+    let prompt = format!(
+        "List the facts in the text below that are worth remembering for future \
+         conversations: the user's preferences, projects, people, decisions and \
+         commitments. Each fact must be a short statement that makes sense on its \
+         own. Respond with only a JSON array of strings, or [] if there are none.\n\n\
+         Text:\n{excerpt}"
+    );
+    let reply = json::parse_embedded(&chat(&prompt)?)
+        .map_err(|_| Error::Backend("memory reply was not JSON".into()))?;
+    let facts = reply
+        .as_array()
+        .ok_or_else(|| Error::Backend("memory reply was not a list".into()))?;
+    let mut store = lock();
+    let added: Vec<Memory> = facts
+        .iter()
+        .filter_map(Value::as_str)
+        .filter_map(|fact| store.insert(fact, source, tags))
+        .collect();
+    if !added.is_empty() {
+        store.save();
+    }
+    Ok(added)
+}
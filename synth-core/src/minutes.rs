@@ -0,0 +1,260 @@
+//! Structured meeting minutes from a speaker-attributed transcript.
+
+use crate::chat::ChatOptions;
+use crate::chunk::chunk_text;
+use crate::error::{Error, Result};
+use crate::json::{self, Value};
+use crate::progress::{Progress, Stage};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MinutesFormat {
+    Json,
+    Markdown,
+}
+
+#[derive(Debug, Clone)]
+pub struct MinutesOptions {
+    pub format: MinutesFormat,
+    /// Transcripts longer than this are sent to the backend in parts.
+    pub max_chunk_chars: usize,
+    pub chat: ChatOptions,
+}
+
+impl Default for MinutesOptions {
+    fn default() -> Self {
+        MinutesOptions {
+            format: MinutesFormat::Json,
+            max_chunk_chars: 12_000,
+            chat: ChatOptions::default(),
+        }
+    }
+}
+
+impl MinutesOptions {
+    /// Read `{"format": "json" | "markdown", "max_chunk_chars": n,
+    /// "backend": "profile"}`; missing keys keep their defaults.
+    pub fn from_json(value: &Value) -> Result<Self> {
+        let mut options = MinutesOptions {
+            chat: ChatOptions::from_json(value),
+            ..MinutesOptions::default()
+        };
+        match value.get("format").and_then(Value::as_str) {
+            Some("json") | None => {}
+            Some("markdown") => options.format = MinutesFormat::Markdown,
+            Some(other) => return Err(Error::Parse(format!("unknown format '{other}'"))),
+        }
+        if let Some(n) = value.get("max_chunk_chars").and_then(Value::as_f64) {
+            options.max_chunk_chars = (n as usize).max(1000);
+        }
+        Ok(options)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActionItem {
+    pub owner: Option<String>,
+    pub task: String,
+    pub due: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Minutes {
+    pub attendees: Vec<String>,
+    /// One summary paragraph per transcript part.
+    pub summary: Vec<String>,
+    pub decisions: Vec<String>,
+    pub action_items: Vec<ActionItem>,
+}
+
+impl Minutes {
+    pub fn to_json(&self) -> Value {
+        let strings = |items: &[String]| {
+            Value::Array(items.iter().map(|s| Value::from(s.as_str())).collect())
+        };
+        let optional = |s: &Option<String>| s.as_deref().map_or(Value::Null, Value::from);
+        Value::object([
+            ("attendees", strings(&self.attendees)),
+            ("summary", Value::from(self.summary.join("\n\n"))),
+            ("decisions", strings(&self.decisions)),
+            (
+                "action_items",
+                Value::Array(
+                    self.action_items
+                        .iter()
+                        .map(|item| {
+                            Value::object([
+                                ("owner", optional(&item.owner)),
+                                ("task", Value::from(item.task.as_str())),
+                                ("due", optional(&item.due)),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+        ])
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::from("# Meeting minutes\n");
+        if !self.attendees.is_empty() {
+            out.push_str("\n## Attendees\n\n");
+            for name in &self.attendees {
+                out.push_str(&format!("- {name}\n"));
+            }
+        }
+        if !self.summary.is_empty() {
+            out.push_str(&format!("\n## Summary\n\n{}\n", self.summary.join("\n\n")));
+        }
+        out.push_str("\n## Decisions\n\n");
+        if self.decisions.is_empty() {
+            out.push_str("None recorded.\n");
+        }
+        for decision in &self.decisions {
+            out.push_str(&format!("- {decision}\n"));
+        }
+        out.push_str("\n## Action items\n\n");
+        if self.action_items.is_empty() {
+            out.push_str("None recorded.\n");
+        }
+        for item in &self.action_items {
+            out.push_str(&format!("- [ ] {}", item.task));
+            if let Some(owner) = &item.owner {
+                out.push_str(&format!(" — {owner}"));
+            }
+            if let Some(due) = &item.due {
+                out.push_str(&format!(" (due {due})"));
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Speaker names in order of first appearance, from lines shaped like
+/// `Name: text` or `[00:01:02] Name: text`.
+pub fn speakers(transcript: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for line in transcript.lines() {
+        if let Some(name) = speaker(line) {
+            if !names.iter().any(|n| n == name) {
+                names.push(name.to_string());
+            }
+        }
+    }
+    names
+}
+
+fn speaker(line: &str) -> Option<&str> {
+    let mut rest = line.trim();
+    if let Some(after) = rest.strip_prefix('[') {
+        rest = after.split_once(']')?.1.trim_start();
+    } else if rest.starts_with(|c: char| c.is_ascii_digit()) {
+        let stamp_end = rest.find(|c: char| !(c.is_ascii_digit() || c == ':' || c == '.'))?;
+        rest = rest[stamp_end..].trim_start();
+    }
+    let (name, said) = rest.split_once(':')?;
+    let name = name.trim();
+    let plausible = !name.is_empty()
+        && name.chars().count() <= 40
+        && name.split_whitespace().count() <= 4
+        && name.starts_with(char::is_uppercase)
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, ' ' | '.' | '-' | '\''))
+        && !said.trim().is_empty();
+    plausible.then_some(name)
+}
+
+fn prompt(part: &str, index: usize, total: usize, attendees: &[String]) -> String {
+    let mut prompt = String::from(
+        "You are taking meeting minutes. From the transcript below, summarize the \
+         discussion and list the decisions made and the action items assigned. \
+         Respond with only a JSON object of the form \
+         {\"summary\": \"...\", \"decisions\": [\"...\"], \
+         \"action_items\": [{\"owner\": \"...\", \"task\": \"...\", \"due\": \"...\"}]}. \
+         Use null for an unknown owner or due date.\n",
+    );
+    if !attendees.is_empty() {
+        prompt.push_str(&format!("Speakers: {}\n", attendees.join(", ")));
+    }
+    if total > 1 {
+        prompt.push_str(&format!("This is part {} of {total}.\n", index + 1));
+    }
+    prompt.push_str("\nTranscript:\n");
+    prompt.push_str(part);
+    prompt
+}
+
+/// Produce minutes by sending each transcript part to `chat` and merging
+/// the structured replies. `progress` hears about chunking and each part.
+pub fn generate_minutes(
+    transcript: &str,
+    options: &MinutesOptions,
+    mut chat: impl FnMut(&str) -> Result<String>,
+    mut progress: impl FnMut(&Progress),
+) -> Result<Minutes> {
+    progress(&Progress {
+        stage: Stage::Chunking,
+        completed: 0,
+        total: 1,
+        percent: 0.0,
+        item: None,
+    });
+    let mut minutes = Minutes {
+        attendees: speakers(transcript),
+        ..Minutes::default()
+    };
+    let parts = chunk_text(transcript, options.max_chunk_chars);
+    for (index, part) in parts.iter().enumerate() {
+        let label = format!("part {} of {}", index + 1, parts.len());
+        progress(&Progress {
+            stage: Stage::Generating,
+            completed: index,
+            total: parts.len(),
+            percent: 5.0 + 95.0 * index as f64 / parts.len() as f64,
+            item: Some(&label),
+        });
+        let reply = chat(&prompt(&part.text, index, parts.len(), &minutes.attendees))?;
+        let value = json::parse_embedded(&reply)
+            .map_err(|_| Error::Backend("minutes reply was not JSON".into()))?;
+        let text = |v: &Value| {
+            v.as_str()
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(String::from)
+        };
+        if let Some(summary) = value.get("summary").and_then(text) {
+            minutes.summary.push(summary);
+        }
+        for decision in value
+            .get("decisions")
+            .and_then(Value::as_array)
+            .unwrap_or(&[])
+        {
+            if let Some(decision) = text(decision) {
+                minutes.decisions.push(decision);
+            }
+        }
+        for item in value
+            .get("action_items")
+            .and_then(Value::as_array)
+            .unwrap_or(&[])
+        {
+            if let Some(task) = item.get("task").and_then(text) {
+                minutes.action_items.push(ActionItem {
+                    owner: item.get("owner").and_then(text),
+                    task,
+                    due: item.get("due").and_then(text),
+                });
+            }
+        }
+    }
+    progress(&Progress {
+        stage: Stage::Generating,
+        completed: parts.len(),
+        total: parts.len(),
+        percent: 100.0,
+        item: None,
+    });
+    Ok(minutes)
+}
@@ -0,0 +1,95 @@
+//! Deterministic replies for the "mock" backend, so host test suites can
+//! run the full chat path offline.
+//!
+//! A script answers from, in order: the first rule whose `contains` text
+//! appears in the prompt, the next scripted reply (cycling), then the
+//! default. `{prompt}` in a reply is replaced by the prompt.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+
+use crate::error::{Error, Result};
+use crate::json::{self, Value};
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Script {
+    /// `(text the prompt contains, reply)`.
+    pub rules: Vec<(String, String)>,
+    pub replies: Vec<String>,
+    pub default: Option<String>,
+}
+
+impl Script {
+    /// Read `{"rules": [{"contains": "minutes", "reply": "..."}],
+    /// "replies": ["first", "second"], "default": "..."}`, or the same
+    /// object from the JSON file named by `"fixture"`.
+    pub fn from_json(value: &Value) -> Result<Self> {
+        if let Some(fixture) = value.get("fixture").and_then(Value::as_str) {
+            let text = std::fs::read_to_string(Path::new(fixture))?;
+            return Script::from_json(&json::parse(&text)?);
+        }
+        let rules = value
+            .get("rules")
+            .and_then(Value::as_array)
+            .unwrap_or(&[])
+            .iter()
+            .map(|rule| {
+                let text = |key| rule.get(key).and_then(Value::as_str).map(String::from);
+                text("contains")
+                    .zip(text("reply"))
+                    .ok_or_else(|| Error::Parse("mock rules need contains and reply".into()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let replies = value
+            .get("replies")
+            .and_then(Value::as_array)
+            .unwrap_or(&[])
+            .iter()
+            .filter_map(Value::as_str)
+            .map(String::from)
+            .collect();
+        Ok(Script {
+            rules,
+            replies,
+            default: value
+                .get("default")
+                .and_then(Value::as_str)
+                .map(String::from),
+        })
+    }
+
+    /// The reply for `prompt` from the profile named `profile`.
+    pub fn reply(&self, profile: &str, prompt: &str) -> Result<String> {
+        let reply = match self
+            .rules
+            .iter()
+            .find(|(needle, _)| prompt.contains(needle))
+        {
+            Some((_, reply)) => reply,
+            None if !self.replies.is_empty() => {
+                let mut counters = lock();
+                let next = counters.entry(profile.to_string()).or_insert(0);
+                let reply = &self.replies[*next % self.replies.len()];
+                *next += 1;
+                reply
+            }
+            None => self
+                .default
+                .as_ref()
+                .ok_or_else(|| Error::Backend(format!("mock '{profile}' has no reply")))?,
+        };
+        Ok(reply.replace("{prompt}", prompt))
+    }
+}
+
+/// How many scripted replies each profile has given.
+fn lock() -> MutexGuard<'static, BTreeMap<String, usize>> {
+    static COUNTERS: Mutex<BTreeMap<String, usize>> = Mutex::new(BTreeMap::new());
+    COUNTERS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Start every script from its first reply again.
+pub fn reset() {
+    lock().clear();
+}
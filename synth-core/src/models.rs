@@ -0,0 +1,249 @@
+//! Local model files: download, verify, list and remove.
+//!
+//! Models live in one directory with a `models.json` manifest recording
+//! each file's source and SHA-256. Downloads run through the system `curl`
+//! (HTTPS needs TLS, which this crate does not link) into a `.part` file
+//! that is hashed before it replaces anything. Only `https://` URLs are
+//! fetched, and curl is not allowed to follow redirects to anything else.
+
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+use crate::error::{Error, Result};
+use crate::hash::{hex, Sha256};
+use crate::json::{self, Value};
+use crate::shutdown::Process;
+
+const MANIFEST: &str = "models.json";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Model {
+    pub name: String,
+    /// File name inside the models directory.
+    pub file: String,
+    pub url: String,
+    /// Expected lowercase hex SHA-256.
+    pub sha256: String,
+    /// Expected size in bytes, if known; used for progress only.
+    pub size: Option<u64>,
+}
+
+impl Model {
+    /// Parse `{"name": "...", "url": "https://...", "sha256": "...",
+    /// "file": "model.gguf", "size": 123}`; `file` defaults to the last
+    /// URL segment.
+    pub fn from_json(value: &Value) -> Result<Model> {
+        let text = |key| value.get(key).and_then(Value::as_str).map(String::from);
+        let need = |key| text(key).ok_or_else(|| Error::Parse(format!("model needs \"{key}\"")));
+        let url = need("url")?;
+        check_url(&url)?;
+        let file = match text("file") {
+            Some(file) => file,
+            None => url
+                .rsplit('/')
+                .next()
+                .and_then(|f| f.split('?').next())
+                .filter(|f| !f.is_empty())
+                .map(String::from)
+                .ok_or_else(|| Error::Parse("cannot derive a file name from the url".into()))?,
+        };
+        if file.contains(['/', '\\']) || file.starts_with('.') {
+            return Err(Error::Parse(format!("bad model file name '{file}'")));
+        }
+        Ok(Model {
+            name: need("name")?,
+            file,
+            url,
+            sha256: need("sha256")?.to_ascii_lowercase(),
+            size: value.get("size").and_then(Value::as_f64).map(|n| n as u64),
+        })
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut pairs = vec![
+            ("name", Value::from(self.name.as_str())),
+            ("file", Value::from(self.file.as_str())),
+            ("url", Value::from(self.url.as_str())),
+            ("sha256", Value::from(self.sha256.as_str())),
+        ];
+        if let Some(size) = self.size {
+            pairs.push(("size", Value::from(size as f64)));
+        }
+        Value::object(pairs)
+    }
+}
+
+fn read_manifest(dir: &Path) -> Result<Vec<Model>> {
+    let path = dir.join(MANIFEST);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let value = json::parse(&fs::read_to_string(path)?)?;
+    Ok(value
+        .as_array()
+        .unwrap_or(&[])
+        .iter()
+        .filter_map(|v| Model::from_json(v).ok())
+        .collect())
+}
+
+fn write_manifest(dir: &Path, models: &[Model]) -> Result<()> {
+    let tmp = dir.join(format!("{MANIFEST}.tmp"));
+    let value = Value::Array(models.iter().map(Model::to_json).collect());
+    fs::write(&tmp, value.to_string())?;
+    fs::rename(tmp, dir.join(MANIFEST))?;
+    Ok(())
+}
+
+/// Installed models with their paths. `present` is false when the file
+/// has gone missing since it was installed.
+pub fn list(dir: &Path) -> Result<Value> {
+    Ok(Value::Array(
+        read_manifest(dir)?
+            .iter()
+            .map(|model| {
+                let path = dir.join(&model.file);
+                let mut value = model.to_json();
+                if let Value::Object(pairs) = &mut value {
+                    pairs.push(("path".into(), Value::from(path.display().to_string())));
+                    pairs.push(("present".into(), Value::from(path.is_file())));
+                }
+                value
+            })
+            .collect(),
+    ))
+}
+
+/// The path of the installed model `name`, once its file is checked
+/// against the SHA-256 it was installed with.
+pub fn installed(dir: &Path, name: &str) -> Result<PathBuf> {
+    let model = read_manifest(dir)?
+        .into_iter()
+        .find(|m| m.name == name)
+        .ok_or_else(|| Error::InvalidArgument(format!("no model named '{name}' is installed")))?;
+    let path = dir.join(&model.file);
+    let actual = file_sha256(&path)?;
+    if actual != model.sha256 {
+        return Err(Error::Parse(format!(
+            "sha256 mismatch for {}: expected {}, got {actual}",
+            model.name, model.sha256
+        )));
+    }
+    Ok(path)
+}
+
+/// SHA-256 of a file, streamed.
+pub fn file_sha256(path: &Path) -> Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::default();
+    let mut buf = vec![0; 1 << 16];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hex(&hasher.finish()))
+}
+
+fn check_url(url: &str) -> Result<()> {
+    if url.starts_with("https://") {
+        Ok(())
+    } else {
+        Err(Error::Parse(format!("model url must be https: '{url}'")))
+    }
+}
+
+/// Download `model` into `dir`, verify its hash and record it. `progress`
+/// receives `(bytes so far, expected bytes or 0)` while downloading.
+/// Returns the installed path.
+pub fn download(dir: &Path, model: &Model, mut progress: impl FnMut(u64, u64)) -> Result<PathBuf> {
+    check_url(&model.url)?;
+    fs::create_dir_all(dir)?;
+    let target = dir.join(&model.file);
+    let part = dir.join(format!("{}.part", model.file));
+    let child = Process::spawn(
+        Command::new("curl")
+            .args(["--fail", "--location", "--silent", "--show-error"])
+            .args(["--proto", "=https", "--proto-redir", "=https", "-o"])
+            .arg(&part)
+            // The URL cannot be read as an option.
+            .arg("--")
+            .arg(&model.url)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped()),
+    )?;
+    let total = model.size.unwrap_or(0);
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        progress(fs::metadata(&part).map_or(0, |m| m.len()), total);
+        thread::sleep(Duration::from_millis(200));
+    };
+    if !status.success() {
+        let mut message = String::new();
+        if let Some(mut stderr) = child.take_stderr() {
+            let _ = stderr.read_to_string(&mut message);
+        }
+        let _ = fs::remove_file(&part);
+        return Err(Error::Backend(format!(
+            "download failed: {}",
+            message.trim()
+        )));
+    }
+    let size = fs::metadata(&part)?.len();
+    progress(size, total.max(size));
+
+    let actual = file_sha256(&part)?;
+    if actual != model.sha256 {
+        let _ = fs::remove_file(&part);
+        return Err(Error::Parse(format!(
+            "sha256 mismatch for {}: expected {}, got {actual}",
+            model.name, model.sha256
+        )));
+    }
+    fs::rename(&part, &target)?;
+
+    // A model of the same name, or in the same file, is replaced: its
+    // entry goes and so does its file, unless the new download took it.
+    let (replaced, mut models): (Vec<Model>, Vec<Model>) = read_manifest(dir)?
+        .into_iter()
+        .partition(|m| m.name == model.name || m.file == model.file);
+    for old in replaced.iter().filter(|m| m.file != model.file) {
+        remove_file(&dir.join(&old.file))?;
+    }
+    models.push(Model {
+        size: Some(size),
+        ..model.clone()
+    });
+    write_manifest(dir, &models)?;
+    Ok(target)
+}
+
+/// Delete a model's file and manifest entry. Returns false if no model has
+/// that name.
+pub fn remove(dir: &Path, name: &str) -> Result<bool> {
+    let mut models = read_manifest(dir)?;
+    let Some(index) = models.iter().position(|m| m.name == name) else {
+        return Ok(false);
+    };
+    let model = models.remove(index);
+    remove_file(&dir.join(&model.file))?;
+    write_manifest(dir, &models)?;
+    Ok(true)
+}
+
+/// Delete `path`; a file already gone is not an error.
+fn remove_file(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,119 @@
+//! `async` variants of the blocking calls, for async services.
+//!
+//! Each call runs on its own thread and resolves a [`Task`] when done, so
+//! awaiting one never blocks the executor. No particular runtime is
+//! required; the futures work under tokio, async-std or a bare executor.
+
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use crate::chat::{self, ChatOptions};
+use crate::compare::{self, Comparison};
+use crate::error::{panic_message, Error, Result};
+use crate::extract;
+use crate::http::{self, RequestOptions, Response, Url};
+use crate::minutes::{self, Minutes, MinutesOptions};
+use crate::shutdown;
+
+struct Slot<T> {
+    result: Option<Result<T>>,
+    waker: Option<Waker>,
+}
+
+/// The pending result of blocking work started by [`spawn`].
+pub struct Task<T> {
+    slot: Arc<Mutex<Slot<T>>>,
+}
+
+impl<T> Future for Task<T> {
+    type Output = Result<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<T>> {
+        let mut slot = self.slot.lock().unwrap_or_else(|e| e.into_inner());
+        match slot.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                slot.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Run `work` on a new thread. A panic in `work` resolves the task with
+/// an error. Dropping the task does not stop the work.
+pub fn spawn<T, F>(work: F) -> Task<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    let slot = Arc::new(Mutex::new(Slot {
+        result: None,
+        waker: None,
+    }));
+    let shared = Arc::clone(&slot);
+    shutdown::spawn_thread(move || {
+        let result = panic::catch_unwind(AssertUnwindSafe(work)).unwrap_or_else(|payload| {
+            Err(Error::Backend(format!(
+                "task panicked: {}",
+                panic_message(payload.as_ref())
+            )))
+        });
+        let mut slot = shared.lock().unwrap_or_else(|e| e.into_inner());
+        slot.result = Some(result);
+        if let Some(waker) = slot.waker.take() {
+            waker.wake();
+        }
+    });
+    Task { slot }
+}
+
+/// [`chat::chat`].
+pub async fn chat(prompt: String, options: ChatOptions) -> Result<String> {
+    spawn(move || chat::chat(&prompt, &options)).await
+}
+
+/// [`http::post_json`].
+pub async fn post_json(url: Url, body: String, options: RequestOptions) -> Result<Response> {
+    spawn(move || http::post_json(&url, &body, &options)).await
+}
+
+/// [`extract::extract_text`].
+pub async fn extract_text(path: PathBuf) -> Result<String> {
+    spawn(move || extract::extract_text(&path)).await
+}
+
+/// [`minutes::generate_minutes`], sending each part through the chat
+/// backend in `options.chat`.
+pub async fn generate_minutes(transcript: String, options: MinutesOptions) -> Result<Minutes> {
+    spawn(move || {
+        minutes::generate_minutes(
+            &transcript,
+            &options,
+            |prompt| chat::chat(prompt, &options.chat),
+            |_| {},
+        )
+    })
+    .await
+}
+
+/// [`compare::compare_corpus`] through the chat backend in `options`.
+pub async fn compare_corpus(
+    paths: Vec<PathBuf>,
+    criteria: String,
+    options: ChatOptions,
+) -> Result<Comparison> {
+    spawn(move || {
+        compare::compare_corpus(
+            &paths,
+            &criteria,
+            |prompt| chat::chat(prompt, &options),
+            |_| {},
+        )
+    })
+    .await
+}
@@ -0,0 +1,383 @@
+//! Named text normalization profiles, so one extraction can be cleaned up
+//! for each consumer that reads it.
+//!
+//! - `display` stays faithful to the document: only line endings, a byte
+//!   order mark and stray control characters are fixed.
+//! - `llm` also removes ANSI escapes, invisible characters and hyphenation
+//!   at line breaks, expands ligatures, straightens quotes, turns exotic
+//!   spaces into plain ones and collapses runs of blank space.
+//! - `search` goes on to lowercase, fold accents (`é` → `e`) and keep only
+//!   words, one line per paragraph, ready for stemming and indexing.
+//!
+//! A profile is a set of steps; hosts can [`define`] their own from
+//! JSON, starting from a built-in one.
+
+use std::sync::RwLock;
+
+use crate::chat::strip_ansi;
+use crate::error::{Error, Result};
+use crate::json::Value;
+use crate::words::words;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profile {
+    pub name: String,
+    /// `\r\n` and `\r` → `\n`, byte order marks and control characters
+    /// other than tabs and newlines removed.
+    pub line_endings: bool,
+    pub strip_ansi: bool,
+    /// Soft hyphens, zero-width spaces and joiners.
+    pub strip_invisible: bool,
+    /// `ﬁ` → `fi` and the like.
+    pub expand_ligatures: bool,
+    /// Curly quotes and apostrophes → `'` and `"`.
+    pub straighten_quotes: bool,
+    /// Non-breaking and other Unicode spaces → ` `.
+    pub plain_spaces: bool,
+    /// `exam-\nple` → `example`.
+    pub join_hyphenated: bool,
+    /// Trailing spaces dropped, runs of spaces and of blank lines
+    /// collapsed.
+    pub collapse_whitespace: bool,
+    pub lowercase: bool,
+    pub fold_diacritics: bool,
+    /// Only words, separated by single spaces, one line per paragraph.
+    pub words_only: bool,
+}
+
+const STEPS: [&str; 11] = [
+    "line_endings",
+    "strip_ansi",
+    "strip_invisible",
+    "expand_ligatures",
+    "straighten_quotes",
+    "plain_spaces",
+    "join_hyphenated",
+    "collapse_whitespace",
+    "lowercase",
+    "fold_diacritics",
+    "words_only",
+];
+
+impl Profile {
+    fn display() -> Profile {
+        Profile {
+            name: "display".into(),
+            line_endings: true,
+            strip_ansi: false,
+            strip_invisible: false,
+            expand_ligatures: false,
+            straighten_quotes: false,
+            plain_spaces: false,
+            join_hyphenated: false,
+            collapse_whitespace: false,
+            lowercase: false,
+            fold_diacritics: false,
+            words_only: false,
+        }
+    }
+
+    fn llm() -> Profile {
+        Profile {
+            name: "llm".into(),
+            strip_ansi: true,
+            strip_invisible: true,
+            expand_ligatures: true,
+            straighten_quotes: true,
+            plain_spaces: true,
+            join_hyphenated: true,
+            collapse_whitespace: true,
+            ..Profile::display()
+        }
+    }
+
+    fn search() -> Profile {
+        Profile {
+            name: "search".into(),
+            lowercase: true,
+            fold_diacritics: true,
+            words_only: true,
+            ..Profile::llm()
+        }
+    }
+
+    fn step(&mut self, name: &str) -> Option<&mut bool> {
+        Some(match name {
+            "line_endings" => &mut self.line_endings,
+            "strip_ansi" => &mut self.strip_ansi,
+            "strip_invisible" => &mut self.strip_invisible,
+            "expand_ligatures" => &mut self.expand_ligatures,
+            "straighten_quotes" => &mut self.straighten_quotes,
+            "plain_spaces" => &mut self.plain_spaces,
+            "join_hyphenated" => &mut self.join_hyphenated,
+            "collapse_whitespace" => &mut self.collapse_whitespace,
+            "lowercase" => &mut self.lowercase,
+            "fold_diacritics" => &mut self.fold_diacritics,
+            "words_only" => &mut self.words_only,
+            _ => return None,
+        })
+    }
+
+    /// Read `{"name": "titles", "base": "llm", "lowercase": true}`: the
+    /// steps of `base` (default `display`) with the named ones switched on
+    /// or off.
+    pub fn from_json(value: &Value) -> Result<Profile> {
+        let name = value
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or_else(|| Error::Parse("a normalization profile needs a name".into()))?;
+        let base = value
+            .get("base")
+            .and_then(Value::as_str)
+            .unwrap_or("display");
+        let mut profile = profile(base)?;
+        profile.name = name.to_string();
+        if let Value::Object(pairs) = value {
+            for (key, setting) in pairs {
+                if key == "name" || key == "base" {
+                    continue;
+                }
+                let on = setting
+                    .as_bool()
+                    .ok_or_else(|| Error::Parse(format!("\"{key}\" must be true or false")))?;
+                *profile
+                    .step(key)
+                    .ok_or_else(|| Error::Parse(format!("unknown normalization step '{key}'")))? =
+                    on;
+            }
+        }
+        Ok(profile)
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut copy = self.clone();
+        let mut pairs = vec![("name", Value::from(self.name.as_str()))];
+        for step in STEPS {
+            let on = copy.step(step).is_some_and(|on| *on);
+            pairs.push((step, Value::from(on)));
+        }
+        Value::object(pairs)
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        // Escape sequences go before the control characters they start with.
+        if self.strip_ansi {
+            text = strip_ansi(&text);
+        }
+        if self.line_endings {
+            text = text.replace("\r\n", "\n").replace('\r', "\n");
+            text.retain(|c| (!c.is_control() || c == '\n' || c == '\t') && c != '\u{feff}');
+        }
+        if self.strip_invisible {
+            text.retain(|c| {
+                !matches!(
+                    c,
+                    '\u{ad}' | '\u{200b}'..='\u{200d}' | '\u{2060}' | '\u{feff}'
+                )
+            });
+        }
+        if self.expand_ligatures || self.straighten_quotes || self.plain_spaces {
+            text = text
+                .chars()
+                .fold(String::with_capacity(text.len()), |mut out, c| {
+                    match self.replacement(c) {
+                        Some(with) => out.push_str(with),
+                        None => out.push(c),
+                    }
+                    out
+                });
+        }
+        if self.join_hyphenated {
+            text = join_hyphenated(&text);
+        }
+        if self.collapse_whitespace {
+            text = collapse_whitespace(&text);
+        }
+        if self.lowercase {
+            text = text.to_lowercase();
+        }
+        if self.fold_diacritics {
+            text = text
+                .chars()
+                .fold(String::with_capacity(text.len()), |mut out, c| {
+                    match fold(c) {
+                        Some(base) if c.is_uppercase() => out.push_str(&base.to_uppercase()),
+                        Some(base) => out.push_str(base),
+                        None => out.push(c),
+                    }
+                    out
+                });
+        }
+        if self.words_only {
+            text = text
+                .split("\n\n")
+                .map(|paragraph| words(paragraph).join(" "))
+                .filter(|line| !line.is_empty())
+                .collect::<Vec<_>>()
+                .join("\n");
+        }
+        text
+    }
+
+    fn replacement(&self, c: char) -> Option<&'static str> {
+        let (enabled, with) = match c {
+            'ﬀ' => (self.expand_ligatures, "ff"),
+            'ﬁ' => (self.expand_ligatures, "fi"),
+            'ﬂ' => (self.expand_ligatures, "fl"),
+            'ﬃ' => (self.expand_ligatures, "ffi"),
+            'ﬄ' => (self.expand_ligatures, "ffl"),
+            'ﬅ' | 'ﬆ' => (self.expand_ligatures, "st"),
+            '‘' | '’' | '‚' | '‛' | '′' => (self.straighten_quotes, "'"),
+            '“' | '”' | '„' | '‟' | '″' | '«' | '»' => (self.straighten_quotes, "\""),
+            '\u{a0}' | '\u{2000}'..='\u{200a}' | '\u{202f}' | '\u{205f}' | '\u{3000}' => {
+                (self.plain_spaces, " ")
+            }
+            _ => return None,
+        };
+        enabled.then_some(with)
+    }
+}
+
+/// Rejoin words split across lines by a hyphen. The break counts as one
+/// when a letter comes before it and a lowercase letter after; otherwise
+/// the hyphen stays.
+fn join_hyphenated(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(at) = rest.find("-\n") {
+        let before = &rest[..at];
+        let after = &rest[at + 2..];
+        let joins = before.chars().next_back().is_some_and(char::is_alphabetic)
+            && after.chars().next().is_some_and(char::is_lowercase);
+        out.push_str(before);
+        if joins {
+            // Move the rest of the word up to this line.
+            let end = after.find(char::is_whitespace).unwrap_or(after.len());
+            out.push_str(&after[..end]);
+            rest = &after[end..];
+            if let Some(next) = rest.strip_prefix([' ', '\n']) {
+                out.push('\n');
+                rest = next;
+            }
+        } else {
+            out.push_str("-\n");
+            rest = after;
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    let mut lines = Vec::new();
+    let mut blank = false;
+    for line in text.lines() {
+        let mut collapsed = String::with_capacity(line.len());
+        let indent = line.len() - line.trim_start_matches([' ', '\t']).len();
+        collapsed.push_str(&line[..indent]);
+        let mut space = false;
+        for c in line[indent..].trim_end().chars() {
+            if c == ' ' || c == '\t' {
+                space = true;
+                continue;
+            }
+            if space {
+                collapsed.push(' ');
+                space = false;
+            }
+            collapsed.push(c);
+        }
+        if collapsed.trim().is_empty() {
+            if !blank && !lines.is_empty() {
+                lines.push(String::new());
+            }
+            blank = true;
+        } else {
+            lines.push(collapsed);
+            blank = false;
+        }
+    }
+    while lines.last().is_some_and(String::is_empty) {
+        lines.pop();
+    }
+    lines.join("\n")
+}
+
+/// The unaccented, lowercase form of a Latin letter.
+pub(crate) fn fold(c: char) -> Option<&'static str> {
+    const GROUPS: [(&str, &str); 24] = [
+        ("àáâãäåāăą", "a"),
+        ("çćĉċč", "c"),
+        ("ďđ", "d"),
+        ("èéêëēĕėęě", "e"),
+        ("ĝğġģ", "g"),
+        ("ĥħ", "h"),
+        ("ìíîïĩīĭįı", "i"),
+        ("ĵ", "j"),
+        ("ķ", "k"),
+        ("ĺļľŀł", "l"),
+        ("ñńņňŉ", "n"),
+        ("òóôõöøōŏő", "o"),
+        ("ŕŗř", "r"),
+        ("śŝşš", "s"),
+        ("ţťŧ", "t"),
+        ("ùúûüũūŭůűų", "u"),
+        ("ŵ", "w"),
+        ("ýÿŷ", "y"),
+        ("źżž", "z"),
+        ("ß", "ss"),
+        ("æ", "ae"),
+        ("œ", "oe"),
+        ("þ", "th"),
+        ("ð", "d"),
+    ];
+    if c.is_ascii() {
+        return None;
+    }
+    let lower = c.to_lowercase().next()?;
+    GROUPS
+        .iter()
+        .find(|(letters, _)| letters.contains(lower))
+        .map(|(_, base)| *base)
+}
+
+static DEFINED: RwLock<Vec<Profile>> = RwLock::new(Vec::new());
+
+/// Add or replace a host-defined profile. Built-in names cannot be taken.
+pub fn define(profile: Profile) -> Result<()> {
+    if ["display", "llm", "search"].contains(&profile.name.as_str()) {
+        return Err(Error::Parse(format!(
+            "'{}' is a built-in normalization profile",
+            profile.name
+        )));
+    }
+    let mut defined = DEFINED.write().unwrap_or_else(|e| e.into_inner());
+    defined.retain(|existing| existing.name != profile.name);
+    defined.push(profile);
+    Ok(())
+}
+
+/// The built-in or defined profile called `name`.
+pub fn profile(name: &str) -> Result<Profile> {
+    match name {
+        "display" => Ok(Profile::display()),
+        "llm" => Ok(Profile::llm()),
+        "search" => Ok(Profile::search()),
+        _ => DEFINED
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .find(|profile| profile.name == name)
+            .cloned()
+            .ok_or_else(|| Error::Parse(format!("unknown normalization profile '{name}'"))),
+    }
+}
+
+/// `text` under each named profile, in order.
+pub fn normalize_all(text: &str, names: &[&str]) -> Result<Vec<(String, String)>> {
+    names
+        .iter()
+        .map(|name| Ok((name.to_string(), profile(name)?.apply(text))))
+        .collect()
+}
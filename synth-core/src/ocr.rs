@@ -0,0 +1,130 @@
+//! Text from images — screenshots and scans of pages — through the system
+//! `tesseract`, so they extract like any other document.
+//!
+//! By default `tesseract` is looked up on `PATH` and reads English; without
+//! it installed, images are unsupported input.
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::backend::wait_with_timeout;
+use crate::error::{Error, Result};
+use crate::json::Value;
+use crate::shutdown::Process;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    pub command: String,
+    /// Tesseract language codes, e.g. "eng" or "eng+deu".
+    pub language: String,
+    pub timeout: Option<Duration>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            command: "tesseract".into(),
+            language: "eng".into(),
+            timeout: Some(Duration::from_secs(120)),
+        }
+    }
+}
+
+impl Config {
+    /// Read `{"command": "tesseract", "language": "eng", "timeout_secs":
+    /// 120}`; every key is optional.
+    pub fn from_json(value: &Value) -> Result<Self> {
+        let text = |key| value.get(key).and_then(Value::as_str);
+        let defaults = Config::default();
+        Ok(Config {
+            command: text("command").map_or(defaults.command, str::to_string),
+            language: text("language").map_or(defaults.language, str::to_string),
+            timeout: match value.get("timeout_secs").and_then(Value::as_f64) {
+                Some(secs) if secs > 0.0 => Some(Duration::from_secs_f64(secs)),
+                Some(_) => None,
+                None => defaults.timeout,
+            },
+        })
+    }
+}
+
+static CONFIG: Mutex<Option<Config>> = Mutex::new(None);
+static SCRATCH: AtomicU64 = AtomicU64::new(0);
+
+fn lock() -> MutexGuard<'static, Option<Config>> {
+    CONFIG.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Replace (or with `None`, restore) the default command and language.
+pub fn configure(config: Option<Config>) {
+    *lock() = config;
+}
+
+/// Whether a file name looks like an image tesseract reads.
+pub fn is_image(path: &Path) -> bool {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase);
+    matches!(
+        ext.as_deref(),
+        Some("png" | "jpg" | "jpeg" | "tif" | "tiff" | "bmp" | "gif" | "webp")
+    )
+}
+
+/// The text recognised in the image at `path`, which the caller has
+/// checked against the policy. Blank lines between blocks are kept; the
+/// page break tesseract ends with is not.
+pub(crate) fn recognize(path: &Path) -> Result<String> {
+    let config = lock().clone().unwrap_or_default();
+    let child = Process::spawn(
+        Command::new(&config.command)
+            .arg(path)
+            .args(["stdout", "-l", &config.language])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped()),
+    )
+    .map_err(|err| match err {
+        Error::Io(e) if e.kind() == ErrorKind::NotFound => {
+            Error::Unsupported(format!("image text recognition needs {}", config.command))
+        }
+        other => other,
+    })?;
+    let (success, stdout) = wait_with_timeout(child, config.timeout)?;
+    if !success {
+        return Err(Error::Parse(format!(
+            "{} could not read {}",
+            config.command,
+            path.display()
+        )));
+    }
+    let lines: Vec<&str> = stdout.lines().map(str::trim_end).collect();
+    let text = lines.join("\n");
+    Ok(text
+        .trim_matches(|c: char| c.is_whitespace() || c == '\x0c')
+        .to_string()
+        + "\n")
+}
+
+/// [`recognize`] for an image already in memory.
+pub(crate) fn recognize_bytes(bytes: &[u8]) -> Result<String> {
+    let path = scratch_path();
+    fs::write(&path, bytes)?;
+    let result = recognize(&path);
+    let _ = fs::remove_file(&path);
+    result
+}
+
+fn scratch_path() -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "synth-ocr-{}-{}",
+        std::process::id(),
+        SCRATCH.fetch_add(1, Ordering::Relaxed)
+    ))
+}
@@ -0,0 +1,73 @@
+//! Open Packaging Conventions, the zip layout shared by .docx and .pptx:
+//! parts linked by relationship files.
+
+use crate::error::Result;
+use crate::xml;
+use crate::zip::Archive;
+
+/// One `<Relationship>` of a part.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Relationship {
+    pub id: String,
+    /// The last segment of the type URI, e.g. "slide" or "notesSlide".
+    pub kind: String,
+    /// The target part's name in the archive, or the URL for external
+    /// targets.
+    pub target: String,
+    pub external: bool,
+}
+
+/// The relationships file of `part`: `word/document.xml` has
+/// `word/_rels/document.xml.rels`.
+pub fn rels_name(part: &str) -> String {
+    match part.rsplit_once('/') {
+        Some((dir, file)) => format!("{dir}/_rels/{file}.rels"),
+        None => format!("_rels/{part}.rels"),
+    }
+}
+
+/// The relationships of `part`; none if it has no relationships file.
+pub fn relationships(archive: &Archive, part: &str) -> Result<Vec<Relationship>> {
+    let name = rels_name(part);
+    if !archive.contains(&name) {
+        return Ok(Vec::new());
+    }
+    let root = xml::parse(&archive.read_string(&name)?, false)?;
+    let base = part.rsplit_once('/').map_or("", |(dir, _)| dir);
+    Ok(root
+        .elements()
+        .filter(|el| el.name == "Relationship")
+        .filter_map(|el| {
+            let external = el.attr("TargetMode") == Some("External");
+            let target = el.attr("Target")?;
+            Some(Relationship {
+                id: el.attr("Id")?.to_string(),
+                kind: el.attr("Type")?.rsplit('/').next()?.to_string(),
+                target: if external {
+                    target.to_string()
+                } else {
+                    resolve(base, target)
+                },
+                external,
+            })
+        })
+        .collect())
+}
+
+/// `target` relative to the directory `base`, as an archive entry name.
+pub fn resolve(base: &str, target: &str) -> String {
+    let mut parts: Vec<&str> = match target.strip_prefix('/') {
+        Some(_) => Vec::new(),
+        None => base.split('/').filter(|p| !p.is_empty()).collect(),
+    };
+    for part in target.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
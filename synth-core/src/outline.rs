@@ -0,0 +1,207 @@
+//! Heading outline for markdown and plain-text documents.
+//!
+//! Form feeds mark page breaks, as in text extracted from a paginated
+//! document; in text that has them each heading carries its page.
+
+use crate::json::Value;
+use crate::segment::utf16_offsets;
+
+/// A heading and the headings nested beneath it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heading {
+    pub level: u8,
+    pub text: String,
+    /// Character offset of the heading line from the start of the document.
+    pub offset: usize,
+    /// `offset` in UTF-16 code units.
+    pub offset_utf16: usize,
+    /// 1-based page, when the text has form-feed page breaks.
+    pub page: Option<usize>,
+    pub children: Vec<Heading>,
+}
+
+impl Heading {
+    pub fn to_json(&self) -> Value {
+        Value::object([
+            ("level", Value::from(self.level as usize)),
+            ("text", Value::from(self.text.as_str())),
+            ("offset", Value::from(self.offset)),
+            ("offset_utf16", Value::from(self.offset_utf16)),
+            ("page", self.page.map_or(Value::Null, Value::from)),
+            (
+                "children",
+                Value::Array(self.children.iter().map(Heading::to_json).collect()),
+            ),
+        ])
+    }
+}
+
+/// Collect ATX (`# Title`) and setext (`Title` / `===`) headings as a tree.
+/// Front matter and fenced code blocks are skipped.
+pub fn outline(text: &str) -> Vec<Heading> {
+    nest(flat_headings(text))
+}
+
+/// Headings in document order, without nesting.
+pub fn flat_headings(text: &str) -> Vec<Heading> {
+    let mut headings = Vec::new();
+    let mut offset = 0;
+    let mut fence: Option<char> = None;
+    let mut in_front_matter = false;
+    // Start offset, page and text of the paragraph a setext underline
+    // would promote.
+    let mut paragraph: Option<(usize, Option<usize>, String)> = None;
+    let paged = text.contains('\u{c}');
+    let mut breaks = 0;
+
+    for (index, raw_line) in text.split('\n').enumerate() {
+        // A line may open with the form feed that starts its page.
+        let line = raw_line.trim_start_matches('\u{c}');
+        let leading = raw_line.len() - line.len();
+        let line_offset = offset + leading;
+        let page = paged.then_some(breaks + leading + 1);
+        offset += raw_line.chars().count() + 1;
+        breaks += raw_line.matches('\u{c}').count();
+        let line = line.trim_end_matches('\r');
+        let trimmed = line.trim();
+
+        if index == 0 && trimmed == "---" {
+            in_front_matter = true;
+            continue;
+        }
+        if in_front_matter {
+            if trimmed == "---" || trimmed == "..." {
+                in_front_matter = false;
+            }
+            continue;
+        }
+
+        if let Some(marker) = fence_marker(line) {
+            match fence {
+                Some(open) if open == marker => fence = None,
+                Some(_) => {}
+                None => fence = Some(marker),
+            }
+            paragraph = None;
+            continue;
+        }
+        if fence.is_some() {
+            continue;
+        }
+
+        if trimmed.is_empty() {
+            paragraph = None;
+            continue;
+        }
+
+        if let Some((level, title)) = atx_heading(line) {
+            headings.push(Heading {
+                level,
+                text: title,
+                offset: line_offset,
+                offset_utf16: 0,
+                page,
+                children: Vec::new(),
+            });
+            paragraph = None;
+            continue;
+        }
+
+        if let Some(level) = setext_level(trimmed) {
+            if let Some((start, page, title)) = paragraph.take() {
+                headings.push(Heading {
+                    level,
+                    text: title,
+                    offset: start,
+                    offset_utf16: 0,
+                    page,
+                    children: Vec::new(),
+                });
+                continue;
+            }
+        }
+
+        match paragraph.as_mut() {
+            // A page break ends the paragraph.
+            Some((_, start_page, title)) if *start_page == page => {
+                title.push(' ');
+                title.push_str(trimmed);
+            }
+            _ => paragraph = Some((line_offset, page, trimmed.to_string())),
+        }
+    }
+    let utf16 = utf16_offsets(text);
+    for heading in &mut headings {
+        heading.offset_utf16 = utf16[heading.offset];
+    }
+    headings
+}
+
+fn indent_ok(line: &str) -> Option<&str> {
+    let rest = line.trim_start_matches(' ');
+    (line.len() - rest.len() <= 3).then_some(rest)
+}
+
+fn fence_marker(line: &str) -> Option<char> {
+    let rest = indent_ok(line)?;
+    if rest.starts_with("```") {
+        Some('`')
+    } else if rest.starts_with("~~~") {
+        Some('~')
+    } else {
+        None
+    }
+}
+
+fn atx_heading(line: &str) -> Option<(u8, String)> {
+    let rest = indent_ok(line)?;
+    let hashes = rest.chars().take_while(|&c| c == '#').count();
+    if hashes == 0 || hashes > 6 {
+        return None;
+    }
+    let after = &rest[hashes..];
+    if !after.is_empty() && !after.starts_with([' ', '\t']) {
+        return None;
+    }
+    let mut title = after.trim();
+    // Optional closing sequence: "## Title ##"
+    let without_closing = title.trim_end_matches('#');
+    if without_closing.is_empty() || without_closing.ends_with([' ', '\t']) {
+        title = without_closing.trim_end();
+    }
+    Some((hashes as u8, title.to_string()))
+}
+
+fn setext_level(trimmed: &str) -> Option<u8> {
+    if trimmed.chars().all(|c| c == '=') {
+        Some(1)
+    } else if trimmed.chars().all(|c| c == '-') {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+fn nest(flat: Vec<Heading>) -> Vec<Heading> {
+    let mut roots = Vec::new();
+    let mut stack: Vec<Heading> = Vec::new();
+    for heading in flat {
+        while stack.last().is_some_and(|top| top.level >= heading.level) {
+            close(&mut stack, &mut roots);
+        }
+        stack.push(heading);
+    }
+    while !stack.is_empty() {
+        close(&mut stack, &mut roots);
+    }
+    roots
+}
+
+fn close(stack: &mut Vec<Heading>, roots: &mut Vec<Heading>) {
+    if let Some(done) = stack.pop() {
+        match stack.last_mut() {
+            Some(parent) => parent.children.push(done),
+            None => roots.push(done),
+        }
+    }
+}
@@ -0,0 +1,195 @@
+//! Plain-text PDF export using the built-in Courier fonts.
+//!
+//! Courier is one of the standard 14 PDF fonts, so nothing is embedded and
+//! its fixed advance width makes wrapping exact. Text outside WinAnsi is
+//! replaced with `?`.
+
+use std::fs;
+use std::path::Path;
+
+use crate::error::Result;
+
+const PAGE_WIDTH: f32 = 612.0;
+const PAGE_HEIGHT: f32 = 792.0;
+const MARGIN: f32 = 72.0;
+const FONT_SIZE: f32 = 10.0;
+const LEADING: f32 = 12.0;
+/// Courier glyphs are 600/1000 em wide.
+const COLUMNS: usize = ((PAGE_WIDTH - 2.0 * MARGIN) / (FONT_SIZE * 0.6)) as usize;
+const ROWS: usize = ((PAGE_HEIGHT - 2.0 * MARGIN) / LEADING) as usize;
+
+struct Line {
+    text: String,
+    bold: bool,
+}
+
+/// Render `content` as a Letter-size PDF at `out_path`. Markdown heading
+/// lines (`# Title`) are set in bold with the marker removed.
+pub fn write_pdf(content: &str, out_path: &Path) -> Result<()> {
+    fs::write(out_path, render_pdf(content))?;
+    Ok(())
+}
+
+/// Build the PDF file bytes for `content`.
+pub fn render_pdf(content: &str) -> Vec<u8> {
+    let lines = layout(content);
+    let pages: Vec<&[Line]> = if lines.is_empty() {
+        vec![&[]]
+    } else {
+        lines.chunks(ROWS).collect()
+    };
+
+    // Objects: 1 catalog, 2 page tree, 3-4 fonts, then a page and its
+    // content stream per page.
+    let mut objects: Vec<Vec<u8>> = Vec::new();
+    objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+    let kids: Vec<String> = (0..pages.len())
+        .map(|i| format!("{} 0 R", 5 + 2 * i))
+        .collect();
+    objects.push(
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            pages.len()
+        )
+        .into_bytes(),
+    );
+    for font in ["Courier", "Courier-Bold"] {
+        objects.push(
+            format!(
+                "<< /Type /Font /Subtype /Type1 /BaseFont /{font} /Encoding /WinAnsiEncoding >>"
+            )
+            .into_bytes(),
+        );
+    }
+    for (i, page) in pages.iter().enumerate() {
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {PAGE_WIDTH} {PAGE_HEIGHT}] \
+                 /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                6 + 2 * i
+            )
+            .into_bytes(),
+        );
+        let stream = page_stream(page);
+        let mut object = format!("<< /Length {} >>\nstream\n", stream.len()).into_bytes();
+        object.extend_from_slice(&stream);
+        object.extend_from_slice(b"\nendstream");
+        objects.push(object);
+    }
+
+    let mut out = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+        out.extend_from_slice(object);
+        out.extend_from_slice(b"\nendobj\n");
+    }
+    let xref = out.len();
+    out.extend_from_slice(
+        format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes(),
+    );
+    for offset in offsets {
+        out.extend_from_slice(format!("{offset:010} 00000 n \n").as_bytes());
+    }
+    out.extend_from_slice(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+            objects.len() + 1
+        )
+        .as_bytes(),
+    );
+    out
+}
+
+fn page_stream(lines: &[Line]) -> Vec<u8> {
+    let top = PAGE_HEIGHT - MARGIN - FONT_SIZE;
+    let mut stream =
+        format!("BT\n/F1 {FONT_SIZE} Tf\n{LEADING} TL\n{MARGIN} {top} Td\n").into_bytes();
+    for line in lines {
+        if line.bold {
+            stream.extend_from_slice(format!("/F2 {FONT_SIZE} Tf\n").as_bytes());
+        }
+        stream.push(b'(');
+        stream.extend_from_slice(&encode(&line.text));
+        stream.extend_from_slice(b") Tj T*\n");
+        if line.bold {
+            stream.extend_from_slice(format!("/F1 {FONT_SIZE} Tf\n").as_bytes());
+        }
+    }
+    stream.extend_from_slice(b"ET");
+    stream
+}
+
+/// Wrap paragraphs to the page width, breaking at spaces where possible.
+fn layout(content: &str) -> Vec<Line> {
+    let mut lines = Vec::new();
+    for raw in content.lines() {
+        let raw = raw.trim_end().replace('\t', "    ");
+        let heading = raw.trim_start().starts_with('#')
+            && raw.trim_start().trim_start_matches('#').starts_with(' ');
+        let text = if heading {
+            raw.trim_start().trim_start_matches('#').trim().to_string()
+        } else {
+            raw
+        };
+        let chars: Vec<char> = text.chars().collect();
+        if chars.is_empty() {
+            lines.push(Line {
+                text: String::new(),
+                bold: false,
+            });
+            continue;
+        }
+        let mut start = 0;
+        while start < chars.len() {
+            let mut end = (start + COLUMNS).min(chars.len());
+            if end < chars.len() {
+                if let Some(space) = chars[start..end].iter().rposition(|c| *c == ' ') {
+                    if space > 0 {
+                        end = start + space + 1;
+                    }
+                }
+            }
+            lines.push(Line {
+                text: chars[start..end]
+                    .iter()
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string(),
+                bold: heading,
+            });
+            start = end;
+        }
+    }
+    lines
+}
+
+/// Encode to WinAnsi bytes and escape PDF string delimiters.
+fn encode(text: &str) -> Vec<u8> {
+    let mut out = Vec::with_capacity(text.len());
+    for c in text.chars() {
+        let byte = match c {
+            '\\' | '(' | ')' => {
+                out.push(b'\\');
+                c as u8
+            }
+            ' '..='~' => c as u8,
+            '\u{A0}'..='\u{FF}' => c as u32 as u8,
+            '€' => 0x80,
+            '…' => 0x85,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            '•' => 0x95,
+            '–' => 0x96,
+            '—' => 0x97,
+            '™' => 0x99,
+            _ => b'?',
+        };
+        out.push(byte);
+    }
+    out
+}
@@ -0,0 +1,217 @@
+//! The annotations reviewers leave on a PDF (ISO 32000-1, section 12.5):
+//! highlights, underlines, strike-outs and squiggles with the text they
+//! mark, sticky notes and text boxes, each with its page and author.
+//!
+//! The marked text is the text drawn inside the annotation's quadrilaterals
+//! (`QuadPoints`), else its rectangle, line by line. Links, form widgets,
+//! pop-up windows and drawings are left out.
+
+use crate::error::Result;
+use crate::json::Value;
+use crate::pdf_objects::{text_string, Dictionary, Object, Page, Pdf};
+use crate::pdf_text::{self, Span};
+
+/// A gap wider than this, in ems, between marked characters is a space.
+const SPACE: f64 = 0.15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnnotationKind {
+    Highlight,
+    Underline,
+    StrikeOut,
+    Squiggly,
+    /// A sticky note (`Text`).
+    Note,
+    /// Text written on the page (`FreeText`).
+    FreeText,
+}
+
+impl AnnotationKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            AnnotationKind::Highlight => "highlight",
+            AnnotationKind::Underline => "underline",
+            AnnotationKind::StrikeOut => "strike_out",
+            AnnotationKind::Squiggly => "squiggly",
+            AnnotationKind::Note => "note",
+            AnnotationKind::FreeText => "free_text",
+        }
+    }
+
+    fn from_subtype(subtype: &str) -> Option<AnnotationKind> {
+        Some(match subtype {
+            "Highlight" => AnnotationKind::Highlight,
+            "Underline" => AnnotationKind::Underline,
+            "StrikeOut" => AnnotationKind::StrikeOut,
+            "Squiggly" => AnnotationKind::Squiggly,
+            "Text" => AnnotationKind::Note,
+            "FreeText" => AnnotationKind::FreeText,
+            _ => return None,
+        })
+    }
+
+    /// Whether it marks text already on the page.
+    fn is_markup(self) -> bool {
+        !matches!(self, AnnotationKind::Note | AnnotationKind::FreeText)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    pub kind: AnnotationKind,
+    /// Counted from 1.
+    pub page: usize,
+    pub author: Option<String>,
+    /// The text marked on the page; empty for notes and text boxes.
+    pub quote: String,
+    /// What the reviewer wrote: the note, or the comment on a markup.
+    pub comment: String,
+}
+
+impl Annotation {
+    pub fn to_json(&self) -> Value {
+        Value::object(vec![
+            ("kind", Value::from(self.kind.name())),
+            ("page", Value::from(self.page)),
+            (
+                "author",
+                self.author.as_deref().map_or(Value::Null, Value::from),
+            ),
+            ("quote", Value::from(self.quote.as_str())),
+            ("comment", Value::from(self.comment.as_str())),
+        ])
+    }
+}
+
+pub fn annotations_to_json(annotations: &[Annotation]) -> Value {
+    Value::Array(annotations.iter().map(Annotation::to_json).collect())
+}
+
+/// The annotations of every page, in page order and, on a page, in the
+/// order the page lists them.
+pub fn annotations(pdf: &Pdf) -> Result<Vec<Annotation>> {
+    let pages = pdf.pages()?;
+    let mut spans = Vec::with_capacity(pages.len());
+    for page in &pages {
+        spans.push(pdf_text::page_spans(pdf, page)?);
+    }
+    read(pdf, &pages, &spans)
+}
+
+/// As [`annotations`], with the pages' text already read.
+pub(crate) fn read(pdf: &Pdf, pages: &[Page], spans: &[Vec<Span>]) -> Result<Vec<Annotation>> {
+    let mut annotations = Vec::new();
+    for (index, (page, spans)) in pages.iter().zip(spans).enumerate() {
+        let annots = match page.dict.get("Annots") {
+            Some(annots) => pdf.resolve(annots)?,
+            None => continue,
+        };
+        for annot in annots.as_array().unwrap_or(&[]) {
+            let dict = pdf.dict(Some(annot))?;
+            let Some(kind) = dict.name("Subtype").and_then(AnnotationKind::from_subtype) else {
+                continue;
+            };
+            let string = |key: &str| -> Result<Option<String>> {
+                Ok(match dict.get(key) {
+                    Some(obj) => pdf.resolve(obj)?.as_bytes().map(text_string),
+                    None => None,
+                })
+            };
+            let quote = match kind.is_markup() {
+                true => marked_text(spans, &boxes(pdf, &dict)?),
+                false => String::new(),
+            };
+            annotations.push(Annotation {
+                kind,
+                page: index + 1,
+                author: string("T")?.filter(|author| !author.is_empty()),
+                quote,
+                comment: string("Contents")?.unwrap_or_default().trim().to_string(),
+            });
+        }
+    }
+    Ok(annotations)
+}
+
+/// `[left bottom right top]` of the annotation's quadrilaterals, else of
+/// its rectangle.
+fn boxes(pdf: &Pdf, dict: &Dictionary) -> Result<Vec<[f64; 4]>> {
+    let numbers = |key: &str| -> Result<Vec<f64>> {
+        Ok(match dict.get(key) {
+            Some(obj) => pdf
+                .resolve(obj)?
+                .as_array()
+                .unwrap_or(&[])
+                .iter()
+                .filter_map(Object::as_f64)
+                .collect(),
+            None => Vec::new(),
+        })
+    };
+    let quads = numbers("QuadPoints")?;
+    if quads.len() >= 8 {
+        return Ok(quads
+            .chunks_exact(8)
+            .map(|quad| {
+                let xs = [quad[0], quad[2], quad[4], quad[6]];
+                let ys = [quad[1], quad[3], quad[5], quad[7]];
+                let min = |v: [f64; 4]| v.into_iter().fold(f64::INFINITY, f64::min);
+                let max = |v: [f64; 4]| v.into_iter().fold(f64::NEG_INFINITY, f64::max);
+                [min(xs), min(ys), max(xs), max(ys)]
+            })
+            .collect());
+    }
+    Ok(match numbers("Rect")?[..] {
+        [a, b, c, d] => vec![[a.min(c), b.min(d), a.max(c), b.max(d)]],
+        _ => Vec::new(),
+    })
+}
+
+/// The characters whose middle is inside one of `boxes`, box by box and
+/// left to right in each.
+fn marked_text(spans: &[Span], boxes: &[[f64; 4]]) -> String {
+    let mut lines = Vec::new();
+    for &[left, bottom, right, top] in boxes {
+        // (start, end, size, char) of each marked character.
+        let mut marked: Vec<(f64, f64, f64, char)> = Vec::new();
+        for span in spans {
+            let middle = span.y + span.size * 0.3;
+            if middle < bottom || middle > top {
+                continue;
+            }
+            let chars: Vec<char> = span.text.chars().collect();
+            for (i, &c) in chars.iter().enumerate() {
+                let start = span.starts.get(i).copied().unwrap_or(span.x);
+                let end = span.starts.get(i + 1).copied().unwrap_or(span.end);
+                let x = (start + end) / 2.0;
+                if x >= left && x <= right {
+                    marked.push((start, end, span.size, c));
+                }
+            }
+        }
+        marked.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut line = String::new();
+        let mut last_end: Option<f64> = None;
+        for (start, end, size, c) in marked {
+            if let Some(last_end) = last_end {
+                if start - last_end > SPACE * size && !line.ends_with(' ') && c != ' ' {
+                    line.push(' ');
+                }
+            }
+            line.push(c);
+            last_end = Some(end);
+        }
+        let line = line.trim();
+        if !line.is_empty() {
+            lines.push(line.to_string());
+        }
+    }
+    let mut quote = String::new();
+    for line in lines {
+        match quote.is_empty() {
+            true => quote = line,
+            false => pdf_text::join_line(&mut quote, &line),
+        }
+    }
+    quote
+}
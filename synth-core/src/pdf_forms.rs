@@ -0,0 +1,179 @@
+//! The fields of a PDF's interactive form (AcroForm, ISO 32000-1, section
+//! 12.7) and their values, so a filled-in form is read as data rather
+//! than as the labels around its boxes.
+//!
+//! Fields are named as Acrobat names them, their ancestors' partial names
+//! joined with `.`. Text fields and lists give their text, check boxes
+//! whether they are checked, and radio buttons the export value of the
+//! chosen button. Push buttons and signature fields hold no data and are
+//! left out, as are forms defined in XFA only.
+
+use std::collections::HashSet;
+
+use crate::docx_text::{FormField, FormKind, FormValue};
+use crate::error::{Error, Result};
+use crate::pdf_objects::{text_string, Dictionary, Object, Pdf};
+
+/// Field trees nested deeper than this are rejected.
+const MAX_DEPTH: usize = 32;
+
+/// Field flags (table 226 and 227).
+const RADIO: i64 = 1 << 15;
+const PUSH_BUTTON: i64 = 1 << 16;
+
+/// What a field inherits from its ancestors (section 12.7.3.1).
+#[derive(Clone, Default)]
+struct Inherited {
+    name: String,
+    kind: Option<String>,
+    flags: i64,
+    value: Option<Object>,
+}
+
+/// The form's fields, in the order of the form's field tree.
+pub fn fields(pdf: &Pdf) -> Result<Vec<FormField>> {
+    let catalog = pdf.catalog()?;
+    let form = pdf.dict(catalog.get("AcroForm"))?;
+    let roots = match form.get("Fields") {
+        Some(roots) => pdf.resolve(roots)?,
+        None => return Ok(Vec::new()),
+    };
+    let mut fields = Vec::new();
+    let mut seen = HashSet::new();
+    for root in roots.as_array().unwrap_or(&[]) {
+        collect(pdf, root, &Inherited::default(), 0, &mut seen, &mut fields)?;
+    }
+    Ok(fields)
+}
+
+fn collect(
+    pdf: &Pdf,
+    node: &Object,
+    inherited: &Inherited,
+    depth: usize,
+    seen: &mut HashSet<u32>,
+    fields: &mut Vec<FormField>,
+) -> Result<()> {
+    if depth > MAX_DEPTH {
+        return Err(Error::Parse("PDF form field tree too deep".into()));
+    }
+    if let Some(r) = node.as_reference() {
+        if !seen.insert(r.number) {
+            return Ok(());
+        }
+    }
+    let dict = pdf.dict(Some(node))?;
+    let mut field = inherited.clone();
+    if let Some(partial) = dict.get("T").and_then(Object::as_bytes) {
+        let partial = text_string(partial);
+        field.name = match field.name.is_empty() {
+            true => partial,
+            false => format!("{}.{partial}", field.name),
+        };
+    }
+    if let Some(kind) = dict.name("FT") {
+        field.kind = Some(kind.to_string());
+    }
+    if let Some(flags) = dict.get("Ff") {
+        field.flags = pdf.resolve(flags)?.as_i64().unwrap_or(0);
+    }
+    if let Some(value) = dict.get("V") {
+        field.value = Some(pdf.resolve(value)?);
+    }
+    // Kids without a partial name are the field's widgets, not fields.
+    let kids = match dict.get("Kids") {
+        Some(kids) => pdf.resolve(kids)?.as_array().unwrap_or(&[]).to_vec(),
+        None => Vec::new(),
+    };
+    let mut children = Vec::new();
+    let mut widgets = Vec::new();
+    for kid in &kids {
+        let kid_dict = pdf.dict(Some(kid))?;
+        match kid_dict.get("T") {
+            Some(_) => children.push(kid),
+            None => widgets.push(kid_dict),
+        }
+    }
+    for child in children {
+        collect(pdf, child, &field, depth + 1, seen, fields)?;
+    }
+    if dict.get("T").is_none() || (!kids.is_empty() && widgets.is_empty()) {
+        return Ok(());
+    }
+    if dict.get("Subtype").is_some() {
+        widgets.push(dict);
+    }
+    if let Some(found) = terminal(pdf, &field, &widgets)? {
+        fields.push(found);
+    }
+    Ok(())
+}
+
+/// The field `field` describes, or `None` for one that holds no data.
+fn terminal(pdf: &Pdf, field: &Inherited, widgets: &[Dictionary]) -> Result<Option<FormField>> {
+    let name = field.name.clone();
+    let value = field.value.as_ref();
+    Ok(Some(match field.kind.as_deref() {
+        Some("Tx") => FormField {
+            name,
+            kind: FormKind::Text,
+            value: text_value(value),
+        },
+        Some("Ch") => FormField {
+            name,
+            kind: FormKind::DropDown,
+            value: match value {
+                Some(Object::Array(items)) => {
+                    let mut chosen = Vec::new();
+                    for item in items {
+                        if let Some(text) = pdf.resolve(item)?.as_bytes() {
+                            chosen.push(text_string(text));
+                        }
+                    }
+                    match chosen.is_empty() {
+                        true => FormValue::Empty,
+                        false => FormValue::Text(chosen.join(", ")),
+                    }
+                }
+                value => text_value(value),
+            },
+        },
+        Some("Btn") if field.flags & PUSH_BUTTON != 0 => return Ok(None),
+        Some("Btn") if field.flags & RADIO != 0 => {
+            let state = state(value, widgets);
+            FormField {
+                name,
+                kind: FormKind::Radio,
+                value: match state {
+                    Some(state) => FormValue::Text(state),
+                    None => FormValue::Empty,
+                },
+            }
+        }
+        Some("Btn") => FormField {
+            name,
+            kind: FormKind::Checkbox,
+            value: FormValue::Checked(state(value, widgets).is_some()),
+        },
+        _ => return Ok(None),
+    }))
+}
+
+fn text_value(value: Option<&Object>) -> FormValue {
+    match value.and_then(Object::as_bytes).map(text_string) {
+        Some(text) if !text.is_empty() => FormValue::Text(text),
+        _ => FormValue::Empty,
+    }
+}
+
+/// The on state of a button field: its value, else the appearance state
+/// of a widget that is switched on; `None` when it is off.
+fn state(value: Option<&Object>, widgets: &[Dictionary]) -> Option<String> {
+    let on = |name: &str| (name != "Off" && !name.is_empty()).then(|| name.to_string());
+    match value {
+        Some(value) => value.as_name().and_then(on),
+        None => widgets
+            .iter()
+            .find_map(|widget| widget.name("AS").and_then(on)),
+    }
+}
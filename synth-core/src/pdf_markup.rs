@@ -0,0 +1,537 @@
+//! Highlights and notes written into a PDF, so the sources of an answer
+//! can be shown where they are in the original document.
+//!
+//! Each [`Mark`] is placed on a page at a rectangle, or over the first
+//! place a quote is drawn, with [`pdf_text`](crate::pdf_text)'s reading of
+//! the page: the quote is looked for in the order the text is drawn, with
+//! runs of white space matching any white space. Marks are annotations
+//! with their own appearance, so every viewer shows them, and they are
+//! appended to the file as an incremental update (ISO 32000-1, section
+//! 7.5.6): the original bytes are kept as they are, and a viewer can still
+//! go back to them. Damaged and encrypted files are refused.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::json::Value;
+use crate::pdf_objects::{encode_text_string, Dictionary, Object, Page, Pdf, Reference, Stream};
+use crate::pdf_text::{self, Span};
+
+/// A gap wider than this, in ems, between pieces of text on a line is a
+/// space.
+const SPACE: f64 = 0.15;
+/// How far a highlight reaches below and above the baseline, in ems.
+const DESCENT: f64 = 0.25;
+const ASCENT: f64 = 0.85;
+/// The side of a note's icon.
+const NOTE_SIZE: f64 = 20.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkKind {
+    Highlight,
+    /// A sticky note: an icon that opens to the comment.
+    Note,
+}
+
+/// Where a mark goes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    /// `[left bottom right top]` on the page, counted from 1, in its user
+    /// space; for a note, where its icon is.
+    Area { page: usize, rect: [f64; 4] },
+    /// Over the first place `text` is drawn, on `page` or any page; a
+    /// note goes just above its start.
+    Quote { text: String, page: Option<usize> },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mark {
+    pub kind: MarkKind,
+    pub target: Target,
+    pub comment: String,
+    pub author: String,
+    /// One of "yellow", "green", "cyan", "magenta", "blue" and "red".
+    pub color: String,
+}
+
+/// The colors a mark can have, as RGB.
+const COLORS: [(&str, [f64; 3]); 6] = [
+    ("yellow", [1.0, 0.92, 0.23]),
+    ("green", [0.56, 0.93, 0.56]),
+    ("cyan", [0.5, 0.9, 1.0]),
+    ("magenta", [1.0, 0.6, 0.9]),
+    ("blue", [0.55, 0.7, 1.0]),
+    ("red", [1.0, 0.55, 0.55]),
+];
+
+impl Mark {
+    /// Read `{"kind": "highlight", "quote": "...", "page": 2}` (page
+    /// optional) or `{"kind": "note", "page": 1, "rect": [72, 700, 92,
+    /// 720]}`, either with `"comment"`, `"author"` (default "Synth") and
+    /// `"color"` (default "yellow").
+    pub fn from_json(value: &Value) -> Result<Mark> {
+        let kind = match value.get("kind").and_then(Value::as_str) {
+            Some("highlight") => MarkKind::Highlight,
+            Some("note") => MarkKind::Note,
+            Some(other) => return Err(Error::Parse(format!("unknown mark '{other}'"))),
+            None => return Err(Error::Parse("mark needs a kind".into())),
+        };
+        let text = |key| value.get(key).and_then(Value::as_str).map(str::to_string);
+        let page = match value.get("page") {
+            None => None,
+            Some(page) => Some(
+                page.as_f64()
+                    .filter(|n| *n >= 1.0 && n.fract() == 0.0)
+                    .map(|n| n as usize)
+                    .ok_or_else(|| Error::Parse("mark page should be counted from 1".into()))?,
+            ),
+        };
+        let target = match (text("quote"), value.get("rect")) {
+            (Some(quote), None) => Target::Quote { text: quote, page },
+            (None, Some(rect)) => {
+                let numbers: Vec<f64> = rect
+                    .as_array()
+                    .map(|items| items.iter().filter_map(Value::as_f64).collect())
+                    .unwrap_or_default();
+                let (Ok(rect), Some(page)) = (<[f64; 4]>::try_from(numbers), page) else {
+                    return Err(Error::Parse(
+                        "mark needs a page and a rect of 4 numbers".into(),
+                    ));
+                };
+                Target::Area { page, rect }
+            }
+            _ => return Err(Error::Parse("mark needs a quote or a rect".into())),
+        };
+        let color = text("color").unwrap_or_else(|| "yellow".into());
+        if rgb(&color).is_none() {
+            return Err(Error::Parse(format!("unknown mark color '{color}'")));
+        }
+        Ok(Mark {
+            kind,
+            target,
+            comment: text("comment").unwrap_or_default(),
+            author: text("author").unwrap_or_else(|| "Synth".into()),
+            color,
+        })
+    }
+}
+
+fn rgb(color: &str) -> Option<[f64; 3]> {
+    COLORS
+        .iter()
+        .find(|(name, _)| *name == color)
+        .map(|(_, rgb)| *rgb)
+}
+
+/// Open `path`, add `marks` and save the result to `out`, which may be
+/// `path`. No file is written if any mark cannot be placed.
+pub fn annotate(path: &Path, marks: &[Mark], out: &Path) -> Result<()> {
+    let bytes = annotate_bytes(crate::policy::read_file(path)?, marks)?;
+    fs::write(out, bytes)?;
+    Ok(())
+}
+
+/// The file `bytes` with `marks` added.
+pub fn annotate_bytes(bytes: Vec<u8>, marks: &[Mark]) -> Result<Vec<u8>> {
+    let pdf = Pdf::from_bytes(bytes)?;
+    let Some((startxref, xref_stream)) = pdf.startxref() else {
+        return Err(Error::Unsupported(
+            "PDF with damaged cross-reference data".into(),
+        ));
+    };
+    let pages = pdf.pages()?;
+    let size = pdf
+        .trailer
+        .get("Size")
+        .and_then(Object::as_i64)
+        .unwrap_or(0);
+    let mut next = (pdf.max_number() + 1).max(size.clamp(1, i64::from(u32::MAX)) as u32);
+    let mut spans: HashMap<usize, Vec<Span>> = HashMap::new();
+    let mut objects: Vec<(Reference, Object)> = Vec::new();
+    // The annotations added to each page, by page index.
+    let mut added: Vec<(usize, Vec<Object>)> = Vec::new();
+    for mark in marks {
+        let (page, boxes) = place(&pdf, &pages, &mut spans, &mark.target)?;
+        let Some(page_ref) = pages[page].reference else {
+            return Err(Error::Parse("PDF page is not an indirect object".into()));
+        };
+        let annotation = Reference {
+            number: next,
+            generation: 0,
+        };
+        let appearance = Reference {
+            number: next + 1,
+            generation: 0,
+        };
+        next += 2;
+        let (dict, stream) = annotation_objects(mark, &boxes, page_ref, appearance);
+        objects.push((annotation, Object::Dictionary(dict)));
+        objects.push((appearance, Object::Stream(stream)));
+        match added.iter_mut().find(|(index, _)| *index == page) {
+            Some((_, refs)) => refs.push(Object::Reference(annotation)),
+            None => added.push((page, vec![Object::Reference(annotation)])),
+        }
+    }
+    for (index, refs) in added {
+        let Some(page_ref) = pages[index].reference else {
+            continue;
+        };
+        // The page as stored, without what it inherits.
+        let mut page = pdf.dict(Some(&Object::Reference(page_ref)))?;
+        match page.get("Annots").cloned() {
+            Some(Object::Reference(list)) => {
+                let mut items = pdf.resolve(&Object::Reference(list))?;
+                match &mut items {
+                    Object::Array(items) => items.extend(refs),
+                    other => *other = Object::Array(refs),
+                }
+                set_object(&mut objects, list, items);
+            }
+            Some(Object::Array(mut items)) => {
+                items.extend(refs);
+                page.set("Annots", Object::Array(items));
+                set_object(&mut objects, page_ref, Object::Dictionary(page));
+            }
+            _ => {
+                page.set("Annots", Object::Array(refs));
+                set_object(&mut objects, page_ref, Object::Dictionary(page));
+            }
+        }
+    }
+    Ok(append(&pdf, objects, next, startxref, xref_stream))
+}
+
+/// Replace the object `r` is written as, or add it.
+fn set_object(objects: &mut Vec<(Reference, Object)>, r: Reference, object: Object) {
+    match objects.iter_mut().find(|(number, _)| *number == r) {
+        Some((_, slot)) => *slot = object,
+        None => objects.push((r, object)),
+    }
+}
+
+/// The page index `target` is on and the boxes it covers there.
+fn place(
+    pdf: &Pdf,
+    pages: &[Page],
+    spans: &mut HashMap<usize, Vec<Span>>,
+    target: &Target,
+) -> Result<(usize, Vec<[f64; 4]>)> {
+    let page_index = |page: usize| match page {
+        1.. if page <= pages.len() => Ok(page - 1),
+        _ => Err(Error::Parse(format!(
+            "page {page} is not in a PDF of {} pages",
+            pages.len()
+        ))),
+    };
+    match target {
+        Target::Area { page, rect } => {
+            let [a, b, c, d] = *rect;
+            Ok((
+                page_index(*page)?,
+                vec![[a.min(c), b.min(d), a.max(c), b.max(d)]],
+            ))
+        }
+        Target::Quote { text, page } => {
+            let wanted: Vec<&str> = text.split_whitespace().collect();
+            if wanted.is_empty() {
+                return Err(Error::Parse("mark quote is empty".into()));
+            }
+            let candidates = match page {
+                Some(page) => vec![page_index(*page)?],
+                None => (0..pages.len()).collect(),
+            };
+            for index in candidates {
+                let page_spans = match spans.entry(index) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => entry.insert(pdf_text::page_spans(pdf, &pages[index])?),
+                };
+                if let Some(boxes) = find_quote(page_spans, &wanted.join(" ")) {
+                    return Ok((index, boxes));
+                }
+            }
+            Err(Error::Parse(format!("quote not found in the PDF: {text}")))
+        }
+    }
+}
+
+/// The boxes, one a line, over the first place `quote` is drawn.
+fn find_quote(spans: &[Span], quote: &str) -> Option<Vec<[f64; 4]>> {
+    // The page's text in drawing order, white space collapsed, and for
+    // each character the span and character it was drawn as.
+    let mut text = String::new();
+    let mut origins: Vec<Option<(usize, usize)>> = Vec::new();
+    let mut last: Option<&Span> = None;
+    for (s, span) in spans.iter().enumerate() {
+        if let Some(last) = last {
+            let same_line = (last.y - span.y).abs() <= span.size / 2.0;
+            if !same_line || span.x - last.end > SPACE * span.size {
+                push_space(&mut text, &mut origins);
+            }
+        }
+        for (c, ch) in span.text.chars().enumerate() {
+            match ch.is_whitespace() {
+                true => push_space(&mut text, &mut origins),
+                false => {
+                    text.push(ch);
+                    origins.push(Some((s, c)));
+                }
+            }
+        }
+        last = Some(span);
+    }
+    let chars: Vec<char> = text.chars().collect();
+    let wanted: Vec<char> = quote.chars().collect();
+    let start = (0..chars.len().checked_sub(wanted.len())? + 1)
+        .find(|&at| chars[at..at + wanted.len()] == wanted[..])?;
+    let mut boxes: Vec<[f64; 4]> = Vec::new();
+    let mut line_y: Option<f64> = None;
+    for &(s, c) in origins[start..start + wanted.len()].iter().flatten() {
+        let span = &spans[s];
+        let left = span.starts.get(c).copied().unwrap_or(span.x);
+        let right = span.starts.get(c + 1).copied().unwrap_or(span.end);
+        let (bottom, top) = (span.y - DESCENT * span.size, span.y + ASCENT * span.size);
+        match boxes.last_mut() {
+            Some(last) if line_y.is_some_and(|y| (y - span.y).abs() <= span.size / 2.0) => {
+                last[0] = last[0].min(left);
+                last[1] = last[1].min(bottom);
+                last[2] = last[2].max(right);
+                last[3] = last[3].max(top);
+            }
+            _ => {
+                boxes.push([left, bottom, right, top]);
+                line_y = Some(span.y);
+            }
+        }
+    }
+    Some(boxes)
+}
+
+fn push_space(text: &mut String, origins: &mut Vec<Option<(usize, usize)>>) {
+    if !text.is_empty() && !text.ends_with(' ') {
+        text.push(' ');
+        origins.push(None);
+    }
+}
+
+/// The annotation dictionary for `mark` and its appearance stream.
+fn annotation_objects(
+    mark: &Mark,
+    boxes: &[[f64; 4]],
+    page: Reference,
+    appearance: Reference,
+) -> (Dictionary, Stream) {
+    let [r, g, b] = rgb(&mark.color).unwrap_or(COLORS[0].1);
+    let number = |n: f64| Object::Real(n);
+    let numbers = |ns: &[f64]| Object::Array(ns.iter().copied().map(number).collect());
+    let rect = match mark.kind {
+        MarkKind::Highlight => boxes.iter().fold(boxes[0], |acc, b| {
+            [
+                acc[0].min(b[0]),
+                acc[1].min(b[1]),
+                acc[2].max(b[2]),
+                acc[3].max(b[3]),
+            ]
+        }),
+        MarkKind::Note => match mark.target {
+            Target::Area { .. } => boxes[0],
+            Target::Quote { .. } => {
+                let [left, _, _, top] = boxes[0];
+                [left, top, left + NOTE_SIZE, top + NOTE_SIZE]
+            }
+        },
+    };
+    let color = format!("{} {} {} rg\n", real(r), real(g), real(b));
+    let mut dict = Dictionary::default();
+    dict.set("Type", Object::Name("Annot".into()));
+    let mut resources = Dictionary::default();
+    let content = match mark.kind {
+        MarkKind::Highlight => {
+            dict.set("Subtype", Object::Name("Highlight".into()));
+            let quads: Vec<f64> = boxes
+                .iter()
+                .flat_map(|&[left, bottom, right, top]| {
+                    [left, top, right, top, left, bottom, right, bottom]
+                })
+                .collect();
+            dict.set("QuadPoints", numbers(&quads));
+            // Multiplied into the page, so the text stays readable.
+            let mut state = Dictionary::default();
+            state.set("Type", Object::Name("ExtGState".into()));
+            state.set("BM", Object::Name("Multiply".into()));
+            let mut states = Dictionary::default();
+            states.set("GS0", Object::Dictionary(state));
+            resources.set("ExtGState", Object::Dictionary(states));
+            let mut content = format!("/GS0 gs\n{color}");
+            for &[left, bottom, right, top] in boxes {
+                content.push_str(&format!(
+                    "{} {} {} {} re f\n",
+                    real(left),
+                    real(bottom),
+                    real(right - left),
+                    real(top - bottom)
+                ));
+            }
+            content
+        }
+        MarkKind::Note => {
+            dict.set("Subtype", Object::Name("Text".into()));
+            dict.set("Name", Object::Name("Comment".into()));
+            let [left, bottom, right, top] = rect;
+            let (width, height) = (right - left, top - bottom);
+            let mut content = format!(
+                "{color}0 G 1 w\n{} {} {} {} re B\n",
+                real(left + 0.5),
+                real(bottom + 0.5),
+                real(width - 1.0),
+                real(height - 1.0)
+            );
+            for i in 1..4 {
+                let y = bottom + height * f64::from(i) / 4.0;
+                content.push_str(&format!(
+                    "{} {} m {} {} l S\n",
+                    real(left + width / 5.0),
+                    real(y),
+                    real(right - width / 5.0),
+                    real(y)
+                ));
+            }
+            content
+        }
+    };
+    dict.set("Rect", numbers(&rect));
+    dict.set("P", Object::Reference(page));
+    // Printed with the page.
+    dict.set("F", Object::Integer(4));
+    dict.set("C", numbers(&[r, g, b]));
+    dict.set("T", Object::String(encode_text_string(&mark.author)));
+    if !mark.comment.is_empty() {
+        dict.set(
+            "Contents",
+            Object::String(encode_text_string(&mark.comment)),
+        );
+    }
+    let mut normal = Dictionary::default();
+    normal.set("N", Object::Reference(appearance));
+    dict.set("AP", Object::Dictionary(normal));
+    let mut form = Dictionary::default();
+    form.set("Type", Object::Name("XObject".into()));
+    form.set("Subtype", Object::Name("Form".into()));
+    form.set("BBox", numbers(&rect));
+    form.set("Resources", Object::Dictionary(resources));
+    let stream = Stream {
+        dict: form,
+        data: content.into_bytes(),
+    };
+    (dict, stream)
+}
+
+fn real(n: f64) -> String {
+    let mut out = Vec::new();
+    Object::Real(n).write(&mut out);
+    String::from_utf8(out).unwrap_or_default()
+}
+
+/// The file with `objects` and a cross-reference section for them
+/// appended: a table, or a stream if the file's newest section is one.
+fn append(
+    pdf: &Pdf,
+    mut objects: Vec<(Reference, Object)>,
+    next: u32,
+    startxref: usize,
+    xref_stream: bool,
+) -> Vec<u8> {
+    let mut out = pdf.bytes().to_vec();
+    if !out.ends_with(b"\n") {
+        out.push(b'\n');
+    }
+    let mut offsets: Vec<(Reference, usize)> = Vec::new();
+    objects.sort_by_key(|(r, _)| r.number);
+    for (r, object) in &objects {
+        offsets.push((*r, out.len()));
+        out.extend_from_slice(format!("{} {} obj\n", r.number, r.generation).as_bytes());
+        object.write(&mut out);
+        out.extend_from_slice(b"\nendobj\n");
+    }
+    let mut trailer = Dictionary::default();
+    for key in ["Root", "Info", "ID"] {
+        if let Some(value) = pdf.trailer.get(key) {
+            trailer.set(key, value.clone());
+        }
+    }
+    trailer.set("Prev", Object::Integer(startxref as i64));
+    let at = out.len();
+    if xref_stream {
+        let stream_ref = Reference {
+            number: next,
+            generation: 0,
+        };
+        offsets.push((stream_ref, at));
+        let mut rows = Vec::new();
+        for (r, offset) in &offsets {
+            rows.push(1);
+            rows.extend_from_slice(&(*offset as u64).to_be_bytes());
+            rows.extend_from_slice(&r.generation.to_be_bytes());
+        }
+        trailer.set("Type", Object::Name("XRef".into()));
+        trailer.set("Size", Object::Integer(i64::from(next) + 1));
+        trailer.set("Index", Object::Array(subsections(&offsets)));
+        trailer.set(
+            "W",
+            Object::Array(vec![
+                Object::Integer(1),
+                Object::Integer(8),
+                Object::Integer(2),
+            ]),
+        );
+        let stream = Object::Stream(Stream {
+            dict: trailer,
+            data: rows,
+        });
+        out.extend_from_slice(format!("{next} 0 obj\n").as_bytes());
+        stream.write(&mut out);
+        out.extend_from_slice(b"\nendobj\n");
+    } else {
+        out.extend_from_slice(b"xref\n");
+        let mut rest = &offsets[..];
+        while let Some(((first, _), _)) = rest.split_first() {
+            let count = rest
+                .iter()
+                .enumerate()
+                .take_while(|(i, (r, _))| r.number == first.number + *i as u32)
+                .count();
+            out.extend_from_slice(format!("{} {count}\n", first.number).as_bytes());
+            for (r, offset) in &rest[..count] {
+                out.extend_from_slice(format!("{offset:010} {:05} n \n", r.generation).as_bytes());
+            }
+            rest = &rest[count..];
+        }
+        trailer.set("Size", Object::Integer(i64::from(next)));
+        out.extend_from_slice(b"trailer\n");
+        Object::Dictionary(trailer).write(&mut out);
+        out.push(b'\n');
+    }
+    out.extend_from_slice(format!("startxref\n{at}\n%%EOF\n").as_bytes());
+    out
+}
+
+/// `[first count ...]` for the runs of consecutive object numbers.
+fn subsections(offsets: &[(Reference, usize)]) -> Vec<Object> {
+    let mut out: Vec<(u32, u32)> = Vec::new();
+    for (r, _) in offsets {
+        match out.last_mut() {
+            Some((first, count)) if *first + *count == r.number => *count += 1,
+            _ => out.push((r.number, 1)),
+        }
+    }
+    out.into_iter()
+        .flat_map(|(first, count)| {
+            [
+                Object::Integer(i64::from(first)),
+                Object::Integer(i64::from(count)),
+            ]
+        })
+        .collect()
+}
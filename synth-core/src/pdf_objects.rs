@@ -0,0 +1,1220 @@
+//! The objects of a PDF file (ISO 32000-1, section 7) and the page tree,
+//! for reading PDFs and appending objects to them; [`pdf`](crate::pdf)
+//! writes new ones.
+//!
+//! Objects are found through the cross-reference tables and streams of
+//! every revision, newest first; a file whose tables are damaged is
+//! scanned for its objects instead. Streams are decoded with the Flate,
+//! ASCIIHex and ASCII85 filters and PNG predictors. Encrypted files are
+//! not read.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::inflate::inflate_zlib;
+
+/// Decoded streams larger than this are a parse error.
+const MAX_STREAM: usize = 256 << 20;
+/// Deeper nesting of arrays, dictionaries and page trees is rejected.
+const MAX_DEPTH: usize = 64;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Object {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    Real(f64),
+    /// Literal or hex string, as bytes.
+    String(Vec<u8>),
+    Name(String),
+    Array(Vec<Object>),
+    Dictionary(Dictionary),
+    Stream(Stream),
+    Reference(Reference),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Reference {
+    pub number: u32,
+    pub generation: u16,
+}
+
+/// Keys in file order, without the leading `/`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Dictionary(pub Vec<(String, Object)>);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stream {
+    pub dict: Dictionary,
+    /// As stored, before the filters are undone.
+    pub data: Vec<u8>,
+}
+
+impl Object {
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Object::Integer(n) => Some(n as f64),
+            Object::Real(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Object::Integer(n) => Some(n),
+            Object::Real(n) => Some(n as i64),
+            _ => None,
+        }
+    }
+
+    pub fn as_name(&self) -> Option<&str> {
+        match self {
+            Object::Name(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Object::String(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Object]> {
+        match self {
+            Object::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// A dictionary, or a stream's.
+    pub fn as_dict(&self) -> Option<&Dictionary> {
+        match self {
+            Object::Dictionary(dict) => Some(dict),
+            Object::Stream(stream) => Some(&stream.dict),
+            _ => None,
+        }
+    }
+
+    pub fn as_reference(&self) -> Option<Reference> {
+        match self {
+            Object::Reference(r) => Some(*r),
+            _ => None,
+        }
+    }
+}
+
+impl Object {
+    /// The object as written in a file, for an incremental update.
+    pub fn write(&self, out: &mut Vec<u8>) {
+        match self {
+            Object::Null => out.extend_from_slice(b"null"),
+            Object::Bool(b) => out.extend_from_slice(if *b { b"true" } else { b"false" }),
+            Object::Integer(n) => out.extend_from_slice(n.to_string().as_bytes()),
+            Object::Real(n) => out.extend_from_slice(real(*n).as_bytes()),
+            Object::String(bytes) => {
+                out.push(b'(');
+                for &b in bytes {
+                    match b {
+                        b'(' | b')' | b'\\' => out.extend_from_slice(&[b'\\', b]),
+                        b'\r' => out.extend_from_slice(b"\\r"),
+                        b'\n' => out.extend_from_slice(b"\\n"),
+                        b => out.push(b),
+                    }
+                }
+                out.push(b')');
+            }
+            Object::Name(name) => write_name(name, out),
+            Object::Array(items) => {
+                out.push(b'[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.push(b' ');
+                    }
+                    item.write(out);
+                }
+                out.push(b']');
+            }
+            Object::Dictionary(dict) => dict.write(out),
+            Object::Stream(stream) => {
+                let mut dict = stream.dict.clone();
+                dict.set("Length", Object::Integer(stream.data.len() as i64));
+                dict.write(out);
+                out.extend_from_slice(b"\nstream\n");
+                out.extend_from_slice(&stream.data);
+                out.extend_from_slice(b"\nendstream");
+            }
+            Object::Reference(r) => {
+                out.extend_from_slice(format!("{} {} R", r.number, r.generation).as_bytes())
+            }
+        }
+    }
+}
+
+/// A number without an exponent, to four decimal places at most.
+fn real(n: f64) -> String {
+    if !n.is_finite() {
+        return "0".into();
+    }
+    let text = format!("{n:.4}");
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    match text {
+        "" | "-" | "-0" => "0".into(),
+        text => text.into(),
+    }
+}
+
+/// `/name`, bytes outside the regular characters written as `#xx`.
+fn write_name(name: &str, out: &mut Vec<u8>) {
+    out.push(b'/');
+    for &b in name.as_bytes() {
+        match b {
+            b'#' => out.extend_from_slice(b"#23"),
+            b if b > b' ' && b < 0x7f && is_regular(b) => out.push(b),
+            b => out.extend_from_slice(format!("#{b:02X}").as_bytes()),
+        }
+    }
+}
+
+impl Dictionary {
+    fn write(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(b"<<");
+        for (key, value) in &self.0 {
+            write_name(key, out);
+            out.push(b' ');
+            value.write(out);
+        }
+        out.extend_from_slice(b">>");
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Object> {
+        self.0.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    /// The name at `key`, as in `/Type /Page`.
+    pub fn name(&self, key: &str) -> Option<&str> {
+        self.get(key).and_then(Object::as_name)
+    }
+
+    pub fn set(&mut self, key: &str, value: Object) {
+        match self.0.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value,
+            None => self.0.push((key.to_string(), value)),
+        }
+    }
+}
+
+impl Stream {
+    /// The data with the stream's filters undone.
+    pub fn decode(&self) -> Result<Vec<u8>> {
+        let as_list = |obj: Option<&Object>| match obj {
+            None | Some(Object::Null) => Vec::new(),
+            Some(Object::Array(items)) => items.clone(),
+            Some(other) => vec![other.clone()],
+        };
+        let filters = as_list(self.dict.get("Filter"));
+        let params = as_list(self.dict.get("DecodeParms"));
+        let mut data = self.data.clone();
+        for (i, filter) in filters.iter().enumerate() {
+            let params = params.get(i).and_then(Object::as_dict);
+            data = match filter.as_name().unwrap_or("") {
+                "FlateDecode" | "Fl" => predict(inflate_zlib(&data, MAX_STREAM)?, params)?,
+                "ASCIIHexDecode" | "AHx" => ascii_hex(&data),
+                "ASCII85Decode" | "A85" => ascii85(&data)?,
+                other => return Err(Error::Unsupported(format!("PDF filter {other}"))),
+            };
+        }
+        Ok(data)
+    }
+}
+
+/// Where an object is stored.
+#[derive(Debug, Clone, Copy)]
+enum Entry {
+    Offset(usize),
+    /// The `index`th object of the object stream `stream`.
+    Compressed {
+        stream: u32,
+        index: usize,
+    },
+}
+
+/// A PDF file, its objects read as they are asked for.
+pub struct Pdf {
+    data: Vec<u8>,
+    entries: HashMap<u32, Entry>,
+    /// The objects of the object streams, read when the file is opened.
+    compressed: HashMap<u32, Object>,
+    pub trailer: Dictionary,
+    /// Where the newest cross-reference section starts.
+    startxref: Option<usize>,
+}
+
+/// A page, its inherited attributes (`Resources`, `MediaBox`, `Rotate`)
+/// copied into `dict`.
+#[derive(Debug, Clone)]
+pub struct Page {
+    pub reference: Option<Reference>,
+    pub dict: Dictionary,
+}
+
+impl Page {
+    /// `[x0, y0, x1, y1]`, US Letter when missing.
+    pub fn media_box(&self) -> [f64; 4] {
+        let mut out = [0.0, 0.0, 612.0, 792.0];
+        if let Some(values) = self.dict.get("MediaBox").and_then(Object::as_array) {
+            for (slot, value) in out.iter_mut().zip(values) {
+                *slot = value.as_f64().unwrap_or(*slot);
+            }
+        }
+        out
+    }
+}
+
+impl Pdf {
+    pub fn open(path: &Path) -> Result<Pdf> {
+        Pdf::from_bytes(crate::policy::read_file(path)?)
+    }
+
+    pub fn from_bytes(data: Vec<u8>) -> Result<Pdf> {
+        if find(&data, b"%PDF-", 0).is_none_or(|at| at > 1024) {
+            return Err(Error::Parse("not a PDF file".into()));
+        }
+        let mut pdf = Pdf {
+            data,
+            entries: HashMap::new(),
+            compressed: HashMap::new(),
+            trailer: Dictionary::default(),
+            startxref: None,
+        };
+        let read = pdf.read_xref();
+        if read.is_err() || pdf.trailer.get("Root").is_none() {
+            pdf.startxref = None;
+            pdf.scan()?;
+        }
+        if pdf.trailer.get("Encrypt").is_some() {
+            return Err(Error::Unsupported("encrypted PDF".into()));
+        }
+        pdf.read_object_streams();
+        Ok(pdf)
+    }
+
+    /// The bytes of the file.
+    pub fn bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Where the newest cross-reference section starts, and whether it is
+    /// a stream; `None` for a file whose cross-reference data was damaged,
+    /// and its objects found by scanning.
+    pub fn startxref(&self) -> Option<(usize, bool)> {
+        let at = self.startxref?;
+        Some((at, !self.data[at..].starts_with(b"xref")))
+    }
+
+    /// The highest object number in use.
+    pub fn max_number(&self) -> u32 {
+        self.entries.keys().copied().max().unwrap_or(0)
+    }
+
+    /// The object `r` refers to; null if there is none.
+    pub fn get(&self, r: Reference) -> Result<Object> {
+        match self.entries.get(&r.number) {
+            None => Ok(Object::Null),
+            Some(Entry::Compressed { .. }) => Ok(self
+                .compressed
+                .get(&r.number)
+                .cloned()
+                .unwrap_or(Object::Null)),
+            Some(&Entry::Offset(at)) => {
+                let mut parser = Parser::new(&self.data, at);
+                let (number, object) = parser.indirect()?;
+                if number != r.number {
+                    return Err(Error::Parse(format!("PDF object {} not found", r.number)));
+                }
+                Ok(object)
+            }
+        }
+    }
+
+    /// `obj`, or what it refers to.
+    pub fn resolve(&self, obj: &Object) -> Result<Object> {
+        let mut obj = obj.clone();
+        for _ in 0..MAX_DEPTH {
+            match obj {
+                Object::Reference(r) => obj = self.get(r)?,
+                other => return Ok(other),
+            }
+        }
+        Err(Error::Parse("PDF reference loop".into()))
+    }
+
+    /// The dictionary `obj` is or refers to; empty if it is something else.
+    pub fn dict(&self, obj: Option<&Object>) -> Result<Dictionary> {
+        Ok(match obj {
+            Some(obj) => match self.resolve(obj)? {
+                Object::Dictionary(dict) => dict,
+                Object::Stream(stream) => stream.dict,
+                _ => Dictionary::default(),
+            },
+            None => Dictionary::default(),
+        })
+    }
+
+    pub fn catalog(&self) -> Result<Dictionary> {
+        self.dict(self.trailer.get("Root"))
+    }
+
+    /// The pages, in order.
+    pub fn pages(&self) -> Result<Vec<Page>> {
+        let catalog = self.catalog()?;
+        let mut pages = Vec::new();
+        let mut seen = HashSet::new();
+        let root = catalog.get("Pages").cloned().unwrap_or(Object::Null);
+        self.collect_pages(&root, &Dictionary::default(), 0, &mut seen, &mut pages)?;
+        Ok(pages)
+    }
+
+    fn collect_pages(
+        &self,
+        node: &Object,
+        inherited: &Dictionary,
+        depth: usize,
+        seen: &mut HashSet<Reference>,
+        pages: &mut Vec<Page>,
+    ) -> Result<()> {
+        if depth > MAX_DEPTH {
+            return Err(Error::Parse("PDF page tree too deep".into()));
+        }
+        let reference = node.as_reference();
+        if let Some(r) = reference {
+            if !seen.insert(r) {
+                return Ok(());
+            }
+        }
+        let mut dict = self.dict(Some(node))?;
+        for key in ["Resources", "MediaBox", "CropBox", "Rotate"] {
+            if dict.get(key).is_none() {
+                if let Some(value) = inherited.get(key) {
+                    dict.set(key, value.clone());
+                }
+            }
+        }
+        match dict.get("Kids") {
+            Some(kids) if dict.name("Type") != Some("Page") => {
+                let kids = self.resolve(kids)?;
+                for kid in kids.as_array().unwrap_or(&[]) {
+                    self.collect_pages(kid, &dict, depth + 1, seen, pages)?;
+                }
+            }
+            _ => pages.push(Page { reference, dict }),
+        }
+        Ok(())
+    }
+
+    /// The page's content streams, decoded and joined.
+    pub fn contents(&self, page: &Page) -> Result<Vec<u8>> {
+        let contents = match page.dict.get("Contents") {
+            Some(obj) => self.resolve(obj)?,
+            None => return Ok(Vec::new()),
+        };
+        let parts = match contents {
+            Object::Array(items) => items,
+            other => vec![other],
+        };
+        let mut out = Vec::new();
+        for part in parts {
+            if let Object::Stream(stream) = self.resolve(&part)? {
+                out.extend(stream.decode()?);
+                out.push(b'\n');
+            }
+        }
+        Ok(out)
+    }
+
+    /// Follow `startxref` back through the revisions.
+    fn read_xref(&mut self) -> Result<()> {
+        let start = rfind(&self.data, b"startxref")
+            .ok_or_else(|| Error::Parse("PDF has no startxref".into()))?;
+        let mut parser = Parser::new(&self.data, start + 9);
+        let mut at = parser.object()?.as_i64().unwrap_or(-1);
+        if at < 0 || at as usize >= self.data.len() {
+            return Err(Error::Parse("PDF startxref out of range".into()));
+        }
+        self.startxref = Some(at as usize);
+        let mut seen = HashSet::new();
+        while at >= 0 && seen.insert(at) {
+            let at_usize = at as usize;
+            let trailer = if self.data[at_usize.min(self.data.len())..].starts_with(b"xref") {
+                self.xref_table(at_usize + 4)?
+            } else {
+                self.xref_stream(at_usize)?
+            };
+            if let Some(hybrid) = trailer.get("XRefStm").and_then(Object::as_i64) {
+                if seen.insert(hybrid) {
+                    self.xref_stream(hybrid as usize)?;
+                }
+            }
+            if self.trailer.0.is_empty() {
+                self.trailer = trailer.clone();
+            }
+            at = trailer.get("Prev").and_then(Object::as_i64).unwrap_or(-1);
+        }
+        Ok(())
+    }
+
+    /// A classic `xref` table at `at`, returning its trailer. Entries of
+    /// newer revisions, read first, are kept.
+    fn xref_table(&mut self, at: usize) -> Result<Dictionary> {
+        let mut parser = Parser::new(&self.data, at);
+        loop {
+            parser.skip_space();
+            if parser.keyword_is(b"trailer") {
+                parser.pos += 7;
+                return match parser.object()? {
+                    Object::Dictionary(dict) => Ok(dict),
+                    _ => Err(Error::Parse("PDF trailer is not a dictionary".into())),
+                };
+            }
+            let first = parser.object()?.as_i64();
+            let count = parser.object()?.as_i64();
+            let (Some(first), Some(count)) = (first, count) else {
+                return Err(Error::Parse("malformed PDF xref table".into()));
+            };
+            for n in 0..count.max(0) {
+                let offset = parser.object()?.as_i64().unwrap_or(0);
+                parser.object()?;
+                parser.skip_space();
+                let used = parser.peek() == Some(b'n');
+                parser.pos += 1;
+                let number = (first + n) as u32;
+                if used && offset > 0 {
+                    self.entries
+                        .entry(number)
+                        .or_insert(Entry::Offset(offset as usize));
+                }
+            }
+        }
+    }
+
+    /// A cross-reference stream at `at`, returning its dictionary.
+    fn xref_stream(&mut self, at: usize) -> Result<Dictionary> {
+        let (_, object) = Parser::new(&self.data, at).indirect()?;
+        let Object::Stream(stream) = object else {
+            return Err(Error::Parse("PDF xref is not a stream".into()));
+        };
+        let widths: Vec<usize> = stream
+            .dict
+            .get("W")
+            .and_then(Object::as_array)
+            .unwrap_or(&[])
+            .iter()
+            .map(|w| w.as_i64().unwrap_or(0).clamp(0, 8) as usize)
+            .collect();
+        if widths.len() != 3 {
+            return Err(Error::Parse("malformed PDF xref stream".into()));
+        }
+        let size = stream
+            .dict
+            .get("Size")
+            .and_then(Object::as_i64)
+            .unwrap_or(0);
+        let index: Vec<i64> = match stream.dict.get("Index").and_then(Object::as_array) {
+            Some(items) => items.iter().filter_map(Object::as_i64).collect(),
+            None => vec![0, size],
+        };
+        let data = stream.decode()?;
+        let row = widths.iter().sum::<usize>().max(1);
+        let mut rows = data.chunks_exact(row);
+        for pair in index.chunks_exact(2) {
+            for number in pair[0]..pair[0] + pair[1].max(0) {
+                let Some(entry) = rows.next() else {
+                    break;
+                };
+                let mut fields = [0u64; 3];
+                let mut pos = 0;
+                for (field, &width) in fields.iter_mut().zip(&widths) {
+                    *field = entry[pos..pos + width]
+                        .iter()
+                        .fold(0, |acc, &b| acc << 8 | u64::from(b));
+                    pos += width;
+                }
+                // A missing type field means type 1.
+                let kind = if widths[0] == 0 { 1 } else { fields[0] };
+                let entry = match kind {
+                    1 => Entry::Offset(fields[1] as usize),
+                    2 => Entry::Compressed {
+                        stream: fields[1] as u32,
+                        index: fields[2] as usize,
+                    },
+                    _ => continue,
+                };
+                self.entries.entry(number as u32).or_insert(entry);
+            }
+        }
+        Ok(stream.dict)
+    }
+
+    /// Find the objects by scanning for `N G obj`, for a file whose
+    /// cross-reference data cannot be used. Later definitions win.
+    fn scan(&mut self) -> Result<()> {
+        self.entries.clear();
+        let mut trailer = None;
+        let mut at = 0;
+        while let Some(found) = find(&self.data, b"obj", at) {
+            at = found + 3;
+            let Some(start) = object_start(&self.data, found) else {
+                continue;
+            };
+            let mut parser = Parser::new(&self.data, start);
+            let Ok((number, object)) = parser.indirect() else {
+                continue;
+            };
+            self.entries.insert(number, Entry::Offset(start));
+            if let Object::Stream(stream) = &object {
+                if stream.dict.name("Type") == Some("XRef") {
+                    trailer = Some(stream.dict.clone());
+                }
+                at = at.max(parser.pos);
+            }
+            if object.as_dict().and_then(|d| d.name("Type")) == Some("Catalog") && trailer.is_none()
+            {
+                trailer = Some(Dictionary(vec![(
+                    "Root".into(),
+                    Object::Reference(Reference {
+                        number,
+                        generation: 0,
+                    }),
+                )]));
+            }
+        }
+        if let Some(at) = rfind(&self.data, b"trailer") {
+            if let Ok(Object::Dictionary(dict)) = Parser::new(&self.data, at + 7).object() {
+                trailer = Some(dict);
+            }
+        }
+        self.trailer = trailer.ok_or_else(|| Error::Parse("PDF has no trailer".into()))?;
+        // Objects inside object streams are listed by the streams.
+        let streams: Vec<u32> = self.entries.keys().copied().collect();
+        for number in streams {
+            let Ok(Object::Stream(stream)) = self.get(Reference {
+                number,
+                generation: 0,
+            }) else {
+                continue;
+            };
+            if stream.dict.name("Type") != Some("ObjStm") {
+                continue;
+            }
+            if let Ok(objects) = object_stream(&stream) {
+                for (index, (n, _)) in objects.into_iter().enumerate() {
+                    self.entries.entry(n).or_insert(Entry::Compressed {
+                        stream: number,
+                        index,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Read the objects the entries place in object streams.
+    fn read_object_streams(&mut self) {
+        let mut streams: Vec<u32> = self
+            .entries
+            .values()
+            .filter_map(|entry| match entry {
+                Entry::Compressed { stream, .. } => Some(*stream),
+                Entry::Offset(_) => None,
+            })
+            .collect();
+        streams.sort_unstable();
+        streams.dedup();
+        for number in streams {
+            let Ok(Object::Stream(stream)) = self.get(Reference {
+                number,
+                generation: 0,
+            }) else {
+                continue;
+            };
+            let Ok(objects) = object_stream(&stream) else {
+                continue;
+            };
+            for (index, (n, object)) in objects.into_iter().enumerate() {
+                let listed = matches!(
+                    self.entries.get(&n),
+                    Some(Entry::Compressed { stream, index: i }) if *stream == number && *i == index
+                );
+                if listed {
+                    self.compressed.insert(n, object);
+                }
+            }
+        }
+    }
+}
+
+/// The numbered objects of an object stream, in order.
+fn object_stream(stream: &Stream) -> Result<Vec<(u32, Object)>> {
+    let data = stream.decode()?;
+    let count = stream.dict.get("N").and_then(Object::as_i64).unwrap_or(0);
+    let first = stream
+        .dict
+        .get("First")
+        .and_then(Object::as_i64)
+        .unwrap_or(0) as usize;
+    let mut header = Parser::new(&data, 0);
+    let mut offsets = Vec::new();
+    for _ in 0..count.max(0) {
+        let number = header.object()?.as_i64().unwrap_or(0) as u32;
+        let offset = header.object()?.as_i64().unwrap_or(0) as usize;
+        offsets.push((number, offset));
+    }
+    let mut objects = Vec::new();
+    for (number, offset) in offsets {
+        let object = Parser::new(&data, first + offset).object()?;
+        objects.push((number, object));
+    }
+    Ok(objects)
+}
+
+/// Where the `N G` before the `obj` at `at` begins.
+fn object_start(data: &[u8], at: usize) -> Option<usize> {
+    let mut pos = at;
+    for _ in 0..2 {
+        while pos > 0 && is_space(data[pos - 1]) {
+            pos -= 1;
+        }
+        let end = pos;
+        while pos > 0 && data[pos - 1].is_ascii_digit() {
+            pos -= 1;
+        }
+        if pos == end {
+            return None;
+        }
+    }
+    (pos == 0 || is_space(data[pos - 1]) || is_delimiter(data[pos - 1])).then_some(pos)
+}
+
+/// Undo a PNG predictor (`/Predictor` 10 to 15).
+fn predict(data: Vec<u8>, params: Option<&Dictionary>) -> Result<Vec<u8>> {
+    let param = |key, default| {
+        params
+            .and_then(|p| p.get(key))
+            .and_then(Object::as_i64)
+            .unwrap_or(default)
+            .max(1) as usize
+    };
+    let predictor = param("Predictor", 1);
+    if predictor < 10 {
+        if predictor == 2 {
+            return Err(Error::Unsupported("PDF TIFF predictor".into()));
+        }
+        return Ok(data);
+    }
+    let bits = param("Colors", 1) * param("BitsPerComponent", 8);
+    let bpp = bits.div_ceil(8);
+    let width = (bits * param("Columns", 1)).div_ceil(8);
+    let mut out = Vec::with_capacity(data.len());
+    let mut previous = vec![0u8; width];
+    for row in data.chunks(width + 1) {
+        let (kind, row) = (row[0], &row[1..]);
+        let mut line = row.to_vec();
+        line.resize(width, 0);
+        for i in 0..width {
+            let left = if i >= bpp { line[i - bpp] } else { 0 };
+            let up = previous[i];
+            let up_left = if i >= bpp { previous[i - bpp] } else { 0 };
+            line[i] = line[i].wrapping_add(match kind {
+                0 => 0,
+                1 => left,
+                2 => up,
+                3 => ((u16::from(left) + u16::from(up)) / 2) as u8,
+                4 => paeth(left, up, up_left),
+                _ => return Err(Error::Parse("bad PNG predictor in PDF stream".into())),
+            });
+        }
+        out.extend_from_slice(&line);
+        previous = line;
+    }
+    Ok(out)
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = i16::from(a) + i16::from(b) - i16::from(c);
+    let (pa, pb, pc) = (
+        (p - i16::from(a)).abs(),
+        (p - i16::from(b)).abs(),
+        (p - i16::from(c)).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+fn ascii_hex(data: &[u8]) -> Vec<u8> {
+    let digits: Vec<u8> = data
+        .iter()
+        .take_while(|&&b| b != b'>')
+        .filter_map(|&b| (b as char).to_digit(16).map(|d| d as u8))
+        .collect();
+    digits
+        .chunks(2)
+        .map(|pair| pair[0] << 4 | pair.get(1).copied().unwrap_or(0))
+        .collect()
+}
+
+fn ascii85(data: &[u8]) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut group = Vec::with_capacity(5);
+    for &b in data {
+        match b {
+            b'~' => break,
+            b'z' if group.is_empty() => out.extend_from_slice(&[0; 4]),
+            b'!'..=b'u' => {
+                group.push(b - b'!');
+                if group.len() == 5 {
+                    let n = group.iter().fold(0u64, |acc, &d| acc * 85 + u64::from(d));
+                    out.extend_from_slice(&(n as u32).to_be_bytes());
+                    group.clear();
+                }
+            }
+            b if is_space(b) => {}
+            _ => return Err(Error::Parse("bad ASCII85 data in PDF stream".into())),
+        }
+    }
+    if !group.is_empty() {
+        let kept = group.len() - 1;
+        group.resize(5, 84);
+        let n = group.iter().fold(0u64, |acc, &d| acc * 85 + u64::from(d));
+        out.extend_from_slice(&(n as u32).to_be_bytes()[..kept]);
+    }
+    Ok(out)
+}
+
+/// A text string (section 7.9.2): UTF-16 or UTF-8 with a byte order mark,
+/// else PDFDocEncoding.
+pub fn text_string(bytes: &[u8]) -> String {
+    if let Some(rest) = bytes.strip_prefix(&[0xfe, 0xff]) {
+        let units: Vec<u16> = rest
+            .chunks_exact(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+            .collect();
+        return String::from_utf16_lossy(&units);
+    }
+    if let Some(rest) = bytes.strip_prefix(&[0xef, 0xbb, 0xbf]) {
+        return String::from_utf8_lossy(rest).into_owned();
+    }
+    bytes.iter().map(|&b| pdf_doc_char(b)).collect()
+}
+
+/// `text` as a text string: ASCII as it is, else UTF-16 with a byte order
+/// mark.
+pub fn encode_text_string(text: &str) -> Vec<u8> {
+    if text.is_ascii() {
+        return text.as_bytes().to_vec();
+    }
+    let mut out = vec![0xfe, 0xff];
+    for unit in text.encode_utf16() {
+        out.extend_from_slice(&unit.to_be_bytes());
+    }
+    out
+}
+
+/// PDFDocEncoding, which is Latin-1 but for 0x80 to 0xa0.
+fn pdf_doc_char(b: u8) -> char {
+    const HIGH: &str = "•†‡…—–ƒ⁄‹›−‰„“”‘’‚™ﬁﬂŁŒŠŸŽıłœšž\u{fffd}€";
+    match b {
+        0x80..=0xa0 => HIGH
+            .chars()
+            .nth(usize::from(b - 0x80))
+            .unwrap_or('\u{fffd}'),
+        b => char::from(b),
+    }
+}
+
+/// One operation of a content stream: the operator and its operands.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Operation {
+    pub operator: String,
+    pub operands: Vec<Object>,
+}
+
+/// The operations of a content stream. Inline images are skipped.
+pub fn operations(data: &[u8]) -> Result<Vec<Operation>> {
+    let mut parser = Parser::new(data, 0);
+    let mut out = Vec::new();
+    let mut operands = Vec::new();
+    loop {
+        parser.skip_space();
+        let Some(b) = parser.peek() else {
+            break;
+        };
+        if is_regular(b) && !b.is_ascii_digit() && !matches!(b, b'+' | b'-' | b'.') {
+            let word = parser.word();
+            match word.as_str() {
+                "true" => operands.push(Object::Bool(true)),
+                "false" => operands.push(Object::Bool(false)),
+                "null" => operands.push(Object::Null),
+                "ID" => {
+                    parser.skip_inline_image();
+                    operands.clear();
+                }
+                _ => out.push(Operation {
+                    operator: word,
+                    operands: std::mem::take(&mut operands),
+                }),
+            }
+            continue;
+        }
+        match parser.direct(0) {
+            Ok(object) => operands.push(object),
+            // A stray delimiter; skip it.
+            Err(_) => parser.pos += 1,
+        }
+    }
+    Ok(out)
+}
+
+fn is_space(b: u8) -> bool {
+    matches!(b, b' ' | b'\t' | b'\n' | b'\r' | b'\x0c' | b'\0')
+}
+
+fn is_delimiter(b: u8) -> bool {
+    matches!(
+        b,
+        b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%'
+    )
+}
+
+fn is_regular(b: u8) -> bool {
+    !is_space(b) && !is_delimiter(b)
+}
+
+fn find(data: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    if from >= data.len() {
+        return None;
+    }
+    data[from..]
+        .windows(needle.len())
+        .position(|w| w == needle)
+        .map(|at| at + from)
+}
+
+fn rfind(data: &[u8], needle: &[u8]) -> Option<usize> {
+    data.windows(needle.len()).rposition(|w| w == needle)
+}
+
+/// Reads objects from `data` starting at `pos`.
+struct Parser<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(data: &'a [u8], pos: usize) -> Self {
+        Parser { data, pos }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.pos).copied()
+    }
+
+    fn skip_space(&mut self) {
+        while let Some(b) = self.peek() {
+            if b == b'%' {
+                while self.peek().is_some_and(|b| b != b'\n' && b != b'\r') {
+                    self.pos += 1;
+                }
+            } else if is_space(b) {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Whether the keyword `word` is next.
+    fn keyword_is(&self, word: &[u8]) -> bool {
+        self.data[self.pos.min(self.data.len())..].starts_with(word)
+            && self
+                .data
+                .get(self.pos + word.len())
+                .is_none_or(|&b| !is_regular(b))
+    }
+
+    fn word(&mut self) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(is_regular) {
+            self.pos += 1;
+        }
+        String::from_utf8_lossy(&self.data[start..self.pos]).into_owned()
+    }
+
+    fn error(&self, what: &str) -> Error {
+        Error::Parse(format!("{what} in PDF at byte {}", self.pos))
+    }
+
+    /// `N G obj ... endobj`: the object's number and the object.
+    fn indirect(&mut self) -> Result<(u32, Object)> {
+        let number = self.object()?.as_i64();
+        let generation = self.object()?.as_i64();
+        self.skip_space();
+        let (Some(number), Some(_), true) = (number, generation, self.keyword_is(b"obj")) else {
+            return Err(self.error("expected an object"));
+        };
+        self.pos += 3;
+        let object = self.object()?;
+        Ok((number as u32, object))
+    }
+
+    /// An object, a reference, or a dictionary with its stream.
+    fn object(&mut self) -> Result<Object> {
+        match self.value(0)? {
+            Object::Dictionary(dict) => {
+                let save = self.pos;
+                self.skip_space();
+                if !self.keyword_is(b"stream") {
+                    self.pos = save;
+                    return Ok(Object::Dictionary(dict));
+                }
+                self.pos += 6;
+                if self.peek() == Some(b'\r') {
+                    self.pos += 1;
+                }
+                if self.peek() == Some(b'\n') {
+                    self.pos += 1;
+                }
+                let start = self.pos;
+                let length = dict.get("Length").and_then(Object::as_i64).unwrap_or(-1);
+                let end = usize::try_from(length)
+                    .ok()
+                    .map(|len| start + len)
+                    .filter(|&end| {
+                        let mut after = Parser::new(self.data, end);
+                        after.skip_space();
+                        end <= self.data.len() && after.keyword_is(b"endstream")
+                    });
+                let end = match end {
+                    Some(end) => end,
+                    None => {
+                        let mut end = find(self.data, b"endstream", start)
+                            .ok_or_else(|| self.error("unterminated stream"))?;
+                        while end > start && matches!(self.data[end - 1], b'\r' | b'\n') {
+                            end -= 1;
+                        }
+                        end
+                    }
+                };
+                let data = self.data[start..end].to_vec();
+                self.pos = end;
+                self.skip_space();
+                if self.keyword_is(b"endstream") {
+                    self.pos += 9;
+                }
+                Ok(Object::Stream(Stream { dict, data }))
+            }
+            other => Ok(other),
+        }
+    }
+
+    /// A direct object, without looking for `R` or `stream` after it.
+    fn direct(&mut self, depth: usize) -> Result<Object> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("nesting too deep"));
+        }
+        self.skip_space();
+        let Some(b) = self.peek() else {
+            return Err(self.error("unexpected end"));
+        };
+        match b {
+            b'/' => {
+                self.pos += 1;
+                Ok(Object::Name(self.name()))
+            }
+            b'(' => Ok(Object::String(self.literal()?)),
+            b'<' if self.data.get(self.pos + 1) == Some(&b'<') => {
+                self.pos += 2;
+                let mut entries = Vec::new();
+                loop {
+                    self.skip_space();
+                    match self.peek() {
+                        Some(b'>') => {
+                            self.pos += 2;
+                            return Ok(Object::Dictionary(Dictionary(entries)));
+                        }
+                        Some(b'/') => {
+                            self.pos += 1;
+                            let key = self.name();
+                            let value = self.value(depth)?;
+                            entries.push((key, value));
+                        }
+                        None => return Err(self.error("unterminated dictionary")),
+                        // A malformed entry; skip it.
+                        Some(_) => {
+                            self.value(depth)?;
+                        }
+                    }
+                }
+            }
+            b'<' => {
+                self.pos += 1;
+                let start = self.pos;
+                while self.peek().is_some_and(|b| b != b'>') {
+                    self.pos += 1;
+                }
+                let bytes = ascii_hex(&self.data[start..self.pos]);
+                self.pos += 1;
+                Ok(Object::String(bytes))
+            }
+            b'[' => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                loop {
+                    self.skip_space();
+                    match self.peek() {
+                        Some(b']') => {
+                            self.pos += 1;
+                            return Ok(Object::Array(items));
+                        }
+                        None => return Err(self.error("unterminated array")),
+                        Some(_) => items.push(self.value(depth)?),
+                    }
+                }
+            }
+            b'0'..=b'9' | b'+' | b'-' | b'.' => {
+                let word = self.word();
+                if let Ok(n) = word.parse::<i64>() {
+                    return Ok(Object::Integer(n));
+                }
+                Ok(Object::Real(word.parse().unwrap_or(0.0)))
+            }
+            _ if is_regular(b) => match self.word().as_str() {
+                "true" => Ok(Object::Bool(true)),
+                "false" => Ok(Object::Bool(false)),
+                "null" => Ok(Object::Null),
+                _ => Err(self.error("unexpected keyword")),
+            },
+            _ => Err(self.error("unexpected delimiter")),
+        }
+    }
+
+    /// An array item or dictionary value: references are allowed.
+    fn value(&mut self, depth: usize) -> Result<Object> {
+        let object = self.direct(depth + 1)?;
+        let Object::Integer(number) = object else {
+            return Ok(object);
+        };
+        let save = self.pos;
+        self.skip_space();
+        if self.peek().is_some_and(|b| b.is_ascii_digit()) {
+            if let Ok(Object::Integer(generation)) = self.direct(depth + 1) {
+                self.skip_space();
+                if self.keyword_is(b"R") {
+                    self.pos += 1;
+                    return Ok(Object::Reference(Reference {
+                        number: number as u32,
+                        generation: generation as u16,
+                    }));
+                }
+            }
+        }
+        self.pos = save;
+        Ok(Object::Integer(number))
+    }
+
+    /// A name after its `/`, `#xx` escapes undone.
+    fn name(&mut self) -> String {
+        let mut out = Vec::new();
+        while let Some(b) = self.peek().filter(|&b| is_regular(b)) {
+            self.pos += 1;
+            if b == b'#' {
+                let hex = self.data.get(self.pos..self.pos + 2).unwrap_or(b"");
+                if let Ok(v) = u8::from_str_radix(&String::from_utf8_lossy(hex), 16) {
+                    out.push(v);
+                    self.pos += 2;
+                    continue;
+                }
+            }
+            out.push(b);
+        }
+        String::from_utf8_lossy(&out).into_owned()
+    }
+
+    /// A literal string, `(...)`, its escapes undone.
+    fn literal(&mut self) -> Result<Vec<u8>> {
+        self.pos += 1;
+        let mut out = Vec::new();
+        let mut depth = 1;
+        while let Some(b) = self.peek() {
+            self.pos += 1;
+            match b {
+                b'(' => {
+                    depth += 1;
+                    out.push(b);
+                }
+                b')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(out);
+                    }
+                    out.push(b);
+                }
+                b'\\' => {
+                    let Some(e) = self.peek() else {
+                        break;
+                    };
+                    self.pos += 1;
+                    match e {
+                        b'n' => out.push(b'\n'),
+                        b'r' => out.push(b'\r'),
+                        b't' => out.push(b'\t'),
+                        b'b' => out.push(8),
+                        b'f' => out.push(12),
+                        b'\r' => {
+                            if self.peek() == Some(b'\n') {
+                                self.pos += 1;
+                            }
+                        }
+                        b'\n' => {}
+                        b'0'..=b'7' => {
+                            let mut value = u32::from(e - b'0');
+                            for _ in 0..2 {
+                                match self.peek() {
+                                    Some(d @ b'0'..=b'7') => {
+                                        value = value * 8 + u32::from(d - b'0');
+                                        self.pos += 1;
+                                    }
+                                    _ => break,
+                                }
+                            }
+                            out.push(value as u8);
+                        }
+                        other => out.push(other),
+                    }
+                }
+                b => out.push(b),
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+
+    /// Past the data of an inline image and its `EI`.
+    fn skip_inline_image(&mut self) {
+        self.pos += 1;
+        while let Some(at) = find(self.data, b"EI", self.pos) {
+            self.pos = at + 2;
+            let before = at == 0 || is_space(self.data[at - 1]);
+            let after = self.data.get(at + 2).is_none_or(|&b| is_space(b));
+            if before && after {
+                return;
+            }
+        }
+        self.pos = self.data.len();
+    }
+}
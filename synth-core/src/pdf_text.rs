@@ -0,0 +1,1105 @@
+//! Text of a PDF document for extraction, pages separated by a form feed.
+//!
+//! The text is read from the pages' content streams, and the form
+//! XObjects they draw, with the fonts' `ToUnicode` maps, or their
+//! encodings for simple fonts. Scanned pages without a text layer give no
+//! text: they are for [`ocr`](crate::ocr).
+//!
+//! In the order the content streams draw it, text is often unusable: a
+//! multi-column page comes out interleaved, broken across lines mid-word,
+//! with the running header and page number in between. Read
+//! [`layout_aware`](Options::layout_aware)ly (the default), lines are
+//! grouped into columns at the gutters between them and read column by
+//! column; lines repeated at the top or bottom of the pages, and page
+//! numbers there, are left out; lines are joined into paragraphs, words
+//! hyphenated across lines joined again.
+//!
+//! What a form's fields hold is drawn in their widgets, not the pages, so
+//! it is read apart, as [`FormField`]s; see [`pdf_forms`]. So are the
+//! highlights and notes left on the pages: see [`pdf_annotations`].
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::docx_text::{self, FormField};
+use crate::error::{Error, Result};
+use crate::json::Value;
+use crate::pdf_annotations::{self, Annotation};
+use crate::pdf_forms;
+use crate::pdf_objects::{self, Dictionary, Object, Page, Pdf, Reference};
+
+/// Form XObjects nested deeper than this are not read.
+const MAX_FORM_DEPTH: usize = 8;
+/// A gap wider than this, in ems, between pieces of text on a line is a
+/// space.
+const SPACE: f64 = 0.15;
+/// A gap wider than this, in ems, may be between columns.
+const GUTTER: f64 = 1.0;
+/// The share of a page's height, at its top and at its bottom, where
+/// headers and footers are looked for.
+const MARGIN_BAND: f64 = 0.12;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// Read columns in turn, leave out running headers, footers and page
+    /// numbers, and join lines into paragraphs; otherwise lines are given
+    /// in the order they are drawn.
+    pub layout_aware: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options { layout_aware: true }
+    }
+}
+
+impl Options {
+    /// Read `{"layout_aware": true}`; every key is optional.
+    pub fn from_json(value: &Value) -> Result<Options> {
+        let defaults = Options::default();
+        Ok(Options {
+            layout_aware: match value.get("layout_aware") {
+                None => defaults.layout_aware,
+                Some(v) => v
+                    .as_bool()
+                    .ok_or_else(|| Error::Parse("layout_aware should be true or false".into()))?,
+            },
+        })
+    }
+}
+
+/// What was read from a PDF.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Extraction {
+    /// Pages separated by a form feed.
+    pub text: String,
+    pub pages: usize,
+    /// The fields of its interactive form, if it has one.
+    pub form: Vec<FormField>,
+    /// Highlights, notes and the other marks reviewers left on it.
+    pub annotations: Vec<Annotation>,
+}
+
+impl Extraction {
+    pub fn to_json(&self) -> Value {
+        Value::object(vec![
+            ("text", Value::from(self.text.as_str())),
+            ("pages", Value::from(self.pages)),
+            ("form", docx_text::form_to_json(&self.form)),
+            (
+                "annotations",
+                pdf_annotations::annotations_to_json(&self.annotations),
+            ),
+        ])
+    }
+}
+
+/// The text of the document at `path`, with the default options.
+pub fn text(path: &Path) -> Result<String> {
+    Ok(extract(path, &Options::default())?.text)
+}
+
+pub fn text_from_bytes(bytes: &[u8]) -> Result<String> {
+    let pdf = Pdf::from_bytes(bytes.to_vec())?;
+    Ok(read(&pdf, &Options::default())?.text)
+}
+
+pub fn extract(path: &Path, options: &Options) -> Result<Extraction> {
+    read(&Pdf::open(path)?, options)
+}
+
+fn read(pdf: &Pdf, options: &Options) -> Result<Extraction> {
+    let pages = pdf.pages()?;
+    let mut spans = Vec::with_capacity(pages.len());
+    for page in &pages {
+        spans.push(page_spans(pdf, page)?);
+    }
+    let annotations = pdf_annotations::read(pdf, &pages, &spans)?;
+    let texts: Vec<String> = if options.layout_aware {
+        let mut rows: Vec<Vec<Row>> = spans.into_iter().map(rows).collect();
+        strip_margins(&mut rows, &pages);
+        rows.iter().map(|rows| page_text(rows)).collect()
+    } else {
+        spans.iter().map(|spans| drawn_text(spans)).collect()
+    };
+    Ok(Extraction {
+        text: texts.join("\u{c}"),
+        pages: pages.len(),
+        form: pdf_forms::fields(pdf)?,
+        annotations,
+    })
+}
+
+/// A piece of text drawn on a page, in its user space: from `x` to `end`
+/// along the baseline at `y`.
+#[derive(Debug, Clone)]
+pub(crate) struct Span {
+    pub(crate) x: f64,
+    pub(crate) end: f64,
+    pub(crate) y: f64,
+    pub(crate) size: f64,
+    pub(crate) text: String,
+    /// Where each character of `text` starts.
+    pub(crate) starts: Vec<f64>,
+}
+
+/// `[a b c d e f]`, as in `cm`.
+type Matrix = [f64; 6];
+
+const IDENTITY: Matrix = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+/// `m` then `n`.
+fn multiply(m: &Matrix, n: &Matrix) -> Matrix {
+    [
+        m[0] * n[0] + m[1] * n[2],
+        m[0] * n[1] + m[1] * n[3],
+        m[2] * n[0] + m[3] * n[2],
+        m[2] * n[1] + m[3] * n[3],
+        m[4] * n[0] + m[5] * n[2] + n[4],
+        m[4] * n[1] + m[5] * n[3] + n[5],
+    ]
+}
+
+fn matrix(operands: &[Object]) -> Option<Matrix> {
+    let mut m = [0.0; 6];
+    if operands.len() < 6 {
+        return None;
+    }
+    for (slot, operand) in m.iter_mut().zip(&operands[operands.len() - 6..]) {
+        *slot = operand.as_f64()?;
+    }
+    Some(m)
+}
+
+/// The graphics state, with the text state in it.
+#[derive(Clone)]
+struct State {
+    ctm: Matrix,
+    font: Option<Rc<Font>>,
+    size: f64,
+    char_spacing: f64,
+    word_spacing: f64,
+    /// Horizontal scaling, as a fraction.
+    scale: f64,
+    leading: f64,
+    rise: f64,
+}
+
+/// Runs content streams, collecting the text they draw.
+struct Interpreter<'a> {
+    pdf: &'a Pdf,
+    fonts: HashMap<Reference, Rc<Font>>,
+    spans: Vec<Span>,
+}
+
+pub(crate) fn page_spans(pdf: &Pdf, page: &Page) -> Result<Vec<Span>> {
+    let mut interpreter = Interpreter {
+        pdf,
+        fonts: HashMap::new(),
+        spans: Vec::new(),
+    };
+    let resources = pdf.dict(page.dict.get("Resources"))?;
+    interpreter.run(&pdf.contents(page)?, &resources, IDENTITY, 0)?;
+    Ok(interpreter.spans)
+}
+
+impl Interpreter<'_> {
+    fn run(
+        &mut self,
+        content: &[u8],
+        resources: &Dictionary,
+        ctm: Matrix,
+        depth: usize,
+    ) -> Result<()> {
+        let fonts = self.pdf.dict(resources.get("Font"))?;
+        let mut state = State {
+            ctm,
+            font: None,
+            size: 0.0,
+            char_spacing: 0.0,
+            word_spacing: 0.0,
+            scale: 1.0,
+            leading: 0.0,
+            rise: 0.0,
+        };
+        let mut saved = Vec::new();
+        let (mut tm, mut tlm) = (IDENTITY, IDENTITY);
+        for op in pdf_objects::operations(content)? {
+            let number = |i: usize| op.operands.get(i).and_then(Object::as_f64).unwrap_or(0.0);
+            match op.operator.as_str() {
+                "q" => saved.push(state.clone()),
+                "Q" => state = saved.pop().unwrap_or(state),
+                "cm" => {
+                    if let Some(m) = matrix(&op.operands) {
+                        state.ctm = multiply(&m, &state.ctm);
+                    }
+                }
+                "BT" => (tm, tlm) = (IDENTITY, IDENTITY),
+                "Tf" => {
+                    let name = op.operands.first().and_then(Object::as_name).unwrap_or("");
+                    state.font = self.font(fonts.get(name))?;
+                    state.size = number(1);
+                }
+                "Tc" => state.char_spacing = number(0),
+                "Tw" => state.word_spacing = number(0),
+                "Tz" => state.scale = number(0) / 100.0,
+                "TL" => state.leading = number(0),
+                "Ts" => state.rise = number(0),
+                "Td" | "TD" => {
+                    if op.operator == "TD" {
+                        state.leading = -number(1);
+                    }
+                    tlm = multiply(&[1.0, 0.0, 0.0, 1.0, number(0), number(1)], &tlm);
+                    tm = tlm;
+                }
+                "Tm" => {
+                    if let Some(m) = matrix(&op.operands) {
+                        (tm, tlm) = (m, m);
+                    }
+                }
+                "T*" | "'" | "\"" => {
+                    if op.operator == "\"" {
+                        state.word_spacing = number(0);
+                        state.char_spacing = number(1);
+                    }
+                    tlm = multiply(&[1.0, 0.0, 0.0, 1.0, 0.0, -state.leading], &tlm);
+                    tm = tlm;
+                    if let Some(text) = op.operands.last().and_then(Object::as_bytes) {
+                        self.show(text, &state, &mut tm);
+                    }
+                }
+                "Tj" => {
+                    if let Some(text) = op.operands.first().and_then(Object::as_bytes) {
+                        self.show(text, &state, &mut tm);
+                    }
+                }
+                "TJ" => {
+                    let items = op
+                        .operands
+                        .first()
+                        .and_then(Object::as_array)
+                        .unwrap_or(&[]);
+                    for item in items {
+                        match item {
+                            Object::String(text) => self.show(text, &state, &mut tm),
+                            item => {
+                                let tx = -item.as_f64().unwrap_or(0.0) / 1000.0
+                                    * state.size
+                                    * state.scale;
+                                tm = multiply(&[1.0, 0.0, 0.0, 1.0, tx, 0.0], &tm);
+                            }
+                        }
+                    }
+                }
+                "Do" if depth < MAX_FORM_DEPTH => {
+                    let name = op.operands.first().and_then(Object::as_name).unwrap_or("");
+                    let xobjects = self.pdf.dict(resources.get("XObject"))?;
+                    let Some(xobject) = xobjects.get(name) else {
+                        continue;
+                    };
+                    let Object::Stream(form) = self.pdf.resolve(xobject)? else {
+                        continue;
+                    };
+                    if form.dict.name("Subtype") != Some("Form") {
+                        continue;
+                    }
+                    let inner = match form.dict.get("Resources") {
+                        Some(r) => self.pdf.dict(Some(r))?,
+                        None => resources.clone(),
+                    };
+                    let m = form
+                        .dict
+                        .get("Matrix")
+                        .and_then(Object::as_array)
+                        .and_then(matrix)
+                        .unwrap_or(IDENTITY);
+                    let data = form.decode()?;
+                    self.run(&data, &inner, multiply(&m, &state.ctm), depth + 1)?;
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn font(&mut self, obj: Option<&Object>) -> Result<Option<Rc<Font>>> {
+        let Some(obj) = obj else {
+            return Ok(None);
+        };
+        if let Some(font) = obj.as_reference().and_then(|r| self.fonts.get(&r)) {
+            return Ok(Some(font.clone()));
+        }
+        let font = Rc::new(Font::load(self.pdf, &self.pdf.dict(Some(obj))?)?);
+        if let Some(r) = obj.as_reference() {
+            self.fonts.insert(r, font.clone());
+        }
+        Ok(Some(font))
+    }
+
+    /// Draw the string `bytes`, moving the text matrix past it.
+    fn show(&mut self, bytes: &[u8], state: &State, tm: &mut Matrix) {
+        let Some(font) = &state.font else {
+            return;
+        };
+        let render = [
+            state.size * state.scale,
+            0.0,
+            0.0,
+            state.size,
+            0.0,
+            state.rise,
+        ];
+        let start = multiply(&render, &multiply(tm, &state.ctm));
+        let mut text = String::new();
+        let mut starts = Vec::new();
+        for code in font.codes(bytes) {
+            let decoded = normalize(&font.decode(code));
+            let x = multiply(&render, &multiply(tm, &state.ctm))[4];
+            starts.extend(std::iter::repeat_n(x, decoded.chars().count()));
+            text.push_str(&decoded);
+            let spacing = match (code, font.two_byte) {
+                (32, false) => state.word_spacing,
+                _ => 0.0,
+            };
+            let tx = (font.width(code) / 1000.0 * state.size + state.char_spacing + spacing)
+                * state.scale;
+            *tm = multiply(&[1.0, 0.0, 0.0, 1.0, tx, 0.0], tm);
+        }
+        let end = multiply(&render, &multiply(tm, &state.ctm));
+        let size = start[2].hypot(start[3]);
+        self.spans.push(Span {
+            x: start[4],
+            end: end[4],
+            y: start[5],
+            size: if size > 0.0 { size } else { 1.0 },
+            text,
+            starts,
+        });
+    }
+}
+
+/// Ligatures spelled out; control characters dropped.
+fn normalize(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            'ﬀ' => out.push_str("ff"),
+            'ﬁ' => out.push_str("fi"),
+            'ﬂ' => out.push_str("fl"),
+            'ﬃ' => out.push_str("ffi"),
+            'ﬄ' => out.push_str("ffl"),
+            'ﬅ' | 'ﬆ' => out.push_str("st"),
+            '\t' => out.push(' '),
+            c if c.is_control() => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// What a font's character codes are, and how wide.
+struct Font {
+    /// Two bytes per code, as for composite (`Type0`) fonts.
+    two_byte: bool,
+    /// From the `ToUnicode` map.
+    unicode: HashMap<u32, String>,
+    /// A simple font's encoding, by code.
+    encoding: Vec<char>,
+    /// In thousandths of an em.
+    widths: HashMap<u32, f64>,
+    default_width: f64,
+}
+
+impl Font {
+    fn load(pdf: &Pdf, dict: &Dictionary) -> Result<Font> {
+        let two_byte = dict.name("Subtype") == Some("Type0");
+        let mut font = Font {
+            two_byte,
+            unicode: HashMap::new(),
+            encoding: WIN_ANSI.to_vec(),
+            widths: HashMap::new(),
+            default_width: 500.0,
+        };
+        if let Some(Object::Stream(cmap)) =
+            dict.get("ToUnicode").map(|o| pdf.resolve(o)).transpose()?
+        {
+            font.unicode = to_unicode(&cmap.decode()?)?;
+        }
+        if two_byte {
+            let descendants = dict
+                .get("DescendantFonts")
+                .map(|o| pdf.resolve(o))
+                .transpose()?
+                .unwrap_or(Object::Null);
+            let descendant = pdf.dict(descendants.as_array().and_then(|d| d.first()))?;
+            font.default_width = descendant
+                .get("DW")
+                .and_then(Object::as_f64)
+                .unwrap_or(1000.0);
+            let widths = match descendant.get("W") {
+                Some(w) => pdf.resolve(w)?,
+                None => Object::Null,
+            };
+            font.read_cid_widths(widths.as_array().unwrap_or(&[]));
+            return Ok(font);
+        }
+        let base = dict.name("BaseFont").unwrap_or("");
+        if base.contains("Courier") {
+            font.default_width = 600.0;
+        }
+        let first = dict.get("FirstChar").and_then(Object::as_i64).unwrap_or(0);
+        if let Some(widths) = dict.get("Widths").map(|o| pdf.resolve(o)).transpose()? {
+            for (i, width) in widths.as_array().unwrap_or(&[]).iter().enumerate() {
+                if let Some(width) = width.as_f64() {
+                    font.widths.insert((first + i as i64) as u32, width);
+                }
+            }
+        }
+        let encoding = match dict.get("Encoding") {
+            Some(e) => pdf.resolve(e)?,
+            None => Object::Null,
+        };
+        let (base_encoding, differences) = match &encoding {
+            Object::Name(name) => (Some(name.as_str()), None),
+            Object::Dictionary(d) => (d.name("BaseEncoding"), d.get("Differences")),
+            _ => (None, None),
+        };
+        if base_encoding.is_none_or(|name| name == "StandardEncoding") {
+            font.encoding['\'' as usize] = '’';
+            font.encoding['`' as usize] = '‘';
+        }
+        if let Some(differences) = differences.and_then(Object::as_array) {
+            let mut code = 0usize;
+            for item in differences {
+                match item {
+                    Object::Integer(n) => code = (*n).clamp(0, 255) as usize,
+                    Object::Name(name) => {
+                        if let (Some(c), true) = (glyph(name), code < 256) {
+                            font.encoding[code] = c;
+                        }
+                        code += 1;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(font)
+    }
+
+    /// `/W [c [w1 w2 ...] c_first c_last w ...]`.
+    fn read_cid_widths(&mut self, items: &[Object]) {
+        let mut i = 0;
+        while i + 1 < items.len() {
+            let Some(first) = items[i].as_i64() else {
+                break;
+            };
+            match &items[i + 1] {
+                Object::Array(widths) => {
+                    for (n, width) in widths.iter().enumerate() {
+                        if let Some(width) = width.as_f64() {
+                            self.widths.insert((first + n as i64) as u32, width);
+                        }
+                    }
+                    i += 2;
+                }
+                last => {
+                    let (Some(last), Some(width)) =
+                        (last.as_i64(), items.get(i + 2).and_then(Object::as_f64))
+                    else {
+                        break;
+                    };
+                    for code in first..=last.min(first + 0xffff) {
+                        self.widths.insert(code as u32, width);
+                    }
+                    i += 3;
+                }
+            }
+        }
+    }
+
+    fn codes(&self, bytes: &[u8]) -> Vec<u32> {
+        match self.two_byte {
+            true => bytes
+                .chunks(2)
+                .map(|pair| pair.iter().fold(0, |acc, &b| acc << 8 | u32::from(b)))
+                .collect(),
+            false => bytes.iter().map(|&b| u32::from(b)).collect(),
+        }
+    }
+
+    fn decode(&self, code: u32) -> String {
+        if let Some(text) = self.unicode.get(&code) {
+            return text.clone();
+        }
+        match self.two_byte {
+            true => String::new(),
+            false => match self.encoding.get(code as usize) {
+                Some('\u{fffd}') | None => String::new(),
+                Some(c) => c.to_string(),
+            },
+        }
+    }
+
+    fn width(&self, code: u32) -> f64 {
+        self.widths
+            .get(&code)
+            .copied()
+            .unwrap_or(self.default_width)
+    }
+}
+
+/// WinAnsiEncoding: Latin-1 but for 0x80 to 0x9f.
+const WIN_ANSI: [char; 256] = {
+    const HIGH: [char; 32] = [
+        '€', '\u{fffd}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{fffd}', 'Ž',
+        '\u{fffd}', '\u{fffd}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ',
+        '\u{fffd}', 'ž', 'Ÿ',
+    ];
+    let mut table = ['\u{fffd}'; 256];
+    let mut i = 0;
+    while i < 256 {
+        table[i] = match i {
+            0x80..=0x9f => HIGH[i - 0x80],
+            _ => i as u8 as char,
+        };
+        i += 1;
+    }
+    table
+};
+
+/// Glyph names of the Adobe Glyph List seen in `/Differences`, for
+/// characters outside the letters and digits, whose names are themselves.
+const GLYPHS: &[(&str, char)] = &[
+    ("space", ' '),
+    ("exclam", '!'),
+    ("quotedbl", '"'),
+    ("numbersign", '#'),
+    ("dollar", '$'),
+    ("percent", '%'),
+    ("ampersand", '&'),
+    ("quotesingle", '\''),
+    ("quoteright", '’'),
+    ("parenleft", '('),
+    ("parenright", ')'),
+    ("asterisk", '*'),
+    ("plus", '+'),
+    ("comma", ','),
+    ("hyphen", '-'),
+    ("minus", '-'),
+    ("period", '.'),
+    ("slash", '/'),
+    ("zero", '0'),
+    ("one", '1'),
+    ("two", '2'),
+    ("three", '3'),
+    ("four", '4'),
+    ("five", '5'),
+    ("six", '6'),
+    ("seven", '7'),
+    ("eight", '8'),
+    ("nine", '9'),
+    ("colon", ':'),
+    ("semicolon", ';'),
+    ("less", '<'),
+    ("equal", '='),
+    ("greater", '>'),
+    ("question", '?'),
+    ("at", '@'),
+    ("bracketleft", '['),
+    ("backslash", '\\'),
+    ("bracketright", ']'),
+    ("asciicircum", '^'),
+    ("underscore", '_'),
+    ("grave", '`'),
+    ("quoteleft", '‘'),
+    ("braceleft", '{'),
+    ("bar", '|'),
+    ("braceright", '}'),
+    ("asciitilde", '~'),
+    ("bullet", '•'),
+    ("endash", '–'),
+    ("emdash", '—'),
+    ("ellipsis", '…'),
+    ("quotedblleft", '“'),
+    ("quotedblright", '”'),
+    ("quotesinglbase", '‚'),
+    ("quotedblbase", '„'),
+    ("guillemotleft", '«'),
+    ("guillemotright", '»'),
+    ("dagger", '†'),
+    ("daggerdbl", '‡'),
+    ("fi", 'ﬁ'),
+    ("fl", 'ﬂ'),
+    ("ff", 'ﬀ'),
+    ("ffi", 'ﬃ'),
+    ("ffl", 'ﬄ'),
+    ("copyright", '©'),
+    ("registered", '®'),
+    ("trademark", '™'),
+    ("degree", '°'),
+    ("section", '§'),
+    ("paragraph", '¶'),
+    ("Euro", '€'),
+    ("euro", '€'),
+    ("sterling", '£'),
+    ("yen", '¥'),
+    ("cent", '¢'),
+    ("germandbls", 'ß'),
+    ("nbspace", '\u{a0}'),
+    ("periodcentered", '·'),
+    ("multiply", '×'),
+    ("divide", '÷'),
+    ("plusminus", '±'),
+    ("exclamdown", '¡'),
+    ("questiondown", '¿'),
+    ("dotlessi", 'ı'),
+    ("ae", 'æ'),
+    ("AE", 'Æ'),
+    ("oslash", 'ø'),
+    ("Oslash", 'Ø'),
+    ("oe", 'œ'),
+    ("OE", 'Œ'),
+];
+
+/// The accents of composed glyph names, as in `eacute`.
+const ACCENTS: &[&str] = &[
+    "acute",
+    "grave",
+    "circumflex",
+    "tilde",
+    "dieresis",
+    "ring",
+    "cedilla",
+    "caron",
+];
+
+/// The character a glyph name stands for, if known. A composed letter
+/// (`eacute`) gives its letter; its accent is lost.
+fn glyph(name: &str) -> Option<char> {
+    let name = name.split('.').next().unwrap_or(name);
+    if let Some((_, c)) = GLYPHS.iter().find(|(n, _)| *n == name) {
+        return Some(*c);
+    }
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(c);
+    }
+    for prefix in ["uni", "u"] {
+        if let Some(hex) = name.strip_prefix(prefix) {
+            if (4..=6).contains(&hex.len()) {
+                return u32::from_str_radix(hex, 16).ok().and_then(char::from_u32);
+            }
+        }
+    }
+    let (letter, accent) = name.split_at(name.chars().next()?.len_utf8());
+    ACCENTS
+        .contains(&accent)
+        .then(|| letter.chars().next())
+        .flatten()
+        .filter(char::is_ascii_alphabetic)
+}
+
+/// The `bfchar` and `bfrange` mappings of a `ToUnicode` CMap.
+fn to_unicode(cmap: &[u8]) -> Result<HashMap<u32, String>> {
+    let code = |obj: &Object| {
+        obj.as_bytes()
+            .map(|b| b.iter().fold(0u32, |acc, &b| acc << 8 | u32::from(b)))
+    };
+    let utf16 = |bytes: &[u8]| -> Vec<u16> {
+        bytes
+            .chunks(2)
+            .map(|pair| pair.iter().fold(0u16, |acc, &b| acc << 8 | u16::from(b)))
+            .collect()
+    };
+    let mut map = HashMap::new();
+    for op in pdf_objects::operations(cmap)? {
+        match op.operator.as_str() {
+            "endbfchar" => {
+                for pair in op.operands.chunks_exact(2) {
+                    if let (Some(code), Some(text)) = (code(&pair[0]), pair[1].as_bytes()) {
+                        map.insert(code, String::from_utf16_lossy(&utf16(text)));
+                    }
+                }
+            }
+            "endbfrange" => {
+                for range in op.operands.chunks_exact(3) {
+                    let (Some(low), Some(high)) = (code(&range[0]), code(&range[1])) else {
+                        continue;
+                    };
+                    let high = high.min(low.saturating_add(0xffff));
+                    match &range[2] {
+                        Object::Array(texts) => {
+                            for (code, text) in (low..=high).zip(texts) {
+                                if let Some(text) = text.as_bytes() {
+                                    map.insert(code, String::from_utf16_lossy(&utf16(text)));
+                                }
+                            }
+                        }
+                        Object::String(first) => {
+                            let first = utf16(first);
+                            for (n, code) in (low..=high).enumerate() {
+                                let mut units = first.clone();
+                                if let Some(last) = units.last_mut() {
+                                    *last = last.wrapping_add(n as u16);
+                                }
+                                map.insert(code, String::from_utf16_lossy(&units));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(map)
+}
+
+/// Lines in the order they are drawn.
+fn drawn_text(spans: &[Span]) -> String {
+    let mut lines: Vec<(f64, f64, String)> = Vec::new();
+    for span in spans.iter().filter(|s| !s.text.trim().is_empty()) {
+        match lines.last_mut() {
+            Some((y, end, text)) if (*y - span.y).abs() <= span.size / 2.0 => {
+                if span.x - *end > SPACE * span.size && !text.ends_with(' ') {
+                    text.push(' ');
+                }
+                text.push_str(&span.text);
+                *end = span.end;
+            }
+            _ => lines.push((span.y, span.end, span.text.clone())),
+        }
+    }
+    let mut out: String = lines
+        .iter()
+        .map(|(_, _, text)| text.trim())
+        .collect::<Vec<_>>()
+        .join("\n");
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
+
+/// Text on one baseline, split where it has a gap wide enough to be a
+/// gutter.
+#[derive(Debug, Clone)]
+struct Row {
+    y: f64,
+    size: f64,
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone)]
+struct Segment {
+    x: f64,
+    end: f64,
+    text: String,
+}
+
+impl Row {
+    fn text(&self) -> String {
+        let texts: Vec<&str> = self.segments.iter().map(|s| s.text.as_str()).collect();
+        texts.join(" ")
+    }
+}
+
+/// The spans grouped by baseline, top to bottom.
+fn rows(mut spans: Vec<Span>) -> Vec<Row> {
+    spans.retain(|s| !s.text.trim().is_empty());
+    spans.sort_by(|a, b| b.y.total_cmp(&a.y).then(a.x.total_cmp(&b.x)));
+    let mut groups: Vec<Vec<Span>> = Vec::new();
+    for span in spans {
+        match groups.last_mut() {
+            Some(group) if (group[0].y - span.y).abs() <= group[0].size.min(span.size) / 2.0 => {
+                group.push(span)
+            }
+            _ => groups.push(vec![span]),
+        }
+    }
+    groups
+        .into_iter()
+        .map(|mut group| {
+            let y = group[0].y;
+            group.sort_by(|a, b| a.x.total_cmp(&b.x));
+            let size = group.iter().map(|s| s.size).fold(0.0, f64::max);
+            let mut segments: Vec<Segment> = Vec::new();
+            for span in group {
+                match segments.last_mut() {
+                    Some(segment) if span.x - segment.end <= GUTTER * size => {
+                        if span.x - segment.end > SPACE * size && !segment.text.ends_with(' ') {
+                            segment.text.push(' ');
+                        }
+                        segment.text.push_str(&span.text);
+                        segment.end = segment.end.max(span.end);
+                    }
+                    _ => segments.push(Segment {
+                        x: span.x,
+                        end: span.end,
+                        text: span.text,
+                    }),
+                }
+            }
+            for segment in &mut segments {
+                segment.text = segment.text.trim().to_string();
+            }
+            Row { y, size, segments }
+        })
+        .collect()
+}
+
+/// Leave out rows at the top and bottom of the pages that repeat on most
+/// of them, digits aside, and page numbers there.
+fn strip_margins(pages: &mut [Vec<Row>], boxes: &[Page]) {
+    let key = |row: &Row| -> String {
+        row.text()
+            .chars()
+            .map(|c| {
+                if c.is_ascii_digit() {
+                    '#'
+                } else {
+                    c.to_ascii_lowercase()
+                }
+            })
+            .filter(|c| !c.is_whitespace())
+            .collect()
+    };
+    // The rows in each page's bands, as indexes.
+    let mut margins: Vec<Vec<usize>> = Vec::new();
+    for (rows, page) in pages.iter().zip(boxes) {
+        let [_, bottom, _, top] = page.media_box();
+        let band = (top - bottom) * MARGIN_BAND;
+        let n = rows.len();
+        let edges = (0..n.min(2)).chain(n.saturating_sub(2).max(2)..n);
+        margins.push(
+            edges
+                .filter(|&i| rows[i].y >= top - band || rows[i].y <= bottom + band)
+                .collect(),
+        );
+    }
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for (rows, margin) in pages.iter().zip(&margins) {
+        let mut keys: Vec<String> = margin.iter().map(|&i| key(&rows[i])).collect();
+        keys.sort();
+        keys.dedup();
+        for key in keys {
+            *counts.entry(key).or_default() += 1;
+        }
+    }
+    let total = pages.len();
+    for (rows, margin) in pages.iter_mut().zip(&margins) {
+        let drop: Vec<usize> = margin
+            .iter()
+            .copied()
+            .filter(|&i| {
+                let repeated = counts[&key(&rows[i])];
+                (repeated >= 2 && repeated * 2 >= total) || is_page_number(&rows[i].text())
+            })
+            .collect();
+        let mut i = 0;
+        rows.retain(|_| {
+            i += 1;
+            !drop.contains(&(i - 1))
+        });
+    }
+}
+
+/// `7`, `- 7 -`, `Page 7`, `7 of 12`, `vii`.
+fn is_page_number(text: &str) -> bool {
+    let text = text
+        .to_lowercase()
+        .replace("page", " ")
+        .replace("of", " ")
+        .replace('/', " ");
+    let words: Vec<&str> = text
+        .split(|c: char| c.is_whitespace() || matches!(c, '-' | '–' | '—'))
+        .filter(|w| !w.is_empty())
+        .collect();
+    (1..=2).contains(&words.len())
+        && words.iter().all(|w| {
+            w.chars().all(|c| c.is_ascii_digit())
+                || (w.len() <= 6 && w.chars().all(|c| matches!(c, 'i' | 'v' | 'x')))
+        })
+}
+
+/// The x ranges between columns: gaps crossed by few rows, with text on
+/// both sides of them in at least two rows and half of them.
+fn gutters(rows: &[Row]) -> Vec<(f64, f64)> {
+    let segments = || rows.iter().flat_map(|row| &row.segments);
+    let (Some(low), Some(high)) = (
+        segments().map(|s| s.x).min_by(f64::total_cmp),
+        segments().map(|s| s.end).max_by(f64::total_cmp),
+    ) else {
+        return Vec::new();
+    };
+    let width = (high - low).ceil();
+    if !(1.0..=20_000.0).contains(&width) {
+        return Vec::new();
+    }
+    let mut coverage = vec![0usize; width as usize + 1];
+    for segment in segments() {
+        let from = (segment.x - low).max(0.0) as usize;
+        let to = ((segment.end - low).ceil() as usize).min(coverage.len());
+        for bin in coverage.iter_mut().take(to).skip(from) {
+            *bin += 1;
+        }
+    }
+    let mut sizes: Vec<f64> = rows.iter().map(|r| r.size).collect();
+    sizes.sort_by(f64::total_cmp);
+    let min_width = sizes[sizes.len() / 2] * GUTTER;
+    let tolerance = rows.len() / 10;
+    let mut gutters = Vec::new();
+    let mut bin = 0;
+    while bin < coverage.len() {
+        if coverage[bin] > tolerance {
+            bin += 1;
+            continue;
+        }
+        let start = bin;
+        while bin < coverage.len() && coverage[bin] <= tolerance {
+            bin += 1;
+        }
+        let (from, to) = (low + start as f64, low + bin as f64);
+        if to - from < min_width || start == 0 || bin == coverage.len() {
+            continue;
+        }
+        let both_sides = rows
+            .iter()
+            .filter(|row| {
+                row.segments.iter().any(|s| s.end <= from + 0.5)
+                    && row.segments.iter().any(|s| s.x >= to - 0.5)
+            })
+            .count();
+        if both_sides >= 2 && both_sides * 2 >= rows.len() {
+            gutters.push((from, to));
+        }
+    }
+    gutters
+}
+
+/// A line in reading order, in the block of lines read together.
+struct Line {
+    y: f64,
+    size: f64,
+    block: usize,
+    text: String,
+}
+
+/// The lines of a page in reading order: each run of rows between rows
+/// that cross a gutter is read column by column.
+fn reading_order(rows: &[Row]) -> Vec<Line> {
+    let gutters = gutters(rows);
+    let mut out = Vec::new();
+    let mut columns: Vec<Vec<Line>> = (0..=gutters.len()).map(|_| Vec::new()).collect();
+    let mut blocks = 0;
+    let mut across: Option<usize> = None;
+    let flush = |columns: &mut Vec<Vec<Line>>, out: &mut Vec<Line>, blocks: &mut usize| {
+        for column in columns.iter_mut() {
+            if column.is_empty() {
+                continue;
+            }
+            for mut line in column.drain(..) {
+                line.block = *blocks;
+                out.push(line);
+            }
+            *blocks += 1;
+        }
+    };
+    for row in rows {
+        let crosses = row
+            .segments
+            .iter()
+            .any(|s| gutters.iter().any(|&(from, to)| s.x < from && s.end > to));
+        if crosses || gutters.is_empty() {
+            flush(&mut columns, &mut out, &mut blocks);
+            let block = *across.get_or_insert_with(|| {
+                blocks += 1;
+                blocks - 1
+            });
+            out.push(Line {
+                y: row.y,
+                size: row.size,
+                block,
+                text: row.text(),
+            });
+            continue;
+        }
+        across = None;
+        for (column, lines) in columns.iter_mut().enumerate() {
+            let texts: Vec<&str> = row
+                .segments
+                .iter()
+                .filter(|s| gutters.iter().filter(|&&(_, to)| s.x >= to - 0.5).count() == column)
+                .map(|s| s.text.as_str())
+                .collect();
+            if !texts.is_empty() {
+                lines.push(Line {
+                    y: row.y,
+                    size: row.size,
+                    block: 0,
+                    text: texts.join(" "),
+                });
+            }
+        }
+    }
+    flush(&mut columns, &mut out, &mut blocks);
+    out
+}
+
+/// A page's paragraphs, separated by a blank line.
+fn page_text(rows: &[Row]) -> String {
+    let lines = reading_order(rows);
+    let mut paragraphs: Vec<String> = Vec::new();
+    let mut previous: Option<&Line> = None;
+    for line in &lines {
+        let joined = previous.is_some_and(|prev| {
+            prev.block == line.block
+                && prev.y - line.y <= 1.7 * prev.size.max(line.size)
+                && (prev.size - line.size).abs() <= 0.15 * prev.size
+                && !starts_item(&line.text)
+        });
+        match paragraphs.last_mut() {
+            Some(paragraph) if joined => join_line(paragraph, &line.text),
+            _ => paragraphs.push(line.text.clone()),
+        }
+        previous = Some(line);
+    }
+    let mut out = paragraphs
+        .iter()
+        .map(|p| p.replace('\u{ad}', ""))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
+
+/// Whether a line starts a list item.
+fn starts_item(text: &str) -> bool {
+    let first = text.split_whitespace().next().unwrap_or("");
+    matches!(first, "•" | "▪" | "◦" | "‣" | "-" | "–" | "*")
+        || (first.len() > 1
+            && first.ends_with(['.', ')'])
+            && first[..first.len() - 1].chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Append `line` to `paragraph`, rejoining a word hyphenated across them.
+pub(crate) fn join_line(paragraph: &mut String, line: &str) {
+    let continues = line.starts_with(|c: char| c.is_lowercase());
+    if paragraph.ends_with('\u{ad}') {
+        paragraph.pop();
+        paragraph.push_str(line);
+        return;
+    }
+    let hyphenated = paragraph
+        .strip_suffix('-')
+        .is_some_and(|rest| rest.ends_with(|c: char| c.is_alphabetic()));
+    if hyphenated && continues {
+        paragraph.pop();
+    } else {
+        paragraph.push(' ');
+    }
+    paragraph.push_str(line);
+}
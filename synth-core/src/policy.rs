@@ -0,0 +1,338 @@
+//! Company policy, enforced inside the library so an embedding app cannot
+//! route around it.
+//!
+//! A policy limits which directories documents may be read from, caps the
+//! tokens a chat session may spend (estimated at four characters a token)
+//! and denies backend profiles by name or type. It is written in TOML or
+//! JSON of the same shape:
+//!
+//! ```toml
+//! [read]
+//! allow = ["~/Documents", "/Volumes/Shared"]
+//! deny = ["~/Documents/HR"]
+//!
+//! [session]
+//! max_tokens = 200000
+//!
+//! [backends]
+//! deny = ["ollama", "cloud"]   # profile names or types; "*" for all
+//! ```
+//!
+//! A managed `policy.toml` or `policy.json` in [`SYSTEM_DIR`] is loaded
+//! first; without one, the file named by `SYNTH_POLICY`; without either,
+//! the host may [`install`] one. Once in force a policy cannot be replaced
+//! or removed, and a policy file that exists but cannot be read denies
+//! everything. Violations fail with [`Error::Denied`].
+
+use std::fs::File;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Once, RwLock};
+
+use crate::backend::Profile;
+use crate::error::{Error, Result};
+use crate::json::{self, Value};
+use crate::toml;
+
+/// Where managed policies are deployed.
+#[cfg(target_os = "macos")]
+pub const SYSTEM_DIR: &str = "/Library/Application Support/Synth";
+#[cfg(not(target_os = "macos"))]
+pub const SYSTEM_DIR: &str = "/etc/synth";
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Policy {
+    /// Documents must be under one of these; `None` allows any directory.
+    pub read_allow: Option<Vec<PathBuf>>,
+    /// Never readable, even under an allowed directory.
+    pub read_deny: Vec<PathBuf>,
+    pub max_session_tokens: Option<u64>,
+    /// Backend profile names or types (`"ollama"`), or `"*"`.
+    pub deny_backends: Vec<String>,
+    /// Where the policy came from, for messages.
+    pub source: String,
+}
+
+fn strings(value: Option<&Value>) -> Option<Vec<String>> {
+    value.and_then(Value::as_array).map(|items| {
+        items
+            .iter()
+            .filter_map(Value::as_str)
+            .map(String::from)
+            .collect()
+    })
+}
+
+/// `~/x` → `$HOME/x`, with `.` and `..` resolved lexically.
+fn normalize(path: &Path) -> PathBuf {
+    let expanded = match (path.strip_prefix("~"), std::env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => Path::new(&home).join(rest),
+        _ => path.to_path_buf(),
+    };
+    let absolute = if expanded.is_absolute() {
+        expanded
+    } else {
+        std::env::current_dir().unwrap_or_default().join(expanded)
+    };
+    let mut out = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// The real location of `path`: symlinks resolved where it exists.
+fn resolve(path: &Path) -> PathBuf {
+    let path = normalize(path);
+    path.canonicalize().unwrap_or(path)
+}
+
+impl Policy {
+    pub fn from_json(value: &Value) -> Result<Policy> {
+        if !matches!(value, Value::Object(_)) {
+            return Err(Error::Parse("a policy must be an object".into()));
+        }
+        let section = |name| value.get(name);
+        let paths = |list: Option<Vec<String>>| {
+            list.map(|list| list.iter().map(|p| normalize(Path::new(p))).collect())
+        };
+        let read = section("read");
+        Ok(Policy {
+            read_allow: paths(strings(read.and_then(|r| r.get("allow")))),
+            read_deny: paths(strings(read.and_then(|r| r.get("deny")))).unwrap_or_default(),
+            max_session_tokens: section("session")
+                .and_then(|s| s.get("max_tokens"))
+                .and_then(Value::as_f64)
+                .map(|n| n.max(0.0) as u64),
+            deny_backends: strings(section("backends").and_then(|b| b.get("deny")))
+                .unwrap_or_default(),
+            source: "configuration".into(),
+        })
+    }
+
+    /// Read a `.toml` file, or JSON otherwise.
+    pub fn from_file(path: &Path) -> Result<Policy> {
+        let text = std::fs::read_to_string(path)?;
+        let value = match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => toml::parse(&text)?,
+            _ => json::parse(&text)?,
+        };
+        Ok(Policy {
+            source: path.display().to_string(),
+            ..Policy::from_json(&value)?
+        })
+    }
+
+    /// Denies every read, backend and session; used in place of a policy
+    /// file that exists but is broken.
+    fn lockdown(source: String) -> Policy {
+        Policy {
+            read_allow: Some(Vec::new()),
+            read_deny: Vec::new(),
+            max_session_tokens: Some(0),
+            deny_backends: vec!["*".into()],
+            source,
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let paths = |paths: &[PathBuf]| {
+            Value::Array(
+                paths
+                    .iter()
+                    .map(|p| Value::from(p.display().to_string()))
+                    .collect(),
+            )
+        };
+        let mut read = vec![("deny", paths(&self.read_deny))];
+        if let Some(allow) = &self.read_allow {
+            read.insert(0, ("allow", paths(allow)));
+        }
+        let mut session = Vec::new();
+        if let Some(max) = self.max_session_tokens {
+            session.push(("max_tokens", Value::from(max as f64)));
+        }
+        Value::object([
+            ("source", Value::from(self.source.as_str())),
+            ("read", Value::object(read)),
+            ("session", Value::object(session)),
+            (
+                "backends",
+                Value::object([(
+                    "deny",
+                    Value::Array(
+                        self.deny_backends
+                            .iter()
+                            .map(|b| Value::from(b.as_str()))
+                            .collect(),
+                    ),
+                )]),
+            ),
+        ])
+    }
+
+    pub fn allows_read(&self, path: &Path) -> bool {
+        let path = resolve(path);
+        let under = |roots: &[PathBuf]| roots.iter().any(|root| path.starts_with(resolve(root)));
+        self.read_allow.as_deref().is_none_or(under) && !under(&self.read_deny)
+    }
+
+    pub fn allows_backend(&self, profile: &Profile) -> bool {
+        !self
+            .deny_backends
+            .iter()
+            .any(|denied| denied == "*" || *denied == profile.name || denied == profile.kind.name())
+    }
+}
+
+/// Rough token count used for session caps.
+pub fn estimate_tokens(text: &str) -> u64 {
+    (text.chars().count() as u64).div_ceil(4)
+}
+
+static POLICY: RwLock<Option<Arc<Policy>>> = RwLock::new(None);
+static LOADED: Once = Once::new();
+
+/// The managed or `SYNTH_POLICY` file, read once per process.
+fn load() {
+    LOADED.call_once(|| {
+        let managed = ["policy.toml", "policy.json"]
+            .iter()
+            .map(|name| Path::new(SYSTEM_DIR).join(name))
+            .find(|path| path.exists());
+        let path = managed.or_else(|| std::env::var_os("SYNTH_POLICY").map(PathBuf::from));
+        if let Some(path) = path {
+            let policy = Policy::from_file(&path)
+                .unwrap_or_else(|e| Policy::lockdown(format!("{} ({e})", path.display())));
+            *POLICY.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(policy));
+        }
+    });
+}
+
+/// The policy in force, if any.
+pub fn current() -> Option<Arc<Policy>> {
+    load();
+    POLICY.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Put `policy` in force for the rest of the process. Fails if one already
+/// is, so a managed policy cannot be replaced by a laxer one.
+pub fn install(policy: Policy) -> Result<()> {
+    load();
+    let mut slot = POLICY.write().unwrap_or_else(|e| e.into_inner());
+    if let Some(existing) = slot.as_ref() {
+        return Err(Error::Denied(format!(
+            "a policy is already in force (from {})",
+            existing.source
+        )));
+    }
+    *slot = Some(Arc::new(policy));
+    Ok(())
+}
+
+pub fn check_read(path: &Path) -> Result<()> {
+    match current() {
+        Some(policy) if !policy.allows_read(path) => Err(Error::Denied(format!(
+            "reading {} is not allowed",
+            path.display()
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Whether the policy in force restricts reads at all.
+fn restricts_reads() -> bool {
+    current().is_some_and(|policy| policy.read_allow.is_some() || !policy.read_deny.is_empty())
+}
+
+/// Where the open file `fd` is: `fcntl(F_GETPATH)` on Apple platforms,
+/// `/proc/self/fd` elsewhere.
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+fn descriptor_path(fd: i32) -> Option<PathBuf> {
+    use std::ffi::{c_char, c_int, CStr, OsStr};
+    use std::os::unix::ffi::OsStrExt;
+
+    const F_GETPATH: c_int = 50;
+    const MAXPATHLEN: usize = 1024;
+    extern "C" {
+        fn fcntl(fd: c_int, cmd: c_int, ...) -> c_int;
+    }
+    let mut buffer = [0 as c_char; MAXPATHLEN];
+    // SAFETY: F_GETPATH writes a NUL-terminated path of at most
+    // MAXPATHLEN bytes into the buffer.
+    if unsafe { fcntl(fd, F_GETPATH, buffer.as_mut_ptr()) } == -1 {
+        return None;
+    }
+    // SAFETY: on success the buffer holds a NUL-terminated string.
+    let path = unsafe { CStr::from_ptr(buffer.as_ptr()) };
+    Some(PathBuf::from(OsStr::from_bytes(path.to_bytes())))
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "ios")))]
+fn descriptor_path(fd: i32) -> Option<PathBuf> {
+    std::fs::read_link(format!("/proc/self/fd/{fd}")).ok()
+}
+
+/// Check a file the host opened itself, by where its descriptor really
+/// is. Where that cannot be found out, descriptor reads are refused
+/// while reads are restricted at all.
+pub fn check_descriptor(fd: i32) -> Result<()> {
+    if !restricts_reads() {
+        return Ok(());
+    }
+    match descriptor_path(fd) {
+        Some(path) => check_read(&path),
+        None => Err(Error::Denied(
+            "files passed as descriptors cannot be checked against the read policy".into(),
+        )),
+    }
+}
+
+/// Open a document, if the policy allows it. The file is opened first and
+/// its descriptor checked, so the path cannot be swapped for a symlink to
+/// a forbidden file between the check and the read.
+pub fn open_file(path: &Path) -> Result<File> {
+    check_read(path)?;
+    let file = File::open(path)?;
+    if restricts_reads() {
+        #[cfg(unix)]
+        check_descriptor(std::os::unix::io::AsRawFd::as_raw_fd(&file))?;
+        // Without descriptors, the path is checked again, now it is open.
+        #[cfg(not(unix))]
+        check_read(path)?;
+    }
+    Ok(file)
+}
+
+/// Read a document's bytes, if the policy allows it.
+pub fn read_file(path: &Path) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    open_file(path)?.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+pub fn check_backend(profile: &Profile) -> Result<()> {
+    match current() {
+        Some(policy) if !policy.allows_backend(profile) => Err(Error::Denied(format!(
+            "backend profile '{}' is not allowed",
+            profile.name
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Whether a session that has spent `used` tokens may spend `more`.
+pub fn check_session_tokens(used: u64, more: u64) -> Result<()> {
+    match current().and_then(|policy| policy.max_session_tokens) {
+        Some(max) if used + more > max => Err(Error::Denied(format!(
+            "session token limit reached ({used} of {max} used)"
+        ))),
+        _ => Ok(()),
+    }
+}
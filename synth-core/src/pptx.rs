@@ -0,0 +1,238 @@
+//! PowerPoint (.pptx) text, slide by slide, with each slide's speaker
+//! notes kept apart from what is shown on it.
+//!
+//! Slides come in presentation order. Text is read from shapes, groups
+//! and tables; pictures, charts and SmartArt have none here.
+
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::json::Value;
+use crate::opc;
+use crate::xml::{self, Element};
+use crate::zip::Archive;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Slide {
+    /// 1 for the first slide shown.
+    pub number: usize,
+    /// The title placeholder's text, if the slide has one.
+    pub title: Option<String>,
+    /// The rest of the slide's text, one line per paragraph and a blank
+    /// line between shapes.
+    pub body: String,
+    /// The speaker notes; empty when there are none.
+    pub notes: String,
+}
+
+impl Slide {
+    pub fn to_json(&self, content: SlideContent) -> Value {
+        let mut pairs = vec![("number", Value::from(self.number))];
+        if content != SlideContent::Notes {
+            pairs.push((
+                "title",
+                self.title.as_deref().map_or(Value::Null, Value::from),
+            ));
+            pairs.push(("body", Value::from(self.body.as_str())));
+        }
+        if content != SlideContent::Body {
+            pairs.push(("notes", Value::from(self.notes.as_str())));
+        }
+        Value::object(pairs)
+    }
+}
+
+/// Which text of each slide to return.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlideContent {
+    All,
+    /// What the audience sees: title and body.
+    Body,
+    /// The speaker notes alone.
+    Notes,
+}
+
+impl SlideContent {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "all" => Some(SlideContent::All),
+            "body" => Some(SlideContent::Body),
+            "notes" => Some(SlideContent::Notes),
+            _ => None,
+        }
+    }
+}
+
+pub fn slides_to_json(slides: &[Slide], content: SlideContent) -> Value {
+    Value::Array(slides.iter().map(|s| s.to_json(content)).collect())
+}
+
+/// The slides of the presentation at `path`.
+pub fn slides(path: &Path) -> Result<Vec<Slide>> {
+    read_slides(&Archive::open(path)?)
+}
+
+pub fn slides_from_bytes(bytes: &[u8]) -> Result<Vec<Slide>> {
+    read_slides(&Archive::from_bytes(bytes.to_vec())?)
+}
+
+fn read_slides(archive: &Archive) -> Result<Vec<Slide>> {
+    let presentation = "ppt/presentation.xml";
+    let root = xml::parse(&archive.read_string(presentation)?, false)?;
+    let rels = opc::relationships(archive, presentation)?;
+    let order = root
+        .child("p:sldIdLst")
+        .ok_or_else(|| Error::Parse("presentation has no slide list".into()))?;
+    let mut slides = Vec::new();
+    for id in order.elements().filter_map(|el| el.attr("r:id")) {
+        let Some(rel) = rels.iter().find(|r| r.id == id && r.kind == "slide") else {
+            continue;
+        };
+        let slide = xml::parse(&archive.read_string(&rel.target)?, true)?;
+        let (title, body) = shape_text(&slide, false);
+        let notes = match opc::relationships(archive, &rel.target)?
+            .into_iter()
+            .find(|r| r.kind == "notesSlide" && !r.external)
+        {
+            Some(notes) if archive.contains(&notes.target) => {
+                let notes = xml::parse(&archive.read_string(&notes.target)?, true)?;
+                shape_text(&notes, true).1
+            }
+            _ => String::new(),
+        };
+        slides.push(Slide {
+            number: slides.len() + 1,
+            title,
+            body,
+            notes,
+        });
+    }
+    Ok(slides)
+}
+
+/// The placeholder type of a shape, e.g. "title", "body" or "sldNum".
+fn placeholder(shape: &Element) -> Option<&str> {
+    let ph = shape.find("p:nvPr")?.child("p:ph")?;
+    // A placeholder without a type is a body placeholder.
+    Some(ph.attr("type").unwrap_or("body"))
+}
+
+/// The title and the other text of a slide's shapes. On a notes page only
+/// the notes placeholder counts, not the slide image or page number.
+fn shape_text(part: &Element, notes: bool) -> (Option<String>, String) {
+    let mut title = None;
+    let mut blocks = Vec::new();
+    let Some(tree) = part.find("p:spTree") else {
+        return (None, String::new());
+    };
+    let mut shapes = Vec::new();
+    collect_shapes(tree, &mut shapes);
+    for shape in shapes {
+        let kind = placeholder(shape);
+        if notes && kind != Some("body") {
+            continue;
+        }
+        let text = match shape.name.as_str() {
+            "p:graphicFrame" => table_text(shape),
+            _ => shape.child("p:txBody").map(body_text).unwrap_or_default(),
+        };
+        if text.trim().is_empty() {
+            continue;
+        }
+        match kind {
+            Some("title" | "ctrTitle") if title.is_none() => title = Some(text),
+            _ => blocks.push(text),
+        }
+    }
+    (title, blocks.join("\n\n"))
+}
+
+/// Shapes and table frames in drawing order, looking inside groups.
+fn collect_shapes<'a>(tree: &'a Element, out: &mut Vec<&'a Element>) {
+    for el in tree.elements() {
+        match el.name.as_str() {
+            "p:sp" | "p:graphicFrame" => out.push(el),
+            "p:grpSp" => collect_shapes(el, out),
+            // Alternate content offers the same shapes twice; take the first.
+            "mc:AlternateContent" => {
+                if let Some(choice) = el.elements().next() {
+                    collect_shapes(choice, out);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The paragraphs of a text body, one per line.
+fn body_text(body: &Element) -> String {
+    let lines: Vec<String> = body
+        .elements()
+        .filter(|el| el.name == "a:p")
+        .map(|p| {
+            let mut line = String::new();
+            for run in p.elements() {
+                match run.name.as_str() {
+                    "a:r" | "a:fld" => {
+                        if let Some(t) = run.child("a:t") {
+                            line.push_str(&t.text());
+                        }
+                    }
+                    "a:br" => line.push('\n'),
+                    _ => {}
+                }
+            }
+            line.trim_end().to_string()
+        })
+        .collect();
+    lines.join("\n").trim_matches('\n').to_string()
+}
+
+/// A table's rows, cells separated by " | ".
+fn table_text(frame: &Element) -> String {
+    let Some(table) = frame.find("a:tbl") else {
+        return String::new();
+    };
+    table
+        .elements()
+        .filter(|el| el.name == "a:tr")
+        .map(|row| {
+            let cells: Vec<String> = row
+                .elements()
+                .filter(|el| el.name == "a:tc")
+                .map(|cell| {
+                    cell.child("a:txBody")
+                        .map(body_text)
+                        .unwrap_or_default()
+                        .replace('\n', " ")
+                })
+                .collect();
+            cells.join(" | ")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// All the text of a presentation for prompts: each slide under a heading,
+/// its notes after it.
+pub fn render(slides: &[Slide]) -> String {
+    let mut out = String::new();
+    for slide in slides {
+        match &slide.title {
+            Some(title) => out.push_str(&format!("## Slide {}: {title}\n\n", slide.number)),
+            None => out.push_str(&format!("## Slide {}\n\n", slide.number)),
+        }
+        if !slide.body.is_empty() {
+            out.push_str(&slide.body);
+            out.push_str("\n\n");
+        }
+        if !slide.notes.is_empty() {
+            out.push_str("Notes:\n");
+            out.push_str(&slide.notes);
+            out.push_str("\n\n");
+        }
+    }
+    out.truncate(out.trim_end().len());
+    out.push('\n');
+    out
+}
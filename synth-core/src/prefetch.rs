@@ -0,0 +1,124 @@
+//! Background warm-up for a document the user has just opened, so the
+//! first question about it does not wait on extraction or a cold model.
+//!
+//! [`prefetch`] returns at once. On its own thread it extracts the
+//! document into a small cache that [`extract_text`] answers from while
+//! the file is unchanged, chunks a document too long for one prompt and
+//! embeds the chunks with the configured embedding model, priming the
+//! [index](crate::ask) questions about it are answered from, and asks the
+//! default backend to load its model (Ollama keeps it resident; other
+//! backends have nothing to load). The outcome is reported to telemetry
+//! as `prefetch`.
+//!
+//! [`extract_text`]: crate::extract::extract_text
+
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::SystemTime;
+
+use crate::ask;
+use crate::backend;
+use crate::error::Result;
+use crate::extract;
+use crate::policy;
+use crate::shutdown;
+use crate::telemetry;
+
+/// Documents kept extracted; the least recently prefetched goes first.
+const CACHED_DOCUMENTS: usize = 8;
+
+/// Which version of a file a cached text belongs to.
+#[derive(Debug, Clone, PartialEq)]
+struct Stamp {
+    path: PathBuf,
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl Stamp {
+    fn of(path: &Path) -> Option<Stamp> {
+        let path = path.canonicalize().ok()?;
+        let metadata = fs::metadata(&path).ok()?;
+        Some(Stamp {
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+            path,
+        })
+    }
+}
+
+#[derive(Default)]
+struct State {
+    documents: VecDeque<(Stamp, String)>,
+    /// Files being prefetched now, so repeated opens do the work once.
+    running: Vec<PathBuf>,
+}
+
+fn lock() -> MutexGuard<'static, State> {
+    static STATE: Mutex<State> = Mutex::new(State {
+        documents: VecDeque::new(),
+        running: Vec::new(),
+    });
+    STATE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// The prefetched text of `path`, if it has not changed since.
+pub(crate) fn cached(path: &Path) -> Option<String> {
+    let stamp = Stamp::of(path)?;
+    lock()
+        .documents
+        .iter()
+        .find(|(cached, _)| *cached == stamp)
+        .map(|(_, text)| text.clone())
+}
+
+/// Forget the prefetched documents.
+pub(crate) fn clear() {
+    lock().documents.clear();
+    ask::clear();
+}
+
+/// Start warming up for `path` in the background. Fails only if the read
+/// policy forbids the file; extraction errors surface on the real call.
+pub fn prefetch(path: &Path) -> Result<()> {
+    policy::check_read(path)?;
+    let path = path.to_path_buf();
+    {
+        let mut state = lock();
+        if state.running.contains(&path) {
+            return Ok(());
+        }
+        state.running.push(path.clone());
+    }
+    shutdown::spawn_thread(move || {
+        let _ = telemetry::measure("prefetch", || warm(&path));
+        lock().running.retain(|running| *running != path);
+    });
+    Ok(())
+}
+
+/// Do the warm-up for `path` now.
+pub fn warm(path: &Path) -> Result<()> {
+    let text = match cached(path) {
+        Some(text) => text,
+        None => {
+            let stamp = Stamp::of(path);
+            let text = extract::extract_text(path)?;
+            if let Some(stamp) = stamp {
+                let mut state = lock();
+                state
+                    .documents
+                    .retain(|(cached, _)| cached.path != stamp.path);
+                if state.documents.len() >= CACHED_DOCUMENTS {
+                    state.documents.pop_front();
+                }
+                state.documents.push_back((stamp, text.clone()));
+            }
+            text
+        }
+    };
+    ask::prime(&text);
+    backend::chain(None)?.first().map_or(Ok(()), backend::warm)
+}
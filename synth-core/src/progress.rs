@@ -0,0 +1,34 @@
+//! Stage-level progress reporting for multi-step pipelines.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Extracting,
+    Chunking,
+    Embedding,
+    Generating,
+    Writing,
+}
+
+impl Stage {
+    pub fn name(self) -> &'static str {
+        match self {
+            Stage::Extracting => "extracting",
+            Stage::Chunking => "chunking",
+            Stage::Embedding => "embedding",
+            Stage::Generating => "generating",
+            Stage::Writing => "writing",
+        }
+    }
+}
+
+/// One progress report. `completed` and `total` count items within the
+/// stage; `percent` (0-100) covers the whole pipeline.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress<'a> {
+    pub stage: Stage,
+    pub completed: usize,
+    pub total: usize,
+    pub percent: f64,
+    /// The item being worked on, such as a file name or chunk label.
+    pub item: Option<&'a str>,
+}
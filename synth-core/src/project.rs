@@ -0,0 +1,472 @@
+//! Projects: a named set of documents with their extraction cache and
+//! saved chat sessions, kept in one directory.
+//!
+//! Layout under `<root>/<name>/`:
+//! - `project.json`: name, creation time and document paths
+//! - `cache/<key>.txt`: extracted text, keyed by path, size and mtime
+//! - `sessions/<session>.json`: saved chat sessions
+//!
+//! [`Project::export`] packs the manifest and sessions into a tar archive
+//! that [`import`] unpacks on another machine. Documents are referenced by
+//! path, not copied, and the cache is rebuilt on demand. In the archive the
+//! paths are relative to the project directory, so documents kept beside
+//! the projects are found again when the whole folder moves.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{self, Component, Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::encryption;
+use crate::error::{Error, Result};
+use crate::extract::extract_text;
+use crate::hash::fnv1a64;
+use crate::json::{self, Value};
+use crate::policy;
+use crate::session::Session;
+use crate::store;
+use crate::tar;
+
+const MANIFEST: &str = "project.json";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Project {
+    pub name: String,
+    pub dir: PathBuf,
+    /// Seconds since the Unix epoch.
+    pub created: u64,
+    pub documents: Vec<PathBuf>,
+}
+
+impl Project {
+    pub fn to_json(&self) -> Value {
+        Value::object([
+            ("name", Value::from(self.name.as_str())),
+            ("dir", Value::from(self.dir.display().to_string())),
+            ("created", Value::from(self.created as f64)),
+            (
+                "documents",
+                Value::Array(
+                    self.documents
+                        .iter()
+                        .map(|p| Value::from(p.display().to_string()))
+                        .collect(),
+                ),
+            ),
+            (
+                "sessions",
+                Value::Array(
+                    self.sessions()
+                        .iter()
+                        .map(|s| Value::from(s.as_str()))
+                        .collect(),
+                ),
+            ),
+        ])
+    }
+
+    fn manifest_json(&self) -> Value {
+        self.manifest_with(self.documents.clone())
+    }
+
+    fn manifest_with(&self, documents: Vec<PathBuf>) -> Value {
+        Value::object([
+            ("name", Value::from(self.name.as_str())),
+            ("created", Value::from(self.created as f64)),
+            (
+                "documents",
+                Value::Array(
+                    documents
+                        .iter()
+                        .map(|p| Value::from(p.display().to_string()))
+                        .collect(),
+                ),
+            ),
+        ])
+    }
+
+    fn load(dir: &Path) -> Result<Project> {
+        let path = dir.join(MANIFEST);
+        let Some(text) = store::read_string(&path)? else {
+            return Err(not_found(&path));
+        };
+        let manifest = json::parse(&text)?;
+        Ok(Project {
+            name: manifest
+                .get("name")
+                .and_then(Value::as_str)
+                .ok_or_else(|| Error::Parse("project manifest has no name".into()))?
+                .to_string(),
+            dir: dir.to_path_buf(),
+            created: manifest
+                .get("created")
+                .and_then(Value::as_f64)
+                .unwrap_or(0.0) as u64,
+            documents: manifest
+                .get("documents")
+                .and_then(Value::as_array)
+                .unwrap_or(&[])
+                .iter()
+                .filter_map(Value::as_str)
+                .map(PathBuf::from)
+                .collect(),
+        })
+    }
+
+    fn save(&self) -> Result<()> {
+        store::write(
+            &self.dir.join(MANIFEST),
+            self.manifest_json().to_string().as_bytes(),
+        )
+    }
+
+    /// Add a document. Returns false if it was already in the project.
+    pub fn add_document(&mut self, path: &Path) -> Result<bool> {
+        if self.documents.iter().any(|p| p == path) {
+            return Ok(false);
+        }
+        self.documents.push(path.to_path_buf());
+        self.save()?;
+        Ok(true)
+    }
+
+    /// Remove a document and its cached text. Returns false if it was not
+    /// in the project.
+    pub fn remove_document(&mut self, path: &Path) -> Result<bool> {
+        let Some(index) = self.documents.iter().position(|p| p == path) else {
+            return Ok(false);
+        };
+        self.documents.remove(index);
+        self.save()?;
+        self.clear_cache(path);
+        Ok(true)
+    }
+
+    fn cache_key(path: &Path) -> Option<String> {
+        let meta = fs::metadata(path).ok()?;
+        let modified = meta
+            .modified()
+            .ok()?
+            .duration_since(UNIX_EPOCH)
+            .ok()?
+            .as_nanos();
+        let key = format!("{}\0{}\0{modified}", path.display(), meta.len());
+        Some(format!("{:016x}", fnv1a64(key.as_bytes())))
+    }
+
+    fn clear_cache(&self, path: &Path) {
+        if let Some(key) = Project::cache_key(path) {
+            let _ = store::remove(&self.dir.join("cache").join(format!("{key}.txt")));
+        }
+    }
+
+    /// Text of `path`, from the cache when the file is unchanged since it
+    /// was last extracted.
+    pub fn extract(&self, path: &Path) -> Result<String> {
+        // The cache must not serve documents the policy no longer allows.
+        policy::check_read(path)?;
+        let Some(key) = Project::cache_key(path) else {
+            return extract_text(path);
+        };
+        let cached = self.dir.join("cache").join(format!("{key}.txt"));
+        if let Ok(Some(text)) = store::read_string(&cached) {
+            return Ok(text);
+        }
+        let text = extract_text(path)?;
+        fs::create_dir_all(self.dir.join("cache"))?;
+        let _ = store::write(&cached, text.as_bytes());
+        Ok(text)
+    }
+
+    /// Names of the saved sessions, sorted.
+    pub fn sessions(&self) -> Vec<String> {
+        store::list(&self.dir.join("sessions"))
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|name| name.strip_suffix(".json").map(String::from))
+            .collect()
+    }
+
+    pub fn save_session(&self, name: &str, session: &Session) -> Result<()> {
+        check_name(name)?;
+        let dir = self.dir.join("sessions");
+        fs::create_dir_all(&dir)?;
+        store::write(
+            &dir.join(format!("{name}.json")),
+            session.to_saved_json().to_string().as_bytes(),
+        )
+    }
+
+    pub fn load_session(&self, name: &str) -> Result<Session> {
+        check_name(name)?;
+        let path = self.dir.join("sessions").join(format!("{name}.json"));
+        let Some(text) = store::read_string(&path)? else {
+            return Err(not_found(&path));
+        };
+        Session::from_saved_json(&json::parse(&text)?)
+    }
+
+    /// Write the manifest and saved sessions to a tar archive at `archive`.
+    /// Document paths are written relative to the project directory, or
+    /// as they are when the two share no root.
+    pub fn export(&self, archive: &Path) -> Result<()> {
+        let dir = path::absolute(&self.dir)?;
+        let documents = self
+            .documents
+            .iter()
+            .map(|document| {
+                let document = path::absolute(document)?;
+                Ok(relative_to(&document, &dir).unwrap_or(document))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mut entries = vec![(
+            format!("{}/{MANIFEST}", self.name),
+            self.manifest_with(documents).to_string().into_bytes(),
+        )];
+        for name in self.sessions() {
+            let path = self.dir.join("sessions").join(format!("{name}.json"));
+            let data = store::read(&path)?.unwrap_or_default();
+            entries.push((format!("{}/sessions/{name}.json", self.name), data));
+        }
+        let mtime = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let tmp = archive.with_extension("tar.tmp");
+        tar::write(&tmp, &entries, mtime)?;
+        fs::rename(&tmp, archive)?;
+        Ok(())
+    }
+}
+
+fn not_found(path: &Path) -> Error {
+    std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        format!("{} not found", path.display()),
+    )
+    .into()
+}
+
+/// `path` relative to `base`, both absolute, climbing out of `base` with
+/// `..` as needed. `None` when they have different roots.
+fn relative_to(path: &Path, base: &Path) -> Option<PathBuf> {
+    let path: Vec<Component> = path.components().collect();
+    let base: Vec<Component> = base.components().collect();
+    if path.first() != base.first() {
+        return None;
+    }
+    let shared = path.iter().zip(&base).take_while(|(a, b)| a == b).count();
+    let mut relative = PathBuf::new();
+    for _ in shared..base.len() {
+        relative.push("..");
+    }
+    relative.extend(&path[shared..]);
+    Some(relative)
+}
+
+/// `path` resolved against `base`, with `.` and `..` taken out.
+fn resolve(base: &Path, path: &Path) -> PathBuf {
+    let mut resolved = PathBuf::new();
+    for component in base.join(path).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            other => resolved.push(other),
+        }
+    }
+    resolved
+}
+
+/// Unpack an archive written by [`Project::export`] into `<root>/<name>`.
+/// Fails if a project with that name already exists; unexpected entries
+/// (anything other than the manifest and session files) are rejected.
+/// Relative document paths are resolved against the new project directory.
+pub fn import(archive: &Path, root: &Path) -> Result<Project> {
+    let entries = tar::read(archive)?;
+    let name = entries
+        .iter()
+        .find_map(|(path, _)| path.strip_suffix(&format!("/{MANIFEST}")))
+        .ok_or_else(|| Error::Parse("archive has no project manifest".into()))?
+        .to_string();
+    check_name(&name)?;
+    let dir = root.join(&name);
+    if dir.exists() {
+        return Err(Error::Parse(format!("project '{name}' already exists")));
+    }
+    let absolute_dir = path::absolute(&dir)?;
+    let mut files: Vec<(&str, Vec<u8>)> = Vec::new();
+    for (path, data) in &entries {
+        let relative = path
+            .strip_prefix(&format!("{name}/"))
+            .ok_or_else(|| Error::Parse(format!("unexpected archive entry '{path}'")))?;
+        let text = std::str::from_utf8(data)
+            .map_err(|_| Error::Parse(format!("'{path}' is not UTF-8")))?;
+        let value = json::parse(text)?;
+        match relative
+            .strip_prefix("sessions/")
+            .and_then(|f| f.strip_suffix(".json"))
+        {
+            _ if relative == MANIFEST => {
+                let mut value = value;
+                if let Value::Object(pairs) = &mut value {
+                    for (key, documents) in pairs.iter_mut() {
+                        if let (true, Value::Array(documents)) = (key == "documents", documents) {
+                            for document in documents.iter_mut() {
+                                if let Some(path) = document.as_str() {
+                                    let resolved = resolve(&absolute_dir, Path::new(path));
+                                    *document = Value::from(resolved.display().to_string());
+                                }
+                            }
+                        }
+                    }
+                }
+                files.push((relative, value.to_string().into_bytes()));
+                continue;
+            }
+            Some(session) => {
+                check_name(session)?;
+                Session::from_saved_json(&value)?;
+            }
+            None => return Err(Error::Parse(format!("unexpected archive entry '{path}'"))),
+        }
+        files.push((relative, data.clone()));
+    }
+    // Unpack beside the target and rename, so a failed import leaves no
+    // half-made project behind.
+    let staging = root.join(format!(".{name}.import"));
+    let _ = fs::remove_dir_all(&staging);
+    // Sealed under the names they end up with, so the project is loaded
+    // once it is in place.
+    let unpacked = (|| -> Result<()> {
+        fs::create_dir_all(staging.join("sessions"))?;
+        for (relative, data) in &files {
+            let name = store::key(&dir.join(relative))?;
+            fs::write(staging.join(relative), encryption::seal(data, &name)?)?;
+        }
+        fs::rename(&staging, &dir)?;
+        Ok(())
+    })();
+    if let Err(e) = unpacked {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(e);
+    }
+    Project::load(&dir).inspect_err(|_| {
+        let _ = fs::remove_dir_all(&dir);
+    })
+}
+
+/// Project and session names become directory and file names, so they
+/// must be a single plain path component.
+fn check_name(name: &str) -> Result<()> {
+    let valid = !name.trim().is_empty()
+        && name.chars().count() <= 100
+        && !name.starts_with('.')
+        && !name.contains(['/', '\\', ':', '\0']);
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::Parse(format!("invalid name '{name}'")))
+    }
+}
+
+/// Create an empty project at `<root>/<name>`.
+pub fn create(root: &Path, name: &str) -> Result<Project> {
+    check_name(name)?;
+    let dir = root.join(name);
+    if store::exists(&dir.join(MANIFEST))? {
+        return Err(Error::Parse(format!("project '{name}' already exists")));
+    }
+    fs::create_dir_all(&dir)?;
+    let project = Project {
+        name: name.to_string(),
+        dir,
+        created: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs()),
+        documents: Vec::new(),
+    };
+    project.save()?;
+    Ok(project)
+}
+
+pub fn open(root: &Path, name: &str) -> Result<Project> {
+    check_name(name)?;
+    Project::load(&root.join(name))
+}
+
+/// Projects under `root`, sorted by name. A missing root has none.
+pub fn list(root: &Path) -> Result<Vec<Project>> {
+    let entries = match fs::read_dir(root) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut projects: Vec<Project> = entries
+        .filter_map(|entry| Project::load(&entry.ok()?.path()).ok())
+        .collect();
+    projects.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(projects)
+}
+
+/// Delete a project directory. Only directories holding a project
+/// manifest are removed.
+pub fn delete(root: &Path, name: &str) -> Result<()> {
+    check_name(name)?;
+    let dir = root.join(name);
+    if !store::exists(&dir.join(MANIFEST))? {
+        return Err(Error::Parse(format!("no project '{name}'")));
+    }
+    close_dir(&dir);
+    store::remove_all(&dir)?;
+    fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+#[derive(Default)]
+struct Registry {
+    next: u64,
+    projects: BTreeMap<u64, Project>,
+}
+
+fn lock() -> MutexGuard<'static, Registry> {
+    static REGISTRY: Mutex<Registry> = Mutex::new(Registry {
+        next: 0,
+        projects: BTreeMap::new(),
+    });
+    REGISTRY.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Keep `project` open and return its handle (never 0).
+pub fn insert(project: Project) -> u64 {
+    let mut registry = lock();
+    registry.next += 1;
+    let handle = registry.next;
+    registry.projects.insert(handle, project);
+    handle
+}
+
+/// A copy of the open project behind `handle`.
+pub fn get(handle: u64) -> Option<Project> {
+    lock().projects.get(&handle).cloned()
+}
+
+/// Run `f` on the open project behind `handle`.
+pub fn with<T>(handle: u64, f: impl FnOnce(&mut Project) -> Result<T>) -> Result<T> {
+    let mut registry = lock();
+    let project = registry
+        .projects
+        .get_mut(&handle)
+        .ok_or_else(|| Error::Parse(format!("unknown project {handle}")))?;
+    f(project)
+}
+
+pub fn close(handle: u64) -> bool {
+    lock().projects.remove(&handle).is_some()
+}
+
+/// Close every handle on a project that is being deleted.
+fn close_dir(dir: &Path) {
+    lock().projects.retain(|_, project| project.dir != dir);
+}
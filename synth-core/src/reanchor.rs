@@ -0,0 +1,245 @@
+//! Re-locating annotations after the annotated document changes.
+//!
+//! Each annotation stores the quoted text and its character offsets in the
+//! old text. It is matched in order of confidence: unchanged at its old
+//! offsets, an exact copy of the quote elsewhere (closest surrounding
+//! context wins), then the best approximate match within an edit budget.
+//!
+//! The approximate search costs `quote × searched` character comparisons.
+//! It first searches a window around the old offset, scaled to the new
+//! length and as wide as the text grew or shrank (at least
+//! [`SEARCH_WINDOW`] characters either side), since that is as far as
+//! edits can have moved the quote; only when nothing fits there is the
+//! whole text scanned.
+
+use crate::error::{Error, Result};
+use crate::json::Value;
+
+/// Characters of surrounding context compared to rank exact matches.
+const CONTEXT_CHARS: usize = 32;
+/// Fuzzy matches may differ from the quote in at most this share of chars.
+const MAX_EDIT_RATIO: f64 = 0.25;
+/// The fuzzy search first looks at least this many chars either side of
+/// the expected spot.
+pub const SEARCH_WINDOW: usize = 4096;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    pub id: Value,
+    pub quote: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Annotation {
+    /// Parse `[{"id": ..., "quote": "...", "start": 10, "end": 24}]`. The
+    /// id is echoed back untouched and may be any JSON value. Offsets past
+    /// the old text are clamped to its end when matching; an end before
+    /// the start is an error.
+    pub fn list_from_json(value: &Value) -> Result<Vec<Annotation>> {
+        let items = value
+            .as_array()
+            .ok_or_else(|| Error::Parse("annotations must be an array".into()))?;
+        items
+            .iter()
+            .map(|item| {
+                let quote = item
+                    .get("quote")
+                    .and_then(Value::as_str)
+                    .ok_or_else(|| Error::Parse("annotation without a quote".into()))?;
+                let offset = |key| item.get(key).and_then(Value::as_f64).map(|n| n as usize);
+                let start = offset("start").unwrap_or(0);
+                let end = offset("end").unwrap_or(start.saturating_add(quote.chars().count()));
+                if end < start {
+                    return Err(Error::InvalidArgument(format!(
+                        "annotation ends at {end}, before its start {start}"
+                    )));
+                }
+                Ok(Annotation {
+                    id: item.get("id").cloned().unwrap_or(Value::Null),
+                    quote: quote.to_string(),
+                    start,
+                    end,
+                })
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// The quote is still at its old offsets.
+    Unchanged,
+    /// An exact copy of the quote was found elsewhere.
+    Moved,
+    /// Only an approximate match was found.
+    Fuzzy,
+    /// No acceptable match; the annotation should be shown as detached.
+    Orphaned,
+}
+
+impl Status {
+    pub fn name(self) -> &'static str {
+        match self {
+            Status::Unchanged => "unchanged",
+            Status::Moved => "moved",
+            Status::Fuzzy => "fuzzy",
+            Status::Orphaned => "orphaned",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Anchor {
+    pub id: Value,
+    pub status: Status,
+    /// New char range, if found.
+    pub range: Option<(usize, usize)>,
+    /// 1.0 for exact matches, `1 - edits / quote length` for fuzzy ones.
+    pub score: f64,
+}
+
+pub fn anchors_to_json(anchors: &[Anchor], new_text: &str) -> Value {
+    let chars: Vec<char> = new_text.chars().collect();
+    Value::Array(
+        anchors
+            .iter()
+            .map(|a| {
+                let mut pairs = vec![
+                    ("id", a.id.clone()),
+                    ("status", Value::from(a.status.name())),
+                    ("score", Value::from((a.score * 100.0).round() / 100.0)),
+                ];
+                if let Some((start, end)) = a.range {
+                    pairs.push(("start", Value::from(start)));
+                    pairs.push(("end", Value::from(end)));
+                    pairs.push((
+                        "quote",
+                        Value::from(chars[start..end].iter().collect::<String>()),
+                    ));
+                }
+                Value::object(pairs)
+            })
+            .collect(),
+    )
+}
+
+/// Re-locate each annotation made against `old_text` in `new_text`.
+pub fn reanchor(old_text: &str, new_text: &str, annotations: &[Annotation]) -> Vec<Anchor> {
+    let old: Vec<char> = old_text.chars().collect();
+    let new: Vec<char> = new_text.chars().collect();
+    annotations
+        .iter()
+        .map(|annotation| locate(&old, &new, annotation))
+        .collect()
+}
+
+fn locate(old: &[char], new: &[char], annotation: &Annotation) -> Anchor {
+    let quote: Vec<char> = annotation.quote.chars().collect();
+    let anchor = |status, range, score| Anchor {
+        id: annotation.id.clone(),
+        status,
+        range,
+        score,
+    };
+    if quote.is_empty() {
+        return anchor(Status::Orphaned, None, 0.0);
+    }
+    // Offsets are chars of the old text; ones past its end mean its end.
+    let start = annotation.start.min(old.len());
+    let end = annotation.end.clamp(start, old.len());
+    let unchanged = start..start + quote.len();
+    if new.get(unchanged.clone()) == Some(&quote[..]) {
+        return anchor(
+            Status::Unchanged,
+            Some((unchanged.start, unchanged.end)),
+            1.0,
+        );
+    }
+
+    // Expected position, scaled by how much the text grew or shrank.
+    let expected = if old.is_empty() {
+        0
+    } else {
+        (start as f64 * new.len() as f64 / old.len() as f64) as usize
+    };
+    let prefix = &old[start.saturating_sub(CONTEXT_CHARS)..start];
+    let suffix = &old[end..end.saturating_add(CONTEXT_CHARS).min(old.len())];
+    let exact = (0..=new.len().saturating_sub(quote.len()))
+        .filter(|&i| new[i..].starts_with(&quote))
+        .max_by_key(|&i| {
+            let before = common_suffix(prefix, &new[..i]);
+            let after = common_prefix(suffix, &new[i + quote.len()..]);
+            (before + after, std::cmp::Reverse(i.abs_diff(expected)))
+        });
+    if let Some(i) = exact {
+        return anchor(Status::Moved, Some((i, i + quote.len())), 1.0);
+    }
+
+    let budget = (quote.len() as f64 * MAX_EDIT_RATIO) as usize;
+    let window = SEARCH_WINDOW.max(new.len().abs_diff(old.len())) + quote.len();
+    let near = expected.saturating_sub(window).min(new.len())
+        ..expected.saturating_add(window).min(new.len());
+    let found = approximate(&quote, &new[near.clone()], budget)
+        .map(|(s, e, d)| (s + near.start, e + near.start, d))
+        .or_else(|| {
+            // The window was the whole text; scanning again finds nothing.
+            (near.len() < new.len())
+                .then(|| approximate(&quote, new, budget))
+                .flatten()
+        });
+    match found {
+        Some((s, e, distance)) => anchor(
+            Status::Fuzzy,
+            Some((s, e)),
+            1.0 - distance as f64 / quote.len() as f64,
+        ),
+        None => anchor(Status::Orphaned, None, 0.0),
+    }
+}
+
+fn common_prefix(a: &[char], b: &[char]) -> usize {
+    a.iter().zip(b).take_while(|(x, y)| x == y).count()
+}
+
+fn common_suffix(a: &[char], b: &[char]) -> usize {
+    a.iter()
+        .rev()
+        .zip(b.iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count()
+}
+
+/// Best approximate occurrence of `pattern` in `text` (Sellers' algorithm),
+/// as `(start, end, edit distance)`, if within `budget` edits.
+fn approximate(pattern: &[char], text: &[char], budget: usize) -> Option<(usize, usize, usize)> {
+    let m = pattern.len();
+    // cost[i] / from[i]: edit distance of pattern[..i] against the best
+    // substring ending at the current text position, and where it starts.
+    let mut cost: Vec<usize> = (0..=m).collect();
+    let mut from: Vec<usize> = vec![0; m + 1];
+    let mut best: Option<(usize, usize, usize)> = None;
+    for (j, &c) in text.iter().enumerate() {
+        let (mut diag_cost, mut diag_from) = (0, j);
+        cost[0] = 0;
+        from[0] = j + 1;
+        for i in 1..=m {
+            let (up_cost, up_from) = (cost[i], from[i]);
+            let substitute = diag_cost + usize::from(pattern[i - 1] != c);
+            let (mut next_cost, mut next_from) = (substitute, diag_from);
+            if up_cost + 1 < next_cost {
+                (next_cost, next_from) = (up_cost + 1, up_from);
+            }
+            if cost[i - 1] + 1 < next_cost {
+                (next_cost, next_from) = (cost[i - 1] + 1, from[i - 1]);
+            }
+            (diag_cost, diag_from) = (up_cost, up_from);
+            cost[i] = next_cost;
+            from[i] = next_from;
+        }
+        if cost[m] <= budget && best.is_none_or(|(_, _, d)| cost[m] < d) {
+            best = Some((from[m], j + 1, cost[m]));
+        }
+    }
+    best
+}
@@ -0,0 +1,311 @@
+//! A registry of ingested files by content, so indexes and caches built
+//! from a document know when to rebuild.
+//!
+//! [`record`] hashes a file (SHA-256) and files it as a version of a
+//! logical document: the first time a path is seen it starts a new
+//! document; new content at the same path adds the next version; a file
+//! whose content is that of a document whose file has disappeared is the
+//! same document moved, and continues its versions. Unchanged content
+//! records nothing. [`check`] reports the same without recording. When a
+//! directory is configured the registry persists in `<dir>/registry.json`.
+
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::Result;
+use crate::hash::{hex, Sha256};
+use crate::json::{self, Value};
+use crate::policy;
+use crate::store;
+
+/// One recorded state of a document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Version {
+    /// The logical document, the same across edits and moves.
+    pub document: u64,
+    /// 1 for the first version, counting up.
+    pub version: u64,
+    pub path: PathBuf,
+    pub sha256: String,
+    pub size: u64,
+    /// Seconds since the Unix epoch.
+    pub ingested: u64,
+}
+
+impl Version {
+    pub fn to_json(&self) -> Value {
+        Value::object([
+            ("document", Value::from(self.document as f64)),
+            ("version", Value::from(self.version as f64)),
+            ("path", Value::from(self.path.display().to_string())),
+            ("sha256", Value::from(self.sha256.as_str())),
+            ("size", Value::from(self.size as f64)),
+            ("ingested", Value::from(self.ingested as f64)),
+        ])
+    }
+
+    fn from_json(value: &Value) -> Option<Version> {
+        let number = |key| value.get(key).and_then(Value::as_f64).map(|n| n as u64);
+        Some(Version {
+            document: number("document")?,
+            version: number("version")?,
+            path: PathBuf::from(value.get("path")?.as_str()?),
+            sha256: value.get("sha256")?.as_str()?.to_string(),
+            size: number("size").unwrap_or(0),
+            ingested: number("ingested").unwrap_or(0),
+        })
+    }
+}
+
+pub fn versions_to_json(versions: &[Version]) -> Value {
+    Value::Array(versions.iter().map(Version::to_json).collect())
+}
+
+/// How a file relates to what the registry holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    /// No document is registered at the path or with the content.
+    New,
+    Unchanged,
+    /// Different content at a registered path.
+    Modified,
+    /// A registered document's content at a new path, its old file gone.
+    Moved,
+}
+
+impl Change {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Change::New => "new",
+            Change::Unchanged => "unchanged",
+            Change::Modified => "modified",
+            Change::Moved => "moved",
+        }
+    }
+}
+
+/// What [`record`] found and filed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recorded {
+    pub change: Change,
+    /// The document's version now current at the path.
+    pub version: Version,
+    /// The version it replaces, for modified and moved files.
+    pub previous: Option<Version>,
+}
+
+impl Recorded {
+    pub fn to_json(&self) -> Value {
+        Value::object([
+            ("change", Value::from(self.change.name())),
+            ("version", self.version.to_json()),
+            (
+                "previous",
+                self.previous.as_ref().map_or(Value::Null, Version::to_json),
+            ),
+        ])
+    }
+}
+
+#[derive(Default)]
+struct Store {
+    dir: Option<PathBuf>,
+    /// Every version, oldest first.
+    versions: Vec<Version>,
+}
+
+impl Store {
+    fn save(&self) {
+        let Some(dir) = &self.dir else {
+            return;
+        };
+        let path = dir.join("registry.json");
+        let _ = store::write(
+            &path,
+            versions_to_json(&self.versions).to_string().as_bytes(),
+        );
+    }
+
+    /// The latest version of each document.
+    fn latest(&self) -> Vec<&Version> {
+        let mut latest: Vec<&Version> = Vec::new();
+        for version in &self.versions {
+            match latest.iter_mut().find(|v| v.document == version.document) {
+                Some(slot) if slot.version < version.version => *slot = version,
+                Some(_) => {}
+                None => latest.push(version),
+            }
+        }
+        latest
+    }
+
+    /// How content `sha256` at `path` compares, and the version it follows.
+    fn compare(&self, path: &Path, sha256: &str) -> (Change, Option<Version>) {
+        let latest = self.latest();
+        if let Some(current) = latest.iter().find(|v| v.path == path) {
+            let change = if current.sha256 == sha256 {
+                Change::Unchanged
+            } else {
+                Change::Modified
+            };
+            return (change, Some((*current).clone()));
+        }
+        match latest
+            .iter()
+            .find(|v| v.sha256 == sha256 && !v.path.exists())
+        {
+            Some(gone) => (Change::Moved, Some((*gone).clone())),
+            None => (Change::New, None),
+        }
+    }
+
+    /// The versions of the document now at `path`, oldest first.
+    fn history(&self, path: &Path) -> Vec<Version> {
+        let Some(document) = self
+            .latest()
+            .into_iter()
+            .find(|v| v.path == path)
+            .map(|v| v.document)
+        else {
+            return Vec::new();
+        };
+        self.versions
+            .iter()
+            .filter(|v| v.document == document)
+            .cloned()
+            .collect()
+    }
+}
+
+fn lock() -> MutexGuard<'static, Store> {
+    static STORE: Mutex<Store> = Mutex::new(Store {
+        dir: None,
+        versions: Vec::new(),
+    });
+    STORE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// The path as registered: canonical when it exists.
+fn key(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// The SHA-256 and size of a file, read in blocks.
+fn digest(path: &Path) -> Result<(String, u64)> {
+    policy::check_read(path)?;
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::default();
+    let mut buffer = [0; 64 * 1024];
+    let mut size = 0;
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        size += read as u64;
+    }
+    Ok((hex(&hasher.finish()), size))
+}
+
+/// Persist the registry under `dir`, loading any saved there earlier.
+/// Without it the registry lives in memory only.
+pub fn init(dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir)?;
+    let path = dir.join("registry.json");
+    let loaded: Vec<Version> = match store::read_string(&path)? {
+        Some(text) => json::parse(&text)?
+            .as_array()
+            .unwrap_or(&[])
+            .iter()
+            .filter_map(Version::from_json)
+            .collect(),
+        None => Vec::new(),
+    };
+    let mut store = lock();
+    store.dir = Some(dir.to_path_buf());
+    for version in loaded {
+        if !store
+            .versions
+            .iter()
+            .any(|v| v.document == version.document && v.version == version.version)
+        {
+            store.versions.push(version);
+        }
+    }
+    store.save();
+    Ok(())
+}
+
+/// Hash `path` and file it as a version, unless its content is unchanged.
+pub fn record(path: &Path) -> Result<Recorded> {
+    let path = key(path);
+    let (sha256, size) = digest(&path)?;
+    let mut store = lock();
+    let (change, previous) = store.compare(&path, &sha256);
+    if let (Change::Unchanged, Some(current)) = (change, &previous) {
+        return Ok(Recorded {
+            change,
+            version: current.clone(),
+            previous: None,
+        });
+    }
+    let (document, number) = match &previous {
+        Some(previous) => (previous.document, previous.version + 1),
+        None => {
+            let last = store.versions.iter().map(|v| v.document).max();
+            (last.map_or(1, |last| last + 1), 1)
+        }
+    };
+    let version = Version {
+        document,
+        version: number,
+        path,
+        sha256,
+        size,
+        ingested: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs()),
+    };
+    store.versions.push(version.clone());
+    store.save();
+    Ok(Recorded {
+        change,
+        version,
+        previous,
+    })
+}
+
+/// How `path` compares with the registry, and the registered version it
+/// would follow, without recording it.
+pub fn check(path: &Path) -> Result<(Change, Option<Version>)> {
+    let path = key(path);
+    let (sha256, _) = digest(&path)?;
+    Ok(lock().compare(&path, &sha256))
+}
+
+/// Every version of the document now at `path`, oldest first. Empty if
+/// none is registered there.
+pub fn history(path: &Path) -> Vec<Version> {
+    lock().history(&key(path))
+}
+
+/// The latest version of every registered document.
+pub fn documents() -> Vec<Version> {
+    lock().latest().into_iter().cloned().collect()
+}
+
+/// Drop the document at `path` and all its versions. Returns false if
+/// none is registered there.
+pub fn forget(path: &Path) -> bool {
+    let mut store = lock();
+    let Some(document) = store.history(&key(path)).first().map(|v| v.document) else {
+        return false;
+    };
+    store.versions.retain(|v| v.document != document);
+    store.save();
+    true
+}
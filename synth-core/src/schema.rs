@@ -0,0 +1,139 @@
+//! Validation against a practical subset of JSON Schema.
+//!
+//! Supported keywords: `type` (string or array of names), `enum`, `const`,
+//! `properties`, `required`, `additionalProperties` (boolean or schema),
+//! `items`, `minItems`, `maxItems`, `minLength`, `maxLength`, `minimum`,
+//! `maximum`, `anyOf`. Unknown keywords are ignored.
+
+use crate::json::Value;
+
+/// Violations of `schema` by `value`, each prefixed with a JSON pointer to
+/// the offending location (`/` for the root). Empty when valid.
+pub fn validate(value: &Value, schema: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    check(value, schema, "", &mut errors);
+    errors
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.fract() == 0.0 => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn matches_type(value: &Value, name: &str) -> bool {
+    let actual = type_name(value);
+    actual == name || (name == "number" && actual == "integer")
+}
+
+fn check(value: &Value, schema: &Value, path: &str, errors: &mut Vec<String>) {
+    let at = if path.is_empty() { "/" } else { path };
+    let mut fail = |msg: String| errors.push(format!("{at}: {msg}"));
+    let number = |key| schema.get(key).and_then(Value::as_f64);
+
+    match schema.get("type") {
+        Some(Value::String(name)) if !matches_type(value, name) => {
+            fail(format!("expected {name}, got {}", type_name(value)));
+            return;
+        }
+        Some(Value::Array(names)) => {
+            let names: Vec<&str> = names.iter().filter_map(Value::as_str).collect();
+            if !names.iter().any(|n| matches_type(value, n)) {
+                fail(format!(
+                    "expected one of {}, got {}",
+                    names.join("/"),
+                    type_name(value)
+                ));
+                return;
+            }
+        }
+        _ => {}
+    }
+    if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+        if !options.contains(value) {
+            let list: Vec<String> = options.iter().map(Value::to_string).collect();
+            fail(format!("must be one of {}", list.join(", ")));
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if expected != value {
+            fail(format!("must be {expected}"));
+        }
+    }
+    if let Some(options) = schema.get("anyOf").and_then(Value::as_array) {
+        if !options
+            .iter()
+            .any(|option| validate(value, option).is_empty())
+        {
+            fail("matches none of the anyOf schemas".into());
+        }
+    }
+
+    match value {
+        Value::String(s) => {
+            let len = s.chars().count() as f64;
+            if let Some(min) = number("minLength").filter(|&min| len < min) {
+                fail(format!("shorter than {min} chars"));
+            }
+            if let Some(max) = number("maxLength").filter(|&max| len > max) {
+                fail(format!("longer than {max} chars"));
+            }
+        }
+        Value::Number(n) => {
+            if let Some(min) = number("minimum").filter(|min| n < min) {
+                fail(format!("less than {min}"));
+            }
+            if let Some(max) = number("maximum").filter(|max| n > max) {
+                fail(format!("greater than {max}"));
+            }
+        }
+        Value::Array(items) => {
+            let len = items.len() as f64;
+            if let Some(min) = number("minItems").filter(|&min| len < min) {
+                fail(format!("fewer than {min} items"));
+            }
+            if let Some(max) = number("maxItems").filter(|&max| len > max) {
+                fail(format!("more than {max} items"));
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    check(item, item_schema, &format!("{path}/{i}"), errors);
+                }
+            }
+        }
+        Value::Object(pairs) => {
+            for name in schema
+                .get("required")
+                .and_then(Value::as_array)
+                .unwrap_or(&[])
+                .iter()
+                .filter_map(Value::as_str)
+            {
+                if value.get(name).is_none() {
+                    fail(format!("missing required property \"{name}\""));
+                }
+            }
+            let properties = schema.get("properties");
+            for (key, item) in pairs {
+                let child = format!("{path}/{}", key.replace('~', "~0").replace('/', "~1"));
+                match properties.and_then(|p| p.get(key)) {
+                    Some(property) => check(item, property, &child, errors),
+                    None => match schema.get("additionalProperties") {
+                        Some(Value::Bool(false)) => {
+                            errors.push(format!("{child}: unexpected property"))
+                        }
+                        Some(extra @ Value::Object(_)) => check(item, extra, &child, errors),
+                        _ => {}
+                    },
+                }
+            }
+        }
+        Value::Null | Value::Bool(_) => {}
+    }
+}
@@ -0,0 +1,185 @@
+//! Sentence and paragraph boundaries.
+//!
+//! Ranges are in characters and trimmed of surrounding whitespace, so the
+//! text between two segments is always whitespace. JSON output gives each
+//! offset in UTF-16 code units as well, for hosts whose strings index
+//! that way (`NSString`, JavaScript); see [`utf16_offsets`].
+
+use std::ops::Range;
+
+use crate::json::Value;
+
+/// Abbreviations whose trailing period does not end a sentence.
+const ABBREVIATIONS: &[&str] = &[
+    "al", "approx", "cf", "co", "corp", "dept", "dr", "e.g", "est", "etc", "fig", "i.e", "inc",
+    "jr", "ltd", "mr", "mrs", "ms", "no", "prof", "sr", "st", "vs",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Sentence,
+    Paragraph,
+}
+
+impl Mode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "sentence" => Some(Mode::Sentence),
+            "paragraph" => Some(Mode::Paragraph),
+            _ => None,
+        }
+    }
+}
+
+/// Segment ranges of `text` in the given mode.
+pub fn segment(text: &str, mode: Mode) -> Vec<Range<usize>> {
+    match mode {
+        Mode::Sentence => sentences(text),
+        Mode::Paragraph => paragraphs(text),
+    }
+}
+
+/// The UTF-16 offset of every character offset of `text`, from 0 to its
+/// length inclusive: characters outside the Basic Multilingual Plane,
+/// such as most emoji, take two code units.
+pub fn utf16_offsets(text: &str) -> Vec<usize> {
+    let mut offsets = Vec::with_capacity(text.len() + 1);
+    let mut offset = 0;
+    offsets.push(0);
+    for c in text.chars() {
+        offset += c.len_utf16();
+        offsets.push(offset);
+    }
+    offsets
+}
+
+/// `[{"start": 0, "end": 12, "start_utf16": 0, "end_utf16": 12, "text":
+/// "..."}]` for each range.
+pub fn segments_to_json(text: &str, ranges: &[Range<usize>]) -> Value {
+    let chars: Vec<char> = text.chars().collect();
+    let utf16 = utf16_offsets(text);
+    Value::Array(
+        ranges
+            .iter()
+            .map(|r| {
+                Value::object([
+                    ("start", Value::from(r.start)),
+                    ("end", Value::from(r.end)),
+                    ("start_utf16", Value::from(utf16[r.start])),
+                    ("end_utf16", Value::from(utf16[r.end])),
+                    (
+                        "text",
+                        Value::from(chars[r.clone()].iter().collect::<String>()),
+                    ),
+                ])
+            })
+            .collect(),
+    )
+}
+
+fn is_terminator(c: char) -> bool {
+    matches!(
+        c,
+        '.' | '!' | '?' | '…' | '‽' | '。' | '！' | '？' | '｡' | '।' | '؟'
+    )
+}
+
+/// Terminators that end a sentence without following whitespace.
+fn is_fullwidth_terminator(c: char) -> bool {
+    matches!(c, '。' | '！' | '？' | '｡')
+}
+
+fn is_closing(c: char) -> bool {
+    matches!(
+        c,
+        '"' | '\'' | ')' | ']' | '}' | '»' | '’' | '”' | '」' | '』' | '）'
+    )
+}
+
+/// Sentence ranges of `text`. A sentence ends at a terminator plus any
+/// closing quotes or brackets, when followed by whitespace (or nothing),
+/// except after a known abbreviation or single-letter initial, or when the
+/// next word starts in lowercase. Blank lines always end a sentence.
+pub fn sentences(text: &str) -> Vec<Range<usize>> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut ends = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\n' && blank_line_follows(&chars, i) {
+            ends.push(i);
+        } else if is_terminator(c) {
+            let mut end = i + 1;
+            while end < chars.len() && (is_terminator(chars[end]) || is_closing(chars[end])) {
+                end += 1;
+            }
+            let spaced = end == chars.len() || chars[end].is_whitespace();
+            if (spaced || is_fullwidth_terminator(c)) && !continues(&chars, i, end) {
+                ends.push(end);
+            }
+            i = end;
+            continue;
+        }
+        i += 1;
+    }
+    ends.push(chars.len());
+    trimmed_ranges(&chars, ends)
+}
+
+/// Paragraph ranges of `text`: blocks separated by one or more blank lines.
+pub fn paragraphs(text: &str) -> Vec<Range<usize>> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut ends: Vec<usize> = (0..chars.len())
+        .filter(|&i| chars[i] == '\n' && blank_line_follows(&chars, i))
+        .collect();
+    ends.push(chars.len());
+    trimmed_ranges(&chars, ends)
+}
+
+/// Whether the line starting after the newline at `i` is blank.
+fn blank_line_follows(chars: &[char], i: usize) -> bool {
+    chars[i + 1..]
+        .iter()
+        .take_while(|c| **c != '\n')
+        .all(|c| c.is_whitespace())
+        && chars[i + 1..].contains(&'\n')
+}
+
+/// Whether the period at `dot` is part of the sentence rather than its end.
+fn continues(chars: &[char], dot: usize, end: usize) -> bool {
+    let next = chars[end..].iter().find(|c| !c.is_whitespace());
+    if next.is_some_and(|c| c.is_lowercase()) && chars[dot] == '.' {
+        return true;
+    }
+    if chars[dot] != '.' || end != dot + 1 {
+        return false;
+    }
+    let start = chars[..dot]
+        .iter()
+        .rposition(|c| c.is_whitespace() || *c == '(')
+        .map_or(0, |p| p + 1);
+    let word: String = chars[start..dot].iter().collect::<String>().to_lowercase();
+    let mut letters = word.chars();
+    let initial = matches!((letters.next(), letters.next()), (Some(c), None) if c.is_alphabetic());
+    initial || ABBREVIATIONS.contains(&word.as_str())
+}
+
+fn trimmed_ranges(chars: &[char], ends: Vec<usize>) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    for end in ends {
+        let mut s = start;
+        let mut e = end;
+        while s < e && chars[s].is_whitespace() {
+            s += 1;
+        }
+        while e > s && chars[e - 1].is_whitespace() {
+            e -= 1;
+        }
+        if s < e {
+            ranges.push(s..e);
+        }
+        start = end;
+    }
+    ranges
+}
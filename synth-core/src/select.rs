@@ -0,0 +1,133 @@
+//! Selecting part of a document before it crosses the FFI boundary.
+
+use crate::error::{Error, Result};
+use crate::json::Value;
+use crate::outline::{flat_headings, Heading};
+
+/// Which part of a document to keep.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Selection {
+    /// A heading's section, including its subsections.
+    Section(String),
+    /// From the start of one heading up to (not including) another, or to
+    /// the end of the document.
+    Headings { from: String, to: Option<String> },
+    /// A 1-based, inclusive line range.
+    Lines(usize, usize),
+    /// A 1-based, inclusive page range of a paged format such as PDF,
+    /// whose pages are separated by form feeds.
+    Pages { first: usize, last: usize },
+}
+
+impl Selection {
+    /// Read `{"section": "Installation"}`, `{"from_heading": "A",
+    /// "to_heading": "B"}`, `{"lines": [10, 40]}` or `{"pages": [3, 5]}`
+    /// (`{"pages": 3}` for one page).
+    pub fn from_json(value: &Value) -> Result<Self> {
+        let text = |key| value.get(key).and_then(Value::as_str).map(String::from);
+        if let Some(section) = text("section") {
+            return Ok(Selection::Section(section));
+        }
+        if let Some(from) = text("from_heading") {
+            return Ok(Selection::Headings {
+                from,
+                to: text("to_heading"),
+            });
+        }
+        if let Some(lines) = value.get("lines").and_then(Value::as_array) {
+            let bound = |i: usize| lines.get(i).and_then(Value::as_f64).map(|n| n as usize);
+            let first = bound(0).ok_or_else(|| Error::Parse("\"lines\" needs a start".into()))?;
+            return Ok(Selection::Lines(
+                first.max(1),
+                bound(1).unwrap_or(usize::MAX),
+            ));
+        }
+        if let Some(pages) = value.get("pages") {
+            let page = |v: Option<&Value>| {
+                v.and_then(Value::as_f64)
+                    .filter(|n| n.fract() == 0.0 && *n >= 1.0)
+                    .map(|n| n as usize)
+                    .ok_or_else(|| Error::InvalidArgument("pages are counted from 1".into()))
+            };
+            let (first, last) = match pages.as_array() {
+                Some(range) => {
+                    let first = page(range.first())?;
+                    match range.get(1) {
+                        Some(last) => (first, page(Some(last))?),
+                        None => (first, first),
+                    }
+                }
+                None => {
+                    let only = page(Some(pages))?;
+                    (only, only)
+                }
+            };
+            if first > last {
+                return Err(Error::InvalidArgument(format!(
+                    "page range {first}-{last} ends before it starts"
+                )));
+            }
+            return Ok(Selection::Pages { first, last });
+        }
+        Err(Error::Parse("no selection given".into()))
+    }
+}
+
+/// The selected part of `text`, or a parse error naming the heading that
+/// was not found.
+pub fn select(text: &str, selection: &Selection) -> Result<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let slice = |start: usize, end: usize| chars[start..end].iter().collect::<String>();
+    match selection {
+        Selection::Section(name) => {
+            let headings = flat_headings(text);
+            let index = find(&headings, name, 0)?;
+            let heading = &headings[index];
+            let end = headings[index + 1..]
+                .iter()
+                .find(|h| h.level <= heading.level)
+                .map_or(chars.len(), |h| h.offset);
+            Ok(slice(heading.offset, end))
+        }
+        Selection::Headings { from, to } => {
+            let headings = flat_headings(text);
+            let start = find(&headings, from, 0)?;
+            let end = match to {
+                Some(to) => headings[find(&headings, to, start + 1)?].offset,
+                None => chars.len(),
+            };
+            Ok(slice(headings[start].offset, end))
+        }
+        Selection::Lines(first, last) => Ok(text
+            .split_inclusive('\n')
+            .skip(first - 1)
+            .take(last.saturating_sub(first - 1))
+            .collect()),
+        Selection::Pages { first, last } => {
+            let pages: Vec<&str> = text.split('\u{c}').collect();
+            if *first > pages.len() {
+                return Err(Error::InvalidArgument(format!(
+                    "page {first} is past the last page, {}",
+                    pages.len()
+                )));
+            }
+            Ok(pages[first - 1..(*last).min(pages.len())].join("\u{c}"))
+        }
+    }
+}
+
+/// First heading at or after `from` matching `name`: the same text ignoring
+/// case, or a numbering prefix such as "7" for "7. Termination".
+fn find(headings: &[Heading], name: &str, from: usize) -> Result<usize> {
+    let name = name.trim().to_lowercase();
+    let numbered = |text: &str| {
+        text.strip_prefix(&name)
+            .is_some_and(|rest| rest.starts_with([' ', '.', ')', ':']))
+    };
+    (from..headings.len())
+        .find(|&i| {
+            let text = headings[i].text.to_lowercase();
+            text == name || numbered(&text)
+        })
+        .ok_or_else(|| Error::Parse(format!("no heading '{name}'")))
+}
@@ -0,0 +1,176 @@
+//! Randomized invariant checks that ship in release builds, so a host can
+//! confirm the text primitives behave on the platform it runs on.
+//!
+//! Inputs are built from fragments chosen to hit the edge cases: escape
+//! sequences cut short, multi-byte characters, and runs of line breaks
+//! and sentence ends where the chunker looks for break points.
+
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::chat::strip_ansi;
+use crate::chunk::chunk_text;
+use crate::error::panic_message;
+use crate::json::Value;
+
+const FRAGMENTS: &[&str] = &[
+    "a",
+    "word",
+    "Z",
+    "7",
+    " ",
+    "  ",
+    "\t",
+    "\n",
+    "\n\n",
+    "\r\n",
+    ". ",
+    "? ",
+    "!",
+    "Dr. ",
+    "\x1b",
+    "\x1b[",
+    "\x1b[0m",
+    "\x1b[1;31m",
+    "\x1b[38;5;208m",
+    "\x1b]0;title\x07",
+    "[",
+    "é",
+    "日本語",
+    "🙂",
+    "\u{200b}",
+    "\u{301}",
+];
+
+/// xorshift64*: small, fast and reproducible from its seed.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed.max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// A value in `0..n`.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n.max(1) as u64) as usize
+    }
+
+    /// Up to `max_fragments` fragments of edge-case text.
+    pub fn text(&mut self, max_fragments: usize) -> String {
+        (0..self.below(max_fragments + 1))
+            .map(|_| FRAGMENTS[self.below(FRAGMENTS.len())])
+            .collect()
+    }
+}
+
+/// An invariant that did not hold, with the input that broke it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Failure {
+    pub property: &'static str,
+    pub input: String,
+    pub message: String,
+}
+
+fn check_strip_ansi(input: &str) -> Result<(), String> {
+    let output = strip_ansi(input);
+    if output.contains('\x1b') {
+        return Err(format!("output still contains ESC: {output:?}"));
+    }
+    if output.chars().count() > input.chars().count() {
+        return Err("output is longer than the input".into());
+    }
+    Ok(())
+}
+
+fn check_chunk_text(input: &str, max_chars: usize) -> Result<(), String> {
+    let chunks = chunk_text(input, max_chars);
+    let chars: Vec<char> = input.chars().collect();
+    let mut position = 0;
+    for chunk in &chunks {
+        if chunk.start != position {
+            return Err(format!("chunk starts at {} after {position}", chunk.start));
+        }
+        if chunk.end <= chunk.start || chunk.end - chunk.start > max_chars.max(1) {
+            return Err(format!(
+                "chunk {}..{} breaks the size limit",
+                chunk.start, chunk.end
+            ));
+        }
+        let slice: String = chars
+            .get(chunk.start..chunk.end)
+            .ok_or_else(|| format!("chunk {}..{} is out of range", chunk.start, chunk.end))?
+            .iter()
+            .collect();
+        if slice != chunk.text {
+            return Err(format!(
+                "chunk {}..{} text differs from its offsets",
+                chunk.start, chunk.end
+            ));
+        }
+        position = chunk.end;
+    }
+    if position != chars.len() {
+        return Err(format!("chunks end at {position} of {}", chars.len()));
+    }
+    Ok(())
+}
+
+/// Run `check`, reporting a panic as a failure too.
+fn holds(check: impl FnOnce() -> Result<(), String>) -> Result<(), String> {
+    panic::catch_unwind(AssertUnwindSafe(check))
+        .unwrap_or_else(|payload| Err(format!("panicked: {}", panic_message(payload.as_ref()))))
+}
+
+/// Check every invariant against `cases` random inputs from `seed`.
+pub fn run(cases: usize, seed: u64) -> Vec<Failure> {
+    let mut rng = Rng::new(seed);
+    let mut failures = Vec::new();
+    for _ in 0..cases {
+        let input = rng.text(64);
+        if let Err(message) = holds(|| check_strip_ansi(&input)) {
+            failures.push(Failure {
+                property: "strip_ansi",
+                input: input.clone(),
+                message,
+            });
+        }
+        let max_chars = 1 + rng.below(80);
+        if let Err(message) = holds(|| check_chunk_text(&input, max_chars)) {
+            failures.push(Failure {
+                property: "chunk_text",
+                input,
+                message: format!("max_chars {max_chars}: {message}"),
+            });
+        }
+    }
+    failures
+}
+
+pub fn report_json(cases: usize, seed: u64, failures: &[Failure]) -> Value {
+    Value::object([
+        ("ok", Value::from(failures.is_empty())),
+        ("cases", Value::from(cases as f64)),
+        ("seed", Value::from(seed.to_string())),
+        (
+            "failures",
+            Value::Array(
+                failures
+                    .iter()
+                    .map(|f| {
+                        Value::object([
+                            ("property", Value::from(f.property)),
+                            ("input", Value::from(f.input.as_str())),
+                            ("message", Value::from(f.message.as_str())),
+                        ])
+                    })
+                    .collect(),
+            ),
+        ),
+    ])
+}
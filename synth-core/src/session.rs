@@ -0,0 +1,324 @@
+//! Multi-turn chat sessions, held in memory and addressed by handle.
+//!
+//! Backends take a single prompt, so each call sends the transcript so far
+//! (dropping the oldest turns once it grows too long).
+
+use std::collections::BTreeMap;
+use std::sync::{Mutex, MutexGuard};
+
+use crate::chat::{self, ChatOptions};
+use crate::error::{Error, Result};
+use crate::json::Value;
+use crate::policy;
+use crate::usage::Tags;
+
+/// Transcript characters sent with each message. Older turns beyond this
+/// are left out of the prompt but kept in the history.
+const HISTORY_BUDGET: usize = 48_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    User,
+    Assistant,
+}
+
+impl Role {
+    pub fn name(self) -> &'static str {
+        match self {
+            Role::User => "user",
+            Role::Assistant => "assistant",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        [Role::User, Role::Assistant]
+            .into_iter()
+            .find(|r| r.name() == name)
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Role::User => "User",
+            Role::Assistant => "Assistant",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Turn {
+    pub role: Role,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Session {
+    pub options: ChatOptions,
+    pub turns: Vec<Turn>,
+    /// Estimated prompt and reply tokens spent so far, counted against the
+    /// policy's session limit.
+    pub tokens_used: u64,
+}
+
+impl Session {
+    pub fn new(options: ChatOptions) -> Self {
+        Session {
+            options,
+            turns: Vec::new(),
+            tokens_used: 0,
+        }
+    }
+
+    /// The prompt asking for the next assistant turn after `turns`.
+    fn prompt(turns: &[Turn]) -> String {
+        let mut kept = Vec::new();
+        let mut used = 0;
+        for turn in turns.iter().rev() {
+            let size = turn.text.chars().count();
+            if used + size > HISTORY_BUDGET && !kept.is_empty() {
+                break;
+            }
+            used += size;
+            kept.push(turn);
+        }
+        let mut prompt = String::from(
+            "The following is a conversation between a user and an assistant. \
+             Reply as the assistant to the user's last message, without a \
+             speaker label.\n",
+        );
+        if kept.len() < turns.len() {
+            prompt.push_str("[earlier messages omitted]\n");
+        }
+        for turn in kept.iter().rev() {
+            prompt.push_str(&format!("\n{}: {}\n", turn.role.label(), turn.text));
+        }
+        prompt.push_str("\nAssistant:");
+        prompt
+    }
+
+    /// Send `prompt` through `chat` if the session's token allowance covers
+    /// it, and count what the exchange spent.
+    fn spend(
+        &mut self,
+        prompt: &str,
+        chat: &mut impl FnMut(&str) -> Result<String>,
+    ) -> Result<String> {
+        let cost = policy::estimate_tokens(prompt);
+        policy::check_session_tokens(self.tokens_used, cost)?;
+        let reply = chat(prompt)?;
+        self.tokens_used += cost + policy::estimate_tokens(&reply);
+        Ok(reply)
+    }
+
+    /// Add a user message and the reply from `chat`. On error the session
+    /// is left unchanged.
+    pub fn send(
+        &mut self,
+        message: &str,
+        mut chat: impl FnMut(&str) -> Result<String>,
+    ) -> Result<String> {
+        self.turns.push(Turn {
+            role: Role::User,
+            text: message.to_string(),
+        });
+        match self.spend(&Session::prompt(&self.turns), &mut chat) {
+            Ok(reply) => {
+                let reply = reply.trim().to_string();
+                self.turns.push(Turn {
+                    role: Role::Assistant,
+                    text: reply.clone(),
+                });
+                Ok(reply)
+            }
+            Err(e) => {
+                self.turns.pop();
+                Err(e)
+            }
+        }
+    }
+
+    /// Replace the last assistant reply with a fresh one. On error the old
+    /// reply is kept.
+    pub fn regenerate(&mut self, mut chat: impl FnMut(&str) -> Result<String>) -> Result<String> {
+        match self.turns.last() {
+            Some(turn) if turn.role == Role::Assistant => {}
+            _ => return Err(Error::Parse("no reply to regenerate".into())),
+        }
+        let prompt = Session::prompt(&self.turns[..self.turns.len() - 1]);
+        let reply = self.spend(&prompt, &mut chat)?.trim().to_string();
+        if let Some(last) = self.turns.last_mut() {
+            last.text = reply.clone();
+        }
+        Ok(reply)
+    }
+
+    /// A new session holding the first `turns` turns of this one, e.g. to
+    /// resend an edited message from there. It inherits the tokens spent,
+    /// so branching does not reset the policy's limit.
+    pub fn branch(&self, turns: usize) -> Option<Session> {
+        (turns <= self.turns.len()).then(|| Session {
+            options: self.options.clone(),
+            turns: self.turns[..turns].to_vec(),
+            tokens_used: self.tokens_used,
+        })
+    }
+
+    /// The turns as `[{"role": "user" | "assistant", "text": "..."}]`.
+    pub fn to_json(&self) -> Value {
+        Value::Array(
+            self.turns
+                .iter()
+                .map(|turn| {
+                    Value::object([
+                        ("role", Value::from(turn.role.name())),
+                        ("text", Value::from(turn.text.as_str())),
+                    ])
+                })
+                .collect(),
+        )
+    }
+
+    /// The whole session, options included, for saving to disk.
+    pub fn to_saved_json(&self) -> Value {
+        Value::object([
+            ("options", self.options.to_json()),
+            ("turns", self.to_json()),
+            ("tokens_used", Value::from(self.tokens_used as f64)),
+        ])
+    }
+
+    /// Read what [`Session::to_saved_json`] wrote.
+    pub fn from_saved_json(value: &Value) -> Result<Session> {
+        let turns = value
+            .get("turns")
+            .and_then(Value::as_array)
+            .ok_or_else(|| Error::Parse("saved session has no turns".into()))?
+            .iter()
+            .map(|turn| {
+                Some(Turn {
+                    role: Role::from_name(turn.get("role")?.as_str()?)?,
+                    text: turn.get("text")?.as_str()?.to_string(),
+                })
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| Error::Parse("saved session has an invalid turn".into()))?;
+        Ok(Session {
+            options: ChatOptions::from_json(value.get("options").unwrap_or(&Value::Null)),
+            turns,
+            tokens_used: value
+                .get("tokens_used")
+                .and_then(Value::as_f64)
+                .unwrap_or(0.0) as u64,
+        })
+    }
+}
+
+#[derive(Default)]
+struct Registry {
+    next: u64,
+    sessions: BTreeMap<u64, Session>,
+}
+
+fn lock() -> MutexGuard<'static, Registry> {
+    static REGISTRY: Mutex<Registry> = Mutex::new(Registry {
+        next: 0,
+        sessions: BTreeMap::new(),
+    });
+    REGISTRY.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn insert(session: Session) -> u64 {
+    let mut registry = lock();
+    registry.next += 1;
+    let handle = registry.next;
+    registry.sessions.insert(handle, session);
+    handle
+}
+
+/// Open a session and return its handle (never 0).
+pub fn create(options: ChatOptions) -> u64 {
+    insert(Session::new(options))
+}
+
+/// Open a handle on an existing session, e.g. one loaded from disk.
+pub fn open(session: Session) -> u64 {
+    insert(session)
+}
+
+/// A copy of the session behind `handle`.
+pub fn get(handle: u64) -> Option<Session> {
+    lock().sessions.get(&handle).cloned()
+}
+
+fn unknown(handle: u64) -> Error {
+    Error::Parse(format!("unknown session {handle}"))
+}
+
+/// Run `f` on a copy of the session without holding the registry lock
+/// across the backend call, then store the result. Calls on one session
+/// should not overlap; the last to finish wins.
+fn update(
+    handle: u64,
+    f: impl FnOnce(&mut Session, &ChatOptions) -> Result<String>,
+) -> Result<String> {
+    let mut session = lock()
+        .sessions
+        .get(&handle)
+        .cloned()
+        .ok_or_else(|| unknown(handle))?;
+    let options = session.options.clone();
+    let reply = f(&mut session, &options)?;
+    if let Some(stored) = lock().sessions.get_mut(&handle) {
+        *stored = session;
+    }
+    Ok(reply)
+}
+
+/// Send a user message through the session's backend and return the reply.
+pub fn send(handle: u64, message: &str) -> Result<String> {
+    update(handle, |session, options| {
+        session.send(message, |prompt| chat::chat(prompt, options))
+    })
+}
+
+/// Ask the backend again for the last reply ("try again").
+pub fn regenerate(handle: u64) -> Result<String> {
+    update(handle, |session, options| {
+        session.regenerate(|prompt| chat::chat(prompt, options))
+    })
+}
+
+/// Open a new session from the first `turns` turns of `handle`.
+pub fn branch(handle: u64, turns: usize) -> Result<u64> {
+    let session = lock()
+        .sessions
+        .get(&handle)
+        .ok_or_else(|| unknown(handle))?
+        .branch(turns)
+        .ok_or_else(|| Error::Parse(format!("session {handle} has fewer than {turns} turns")))?;
+    Ok(insert(session))
+}
+
+/// The open sessions whose options carry the tags `filter` sets, as
+/// `[{"handle": 1, "tenant_id": "...", "user_id": "...", "turns": 4}]`.
+pub fn list(filter: &Tags) -> Value {
+    let sessions = lock()
+        .sessions
+        .iter()
+        .filter(|(_, session)| filter.matches(&session.options.tags()))
+        .map(|(handle, session)| {
+            let mut pairs = vec![("handle", Value::from(*handle as f64))];
+            pairs.extend(session.options.tags().pairs());
+            pairs.push(("turns", Value::from(session.turns.len())));
+            Value::object(pairs)
+        })
+        .collect();
+    Value::Array(sessions)
+}
+
+pub fn history(handle: u64) -> Option<Value> {
+    lock().sessions.get(&handle).map(Session::to_json)
+}
+
+pub fn close(handle: u64) -> bool {
+    lock().sessions.remove(&handle).is_some()
+}
@@ -0,0 +1,253 @@
+//! Orderly teardown for a host app that is quitting, so no `kiro-cli` or
+//! `curl` process outlives it.
+//!
+//! [`shutdown`] cancels the queued and running jobs, kills every child
+//! process the library started, and waits, up to a deadline, for its
+//! background threads (the job worker, prefetches, webhook deliveries and
+//! async tasks) to finish. It then drops the in-memory caches. Jobs,
+//! memories, sessions, the registry, the reply cache and the audit log
+//! are written through as they change, so nothing else is left to flush.
+//!
+//! While a shutdown runs, starting a child process fails and webhook
+//! deliveries are not retried. The library can be used again afterwards.
+//!
+//! Work run [`in_task`] has its child processes attributed to the task,
+//! so a cancelled job's processes can be killed on their own with
+//! [`kill_task`].
+
+use std::cell::Cell;
+use std::io;
+use std::process::{self, ChildStderr, ChildStdin, ChildStdout, Command, ExitStatus};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::cache;
+use crate::error::{Error, Result};
+use crate::jobs;
+use crate::json::Value;
+use crate::prefetch;
+
+static STOPPING: AtomicBool = AtomicBool::new(false);
+static NEXT_CHILD: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    static TASK: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+struct Child {
+    id: u64,
+    task: Option<u64>,
+    child: Arc<Mutex<process::Child>>,
+}
+
+#[derive(Default)]
+struct Children {
+    running: Vec<Child>,
+    /// Tasks cancelled while they run; they may start no more children.
+    cancelled: Vec<u64>,
+}
+
+fn children() -> MutexGuard<'static, Children> {
+    static CHILDREN: Mutex<Children> = Mutex::new(Children {
+        running: Vec::new(),
+        cancelled: Vec::new(),
+    });
+    CHILDREN.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn kill(child: &Mutex<process::Child>) {
+    let mut child = child.lock().unwrap_or_else(|e| e.into_inner());
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+fn threads() -> MutexGuard<'static, Vec<JoinHandle<()>>> {
+    static THREADS: Mutex<Vec<JoinHandle<()>>> = Mutex::new(Vec::new());
+    THREADS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Whether a shutdown is under way.
+pub fn stopping() -> bool {
+    STOPPING.load(Ordering::SeqCst)
+}
+
+/// Run `work` on this thread as `task`.
+pub(crate) fn in_task<T>(task: u64, work: impl FnOnce() -> T) -> T {
+    let outer = TASK.with(|current| current.replace(Some(task)));
+    // Reset even if `work` panics, since the caller may catch it.
+    struct Reset(Option<u64>, u64);
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            TASK.with(|current| current.set(self.0));
+            children().cancelled.retain(|task| *task != self.1);
+        }
+    }
+    let _reset = Reset(outer, task);
+    work()
+}
+
+/// Whether the task this thread runs has been cancelled.
+pub(crate) fn cancelled() -> bool {
+    TASK.with(Cell::get)
+        .is_some_and(|task| children().cancelled.contains(&task))
+}
+
+/// Cancel a task running [`in_task`]: kill its child processes and refuse
+/// it new ones. Returns how many were killed.
+pub(crate) fn kill_task(task: u64) -> usize {
+    let mut children = children();
+    children.cancelled.push(task);
+    let (killed, kept): (Vec<_>, Vec<_>) = children
+        .running
+        .drain(..)
+        .partition(|child| child.task == Some(task));
+    children.running = kept;
+    drop(children);
+    for child in &killed {
+        kill(&child.child);
+    }
+    killed.len()
+}
+
+/// A child process that [`shutdown`] can kill.
+pub(crate) struct Process {
+    id: u64,
+    child: Arc<Mutex<process::Child>>,
+}
+
+impl Process {
+    pub(crate) fn spawn(command: &mut Command) -> Result<Process> {
+        let task = TASK.with(Cell::get);
+        let mut children = children();
+        if stopping() {
+            return Err(Error::Backend("shutting down".into()));
+        }
+        if task.is_some_and(|task| children.cancelled.contains(&task)) {
+            return Err(Error::Backend("cancelled".into()));
+        }
+        let child = Arc::new(Mutex::new(command.spawn()?));
+        let id = NEXT_CHILD.fetch_add(1, Ordering::Relaxed);
+        children.running.push(Child {
+            id,
+            task,
+            child: Arc::clone(&child),
+        });
+        Ok(Process { id, child })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, process::Child> {
+        self.child.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub(crate) fn take_stdin(&self) -> Option<ChildStdin> {
+        self.lock().stdin.take()
+    }
+
+    pub(crate) fn take_stdout(&self) -> Option<ChildStdout> {
+        self.lock().stdout.take()
+    }
+
+    pub(crate) fn take_stderr(&self) -> Option<ChildStderr> {
+        self.lock().stderr.take()
+    }
+
+    pub(crate) fn try_wait(&self) -> io::Result<Option<ExitStatus>> {
+        self.lock().try_wait()
+    }
+
+    /// Wait for the process to exit. Polls, so the process stays
+    /// killable while it is waited on.
+    pub(crate) fn wait(&self) -> io::Result<ExitStatus> {
+        loop {
+            if let Some(status) = self.try_wait()? {
+                return Ok(status);
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    pub(crate) fn kill(&self) {
+        kill(&self.child);
+    }
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        children().running.retain(|child| child.id != self.id);
+    }
+}
+
+/// Run `work` on a thread that [`shutdown`] waits for.
+pub(crate) fn spawn_thread(work: impl FnOnce() + Send + 'static) {
+    let handle = thread::spawn(work);
+    let mut threads = threads();
+    threads.retain(|thread| !thread.is_finished());
+    threads.push(handle);
+}
+
+/// What [`shutdown`] did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Report {
+    pub jobs_cancelled: usize,
+    pub processes_killed: usize,
+    pub threads_joined: usize,
+    /// Threads still running at the deadline, left detached.
+    pub threads_left: usize,
+}
+
+impl Report {
+    /// Whether everything stopped before the deadline.
+    pub fn clean(&self) -> bool {
+        self.threads_left == 0
+    }
+
+    pub fn to_json(&self) -> Value {
+        Value::object([
+            ("jobs_cancelled", Value::from(self.jobs_cancelled)),
+            ("processes_killed", Value::from(self.processes_killed)),
+            ("threads_joined", Value::from(self.threads_joined)),
+            ("threads_left", Value::from(self.threads_left)),
+        ])
+    }
+}
+
+/// Kill the child processes still registered. Returns how many.
+fn kill_children() -> usize {
+    let running: Vec<Child> = children().running.drain(..).collect();
+    for child in &running {
+        kill(&child.child);
+    }
+    running.len()
+}
+
+/// Stop all background work, waiting up to `timeout` for threads.
+pub fn shutdown(timeout: Duration) -> Report {
+    let deadline = Instant::now() + timeout;
+    STOPPING.store(true, Ordering::SeqCst);
+    let mut report = Report {
+        jobs_cancelled: jobs::cancel_all(),
+        processes_killed: kill_children(),
+        ..Report::default()
+    };
+    let mut pending: Vec<JoinHandle<()>> = threads().drain(..).collect();
+    loop {
+        let (finished, running): (Vec<_>, Vec<_>) =
+            pending.into_iter().partition(JoinHandle::is_finished);
+        for thread in finished {
+            let _ = thread.join();
+            report.threads_joined += 1;
+        }
+        pending = running;
+        if pending.is_empty() || Instant::now() >= deadline {
+            break;
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+    report.threads_left = pending.len();
+    prefetch::clear();
+    cache::clear();
+    STOPPING.store(false, Ordering::SeqCst);
+    report
+}
@@ -0,0 +1,82 @@
+//! Local document statistics for the inspector panel.
+
+use crate::json::Value;
+use crate::segment::{paragraphs, sentences};
+use crate::words::words;
+
+/// Typical adult silent-reading speed.
+const WORDS_PER_MINUTE: f64 = 238.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stats {
+    pub characters: usize,
+    pub words: usize,
+    pub sentences: usize,
+    pub paragraphs: usize,
+    pub reading_minutes: f64,
+    pub avg_sentence_words: f64,
+    /// Flesch reading ease: roughly 0 (very hard) to 100 (very easy).
+    pub flesch_reading_ease: f64,
+}
+
+impl Stats {
+    pub fn to_json(&self) -> Value {
+        let round = |n: f64| (n * 10.0).round() / 10.0;
+        Value::object([
+            ("characters", Value::from(self.characters)),
+            ("words", Value::from(self.words)),
+            ("sentences", Value::from(self.sentences)),
+            ("paragraphs", Value::from(self.paragraphs)),
+            ("reading_minutes", Value::from(round(self.reading_minutes))),
+            (
+                "avg_sentence_words",
+                Value::from(round(self.avg_sentence_words)),
+            ),
+            (
+                "flesch_reading_ease",
+                Value::from(round(self.flesch_reading_ease)),
+            ),
+        ])
+    }
+}
+
+pub fn document_stats(text: &str) -> Stats {
+    let words = words(text);
+    let sentences = sentences(text).len();
+    let syllables: usize = words.iter().map(|w| syllables(w)).sum();
+    let per_sentence = words.len() as f64 / sentences.max(1) as f64;
+    let per_word = syllables as f64 / words.len().max(1) as f64;
+    let flesch = if words.is_empty() {
+        0.0
+    } else {
+        206.835 - 1.015 * per_sentence - 84.6 * per_word
+    };
+    Stats {
+        characters: text.chars().count(),
+        words: words.len(),
+        sentences,
+        paragraphs: paragraphs(text).len(),
+        reading_minutes: words.len() as f64 / WORDS_PER_MINUTE,
+        avg_sentence_words: if sentences == 0 { 0.0 } else { per_sentence },
+        flesch_reading_ease: flesch,
+    }
+}
+
+/// Estimate syllables from vowel groups, dropping a silent final `e`.
+fn syllables(word: &str) -> usize {
+    let vowel = |c: char| matches!(c, 'a' | 'e' | 'i' | 'o' | 'u' | 'y');
+    let chars: Vec<char> = word.chars().filter(|c| c.is_alphabetic()).collect();
+    if chars.is_empty() {
+        return 1;
+    }
+    let mut count = chars
+        .iter()
+        .enumerate()
+        .filter(|&(i, &c)| vowel(c) && (i == 0 || !vowel(chars[i - 1])))
+        .count();
+    let n = chars.len();
+    if count > 1 && chars[n - 1] == 'e' && !(n > 2 && chars[n - 2] == 'l' && !vowel(chars[n - 3])) {
+        count -= 1;
+    }
+    count.max(1)
+}
@@ -0,0 +1,612 @@
+//! Where persisted state is kept: the reply cache, jobs, memories, prompt
+//! versions and projects' manifests, text caches and saved sessions.
+//!
+//! Each record is addressed by the path of the file it would be. By
+//! default it is that file, written to a temporary file named for the
+//! process and renamed into place, so readers never see a partial write.
+//! With a database configured, records are rows of one SQLite database
+//! instead: every write is a transaction, and host processes sharing the
+//! database wait for each other's locks rather than overwrite each other's
+//! files. With the `sqlite` feature SQLite is compiled in and the database
+//! kept open; without it every call runs the `sqlite3` found on `PATH`.
+//! Records still only on disk, written before the database was configured
+//! or unpacked by an import, are read from there until they are next
+//! written.
+//!
+//! With [`crate::encryption`] configured, records are sealed before they
+//! are written either way, under their absolute path as their name.
+//! [`seal_existing`] seals the ones written before the key.
+//!
+//! The database's schema version is `PRAGMA user_version`; a database is
+//! brought up to date when it is configured, and one from a newer build
+//! is refused.
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{self, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[cfg(not(feature = "sqlite"))]
+use cli as db;
+#[cfg(feature = "sqlite")]
+use embedded as db;
+
+use crate::encryption;
+use crate::error::{Error, Result};
+use crate::json::Value;
+
+/// The schema, one step per version: the database is at version `n` once
+/// the first `n` have run. Each step must be safe to run twice, should two
+/// processes migrate at once.
+const MIGRATIONS: &[&str] = &["CREATE TABLE IF NOT EXISTS records (
+        path TEXT PRIMARY KEY,
+        parent TEXT NOT NULL,
+        data BLOB NOT NULL,
+        modified INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS records_parent ON records (parent);"];
+
+static CONFIG: Mutex<Option<Config>> = Mutex::new(None);
+static SCRATCH: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    /// The database file, created if missing.
+    pub db_path: PathBuf,
+    /// How long to wait for another process's lock.
+    pub busy_timeout: Duration,
+}
+
+impl Config {
+    pub fn new(db_path: &Path) -> Config {
+        Config {
+            db_path: db_path.to_path_buf(),
+            busy_timeout: Duration::from_secs(10),
+        }
+    }
+
+    /// Read `{"synth_db_path": "/path/synth.db", "busy_timeout_secs": 10}`;
+    /// only the path is required.
+    pub fn from_json(value: &Value) -> Result<Config> {
+        let text = |key| value.get(key).and_then(Value::as_str);
+        let path = text("synth_db_path")
+            .ok_or_else(|| Error::Parse("database config needs a synth_db_path".into()))?;
+        let mut config = Config::new(Path::new(path));
+        if let Some(secs) = value.get("busy_timeout_secs").and_then(Value::as_f64) {
+            config.busy_timeout = Duration::from_secs_f64(secs.max(0.0));
+        }
+        Ok(config)
+    }
+}
+
+fn lock() -> MutexGuard<'static, Option<Config>> {
+    CONFIG.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn config() -> Option<Config> {
+    lock().clone()
+}
+
+/// Keep records in the database `config` names, migrating it first, or
+/// with `None` in files again. Records already in the database stay
+/// there.
+pub fn configure(config: Option<Config>) -> Result<()> {
+    if let Some(config) = &config {
+        migrate(config)?;
+    }
+    *lock() = config;
+    Ok(())
+}
+
+/// The schema version of the configured database; `None` without one.
+pub fn schema_version() -> Result<Option<usize>> {
+    match config() {
+        Some(config) => Ok(Some(db::user_version(&config)?)),
+        None => Ok(None),
+    }
+}
+
+fn migrate(config: &Config) -> Result<()> {
+    if let Some(parent) = config.db_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // Readers and a writer can then work at the same time.
+    db::execute(config, "PRAGMA journal_mode = WAL;")?;
+    let version = db::user_version(config)?;
+    if version > MIGRATIONS.len() {
+        return Err(Error::Unsupported(format!(
+            "database schema version {version} is newer than this build's {}",
+            MIGRATIONS.len()
+        )));
+    }
+    for (done, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        db::execute(
+            config,
+            &format!(
+                "BEGIN IMMEDIATE;\n{migration}\nPRAGMA user_version = {};\nCOMMIT;",
+                done + 1
+            ),
+        )?;
+    }
+    Ok(())
+}
+
+/// The record at `path`; `None` if there is none.
+pub fn read(path: &Path) -> Result<Option<Vec<u8>>> {
+    if let Some(config) = config() {
+        if let Some(data) = db::get(&config, &key(path)?)? {
+            return open(data, path).map(Some);
+        }
+    }
+    match fs::read(path) {
+        Ok(data) => open(data, path).map(Some),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// The record at `path` as text.
+pub fn read_string(path: &Path) -> Result<Option<String>> {
+    match read(path)? {
+        Some(data) => String::from_utf8(data)
+            .map(Some)
+            .map_err(|_| Error::Parse(format!("{} is not UTF-8", path.display()))),
+        None => Ok(None),
+    }
+}
+
+/// Store `data` as the record at `path`, replacing it. Its directory must
+/// exist.
+pub fn write(path: &Path, data: &[u8]) -> Result<()> {
+    let data = &encryption::seal(data, &key(path)?)?;
+    match config() {
+        Some(config) => {
+            let key = key(path)?;
+            let parent = Path::new(&key)
+                .parent()
+                .map(|p| p.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let modified = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs());
+            db::put(&config, &key, &parent, data, modified)?;
+            // The file is stale now; reads must not fall back to it.
+            match fs::remove_file(path) {
+                Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            }
+        }
+        None => {
+            // Named for the process and the write, so two processes
+            // writing the same record never share a temporary file.
+            let mut tmp = path.as_os_str().to_owned();
+            tmp.push(format!(
+                ".{}-{}.tmp",
+                std::process::id(),
+                SCRATCH.fetch_add(1, Ordering::Relaxed)
+            ));
+            let tmp = PathBuf::from(tmp);
+            fs::write(&tmp, data)?;
+            fs::rename(&tmp, path).inspect_err(|_| {
+                let _ = fs::remove_file(&tmp);
+            })?;
+            Ok(())
+        }
+    }
+}
+
+/// Remove the record at `path`. Returns false if there was none.
+pub fn remove(path: &Path) -> Result<bool> {
+    let mut removed = false;
+    if let Some(config) = config() {
+        removed = db::delete(&config, &key(path)?)?;
+    }
+    match fs::remove_file(path) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(removed),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Remove the records in the database under `dir`, at any depth, for a
+/// directory about to be deleted; its files are the caller's to delete.
+pub fn remove_all(dir: &Path) -> Result<()> {
+    if let Some(config) = config() {
+        let mut prefix = key(dir)?;
+        if !prefix.ends_with(path::MAIN_SEPARATOR) {
+            prefix.push(path::MAIN_SEPARATOR);
+        }
+        db::delete_under(&config, &prefix)?;
+    }
+    Ok(())
+}
+
+/// Whether there is a record at `path`.
+pub fn exists(path: &Path) -> Result<bool> {
+    if let Some(config) = config() {
+        if db::contains(&config, &key(path)?)? {
+            return Ok(true);
+        }
+    }
+    Ok(path.is_file())
+}
+
+/// The names of the records directly in `dir`, sorted.
+pub fn list(dir: &Path) -> Result<Vec<String>> {
+    let mut names: Vec<String> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                entry.file_type().ok()?.is_file().then_some(())?;
+                let name = entry.file_name().into_string().ok()?;
+                (!name.ends_with(".tmp")).then_some(name)
+            })
+            .collect(),
+        Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+    if let Some(config) = config() {
+        for path in db::children(&config, &key(dir)?)? {
+            if let Some(name) = Path::new(&path).file_name().and_then(|n| n.to_str()) {
+                names.push(name.to_string());
+            }
+        }
+    }
+    names.sort();
+    names.dedup();
+    Ok(names)
+}
+
+/// Seal the records written plain before an encryption key was
+/// configured, which are refused once it is: every record in the database
+/// and every file at any depth under `dirs`, which must hold only records
+/// (the cache, jobs, memories, the registry, projects and sessions; not
+/// ingested documents or the audit log). Run once, after configuring the
+/// key. Returns how many records were sealed.
+pub fn seal_existing(dirs: &[&Path]) -> Result<usize> {
+    if !encryption::is_enabled() {
+        return Err(Error::Unsupported(
+            "records are sealed under a key, and none is configured".into(),
+        ));
+    }
+    let mut sealed = 0;
+    if let Some(config) = config() {
+        for key in db::paths(&config)? {
+            match db::get(&config, &key)? {
+                Some(data) if !encryption::is_sealed(&data) => {
+                    write(Path::new(&key), &data)?;
+                    sealed += 1;
+                }
+                _ => {}
+            }
+        }
+    }
+    for dir in dirs {
+        sealed += seal_files(dir)?;
+    }
+    Ok(sealed)
+}
+
+fn seal_files(dir: &Path) -> Result<usize> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e.into()),
+    };
+    let mut sealed = 0;
+    for entry in entries {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            sealed += seal_files(&path)?;
+            continue;
+        }
+        if path.extension().is_some_and(|e| e == "tmp") {
+            continue;
+        }
+        let data = fs::read(&path)?;
+        if !encryption::is_sealed(&data) {
+            write(&path, &data)?;
+            sealed += 1;
+        }
+    }
+    Ok(sealed)
+}
+
+/// The record's key in the database, and its name when sealed: its
+/// absolute path.
+pub(crate) fn key(path: &Path) -> Result<String> {
+    let path = path::absolute(path)?;
+    path.to_str()
+        .map(str::to_string)
+        .ok_or_else(|| Error::Unsupported(format!("{} is not UTF-8", path.display())))
+}
+
+fn open(data: Vec<u8>, path: &Path) -> Result<Vec<u8>> {
+    encryption::open(data, &key(path)?)
+}
+
+/// The database through SQLite compiled in, kept open between calls.
+#[cfg(feature = "sqlite")]
+mod embedded {
+    use std::path::PathBuf;
+    use std::sync::{Mutex, MutexGuard};
+
+    use rusqlite::{params, Connection, OptionalExtension};
+
+    use super::Config;
+    use crate::error::{Error, Result};
+
+    static OPEN: Mutex<Option<(PathBuf, Connection)>> = Mutex::new(None);
+
+    /// Run `f` on a connection to `config`'s database, opened if another
+    /// or none is.
+    fn with<T>(config: &Config, f: impl FnOnce(&Connection) -> rusqlite::Result<T>) -> Result<T> {
+        let failed = |e: rusqlite::Error| {
+            Error::Backend(format!(
+                "sqlite failed on {}: {e}",
+                config.db_path.display()
+            ))
+        };
+        let mut open: MutexGuard<_> = OPEN.lock().unwrap_or_else(|e| e.into_inner());
+        if open
+            .as_ref()
+            .is_none_or(|(path, _)| *path != config.db_path)
+        {
+            let connection = Connection::open(&config.db_path).map_err(failed)?;
+            connection
+                .busy_timeout(config.busy_timeout)
+                .map_err(failed)?;
+            *open = Some((config.db_path.clone(), connection));
+        }
+        match open.as_ref() {
+            Some((_, connection)) => f(connection).map_err(failed),
+            None => Err(Error::Backend("no database connection".into())),
+        }
+    }
+
+    pub(super) fn execute(config: &Config, sql: &str) -> Result<()> {
+        with(config, |c| c.execute_batch(sql))
+    }
+
+    pub(super) fn user_version(config: &Config) -> Result<usize> {
+        let version: i64 = with(config, |c| {
+            c.query_row("PRAGMA user_version", [], |row| row.get(0))
+        })?;
+        Ok(version as usize)
+    }
+
+    pub(super) fn get(config: &Config, key: &str) -> Result<Option<Vec<u8>>> {
+        with(config, |c| {
+            c.query_row("SELECT data FROM records WHERE path = ?1", [key], |row| {
+                row.get(0)
+            })
+            .optional()
+        })
+    }
+
+    pub(super) fn put(
+        config: &Config,
+        key: &str,
+        parent: &str,
+        data: &[u8],
+        modified: u64,
+    ) -> Result<()> {
+        with(config, |c| {
+            c.execute(
+                "INSERT INTO records (path, parent, data, modified) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (path) DO UPDATE SET data = excluded.data, modified = excluded.modified",
+                params![key, parent, data, modified as i64],
+            )
+            .map(|_| ())
+        })
+    }
+
+    pub(super) fn delete(config: &Config, key: &str) -> Result<bool> {
+        with(config, |c| {
+            c.execute("DELETE FROM records WHERE path = ?1", [key])
+                .map(|n| n > 0)
+        })
+    }
+
+    pub(super) fn delete_under(config: &Config, prefix: &str) -> Result<()> {
+        with(config, |c| {
+            c.execute(
+                "DELETE FROM records WHERE substr(path, 1, length(?1)) = ?1",
+                [prefix],
+            )
+            .map(|_| ())
+        })
+    }
+
+    pub(super) fn contains(config: &Config, key: &str) -> Result<bool> {
+        with(config, |c| {
+            c.query_row(
+                "SELECT count(*) FROM records WHERE path = ?1",
+                [key],
+                |row| row.get::<_, i64>(0),
+            )
+            .map(|n| n > 0)
+        })
+    }
+
+    pub(super) fn paths(config: &Config) -> Result<Vec<String>> {
+        with(config, |c| {
+            let mut statement = c.prepare("SELECT path FROM records")?;
+            let paths = statement.query_map([], |row| row.get(0))?;
+            paths.collect()
+        })
+    }
+
+    pub(super) fn children(config: &Config, parent: &str) -> Result<Vec<String>> {
+        with(config, |c| {
+            let mut statement = c.prepare("SELECT path FROM records WHERE parent = ?1")?;
+            let paths = statement.query_map([parent], |row| row.get(0))?;
+            paths.collect()
+        })
+    }
+}
+
+/// The database through the system `sqlite3`, run once per call.
+#[cfg(not(feature = "sqlite"))]
+mod cli {
+    use std::io::{ErrorKind, Write};
+    use std::process::{Command, Stdio};
+    use std::thread;
+
+    use super::Config;
+    use crate::error::{Error, Result};
+    use crate::hash;
+
+    pub(super) fn execute(config: &Config, sql: &str) -> Result<()> {
+        sqlite(config, sql).map(|_| ())
+    }
+
+    pub(super) fn user_version(config: &Config) -> Result<usize> {
+        let out = sqlite(config, "PRAGMA user_version;")?;
+        out.trim()
+            .parse()
+            .map_err(|_| Error::Parse(format!("unexpected schema version '{}'", out.trim())))
+    }
+
+    pub(super) fn get(config: &Config, key: &str) -> Result<Option<Vec<u8>>> {
+        let out = sqlite(
+            config,
+            &format!("SELECT hex(data) FROM records WHERE path = {};", quote(key)),
+        )?;
+        out.lines().next().map(|hex| unhex(hex.trim())).transpose()
+    }
+
+    pub(super) fn put(
+        config: &Config,
+        key: &str,
+        parent: &str,
+        data: &[u8],
+        modified: u64,
+    ) -> Result<()> {
+        sqlite(
+            config,
+            &format!(
+                "INSERT INTO records (path, parent, data, modified) VALUES ({}, {}, X'{}', {modified})
+                 ON CONFLICT (path) DO UPDATE SET data = excluded.data, modified = excluded.modified;",
+                quote(key),
+                quote(parent),
+                hex(data)
+            ),
+        )
+        .map(|_| ())
+    }
+
+    pub(super) fn delete(config: &Config, key: &str) -> Result<bool> {
+        let out = sqlite(
+            config,
+            &format!(
+                "DELETE FROM records WHERE path = {};\nSELECT changes();",
+                quote(key)
+            ),
+        )?;
+        Ok(out.trim() != "0")
+    }
+
+    pub(super) fn delete_under(config: &Config, prefix: &str) -> Result<()> {
+        sqlite(
+            config,
+            &format!(
+                "DELETE FROM records WHERE substr(path, 1, length({0})) = {0};",
+                quote(prefix)
+            ),
+        )
+        .map(|_| ())
+    }
+
+    pub(super) fn contains(config: &Config, key: &str) -> Result<bool> {
+        let out = sqlite(
+            config,
+            &format!("SELECT count(*) FROM records WHERE path = {};", quote(key)),
+        )?;
+        Ok(out.trim() != "0")
+    }
+
+    pub(super) fn paths(config: &Config) -> Result<Vec<String>> {
+        hex_lines(&sqlite(config, "SELECT hex(path) FROM records;")?)
+    }
+
+    pub(super) fn children(config: &Config, parent: &str) -> Result<Vec<String>> {
+        let out = sqlite(
+            config,
+            &format!(
+                "SELECT hex(path) FROM records WHERE parent = {};",
+                quote(parent)
+            ),
+        )?;
+        hex_lines(&out)
+    }
+
+    /// Each line of `out` as the text it is hex digits of.
+    fn hex_lines(out: &str) -> Result<Vec<String>> {
+        out.lines()
+            .map(|line| {
+                String::from_utf8(unhex(line.trim())?)
+                    .map_err(|_| Error::Parse("record path is not UTF-8".into()))
+            })
+            .collect()
+    }
+
+    /// `text` as an SQL string literal, written in hex so no byte of it
+    /// needs escaping.
+    fn quote(text: &str) -> String {
+        format!("CAST(X'{}' AS TEXT)", hex(text.as_bytes()))
+    }
+
+    fn hex(data: &[u8]) -> String {
+        data.iter().map(|b| format!("{b:02X}")).collect()
+    }
+
+    fn unhex(text: &str) -> Result<Vec<u8>> {
+        hash::unhex(text).ok_or_else(|| Error::Parse("unexpected output from sqlite3".into()))
+    }
+
+    /// Run `sql` against the database, returning what it prints.
+    fn sqlite(config: &Config, sql: &str) -> Result<String> {
+        let mut child = Command::new("sqlite3")
+            .args(["-batch", "-bail", "-noheader", "-list"])
+            // The path cannot be read as an option.
+            .arg("--")
+            .arg(&config.db_path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|err| match err.kind() {
+                ErrorKind::NotFound => {
+                    Error::Unsupported("a database needs sqlite3, or the sqlite feature".into())
+                }
+                _ => err.into(),
+            })?;
+        let script = format!(
+            ".timeout {}\n{sql}\n",
+            config.busy_timeout.as_millis().min(i32::MAX as u128)
+        );
+        // Written on a thread so a large result cannot fill the output pipe
+        // while the script is still being written.
+        let mut stdin = child.stdin.take();
+        let writer = thread::spawn(move || {
+            if let Some(stdin) = stdin.as_mut() {
+                let _ = stdin.write_all(script.as_bytes());
+            }
+        });
+        let output = child.wait_with_output()?;
+        let _ = writer.join();
+        if !output.status.success() {
+            let message = String::from_utf8_lossy(&output.stderr);
+            return Err(Error::Backend(format!(
+                "sqlite3 failed on {}: {}",
+                config.db_path.display(),
+                message.trim()
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
@@ -0,0 +1,732 @@
+//! JSON, XML and YAML files rendered as readable prompt context.
+
+use crate::error::{Error, Result};
+use crate::json::{self, Value};
+use crate::xml::{self, Element, Node};
+
+/// Deeper YAML is rejected: rendering and dropping the tree both recurse.
+const MAX_DEPTH: usize = 256;
+
+/// Layout for structured data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StructuredStyle {
+    /// Indented, one value per line.
+    Pretty,
+    /// One `path.to[0].key: value` line per leaf.
+    Flat,
+}
+
+impl StructuredStyle {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "pretty" => Some(StructuredStyle::Pretty),
+            "flat" => Some(StructuredStyle::Flat),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StructuredOptions {
+    pub style: StructuredStyle,
+    /// Containers nested deeper than this are summarized instead of expanded.
+    pub max_depth: Option<usize>,
+    /// Output is cut at this many characters with a trailing note.
+    pub max_chars: Option<usize>,
+}
+
+impl Default for StructuredOptions {
+    fn default() -> Self {
+        StructuredOptions {
+            style: StructuredStyle::Pretty,
+            max_depth: None,
+            max_chars: None,
+        }
+    }
+}
+
+/// Pretty-print or flatten a JSON document.
+pub fn render_json(text: &str, options: &StructuredOptions) -> Result<String> {
+    let value = json::parse(text)?;
+    let depth = options.max_depth.unwrap_or(usize::MAX);
+    let mut out = String::new();
+    match options.style {
+        StructuredStyle::Pretty => {
+            pretty_json(&mut out, &value, 0, depth);
+            out.push('\n');
+        }
+        StructuredStyle::Flat => flat_json(&mut out, "", &value, depth),
+    }
+    Ok(truncate(out, options.max_chars))
+}
+
+/// Pretty-print or flatten an XML document. Comments, processing
+/// instructions and the doctype are dropped.
+pub fn render_xml(text: &str, options: &StructuredOptions) -> Result<String> {
+    let root = xml::parse(text, false)?;
+    let depth = options.max_depth.unwrap_or(usize::MAX);
+    let mut out = String::new();
+    match options.style {
+        StructuredStyle::Pretty => pretty_xml(&mut out, &root, 0, depth),
+        StructuredStyle::Flat => flat_xml(&mut out, &root.name, &root, depth),
+    }
+    Ok(truncate(out, options.max_chars))
+}
+
+/// Pretty-print or flatten a YAML document. YAML is already readable, so
+/// pretty output without a depth limit is the text as written, comments
+/// included. Otherwise the document is parsed: block mappings and
+/// sequences, single-line flow collections, quoted, plain and block
+/// scalars. Anchors, aliases, tags and multiple documents are refused
+/// with [`Error::InvalidArgument`].
+pub fn render_yaml(text: &str, options: &StructuredOptions) -> Result<String> {
+    if options.style == StructuredStyle::Pretty && options.max_depth.is_none() {
+        return Ok(truncate(text.to_string(), options.max_chars));
+    }
+    let value = parse_yaml(text)?;
+    let depth = options.max_depth.unwrap_or(usize::MAX);
+    let mut out = String::new();
+    match options.style {
+        StructuredStyle::Pretty => pretty_yaml(&mut out, &value, 0, depth),
+        StructuredStyle::Flat => flat_json(&mut out, "", &value, depth),
+    }
+    Ok(truncate(out, options.max_chars))
+}
+
+fn truncate(mut out: String, max_chars: Option<usize>) -> String {
+    let Some(limit) = max_chars else {
+        return out;
+    };
+    if let Some((cut, _)) = out.char_indices().nth(limit) {
+        let remaining = out[cut..].chars().count();
+        out.truncate(cut);
+        out.push_str(&format!("\n… (truncated, {remaining} more characters)\n"));
+    }
+    out
+}
+
+fn summary(value: &Value) -> String {
+    match value {
+        Value::Array(items) => format!("[… {} items]", items.len()),
+        Value::Object(pairs) => format!("{{… {} keys}}", pairs.len()),
+        other => other.to_string(),
+    }
+}
+
+fn indent(out: &mut String, level: usize) {
+    for _ in 0..level {
+        out.push_str("  ");
+    }
+}
+
+fn pretty_json(out: &mut String, value: &Value, level: usize, max_depth: usize) {
+    match value {
+        Value::Array(items) if !items.is_empty() && level < max_depth => {
+            out.push_str("[\n");
+            for (i, item) in items.iter().enumerate() {
+                indent(out, level + 1);
+                pretty_json(out, item, level + 1, max_depth);
+                out.push_str(if i + 1 < items.len() { ",\n" } else { "\n" });
+            }
+            indent(out, level);
+            out.push(']');
+        }
+        Value::Object(pairs) if !pairs.is_empty() && level < max_depth => {
+            out.push_str("{\n");
+            for (i, (key, item)) in pairs.iter().enumerate() {
+                indent(out, level + 1);
+                out.push_str(&format!("{}: ", Value::from(key.as_str())));
+                pretty_json(out, item, level + 1, max_depth);
+                out.push_str(if i + 1 < pairs.len() { ",\n" } else { "\n" });
+            }
+            indent(out, level);
+            out.push('}');
+        }
+        Value::Array(items) if items.is_empty() => out.push_str("[]"),
+        Value::Object(pairs) if pairs.is_empty() => out.push_str("{}"),
+        other => out.push_str(&summary(other)),
+    }
+}
+
+/// A scalar or collapsed container on one line of YAML.
+fn inline_yaml(value: &Value) -> String {
+    match value {
+        Value::String(s) if s.contains('\n') || s.trim() != s => {
+            Value::from(s.as_str()).to_string()
+        }
+        Value::String(s) => s.clone(),
+        Value::Array(items) if items.is_empty() => "[]".to_string(),
+        Value::Object(pairs) if pairs.is_empty() => "{}".to_string(),
+        other => summary(other),
+    }
+}
+
+fn pretty_yaml(out: &mut String, value: &Value, level: usize, max_depth: usize) {
+    let expands = |value: &Value, level: usize| {
+        level < max_depth
+            && match value {
+                Value::Array(items) => !items.is_empty(),
+                Value::Object(pairs) => !pairs.is_empty(),
+                _ => false,
+            }
+    };
+    match value {
+        Value::Array(items) if expands(value, level) => {
+            for item in items {
+                indent(out, level);
+                if expands(item, level + 1) {
+                    // The first line of the item goes after the dash.
+                    let mut nested = String::new();
+                    pretty_yaml(&mut nested, item, level + 1, max_depth);
+                    out.push_str("- ");
+                    out.push_str(&nested[2 * (level + 1)..]);
+                } else {
+                    out.push_str(&format!("- {}\n", inline_yaml(item)));
+                }
+            }
+        }
+        Value::Object(pairs) if expands(value, level) => {
+            for (key, item) in pairs {
+                indent(out, level);
+                if expands(item, level + 1) {
+                    out.push_str(&format!("{key}:\n"));
+                    pretty_yaml(out, item, level + 1, max_depth);
+                } else {
+                    out.push_str(&format!("{key}: {}\n", inline_yaml(item)));
+                }
+            }
+        }
+        other => {
+            indent(out, level);
+            out.push_str(&inline_yaml(other));
+            out.push('\n');
+        }
+    }
+}
+
+fn flat_json(out: &mut String, path: &str, value: &Value, depth_left: usize) {
+    let expandable = match value {
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(pairs) => !pairs.is_empty(),
+        _ => false,
+    };
+    if !expandable || depth_left == 0 {
+        let key = if path.is_empty() { "$" } else { path };
+        let rendered = match value {
+            Value::String(s) => s.clone(),
+            Value::Array(items) if items.is_empty() => "[]".to_string(),
+            Value::Object(pairs) if pairs.is_empty() => "{}".to_string(),
+            other => summary(other),
+        };
+        out.push_str(&format!("{key}: {rendered}\n"));
+        return;
+    }
+    match value {
+        Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                flat_json(out, &format!("{path}[{i}]"), item, depth_left - 1);
+            }
+        }
+        Value::Object(pairs) => {
+            for (key, item) in pairs {
+                let child = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                flat_json(out, &child, item, depth_left - 1);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn open_tag(element: &Element) -> String {
+    let mut tag = format!("<{}", element.name);
+    for (key, value) in &element.attrs {
+        tag.push_str(&format!(" {key}=\"{value}\""));
+    }
+    tag.push('>');
+    tag
+}
+
+fn pretty_xml(out: &mut String, element: &Element, level: usize, max_depth: usize) {
+    indent(out, level);
+    let open = open_tag(element);
+    let close = format!("</{}>", element.name);
+    match element.children.as_slice() {
+        [] => out.push_str(&format!("{}/>\n", open.trim_end_matches('>'))),
+        [Node::Text(text)] => out.push_str(&format!("{open}{}{close}\n", collapse(text))),
+        _ if level >= max_depth => out.push_str(&format!("{open}…{close}\n")),
+        children => {
+            out.push_str(&open);
+            out.push('\n');
+            for child in children {
+                match child {
+                    Node::Element(el) => pretty_xml(out, el, level + 1, max_depth),
+                    Node::Text(text) => {
+                        indent(out, level + 1);
+                        out.push_str(&collapse(text));
+                        out.push('\n');
+                    }
+                }
+            }
+            indent(out, level);
+            out.push_str(&close);
+            out.push('\n');
+        }
+    }
+}
+
+fn flat_xml(out: &mut String, path: &str, element: &Element, depth_left: usize) {
+    for (key, value) in &element.attrs {
+        out.push_str(&format!("{path}/@{key}: {value}\n"));
+    }
+    let text: Vec<String> = element
+        .children
+        .iter()
+        .filter_map(|child| match child {
+            Node::Text(text) => Some(collapse(text)),
+            Node::Element(_) => None,
+        })
+        .collect();
+    if !text.is_empty() {
+        out.push_str(&format!("{path}: {}\n", text.join(" ")));
+    }
+    let elements: Vec<&Element> = element
+        .children
+        .iter()
+        .filter_map(|child| match child {
+            Node::Element(el) => Some(el),
+            Node::Text(_) => None,
+        })
+        .collect();
+    if elements.is_empty() {
+        return;
+    }
+    if depth_left == 0 {
+        out.push_str(&format!("{path}: <… {} elements>\n", elements.len()));
+        return;
+    }
+    for el in &elements {
+        let siblings = elements
+            .iter()
+            .filter(|other| other.name == el.name)
+            .count();
+        let child_path = if siblings > 1 {
+            let index = elements
+                .iter()
+                .take_while(|other| !std::ptr::eq(**other, *el))
+                .filter(|other| other.name == el.name)
+                .count();
+            format!("{path}/{}[{index}]", el.name)
+        } else {
+            format!("{path}/{}", el.name)
+        };
+        flat_xml(out, &child_path, el, depth_left - 1);
+    }
+}
+
+struct YamlLine<'a> {
+    indent: usize,
+    /// The line without its indentation or comment.
+    text: String,
+    /// The line as written, for block scalars.
+    raw: &'a str,
+}
+
+fn unsupported_yaml(what: &str) -> Error {
+    Error::InvalidArgument(format!("YAML {what} cannot be restructured"))
+}
+
+/// `line` without a trailing `# comment`, ignoring `#` inside quotes.
+fn strip_yaml_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (i, c) in line.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') if previous.is_whitespace() || "[{,:".contains(previous) => {
+                quote = Some(c)
+            }
+            (Some(q), _) if q == c => quote = None,
+            (None, '#') if previous.is_whitespace() => return &line[..i],
+            _ => {}
+        }
+        previous = c;
+    }
+    line
+}
+
+fn parse_yaml(text: &str) -> Result<Value> {
+    let mut lines = Vec::new();
+    let mut started = false;
+    for raw in text.lines() {
+        let content = raw.trim_start_matches(' ');
+        if content.starts_with('\t') {
+            return Err(Error::Parse("tabs cannot indent YAML".into()));
+        }
+        let stripped = if content.starts_with('#') {
+            ""
+        } else {
+            strip_yaml_comment(content).trim_end()
+        };
+        if raw.starts_with("---") && stripped.trim_start_matches('-').trim().is_empty() {
+            if started {
+                return Err(unsupported_yaml("multiple documents"));
+            }
+            started = true;
+            continue;
+        }
+        if stripped == "..." {
+            break;
+        }
+        if stripped.starts_with('%') {
+            return Err(unsupported_yaml("directives"));
+        }
+        started |= !stripped.is_empty();
+        lines.push(YamlLine {
+            indent: raw.len() - content.len(),
+            text: stripped.to_string(),
+            raw,
+        });
+    }
+    let mut parser = YamlParser { lines, pos: 0 };
+    parser.skip_blank();
+    let Some(indent) = parser.peek().map(|line| line.indent) else {
+        return Ok(Value::Null);
+    };
+    let value = parser.node(indent, 0)?;
+    parser.skip_blank();
+    match parser.peek() {
+        Some(line) => Err(Error::Parse(format!(
+            "unexpected YAML line '{}'",
+            line.text
+        ))),
+        None => Ok(value),
+    }
+}
+
+struct YamlParser<'a> {
+    lines: Vec<YamlLine<'a>>,
+    pos: usize,
+}
+
+impl YamlParser<'_> {
+    fn skip_blank(&mut self) {
+        while self
+            .lines
+            .get(self.pos)
+            .is_some_and(|line| line.text.is_empty())
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<&YamlLine<'_>> {
+        self.lines.get(self.pos)
+    }
+
+    /// The block starting at the current line, indented by `indent`.
+    fn node(&mut self, indent: usize, depth: usize) -> Result<Value> {
+        if depth >= MAX_DEPTH {
+            return Err(Error::Parse("YAML nesting too deep".into()));
+        }
+        let text = self.lines[self.pos].text.clone();
+        if text == "-" || text.starts_with("- ") {
+            return self.sequence(indent, depth);
+        }
+        match split_key(&text)? {
+            Some(_) => self.mapping(indent, depth),
+            None => {
+                self.pos += 1;
+                scalar(&text)
+            }
+        }
+    }
+
+    fn sequence(&mut self, indent: usize, depth: usize) -> Result<Value> {
+        let mut items = Vec::new();
+        loop {
+            self.skip_blank();
+            let Some(line) = self.lines.get_mut(self.pos) else {
+                break;
+            };
+            if line.indent != indent || !(line.text == "-" || line.text.starts_with("- ")) {
+                break;
+            }
+            let rest = line.text[1..].trim_start().to_string();
+            if rest.is_empty() {
+                self.pos += 1;
+                items.push(self.child(indent, false, depth)?);
+            } else {
+                // Read the rest of the line as the first line of a block
+                // indented to where it starts.
+                line.indent = indent + line.text.len() - rest.len();
+                line.text = rest;
+                let indent = line.indent;
+                items.push(self.node(indent, depth + 1)?);
+            }
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn mapping(&mut self, indent: usize, depth: usize) -> Result<Value> {
+        let mut pairs = Vec::new();
+        loop {
+            self.skip_blank();
+            let Some(line) = self.lines.get(self.pos) else {
+                break;
+            };
+            if line.indent != indent {
+                if line.indent > indent {
+                    return Err(Error::Parse(format!(
+                        "unexpected YAML line '{}'",
+                        line.text
+                    )));
+                }
+                break;
+            }
+            let Some((key, rest)) = split_key(&line.text)? else {
+                return Err(Error::Parse(format!(
+                    "expected 'key: value' in YAML, got '{}'",
+                    line.text
+                )));
+            };
+            self.pos += 1;
+            let value = match rest.chars().next() {
+                None => self.child(indent, true, depth)?,
+                Some('|' | '>') => self.block_scalar(indent, &rest)?,
+                Some(_) => scalar(&rest)?,
+            };
+            pairs.push((key, value));
+        }
+        Ok(Value::Object(pairs))
+    }
+
+    /// The value of a key or dash with nothing after it on the line: the
+    /// more indented block that follows, a sequence at the same indent
+    /// under a key, or null.
+    fn child(&mut self, indent: usize, under_key: bool, depth: usize) -> Result<Value> {
+        self.skip_blank();
+        match self.peek() {
+            Some(line) if line.indent > indent => {
+                let indent = line.indent;
+                self.node(indent, depth + 1)
+            }
+            Some(line)
+                if under_key
+                    && line.indent == indent
+                    && (line.text == "-" || line.text.starts_with("- ")) =>
+            {
+                self.sequence(indent, depth + 1)
+            }
+            _ => Ok(Value::Null),
+        }
+    }
+
+    /// A `|` (literal) or `>` (folded) scalar in the lines indented past
+    /// `indent`.
+    fn block_scalar(&mut self, indent: usize, header: &str) -> Result<Value> {
+        let folded = header.starts_with('>');
+        let chomp = &header[1..];
+        if !matches!(chomp, "" | "-" | "+") {
+            return Err(unsupported_yaml("block scalar indicators"));
+        }
+        let mut block: Vec<&str> = Vec::new();
+        let mut margin = None;
+        while let Some(line) = self.lines.get(self.pos) {
+            let blank = line.raw.trim().is_empty();
+            if !blank && line.indent <= indent {
+                break;
+            }
+            let margin = *margin.get_or_insert(line.indent);
+            if !blank && line.indent < margin {
+                break;
+            }
+            block.push(line.raw.get(margin..).unwrap_or(""));
+            self.pos += 1;
+        }
+        let trailing = block
+            .iter()
+            .rev()
+            .take_while(|line| line.is_empty())
+            .count();
+        block.truncate(block.len() - trailing);
+        let mut text = String::new();
+        for (i, line) in block.iter().enumerate() {
+            if i > 0 {
+                let joined = folded && !line.is_empty() && !block[i - 1].is_empty();
+                text.push(if joined { ' ' } else { '\n' });
+            }
+            text.push_str(line);
+        }
+        match chomp {
+            "-" => {}
+            "+" => text.push_str(&"\n".repeat(trailing + 1)),
+            _ if !text.is_empty() => text.push('\n'),
+            _ => {}
+        }
+        Ok(Value::String(text))
+    }
+}
+
+/// `key: rest` split at the first `: ` (or trailing `:`) outside quotes
+/// and brackets; `None` when the line has no key.
+fn split_key(text: &str) -> Result<Option<(String, String)>> {
+    if text.starts_with(['[', '{']) {
+        return Ok(None);
+    }
+    let mut quote = None;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') if i == 0 => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            (None, ':') if text[i + 1..].is_empty() || text[i + 1..].starts_with(' ') => {
+                let key = text[..i].trim();
+                if key.starts_with(['&', '*', '!', '?']) {
+                    return Err(unsupported_yaml("anchors, aliases, tags and complex keys"));
+                }
+                let key = match scalar(key)? {
+                    Value::String(s) => s,
+                    other => other.to_string(),
+                };
+                return Ok(Some((key, text[i + 1..].trim().to_string())));
+            }
+            _ => {}
+        }
+    }
+    Ok(None)
+}
+
+/// A value written on one line: a flow collection or a scalar.
+fn scalar(text: &str) -> Result<Value> {
+    let text = text.trim();
+    if text.starts_with(['&', '*', '!']) {
+        return Err(unsupported_yaml("anchors, aliases and tags"));
+    }
+    if text.starts_with(['[', '{']) {
+        let mut rest = text;
+        let value = flow(&mut rest, 0)?;
+        if !rest.trim().is_empty() {
+            return Err(Error::Parse(format!(
+                "unexpected '{}' after a YAML collection",
+                rest.trim()
+            )));
+        }
+        return Ok(value);
+    }
+    if let Some(inner) = text.strip_prefix('"') {
+        let inner = inner
+            .strip_suffix('"')
+            .ok_or_else(|| Error::Parse("unterminated YAML string".into()))?;
+        // Double-quoted YAML escapes are JSON's, plus a few more.
+        return match json::parse(&format!("\"{inner}\"")) {
+            Ok(value) => Ok(value),
+            Err(_) => Err(unsupported_yaml("escape sequences beyond JSON's")),
+        };
+    }
+    if let Some(inner) = text.strip_prefix('\'') {
+        let inner = inner
+            .strip_suffix('\'')
+            .ok_or_else(|| Error::Parse("unterminated YAML string".into()))?;
+        return Ok(Value::String(inner.replace("''", "'")));
+    }
+    Ok(match text {
+        "" | "~" | "null" | "Null" | "NULL" => Value::Null,
+        "true" | "True" | "TRUE" => Value::Bool(true),
+        "false" | "False" | "FALSE" => Value::Bool(false),
+        _ => match text.parse::<f64>() {
+            Ok(n)
+                if n.is_finite()
+                    && text.starts_with(|c: char| c.is_ascii_digit() || "+-.".contains(c)) =>
+            {
+                Value::Number(n)
+            }
+            _ => Value::String(text.to_string()),
+        },
+    })
+}
+
+/// A `[...]` or `{...}` flow collection at the start of `rest`, advancing
+/// past it.
+fn flow(rest: &mut &str, depth: usize) -> Result<Value> {
+    if depth >= MAX_DEPTH {
+        return Err(Error::Parse("YAML nesting too deep".into()));
+    }
+    let close = if rest.starts_with('[') { ']' } else { '}' };
+    *rest = &rest[1..];
+    let mut items = Vec::new();
+    let mut pairs = Vec::new();
+    loop {
+        *rest = rest.trim_start();
+        if let Some(after) = rest.strip_prefix(close) {
+            *rest = after;
+            break;
+        }
+        if rest.is_empty() {
+            return Err(unsupported_yaml("collections spanning lines"));
+        }
+        let value = if rest.starts_with(['[', '{']) {
+            flow(rest, depth + 1)?
+        } else {
+            // A scalar runs to the next comma or closing bracket outside
+            // quotes.
+            let mut quote = None;
+            let mut end = rest.len();
+            for (i, c) in rest.char_indices() {
+                match (quote, c) {
+                    (None, '"' | '\'') if i == 0 => quote = Some(c),
+                    (Some(q), _) if q == c => quote = None,
+                    (None, ',' | ']' | '}') => {
+                        end = i;
+                        break;
+                    }
+                    (None, ':') if close == '}' && rest[i + 1..].starts_with(' ') => {
+                        end = i + 1;
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+            let piece = &rest[..end];
+            *rest = &rest[end..];
+            if let Some(key) = piece.strip_suffix(':') {
+                let key = match scalar(key)? {
+                    Value::String(s) => s,
+                    other => other.to_string(),
+                };
+                *rest = rest.trim_start();
+                let value = if rest.starts_with(['[', '{']) {
+                    flow(rest, depth + 1)?
+                } else {
+                    let end = rest.find([',', '}']).unwrap_or(rest.len());
+                    let value = scalar(&rest[..end])?;
+                    *rest = &rest[end..];
+                    value
+                };
+                pairs.push((key, value));
+                *rest = rest.trim_start();
+                *rest = rest.strip_prefix(',').unwrap_or(rest);
+                continue;
+            }
+            scalar(piece)?
+        };
+        if close == '}' {
+            return Err(Error::Parse(
+                "expected 'key: value' in a YAML mapping".into(),
+            ));
+        }
+        items.push(value);
+        *rest = rest.trim_start();
+        *rest = rest.strip_prefix(',').unwrap_or(rest);
+    }
+    Ok(if close == ']' {
+        Value::Array(items)
+    } else {
+        Value::Object(pairs)
+    })
+}
@@ -0,0 +1,156 @@
+//! Summaries of documents of any length.
+//!
+//! A document that fits one prompt is summarized at once. A longer one is
+//! chunked, each chunk summarized in turn, and the partial summaries
+//! combined into one.
+
+use std::path::Path;
+
+use crate::chat::ChatOptions;
+use crate::chunk::chunk_text;
+use crate::error::Result;
+use crate::extract::extract_text;
+use crate::json::Value;
+use crate::progress::{Progress, Stage};
+
+#[derive(Debug, Clone)]
+pub struct SummarizeOptions {
+    /// Documents longer than this are summarized in parts.
+    pub max_chunk_chars: usize,
+    /// What the summary should be like, e.g. "three bullet points".
+    pub instructions: Option<String>,
+    pub chat: ChatOptions,
+}
+
+impl Default for SummarizeOptions {
+    fn default() -> Self {
+        SummarizeOptions {
+            max_chunk_chars: 12_000,
+            instructions: None,
+            chat: ChatOptions::default(),
+        }
+    }
+}
+
+impl SummarizeOptions {
+    /// Read `{"max_chunk_chars": n, "instructions": "...", "backend":
+    /// "profile"}`; missing keys keep their defaults.
+    pub fn from_json(value: &Value) -> Self {
+        let mut options = SummarizeOptions {
+            chat: ChatOptions::from_json(value),
+            instructions: value
+                .get("instructions")
+                .and_then(Value::as_str)
+                .filter(|s| !s.trim().is_empty())
+                .map(String::from),
+            ..SummarizeOptions::default()
+        };
+        if let Some(n) = value.get("max_chunk_chars").and_then(Value::as_f64) {
+            options.max_chunk_chars = (n as usize).max(1000);
+        }
+        options
+    }
+
+    fn instructions(&self) -> String {
+        match &self.instructions {
+            Some(instructions) => format!(" {}", instructions.trim()),
+            None => String::new(),
+        }
+    }
+}
+
+/// Summarize `text` through `chat`. `progress` hears about chunking and
+/// each part.
+pub fn summarize(
+    text: &str,
+    options: &SummarizeOptions,
+    chat: impl FnMut(&str) -> Result<String>,
+    progress: impl FnMut(&Progress),
+) -> Result<String> {
+    summarize_from(text, options, chat, progress, 0.0)
+}
+
+/// Extract the document at `path` and summarize it.
+pub fn summarize_document(
+    path: &Path,
+    options: &SummarizeOptions,
+    chat: impl FnMut(&str) -> Result<String>,
+    mut progress: impl FnMut(&Progress),
+) -> Result<String> {
+    let name = path.display().to_string();
+    progress(&Progress {
+        stage: Stage::Extracting,
+        completed: 0,
+        total: 1,
+        percent: 0.0,
+        item: Some(&name),
+    });
+    let text = extract_text(path)?;
+    summarize_from(&text, options, chat, progress, 10.0)
+}
+
+fn summarize_from(
+    text: &str,
+    options: &SummarizeOptions,
+    mut chat: impl FnMut(&str) -> Result<String>,
+    mut progress: impl FnMut(&Progress),
+    start: f64,
+) -> Result<String> {
+    progress(&Progress {
+        stage: Stage::Chunking,
+        completed: 0,
+        total: 1,
+        percent: start,
+        item: None,
+    });
+    let parts = chunk_text(text, options.max_chunk_chars);
+    // One step per part, and one to combine them when there are several.
+    let steps = match parts.len() {
+        0 | 1 => 1,
+        n => n + 1,
+    };
+    let mut report = |completed: usize, item: Option<&str>| {
+        progress(&Progress {
+            stage: Stage::Generating,
+            completed,
+            total: steps,
+            percent: start + (100.0 - start) * completed as f64 / steps as f64,
+            item,
+        })
+    };
+    let instructions = options.instructions();
+    let summary = match parts.as_slice() {
+        [] => String::new(),
+        [only] => {
+            report(0, None);
+            chat(&format!(
+                "Summarize the document below.{instructions} Respond with only the \
+                 summary.\n\nDocument:\n{}",
+                only.text
+            ))?
+        }
+        parts => {
+            let mut summaries = Vec::new();
+            for (index, part) in parts.iter().enumerate() {
+                let label = format!("part {} of {}", index + 1, parts.len());
+                report(index, Some(&label));
+                let summary = chat(&format!(
+                    "Summarize this part ({label}) of a longer document. Keep every \
+                     fact, figure and decision a summary of the whole would need. \
+                     Respond with only the summary.\n\nText:\n{}",
+                    part.text
+                ))?;
+                summaries.push(format!("Part {}:\n{}", index + 1, summary.trim()));
+            }
+            report(parts.len(), Some("combining"));
+            chat(&format!(
+                "Below are summaries of the consecutive parts of one document. \
+                 Combine them into a single summary of the whole document.{instructions} \
+                 Respond with only the summary.\n\n{}",
+                summaries.join("\n\n")
+            ))?
+        }
+    };
+    report(steps, None);
+    Ok(summary.trim().to_string())
+}
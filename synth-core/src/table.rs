@@ -0,0 +1,309 @@
+//! CSV/TSV ingestion rendered as Markdown tables or prompt-friendly records.
+
+use std::io::{BufRead, Cursor, Read};
+
+use crate::error::{Error, Result};
+
+/// How rows are laid out in the rendered text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableStyle {
+    /// A Markdown pipe table.
+    Markdown,
+    /// One `column: value` line per cell, blank line between rows.
+    Records,
+}
+
+impl TableStyle {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "markdown" => Some(TableStyle::Markdown),
+            "records" => Some(TableStyle::Records),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TableOptions {
+    pub style: TableStyle,
+    /// Stop rendering after this many data rows; the rest are only counted.
+    pub max_rows: Option<usize>,
+    /// Field delimiter; detected from the first lines when `None`.
+    pub delimiter: Option<char>,
+    /// Whether the first row is a header; guessed when `None`.
+    pub header: Option<bool>,
+}
+
+impl Default for TableOptions {
+    fn default() -> Self {
+        TableOptions {
+            style: TableStyle::Markdown,
+            max_rows: None,
+            delimiter: None,
+            header: None,
+        }
+    }
+}
+
+const SAMPLE_LINES: usize = 10;
+/// A quoted field longer than this is taken for a stray quote.
+pub const MAX_FIELD_BYTES: usize = 1 << 20;
+const CANDIDATE_DELIMITERS: [char; 4] = [',', '\t', ';', '|'];
+
+/// Parse delimited text from `reader` and render it according to `options`.
+/// Rows are streamed, so a row limit keeps memory flat on huge files. Rows
+/// wider than the header get extra `column N` names, and every row is
+/// padded to the widest. A header with no rows still renders as a table.
+pub fn render_table<R: BufRead>(mut reader: R, options: &TableOptions) -> Result<String> {
+    let mut sample = Vec::new();
+    for _ in 0..SAMPLE_LINES {
+        if reader.read_until(b'\n', &mut sample)? == 0 {
+            break;
+        }
+    }
+    let delimiter = options
+        .delimiter
+        .unwrap_or_else(|| detect_delimiter(&String::from_utf8_lossy(&sample)));
+    let mut rows = Rows {
+        reader: Cursor::new(sample).chain(reader),
+        delimiter,
+        line: 0,
+    };
+
+    let mut head = Vec::new();
+    for row in rows.by_ref().take(SAMPLE_LINES) {
+        head.push(row?);
+    }
+    let has_header = match options.header {
+        Some(flag) => flag,
+        None => head
+            .split_first()
+            .is_some_and(|(first, rest)| looks_like_header(first, rest)),
+    };
+    let mut head = head.into_iter();
+    let header = if has_header { head.next() } else { None };
+    let data = head.map(Ok).chain(rows);
+
+    let has_header = header.is_some();
+    let mut columns: Vec<String> = header.unwrap_or_default();
+    let limit = options.max_rows.unwrap_or(usize::MAX);
+    // The shown rows are held until the widest is known; the rest are
+    // only counted.
+    let mut shown = Vec::new();
+    let mut omitted = 0;
+    for row in data {
+        let row = row?;
+        if shown.len() == limit {
+            omitted += 1;
+            continue;
+        }
+        while columns.len() < row.len() {
+            columns.push(format!("column {}", columns.len() + 1));
+        }
+        shown.push(row);
+    }
+    let mut out = String::new();
+    match options.style {
+        TableStyle::Markdown if has_header || !shown.is_empty() => {
+            out = markdown_table(&columns, &shown);
+        }
+        TableStyle::Markdown => {}
+        TableStyle::Records => {
+            for row in &shown {
+                push_record(&mut out, &columns, row);
+            }
+        }
+    }
+    if omitted > 0 {
+        out.push_str(&format!("\n({omitted} more rows omitted)\n"));
+    }
+    Ok(out)
+}
+
+/// Pick the candidate delimiter that splits the sample lines most consistently.
+pub fn detect_delimiter(sample: &str) -> char {
+    let lines: Vec<&str> = sample.lines().filter(|l| !l.trim().is_empty()).collect();
+    let mut best = (',', 0usize, false);
+    for candidate in CANDIDATE_DELIMITERS {
+        let counts: Vec<usize> = lines.iter().map(|l| count_unquoted(l, candidate)).collect();
+        let total: usize = counts.iter().sum();
+        if total == 0 {
+            continue;
+        }
+        let consistent = counts.iter().all(|&n| n == counts[0]);
+        let better = match (consistent, best.2) {
+            (true, false) => true,
+            (false, true) => false,
+            _ => total > best.1,
+        };
+        if better {
+            best = (candidate, total, consistent);
+        }
+    }
+    best.0
+}
+
+fn count_unquoted(line: &str, delimiter: char) -> usize {
+    let mut in_quotes = false;
+    let mut count = 0;
+    for c in line.chars() {
+        if c == '"' {
+            in_quotes = !in_quotes;
+        } else if c == delimiter && !in_quotes {
+            count += 1;
+        }
+    }
+    count
+}
+
+fn looks_like_header(first: &[String], rest: &[Vec<String>]) -> bool {
+    if first.iter().any(|c| c.trim().is_empty() || is_numeric(c)) {
+        return false;
+    }
+    let unique = first
+        .iter()
+        .enumerate()
+        .all(|(i, cell)| !first[..i].contains(cell));
+    // A lone row of distinct labels is a header with no data yet.
+    if rest.is_empty() {
+        return unique;
+    }
+    let numeric_column = (0..first.len()).any(|col| {
+        rest.iter()
+            .filter_map(|row| row.get(col))
+            .all(|cell| is_numeric(cell))
+    });
+    let repeats = (0..first.len()).any(|col| rest.iter().any(|row| row.get(col) == first.get(col)));
+    unique && (numeric_column || !repeats)
+}
+
+fn is_numeric(cell: &str) -> bool {
+    let cleaned: String = cell
+        .trim()
+        .chars()
+        .filter(|c| !matches!(c, '$' | '%' | ','))
+        .collect();
+    !cleaned.is_empty() && cleaned.parse::<f64>().is_ok()
+}
+
+/// Render rows as a Markdown pipe table under the given column names.
+pub fn markdown_table(columns: &[String], rows: &[Vec<String>]) -> String {
+    let mut out = String::new();
+    push_markdown_header(&mut out, columns);
+    for row in rows {
+        push_markdown_row(&mut out, row, columns.len());
+    }
+    out
+}
+
+fn push_markdown_header(out: &mut String, columns: &[String]) {
+    push_markdown_row(out, columns, columns.len());
+    out.push('|');
+    for _ in columns {
+        out.push_str(" --- |");
+    }
+    out.push('\n');
+}
+
+fn push_markdown_row(out: &mut String, row: &[String], width: usize) {
+    out.push('|');
+    for i in 0..width.max(row.len()) {
+        let cell = row.get(i).map_or("", String::as_str);
+        out.push(' ');
+        out.push_str(&cell.replace('|', "\\|").replace(['\r', '\n'], " "));
+        out.push_str(" |");
+    }
+    out.push('\n');
+}
+
+fn push_record(out: &mut String, columns: &[String], row: &[String]) {
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    for (name, value) in columns.iter().zip(row) {
+        if !value.trim().is_empty() {
+            let value = value.trim().replace(['\r', '\n'], " ");
+            out.push_str(&format!("{name}: {value}\n"));
+        }
+    }
+}
+
+/// Streaming RFC 4180 record reader; quoted fields may span lines. A
+/// quote still open at the end of the input, or held open past
+/// [`MAX_FIELD_BYTES`], is a parse error naming the line it opened on.
+struct Rows<R> {
+    reader: R,
+    delimiter: char,
+    line: usize,
+}
+
+impl<R: BufRead> Rows<R> {
+    fn read_record(&mut self) -> Result<Option<Vec<String>>> {
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut in_quotes = false;
+        let mut opened = 0;
+        let mut bytes = Vec::new();
+        loop {
+            bytes.clear();
+            if self.reader.read_until(b'\n', &mut bytes)? == 0 {
+                if in_quotes {
+                    return Err(unterminated(opened));
+                }
+                return Ok(None);
+            }
+            self.line += 1;
+            let line = String::from_utf8_lossy(&bytes);
+            let content = line.trim_end_matches(['\n', '\r']);
+            let mut chars = content.chars().peekable();
+            while let Some(c) = chars.next() {
+                if in_quotes {
+                    if c == '"' {
+                        if chars.peek() == Some(&'"') {
+                            field.push('"');
+                            chars.next();
+                        } else {
+                            in_quotes = false;
+                        }
+                    } else {
+                        field.push(c);
+                    }
+                } else if c == '"' && field.is_empty() {
+                    in_quotes = true;
+                    opened = self.line;
+                } else if c == self.delimiter {
+                    fields.push(std::mem::take(&mut field));
+                } else {
+                    field.push(c);
+                }
+            }
+            if in_quotes {
+                if field.len() > MAX_FIELD_BYTES {
+                    return Err(unterminated(opened));
+                }
+                field.push('\n');
+                continue;
+            }
+            if fields.is_empty() && field.trim().is_empty() {
+                field.clear();
+                continue;
+            }
+            fields.push(field);
+            return Ok(Some(fields));
+        }
+    }
+}
+
+fn unterminated(line: usize) -> Error {
+    Error::Parse(format!(
+        "quoted field opened on line {line} is never closed"
+    ))
+}
+
+impl<R: BufRead> Iterator for Rows<R> {
+    type Item = Result<Vec<String>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
@@ -0,0 +1,125 @@
+//! Minimal ustar archives of regular files, readable by any `tar`.
+
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::error::{Error, Result};
+
+const BLOCK: usize = 512;
+
+/// Split `name` into the ustar `prefix` and `name` fields.
+fn split_name(name: &str) -> Result<(&str, &str)> {
+    if name.len() <= 100 {
+        return Ok(("", name));
+    }
+    name.match_indices('/')
+        .map(|(i, _)| (&name[..i], &name[i + 1..]))
+        .find(|(prefix, rest)| prefix.len() <= 155 && rest.len() <= 100 && !rest.is_empty())
+        .ok_or_else(|| Error::Parse(format!("archive path too long: {name}")))
+}
+
+fn octal(field: &mut [u8], value: u64) {
+    let digits = format!("{value:0width$o}", width = field.len() - 1);
+    field[..digits.len()].copy_from_slice(digits.as_bytes());
+}
+
+fn header(name: &str, size: u64, mtime: u64) -> Result<[u8; BLOCK]> {
+    let (prefix, name) = split_name(name)?;
+    let mut block = [0u8; BLOCK];
+    block[..name.len()].copy_from_slice(name.as_bytes());
+    octal(&mut block[100..108], 0o644);
+    octal(&mut block[108..116], 0);
+    octal(&mut block[116..124], 0);
+    octal(&mut block[124..136], size);
+    octal(&mut block[136..148], mtime);
+    block[156] = b'0';
+    block[257..263].copy_from_slice(b"ustar\0");
+    block[263..265].copy_from_slice(b"00");
+    block[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+    // The checksum is computed with its own field read as spaces.
+    block[148..156].fill(b' ');
+    let sum: u64 = block.iter().map(|&b| u64::from(b)).sum();
+    let digits = format!("{sum:06o}\0 ");
+    block[148..156].copy_from_slice(digits.as_bytes());
+    Ok(block)
+}
+
+/// Write `entries` (`(path inside the archive, contents)`) to `path`.
+pub fn write(path: &Path, entries: &[(String, Vec<u8>)], mtime: u64) -> Result<()> {
+    let mut out = BufWriter::new(fs::File::create(path)?);
+    for (name, data) in entries {
+        out.write_all(&header(name, data.len() as u64, mtime)?)?;
+        out.write_all(data)?;
+        let padding = (BLOCK - data.len() % BLOCK) % BLOCK;
+        out.write_all(&vec![0u8; padding])?;
+    }
+    out.write_all(&[0u8; BLOCK * 2])?;
+    out.flush()?;
+    Ok(())
+}
+
+fn field(bytes: &[u8]) -> &str {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    std::str::from_utf8(&bytes[..end]).unwrap_or("")
+}
+
+fn parse_octal(bytes: &[u8]) -> Result<u64> {
+    let text = field(bytes).trim_matches(|c: char| c == ' ' || c == '\0');
+    if text.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(text, 8).map_err(|_| Error::Parse("corrupt archive header".into()))
+}
+
+/// The regular files in the archive at `path`. Directories, links and
+/// other entry types are skipped.
+pub fn read(path: &Path) -> Result<Vec<(String, Vec<u8>)>> {
+    let data = fs::read(path)?;
+    let mut entries = Vec::new();
+    let mut offset = 0;
+    while offset + BLOCK <= data.len() {
+        let block = &data[offset..offset + BLOCK];
+        if block.iter().all(|&b| b == 0) {
+            break;
+        }
+        let stored = parse_octal(&block[148..156])?;
+        let sum: u64 = block
+            .iter()
+            .enumerate()
+            .map(|(i, &b)| {
+                if (148..156).contains(&i) {
+                    32
+                } else {
+                    u64::from(b)
+                }
+            })
+            .sum();
+        if sum != stored {
+            return Err(Error::Parse("corrupt archive header".into()));
+        }
+        let size = parse_octal(&block[124..136])? as usize;
+        let start = offset + BLOCK;
+        let end = start
+            .checked_add(size)
+            .filter(|&end| end <= data.len())
+            .ok_or_else(|| Error::Parse("truncated archive".into()))?;
+        if matches!(block[156], b'0' | 0) {
+            // GNU archives reuse the prefix field, so only POSIX ones have it.
+            let prefix = if &block[257..263] == b"ustar\0" {
+                field(&block[345..500])
+            } else {
+                ""
+            };
+            let name = field(&block[..100]);
+            let name = if prefix.is_empty() {
+                name.to_string()
+            } else {
+                format!("{prefix}/{name}")
+            };
+            entries.push((name, data[start..end].to_vec()));
+        }
+        offset = start + size.div_ceil(BLOCK) * BLOCK;
+    }
+    Ok(entries)
+}
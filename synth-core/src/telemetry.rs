@@ -0,0 +1,59 @@
+//! Opt-in operational events for a host-registered exporter.
+//!
+//! Nothing is emitted until an exporter is set. Events are anonymous: the
+//! operation name, how long it took and the error class, never paths,
+//! prompts or document content.
+
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::error::Result;
+use crate::json::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event<'a> {
+    /// e.g. "chat", "extract" or "job.generate_minutes".
+    pub operation: &'a str,
+    pub duration: Duration,
+    /// [`crate::error::Error::kind`] of the failure, if any.
+    pub error: Option<&'static str>,
+}
+
+impl Event<'_> {
+    pub fn to_json(&self) -> Value {
+        Value::object([
+            ("operation", Value::from(self.operation)),
+            (
+                "duration_ms",
+                Value::from(self.duration.as_secs_f64() * 1000.0),
+            ),
+            ("ok", Value::from(self.error.is_none())),
+            ("error", self.error.map_or(Value::Null, Value::from)),
+        ])
+    }
+}
+
+pub type Exporter = Arc<dyn Fn(&Event) + Send + Sync>;
+
+static EXPORTER: RwLock<Option<Exporter>> = RwLock::new(None);
+
+/// Install (or with `None`, remove) the exporter.
+pub fn set_exporter(exporter: Option<Exporter>) {
+    *EXPORTER.write().unwrap_or_else(|e| e.into_inner()) = exporter;
+}
+
+/// Run `f` and report it as `operation` when an exporter is set.
+pub fn measure<T>(operation: &str, f: impl FnOnce() -> Result<T>) -> Result<T> {
+    let exporter = EXPORTER.read().unwrap_or_else(|e| e.into_inner()).clone();
+    let Some(exporter) = exporter else {
+        return f();
+    };
+    let started = Instant::now();
+    let result = f();
+    exporter(&Event {
+        operation,
+        duration: started.elapsed(),
+        error: result.as_ref().err().map(|e| e.kind()),
+    });
+    result
+}
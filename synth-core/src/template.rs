@@ -0,0 +1,141 @@
+//! Mail-merge: fill `{{field}}` placeholders in a text template per record.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+use crate::extract::Format;
+use crate::json::Value;
+use crate::policy;
+
+/// Result of filling one template.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filled {
+    pub text: String,
+    /// Placeholders with no matching field in the record.
+    pub missing: Vec<String>,
+}
+
+/// Replace each `{{ field }}` (or `{{ nested.field }}`) with the record's
+/// value. Missing fields render as empty and are reported.
+pub fn fill(template: &str, record: &Value) -> Filled {
+    let mut text = String::with_capacity(template.len());
+    let mut missing = Vec::new();
+    let mut rest = template;
+    while let Some(open) = rest.find("{{") {
+        let Some(close) = rest[open + 2..].find("}}") else {
+            break;
+        };
+        text.push_str(&rest[..open]);
+        let field = rest[open + 2..open + 2 + close].trim();
+        match lookup(record, field) {
+            Some(value) => text.push_str(&display(value)),
+            None => {
+                if !missing.iter().any(|m| m == field) {
+                    missing.push(field.to_string());
+                }
+            }
+        }
+        rest = &rest[open + 2 + close + 2..];
+    }
+    text.push_str(rest);
+    Filled { text, missing }
+}
+
+fn lookup<'a>(record: &'a Value, field: &str) -> Option<&'a Value> {
+    field
+        .split('.')
+        .try_fold(record, |value, key| value.get(key))
+        .filter(|value| !matches!(value, Value::Null))
+}
+
+fn display(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Summary of a bulk generation run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Generated {
+    pub written: Vec<PathBuf>,
+    /// `(record index, missing fields)` for records with unfilled placeholders.
+    pub missing: Vec<(usize, Vec<String>)>,
+}
+
+impl Generated {
+    pub fn to_json(&self) -> Value {
+        Value::object([
+            (
+                "written",
+                Value::Array(
+                    self.written
+                        .iter()
+                        .map(|p| Value::from(p.display().to_string()))
+                        .collect(),
+                ),
+            ),
+            (
+                "missing",
+                Value::Array(
+                    self.missing
+                        .iter()
+                        .map(|(index, fields)| {
+                            Value::object([
+                                ("index", Value::from(*index)),
+                                (
+                                    "fields",
+                                    Value::Array(
+                                        fields.iter().map(|f| Value::from(f.as_str())).collect(),
+                                    ),
+                                ),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+        ])
+    }
+}
+
+/// Write one filled copy of `template_path` per record into `out_dir`,
+/// named `<template stem>-<n>.<ext>`. `progress` is called after each file
+/// with `(completed, total)`.
+pub fn generate_documents(
+    template_path: &Path,
+    records: &[Value],
+    out_dir: &Path,
+    mut progress: impl FnMut(usize, usize),
+) -> Result<Generated> {
+    if Format::from_path(template_path) != Some(Format::Text) {
+        return Err(Error::Unsupported(template_path.display().to_string()));
+    }
+    let template = String::from_utf8_lossy(&policy::read_file(template_path)?).into_owned();
+    let stem = template_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("document");
+    let ext = template_path
+        .extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or("txt");
+    let width = records.len().to_string().len();
+    fs::create_dir_all(out_dir)?;
+
+    let mut generated = Generated {
+        written: Vec::new(),
+        missing: Vec::new(),
+    };
+    for (index, record) in records.iter().enumerate() {
+        let filled = fill(&template, record);
+        let path = out_dir.join(format!("{stem}-{:0width$}.{ext}", index + 1));
+        fs::write(&path, filled.text)?;
+        if !filled.missing.is_empty() {
+            generated.missing.push((index, filled.missing));
+        }
+        generated.written.push(path);
+        progress(index + 1, records.len());
+    }
+    Ok(generated)
+}
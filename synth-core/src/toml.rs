@@ -0,0 +1,255 @@
+//! The subset of TOML that configuration files need, parsed into the same
+//! [`Value`] tree as JSON: `[table]` headers and `key = value` pairs
+//! (dotted names allowed), basic and literal strings, numbers, booleans,
+//! arrays of those (over several lines if need be) and `#` comments.
+//! Inline tables, arrays of tables, dates and multi-line strings are
+//! rejected.
+
+use crate::error::{Error, Result};
+use crate::json::Value;
+
+struct Parser<'a> {
+    rest: &'a str,
+    line: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> Error {
+        Error::Parse(format!("TOML line {}: {message}", self.line))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest.chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.rest = &self.rest[c.len_utf8()..];
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    /// Skip spaces and tabs, and with `lines` also newlines and comments.
+    fn skip(&mut self, lines: bool) {
+        while let Some(c) = self.peek() {
+            match c {
+                ' ' | '\t' => {}
+                '\r' | '\n' if lines => {}
+                '#' if lines => {
+                    while self.peek().is_some_and(|c| c != '\n') {
+                        self.bump();
+                    }
+                    continue;
+                }
+                _ => return,
+            }
+            self.bump();
+        }
+    }
+
+    /// After a header or value: optional comment, then a newline or the end.
+    fn end_of_line(&mut self) -> Result<()> {
+        self.skip(false);
+        if self.peek() == Some('#') {
+            while self.peek().is_some_and(|c| c != '\n') {
+                self.bump();
+            }
+        }
+        match self.bump() {
+            None | Some('\n') => Ok(()),
+            Some('\r') if self.bump() == Some('\n') => Ok(()),
+            _ => Err(self.error("expected the end of the line")),
+        }
+    }
+
+    /// `a.b."c d"` → `["a", "b", "c d"]`.
+    fn key(&mut self) -> Result<Vec<String>> {
+        let mut parts = Vec::new();
+        loop {
+            self.skip(false);
+            let part = match self.peek() {
+                Some('"') => self.basic_string()?,
+                Some('\'') => self.literal_string()?,
+                _ => {
+                    let len = self
+                        .rest
+                        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+                        .unwrap_or(self.rest.len());
+                    if len == 0 {
+                        return Err(self.error("expected a key"));
+                    }
+                    let part = self.rest[..len].to_string();
+                    self.rest = &self.rest[len..];
+                    part
+                }
+            };
+            parts.push(part);
+            self.skip(false);
+            if self.peek() != Some('.') {
+                return Ok(parts);
+            }
+            self.bump();
+        }
+    }
+
+    fn basic_string(&mut self) -> Result<String> {
+        self.bump();
+        let mut out = String::new();
+        loop {
+            match self.bump() {
+                Some('"') => return Ok(out),
+                Some('\\') => {
+                    let c = match self.bump() {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some(u @ ('u' | 'U')) => {
+                            let len = if u == 'u' { 4 } else { 8 };
+                            let hex = self.rest.get(..len).unwrap_or("");
+                            let c = u32::from_str_radix(hex, 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| self.error("bad unicode escape"))?;
+                            self.rest = &self.rest[len..];
+                            c
+                        }
+                        _ => return Err(self.error("bad escape")),
+                    };
+                    out.push(c);
+                }
+                Some('\n') | None => return Err(self.error("unterminated string")),
+                Some(c) => out.push(c),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String> {
+        self.bump();
+        let end = self
+            .rest
+            .find(['\'', '\n'])
+            .filter(|&end| self.rest[end..].starts_with('\''))
+            .ok_or_else(|| self.error("unterminated string"))?;
+        let text = self.rest[..end].to_string();
+        self.rest = &self.rest[end + 1..];
+        Ok(text)
+    }
+
+    fn value(&mut self) -> Result<Value> {
+        match self.peek() {
+            Some('"') => {
+                if self.rest.starts_with("\"\"\"") {
+                    return Err(self.error("multi-line strings are not supported"));
+                }
+                Ok(Value::String(self.basic_string()?))
+            }
+            Some('\'') => Ok(Value::String(self.literal_string()?)),
+            Some('[') => {
+                self.bump();
+                let mut items = Vec::new();
+                loop {
+                    self.skip(true);
+                    if self.peek() == Some(']') {
+                        self.bump();
+                        return Ok(Value::Array(items));
+                    }
+                    items.push(self.value()?);
+                    self.skip(true);
+                    match self.bump() {
+                        Some(',') => {}
+                        Some(']') => return Ok(Value::Array(items)),
+                        _ => return Err(self.error("expected ',' or ']' in array")),
+                    }
+                }
+            }
+            Some('{') => Err(self.error("inline tables are not supported")),
+            _ => {
+                let len = self
+                    .rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || "+-._".contains(c)))
+                    .unwrap_or(self.rest.len());
+                let word = &self.rest[..len];
+                let value = match word {
+                    "true" => Value::Bool(true),
+                    "false" => Value::Bool(false),
+                    _ => word
+                        .replace('_', "")
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|n| n.is_finite())
+                        .map(Value::Number)
+                        .ok_or_else(|| self.error(&format!("unsupported value '{word}'")))?,
+                };
+                self.rest = &self.rest[len..];
+                Ok(value)
+            }
+        }
+    }
+}
+
+/// The pairs of the table at `path`, creating tables on the way.
+fn table<'v>(
+    root: &'v mut Vec<(String, Value)>,
+    path: &[String],
+) -> std::result::Result<&'v mut Vec<(String, Value)>, String> {
+    let Some((first, rest)) = path.split_first() else {
+        return Ok(root);
+    };
+    let index = match root.iter().position(|(key, _)| key == first) {
+        Some(index) => index,
+        None => {
+            root.push((first.clone(), Value::Object(Vec::new())));
+            root.len() - 1
+        }
+    };
+    match &mut root[index].1 {
+        Value::Object(pairs) => table(pairs, rest),
+        _ => Err(format!("'{first}' is not a table")),
+    }
+}
+
+pub fn parse(text: &str) -> Result<Value> {
+    let mut parser = Parser {
+        rest: text.strip_prefix('\u{feff}').unwrap_or(text),
+        line: 1,
+    };
+    let mut root = Vec::new();
+    let mut current: Vec<String> = Vec::new();
+    loop {
+        parser.skip(true);
+        let Some(c) = parser.peek() else {
+            return Ok(Value::Object(root));
+        };
+        if c == '[' {
+            parser.bump();
+            if parser.peek() == Some('[') {
+                return Err(parser.error("arrays of tables are not supported"));
+            }
+            current = parser.key()?;
+            if parser.bump() != Some(']') {
+                return Err(parser.error("expected ']'"));
+            }
+            table(&mut root, &current).map_err(|e| parser.error(&e))?;
+        } else {
+            let mut key = parser.key()?;
+            parser.skip(false);
+            if parser.bump() != Some('=') {
+                return Err(parser.error("expected '='"));
+            }
+            parser.skip(false);
+            let value = parser.value()?;
+            let name = key.pop().unwrap_or_default();
+            let path: Vec<String> = current.iter().cloned().chain(key).collect();
+            let pairs = table(&mut root, &path).map_err(|e| parser.error(&e))?;
+            if pairs.iter().any(|(existing, _)| *existing == name) {
+                return Err(parser.error(&format!("duplicate key '{name}'")));
+            }
+            pairs.push((name, value));
+        }
+        parser.end_of_line()?;
+    }
+}
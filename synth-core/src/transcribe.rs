@@ -0,0 +1,206 @@
+//! Recorded audio as text, through a local whisper.cpp build
+//! (`whisper-cli`) with the `transcribe` feature, so meetings go through
+//! the same summarize and ask pipelines as documents.
+//!
+//! whisper.cpp reads WAV, MP3, FLAC and Ogg itself; anything else (m4a,
+//! webm, ...) is converted to 16 kHz mono WAV with `ffmpeg` first. Nothing
+//! is transcribed until a model is configured.
+
+use std::fs;
+#[cfg(feature = "transcribe")]
+use std::io::Read;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "transcribe")]
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+#[cfg(feature = "transcribe")]
+use crate::backend::wait_with_timeout;
+use crate::error::{Error, Result};
+use crate::json::Value;
+use crate::policy;
+#[cfg(feature = "transcribe")]
+use crate::shutdown::Process;
+use crate::telemetry;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    /// The ggml model file, e.g. `ggml-base.en.bin`.
+    pub model: PathBuf,
+    pub command: String,
+    pub ffmpeg: String,
+    /// The spoken language, e.g. "en"; whisper detects it when unset.
+    pub language: Option<String>,
+    pub timeout: Option<Duration>,
+}
+
+impl Config {
+    /// Read `{"model": "/path/ggml-base.en.bin", "command": "whisper-cli",
+    /// "ffmpeg": "ffmpeg", "language": "en", "timeout_secs": 600}`; only
+    /// `model` is required.
+    pub fn from_json(value: &Value) -> Result<Self> {
+        let text = |key| value.get(key).and_then(Value::as_str);
+        Ok(Config {
+            model: text("model")
+                .ok_or_else(|| Error::Parse("transcription config needs a model".into()))?
+                .into(),
+            command: text("command").unwrap_or("whisper-cli").to_string(),
+            ffmpeg: text("ffmpeg").unwrap_or("ffmpeg").to_string(),
+            language: text("language").map(str::to_string),
+            timeout: value
+                .get("timeout_secs")
+                .and_then(Value::as_f64)
+                .filter(|secs| *secs > 0.0)
+                .map(Duration::from_secs_f64),
+        })
+    }
+}
+
+static CONFIG: Mutex<Option<Config>> = Mutex::new(None);
+static SCRATCH: AtomicU64 = AtomicU64::new(0);
+
+fn lock() -> MutexGuard<'static, Option<Config>> {
+    CONFIG.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Set (or with `None`, clear) the model and tools used.
+pub fn configure(config: Option<Config>) {
+    *lock() = config;
+}
+
+/// Whether a file name looks like recorded audio.
+pub fn is_audio(path: &Path) -> bool {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase);
+    matches!(
+        ext.as_deref(),
+        Some("wav" | "mp3" | "m4a" | "aac" | "flac" | "ogg" | "oga" | "opus" | "webm")
+    )
+}
+
+/// The transcript of the recording at `path`, one line per segment.
+pub fn transcribe(path: &Path) -> Result<String> {
+    telemetry::measure("transcribe", || {
+        policy::check_read(path)?;
+        transcribe_file(path)
+    })
+}
+
+/// [`transcribe`] without the policy check and telemetry, for extraction,
+/// which has done both.
+#[cfg(feature = "transcribe")]
+pub(crate) fn transcribe_file(path: &Path) -> Result<String> {
+    let config = lock()
+        .clone()
+        .ok_or_else(|| Error::Unsupported("no transcription model is configured".into()))?;
+    if !config.model.is_file() {
+        return Err(Error::Backend(format!(
+            "model not found: {}",
+            config.model.display()
+        )));
+    }
+    let mut head = Vec::new();
+    fs::File::open(path)?.take(12).read_to_end(&mut head)?;
+    if whisper_reads(&head) {
+        return whisper(&config, path);
+    }
+    let wav = scratch_path();
+    let result = convert(&config, path, &wav).and_then(|()| whisper(&config, &wav));
+    let _ = fs::remove_file(&wav);
+    result
+}
+
+#[cfg(not(feature = "transcribe"))]
+pub(crate) fn transcribe_file(_path: &Path) -> Result<String> {
+    Err(Error::Unsupported(
+        "audio transcription needs the transcribe feature".into(),
+    ))
+}
+
+/// [`transcribe_file`] for a recording already in memory.
+pub(crate) fn transcribe_bytes(bytes: &[u8]) -> Result<String> {
+    let path = scratch_path();
+    fs::write(&path, bytes)?;
+    let result = transcribe_file(&path);
+    let _ = fs::remove_file(&path);
+    result
+}
+
+fn scratch_path() -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "synth-audio-{}-{}.wav",
+        std::process::id(),
+        SCRATCH.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+/// Whether whisper.cpp decodes a file starting with `head` on its own:
+/// RIFF/WAVE, MP3 (with or without an ID3 tag), FLAC or Ogg.
+#[cfg(feature = "transcribe")]
+fn whisper_reads(head: &[u8]) -> bool {
+    (head.starts_with(b"RIFF") && head.get(8..12) == Some(b"WAVE"))
+        || head.starts_with(b"ID3")
+        || (head.len() >= 2 && head[0] == 0xff && head[1] & 0xe0 == 0xe0)
+        || head.starts_with(b"fLaC")
+        || head.starts_with(b"OggS")
+}
+
+#[cfg(feature = "transcribe")]
+fn convert(config: &Config, input: &Path, wav: &Path) -> Result<()> {
+    let child = Process::spawn(
+        Command::new(&config.ffmpeg)
+            .args(["-nostdin", "-loglevel", "error", "-y", "-i"])
+            .arg(input)
+            .args(["-ar", "16000", "-ac", "1", "-c:a", "pcm_s16le"])
+            .arg(wav)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped()),
+    )?;
+    let (success, _) = wait_with_timeout(child, config.timeout)?;
+    if !success {
+        return Err(Error::Unsupported(format!(
+            "{} could not convert {}",
+            config.ffmpeg,
+            input.display()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "transcribe")]
+fn whisper(config: &Config, audio: &Path) -> Result<String> {
+    let mut command = Command::new(&config.command);
+    command
+        .arg("-m")
+        .arg(&config.model)
+        .arg("-f")
+        .arg(audio)
+        .args(["--no-timestamps", "--no-prints"]);
+    if let Some(language) = &config.language {
+        command.args(["-l", language]);
+    }
+    let child = Process::spawn(
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped()),
+    )?;
+    let (success, stdout) = wait_with_timeout(child, config.timeout)?;
+    if !success {
+        return Err(Error::Backend(format!(
+            "{} exited with an error",
+            config.command
+        )));
+    }
+    let lines: Vec<&str> = stdout
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    Ok(lines.join("\n") + "\n")
+}
@@ -0,0 +1,170 @@
+//! Translation of documents of any length, chunk by chunk.
+//!
+//! Chunks break at paragraphs and sentences where they can, and each is
+//! translated on its own with the whitespace that followed it kept, so the
+//! translation keeps the document's paragraphs. With a
+//! [glossary](crate::glossary) selected, the entries whose term occurs in
+//! a chunk are added to its prompt and its translation is checked against
+//! them, sent back once with the violations, and the translation fails
+//! with [`Error::Terminology`] if the reply still breaks them.
+
+use std::path::Path;
+
+use crate::chat::ChatOptions;
+use crate::chunk::chunk_text;
+use crate::error::{Error, Result};
+use crate::extract::extract_text;
+use crate::glossary;
+use crate::json::Value;
+use crate::progress::{Progress, Stage};
+
+#[derive(Debug, Clone)]
+pub struct TranslateOptions {
+    /// The language to translate into, e.g. "German" or "de".
+    pub target: String,
+    /// The language translated from, when the backend should not guess.
+    pub source: Option<String>,
+    /// Characters per part sent to the backend.
+    pub max_chunk_chars: usize,
+    /// Name of a [defined](glossary::define) glossary each part's
+    /// translation must follow.
+    pub glossary: Option<String>,
+    /// Options for each call. A glossary belongs in `glossary`, which
+    /// checks each part against its own terms.
+    pub chat: ChatOptions,
+}
+
+impl TranslateOptions {
+    pub fn new(target: &str) -> Self {
+        TranslateOptions {
+            target: target.to_string(),
+            source: None,
+            max_chunk_chars: 6_000,
+            glossary: None,
+            chat: ChatOptions::default(),
+        }
+    }
+
+    /// Read `{"target": "German", "source": "English", "max_chunk_chars":
+    /// n, "glossary": "contracts-de", "backend": "profile"}`; only `target`
+    /// is required.
+    pub fn from_json(value: &Value) -> Result<Self> {
+        let text = |key| {
+            value
+                .get(key)
+                .and_then(Value::as_str)
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+        };
+        let target =
+            text("target").ok_or_else(|| Error::Parse("translation needs a \"target\"".into()))?;
+        let mut chat = ChatOptions::from_json(value);
+        let mut options = TranslateOptions {
+            source: text("source").map(String::from),
+            glossary: chat.glossary.take(),
+            chat,
+            ..TranslateOptions::new(target)
+        };
+        if let Some(n) = value.get("max_chunk_chars").and_then(Value::as_f64) {
+            options.max_chunk_chars = (n as usize).max(500);
+        }
+        Ok(options)
+    }
+
+    fn prompt(&self, text: &str) -> String {
+        let from = match &self.source {
+            Some(source) => format!(" from {source}"),
+            None => String::new(),
+        };
+        format!(
+            "Translate the text below{from} into {}. Keep its formatting, names, \
+             numbers and line breaks. Respond with only the translation.\n\nText:\n{text}",
+            self.target
+        )
+    }
+}
+
+/// Translate `text` through `chat`. `progress` hears about chunking and
+/// each part.
+pub fn translate(
+    text: &str,
+    options: &TranslateOptions,
+    chat: impl FnMut(&str) -> Result<String>,
+    progress: impl FnMut(&Progress),
+) -> Result<String> {
+    translate_from(text, options, chat, progress, 0.0)
+}
+
+/// Extract the document at `path` and translate it.
+pub fn translate_document(
+    path: &Path,
+    options: &TranslateOptions,
+    chat: impl FnMut(&str) -> Result<String>,
+    mut progress: impl FnMut(&Progress),
+) -> Result<String> {
+    let name = path.display().to_string();
+    progress(&Progress {
+        stage: Stage::Extracting,
+        completed: 0,
+        total: 1,
+        percent: 0.0,
+        item: Some(&name),
+    });
+    let text = extract_text(path)?;
+    translate_from(&text, options, chat, progress, 10.0)
+}
+
+fn translate_from(
+    text: &str,
+    options: &TranslateOptions,
+    mut chat: impl FnMut(&str) -> Result<String>,
+    mut progress: impl FnMut(&Progress),
+    start: f64,
+) -> Result<String> {
+    progress(&Progress {
+        stage: Stage::Chunking,
+        completed: 0,
+        total: 1,
+        percent: start,
+        item: None,
+    });
+    let glossary = options
+        .glossary
+        .as_deref()
+        .map(glossary::glossary)
+        .transpose()?;
+    let parts = chunk_text(text, options.max_chunk_chars);
+    let mut translation = String::new();
+    for (index, part) in parts.iter().enumerate() {
+        let label = format!("part {} of {}", index + 1, parts.len());
+        progress(&Progress {
+            stage: Stage::Generating,
+            completed: index,
+            total: parts.len(),
+            percent: start + (100.0 - start) * index as f64 / parts.len() as f64,
+            item: Some(&label),
+        });
+        let body = part.text.trim_end();
+        if body.trim().is_empty() {
+            translation.push_str(&part.text);
+            continue;
+        }
+        let prompt = options.prompt(body);
+        let reply = match &glossary {
+            Some(glossary) => {
+                glossary::enforce_for(glossary, body, &prompt, glossary::RETRIES, &mut chat)?
+            }
+            None => chat(&prompt)?,
+        };
+        translation.push_str(reply.trim());
+        translation.push_str(&part.text[body.len()..]);
+    }
+    progress(&Progress {
+        stage: Stage::Generating,
+        completed: parts.len(),
+        total: parts.len(),
+        percent: 100.0,
+        item: None,
+    });
+    Ok(translation)
+}
@@ -0,0 +1,126 @@
+//! Per-user tags and usage counters, so one deployment serving many end
+//! users can keep their usage and history apart.
+//!
+//! A chat call carries the caller's optional `tenant_id` and `user_id`
+//! from [`ChatOptions`](crate::chat::ChatOptions). The tags are written to
+//! the audit log, decide which long-term memories a call may recall, let
+//! sessions be listed per user, and key the counters kept here: calls,
+//! failures and estimated tokens (four characters a token). Counters live
+//! in memory for the life of the process.
+
+use std::collections::BTreeMap;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::Error;
+use crate::json::Value;
+use crate::policy::estimate_tokens;
+
+/// Who a call, session or memory belongs to. Both tags are opaque strings
+/// chosen by the host.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Tags {
+    pub tenant_id: Option<String>,
+    pub user_id: Option<String>,
+}
+
+impl Tags {
+    pub fn new(tenant_id: Option<&str>, user_id: Option<&str>) -> Tags {
+        Tags {
+            tenant_id: tenant_id.map(String::from),
+            user_id: user_id.map(String::from),
+        }
+    }
+
+    /// Read `{"tenant_id": "...", "user_id": "..."}`; both are optional.
+    pub fn from_json(value: &Value) -> Tags {
+        let text = |key| value.get(key).and_then(Value::as_str);
+        Tags::new(text("tenant_id"), text("user_id"))
+    }
+
+    /// The set tags as JSON pairs, for adding to a record.
+    pub fn pairs(&self) -> Vec<(&'static str, Value)> {
+        let mut pairs = Vec::new();
+        if let Some(tenant_id) = &self.tenant_id {
+            pairs.push(("tenant_id", Value::from(tenant_id.as_str())));
+        }
+        if let Some(user_id) = &self.user_id {
+            pairs.push(("user_id", Value::from(user_id.as_str())));
+        }
+        pairs
+    }
+
+    /// Whether every tag set here is the same in `other`. Used as a
+    /// filter, unset tags match anything; a memory's tags limit who may
+    /// recall it in the same way.
+    pub fn matches(&self, other: &Tags) -> bool {
+        let same =
+            |mine: &Option<String>, theirs: &Option<String>| mine.is_none() || mine == theirs;
+        same(&self.tenant_id, &other.tenant_id) && same(&self.user_id, &other.user_id)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Usage {
+    pub calls: u64,
+    pub failures: u64,
+    pub prompt_tokens: u64,
+    pub reply_tokens: u64,
+    /// Seconds since the Unix epoch of the latest call.
+    pub last_call: u64,
+}
+
+fn lock() -> MutexGuard<'static, BTreeMap<Tags, Usage>> {
+    static COUNTERS: Mutex<BTreeMap<Tags, Usage>> = Mutex::new(BTreeMap::new());
+    COUNTERS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Count one chat call against `tags`.
+pub fn record(tags: &Tags, prompt: &str, outcome: std::result::Result<&str, &Error>) {
+    let mut counters = lock();
+    let usage = counters.entry(tags.clone()).or_default();
+    usage.calls += 1;
+    usage.prompt_tokens += estimate_tokens(prompt);
+    match outcome {
+        Ok(reply) => usage.reply_tokens += estimate_tokens(reply),
+        Err(_) => usage.failures += 1,
+    }
+    usage.last_call = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+}
+
+/// The counters for each tenant and user that `filter` matches.
+pub fn report(filter: &Tags) -> Vec<(Tags, Usage)> {
+    lock()
+        .iter()
+        .filter(|(tags, _)| filter.matches(tags))
+        .map(|(tags, usage)| (tags.clone(), *usage))
+        .collect()
+}
+
+/// `[{"tenant_id", "user_id", "calls", "failures", "prompt_tokens",
+/// "reply_tokens", "last_call"}]`, with unset tags left out.
+pub fn report_json(filter: &Tags) -> Value {
+    Value::Array(
+        report(filter)
+            .into_iter()
+            .map(|(tags, usage)| {
+                let mut pairs = tags.pairs();
+                pairs.extend([
+                    ("calls", Value::from(usage.calls as f64)),
+                    ("failures", Value::from(usage.failures as f64)),
+                    ("prompt_tokens", Value::from(usage.prompt_tokens as f64)),
+                    ("reply_tokens", Value::from(usage.reply_tokens as f64)),
+                    ("last_call", Value::from(usage.last_call as f64)),
+                ]);
+                Value::object(pairs)
+            })
+            .collect(),
+    )
+}
+
+/// Forget the counters that `filter` matches.
+pub fn reset(filter: &Tags) {
+    lock().retain(|tags, _| !filter.matches(tags));
+}
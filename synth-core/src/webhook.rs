@@ -0,0 +1,243 @@
+//! Webhook notifications, so downstream systems learn when work finishes.
+//!
+//! Once configured, finished jobs (and `synth --jsonrpc` file watches)
+//! POST `{"event": "job.done", "time": <unix seconds>, "data": {...}}`
+//! to the webhook URL, with these headers:
+//!
+//! - `X-Synth-Event`: the event name
+//! - `X-Synth-Delivery`: an id unique to the delivery, the same on retries
+//! - `X-Synth-Timestamp`: unix seconds when the delivery was first made
+//! - `X-Synth-Signature`: with a secret, `sha256=` and the hex HMAC-SHA256
+//!   of `"<timestamp>.<body>"`; see [`signature`]
+//!
+//! Connection errors, 429 and 5xx responses are retried with exponential
+//! backoff. Deliveries run on their own threads and never hold up the work
+//! that produced them; outcomes are reported to telemetry as `webhook`.
+
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::backend::Secret;
+use crate::error::{Error, Result};
+use crate::hash::{hex, hmac_sha256};
+use crate::http::{self, RequestOptions, Url};
+use crate::json::Value;
+use crate::shutdown::{self, Process};
+use crate::telemetry;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Webhook {
+    /// `http://` or `https://` endpoint.
+    pub url: String,
+    pub secret: Option<Secret>,
+    /// Event names or prefixes (`"job"` covers `"job.done"`) to send;
+    /// empty sends every event.
+    pub events: Vec<String>,
+    /// Attempts after the first.
+    pub retries: u32,
+    /// Wait before the first retry; doubled for each one after.
+    pub backoff: Duration,
+    pub timeout: Duration,
+}
+
+impl Webhook {
+    pub fn new(url: &str) -> Webhook {
+        Webhook {
+            url: url.to_string(),
+            secret: None,
+            events: Vec::new(),
+            retries: 3,
+            backoff: Duration::from_secs(1),
+            timeout: Duration::from_secs(10),
+        }
+    }
+
+    /// Parse `{"url": "https://...", "secret_env": "SYNTH_WEBHOOK_SECRET",
+    /// "events": ["job"], "retries": 3, "backoff_ms": 1000,
+    /// "timeout_secs": 10}`. The secret may also be given as `"secret"` or,
+    /// for the host's secret provider, `"secret_host"`.
+    pub fn from_json(value: &Value) -> Result<Webhook> {
+        let text = |key| value.get(key).and_then(Value::as_str).map(String::from);
+        let number = |key| value.get(key).and_then(Value::as_f64);
+        let url = text("url").ok_or_else(|| Error::Parse("webhook needs a \"url\"".into()))?;
+        if !(url.starts_with("http://") || url.starts_with("https://")) {
+            return Err(Error::Unsupported(format!(
+                "webhook URLs must be http:// or https://: {url}"
+            )));
+        }
+        let defaults = Webhook::new(&url);
+        Ok(Webhook {
+            secret: text("secret")
+                .map(Secret::Literal)
+                .or_else(|| text("secret_env").map(Secret::Env))
+                .or_else(|| text("secret_host").map(Secret::Host)),
+            events: match value.get("events") {
+                Some(Value::Array(items)) => items
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(String::from)
+                    .collect(),
+                _ => Vec::new(),
+            },
+            retries: number("retries").map_or(defaults.retries, |n| n as u32),
+            backoff: number("backoff_ms")
+                .map_or(defaults.backoff, |n| Duration::from_millis(n as u64)),
+            timeout: number("timeout_secs").map_or(defaults.timeout, |n| {
+                Duration::from_secs_f64(n.clamp(0.0, 3600.0))
+            }),
+            ..defaults
+        })
+    }
+
+    pub fn wants(&self, event: &str) -> bool {
+        self.events.is_empty()
+            || self.events.iter().any(|wanted| {
+                event == wanted
+                    || event
+                        .strip_prefix(wanted.as_str())
+                        .is_some_and(|rest| rest.starts_with('.'))
+            })
+    }
+}
+
+static WEBHOOK: RwLock<Option<Webhook>> = RwLock::new(None);
+static DELIVERIES: AtomicU64 = AtomicU64::new(0);
+
+/// Install (or with `None`, remove) the webhook.
+pub fn configure(webhook: Option<Webhook>) {
+    *WEBHOOK.write().unwrap_or_else(|e| e.into_inner()) = webhook;
+}
+
+pub fn configured() -> Option<Webhook> {
+    WEBHOOK.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// The `X-Synth-Signature` value for a delivery. Receivers recompute it
+/// and should also reject old timestamps, so captured deliveries cannot be
+/// replayed.
+pub fn signature(secret: &str, timestamp: u64, body: &str) -> String {
+    let message = format!("{timestamp}.{body}");
+    format!(
+        "sha256={}",
+        hex(&hmac_sha256(secret.as_bytes(), message.as_bytes()))
+    )
+}
+
+/// Send `event` to the configured webhook in the background, if there is
+/// one and it wants the event.
+pub fn notify(event: &str, data: Value) {
+    let Some(webhook) = configured().filter(|webhook| webhook.wants(event)) else {
+        return;
+    };
+    let event = event.to_string();
+    shutdown::spawn_thread(move || {
+        let _ = telemetry::measure("webhook", || deliver(&webhook, &event, &data));
+    });
+}
+
+/// Send one event now, retrying as configured. Returns the final status.
+pub fn deliver(webhook: &Webhook, event: &str, data: &Value) -> Result<u16> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let body = Value::object([
+        ("event", Value::from(event)),
+        ("time", Value::from(timestamp as f64)),
+        ("data", data.clone()),
+    ])
+    .to_string();
+    let delivery = format!(
+        "{timestamp}-{}-{}",
+        std::process::id(),
+        DELIVERIES.fetch_add(1, Ordering::Relaxed)
+    );
+    let mut headers = vec![
+        ("X-Synth-Event".to_string(), event.to_string()),
+        ("X-Synth-Delivery".to_string(), delivery),
+        ("X-Synth-Timestamp".to_string(), timestamp.to_string()),
+    ];
+    if let Some(secret) = &webhook.secret {
+        let secret = secret
+            .resolve()
+            .ok_or_else(|| Error::Backend("webhook secret is not available".into()))?;
+        headers.push((
+            "X-Synth-Signature".to_string(),
+            signature(&secret, timestamp, &body),
+        ));
+    }
+    let mut wait = webhook.backoff;
+    let mut attempt = 0;
+    loop {
+        let outcome = post(webhook, &body, &headers);
+        let retry = match &outcome {
+            Ok(status) => *status == 429 || *status >= 500,
+            Err(_) => true,
+        };
+        if !retry || attempt >= webhook.retries || shutdown::stopping() {
+            return match outcome? {
+                status @ 200..=299 => Ok(status),
+                status => Err(Error::Backend(format!(
+                    "webhook answered {status} to {event}"
+                ))),
+            };
+        }
+        attempt += 1;
+        thread::sleep(wait);
+        wait *= 2;
+    }
+}
+
+fn post(webhook: &Webhook, body: &str, headers: &[(String, String)]) -> Result<u16> {
+    if webhook.url.starts_with("http://") {
+        let options = RequestOptions {
+            headers: headers.to_vec(),
+            proxy: None,
+            timeout: Some(webhook.timeout),
+        };
+        return Ok(http::post_json(&Url::parse(&webhook.url)?, body, &options)?.status);
+    }
+    // The built-in client has no TLS; curl handles https.
+    let mut command = Command::new("curl");
+    command
+        .args(["--silent", "--show-error", "--proto", "=https"])
+        .args(["--output", "/dev/null", "--write-out", "%{http_code}"])
+        .arg("--max-time")
+        .arg(webhook.timeout.as_secs().max(1).to_string())
+        .args(["-H", "Content-Type: application/json"]);
+    for (name, value) in headers {
+        command.arg("-H").arg(format!("{name}: {value}"));
+    }
+    let child = Process::spawn(
+        command
+            .args(["--data-binary", "@-", "--"])
+            .arg(&webhook.url)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped()),
+    )?;
+    if let Some(mut stdin) = child.take_stdin() {
+        stdin.write_all(body.as_bytes())?;
+    }
+    let mut status = String::new();
+    let mut message = String::new();
+    if let Some(mut stdout) = child.take_stdout() {
+        let _ = stdout.read_to_string(&mut status);
+    }
+    if let Some(mut stderr) = child.take_stderr() {
+        let _ = stderr.read_to_string(&mut message);
+    }
+    if !child.wait()?.success() {
+        return Err(Error::Backend(format!(
+            "webhook delivery failed: {}",
+            message.trim()
+        )));
+    }
+    status
+        .trim()
+        .parse()
+        .map_err(|_| Error::Backend(format!("webhook delivery failed: status {status}")))
+}
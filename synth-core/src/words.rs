@@ -0,0 +1,225 @@
+//! Word tokenization shared by the local text-analysis features.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Common English function words ignored when scoring content terms.
+pub const STOPWORDS: &[&str] = &[
+    "a",
+    "about",
+    "above",
+    "after",
+    "again",
+    "against",
+    "all",
+    "also",
+    "am",
+    "an",
+    "and",
+    "any",
+    "are",
+    "as",
+    "at",
+    "be",
+    "because",
+    "been",
+    "before",
+    "being",
+    "below",
+    "between",
+    "both",
+    "but",
+    "by",
+    "can",
+    "could",
+    "did",
+    "do",
+    "does",
+    "doing",
+    "down",
+    "during",
+    "each",
+    "either",
+    "etc",
+    "few",
+    "for",
+    "from",
+    "further",
+    "had",
+    "has",
+    "have",
+    "having",
+    "he",
+    "her",
+    "here",
+    "hers",
+    "herself",
+    "him",
+    "himself",
+    "his",
+    "how",
+    "i",
+    "if",
+    "in",
+    "into",
+    "is",
+    "it",
+    "its",
+    "itself",
+    "just",
+    "may",
+    "me",
+    "might",
+    "more",
+    "most",
+    "must",
+    "my",
+    "myself",
+    "no",
+    "nor",
+    "not",
+    "now",
+    "of",
+    "off",
+    "on",
+    "once",
+    "only",
+    "or",
+    "other",
+    "our",
+    "ours",
+    "ourselves",
+    "out",
+    "over",
+    "own",
+    "per",
+    "same",
+    "shall",
+    "she",
+    "should",
+    "so",
+    "some",
+    "such",
+    "than",
+    "that",
+    "the",
+    "their",
+    "theirs",
+    "them",
+    "themselves",
+    "then",
+    "there",
+    "these",
+    "they",
+    "this",
+    "those",
+    "through",
+    "to",
+    "too",
+    "under",
+    "until",
+    "up",
+    "upon",
+    "us",
+    "very",
+    "was",
+    "we",
+    "were",
+    "what",
+    "when",
+    "where",
+    "which",
+    "while",
+    "who",
+    "whom",
+    "why",
+    "will",
+    "with",
+    "within",
+    "without",
+    "would",
+    "you",
+    "your",
+    "yours",
+    "yourself",
+    "yourselves",
+];
+
+pub fn is_stopword(word: &str) -> bool {
+    STOPWORDS.binary_search(&word).is_ok()
+}
+
+/// Lowercased words: runs of letters, digits and inner apostrophes or
+/// hyphens, with their character offsets in `text`.
+pub fn words_with_offsets(text: &str) -> Vec<(usize, String)> {
+    word_spans(text)
+        .into_iter()
+        .map(|(span, word)| (span.start, word))
+        .collect()
+}
+
+/// Lowercased words with the range of characters each spans in `text`.
+pub fn word_spans(text: &str) -> Vec<(Range<usize>, String)> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut start = 0;
+    let chars: Vec<char> = text.chars().collect();
+    for (i, &c) in chars.iter().enumerate() {
+        let joiner = matches!(c, '\'' | '’' | '-')
+            && !current.is_empty()
+            && chars.get(i + 1).is_some_and(|n| n.is_alphanumeric());
+        if c.is_alphanumeric() || joiner {
+            if current.is_empty() {
+                start = i;
+            }
+            current.extend(c.to_lowercase());
+        } else if !current.is_empty() {
+            words.push((start..i, std::mem::take(&mut current)));
+        }
+    }
+    if !current.is_empty() {
+        words.push((start..chars.len(), current));
+    }
+    words
+}
+
+/// Lowercased words of `text`, in order.
+pub fn words(text: &str) -> Vec<String> {
+    words_with_offsets(text)
+        .into_iter()
+        .map(|(_, w)| w)
+        .collect()
+}
+
+/// Words that carry content: not stopwords, not bare numbers, 2+ chars.
+pub fn content_words(text: &str) -> Vec<String> {
+    words(text)
+        .into_iter()
+        .filter(|w| is_content_word(w))
+        .collect()
+}
+
+/// Not a stopword, not a bare number, and at least two characters.
+pub fn is_content_word(word: &str) -> bool {
+    word.chars().count() > 1 && !is_stopword(word) && !word.chars().all(|c| c.is_numeric())
+}
+
+/// Content-word counts of `text`, for [`cosine`] similarity.
+pub fn term_counts(text: &str) -> HashMap<String, f64> {
+    let mut counts = HashMap::new();
+    for word in content_words(text) {
+        *counts.entry(word).or_insert(0.0) += 1.0;
+    }
+    counts
+}
+
+pub fn cosine(a: &HashMap<String, f64>, b: &HashMap<String, f64>) -> f64 {
+    let dot: f64 = a.iter().filter_map(|(k, x)| b.get(k).map(|y| x * y)).sum();
+    let norm = |m: &HashMap<String, f64>| m.values().map(|v| v * v).sum::<f64>().sqrt();
+    let denominator = norm(a) * norm(b);
+    if denominator == 0.0 {
+        0.0
+    } else {
+        dot / denominator
+    }
+}
@@ -0,0 +1,288 @@
+//! A small non-validating XML reader: elements, attributes and text, with
+//! the byte range each element spans so callers can splice edits into the
+//! original text.
+//!
+//! Comments, processing instructions and the doctype are skipped.
+//! Namespace prefixes are kept as written (`w:p`), which is how Office
+//! documents are matched: their prefixes are fixed by convention.
+
+use std::ops::Range;
+
+use crate::error::{Error, Result};
+
+/// Deeper documents are rejected: walking and dropping the tree recurse.
+const MAX_DEPTH: usize = 256;
+
+#[derive(Debug, Clone)]
+pub struct Element {
+    pub name: String,
+    pub attrs: Vec<(String, String)>,
+    pub children: Vec<Node>,
+    /// From the `<` of the start tag to past the end tag.
+    pub span: Range<usize>,
+    /// Between the start and end tags; empty for `<a/>`.
+    pub inner: Range<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Node {
+    Element(Element),
+    Text(String),
+}
+
+impl Element {
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Child elements in order.
+    pub fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|child| match child {
+            Node::Element(el) => Some(el),
+            Node::Text(_) => None,
+        })
+    }
+
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.elements().find(|el| el.name == name)
+    }
+
+    /// The first element named `name` at any depth, this one included.
+    pub fn find(&self, name: &str) -> Option<&Element> {
+        if self.name == name {
+            return Some(self);
+        }
+        self.elements().find_map(|el| el.find(name))
+    }
+
+    /// Every element named `name` at any depth, in document order, not
+    /// looking inside matches.
+    pub fn find_all<'a>(&'a self, name: &str, out: &mut Vec<&'a Element>) {
+        for el in self.elements() {
+            if el.name == name {
+                out.push(el);
+            } else {
+                el.find_all(name, out);
+            }
+        }
+    }
+
+    /// All text inside, concatenated.
+    pub fn text(&self) -> String {
+        let mut out = String::new();
+        self.push_text(&mut out);
+        out
+    }
+
+    fn push_text(&self, out: &mut String) {
+        for child in &self.children {
+            match child {
+                Node::Text(text) => out.push_str(text),
+                Node::Element(el) => el.push_text(out),
+            }
+        }
+    }
+}
+
+/// Parse `text`. Text between elements that is only whitespace is kept
+/// when `keep_blank` is set, as document formats need for `xml:space`.
+pub fn parse(text: &str, keep_blank: bool) -> Result<Element> {
+    let mut stack: Vec<Element> = Vec::new();
+    let mut root = None;
+    let mut pos = 0;
+    while pos < text.len() {
+        let rest = &text[pos..];
+        if let Some(after) = rest.strip_prefix("<!--") {
+            pos = text.len() - skip_past(after, "-->")?.len();
+        } else if let Some(after) = rest.strip_prefix("<![CDATA[") {
+            let end = after
+                .find("]]>")
+                .ok_or_else(|| Error::Parse("unterminated CDATA".into()))?;
+            push_text(&mut stack, &after[..end]);
+            pos += 9 + end + 3;
+        } else if let Some(after) = rest.strip_prefix("<?") {
+            pos = text.len() - skip_past(after, "?>")?.len();
+        } else if let Some(after) = rest.strip_prefix("<!") {
+            pos = text.len() - skip_doctype(after)?.len();
+        } else if let Some(after) = rest.strip_prefix("</") {
+            let end = after
+                .find('>')
+                .ok_or_else(|| Error::Parse("unterminated closing tag".into()))?;
+            let name = after[..end].trim();
+            let mut done = stack
+                .pop()
+                .filter(|open| open.name == name)
+                .ok_or_else(|| Error::Parse(format!("unexpected closing tag </{name}>")))?;
+            done.inner.end = pos;
+            pos += 2 + end + 1;
+            done.span.end = pos;
+            attach(&mut stack, &mut root, done);
+        } else if let Some(after) = rest.strip_prefix('<') {
+            let end = tag_end(after).ok_or_else(|| Error::Parse("unterminated tag".into()))?;
+            let (body, self_closing) = match after[..end].strip_suffix('/') {
+                Some(body) => (body, true),
+                None => (&after[..end], false),
+            };
+            let mut element = parse_tag(body)?;
+            let start = pos;
+            pos += 1 + end + 1;
+            element.span = start..pos;
+            element.inner = pos..pos;
+            if self_closing {
+                attach(&mut stack, &mut root, element);
+            } else if stack.len() >= MAX_DEPTH {
+                return Err(Error::Parse(format!(
+                    "nesting too deep in <{}>",
+                    element.name
+                )));
+            } else {
+                stack.push(element);
+            }
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            let decoded = decode_entities(&rest[..end]);
+            if keep_blank || !decoded.trim().is_empty() {
+                push_text(&mut stack, &decoded);
+            }
+            pos += end;
+        }
+    }
+    if let Some(open) = stack.last() {
+        return Err(Error::Parse(format!("unclosed element <{}>", open.name)));
+    }
+    root.ok_or_else(|| Error::Parse("no root element".into()))
+}
+
+fn skip_past<'a>(text: &'a str, marker: &str) -> Result<&'a str> {
+    text.find(marker)
+        .map(|end| &text[end + marker.len()..])
+        .ok_or_else(|| Error::Parse(format!("missing '{marker}'")))
+}
+
+fn skip_doctype(text: &str) -> Result<&str> {
+    let mut brackets = 0usize;
+    for (i, c) in text.char_indices() {
+        match c {
+            '[' => brackets += 1,
+            ']' => brackets = brackets.saturating_sub(1),
+            '>' if brackets == 0 => return Ok(&text[i + 1..]),
+            _ => {}
+        }
+    }
+    Err(Error::Parse("unterminated declaration".into()))
+}
+
+/// Index of the `>` closing a start tag, ignoring any inside quoted values.
+fn tag_end(text: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            (None, '>') => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn parse_tag(body: &str) -> Result<Element> {
+    let body = body.trim();
+    let name_end = body.find(char::is_whitespace).unwrap_or(body.len());
+    let name = &body[..name_end];
+    if name.is_empty() {
+        return Err(Error::Parse("empty tag name".into()));
+    }
+    let mut attrs = Vec::new();
+    let mut rest = body[name_end..].trim_start();
+    while !rest.is_empty() {
+        let eq = rest
+            .find('=')
+            .ok_or_else(|| Error::Parse(format!("malformed attribute in <{name}>")))?;
+        let key = rest[..eq].trim().to_string();
+        let after = rest[eq + 1..].trim_start();
+        let quote = after
+            .chars()
+            .next()
+            .filter(|c| *c == '"' || *c == '\'')
+            .ok_or_else(|| Error::Parse(format!("unquoted attribute in <{name}>")))?;
+        let close = after[1..]
+            .find(quote)
+            .ok_or_else(|| Error::Parse(format!("unterminated attribute in <{name}>")))?;
+        attrs.push((key, decode_entities(&after[1..1 + close])));
+        rest = after[close + 2..].trim_start();
+    }
+    Ok(Element {
+        name: name.to_string(),
+        attrs,
+        children: Vec::new(),
+        span: 0..0,
+        inner: 0..0,
+    })
+}
+
+fn attach(stack: &mut [Element], root: &mut Option<Element>, element: Element) {
+    match stack.last_mut() {
+        Some(parent) => parent.children.push(Node::Element(element)),
+        None => {
+            if root.is_none() {
+                *root = Some(element);
+            }
+        }
+    }
+}
+
+fn push_text(stack: &mut [Element], text: &str) {
+    if let Some(parent) = stack.last_mut() {
+        parent.children.push(Node::Text(text.to_string()));
+    }
+}
+
+/// `text` with the characters XML reserves as entities, for text and
+/// attribute values.
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let decoded = after.find(';').and_then(|end| {
+            let entity = &after[..end];
+            let c = match entity {
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "amp" => Some('&'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => entity
+                    .strip_prefix("#x")
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                    .and_then(char::from_u32),
+            };
+            c.map(|c| (c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
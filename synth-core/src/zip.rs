@@ -0,0 +1,246 @@
+//! Zip archives as Office documents use them: stored and deflated
+//! entries, read fully into memory, and written back with the entries
+//! that did not change copied as they were.
+//!
+//! Zip64, encryption and multi-disk archives are not supported.
+
+use std::fs;
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::hash::crc32;
+use crate::inflate::inflate;
+
+/// No entry is expanded past this, whatever its header claims.
+const MAX_ENTRY: usize = 256 << 20;
+
+const LOCAL: u32 = 0x0403_4b50;
+const CENTRAL: u32 = 0x0201_4b50;
+const END: u32 = 0x0605_4b50;
+
+fn corrupt(what: &str) -> Error {
+    Error::Parse(format!("corrupt zip archive: {what}"))
+}
+
+fn u16_at(data: &[u8], at: usize) -> Result<u16> {
+    data.get(at..at + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| corrupt("truncated"))
+}
+
+fn u32_at(data: &[u8], at: usize) -> Result<u32> {
+    data.get(at..at + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| corrupt("truncated"))
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    name: String,
+    method: u16,
+    crc: u32,
+    compressed: usize,
+    size: usize,
+    /// Where the compressed data starts.
+    data: usize,
+}
+
+/// An archive read fully into memory.
+pub struct Archive {
+    bytes: Vec<u8>,
+    entries: Vec<Entry>,
+}
+
+impl Archive {
+    pub fn open(path: &Path) -> Result<Archive> {
+        Archive::from_bytes(crate::policy::read_file(path)?)
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Archive> {
+        // The end record is in the last 64 KiB (its comment's maximum).
+        let floor = bytes.len().saturating_sub(22 + 0xffff);
+        let end = (floor..bytes.len().saturating_sub(21))
+            .rev()
+            .find(|&at| u32_at(&bytes, at).ok() == Some(END))
+            .ok_or_else(|| Error::Parse("not a zip archive".into()))?;
+        let count = u16_at(&bytes, end + 10)? as usize;
+        let mut at = u32_at(&bytes, end + 16)? as usize;
+        if count == 0xffff || at == 0xffff_ffff {
+            return Err(Error::Unsupported("zip64 archives".into()));
+        }
+        let mut entries = Vec::with_capacity(count);
+        for _ in 0..count {
+            if u32_at(&bytes, at)? != CENTRAL {
+                return Err(corrupt("bad central directory"));
+            }
+            let flags = u16_at(&bytes, at + 8)?;
+            if flags & 1 != 0 {
+                return Err(Error::Unsupported("encrypted zip entries".into()));
+            }
+            let name_len = u16_at(&bytes, at + 28)? as usize;
+            let extra_len = u16_at(&bytes, at + 30)? as usize;
+            let comment_len = u16_at(&bytes, at + 32)? as usize;
+            let local = u32_at(&bytes, at + 42)? as usize;
+            let name = bytes
+                .get(at + 46..at + 46 + name_len)
+                .ok_or_else(|| corrupt("truncated name"))?;
+            if u32_at(&bytes, local)? != LOCAL {
+                return Err(corrupt("bad local header"));
+            }
+            let data = local
+                + 30
+                + u16_at(&bytes, local + 26)? as usize
+                + u16_at(&bytes, local + 28)? as usize;
+            let entry = Entry {
+                name: String::from_utf8_lossy(name).into_owned(),
+                method: u16_at(&bytes, at + 10)?,
+                crc: u32_at(&bytes, at + 16)?,
+                compressed: u32_at(&bytes, at + 20)? as usize,
+                size: u32_at(&bytes, at + 24)? as usize,
+                data,
+            };
+            if entry.data + entry.compressed > bytes.len() {
+                return Err(corrupt("entry past the end"));
+            }
+            entries.push(entry);
+            at += 46 + name_len + extra_len + comment_len;
+        }
+        Ok(Archive { bytes, entries })
+    }
+
+    /// Entry names in archive order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|e| e.name.as_str())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.entry(name).is_some()
+    }
+
+    fn entry(&self, name: &str) -> Option<&Entry> {
+        self.entries.iter().find(|e| e.name == name)
+    }
+
+    /// The uncompressed contents of `name`.
+    pub fn read(&self, name: &str) -> Result<Vec<u8>> {
+        let entry = self
+            .entry(name)
+            .ok_or_else(|| Error::Parse(format!("missing zip entry {name}")))?;
+        let raw = &self.bytes[entry.data..entry.data + entry.compressed];
+        let data = match entry.method {
+            0 => raw.to_vec(),
+            8 => inflate(raw, entry.size.min(MAX_ENTRY))?,
+            other => {
+                return Err(Error::Unsupported(format!(
+                    "zip compression method {other}"
+                )))
+            }
+        };
+        if data.len() != entry.size || crc32(&data) != entry.crc {
+            return Err(corrupt(&format!("checksum mismatch in {name}")));
+        }
+        Ok(data)
+    }
+
+    /// [`Archive::read`] as text, for the XML parts.
+    pub fn read_string(&self, name: &str) -> Result<String> {
+        let data = self.read(name)?;
+        String::from_utf8(data).map_err(|_| Error::Parse(format!("{name} is not UTF-8")))
+    }
+}
+
+/// An archive being written: entries copied from another archive as they
+/// were compressed, or new ones stored uncompressed.
+#[derive(Default)]
+pub struct Writer {
+    out: Vec<u8>,
+    central: Vec<u8>,
+    count: u16,
+    names: Vec<String>,
+}
+
+impl Writer {
+    fn push(&mut self, name: &str, method: u16, crc: u32, size: usize, data: &[u8]) -> Result<()> {
+        if self.names.iter().any(|n| n == name) {
+            return Err(Error::InvalidArgument(format!(
+                "duplicate zip entry {name}"
+            )));
+        }
+        if self.out.len() + data.len() > u32::MAX as usize || self.count == u16::MAX - 1 {
+            return Err(Error::Unsupported("zip64 archives".into()));
+        }
+        let offset = self.out.len() as u32;
+        // 1980-01-01 00:00, so the same content always gives the same file.
+        let (time, date) = (0u16, 0x21u16);
+        let header = |signature: u32, central: bool| {
+            let mut h = Vec::with_capacity(46 + name.len());
+            h.extend_from_slice(&signature.to_le_bytes());
+            if central {
+                h.extend_from_slice(&20u16.to_le_bytes());
+            }
+            h.extend_from_slice(&20u16.to_le_bytes());
+            // Bit 11: the name is UTF-8.
+            h.extend_from_slice(&0x0800u16.to_le_bytes());
+            h.extend_from_slice(&method.to_le_bytes());
+            h.extend_from_slice(&time.to_le_bytes());
+            h.extend_from_slice(&date.to_le_bytes());
+            h.extend_from_slice(&crc.to_le_bytes());
+            h.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            h.extend_from_slice(&(size as u32).to_le_bytes());
+            h.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            h.extend_from_slice(&0u16.to_le_bytes());
+            if central {
+                // Comment length, disk, internal and external attributes.
+                h.extend_from_slice(&[0; 10]);
+                h.extend_from_slice(&offset.to_le_bytes());
+            }
+            h.extend_from_slice(name.as_bytes());
+            h
+        };
+        let local = header(LOCAL, false);
+        let central = header(CENTRAL, true);
+        self.out.extend_from_slice(&local);
+        self.out.extend_from_slice(data);
+        self.central.extend_from_slice(&central);
+        self.count += 1;
+        self.names.push(name.to_string());
+        Ok(())
+    }
+
+    /// Add `data` as `name`, stored uncompressed.
+    pub fn add(&mut self, name: &str, data: &[u8]) -> Result<()> {
+        self.push(name, 0, crc32(data), data.len(), data)
+    }
+
+    /// Copy entry `name` from `archive` without recompressing it.
+    pub fn copy(&mut self, archive: &Archive, name: &str) -> Result<()> {
+        let entry = archive
+            .entry(name)
+            .ok_or_else(|| Error::Parse(format!("missing zip entry {name}")))?;
+        let raw = &archive.bytes[entry.data..entry.data + entry.compressed];
+        self.push(name, entry.method, entry.crc, entry.size, raw)
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        let directory = self.out.len() as u32;
+        self.out.extend_from_slice(&self.central);
+        self.out.extend_from_slice(&END.to_le_bytes());
+        self.out.extend_from_slice(&[0; 4]);
+        self.out.extend_from_slice(&self.count.to_le_bytes());
+        self.out.extend_from_slice(&self.count.to_le_bytes());
+        self.out
+            .extend_from_slice(&(self.central.len() as u32).to_le_bytes());
+        self.out.extend_from_slice(&directory.to_le_bytes());
+        self.out.extend_from_slice(&0u16.to_le_bytes());
+        self.out
+    }
+
+    /// Write the archive to `path`, through a temporary file so a failure
+    /// never leaves a half-written document.
+    pub fn save(self, path: &Path) -> Result<()> {
+        let tmp = path.with_extension("synth-tmp");
+        fs::write(&tmp, self.finish())?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
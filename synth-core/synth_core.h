@@ -1,7 +0,0 @@
-#ifndef SYNTH_CORE_H
-#define SYNTH_CORE_H
-
-char* kiro_chat(const char* prompt);
-void free_string(char* s);
-
-#endif
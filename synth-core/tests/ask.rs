@@ -0,0 +1,61 @@
+//! Questions about long documents go in with the passages they are about.
+
+use std::fs;
+use std::sync::Arc;
+
+use synth_core::{ask, chat, prefetch};
+
+/// A document well over the prompt budget with one paragraph about
+/// warranties in the middle.
+fn long_document() -> String {
+    let filler = "The committee reviewed the quarterly figures without comment. ";
+    let mut text = String::new();
+    for section in 0..120 {
+        text.push_str(&format!("Section {section}. "));
+        text.push_str(&filler.repeat(8));
+        text.push_str("\n\n");
+        if section == 60 {
+            text.push_str("The warranty period for all hardware is 24 months.\n\n");
+        }
+    }
+    text
+}
+
+#[test]
+fn short_documents_go_in_whole() {
+    let prompt = chat::ask_prompt("The fee is due in May.", "When is the fee due?");
+    assert!(
+        prompt.ends_with("Document:\nThe fee is due in May."),
+        "{prompt}"
+    );
+}
+
+#[test]
+fn long_documents_are_cut_to_the_relevant_chunks() {
+    let document = long_document();
+    assert!(document.chars().count() > ask::BUDGET);
+    let excerpt = ask::excerpt(&document, "How long is the warranty period?");
+    assert!(excerpt.contains("The warranty period for all hardware is 24 months."));
+    assert!(excerpt.contains("[...]"));
+    assert!(excerpt.chars().count() <= ask::BUDGET);
+    // Chunks stay in document order.
+    let first = excerpt.find("Section 1.").unwrap();
+    assert!(first < excerpt.find("warranty").unwrap());
+
+    let prompt = chat::ask_prompt(&document, "How long is the warranty period?");
+    assert!(prompt.contains("24 months"));
+}
+
+#[test]
+fn prefetching_builds_the_index_questions_use() {
+    let path = std::env::temp_dir().join(format!("synth-ask-{}.txt", std::process::id()));
+    let document = long_document().replace("hardware", "prefetched hardware");
+    fs::write(&path, &document).unwrap();
+    assert!(ask::indexed(&document).is_none());
+    prefetch::warm(&path).unwrap();
+    let primed = ask::indexed(&document).unwrap();
+    assert!(primed.chunks.len() > 1);
+    assert!(Arc::ptr_eq(&primed, &ask::index(&document)));
+    assert!(ask::excerpt(&document, "warranty period").contains("prefetched hardware"));
+    let _ = fs::remove_file(&path);
+}
@@ -0,0 +1,111 @@
+//! The audit log: what a chat call records, rotation and queries.
+
+use std::fs;
+use std::time::Duration;
+
+use synth_core::audit::{self, Config, Entry, Filter};
+use synth_core::backend;
+use synth_core::chat::{self, ChatOptions};
+use synth_core::error::Error;
+use synth_core::json::{self, Value};
+
+fn entry<'a>(prompt: &'a str, outcome: Result<&'a str, &'a Error>, user: &'a str) -> Entry<'a> {
+    Entry {
+        prompt,
+        outcome,
+        backend: Some("mock"),
+        model: None,
+        user_id: Some(user),
+        tenant_id: None,
+        duration: Duration::from_millis(12),
+    }
+}
+
+#[test]
+fn calls_are_logged_rotated_and_queried() {
+    let dir = std::env::temp_dir().join(format!("synth-audit-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let config = |extra: &str| {
+        let text = format!(
+            r#"{{"dir": {:?}, "max_bytes": 4096, "keep": 2{extra}}}"#,
+            dir.display().to_string()
+        );
+        Config::from_json(&json::parse(&text).unwrap()).unwrap()
+    };
+    assert_eq!(
+        audit::query(&Filter::default()).unwrap_err().kind(),
+        "unsupported"
+    );
+    audit::configure(Some(config(""))).unwrap();
+
+    // A chat call through a profile is recorded with who answered.
+    let profiles =
+        json::parse(r#"{"profiles": {"m": {"type": "mock", "default": "Net 30."}}}"#).unwrap();
+    backend::configure(backend::Config::from_json(&profiles).unwrap());
+    let options = ChatOptions {
+        user_id: Some("ana".into()),
+        ..ChatOptions::default()
+    };
+    chat::chat("Payment terms?", &options).unwrap();
+    let records = audit::query(&Filter::default()).unwrap();
+    assert_eq!(records.len(), 1);
+    let text = |record: &Value, key| record.get(key).and_then(Value::as_str).map(String::from);
+    assert_eq!(text(&records[0], "backend").as_deref(), Some("m"));
+    assert_eq!(text(&records[0], "user_id").as_deref(), Some("ana"));
+    assert_eq!(
+        text(&records[0], "prompt").as_deref(),
+        Some("Payment terms?")
+    );
+    assert_eq!(text(&records[0], "response").as_deref(), Some("Net 30."));
+
+    let failure = Error::Backend("down".into());
+    audit::record(&entry("Q", Err(&failure), "bo"));
+    let failed = audit::query(&Filter {
+        user_id: Some("bo".into()),
+        ..Filter::default()
+    })
+    .unwrap();
+    assert_eq!(failed[0].get("ok"), Some(&Value::Bool(false)));
+    assert_eq!(text(&failed[0], "error").as_deref(), Some("backend"));
+
+    // Past max_bytes the log rotates, keeping two old files.
+    let long = "x".repeat(1500);
+    for _ in 0..12 {
+        audit::record(&entry(&long, Ok("ok"), "cy"));
+    }
+    assert!(dir.join("audit.1.jsonl").is_file());
+    assert!(dir.join("audit.2.jsonl").is_file());
+    assert!(!dir.join("audit.3.jsonl").exists());
+    assert!(fs::metadata(dir.join("audit.jsonl")).unwrap().len() <= 4096);
+    let recent = audit::query(&Filter {
+        limit: 3,
+        ..Filter::default()
+    })
+    .unwrap();
+    assert_eq!(recent.len(), 3);
+    let all = audit::query(&Filter::default()).unwrap();
+    assert!(all.len() < 14, "the oldest records were rotated away");
+    let since = all[0].get("time").and_then(Value::as_f64).unwrap();
+    assert_eq!(
+        audit::query(&Filter::from_json(&Value::object([(
+            "since",
+            Value::from(since + 1e6)
+        )])))
+        .unwrap()
+        .len(),
+        0
+    );
+
+    // Hash mode keeps digests instead of the text.
+    fs::remove_dir_all(&dir).unwrap();
+    audit::configure(Some(config(r#", "content": "hash""#))).unwrap();
+    audit::record(&entry("abc", Ok("ok"), "ana"));
+    let hashed = &audit::query(&Filter::default()).unwrap()[0];
+    assert!(hashed.get("prompt").is_none());
+    assert_eq!(
+        text(hashed, "prompt_sha256").as_deref(),
+        Some("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+    );
+    audit::configure(None).unwrap();
+    let _ = fs::remove_dir_all(&dir);
+}
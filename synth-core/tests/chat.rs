@@ -0,0 +1,56 @@
+//! Chat options: stop sequences, length limits and fence stripping.
+
+use synth_core::chat::{strip_fences, ChatOptions};
+use synth_core::json::{self, Value};
+
+#[test]
+fn replies_are_cut_at_the_earliest_stop_then_limited() {
+    let options = ChatOptions {
+        stop: vec!["END".into(), "\n\n".into()],
+        max_chars: Some(4),
+        ..ChatOptions::default()
+    };
+    assert_eq!(options.filter("Grüße\n\nmore END"), "Grüß");
+    assert_eq!(options.filter("ab END\n\n"), "ab ");
+    let unlimited = ChatOptions {
+        stop: vec!["END".into()],
+        ..ChatOptions::default()
+    };
+    assert_eq!(unlimited.filter("no stop here"), "no stop here");
+}
+
+#[test]
+fn fences_are_removed_but_their_content_kept() {
+    let reply = "Here:\n```json\n{\"a\": 1}\n```\n~~~\nplain\n~~~\n";
+    assert_eq!(strip_fences(reply), "Here:\n{\"a\": 1}\nplain");
+    let options = ChatOptions {
+        strip_fences: true,
+        max_chars: Some(8),
+        ..ChatOptions::default()
+    };
+    // The limit counts what is left after stripping.
+    assert_eq!(options.filter("```\n{\"a\": 1}\n```"), "{\"a\": 1}");
+}
+
+#[test]
+fn options_round_trip_through_json() {
+    let value = json::parse(
+        r#"{"stop": ["\n\n", ""], "max_chars": 2000, "strip_fences": true,
+            "backend": "fast-local", "seed": "18446744073709551615", "chunk_size": 10}"#,
+    )
+    .unwrap();
+    let options = ChatOptions::from_json(&value);
+    assert_eq!(options.stop, ["\n\n"]);
+    assert_eq!(options.max_chars, Some(2000));
+    assert!(options.strip_fences);
+    assert_eq!(options.seed, Some(u64::MAX));
+    assert_eq!(ChatOptions::from_json(&options.to_json()), options);
+
+    let single =
+        ChatOptions::from_json(&json::parse(r#"{"stop": "END", "max_chars": 0}"#).unwrap());
+    assert_eq!((single.stop, single.max_chars), (vec!["END".into()], None));
+    assert_eq!(
+        ChatOptions::default().to_json().get("strip_fences"),
+        Some(&Value::Bool(false))
+    );
+}
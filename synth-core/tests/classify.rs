@@ -0,0 +1,43 @@
+//! Document classification against caller-defined labels.
+
+use synth_core::classify::{classify_lexical, classify_with_backend, Label};
+use synth_core::json;
+
+fn labels() -> Vec<Label> {
+    Label::list_from_json(
+        &json::parse(
+            r#"["invoice", {"label": "contract", "description": "agreement between parties"}, "memo"]"#,
+        )
+        .unwrap(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn repeated_labels_keep_their_highest_confidence() {
+    // The repeats are not adjacent once sorted, so a plain dedup would
+    // keep both; an invented label is dropped.
+    let reply = r#"{"labels": [
+        {"label": "contract", "confidence": 0.2},
+        {"label": "invoice", "confidence": 0.5},
+        {"label": "Contract", "confidence": 0.9},
+        {"label": "receipt", "confidence": 1.0},
+        {"label": "invoice", "confidence": 0.4}
+    ]}"#;
+    let ranking = classify_with_backend("text", &labels(), |_| Ok(reply.to_string())).unwrap();
+    assert_eq!(
+        ranking,
+        vec![("contract".to_string(), 0.9), ("invoice".to_string(), 0.5)]
+    );
+}
+
+#[test]
+fn lexical_scores_sum_to_one_best_first() {
+    let ranking = classify_lexical(
+        "This agreement is made between the parties to the contract.",
+        &labels(),
+    );
+    assert_eq!(ranking[0].0, "contract");
+    let total: f64 = ranking.iter().map(|(_, score)| score).sum();
+    assert!((total - 1.0).abs() < 1e-9);
+}
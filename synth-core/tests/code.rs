@@ -0,0 +1,93 @@
+//! Source files: language detection and declaration-level chunks.
+
+use std::path::Path;
+
+use synth_core::code::{chunk_code, Language};
+
+fn outline(source: &str, language: Language) -> Vec<(String, Option<String>, usize, usize)> {
+    chunk_code(source, language, None)
+        .into_iter()
+        .map(|c| (c.kind, c.name, c.start_line, c.end_line))
+        .collect()
+}
+
+#[test]
+fn languages_come_from_the_extension() {
+    assert_eq!(
+        Language::from_path(Path::new("src/Main.KT")),
+        Some(Language::Kotlin)
+    );
+    assert_eq!(
+        Language::from_path(Path::new("view.tsx")),
+        Some(Language::TypeScript)
+    );
+    assert_eq!(Language::from_path(Path::new("notes.md")), None);
+    assert_eq!(Language::Cpp.name(), "cpp");
+}
+
+#[test]
+fn rust_items_keep_their_comments_and_ignore_braces_in_strings() {
+    let source = "\
+use std::fmt;
+
+/// A point.
+#[derive(Debug)]
+struct Point {
+    x: i32,
+}
+
+impl Point {
+    fn brace(&self) -> &str {
+        \"}\"
+    }
+}
+
+fn main() {
+    // { not a block
+    println!(\"{}\", 1);
+}
+";
+    assert_eq!(
+        outline(source, Language::Rust),
+        [
+            ("statements".to_string(), None, 1, 1),
+            ("struct".to_string(), Some("Point".to_string()), 3, 7),
+            ("impl".to_string(), Some("Point".to_string()), 9, 13),
+            ("fn".to_string(), Some("main".to_string()), 15, 18),
+        ]
+    );
+}
+
+#[test]
+fn python_blocks_end_with_their_indentation() {
+    let source = "\
+import os
+
+class Store:
+    def get(self):
+        return os.getcwd()
+
+def main():
+    Store().get()
+";
+    assert_eq!(
+        outline(source, Language::Python),
+        [
+            ("statements".to_string(), None, 1, 1),
+            ("class".to_string(), Some("Store".to_string()), 3, 5),
+            ("def".to_string(), Some("main".to_string()), 7, 8),
+        ]
+    );
+}
+
+#[test]
+fn long_chunks_split_at_blank_lines() {
+    let body: Vec<String> = (0..6).map(|i| format!("    let a{i} = {i};\n\n")).collect();
+    let source = format!("fn long() {{\n{}}}\n", body.concat());
+    let chunks = chunk_code(&source, Language::Rust, Some(5));
+    assert!(chunks.len() > 1);
+    assert!(chunks.iter().all(|c| c.end_line - c.start_line < 5));
+    assert!(chunks.iter().all(|c| c.name.as_deref() == Some("long")));
+    let covered: usize = chunks.iter().map(|c| c.end_line - c.start_line + 1).sum();
+    assert_eq!(covered, source.lines().count());
+}
@@ -0,0 +1,82 @@
+//! Comparing documents: the prompt, and rows matched to documents.
+
+use std::fs;
+use std::path::PathBuf;
+
+use synth_core::compare::compare_corpus;
+
+fn documents(name: &str) -> (PathBuf, Vec<PathBuf>) {
+    let dir = std::env::temp_dir().join(format!("synth-compare-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let paths: Vec<PathBuf> = [
+        ("acme.txt", "Net 30. Renews yearly."),
+        ("bolt.txt", "Net 60."),
+    ]
+    .iter()
+    .map(|(file, text)| {
+        let path = dir.join(file);
+        fs::write(&path, text).unwrap();
+        path
+    })
+    .collect();
+    (dir, paths)
+}
+
+#[test]
+fn fixed_criteria_become_the_columns() {
+    let (dir, paths) = documents("fixed");
+    let mut prompt = String::new();
+    let comparison = compare_corpus(
+        &paths,
+        r#"["Payment", "Renewal"]"#,
+        |sent| {
+            prompt = sent.to_string();
+            // Rows out of order, one short a value.
+            Ok(r#"{"rows": [{"document": 2, "values": ["60 days"]},
+                   {"document": 1, "values": ["30 days", "yearly"]}]}"#
+                .into())
+        },
+        |_| {},
+    )
+    .unwrap();
+    assert!(prompt.contains("in this order: Payment; Renewal."));
+    assert!(prompt.contains("--- Document 1: acme.txt ---\nNet 30. Renews yearly.\n"));
+    assert_eq!(comparison.columns, ["Payment", "Renewal"]);
+    assert_eq!(comparison.rows[0].1, ["30 days", "yearly"]);
+    assert_eq!(comparison.rows[1].1, ["60 days", ""]);
+    assert_eq!(
+        comparison.to_markdown().lines().nth(3),
+        Some("| bolt.txt | 60 days |  |")
+    );
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn free_text_criteria_take_the_backends_columns() {
+    let (dir, paths) = documents("free");
+    let comparison = compare_corpus(
+        &paths,
+        "payment terms",
+        |_| {
+            Ok(r#"```json
+{"columns": ["Terms"], "rows": [{"values": ["30"]}, {"values": [60]}]}
+```"#
+                .into())
+        },
+        |_| {},
+    )
+    .unwrap();
+    assert_eq!(comparison.columns, ["Terms"]);
+    assert_eq!(comparison.rows[1].1, ["60"]);
+
+    let err = compare_corpus(&paths, "payment terms", |_| Ok("{}".into()), |_| {}).unwrap_err();
+    assert_eq!(err.kind(), "backend");
+    assert_eq!(
+        compare_corpus(&[], "x", |_| Ok(String::new()), |_| {})
+            .unwrap_err()
+            .kind(),
+        "parse"
+    );
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,43 @@
+//! Concordance and word frequencies.
+
+use synth_core::concordance::{concordance, word_frequencies};
+
+const CONTRACT: &str = "1. Termination\n\nEither party may terminate this Agreement.\n\
+                        On termination, the Party shall return   all materials.\n\n\
+                        Early termination fees apply.";
+
+#[test]
+fn finds_whole_word_matches_in_context() {
+    let found = concordance(CONTRACT, "termination", 3);
+    assert_eq!(found.len(), 3);
+    // The blank line after the heading stops its context.
+    assert_eq!(found[0].matched, "Termination");
+    assert_eq!((found[0].line, found[0].column), (1, 4));
+    assert_eq!(
+        (found[0].before.as_str(), found[0].after.as_str()),
+        ("1.", "")
+    );
+    assert_eq!(found[1].before, "this Agreement. On");
+    assert_eq!(found[1].after, ", the Party shall");
+    assert_eq!(found[1].line, 4);
+    assert_eq!(found[2].before, "Early");
+    let chars: Vec<char> = CONTRACT.chars().collect();
+    let matched: String = chars[found[1].start..found[1].end].iter().collect();
+    assert_eq!(matched, "termination");
+
+    let phrase = concordance(CONTRACT, "the  PARTY", 1);
+    assert_eq!(phrase.len(), 1);
+    assert_eq!(phrase[0].matched, "the Party");
+    assert!(concordance(CONTRACT, "!!", 2).is_empty());
+}
+
+#[test]
+fn counts_words_most_frequent_first() {
+    let all = word_frequencies(CONTRACT, false, 2);
+    assert_eq!(
+        all,
+        [("termination".to_string(), 3), ("party".to_string(), 2)]
+    );
+    let content = word_frequencies(CONTRACT, true, 0);
+    assert!(content.iter().all(|(word, _)| word != "the" && word != "1"));
+}
@@ -0,0 +1,21 @@
+//! Extraction over `tests/corpus` must match the stored goldens. After an
+//! intended output change, regenerate them with
+//! `cargo run --bin synth -- verify-corpus tests/corpus --update`.
+
+use std::path::Path;
+
+use synth_core::corpus;
+
+#[test]
+fn corpus_matches_goldens() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+    let report = corpus::verify(&dir, false).unwrap();
+    assert!(!report.documents.is_empty());
+    assert!(report.ok(), "{}", report.to_text());
+}
+
+#[test]
+fn diff_marks_changed_lines() {
+    let diff = corpus::diff("a\nb\nc\nd\n", "a\nB\nc\nd\ne\n");
+    assert_eq!(diff, "@@ -2,3 +2,4 @@\n- b\n+ B\n  c\n  d\n+ e\n");
+}
@@ -0,0 +1,64 @@
+//! Duplicate detection: exact copies, near duplicates and unrelated files.
+
+use std::fs;
+
+use synth_core::dedup::{find_duplicates, signature, similarity};
+
+const CONTRACT: &str = "The supplier shall deliver the goods within thirty days of the \
+    purchase order. Payment is due sixty days after the invoice date. Either party may \
+    terminate this agreement with ninety days written notice to the other party.";
+
+#[test]
+fn similarity_estimates_shared_shingles() {
+    let a = signature(CONTRACT);
+    assert_eq!(similarity(&a, &a), 1.0);
+    let edited = CONTRACT.replace("sixty", "forty-five");
+    let near = similarity(&a, &signature(&edited));
+    assert!((0.6..1.0).contains(&near), "{near}");
+    let other = similarity(
+        &a,
+        &signature("Minutes of the weekly design review meeting."),
+    );
+    assert!(other < 0.1, "{other}");
+    assert_eq!(similarity(&a, &[]), 0.0);
+}
+
+#[test]
+fn copies_and_drafts_cluster_and_the_rest_is_left_alone() {
+    let dir = std::env::temp_dir().join(format!("synth-dedup-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let write = |name: &str, text: &str| {
+        let path = dir.join(name);
+        fs::write(&path, text).unwrap();
+        path
+    };
+    let paths = vec![
+        write("contract.txt", CONTRACT),
+        write("notes.txt", "Minutes of the weekly design review meeting."),
+        write("contract copy.txt", CONTRACT),
+        write("contract v2.txt", &CONTRACT.replace("ninety", "sixty")),
+        dir.join("missing.txt"),
+    ];
+
+    let exact = find_duplicates(&paths, 1.0);
+    assert_eq!(exact.clusters.len(), 1);
+    assert!(exact.clusters[0].exact);
+    assert_eq!(
+        exact.clusters[0].documents,
+        [(paths[0].clone(), 1.0), (paths[2].clone(), 1.0)]
+    );
+    assert_eq!(exact.skipped, [paths[4].clone()]);
+
+    let near = find_duplicates(&paths, 0.5);
+    assert_eq!(near.clusters.len(), 1);
+    let cluster = &near.clusters[0];
+    assert!(!cluster.exact);
+    let members: Vec<_> = cluster.documents.iter().map(|(p, _)| p.clone()).collect();
+    assert_eq!(
+        members,
+        [paths[0].clone(), paths[2].clone(), paths[3].clone()]
+    );
+    assert!(cluster.documents[2].1 < 1.0);
+    let _ = fs::remove_dir_all(&dir);
+}
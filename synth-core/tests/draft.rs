@@ -0,0 +1,65 @@
+//! Drafting in a document's style: sampling, the prompt and the styled
+//! paragraphs the reply becomes.
+
+use synth_core::draft::{self, Draft, Style};
+
+#[test]
+fn drafts_follow_the_source_style() {
+    let dir = std::env::temp_dir().join(format!("synth-draft-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("contract.md");
+    std::fs::write(
+        &path,
+        "# Services Agreement\n\n\
+         1.1 The Supplier shall provide the Services with reasonable skill and care.\n\n\
+         1.2 The Customer shall pay each invoice within thirty (30) days of receipt.\n\n\
+         Signed.\n\n\
+         - the Services;\n- the Deliverables.\n",
+    )
+    .unwrap();
+
+    let mut prompt = String::new();
+    let draft = draft::draft_in_style(&path, "a confidentiality clause", |p| {
+        prompt = p.to_string();
+        Ok(
+            "```markdown\n## 2. Confidentiality\n\n2.1 Each party shall keep the \
+            **Confidential Information**\nsecret.\n2.2 This clause survives *termination*.\n\n\
+            - trade secrets & pricing\n```"
+                .into(),
+        )
+    })
+    .unwrap();
+    assert!(prompt.contains("Instruction: a confidentiality clause"));
+    assert!(prompt.contains("# Services Agreement") && prompt.contains("- the Services;"));
+    assert!(prompt.contains("thirty (30) days"));
+
+    let styles: Vec<(Style, String)> = draft
+        .paragraphs
+        .iter()
+        .map(|p| (p.style, p.text()))
+        .collect();
+    assert_eq!(
+        styles,
+        [
+            (Style::Heading(2), "2. Confidentiality".to_string()),
+            (
+                Style::Body,
+                "2.1 Each party shall keep the Confidential Information secret.".to_string()
+            ),
+            (
+                Style::Body,
+                "2.2 This clause survives termination.".to_string()
+            ),
+            (Style::Bullet, "trade secrets & pricing".to_string()),
+        ]
+    );
+    let wordml = draft.to_wordml();
+    assert!(wordml.starts_with(
+        "<w:p><w:pPr><w:pStyle w:val=\"Heading2\"/></w:pPr><w:r><w:t xml:space=\"preserve\">2. Confidentiality"
+    ));
+    assert!(wordml.contains("<w:r><w:rPr><w:b/></w:rPr><w:t xml:space=\"preserve\">Confidential Information</w:t></w:r>"));
+    assert!(wordml.contains("<w:rPr><w:i/></w:rPr><w:t xml:space=\"preserve\">termination</w:t>"));
+    assert!(wordml.contains("trade secrets &amp; pricing"));
+    assert_eq!(Draft::from_markdown("a * b").paragraphs[0].text(), "a * b");
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,246 @@
+//! Sentence embeddings: BERT's WordPiece tokenizer, and with the
+//! `embeddings` feature a tiny ONNX model whose "embedding" of a token is
+//! a row of a table, invoice words pointing one way and pets another,
+//! and memories recalled by it.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use synth_core::embeddings::{self, Config, Tokenizer};
+use synth_core::json;
+
+/// The model is configured for the whole process, so the tests using it
+/// take turns.
+#[cfg(feature = "embeddings")]
+static TURN: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+#[cfg(feature = "embeddings")]
+fn tiny() -> Config {
+    Config {
+        model: fixtures().join("tiny.onnx"),
+        vocab: fixtures().join("vocab.txt"),
+        max_tokens: 16,
+        lowercase: true,
+    }
+}
+
+fn fixtures() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/embeddings")
+}
+
+fn tokenizer() -> Tokenizer {
+    let vocab = fs::read_to_string(fixtures().join("vocab.txt")).unwrap();
+    Tokenizer::from_vocab(&vocab, true).unwrap()
+}
+
+#[test]
+fn text_is_split_into_word_pieces() {
+    let tokenizer = tokenizer();
+    // [CLS] the fee , is due . [SEP]
+    assert_eq!(
+        tokenizer.encode("The FEE, is due.", 128),
+        [2, 4, 5, 14, 6, 7, 15, 3]
+    );
+    // "cats" is "cat" "##s"; accents are folded; an unknown word is [UNK].
+    assert_eq!(
+        tokenizer.encode("cats and Café", 128),
+        [2, 10, 12, 13, 16, 3]
+    );
+    assert_eq!(tokenizer.encode("zebra dogs", 128), [2, 1, 11, 12, 3]);
+    // Long texts are cut off, still ending in [SEP].
+    assert_eq!(tokenizer.encode("the fee is due", 4), [2, 4, 5, 3]);
+    assert_eq!(tokenizer.encode("", 128), [2, 3]);
+    assert_eq!(tokenizer.pad(), 0);
+
+    assert!(Tokenizer::from_vocab("the\nfee\n", true).is_err());
+}
+
+#[test]
+fn models_are_named_through_the_manifest_and_checked() {
+    let dir = std::env::temp_dir().join(format!("synth-embeddings-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    for file in ["tiny.onnx", "vocab.txt"] {
+        fs::copy(fixtures().join(file), dir.join(file)).unwrap();
+    }
+    let entry = |name: &str, file: &str| {
+        let sha256 = synth_core::models::file_sha256(&dir.join(file)).unwrap();
+        format!(
+            r#"{{"name": "{name}", "file": "{file}", "url": "https://example.com/{file}", "sha256": "{sha256}"}}"#
+        )
+    };
+    fs::write(
+        dir.join("models.json"),
+        format!(
+            "[{}, {}]",
+            entry("tiny", "tiny.onnx"),
+            entry("tiny-vocab", "vocab.txt")
+        ),
+    )
+    .unwrap();
+    let config = |text: &str| {
+        Config::from_json(&json::parse(&text.replace("DIR", &dir.display().to_string())).unwrap())
+    };
+
+    let named = config(r#"{"models_dir": "DIR", "model": "tiny", "vocab": "tiny-vocab"}"#).unwrap();
+    assert_eq!(named.model, dir.join("tiny.onnx"));
+    assert_eq!(named.vocab, dir.join("vocab.txt"));
+    assert_eq!(named.max_tokens, 128);
+    let given = config(
+        r#"{"model_path": "DIR/tiny.onnx", "vocab_path": "DIR/vocab.txt", "max_tokens": 16}"#,
+    )
+    .unwrap();
+    assert_eq!(given.max_tokens, 16);
+
+    assert_eq!(
+        config(r#"{"models_dir": "DIR", "model": "other", "vocab": "tiny-vocab"}"#)
+            .unwrap_err()
+            .kind(),
+        "invalid_argument"
+    );
+    assert!(config(r#"{"model_path": "DIR/tiny.onnx"}"#).is_err());
+    assert!(config(r#"{"model_path": "a", "vocab_path": "b", "max_tokens": 1}"#).is_err());
+    // A model changed since it was installed is refused.
+    fs::write(dir.join("vocab.txt"), "[PAD]\n").unwrap();
+    assert_eq!(
+        config(r#"{"models_dir": "DIR", "model": "tiny", "vocab": "tiny-vocab"}"#)
+            .unwrap_err()
+            .kind(),
+        "parse"
+    );
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[cfg(not(feature = "embeddings"))]
+#[test]
+fn embedding_needs_the_feature() {
+    let config = Config {
+        model: fixtures().join("tiny.onnx"),
+        vocab: fixtures().join("vocab.txt"),
+        max_tokens: 16,
+        lowercase: true,
+    };
+    assert_eq!(
+        embeddings::configure(Some(config)).unwrap_err().kind(),
+        "unsupported"
+    );
+    assert!(!embeddings::is_configured());
+    assert_eq!(
+        embeddings::embed(&["fee"]).unwrap_err().kind(),
+        "unsupported"
+    );
+}
+
+#[cfg(feature = "embeddings")]
+#[test]
+fn similar_texts_have_close_embeddings() {
+    use embeddings::similarity;
+
+    let _turn = TURN.lock().unwrap_or_else(|e| e.into_inner());
+    embeddings::configure(Some(tiny())).unwrap();
+    assert!(embeddings::is_configured());
+    let vectors = embeddings::embed(&[
+        "The invoice payment is due.",
+        "the fee is due",
+        "cats and dogs",
+    ])
+    .unwrap();
+    assert_eq!(vectors.len(), 3);
+    for vector in &vectors {
+        assert_eq!(vector.len(), 4);
+        assert!((similarity(vector, vector) - 1.0).abs() < 1e-5);
+    }
+    let bills = similarity(&vectors[0], &vectors[1]);
+    let pets = similarity(&vectors[0], &vectors[2]);
+    assert!(bills > 0.9, "{bills}");
+    assert!(pets < 0.5, "{pets}");
+
+    embeddings::configure(None).unwrap();
+    assert_eq!(
+        embeddings::embed(&["fee"]).unwrap_err().kind(),
+        "unsupported"
+    );
+}
+
+#[cfg(feature = "embeddings")]
+#[test]
+fn memories_are_recalled_by_meaning() {
+    use synth_core::memory;
+    use synth_core::usage::Tags;
+
+    let _turn = TURN.lock().unwrap_or_else(|e| e.into_inner());
+    let tags = Tags::new(Some("embedded-memories"), None);
+    memory::add("The invoice payment is due", None, &tags).unwrap();
+    memory::add("cats and dogs", None, &tags).unwrap();
+    // No memory shares a word with the query.
+    assert!(memory::search("fee", 0, &tags).is_empty());
+
+    embeddings::configure(Some(tiny())).unwrap();
+    let found = memory::search("fee", 0, &tags);
+    assert_eq!(found[0].0.text, "The invoice payment is due");
+    assert!(found[0].1 > 0.9, "{found:?}");
+    assert!(found
+        .iter()
+        .all(|(m, score)| m.text != "cats and dogs" || *score < 0.35));
+    let recalled = memory::recall("fee?", &tags);
+    assert!(
+        recalled.contains("- The invoice payment is due\n"),
+        "{recalled}"
+    );
+    assert!(!recalled.contains("cats"));
+
+    // An updated memory is embedded again.
+    let id = found[0].0.id;
+    assert!(memory::update(id, "cats", &tags));
+    assert!(memory::search("fee", 0, &tags)
+        .iter()
+        .all(|(_, score)| *score < 0.35));
+
+    embeddings::configure(None).unwrap();
+    assert!(memory::search("fee", 0, &tags).is_empty());
+}
+
+#[cfg(feature = "embeddings")]
+#[test]
+fn long_documents_are_indexed_with_embeddings() {
+    use synth_core::ask;
+
+    let _turn = TURN.lock().unwrap_or_else(|e| e.into_inner());
+    let mut document = "cats and dogs. ".repeat(4000);
+    document.push_str("the invoice payment is due.");
+    assert!(!ask::index(&document).is_embedded());
+
+    embeddings::configure(Some(tiny())).unwrap();
+    // Another model needs another index.
+    assert!(ask::indexed(&document).is_none());
+    let index = ask::index(&document);
+    assert!(index.is_embedded());
+    assert!(ask::excerpt(&document, "fee").ends_with("the invoice payment is due."));
+    embeddings::configure(None).unwrap();
+}
+
+#[cfg(feature = "embeddings")]
+#[test]
+fn indexing_reports_each_chunk_embedded() {
+    use synth_core::ask;
+
+    let _turn = TURN.lock().unwrap_or_else(|e| e.into_inner());
+    let path = std::env::temp_dir().join(format!("synth-embeddings-{}.txt", std::process::id()));
+    fs::write(&path, "the fee is due. ".repeat(300)).unwrap();
+    embeddings::configure(Some(tiny())).unwrap();
+    let mut reports = Vec::new();
+    let index = ask::index_document(&path, |p| {
+        reports.push((p.stage.name(), p.completed, p.total, p.percent))
+    })
+    .unwrap();
+    assert!(index.is_embedded());
+    let chunks = index.chunks.len();
+    assert!(chunks > 1);
+    let embedding: Vec<_> = reports.iter().filter(|r| r.0 == "embedding").collect();
+    assert_eq!(embedding.len(), chunks + 1);
+    assert_eq!(*embedding[chunks], ("embedding", chunks, chunks, 100.0));
+    assert_eq!(reports[0].0, "extracting");
+    assert_eq!(reports[1].0, "chunking");
+    embeddings::configure(None).unwrap();
+    let _ = fs::remove_file(&path);
+}
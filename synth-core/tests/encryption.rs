@@ -0,0 +1,332 @@
+//! Encryption at rest: AES-256-GCM and PBKDF2 against published vectors,
+//! and with the `encryption` feature the records, projects and audit log
+//! sealed with them.
+
+#[cfg(feature = "encryption")]
+use std::fs;
+#[cfg(feature = "encryption")]
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+#[cfg(feature = "encryption")]
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+#[cfg(feature = "encryption")]
+use aes_gcm::Aes256Gcm;
+use synth_core::encryption::{self, derive_key, Config};
+use synth_core::json;
+
+/// The key is configured for the whole process, so the tests take turns.
+static TURN: Mutex<()> = Mutex::new(());
+
+#[cfg(feature = "encryption")]
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("synth-encryption-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn unhex(text: &str) -> Vec<u8> {
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&text[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(feature = "encryption")]
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+/// The cipher records are sealed with, sealing `plaintext` with `aad`.
+#[cfg(feature = "encryption")]
+fn seal(key: &[u8; 32], nonce: &[u8; 12], aad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+    Aes256Gcm::new(key.into())
+        .encrypt(
+            nonce.into(),
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .unwrap()
+}
+
+#[cfg(feature = "encryption")]
+fn open(key: &[u8; 32], nonce: &[u8; 12], aad: &[u8], sealed: &[u8]) -> Option<Vec<u8>> {
+    Aes256Gcm::new(key.into())
+        .decrypt(nonce.into(), Payload { msg: sealed, aad })
+        .ok()
+}
+
+#[cfg(feature = "encryption")]
+#[test]
+fn aes_256_gcm_matches_the_published_vectors() {
+    // Test cases 13 to 16 of the GCM specification (McGrew and Viega),
+    // as used by NIST's validation of SP 800-38D.
+    let zero = [0u8; 32];
+    assert_eq!(
+        hex(&seal(&zero, &[0; 12], b"", b"")),
+        "530f8afbc74536b9a963b4f1c4cb738b"
+    );
+    assert_eq!(
+        hex(&seal(&zero, &[0; 12], b"", &[0; 16])),
+        "cea7403d4d606b6e074ec5d3baf39d18d0d1c8a799996bf0265b98b5d48ab919"
+    );
+    let key: [u8; 32] = unhex(&"feffe9928665731c6d6a8f9467308308".repeat(2))
+        .try_into()
+        .unwrap();
+    let nonce: [u8; 12] = unhex("cafebabefacedbaddecaf888").try_into().unwrap();
+    let plaintext = unhex(
+        "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a72\
+         1c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b391aafd255",
+    );
+    assert_eq!(
+        hex(&seal(&key, &nonce, b"", &plaintext)),
+        "522dc1f099567d07f47f37a32a84427d643a8cdcbfe5c0c97598a2bd2555d1aa\
+         8cb08e48590dbb3da7b08b1056828838c5f61e6393ba7a0abcc9f662898015ad\
+         b094dac5d93471bdec1a502270e3cc6c"
+    );
+    let aad = unhex("feedfacedeadbeeffeedfacedeadbeefabaddad2");
+    let sealed = seal(&key, &nonce, &aad, &plaintext[..60]);
+    assert_eq!(
+        hex(&sealed),
+        "522dc1f099567d07f47f37a32a84427d643a8cdcbfe5c0c97598a2bd2555d1aa\
+         8cb08e48590dbb3da7b08b1056828838c5f61e6393ba7a0abcc9f662\
+         76fc6ece0f4e1768cddf8853bb2d551b"
+    );
+
+    // Opening checks the tag over the ciphertext and the associated data.
+    assert_eq!(
+        open(&key, &nonce, &aad, &sealed).as_deref(),
+        Some(&plaintext[..60])
+    );
+    assert_eq!(open(&key, &nonce, b"", &sealed), None);
+    let mut tampered = sealed.clone();
+    tampered[3] ^= 1;
+    assert_eq!(open(&key, &nonce, &aad, &tampered), None);
+    assert_eq!(open(&zero, &nonce, &aad, &sealed), None);
+    assert_eq!(open(&key, &nonce, &aad, &sealed[..15]), None);
+}
+
+#[test]
+fn keys_are_derived_with_pbkdf2_hmac_sha256() {
+    // RFC 7914, section 11 (the first 32 bytes of the 64-byte key).
+    assert_eq!(
+        hex(&derive_key("passwd", b"salt", 1)),
+        "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc"
+    );
+    assert_eq!(
+        hex(&derive_key("correct horse", b"synth", 1000)),
+        "8df5b2407c3d7072eed3d745b5fc4a1ef8867b28006d90969e4fe6f8b2f851a5"
+    );
+    assert_eq!(
+        derive_key("correct horse", b"synth", 1000),
+        derive_key("correct horse", b"synth", 1000)
+    );
+
+    let config = |text: &str| Config::from_json(&json::parse(text).unwrap());
+    assert_eq!(
+        config(r#"{"passphrase": "correct horse", "salt": "synth", "iterations": 1000}"#).unwrap(),
+        Config::from_key(derive_key("correct horse", b"synth", 1000))
+    );
+    let key = "00112233445566778899aabbccddeeff".repeat(2);
+    assert_eq!(
+        config(&format!(r#"{{"key": "{key}"}}"#)).unwrap(),
+        Config::from_key(unhex(&key).try_into().unwrap())
+    );
+    assert!(config(r#"{"key": "0011"}"#).is_err());
+    assert!(config(r#"{"passphrase": ""}"#).is_err());
+    assert!(config(r#"{"passphrase": "x", "iterations": 0}"#).is_err());
+    assert!(config("{}").is_err());
+    // The key is not printed.
+    assert!(!format!("{:?}", Config::from_key([7; 32])).contains("7"));
+}
+
+#[cfg(not(feature = "encryption"))]
+#[test]
+fn a_key_needs_the_feature() {
+    let _turn = TURN.lock().unwrap_or_else(|e| e.into_inner());
+    let err = encryption::configure(Some(Config::from_key([1; 32]))).unwrap_err();
+    assert_eq!(err.kind(), "unsupported");
+    assert!(!encryption::is_enabled());
+    encryption::configure(None).unwrap();
+}
+
+#[cfg(feature = "encryption")]
+#[test]
+fn persisted_records_are_sealed_and_older_ones_sealed_once() {
+    use synth_core::chat::ChatOptions;
+    use synth_core::session::Session;
+    use synth_core::usage::Tags;
+    use synth_core::{memory, project, store};
+
+    let _turn = TURN.lock().unwrap_or_else(|e| e.into_inner());
+    let dir = scratch("records");
+    let plain = dir.join("plain.json");
+    store::write(&plain, b"{\"written\": \"before\"}").unwrap();
+    fs::create_dir_all(dir.join("nested")).unwrap();
+    store::write(&dir.join("nested").join("older.json"), b"older").unwrap();
+
+    encryption::configure(Some(Config::from_passphrase(
+        "correct horse",
+        b"synth",
+        1000,
+    )))
+    .unwrap();
+    let record = dir.join("record.json");
+    store::write(&record, b"Quarterly revenue was 4.2M.").unwrap();
+    let raw = fs::read(&record).unwrap();
+    assert!(encryption::is_sealed(&raw));
+    assert!(!contains(&raw, b"revenue"));
+    assert_eq!(
+        store::read(&record).unwrap().as_deref(),
+        Some(&b"Quarterly revenue was 4.2M."[..])
+    );
+    // Each write has its own nonce.
+    store::write(&record, b"Quarterly revenue was 4.2M.").unwrap();
+    assert_ne!(fs::read(&record).unwrap(), raw);
+
+    // Records written before the key are refused, since anyone could
+    // have planted them, until they are sealed, once.
+    assert_eq!(store::read(&plain).unwrap_err().kind(), "unsupported");
+    assert_eq!(store::seal_existing(&[&dir]).unwrap(), 2);
+    assert!(encryption::is_sealed(&fs::read(&plain).unwrap()));
+    assert_eq!(
+        store::read_string(&plain).unwrap().as_deref(),
+        Some("{\"written\": \"before\"}")
+    );
+    assert_eq!(
+        store::read_string(&dir.join("nested").join("older.json"))
+            .unwrap()
+            .as_deref(),
+        Some("older")
+    );
+    assert_eq!(store::seal_existing(&[&dir]).unwrap(), 0);
+
+    // A sealed record is bound to its path: copied over another, it is
+    // refused like a changed one.
+    fs::copy(&record, &plain).unwrap();
+    assert_eq!(store::read(&plain).unwrap_err().kind(), "parse");
+    assert!(encryption::open(fs::read(&record).unwrap(), "elsewhere").is_err());
+
+    // A changed record is refused, as is one read with another key.
+    let mut tampered = fs::read(&record).unwrap();
+    let last = tampered.len() - 1;
+    tampered[last] ^= 1;
+    fs::write(dir.join("tampered.json"), tampered).unwrap();
+    assert_eq!(
+        store::read(&dir.join("tampered.json")).unwrap_err().kind(),
+        "parse"
+    );
+    encryption::configure(Some(Config::from_key([9; 32]))).unwrap();
+    assert_eq!(store::read(&record).unwrap_err().kind(), "parse");
+    encryption::configure(None).unwrap();
+    assert_eq!(store::read(&record).unwrap_err().kind(), "unsupported");
+    assert_eq!(
+        store::seal_existing(&[&dir]).unwrap_err().kind(),
+        "unsupported"
+    );
+
+    // Stores built on records keep working under a key.
+    encryption::configure(Some(Config::from_passphrase(
+        "correct horse",
+        b"synth",
+        1000,
+    )))
+    .unwrap();
+    let memories = dir.join("memories");
+    memory::init(&memories).unwrap();
+    memory::add("The fiscal year ends in March.", None, &Tags::default()).unwrap();
+    for name in fs::read_dir(&memories).unwrap() {
+        assert!(!contains(
+            &fs::read(name.unwrap().path()).unwrap(),
+            b"fiscal"
+        ));
+    }
+    memory::init(&memories).unwrap();
+    assert!(memory::list(&Tags::default())
+        .iter()
+        .any(|m| m.text.contains("fiscal")));
+
+    // A project exports plain and is sealed again when imported.
+    let root = dir.join("projects");
+    let alpha = project::create(&root, "alpha").unwrap();
+    let mut session = Session::new(ChatOptions::default());
+    session
+        .send("Summarize the merger terms.", |_| Ok("Noted.".into()))
+        .unwrap();
+    alpha.save_session("kickoff", &session).unwrap();
+    let archive = dir.join("alpha.tar");
+    alpha.export(&archive).unwrap();
+    assert!(contains(&fs::read(&archive).unwrap(), b"merger"));
+    project::delete(&root, "alpha").unwrap();
+    let imported = project::import(&archive, &root).unwrap();
+    let saved = root.join("alpha").join("sessions").join("kickoff.json");
+    assert!(encryption::is_sealed(&fs::read(saved).unwrap()));
+    assert_eq!(imported.sessions(), ["kickoff"]);
+    imported.load_session("kickoff").unwrap();
+
+    encryption::configure(None).unwrap();
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[cfg(feature = "encryption")]
+#[test]
+fn audit_lines_are_sealed_and_queried() {
+    use std::time::Duration;
+
+    use synth_core::audit::{self, Entry, Filter};
+    use synth_core::json::Value;
+
+    let _turn = TURN.lock().unwrap_or_else(|e| e.into_inner());
+    let dir = scratch("audit");
+    let config = format!(r#"{{"dir": {:?}}}"#, dir.display().to_string());
+    audit::configure(Some(
+        audit::Config::from_json(&json::parse(&config).unwrap()).unwrap(),
+    ))
+    .unwrap();
+    let entry = |prompt: &'static str| Entry {
+        prompt,
+        outcome: Ok("Noted."),
+        backend: Some("mock"),
+        model: None,
+        user_id: None,
+        tenant_id: None,
+        duration: Duration::from_millis(5),
+    };
+    audit::record(&entry("Plain before the key."));
+    encryption::configure(Some(Config::from_key([3; 32]))).unwrap();
+    audit::record(&entry("The acquisition closes in May."));
+
+    let log = fs::read_to_string(dir.join("audit.jsonl")).unwrap();
+    assert!(log.contains("Plain before the key."));
+    assert!(!log.contains("acquisition"));
+    // The plain line is refused until the log is sealed.
+    assert!(audit::query(&Filter::default()).is_err());
+    assert_eq!(audit::seal_existing().unwrap(), 1);
+    assert_eq!(audit::seal_existing().unwrap(), 0);
+    let log = fs::read_to_string(dir.join("audit.jsonl")).unwrap();
+    assert!(!log.contains("Plain before the key."));
+    let prompts: Vec<String> = audit::query(&Filter::default())
+        .unwrap()
+        .iter()
+        .filter_map(|r| r.get("prompt").and_then(Value::as_str).map(String::from))
+        .collect();
+    assert_eq!(
+        prompts,
+        ["Plain before the key.", "The acquisition closes in May."]
+    );
+
+    // Another key cannot read the log; only a torn last line is skipped.
+    encryption::configure(Some(Config::from_key([4; 32]))).unwrap();
+    audit::record(&entry("Under the second key."));
+    assert!(audit::query(&Filter::default()).is_err());
+    encryption::configure(None).unwrap();
+    audit::configure(None).unwrap();
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,83 @@
+//! Falling back along the configured chain when a backend fails or times
+//! out.
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::time::{Duration, Instant};
+
+use synth_core::backend::{self, Config};
+use synth_core::chat::{self, ChatOptions};
+use synth_core::json::{self, Value};
+
+fn kiro(command: &str, timeout_secs: Option<f64>) -> Value {
+    let mut pairs = vec![
+        ("type", Value::from("kiro")),
+        ("command", Value::from(command)),
+    ];
+    if let Some(secs) = timeout_secs {
+        pairs.push(("timeout_secs", Value::from(secs)));
+    }
+    Value::object(pairs)
+}
+
+#[test]
+fn failures_and_timeouts_move_down_the_chain() {
+    let dir = std::env::temp_dir().join(format!("synth-fallback-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let slow = dir.join("slow-kiro");
+    fs::write(&slow, "#!/bin/sh\nexec sleep 30\n").unwrap();
+    fs::set_permissions(&slow, fs::Permissions::from_mode(0o755)).unwrap();
+    let missing = dir.join("missing-kiro");
+
+    let config = Value::object([
+        ("default", Value::from("slow")),
+        (
+            "profiles",
+            Value::object([
+                ("slow", kiro(&slow.display().to_string(), Some(0.3))),
+                ("broken", kiro(&missing.display().to_string(), None)),
+                (
+                    "mock",
+                    json::parse(r#"{"type": "mock", "default": "from mock"}"#).unwrap(),
+                ),
+            ]),
+        ),
+        (
+            "fallback",
+            json::parse(r#"["broken", "slow", "mock"]"#).unwrap(),
+        ),
+    ]);
+    backend::configure(Config::from_json(&config).unwrap());
+
+    // The chain starts at the chosen profile and skips it later on.
+    let names = |chain: Vec<backend::Profile>| -> Vec<String> {
+        chain.into_iter().map(|p| p.name).collect()
+    };
+    assert_eq!(
+        names(backend::chain(None).unwrap()),
+        ["slow", "broken", "mock"]
+    );
+    assert_eq!(
+        names(backend::chain(Some("mock")).unwrap()),
+        ["mock", "broken", "slow"]
+    );
+
+    let started = Instant::now();
+    assert_eq!(chat::kiro_chat("hi").unwrap(), "from mock");
+    assert!(started.elapsed() < Duration::from_secs(10));
+    assert_eq!(backend::last_answered().as_deref(), Some("mock"));
+
+    // With every profile failing the last error is returned.
+    let chain = backend::chain(Some("broken")).unwrap();
+    let err = backend::send_with_fallback(&chain[..2], "hi", None).unwrap_err();
+    assert_eq!(err.to_string(), "backend error: backend timed out");
+    let options = ChatOptions {
+        backend: Some("broken".into()),
+        ..ChatOptions::default()
+    };
+    assert_eq!(chat::chat("hi", &options).unwrap(), "from mock");
+
+    backend::configure(Config::default());
+    let _ = fs::remove_dir_all(&dir);
+}
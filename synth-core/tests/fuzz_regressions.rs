@@ -0,0 +1,30 @@
+//! Inputs that once crashed an extractor, from `tests/crashes`. Minimized
+//! fuzzer artifacts (`fuzz/artifacts/<target>/crash-*`) are added there
+//! with the extension of the format that failed.
+
+use std::path::Path;
+
+use synth_core::extract::{extract_bytes, extract_from_bytes_fallible, Format};
+
+#[test]
+fn crash_fixtures_return_without_panicking() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/crashes");
+    let mut fixtures = 0;
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        let format = Format::from_path(&path).unwrap_or(Format::Text);
+        let bytes = std::fs::read(&path).unwrap();
+        // Malformed input may fail, but must not panic or overflow the stack.
+        let _ = extract_bytes(&bytes, format);
+        let _ = extract_from_bytes_fallible(&bytes, format);
+        fixtures += 1;
+    }
+    assert!(fixtures > 0);
+}
+
+#[test]
+fn deep_xml_is_rejected() {
+    let depth = 100_000;
+    let xml = format!("{}{}", "<a>".repeat(depth), "</a>".repeat(depth));
+    assert!(extract_from_bytes_fallible(xml.as_bytes(), Format::Xml).is_err());
+}
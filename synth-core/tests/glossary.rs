@@ -0,0 +1,72 @@
+//! Glossaries: relevant terms go into the prompt and replies that break
+//! them are sent back.
+
+use synth_core::backend::{self, Config};
+use synth_core::chat::{self, ChatOptions};
+use synth_core::error::Error;
+use synth_core::glossary::{self, Glossary};
+use synth_core::json;
+
+#[test]
+fn replies_are_held_to_the_glossary() {
+    let entries = json::parse(
+        r#"[{"term": "purchase order", "use": "Bestellung", "avoid": ["Kaufauftrag"]},
+            {"term": "invoice", "use": "Rechnung"}]"#,
+    )
+    .unwrap();
+    glossary::define("de", Glossary::from_json(&entries).unwrap());
+    let defined = glossary::glossary("de").unwrap();
+    assert_eq!(
+        glossary::check(
+            &defined,
+            "Translate: The purchase order is due.",
+            "Der Kaufauftrag ist fällig."
+        ),
+        [
+            "\"purchase order\" must be rendered as \"Bestellung\"",
+            "use \"Bestellung\" instead of \"Kaufauftrag\""
+        ]
+    );
+    // Terms missing from the source are not required.
+    assert!(glossary::check(
+        &defined,
+        "Translate: The Purchase-Order.",
+        "Die Bestellung."
+    )
+    .is_empty());
+
+    let config = json::parse(
+        r#"{"profiles": {"m": {"type": "mock",
+            "rules": [{"contains": "broke the terminology", "reply": "Die Bestellung ist fällig."},
+                      {"contains": "write \"Bestellung\"", "reply": "Der Kaufauftrag ist fällig."}],
+            "default": "no terminology given"}}}"#,
+    )
+    .unwrap();
+    backend::configure(Config::from_json(&config).unwrap());
+    let options = ChatOptions {
+        glossary: Some("de".into()),
+        ..ChatOptions::default()
+    };
+    assert_eq!(
+        chat::chat("Translate: The purchase order is due.", &options).unwrap(),
+        "Die Bestellung ist fällig."
+    );
+
+    glossary::define(
+        "strict",
+        Glossary::from_json(
+            &json::parse(r#"[{"term": "purchase order", "use": "Auftrag"}]"#).unwrap(),
+        )
+        .unwrap(),
+    );
+    let strict = ChatOptions {
+        glossary: Some("strict".into()),
+        ..ChatOptions::default()
+    };
+    let err = chat::chat("Translate: The purchase order is due.", &strict).unwrap_err();
+    assert!(
+        matches!(err, Error::Terminology(ref v) if v == &["\"purchase order\" must be rendered as \"Auftrag\""])
+    );
+    assert!(glossary::remove("strict"));
+    assert!(chat::chat("hi", &strict).is_err());
+}
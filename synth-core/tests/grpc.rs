@@ -0,0 +1,152 @@
+//! `synth-grpc` end to end, on an ephemeral port with a mock backend,
+//! through a client generated from the same proto.
+
+#![cfg(feature = "grpc")]
+
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+
+use tonic::Code;
+
+#[allow(clippy::all)]
+mod proto {
+    tonic::include_proto!("synth.v1");
+}
+
+use proto::chat_event::Event;
+use proto::synth_client::SynthClient;
+use proto::{AskRequest, ChatRequest, ChunkRequest, Document};
+
+struct Server {
+    child: Child,
+    addr: String,
+}
+
+impl Server {
+    fn start() -> Server {
+        let config = std::env::temp_dir().join(format!("synth-grpc-{}.json", std::process::id()));
+        std::fs::write(
+            &config,
+            r#"{"profiles": {"mock": {"type": "mock", "default": "echo: {prompt}"}}}"#,
+        )
+        .unwrap();
+        let mut child = Command::new(env!("CARGO_BIN_EXE_synth-grpc"))
+            .args(["--addr", "127.0.0.1:0", "--backends"])
+            .arg(&config)
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut line = String::new();
+        BufReader::new(child.stderr.take().unwrap())
+            .read_line(&mut line)
+            .unwrap();
+        let addr = line.trim().rsplit(' ').next().unwrap().to_string();
+        Server { child, addr }
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn document(name: &str, content: &str) -> Option<Document> {
+    Some(Document {
+        name: name.into(),
+        content: content.as_bytes().to_vec(),
+    })
+}
+
+/// The events of a streamed reply, in order.
+async fn events(
+    stream: Result<tonic::Response<tonic::Streaming<proto::ChatEvent>>, tonic::Status>,
+) -> Vec<Event> {
+    let mut stream = stream.unwrap().into_inner();
+    let mut events = Vec::new();
+    while let Some(event) = stream.message().await.unwrap() {
+        events.extend(event.event);
+    }
+    events
+}
+
+#[test]
+fn serves_extraction_and_streamed_chat() {
+    let server = Server::start();
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime.block_on(async {
+        let mut client = SynthClient::connect(server.addr.clone()).await.unwrap();
+
+        let extracted = client
+            .extract(document("data.csv", "a,b\n1,2\n").unwrap())
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(extracted.name, "data.csv");
+        assert_eq!(extracted.text, "| a | b |\n| --- | --- |\n| 1 | 2 |\n");
+
+        let chunks = client
+            .chunk(ChunkRequest {
+                document: document("notes.txt", &"word ".repeat(100)),
+                max_chars: 100,
+            })
+            .await
+            .unwrap()
+            .into_inner()
+            .chunks;
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks[0].start, 0);
+        assert!(chunks.windows(2).all(|pair| pair[0].end == pair[1].start));
+
+        let chat = client.chat(ChatRequest {
+            prompt: "hello".into(),
+            options: None,
+        });
+        assert_eq!(
+            events(chat.await).await,
+            [Event::Text("echo: hello".into()), Event::Done(true)]
+        );
+
+        let ask = client.ask(AskRequest {
+            document: document("notes.txt", "The fee is due in May."),
+            question: "When is the fee due?".into(),
+            options: None,
+        });
+        match &events(ask.await).await[..] {
+            [Event::Text(answer), Event::Done(true)] => {
+                assert!(answer.contains("The fee is due in May."), "{answer}")
+            }
+            other => panic!("{other:?}"),
+        }
+
+        // Bad input fails the call rather than the stream.
+        let missing = client
+            .ask(AskRequest {
+                document: document("notes.txt", "text"),
+                question: " ".into(),
+                options: None,
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(missing.code(), Code::InvalidArgument);
+        let unknown = client
+            .extract(document("image.xyz", "?").unwrap())
+            .await
+            .unwrap_err();
+        assert_eq!(unknown.code(), Code::InvalidArgument);
+
+        // A failing backend ends the stream with an error event.
+        let failed = client.chat(ChatRequest {
+            prompt: "hello".into(),
+            options: Some(proto::ChatOptions {
+                backend: "missing".into(),
+                ..Default::default()
+            }),
+        });
+        match &events(failed.await).await[..] {
+            [Event::Error(error)] => assert!(!error.kind.is_empty(), "{error:?}"),
+            other => panic!("{other:?}"),
+        }
+    });
+}
@@ -0,0 +1,30 @@
+//! Guardrail hooks around chat backend calls.
+
+use std::sync::Arc;
+
+use synth_core::hooks::{self, Stage};
+
+#[test]
+fn hooks_chain_in_order_and_ids_are_not_reused() {
+    let redact = hooks::register(
+        Stage::Pre,
+        Arc::new(|text: &str| text.replace("secret", "[x]")),
+    );
+    let shout = hooks::register(Stage::Pre, Arc::new(|text: &str| text.to_uppercase()));
+    let tag = hooks::register(Stage::Post, Arc::new(|text: &str| format!("{text}!")));
+    assert_eq!(hooks::apply(Stage::Pre, "a secret"), "A [X]");
+    assert_eq!(hooks::apply(Stage::Post, "done"), "done!");
+
+    // A hook registered after one is removed gets a fresh id, so the old
+    // id cannot remove it.
+    assert!(hooks::unregister(tag));
+    let again = hooks::register(Stage::Post, Arc::new(|text: &str| format!("{text}?")));
+    assert_ne!(again, tag);
+    assert!(!hooks::unregister(tag));
+    assert_eq!(hooks::apply(Stage::Post, "done"), "done?");
+
+    for id in [redact, shout, again] {
+        assert!(hooks::unregister(id));
+    }
+    assert_eq!(hooks::apply(Stage::Pre, "a secret"), "a secret");
+}
@@ -0,0 +1,66 @@
+//! The plain-HTTP client: body limits and where API keys may be sent.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::thread;
+
+use synth_core::backend::{Profile, Secret};
+use synth_core::http::{self, RequestOptions, Url};
+use synth_core::json;
+
+/// A server that answers one request with `response`.
+fn serve(response: &'static str) -> Url {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+    thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        while reader.read_line(&mut line).unwrap() > 2 {
+            line.clear();
+        }
+        let _ = reader.get_mut().write_all(response.as_bytes());
+    });
+    url
+}
+
+#[test]
+fn refuses_bodies_past_the_limit() {
+    let ok = serve("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n2\r\nok\r\n0\r\n\r\n");
+    let response = http::post_json(&ok, "{}", &RequestOptions::default()).unwrap();
+    assert_eq!((response.status, response.body.as_str()), (200, "ok"));
+
+    for huge in [
+        "HTTP/1.1 200 OK\r\nContent-Length: 99999999999999\r\n\r\n",
+        "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n2\r\nok\r\nffffffffffffffff\r\n",
+    ] {
+        let err = http::post_json(&serve(huge), "{}", &RequestOptions::default()).unwrap_err();
+        assert_eq!(err.kind(), "backend", "{err}");
+    }
+}
+
+#[test]
+fn api_keys_are_only_sent_to_this_machine() {
+    let profile = |extra: &str| {
+        Profile::from_json(
+            "p",
+            &json::parse(&format!(
+                r#"{{"type": "ollama", "model": "m", "api_key": "sk-123", {extra}}}"#
+            ))
+            .unwrap(),
+        )
+    };
+    assert!(profile(r#""url": "http://localhost:11434""#).is_ok());
+    assert!(profile(r#""url": "http://127.0.0.1:11434""#).is_ok());
+    assert!(profile(r#""url": "http://[::1]:11434""#).is_ok());
+    assert!(profile(r#""url": "http://models.example.com""#).is_err());
+    assert!(
+        profile(r#""url": "http://localhost:1", "proxy": "http://proxy.example.com:3128""#)
+            .is_err()
+    );
+
+    let secret = format!("{:?}", Secret::Literal("sk-123".into()));
+    assert!(!secret.contains("sk-123"), "{secret}");
+    let debug = format!("{:?}", profile(r#""url": "http://localhost:1""#).unwrap());
+    assert!(!debug.contains("sk-123"), "{debug}");
+}
@@ -0,0 +1,76 @@
+//! URL and pasted-text ingestion. The fetch test serves a page from a local
+//! listener and needs `curl` on the PATH.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::thread;
+
+use synth_core::{extract, html, ingest};
+
+const PAGE: &str = "<html><head><title>Tides | Sea Notes</title></head><body>\
+    <nav><a href=\"/\">Home</a></nav>\
+    <div class=\"post-content\"><h1>Tides</h1>\
+    <p>Tides rise and fall twice a day, pulled by the moon and, to a lesser degree, the sun.</p>\
+    <p>Spring tides, the largest, come when the sun and moon line up.</p></div>\
+    <div class=\"related-links\"><p>More posts, more reading, more links, more noise.</p></div>\
+    </body></html>";
+
+#[test]
+fn article_drops_page_chrome() {
+    let article = html::article(PAGE);
+    assert_eq!(article.title.as_deref(), Some("Tides | Sea Notes"));
+    assert_eq!(
+        article.text,
+        "# Tides\n\nTides rise and fall twice a day, pulled by the moon and, to a lesser \
+         degree, the sun.\n\nSpring tides, the largest, come when the sun and moon line up.\n"
+    );
+}
+
+#[test]
+fn extracts_a_fetched_page() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        while reader.read_line(&mut line).unwrap() > 2 {
+            line.clear();
+        }
+        write!(
+            reader.get_mut(),
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\n\
+             Content-Length: {}\r\nConnection: close\r\n\r\n{PAGE}",
+            PAGE.len()
+        )
+        .unwrap();
+    });
+    let text = ingest::extract_url(&format!("http://{addr}/tides")).unwrap();
+    server.join().unwrap();
+    assert!(text.starts_with("# Tides\n\nTides rise"), "{text}");
+    assert!(!text.contains("Home") && !text.contains("noise"), "{text}");
+    assert!(ingest::extract_url("file:///etc/passwd").is_err());
+}
+
+#[test]
+fn ingested_text_is_a_document() {
+    let dir = std::env::temp_dir().join(format!("synth-ingest-test-{}", std::process::id()));
+    ingest::set_dir(Some(dir.clone()));
+    let path = ingest::ingest_text("First line\r\nsecond line\n", "Meeting: Q3 plan").unwrap();
+    assert!(path.starts_with(&dir));
+    assert!(path
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .starts_with("meeting-q3-plan-"));
+    assert_eq!(
+        extract::extract_text(&path).unwrap(),
+        "# Meeting: Q3 plan\n\nFirst line\nsecond line\n"
+    );
+    assert_eq!(
+        ingest::ingest_text("First line\nsecond line", "Meeting: Q3 plan").unwrap(),
+        path
+    );
+    assert!(ingest::ingest_text(" \n", "empty").is_err());
+    let _ = std::fs::remove_dir_all(&dir);
+}
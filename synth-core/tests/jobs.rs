@@ -0,0 +1,191 @@
+//! The background job queue: cancelling kills the job's backend, a
+//! panicking job fails without stopping the worker, and restored jobs
+//! resume and keep their ids.
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use synth_core::backend::{self, Config};
+use synth_core::hooks::{self, Stage};
+use synth_core::jobs::{self, Job, State};
+use synth_core::json::{self, Value};
+
+fn dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("synth-jobs-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Every test configures the same profiles, so running in parallel is
+/// harmless: "slow" records its pid and sleeps, "mock" echoes.
+fn configure(dir: &Path) -> PathBuf {
+    let pid = dir.join("pid");
+    let script = dir.join("kiro-cli");
+    fs::write(
+        &script,
+        format!("#!/bin/sh\necho $$ > '{}'\nexec sleep 30\n", pid.display()),
+    )
+    .unwrap();
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+    let config = json::parse(&format!(
+        r#"{{"profiles": {{"slow": {{"type": "kiro", "command": {:?}}},
+            "mock": {{"type": "mock", "default": "echo"}}}}}}"#,
+        script.display().to_string()
+    ))
+    .unwrap();
+    backend::configure(Config::from_json(&config).unwrap());
+    pid
+}
+
+fn minutes(dir: &Path, transcript: &str, backend: &str) -> Value {
+    let path = dir.join(format!("{backend}.txt"));
+    fs::write(&path, transcript).unwrap();
+    Value::object([
+        ("path", Value::from(path.display().to_string())),
+        ("backend", Value::from(backend)),
+    ])
+}
+
+fn extract(dir: &Path) -> Value {
+    let path = dir.join("note.txt");
+    fs::write(&path, "a note").unwrap();
+    Value::object([("path", Value::from(path.display().to_string()))])
+}
+
+fn wait_for(id: u64, state: State) -> Job {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        let job = jobs::status(id).unwrap();
+        if job.state == state {
+            return job;
+        }
+        assert!(Instant::now() < deadline, "job {id} stuck: {job:?}");
+        thread::sleep(Duration::from_millis(20));
+    }
+}
+
+#[test]
+fn cancelling_a_running_job_kills_its_backend() {
+    let dir = dir("cancel");
+    let pid = configure(&dir);
+    let id = jobs::submit("generate_minutes", minutes(&dir, "Ann: Ship it.\n", "slow")).unwrap();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let pid = loop {
+        if let Some(pid) = fs::read_to_string(&pid).ok().filter(|p| p.ends_with('\n')) {
+            break pid.trim().to_string();
+        }
+        assert!(Instant::now() < deadline, "the backend never started");
+        thread::sleep(Duration::from_millis(20));
+    };
+    wait_for(id, State::Running);
+
+    let began = Instant::now();
+    assert!(jobs::cancel(id));
+    assert!(!jobs::cancel(id));
+    assert_eq!(jobs::status(id).unwrap().state, State::Cancelled);
+    let alive = Command::new("kill")
+        .args(["-0", &pid])
+        .stderr(Stdio::null())
+        .status()
+        .unwrap();
+    assert!(!alive.success(), "the backend outlived the cancel");
+
+    // The worker moves on rather than waiting out the sleep.
+    let next = jobs::submit("extract_text", extract(&dir)).unwrap();
+    assert_eq!(
+        wait_for(next, State::Done).result.as_deref(),
+        Some("a note")
+    );
+    assert!(began.elapsed() < Duration::from_secs(10));
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn a_panicking_job_fails_and_the_queue_carries_on() {
+    let dir = dir("panic");
+    configure(&dir);
+    let hook = hooks::register(
+        Stage::Pre,
+        Arc::new(|prompt: &str| {
+            if prompt.contains("EXPLODE") {
+                panic!("hook exploded");
+            }
+            prompt.to_string()
+        }),
+    );
+    let id = jobs::submit("generate_minutes", minutes(&dir, "Ann: EXPLODE.\n", "mock")).unwrap();
+    let failed = wait_for(id, State::Failed);
+    assert_eq!(failed.error.as_deref(), Some("job panicked: hook exploded"));
+
+    let next = jobs::submit("extract_text", extract(&dir)).unwrap();
+    wait_for(next, State::Done);
+    hooks::unregister(hook);
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn restored_jobs_resume_and_ids_continue_past_them() {
+    let dir = dir("restart");
+    let note = dir.join("note.txt");
+    fs::write(&note, "restored").unwrap();
+    let params = format!(r#"{{"path": {:?}}}"#, note.display().to_string());
+    fs::write(
+        dir.join("70.json"),
+        format!(r#"{{"id": 70, "kind": "extract_text", "state": "running", "params": {params}}}"#),
+    )
+    .unwrap();
+    fs::write(
+        dir.join("120.json"),
+        r#"{"id": 120, "kind": "extract_text", "state": "done", "result": "old"}"#,
+    )
+    .unwrap();
+
+    jobs::init(&dir).unwrap();
+    assert_eq!(
+        wait_for(70, State::Done).result.as_deref(),
+        Some("restored")
+    );
+    assert_eq!(jobs::result(120).as_deref(), Some("old"));
+    let id = jobs::submit("extract_text", json::parse(&params).unwrap()).unwrap();
+    assert!(id > 120, "{id}");
+    wait_for(id, State::Done);
+    assert!(dir.join(format!("{id}.json")).exists());
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn documents_are_summarized_and_translated_as_jobs() {
+    let dir = dir("summarize");
+    configure(&dir);
+    assert!(jobs::KINDS.contains(&"summarize") && jobs::KINDS.contains(&"translate"));
+    let params = minutes(&dir, "The renewal terms were agreed.", "mock");
+    let mut stages = Vec::new();
+    let summary = jobs::run("summarize", &params, |p| stages.push(p.stage.name())).unwrap();
+    assert_eq!(summary, "echo");
+    assert_eq!(stages[0], "extracting");
+
+    let id = jobs::submit("summarize", params.clone()).unwrap();
+    assert_eq!(wait_for(id, State::Done).result.as_deref(), Some("echo"));
+
+    let mut translate = params.clone();
+    assert_eq!(
+        jobs::run("translate", &translate, |_| {})
+            .unwrap_err()
+            .kind(),
+        "parse"
+    );
+    if let Value::Object(pairs) = &mut translate {
+        pairs.push((
+            "options".into(),
+            json::parse(r#"{"target": "German"}"#).unwrap(),
+        ));
+    }
+    assert_eq!(jobs::run("translate", &translate, |_| {}).unwrap(), "echo");
+    let _ = fs::remove_dir_all(&dir);
+}
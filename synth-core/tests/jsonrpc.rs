@@ -0,0 +1,77 @@
+//! `synth --jsonrpc` end to end with a mock backend.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use synth_core::json::{self, Value};
+
+#[test]
+fn answers_requests_until_shutdown() {
+    let config = std::env::temp_dir().join(format!("synth-jsonrpc-{}.json", std::process::id()));
+    std::fs::write(
+        &config,
+        r#"{"profiles": {"mock": {"type": "mock", "default": "echo: {prompt}"}}}"#,
+    )
+    .unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_synth"))
+        .arg("--jsonrpc")
+        .arg("--backends")
+        .arg(&config)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(
+            concat!(
+                r#"{"jsonrpc": "2.0", "id": 1, "method": "extract", "params": {"text": "a,b\n1,2", "name": "t.csv"}}"#,
+                "\n",
+                r#"{"jsonrpc": "2.0", "id": 2, "method": "chat", "params": {"prompt": "hi"}}"#,
+                "\n",
+                r#"{"jsonrpc": "2.0", "id": 3, "method": "missing"}"#,
+                "\n",
+                r#"{"jsonrpc": "2.0", "id": 4, "method": "shutdown"}"#,
+                "\n",
+            )
+            .as_bytes(),
+        )
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let replies: Vec<Value> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| json::parse(line).unwrap())
+        .collect();
+    let reply = |id: f64| {
+        replies
+            .iter()
+            .find(|r| r.get("id").and_then(Value::as_f64) == Some(id))
+            .unwrap_or_else(|| panic!("no reply to {id}: {replies:?}"))
+    };
+    let result = |id, key| {
+        reply(id)
+            .get("result")
+            .and_then(|r| r.get(key))
+            .and_then(Value::as_str)
+            .map(String::from)
+    };
+    assert_eq!(
+        result(1.0, "text").as_deref(),
+        Some("| a | b |\n| --- | --- |\n| 1 | 2 |\n")
+    );
+    assert_eq!(result(2.0, "reply").as_deref(), Some("echo: hi"));
+    let code = reply(3.0)
+        .get("error")
+        .and_then(|e| e.get("code"))
+        .and_then(Value::as_f64);
+    assert_eq!(code, Some(-32601.0));
+    // The shutdown reply comes last.
+    assert_eq!(
+        replies.last().and_then(|r| r.get("id")),
+        Some(&Value::from(4.0))
+    );
+}
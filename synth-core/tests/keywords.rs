@@ -0,0 +1,31 @@
+//! RAKE keyphrase extraction.
+
+use synth_core::keywords::extract_keywords;
+
+#[test]
+fn scores_phrases_once_best_first() {
+    let text = "Compatibility of systems of linear constraints. Linear constraints \
+                and linear diophantine equations. Linear constraints again.";
+    let keywords = extract_keywords(text, 0);
+    let phrases: Vec<&str> = keywords.iter().map(|k| k.phrase.as_str()).collect();
+    assert_eq!(
+        phrases,
+        [
+            "linear diophantine equations",
+            "linear constraints",
+            "compatibility",
+            "systems"
+        ]
+    );
+    assert!(keywords.windows(2).all(|w| w[0].score >= w[1].score));
+    assert_eq!(extract_keywords(text, 2).len(), 2);
+}
+
+#[test]
+fn many_distinct_phrases_stay_fast() {
+    // Quadratic deduplication took seconds on this many candidates.
+    let text: String = (0..40_000).map(|i| format!("term{i} and ")).collect();
+    let started = std::time::Instant::now();
+    assert_eq!(extract_keywords(&text, 0).len(), 40_000);
+    assert!(started.elapsed().as_secs() < 5);
+}
@@ -0,0 +1,38 @@
+//! Extraction passes reported as deltas.
+
+use synth_core::live::{self, Delta, PREVIEW_BYTES};
+
+#[test]
+fn large_files_are_previewed_before_the_full_text() {
+    let dir = std::env::temp_dir();
+    let large = dir.join(format!("synth-live-{}.md", std::process::id()));
+    // Three-byte characters, so the preview cut falls inside one.
+    let body = "# Report\n\n".to_string() + &"€".repeat(PREVIEW_BYTES);
+    std::fs::write(&large, &body).unwrap();
+    let mut passes = Vec::new();
+    let text = live::extract_live(&large, |delta| passes.push(delta.clone())).unwrap();
+    assert_eq!(text, body);
+    let names: Vec<&str> = passes.iter().map(Delta::name).collect();
+    assert_eq!(names, ["preview", "text", "outline", "stats"]);
+    let Delta::Preview(preview) = &passes[0] else {
+        unreachable!()
+    };
+    assert!(body.starts_with(preview.as_str()));
+    assert!(preview.len() <= PREVIEW_BYTES && preview.len() > PREVIEW_BYTES - 3);
+    let outline = passes[2].to_json();
+    assert_eq!(
+        outline
+            .get("outline")
+            .and_then(|o| o.as_array())
+            .map(<[_]>::len),
+        Some(1)
+    );
+
+    let small = dir.join(format!("synth-live-{}-small.md", std::process::id()));
+    std::fs::write(&small, "short").unwrap();
+    let mut names = Vec::new();
+    live::extract_live(&small, |delta| names.push(delta.name())).unwrap();
+    assert_eq!(names, ["text", "outline", "stats"]);
+    let _ = std::fs::remove_file(&large);
+    let _ = std::fs::remove_file(&small);
+}
@@ -0,0 +1,58 @@
+//! llama.cpp profiles, run offline through `llama-cli` with the
+//! `local-llm` feature.
+
+use synth_core::backend::Profile;
+use synth_core::json;
+
+#[cfg(feature = "local-llm")]
+#[test]
+fn llama_cli_gets_the_model_prompt_and_seed() {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    use synth_core::backend;
+
+    let dir = std::env::temp_dir().join(format!("synth-llama-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    // A stand-in for llama-cli that replies with its arguments.
+    let command = dir.join("llama-cli");
+    fs::write(&command, "#!/bin/sh\necho \"$@\"\n").unwrap();
+    fs::set_permissions(&command, fs::Permissions::from_mode(0o755)).unwrap();
+    let model = dir.join("tiny.gguf");
+    let profile = |model: &std::path::Path| {
+        let config = json::parse(&format!(
+            r#"{{"type": "llama.cpp", "model": {:?}, "command": {:?}, "max_tokens": 64}}"#,
+            model.display().to_string(),
+            command.display().to_string()
+        ))
+        .unwrap();
+        Profile::from_json("local", &config).unwrap()
+    };
+
+    // The model file has to exist before anything is run.
+    let err = backend::send(&profile(&model), "hi", None).unwrap_err();
+    assert!(err.to_string().contains("model not found"), "{err}");
+
+    fs::write(&model, b"GGUF").unwrap();
+    let local = profile(&model);
+    assert_eq!(local.kind.name(), "llama.cpp");
+    assert_eq!(local.model().as_deref(), Some("tiny.gguf"));
+    let reply = backend::send(&local, "Summarise.", Some(7)).unwrap();
+    assert_eq!(
+        reply,
+        format!(
+            "-m {} -p Summarise. -n 64 --no-display-prompt -no-cnv --log-disable --seed 7",
+            model.display()
+        )
+    );
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[cfg(not(feature = "local-llm"))]
+#[test]
+fn llama_profiles_need_the_feature() {
+    let config = json::parse(r#"{"type": "llama.cpp", "model": "/m.gguf"}"#).unwrap();
+    let err = Profile::from_json("local", &config).unwrap_err();
+    assert_eq!(err.kind(), "unsupported");
+}
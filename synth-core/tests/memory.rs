@@ -0,0 +1,71 @@
+//! Long-term memory: storing facts once, recalling the relevant ones and
+//! extracting them from text.
+
+use std::fs;
+
+use synth_core::json;
+use synth_core::memory;
+use synth_core::usage::Tags;
+
+#[test]
+fn facts_are_stored_once_and_recalled_when_relevant() {
+    let tags = Tags::new(Some("memory-recall"), None);
+    let first = memory::add("The supplier contract renews in March", None, &tags).unwrap();
+    assert!(memory::add("the supplier contract renews in march", None, &tags).is_none());
+    assert!(memory::add("   ", None, &tags).is_none());
+    memory::add("Invoices go to the finance team", Some("notes.md"), &tags).unwrap();
+
+    let prompt = "When does the supplier contract renew?";
+    let recalled = memory::recall(prompt, &tags);
+    assert!(
+        recalled.starts_with("Facts remembered from earlier conversations"),
+        "{recalled}"
+    );
+    assert!(recalled.contains("- The supplier contract renews in March\n"));
+    assert!(!recalled.contains("finance"));
+    assert!(recalled.ends_with(&format!("\n\n{prompt}")));
+    assert_eq!(memory::recall("Weather today?", &tags), "Weather today?");
+
+    let best = memory::search("finance invoices", 1, &tags);
+    assert_eq!(best.len(), 1);
+    assert_eq!(best[0].0.source.as_deref(), Some("notes.md"));
+
+    assert!(memory::update(
+        first.id,
+        "The supplier contract renews in April",
+        &tags
+    ));
+    assert!(memory::recall(prompt, &tags).contains("renews in April"));
+    assert!(memory::remove(first.id, &tags));
+    assert!(!memory::remove(first.id, &tags));
+    assert_eq!(memory::recall(prompt, &tags), prompt);
+}
+
+#[test]
+fn extracted_facts_persist_under_the_configured_dir() {
+    let dir = std::env::temp_dir().join(format!("synth-memory-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    memory::init(&dir).unwrap();
+    let tags = Tags::new(Some("memory-extract"), None);
+    let added = memory::extract("Ana: I prefer summaries in German.", Some("call"), &tags, |prompt| {
+        assert!(prompt.contains("Ana: I prefer summaries in German."));
+        Ok(r#"Sure: ["Ana prefers summaries in German", "Ana prefers summaries in German", ""]"#.into())
+    })
+    .unwrap();
+    assert_eq!(added.len(), 1);
+    assert_eq!(added[0].source.as_deref(), Some("call"));
+
+    let saved = json::parse(&fs::read_to_string(dir.join("memories.json")).unwrap()).unwrap();
+    assert!(
+        saved
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|m| m.get("text").and_then(|t| t.as_str())
+                == Some("Ana prefers summaries in German"))
+    );
+
+    let err = memory::extract("x", None, &tags, |_| Ok(r#"{"facts": 1}"#.into())).unwrap_err();
+    assert_eq!(err.kind(), "backend");
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,75 @@
+//! Meeting minutes: speakers, per-part replies merged, and rendering.
+
+use synth_core::json;
+use synth_core::minutes::{generate_minutes, speakers, MinutesFormat, MinutesOptions};
+
+#[test]
+fn speakers_are_found_after_timestamps() {
+    let transcript = "\
+[00:00:01] Ana Ruiz: Let's start.
+00:00:09 Bo: Agreed.
+just text: lowercase names are not speakers
+Ana Ruiz: Next item.
+Q3 plan:
+";
+    assert_eq!(speakers(transcript), ["Ana Ruiz", "Bo"]);
+}
+
+#[test]
+fn replies_for_each_part_are_merged() {
+    let line = "Ana: We discussed the renewal terms at some length today.\n";
+    let transcript = line.repeat(40);
+    let options = MinutesOptions::from_json(
+        &json::parse(r#"{"format": "markdown", "max_chunk_chars": 1200}"#).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(options.format, MinutesFormat::Markdown);
+
+    let mut prompts = Vec::new();
+    let minutes = generate_minutes(
+        &transcript,
+        &options,
+        |prompt| {
+            prompts.push(prompt.to_string());
+            Ok(format!(
+                r#"Here you go: {{"summary": "Part {}.", "decisions": ["Renew", " "],
+                "action_items": [{{"owner": "Ana", "task": "Send terms", "due": null}},
+                {{"owner": null, "task": ""}}]}}"#,
+                prompts.len()
+            ))
+        },
+        |_| {},
+    )
+    .unwrap();
+    assert_eq!(prompts.len(), 2);
+    assert!(prompts[0].contains("Speakers: Ana\n"));
+    assert!(prompts[1].contains("This is part 2 of 2."));
+    assert_eq!(minutes.attendees, ["Ana"]);
+    assert_eq!(minutes.summary, ["Part 1.", "Part 2."]);
+    assert_eq!(minutes.decisions, ["Renew", "Renew"]);
+    assert_eq!(minutes.action_items.len(), 2);
+    assert_eq!(minutes.action_items[0].owner.as_deref(), Some("Ana"));
+    assert_eq!(minutes.action_items[0].due, None);
+
+    let markdown = minutes.to_markdown();
+    assert!(markdown.contains("## Attendees\n\n- Ana\n"));
+    assert!(markdown.contains("- [ ] Send terms — Ana\n"));
+    assert_eq!(
+        minutes.to_json().get("summary").and_then(|s| s.as_str()),
+        Some("Part 1.\n\nPart 2.")
+    );
+}
+
+#[test]
+fn a_reply_without_json_fails() {
+    let err = generate_minutes(
+        "Ana: hello",
+        &MinutesOptions::default(),
+        |_| Ok("I could not do that.".into()),
+        |_| {},
+    )
+    .unwrap_err();
+    assert_eq!(err.kind(), "backend");
+    let bad = json::parse(r#"{"format": "pdf"}"#).unwrap();
+    assert_eq!(MinutesOptions::from_json(&bad).unwrap_err().kind(), "parse");
+}
@@ -0,0 +1,53 @@
+//! The mock backend: rules, cycling replies and fixtures.
+
+use std::fs;
+
+use synth_core::backend::{self, Config};
+use synth_core::chat;
+use synth_core::json;
+use synth_core::mock::Script;
+
+#[test]
+fn scripts_answer_by_rule_then_reply_then_default() {
+    let script = Script::from_json(
+        &json::parse(
+            r#"{"rules": [{"contains": "minutes", "reply": "Minutes for: {prompt}"}],
+                "replies": ["first", "second"], "default": "unused"}"#,
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let reply = |prompt| script.reply("mock-cycle", prompt).unwrap();
+    assert_eq!(reply("write minutes"), "Minutes for: write minutes");
+    assert_eq!(reply("a"), "first");
+    assert_eq!(reply("b"), "second");
+    assert_eq!(reply("c"), "first");
+
+    let fallback = Script::from_json(&json::parse(r#"{"default": "n/a"}"#).unwrap()).unwrap();
+    assert_eq!(fallback.reply("mock-default", "x").unwrap(), "n/a");
+    let silent = Script::default();
+    assert_eq!(
+        silent.reply("mock-silent", "x").unwrap_err().kind(),
+        "backend"
+    );
+    let bad = json::parse(r#"{"rules": [{"contains": "x"}]}"#).unwrap();
+    assert_eq!(Script::from_json(&bad).unwrap_err().kind(), "parse");
+
+    // Fixtures hold the same object, and configuring restarts the replies.
+    let dir = std::env::temp_dir().join(format!("synth-mock-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let fixture = dir.join("replies.json");
+    fs::write(&fixture, r#"{"replies": ["one", "two"]}"#).unwrap();
+    let config = json::parse(&format!(
+        r#"{{"profiles": {{"m": {{"type": "mock", "fixture": {:?}}}}}}}"#,
+        fixture.display().to_string()
+    ))
+    .unwrap();
+    backend::configure(Config::from_json(&config).unwrap());
+    assert_eq!(chat::kiro_chat("a").unwrap(), "one");
+    assert_eq!(chat::kiro_chat("b").unwrap(), "two");
+    backend::configure(Config::from_json(&config).unwrap());
+    assert_eq!(chat::kiro_chat("c").unwrap(), "one");
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,100 @@
+//! Model download manager: only HTTPS sources are fetched.
+
+use synth_core::json;
+use synth_core::models::{self, Model};
+
+fn model(url: &str) -> synth_core::error::Result<Model> {
+    Model::from_json(
+        &json::parse(&format!(
+            r#"{{"name": "tiny", "url": "{url}", "sha256": "AB12"}}"#
+        ))
+        .unwrap(),
+    )
+}
+
+#[test]
+fn refuses_urls_that_are_not_https() {
+    let https = model("https://example.com/models/tiny.gguf?download=1").unwrap();
+    assert_eq!(https.file, "tiny.gguf");
+    assert_eq!(https.sha256, "ab12");
+    for url in [
+        "http://example.com/tiny.gguf",
+        "file:///etc/passwd",
+        "-o/tmp/x",
+    ] {
+        assert!(model(url).is_err(), "{url}");
+    }
+
+    let dir = std::env::temp_dir().join(format!("synth-models-{}", std::process::id()));
+    let plain = Model {
+        url: "http://example.com/tiny.gguf".into(),
+        ..https.clone()
+    };
+    assert!(models::download(&dir, &plain, |_, _| {}).is_err());
+    assert!(!dir.exists());
+
+    // A failed fetch leaves no partial file behind.
+    let unreachable = Model {
+        url: "https://127.0.0.1:9/tiny.gguf".into(),
+        ..https
+    };
+    let err = models::download(&dir, &unreachable, |_, _| {}).unwrap_err();
+    assert!(err.to_string().contains("download failed"), "{err}");
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn a_model_downloaded_again_under_a_new_file_replaces_the_old_one() {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = std::env::temp_dir().join(format!("synth-models-replace-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let bin = dir.join("bin");
+    fs::create_dir_all(&bin).unwrap();
+    // A stand-in for curl that "downloads" the URL's own text, and fails
+    // like curl for anything but example.com.
+    let curl = bin.join("curl");
+    fs::write(
+        &curl,
+        "#!/bin/sh\nwhile [ \"$1\" != \"--\" ]; do [ \"$1\" = \"-o\" ] && out=\"$2\"; shift; done\n\
+         case \"$2\" in https://example.com/*) printf '%s' \"$2\" > \"$out\";; \
+         *) echo 'curl: (7) Failed to connect' >&2; exit 7;; esac\n",
+    )
+    .unwrap();
+    fs::set_permissions(&curl, fs::Permissions::from_mode(0o755)).unwrap();
+    let path = std::env::var("PATH").unwrap_or_default();
+    std::env::set_var("PATH", format!("{}:{path}", bin.display()));
+
+    let models_dir = dir.join("models");
+    let install = |url: &str| {
+        let expected = dir.join("expected");
+        fs::write(&expected, url).unwrap();
+        let sha = models::file_sha256(&expected).unwrap();
+        let model = Model::from_json(
+            &json::parse(&format!(
+                r#"{{"name": "tiny", "url": "{url}", "sha256": "{sha}"}}"#
+            ))
+            .unwrap(),
+        )
+        .unwrap();
+        models::download(&models_dir, &model, |_, _| {}).unwrap()
+    };
+    let first = install("https://example.com/v1/tiny-a.bin");
+    assert!(first.is_file());
+    let second = install("https://example.com/v2/tiny-b.bin");
+    assert!(second.is_file());
+    assert!(!first.exists(), "the replaced file is deleted");
+    let listed = models::list(&models_dir).unwrap();
+    let listed = listed.as_array().unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(
+        listed[0].get("file").and_then(json::Value::as_str),
+        Some("tiny-b.bin")
+    );
+    // The same file again keeps it.
+    let again = install("https://example.com/v2/tiny-b.bin");
+    assert!(again.is_file());
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,49 @@
+//! The `async` API resolves through any executor; a minimal one is enough.
+#![cfg(feature = "async")]
+
+use std::future::Future;
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+
+use synth_core::backend::{self, Config};
+use synth_core::chat::ChatOptions;
+use synth_core::error::Error;
+use synth_core::{json, nonblocking};
+
+struct Unpark(Thread);
+
+impl Wake for Unpark {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let waker = Waker::from(Arc::new(Unpark(thread::current())));
+    let mut context = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        match future.as_mut().poll(&mut context) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+#[test]
+fn chat_resolves_with_the_backend_reply() {
+    let config =
+        json::parse(r#"{"profiles": {"test": {"type": "mock", "default": "echo: {prompt}"}}}"#)
+            .unwrap();
+    backend::configure(Config::from_json(&config).unwrap());
+    let reply = block_on(nonblocking::chat("hello".into(), ChatOptions::default())).unwrap();
+    assert_eq!(reply, "echo: hello");
+}
+
+#[test]
+fn panics_become_errors() {
+    let result = block_on(nonblocking::spawn::<(), _>(|| panic!("boom")));
+    assert!(matches!(result, Err(Error::Backend(message)) if message.contains("boom")));
+}
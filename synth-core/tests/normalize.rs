@@ -0,0 +1,54 @@
+//! Normalization profiles.
+
+use synth_core::json;
+use synth_core::normalize::{self, Profile};
+
+const SAMPLE: &str = "\u{feff}Caf\u{e9} \u{201c}Ré\u{ad}sumé\u{201d}\r\n\r\n\r\n\
+                      The e\u{fb03}cient exam-\r\nple\u{a0}text.\x1b[1m  \x07\r\n";
+
+#[test]
+fn built_in_profiles_serve_each_consumer() {
+    let display = normalize::profile("display").unwrap().apply(SAMPLE);
+    assert_eq!(
+        display,
+        "Café \u{201c}Ré\u{ad}sumé\u{201d}\n\n\nThe e\u{fb03}cient exam-\nple\u{a0}text.[1m  \n"
+    );
+    let llm = normalize::profile("llm").unwrap().apply(SAMPLE);
+    assert_eq!(llm, "Café \"Résumé\"\n\nThe efficient example\ntext.");
+    let search = normalize::profile("search").unwrap().apply(SAMPLE);
+    assert_eq!(search, "cafe resume\nthe efficient example text");
+
+    let all = normalize::normalize_all(SAMPLE, &["llm", "search"]).unwrap();
+    assert_eq!(all[1], ("search".to_string(), search));
+    assert!(normalize::profile("nope").is_err());
+}
+
+#[test]
+fn hosts_define_profiles_from_a_base() {
+    let profile = Profile::from_json(
+        &json::parse(r#"{"name": "titles", "base": "llm", "fold_diacritics": true}"#).unwrap(),
+    )
+    .unwrap();
+    normalize::define(profile).unwrap();
+    assert_eq!(
+        normalize::profile("titles")
+            .unwrap()
+            .apply("Ångström  über"),
+        "Angstrom uber"
+    );
+    let builtin = Profile {
+        name: "llm".into(),
+        ..normalize::profile("titles").unwrap()
+    };
+    assert!(normalize::define(builtin).is_err());
+    for bad in [
+        r#"{"base": "llm"}"#,
+        r#"{"name": "x", "shout": true}"#,
+        r#"{"name": "x", "lowercase": 1}"#,
+    ] {
+        assert!(
+            Profile::from_json(&json::parse(bad).unwrap()).is_err(),
+            "{bad}"
+        );
+    }
+}
@@ -0,0 +1,54 @@
+//! Images as extraction input, read through `tesseract`.
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+
+use synth_core::error::Error;
+use synth_core::extract::{self, Format};
+use synth_core::json;
+use synth_core::ocr::{self, Config};
+
+#[test]
+fn images_are_read_through_tesseract() {
+    let dir = std::env::temp_dir().join(format!("synth-ocr-test-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    // A stand-in that prints two blocks, its arguments and a page break.
+    let command = dir.join("tesseract");
+    fs::write(
+        &command,
+        "#!/bin/sh\nprintf 'Clause 4.2   \\n\\nTermination\\n'\necho \"$2 $3 $4\"\nprintf '\\f'\n",
+    )
+    .unwrap();
+    fs::set_permissions(&command, fs::Permissions::from_mode(0o755)).unwrap();
+    let page = dir.join("contract page.PNG");
+    fs::write(&page, b"\x89PNG\r\n\x1a\n").unwrap();
+    assert_eq!(Format::from_path(&page), Some(Format::Image));
+
+    let config = json::parse(&format!(
+        r#"{{"command": {:?}, "language": "eng+deu"}}"#,
+        command.display().to_string()
+    ))
+    .unwrap();
+    let config = Config::from_json(&config).unwrap();
+    assert_eq!(config.timeout, Config::default().timeout);
+    ocr::configure(Some(config));
+    assert_eq!(
+        extract::extract_text(&page).unwrap(),
+        "Clause 4.2\n\nTermination\nstdout -l eng+deu\n"
+    );
+    let bytes = fs::read(&page).unwrap();
+    assert_eq!(
+        extract::extract_bytes(&bytes, Format::Image).unwrap(),
+        "Clause 4.2\n\nTermination\nstdout -l eng+deu\n"
+    );
+
+    let missing = dir.join("no-such-tesseract").display().to_string();
+    let config = json::parse(&format!(r#"{{"command": {missing:?}}}"#)).unwrap();
+    ocr::configure(Some(Config::from_json(&config).unwrap()));
+    let err = extract::extract_text(&page).unwrap_err();
+    assert!(matches!(err, Error::Unsupported(_)), "{err}");
+
+    ocr::configure(None);
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,47 @@
+//! Heading outlines: nesting, offsets and pages.
+
+use synth_core::json::Value;
+use synth_core::outline::{flat_headings, outline};
+
+#[test]
+fn headings_nest_with_their_offsets() {
+    let text = "---\ntitle: x\n---\n# Terms\n\nIntro.\n\n## Payment\n```\n# not a heading\n```\n\
+                Delivery\n--------\n# Annex\n";
+    let tree = outline(text);
+    assert_eq!(tree.len(), 2);
+    assert_eq!(
+        (tree[0].level, tree[0].text.as_str(), tree[0].offset),
+        (1, "Terms", 17)
+    );
+    let children: Vec<&str> = tree[0].children.iter().map(|h| h.text.as_str()).collect();
+    assert_eq!(children, ["Payment", "Delivery"]);
+    assert_eq!(tree[0].children[1].offset, text.find("Delivery").unwrap());
+    assert_eq!(tree[1].text, "Annex");
+    assert_eq!(tree[0].page, None);
+    assert_eq!(tree[0].to_json().get("page"), Some(&Value::Null));
+}
+
+#[test]
+fn form_feeds_give_headings_their_page() {
+    let text = "# One\ntext\n\u{c}# Two\nmore\u{c}\nTitle\n=====\n";
+    let headings = flat_headings(text);
+    let pages: Vec<(&str, Option<usize>)> =
+        headings.iter().map(|h| (h.text.as_str(), h.page)).collect();
+    assert_eq!(
+        pages,
+        [("One", Some(1)), ("Two", Some(2)), ("Title", Some(3))]
+    );
+    // The offset is the heading's, past the form feed.
+    assert_eq!(headings[1].offset, text.find("# Two").unwrap());
+    let emoji = flat_headings(
+        "🙂 intro
+
+# Terms
+",
+    );
+    assert_eq!((emoji[0].offset, emoji[0].offset_utf16), (9, 10));
+    assert_eq!(
+        headings[1].to_json().get("page").and_then(Value::as_f64),
+        Some(2.0)
+    );
+}
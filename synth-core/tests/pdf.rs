@@ -0,0 +1,390 @@
+//! PDF export: a well-formed file, paginated and wrapped, with headings
+//! in bold. PDF text extraction, from the files in `tests/corpus` and the
+//! ones exported here.
+
+use std::path::{Path, PathBuf};
+
+use synth_core::docx_text::{self, FormField, FormKind, FormValue};
+use synth_core::error::Error;
+use synth_core::json;
+use synth_core::pdf::{render_pdf, write_pdf};
+use synth_core::pdf_annotations::{self, Annotation, AnnotationKind};
+use synth_core::pdf_markup::{self, Mark, MarkKind, Target};
+use synth_core::pdf_objects::Pdf;
+use synth_core::pdf_text::{self, Options};
+
+fn corpus(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/corpus")
+        .join(name)
+}
+
+fn text(pdf: &[u8]) -> String {
+    String::from_utf8_lossy(pdf).into_owned()
+}
+
+/// The byte offsets the cross-reference table gives for objects 1..
+fn xref_offsets(pdf: &str) -> Vec<usize> {
+    let table = &pdf[pdf.find("\nxref\n").unwrap() + 1..];
+    table
+        .lines()
+        .skip(3)
+        .take_while(|line| line.ends_with(" n "))
+        .map(|line| line[..10].parse().unwrap())
+        .collect()
+}
+
+#[test]
+fn objects_sit_where_the_xref_says() {
+    let bytes = render_pdf("# Terms\nNet (30) days \\ 5% — “paid” ✓\n");
+    let pdf = text(&bytes);
+    assert!(pdf.starts_with("%PDF-1.4\n"));
+    assert!(pdf.ends_with("%%EOF\n"));
+    // Offsets count bytes, so check them against the bytes.
+    let offsets = xref_offsets(&pdf);
+    assert_eq!(offsets.len(), 6);
+    for (i, offset) in offsets.iter().enumerate() {
+        assert!(bytes[*offset..].starts_with(format!("{} 0 obj\n", i + 1).as_bytes()));
+    }
+    let startxref: usize = pdf
+        .rsplit("startxref\n")
+        .next()
+        .and_then(|rest| rest.lines().next())
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(bytes[startxref..].starts_with(b"xref"));
+
+    // The heading is bold, and its marker gone; delimiters are escaped.
+    assert!(
+        pdf.contains("/F2 10 Tf\n(Terms) Tj T*\n/F1 10 Tf\n"),
+        "{pdf}"
+    );
+    let body = render_pdf("Net (30) days \\ ✓");
+    let escaped = b"(Net \\(30\\) days \\\\ ?) Tj";
+    assert!(body.windows(escaped.len()).any(|w| w == escaped));
+    // WinAnsi has the dash and quotes as single bytes.
+    let quoted = render_pdf("— “a”");
+    assert!(quoted.windows(6).any(|w| w == b"(\x97 \x93a\x94"));
+}
+
+#[test]
+fn long_text_wraps_and_runs_onto_more_pages() {
+    let words = "lorem ".repeat(30);
+    let pdf = text(&render_pdf(&words));
+    let shown: Vec<&str> = pdf.lines().filter(|l| l.ends_with(") Tj T*")).collect();
+    assert_eq!(shown.len(), 3);
+    assert!(shown.iter().all(|l| l.len() - "() Tj T*".len() <= 78));
+
+    let many: String = (0..120).map(|i| format!("line {i}\n")).collect();
+    let pdf = text(&render_pdf(&many));
+    assert!(pdf.contains("/Count 3 >>"));
+    assert!(text(&render_pdf("")).contains("/Count 1 >>"));
+
+    let path = std::env::temp_dir().join(format!("synth-pdf-{}.pdf", std::process::id()));
+    write_pdf("hello", &path).unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), render_pdf("hello"));
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn exported_text_reads_back() {
+    let pdf = render_pdf("# Terms\n\nNet 30 days,\npaid “in full”.\n");
+    assert_eq!(
+        pdf_text::text_from_bytes(&pdf).unwrap(),
+        "Terms\n\nNet 30 days, paid “in full”.\n"
+    );
+    let many: String = (0..120).map(|i| format!("line {i}\n\n")).collect();
+    let text = pdf_text::text_from_bytes(&render_pdf(&many)).unwrap();
+    assert_eq!(text.matches('\u{c}').count(), 4);
+    assert!(text.starts_with("line 0\n\nline 1\n"));
+}
+
+#[test]
+fn columns_are_read_in_turn_without_running_headers() {
+    let columns = corpus("columns.pdf");
+    let extraction = pdf_text::extract(&columns, &Options::default()).unwrap();
+    assert_eq!(extraction.pages, 2);
+    let pages: Vec<&str> = extraction.text.split('\u{c}').collect();
+    assert_eq!(
+        pages[0],
+        "Market Outlook\n\n\
+         The market grew in every region this quarter, led by strong demand for storage. \
+         Margins held despite information costs rising again.\n\n\
+         Hiring slowed in the second half.\n\n\
+         Outlook for next year is cautious. We expect prices to stay flat while new capacity \
+         comes online in the autumn.\n\n\
+         Risks are listed on the next page.\n"
+    );
+    assert_eq!(
+        pages[1],
+        "Supply remains the main risk for the whole sector.\n\n\
+         Currency moves are the second. Both are watched each month.\n"
+    );
+
+    let options = Options::from_json(&json::parse(r#"{"layout_aware": false}"#).unwrap()).unwrap();
+    let drawn = pdf_text::extract(&columns, &options).unwrap().text;
+    assert!(drawn.starts_with(
+        "Acme Quarterly Review\n\
+         Market Outlook\n\
+         The market grew in every region this Outlook for next year is cautious.\n"
+    ));
+    assert!(drawn.ends_with("Page 2 of 2\n"));
+}
+
+#[test]
+fn compressed_objects_and_composite_fonts_are_read() {
+    // A cross-reference stream, objects in an object stream, a font with
+    // two-byte codes mapped to Unicode, and a form drawn on the page.
+    assert_eq!(
+        pdf_text::text(&corpus("compact.pdf")).unwrap(),
+        "LEGALNOTICE fiNAL CAFé TERMS\n\nIT’S SIGNED\n"
+    );
+}
+
+#[test]
+fn form_fields_are_read_as_data() {
+    let extraction = pdf_text::extract(&corpus("intake.pdf"), &Options::default()).unwrap();
+    let field = |name: &str, kind, value| FormField {
+        name: name.into(),
+        kind,
+        value,
+    };
+    let text = |text: &str| FormValue::Text(text.into());
+    // The submit button holds nothing and is left out.
+    assert_eq!(
+        extraction.form,
+        vec![
+            field("applicant.name", FormKind::Text, text("Jane Doe")),
+            field("applicant.phone", FormKind::Text, FormValue::Empty),
+            field("applicant.city", FormKind::Text, text("Café")),
+            field("consent", FormKind::Checkbox, FormValue::Checked(true)),
+            field("newsletter", FormKind::Checkbox, FormValue::Checked(false)),
+            field("contact", FormKind::Radio, text("Email")),
+            field("state", FormKind::DropDown, text("Ohio")),
+        ]
+    );
+    assert_eq!(
+        docx_text::form_to_json(&extraction.form).to_string(),
+        json::parse(
+            r#"{"applicant.name": "Jane Doe", "applicant.phone": null, "applicant.city": "Café",
+                "consent": true, "newsletter": false, "contact": "Email", "state": "Ohio"}"#
+        )
+        .unwrap()
+        .to_string()
+    );
+    assert!(extraction.text.starts_with("Name:\n"));
+    assert!(
+        pdf_text::extract(&corpus("columns.pdf"), &Options::default())
+            .unwrap()
+            .form
+            .is_empty()
+    );
+}
+
+#[test]
+fn highlights_and_notes_are_read_with_their_text() {
+    let extraction = pdf_text::extract(&corpus("reviewed.pdf"), &Options::default()).unwrap();
+    let annotation = |kind, page, author: Option<&str>, quote: &str, comment: &str| Annotation {
+        kind,
+        page,
+        author: author.map(String::from),
+        quote: quote.into(),
+        comment: comment.into(),
+    };
+    // The pop-up window and the link are left out.
+    assert_eq!(
+        extraction.annotations,
+        vec![
+            annotation(
+                AnnotationKind::Highlight,
+                1,
+                Some("Dana Reviewer"),
+                "deliver all goods within thirty days",
+                "Key obligation"
+            ),
+            annotation(AnnotationKind::Underline, 1, None, "penalty of 2%", ""),
+            annotation(
+                AnnotationKind::Note,
+                1,
+                Some("Sam"),
+                "",
+                "Check the penalty cap."
+            ),
+            annotation(
+                AnnotationKind::StrikeOut,
+                2,
+                Some("Zoë"),
+                "on notice",
+                "Too vague"
+            ),
+            annotation(
+                AnnotationKind::FreeText,
+                2,
+                Some("Dana Reviewer"),
+                "",
+                "Approved"
+            ),
+        ]
+    );
+    assert_eq!(
+        extraction
+            .to_json()
+            .get("annotations")
+            .unwrap()
+            .as_array()
+            .unwrap()[1]
+            .to_string(),
+        json::parse(
+            r#"{"kind": "underline", "page": 1, "author": null, "quote": "penalty of 2%",
+                "comment": ""}"#
+        )
+        .unwrap()
+        .to_string()
+    );
+}
+
+fn mark(value: &str) -> Mark {
+    Mark::from_json(&json::parse(value).unwrap()).unwrap()
+}
+
+#[test]
+fn marks_are_appended_as_an_incremental_update() {
+    // A classic cross-reference table, and a page with annotations.
+    let reviewed = std::fs::read(corpus("reviewed.pdf")).unwrap();
+    let marks = [
+        mark(
+            r#"{"kind": "highlight", "quote": "all  goods within\nthirty", "comment": "Source 1"}"#,
+        ),
+        mark(
+            r#"{"kind": "note", "page": 2, "rect": [500, 700, 520, 720], "comment": "Café?",
+                 "author": "Zoë", "color": "cyan"}"#,
+        ),
+    ];
+    assert_eq!(
+        marks[1].target,
+        Target::Area {
+            page: 2,
+            rect: [500.0, 700.0, 520.0, 720.0]
+        }
+    );
+    let out = pdf_markup::annotate_bytes(reviewed.clone(), &marks).unwrap();
+    assert!(out.starts_with(&reviewed));
+    assert!(text(&out[reviewed.len()..]).contains("/Prev "));
+    let pdf = Pdf::from_bytes(out.clone()).unwrap();
+    let annotations = pdf_annotations::annotations(&pdf).unwrap();
+    // After the page's own.
+    assert_eq!(annotations.len(), 7);
+    assert_eq!(
+        annotations[3],
+        Annotation {
+            kind: AnnotationKind::Highlight,
+            page: 1,
+            author: Some("Synth".into()),
+            quote: "all goods within thirty".into(),
+            comment: "Source 1".into(),
+        }
+    );
+    assert_eq!(annotations[6].kind, AnnotationKind::Note);
+    assert_eq!(annotations[6].page, 2);
+    assert_eq!(annotations[6].author.as_deref(), Some("Zoë"));
+    assert_eq!(annotations[6].comment, "Café?");
+    assert_eq!(
+        pdf_text::text_from_bytes(&out).unwrap(),
+        pdf_text::text_from_bytes(&reviewed).unwrap()
+    );
+
+    // A cross-reference stream, and a page kept in an object stream.
+    let compact = std::fs::read(corpus("compact.pdf")).unwrap();
+    let marks = [
+        mark(r#"{"kind": "highlight", "quote": "CAFé TERMS", "page": 1, "color": "green"}"#),
+        mark(r#"{"kind": "note", "quote": "SIGNED", "comment": "Signed on the 3rd"}"#),
+    ];
+    assert_eq!(marks[0].kind, MarkKind::Highlight);
+    let out = pdf_markup::annotate_bytes(compact.clone(), &marks).unwrap();
+    assert!(out.starts_with(&compact));
+    assert!(text(&out[compact.len()..]).contains("/Type /XRef"));
+    let annotations = pdf_annotations::annotations(&Pdf::from_bytes(out.clone()).unwrap()).unwrap();
+    assert_eq!(annotations.len(), 2);
+    assert_eq!(annotations[0].quote, "CAFé TERMS");
+    assert_eq!(annotations[1].comment, "Signed on the 3rd");
+    // Marking the marked file again adds to it.
+    let again = pdf_markup::annotate_bytes(out.clone(), &marks[..1]).unwrap();
+    assert!(again.starts_with(&out));
+    let annotations = pdf_annotations::annotations(&Pdf::from_bytes(again).unwrap()).unwrap();
+    assert_eq!(annotations.len(), 3);
+}
+
+#[test]
+fn marks_that_cannot_be_placed_write_nothing() {
+    let dir = std::env::temp_dir().join(format!("synth-pdf-markup-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let out = dir.join("out.pdf");
+    let reviewed = corpus("reviewed.pdf");
+    for marks in [
+        vec![mark(r#"{"kind": "highlight", "quote": "not on any page"}"#)],
+        vec![
+            mark(r#"{"kind": "highlight", "quote": "penalty"}"#),
+            mark(r#"{"kind": "highlight", "quote": "penalty", "page": 2}"#),
+        ],
+        vec![mark(
+            r#"{"kind": "note", "page": 3, "rect": [0, 0, 20, 20]}"#,
+        )],
+    ] {
+        let err = pdf_markup::annotate(&reviewed, &marks, &out).unwrap_err();
+        assert!(matches!(err, Error::Parse(_)), "{err}");
+        assert!(!out.exists());
+    }
+    pdf_markup::annotate(
+        &reviewed,
+        &[mark(r#"{"kind": "highlight", "quote": "penalty"}"#)],
+        &out,
+    )
+    .unwrap();
+    assert!(out.exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    for bad in [
+        r#"{"kind": "underline", "quote": "x"}"#,
+        r#"{"kind": "note", "rect": [0, 0, 20, 20]}"#,
+        r#"{"kind": "note", "page": 1, "rect": [0, 0, 20]}"#,
+        r#"{"kind": "highlight", "quote": "x", "page": 0}"#,
+        r#"{"kind": "highlight", "quote": "x", "color": "mauve"}"#,
+        r#"{"kind": "highlight"}"#,
+    ] {
+        assert!(
+            Mark::from_json(&json::parse(bad).unwrap()).is_err(),
+            "{bad}"
+        );
+    }
+}
+
+#[test]
+fn damaged_and_encrypted_files() {
+    let bytes = std::fs::read(corpus("columns.pdf")).unwrap();
+    let text = pdf_text::text_from_bytes(&bytes).unwrap();
+    // A wrong startxref: the objects are found by scanning for them.
+    let at = bytes
+        .windows(10)
+        .rposition(|w| w == b"startxref\n")
+        .unwrap()
+        + 10;
+    let mut damaged = bytes[..at].to_vec();
+    damaged.extend_from_slice(b"999999\n%%EOF\n");
+    assert_eq!(pdf_text::text_from_bytes(&damaged).unwrap(), text);
+    // Its objects could be anywhere, so nothing is appended to it.
+    let highlight = mark(r#"{"kind": "highlight", "quote": "market"}"#);
+    let err = pdf_markup::annotate_bytes(damaged, &[highlight]).unwrap_err();
+    assert!(matches!(err, Error::Unsupported(_)), "{err}");
+
+    let encrypted = String::from_utf8_lossy(&render_pdf("secret")).replace(
+        "/Root 1 0 R",
+        "/Root 1 0 R /Encrypt << /Filter /Standard >>",
+    );
+    let err = pdf_text::text_from_bytes(encrypted.as_bytes()).unwrap_err();
+    assert!(matches!(err, Error::Unsupported(_)), "{err}");
+    assert!(matches!(
+        pdf_text::text_from_bytes(b"not a pdf"),
+        Err(Error::Parse(_))
+    ));
+}
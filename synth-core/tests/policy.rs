@@ -0,0 +1,149 @@
+//! Policy enforcement. A policy stays in force for the whole process, so
+//! everything that needs one installed runs in a single test.
+
+use std::fs;
+use std::path::PathBuf;
+
+use synth_core::backend::{self, Config};
+use synth_core::chat::{self, ChatOptions};
+use synth_core::json;
+use synth_core::policy::{self, Policy};
+use synth_core::{extract, session};
+
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("synth-policy-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn rejects_malformed_toml() {
+    let dir = scratch("bad");
+    for text in [
+        "[read\nallow = []",
+        "max = { a = 1 }",
+        "a = 1\na = 2",
+        "s = \"open",
+    ] {
+        fs::write(dir.join("policy.toml"), text).unwrap();
+        assert!(
+            Policy::from_file(&dir.join("policy.toml")).is_err(),
+            "{text}"
+        );
+    }
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn enforces_reads_backends_and_session_tokens() {
+    let dir = scratch("enforce");
+    let allowed = dir.join("docs");
+    let secret = allowed.join("hr");
+    let outside = dir.join("elsewhere");
+    for d in [&allowed, &secret, &outside] {
+        fs::create_dir_all(d).unwrap();
+    }
+    fs::write(allowed.join("plan.txt"), "the plan").unwrap();
+    fs::write(secret.join("salaries.txt"), "private").unwrap();
+    fs::write(outside.join("notes.txt"), "notes").unwrap();
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(secret.join("salaries.txt"), allowed.join("link.txt")).unwrap();
+
+    let file = dir.join("policy.toml");
+    fs::write(
+        &file,
+        format!(
+            "# company policy\n[read]\nallow = [\n  \"{}\",\n]\ndeny = ['{}']\n\n\
+             [session]\nmax_tokens = 1_000\n\n[backends]\ndeny = [\"blocked\"]  # no cloud\n",
+            allowed.display(),
+            secret.display()
+        ),
+    )
+    .unwrap();
+    let loaded = Policy::from_file(&file).unwrap();
+    assert_eq!(loaded.max_session_tokens, Some(1000));
+    assert_eq!(loaded.deny_backends, ["blocked"]);
+    policy::install(loaded).unwrap();
+    assert!(policy::install(Policy::default()).is_err());
+
+    assert_eq!(
+        extract::extract_text(&allowed.join("plan.txt")).unwrap(),
+        "the plan"
+    );
+    for denied in [
+        outside.join("notes.txt"),
+        secret.join("salaries.txt"),
+        allowed.join("hr/../hr/salaries.txt"),
+    ] {
+        let err = extract::extract_text(&denied).unwrap_err();
+        assert_eq!(err.kind(), "denied", "{}", denied.display());
+    }
+    #[cfg(unix)]
+    assert_eq!(
+        extract::extract_text(&allowed.join("link.txt"))
+            .unwrap_err()
+            .kind(),
+        "denied"
+    );
+    // An open file is checked by where its descriptor is, whatever path
+    // it was opened by.
+    #[cfg(unix)]
+    {
+        use std::os::unix::io::AsRawFd;
+
+        let plan = fs::File::open(allowed.join("plan.txt")).unwrap();
+        policy::check_descriptor(plan.as_raw_fd()).unwrap();
+        let salaries = fs::File::open(allowed.join("link.txt")).unwrap();
+        assert_eq!(
+            policy::check_descriptor(salaries.as_raw_fd())
+                .unwrap_err()
+                .kind(),
+            "denied"
+        );
+        assert!(policy::open_file(&allowed.join("plan.txt")).is_ok());
+        assert_eq!(
+            policy::read_file(&allowed.join("link.txt"))
+                .unwrap_err()
+                .kind(),
+            "denied"
+        );
+    }
+
+    let config = json::parse(
+        r#"{"profiles": {"ok": {"type": "mock", "default": "fine"},
+            "blocked": {"type": "mock", "default": "leaked"}},
+            "default": "blocked", "fallback": ["blocked", "ok"]}"#,
+    )
+    .unwrap();
+    backend::configure(Config::from_json(&config).unwrap());
+    let blocked = ChatOptions {
+        backend: Some("blocked".into()),
+        ..ChatOptions::default()
+    };
+    assert_eq!(chat::chat("hi", &blocked).unwrap_err().kind(), "denied");
+    let ok = ChatOptions {
+        backend: Some("ok".into()),
+        ..ChatOptions::default()
+    };
+    assert_eq!(chat::chat("hi", &ok).unwrap(), "fine");
+
+    // Each exchange resends the history, so the cap of 1000 is reached fast.
+    let handle = session::create(ok);
+    let message = "x".repeat(800);
+    let mut sent = 0;
+    let err = loop {
+        match session::send(handle, &message) {
+            Ok(_) => sent += 1,
+            Err(err) => break err,
+        }
+        assert!(sent < 10, "the token cap was never reached");
+    };
+    assert_eq!(err.kind(), "denied");
+    assert!(sent >= 1);
+    let spent = session::get(handle).unwrap().tokens_used;
+    assert!(spent > 0 && spent <= 1000, "{spent}");
+    let branch = session::branch(handle, 0).unwrap();
+    assert_eq!(session::get(branch).unwrap().tokens_used, spent);
+    let _ = fs::remove_dir_all(&dir);
+}
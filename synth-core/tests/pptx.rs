@@ -0,0 +1,64 @@
+//! Slides and speaker notes from .pptx files, read through the built-in zip
+//! reader. The sample deck lives in `tests/corpus`.
+
+use std::path::{Path, PathBuf};
+
+use synth_core::error::Error;
+use synth_core::pptx::{self, SlideContent};
+
+fn deck() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus/deck.pptx")
+}
+
+#[test]
+fn slides_follow_presentation_order() {
+    let slides = pptx::slides(&deck()).unwrap();
+    assert_eq!(slides.len(), 2);
+    assert_eq!(slides[0].number, 1);
+    assert_eq!(slides[0].title.as_deref(), Some("Quarterly review"));
+    assert_eq!(slides[0].body, "Sales & support\nQ3 2026");
+    // Only the notes placeholder counts, not the page number.
+    assert_eq!(
+        slides[0].notes,
+        "Open with the churn numbers.\nKeep it under five minutes."
+    );
+    assert_eq!(slides[1].title, None);
+    assert_eq!(
+        slides[1].body,
+        "Grouped callout\n\nRegion | Revenue\nNorth | 1.2M"
+    );
+    assert_eq!(slides[1].notes, "");
+}
+
+#[test]
+fn json_keeps_the_requested_content() {
+    let slides = pptx::slides(&deck()).unwrap();
+    let notes = pptx::slides_to_json(&slides, SlideContent::Notes);
+    assert_eq!(
+        notes.to_string(),
+        r#"[{"number":1,"notes":"Open with the churn numbers.\nKeep it under five minutes."},{"number":2,"notes":""}]"#
+    );
+    let body = pptx::slides_to_json(&slides, SlideContent::Body).to_string();
+    assert!(body.contains(r#""title":null"#));
+    assert!(!body.contains("notes"));
+    assert_eq!(SlideContent::from_name("all"), Some(SlideContent::All));
+    assert_eq!(SlideContent::from_name("speaker"), None);
+}
+
+#[test]
+fn damaged_archives_are_parse_errors() {
+    let mut bytes = std::fs::read(deck()).unwrap();
+    assert!(matches!(
+        pptx::slides_from_bytes(b"not a zip"),
+        Err(Error::Parse(_))
+    ));
+    // Flip a byte in the compressed presentation part, just past its name
+    // in the local header.
+    let name = b"ppt/presentation.xml";
+    let at = bytes.windows(name.len()).position(|w| w == name).unwrap();
+    bytes[at + name.len() + 8] ^= 0xff;
+    assert!(matches!(
+        pptx::slides_from_bytes(&bytes),
+        Err(Error::Parse(_))
+    ));
+}
@@ -0,0 +1,34 @@
+//! Prefetched documents are answered from the cache until they change.
+
+use std::fs::{self, File};
+
+use synth_core::{extract, prefetch};
+
+#[test]
+fn prefetched_text_is_reused_until_the_file_changes() {
+    let path = std::env::temp_dir().join(format!("synth-prefetch-{}.md", std::process::id()));
+    fs::write(&path, "first draft").unwrap();
+    let modified = fs::metadata(&path).unwrap().modified().unwrap();
+    prefetch::warm(&path).unwrap();
+
+    // Same size and time: the file counts as unchanged, so the cached text
+    // is what comes back.
+    fs::write(&path, "other draft").unwrap();
+    File::options()
+        .write(true)
+        .open(&path)
+        .unwrap()
+        .set_modified(modified)
+        .unwrap();
+    assert_eq!(extract::extract_text(&path).unwrap(), "first draft");
+
+    fs::write(&path, "second draft, longer").unwrap();
+    assert_eq!(
+        extract::extract_text(&path).unwrap(),
+        "second draft, longer"
+    );
+
+    assert!(prefetch::prefetch(&path).is_ok());
+    assert!(prefetch::prefetch(&path.with_extension("missing")).is_ok());
+    let _ = fs::remove_file(&path);
+}
@@ -0,0 +1,63 @@
+//! Named backend profiles, chosen per call.
+
+use synth_core::backend::{self, Config, Kind};
+use synth_core::chat::{self, ChatOptions};
+use synth_core::json;
+
+fn config(text: &str) -> synth_core::error::Result<Config> {
+    Config::from_json(&json::parse(text).unwrap())
+}
+
+#[test]
+fn calls_pick_their_profile_by_name() {
+    let parsed = config(
+        r#"{"profiles": {
+            "smart": {"type": "mock", "default": "smart: {prompt}"},
+            "fast-local": {"type": "mock", "default": "fast", "timeout_secs": 5},
+            "cli": {"type": "kiro", "command": "/opt/kiro-cli"}
+        }}"#,
+    )
+    .unwrap();
+    // Without "default" the first profile is it.
+    assert_eq!(parsed.default, "smart");
+    assert_eq!(parsed.profiles.len(), 3);
+    let cli = parsed.profiles.iter().find(|p| p.name == "cli").unwrap();
+    assert_eq!(
+        cli.kind,
+        Kind::Kiro {
+            command: "/opt/kiro-cli".into()
+        }
+    );
+    backend::configure(parsed);
+
+    assert_eq!(chat::kiro_chat("hi").unwrap(), "smart: hi");
+    assert_eq!(backend::last_answered().as_deref(), Some("smart"));
+    let fast = ChatOptions::from_json(&json::parse(r#"{"backend": "fast-local"}"#).unwrap());
+    assert_eq!(chat::chat("hi", &fast).unwrap(), "fast");
+    assert_eq!(backend::last_answered().as_deref(), Some("fast-local"));
+
+    let unknown = ChatOptions {
+        backend: Some("gpu".into()),
+        ..ChatOptions::default()
+    };
+    assert_eq!(chat::chat("hi", &unknown).unwrap_err().kind(), "parse");
+    backend::configure(Config::default());
+    assert_eq!(backend::chain(None).unwrap()[0].name, "kiro");
+}
+
+#[test]
+fn malformed_configurations_are_refused() {
+    for (text, kind) in [
+        (r#"{"profiles": []}"#, "parse"),
+        (r#"{"profiles": {}}"#, "parse"),
+        (
+            r#"{"profiles": {"a": {"type": "mock"}}, "default": "b"}"#,
+            "parse",
+        ),
+        (r#"{"profiles": {"a": {"model": "x"}}}"#, "parse"),
+        (r#"{"profiles": {"a": {"type": "ollama"}}}"#, "parse"),
+        (r#"{"profiles": {"a": {"type": "gpt"}}}"#, "unsupported"),
+    ] {
+        assert_eq!(config(text).unwrap_err().kind(), kind, "{text}");
+    }
+}
@@ -0,0 +1,164 @@
+//! Stage progress from the pipelines: stages in order, the percentage
+//! rising to 100, and the item being worked on.
+
+use std::fs;
+
+use synth_core::ask;
+use synth_core::compare::compare_corpus;
+use synth_core::jobs;
+use synth_core::json;
+use synth_core::minutes::{generate_minutes, MinutesOptions};
+use synth_core::progress::Progress;
+use synth_core::summarize::{summarize_document, SummarizeOptions};
+use synth_core::translate::{translate_document, TranslateOptions};
+
+type Report = (&'static str, usize, usize, f64, Option<String>);
+
+fn record(reports: &mut Vec<Report>) -> impl FnMut(&Progress) + '_ {
+    |p: &Progress| {
+        reports.push((
+            p.stage.name(),
+            p.completed,
+            p.total,
+            p.percent,
+            p.item.map(String::from),
+        ))
+    }
+}
+
+fn rising(reports: &[Report]) -> bool {
+    reports.windows(2).all(|pair| pair[0].3 <= pair[1].3) && reports.last().unwrap().3 == 100.0
+}
+
+#[test]
+fn minutes_report_chunking_then_each_part() {
+    let transcript = "Ana: The renewal terms were discussed at length.\n".repeat(40);
+    let options = MinutesOptions {
+        max_chunk_chars: 1000,
+        ..MinutesOptions::default()
+    };
+    let mut reports = Vec::new();
+    generate_minutes(
+        &transcript,
+        &options,
+        |_| Ok("{}".into()),
+        record(&mut reports),
+    )
+    .unwrap();
+    let stages: Vec<&str> = reports.iter().map(|r| r.0).collect();
+    assert_eq!(stages[0], "chunking");
+    assert!(stages[1..].iter().all(|s| *s == "generating"));
+    assert_eq!(reports[1].4.as_deref(), Some("part 1 of 2"));
+    assert_eq!((reports[2].1, reports[2].2), (1, 2));
+    assert!(rising(&reports), "{reports:?}");
+}
+
+#[test]
+fn comparisons_report_each_document_then_the_backend_call() {
+    let dir = std::env::temp_dir().join(format!("synth-progress-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let paths: Vec<_> = ["a.txt", "b.txt"]
+        .iter()
+        .map(|name| {
+            let path = dir.join(name);
+            fs::write(&path, "Net 30.").unwrap();
+            path
+        })
+        .collect();
+    let mut reports = Vec::new();
+    compare_corpus(
+        &paths,
+        r#"["Terms"]"#,
+        |_| Ok(r#"{"rows": []}"#.into()),
+        record(&mut reports),
+    )
+    .unwrap();
+    let items: Vec<(&str, Option<&str>)> = reports.iter().map(|r| (r.0, r.4.as_deref())).collect();
+    assert_eq!(
+        items,
+        [
+            ("extracting", Some("a.txt")),
+            ("extracting", Some("b.txt")),
+            ("generating", None),
+            ("generating", None),
+        ]
+    );
+    assert!(rising(&reports), "{reports:?}");
+
+    // Bulk generation reports each file written.
+    let template = dir.join("letter.txt");
+    fs::write(&template, "Dear {{name}}").unwrap();
+    let params = json::parse(&format!(
+        r#"{{"template": {:?}, "records": [{{"name": "A"}}, {{"name": "B"}}], "out_dir": {:?}}}"#,
+        template.display().to_string(),
+        dir.join("out").display().to_string()
+    ))
+    .unwrap();
+    let mut reports = Vec::new();
+    jobs::run("generate_documents", &params, record(&mut reports)).unwrap();
+    let writing: Vec<(&str, usize, f64)> = reports.iter().map(|r| (r.0, r.1, r.3)).collect();
+    assert_eq!(writing, [("writing", 1, 50.0), ("writing", 2, 100.0)]);
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn summaries_translations_and_indexes_report_their_stages() {
+    let dir = std::env::temp_dir().join(format!("synth-progress-stages-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("terms.txt");
+    fs::write(
+        &path,
+        "The renewal terms were discussed at length.\n".repeat(60),
+    )
+    .unwrap();
+    let stages = |reports: &[Report]| {
+        let mut stages: Vec<&str> = reports.iter().map(|r| r.0).collect();
+        stages.dedup();
+        stages
+    };
+
+    let mut reports = Vec::new();
+    let options = SummarizeOptions {
+        max_chunk_chars: 1000,
+        ..SummarizeOptions::default()
+    };
+    summarize_document(
+        &path,
+        &options,
+        |_| Ok("Short.".into()),
+        record(&mut reports),
+    )
+    .unwrap();
+    assert_eq!(stages(&reports), ["extracting", "chunking", "generating"]);
+    assert_eq!(
+        reports[0].4.as_deref(),
+        Some(path.display().to_string().as_str())
+    );
+    assert_eq!(reports[2].4.as_deref(), Some("part 1 of 3"));
+    assert_eq!(reports[reports.len() - 2].4.as_deref(), Some("combining"));
+    assert!(rising(&reports), "{reports:?}");
+
+    let mut reports = Vec::new();
+    let options = TranslateOptions {
+        max_chunk_chars: 1000,
+        ..TranslateOptions::new("German")
+    };
+    translate_document(
+        &path,
+        &options,
+        |_| Ok("Kurz.".into()),
+        record(&mut reports),
+    )
+    .unwrap();
+    assert_eq!(stages(&reports), ["extracting", "chunking", "generating"]);
+    assert!(rising(&reports), "{reports:?}");
+
+    // Without an embedding model an index is only chunked.
+    let mut reports = Vec::new();
+    ask::index_document(&path, record(&mut reports)).unwrap();
+    assert_eq!(stages(&reports), ["extracting", "chunking"]);
+    assert!(rising(&reports), "{reports:?}");
+    let _ = fs::remove_dir_all(&dir);
+}
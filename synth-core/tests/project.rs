@@ -0,0 +1,86 @@
+//! Projects: their lifecycle, the extraction cache and moving one between
+//! machines with export and import.
+
+use std::fs;
+
+use synth_core::project;
+use synth_core::session::Session;
+
+#[test]
+fn imported_projects_find_documents_moved_with_them() {
+    let base = std::env::temp_dir().join(format!("synth-project-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&base);
+    let old = base.join("old");
+    fs::create_dir_all(old.join("docs")).unwrap();
+    fs::write(old.join("docs/terms.md"), "Net 30.").unwrap();
+    let mut original = project::create(&old, "deal").unwrap();
+    original.add_document(&old.join("docs/terms.md")).unwrap();
+    original
+        .save_session("review", &Session::default())
+        .unwrap();
+    let archive = base.join("deal.tar");
+    original.export(&archive).unwrap();
+
+    // The machine the project moves to has the folder somewhere else.
+    let new = base.join("elsewhere/new");
+    fs::create_dir_all(new.join("docs")).unwrap();
+    fs::rename(old.join("docs/terms.md"), new.join("docs/terms.md")).unwrap();
+    let imported = project::import(&archive, &new).unwrap();
+    let document = std::path::absolute(new.join("docs/terms.md")).unwrap();
+    assert_eq!(imported.documents, vec![document.clone()]);
+    assert_eq!(imported.extract(&document).unwrap().trim(), "Net 30.");
+    assert_eq!(imported.sessions(), ["review"]);
+    assert_eq!(project::open(&new, "deal").unwrap().documents, [document]);
+    let _ = fs::remove_dir_all(&base);
+}
+
+#[test]
+fn projects_are_created_listed_and_deleted() {
+    let root = std::env::temp_dir().join(format!("synth-projects-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+    let doc = root.join("notes.txt");
+    fs::write(&doc, "First draft.").unwrap();
+
+    let mut beta = project::create(&root, "beta").unwrap();
+    project::create(&root, "alpha").unwrap();
+    assert_eq!(project::create(&root, "beta").unwrap_err().kind(), "parse");
+    for name in ["", "../up", ".hidden", "a/b"] {
+        assert_eq!(project::create(&root, name).unwrap_err().kind(), "parse");
+    }
+    let names: Vec<String> = project::list(&root)
+        .unwrap()
+        .into_iter()
+        .map(|p| p.name)
+        .collect();
+    assert_eq!(names, ["alpha", "beta"]);
+
+    // Extracted text is cached until the file changes.
+    assert!(beta.add_document(&doc).unwrap());
+    assert!(!beta.add_document(&doc).unwrap());
+    assert_eq!(beta.extract(&doc).unwrap().trim(), "First draft.");
+    let cached: Vec<_> = fs::read_dir(root.join("beta/cache"))
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .collect();
+    assert_eq!(cached.len(), 1);
+    fs::write(&cached[0], "from the cache").unwrap();
+    assert_eq!(beta.extract(&doc).unwrap(), "from the cache");
+    fs::write(&doc, "Second, longer draft.").unwrap();
+    assert_eq!(beta.extract(&doc).unwrap().trim(), "Second, longer draft.");
+
+    let handle = project::insert(beta.clone());
+    assert!(project::with(handle, |p| p.remove_document(&doc)).unwrap());
+    assert!(project::get(handle).unwrap().documents.is_empty());
+    assert!(project::open(&root, "beta").unwrap().documents.is_empty());
+
+    // Deleting closes handles, and only project directories are removed.
+    project::delete(&root, "beta").unwrap();
+    assert!(project::get(handle).is_none());
+    fs::create_dir_all(root.join("plain")).unwrap();
+    assert_eq!(project::delete(&root, "plain").unwrap_err().kind(), "parse");
+    assert!(root.join("plain").is_dir());
+    assert_eq!(project::list(&root).unwrap().len(), 1);
+    assert!(project::list(&root.join("missing")).unwrap().is_empty());
+    let _ = fs::remove_dir_all(&root);
+}
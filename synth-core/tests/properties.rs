@@ -0,0 +1,12 @@
+//! Invariants of the text primitives over many random inputs. The same
+//! checks run in release builds through `synth_self_test()`.
+
+use synth_core::selftest;
+
+#[test]
+fn invariants_hold() {
+    for seed in 1..=8 {
+        let failures = selftest::run(2_000, seed);
+        assert!(failures.is_empty(), "seed {seed}: {failures:#?}");
+    }
+}
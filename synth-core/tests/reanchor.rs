@@ -0,0 +1,79 @@
+//! Re-anchoring annotations after the document changes.
+
+use synth_core::json::{self, Value};
+use synth_core::reanchor::{reanchor, Annotation, Status};
+
+fn annotation(quote: &str, start: usize) -> Annotation {
+    Annotation {
+        id: Value::from(quote),
+        quote: quote.into(),
+        start,
+        end: start + quote.chars().count(),
+    }
+}
+
+#[test]
+fn anchors_by_offset_copy_and_approximate_match() {
+    let old = "The fee is due within thirty days of invoice.";
+    let new = "Note: the fee is due within thirty days of an invoice.";
+    let anchors = reanchor(
+        old,
+        new,
+        &[
+            annotation("fee is due", 4),
+            annotation("thirty days of invoice", 22),
+            annotation("late penalty", 0),
+        ],
+    );
+    assert_eq!(anchors[0].status, Status::Moved);
+    assert_eq!(anchors[0].range, Some((10, 20)));
+    assert_eq!(anchors[1].status, Status::Fuzzy);
+    let (start, end) = anchors[1].range.unwrap();
+    let found: String = new.chars().skip(start).take(end - start).collect();
+    assert!(
+        found.starts_with("thirty") && found.ends_with("invoice"),
+        "{found}"
+    );
+    assert!(anchors[1].score < 1.0 && anchors[1].score >= 0.75);
+    assert_eq!(anchors[2].status, Status::Orphaned);
+
+    let same = reanchor(old, old, &[annotation("due within", 11)]);
+    assert_eq!(
+        (same[0].status, same[0].range),
+        (Status::Unchanged, Some((11, 21)))
+    );
+}
+
+#[test]
+fn the_search_window_grows_with_the_edit() {
+    // 10,000 chars inserted ahead of the quote move it further than the
+    // minimum window from where proportional scaling expects it; a worse
+    // match sits there.
+    let filler = "x".repeat(100);
+    let old = format!("{filler}payment schedule B{filler}");
+    let decoy = "paymant schedulx B";
+    let new = format!(
+        "{}{decoy}{}{filler}paymentt schedule B{filler}",
+        "y".repeat(5_000),
+        "y".repeat(5_000 - decoy.len()),
+    );
+    let anchors = reanchor(&old, &new, &[annotation("payment schedule B", 100)]);
+    assert_eq!(anchors[0].status, Status::Fuzzy);
+    assert_eq!(anchors[0].range.map(|(s, _)| s), Some(10_100));
+}
+
+#[test]
+fn offsets_out_of_range_are_clamped_or_refused() {
+    let old = "The fee is due in thirty days.";
+    let new = "Note: the fee is due in thirty days.";
+    let parse = |text: &str| Annotation::list_from_json(&json::parse(text).unwrap());
+    let huge = parse(r#"[{"id": 1, "quote": "thirty days", "start": 0, "end": 1e20}]"#).unwrap();
+    let far = parse(r#"[{"id": 2, "quote": "thirty days", "start": 1e20}]"#).unwrap();
+    for annotations in [huge, far] {
+        let anchors = reanchor(old, new, &annotations);
+        assert_eq!(anchors[0].status, Status::Moved);
+        assert_eq!(anchors[0].range, Some((24, 35)));
+    }
+    let backwards = parse(r#"[{"quote": "fee", "start": 8, "end": 4}]"#).unwrap_err();
+    assert_eq!(backwards.kind(), "invalid_argument");
+}
@@ -0,0 +1,55 @@
+//! The document registry: content hashes, changes and linked versions.
+
+use std::fs;
+
+use synth_core::registry::{self, Change};
+
+#[test]
+fn versions_follow_edits_and_moves() {
+    let dir = std::env::temp_dir().join(format!("synth-registry-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    registry::init(&dir.join("state")).unwrap();
+    let path = dir.join("terms.md");
+    fs::write(&path, "Net 30.").unwrap();
+
+    let first = registry::record(&path).unwrap();
+    assert_eq!(first.change, Change::New);
+    assert_eq!((first.version.version, first.version.size), (1, 7));
+    assert_eq!(
+        first.version.sha256,
+        "7b997277c55a697d8b6e654593a9c790adca06e62dc83ff3fa4714c283972e20"
+    );
+    assert_eq!(registry::record(&path).unwrap().change, Change::Unchanged);
+
+    fs::write(&path, "Net 45.").unwrap();
+    assert_eq!(registry::check(&path).unwrap().0, Change::Modified);
+    let second = registry::record(&path).unwrap();
+    assert_eq!(second.change, Change::Modified);
+    assert_eq!(second.version.document, first.version.document);
+    assert_eq!(second.version.version, 2);
+    assert_eq!(second.previous.unwrap().sha256, first.version.sha256);
+
+    let moved = dir.join("payment-terms.md");
+    fs::rename(&path, &moved).unwrap();
+    let third = registry::record(&moved).unwrap();
+    assert_eq!(third.change, Change::Moved);
+    assert_eq!(
+        (third.version.document, third.version.version),
+        (first.version.document, 3)
+    );
+    let history: Vec<u64> = registry::history(&moved)
+        .iter()
+        .map(|v| v.version)
+        .collect();
+    assert_eq!(history, [1, 2, 3]);
+    assert!(registry::history(&path).is_empty());
+
+    let saved = fs::read_to_string(dir.join("state").join("registry.json")).unwrap();
+    assert_eq!(saved.matches("\"sha256\"").count(), 3);
+    assert!(registry::forget(&moved));
+    assert!(!registry::documents()
+        .iter()
+        .any(|v| v.document == first.version.document));
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,78 @@
+//! JSON replies: validation against a schema and re-prompting with the
+//! violations.
+
+use synth_core::chat::chat_json;
+use synth_core::error::Error;
+use synth_core::json;
+use synth_core::schema::validate;
+
+const SCHEMA: &str = r#"{
+    "type": "object",
+    "required": ["title", "tags"],
+    "additionalProperties": false,
+    "properties": {
+        "title": {"type": "string", "minLength": 1},
+        "tags": {"type": "array", "items": {"enum": ["legal", "finance"]}, "maxItems": 2},
+        "score": {"type": "number", "minimum": 0, "maximum": 1}
+    }
+}"#;
+
+#[test]
+fn violations_name_where_they_are() {
+    let schema = json::parse(SCHEMA).unwrap();
+    let valid = json::parse(r#"{"title": "Terms", "tags": ["legal"], "score": 0.5}"#).unwrap();
+    assert!(validate(&valid, &schema).is_empty());
+
+    let invalid =
+        json::parse(r#"{"title": "", "tags": ["legal", "hr"], "score": 2, "extra": 1}"#).unwrap();
+    assert_eq!(
+        validate(&invalid, &schema),
+        [
+            "/title: shorter than 1 chars",
+            "/tags/1: must be one of \"legal\", \"finance\"",
+            "/score: greater than 1",
+            "/extra: unexpected property",
+        ]
+    );
+    assert_eq!(
+        validate(&json::parse("[]").unwrap(), &schema),
+        ["/: expected object, got array"]
+    );
+    let either = json::parse(r#"{"anyOf": [{"type": "integer"}, {"const": "none"}]}"#).unwrap();
+    assert!(validate(&json::parse("3").unwrap(), &either).is_empty());
+    assert_eq!(
+        validate(&json::parse("3.5").unwrap(), &either),
+        ["/: matches none of the anyOf schemas"]
+    );
+}
+
+#[test]
+fn invalid_replies_are_sent_back_with_the_violations() {
+    let schema = json::parse(SCHEMA).unwrap();
+    let mut requests = Vec::new();
+    let mut replies = [
+        "Sure! Here it is.",
+        r#"```json
+{"title": "Terms"}
+```"#,
+        r#"{"title": "Terms", "tags": []}"#,
+    ]
+    .into_iter();
+    let value = chat_json("Describe the contract.", &schema, 2, |request| {
+        requests.push(request.to_string());
+        Ok(replies.next().unwrap().to_string())
+    })
+    .unwrap();
+    assert_eq!(value.get("title").and_then(|v| v.as_str()), Some("Terms"));
+    assert_eq!(requests.len(), 3);
+    assert!(requests[0].contains("conforms to this JSON Schema"));
+    assert!(requests[1].contains("- the reply was not valid JSON"));
+    assert!(requests[2].contains("- /: missing required property \"tags\""));
+    assert!(requests[2].starts_with("Describe the contract."));
+
+    // Out of retries, the last problem is the error.
+    let err = chat_json("Describe.", &schema, 1, |_| Ok(r#"{"title": 1}"#.into())).unwrap_err();
+    assert!(matches!(&err, Error::Schema(v) if v.len() == 2), "{err:?}");
+    let err = chat_json("Describe.", &schema, 0, |_| Ok("no".into())).unwrap_err();
+    assert_eq!(err.kind(), "backend");
+}
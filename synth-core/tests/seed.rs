@@ -0,0 +1,107 @@
+//! Seeded chat calls: the seed reaches the backend and exact repeats are
+//! answered from the reply cache.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::thread;
+
+use synth_core::backend::{self, Config};
+use synth_core::chat::{self, ChatOptions};
+use synth_core::{cache, json};
+
+fn seeded(seed: u64) -> ChatOptions {
+    ChatOptions {
+        seed: Some(seed),
+        ..ChatOptions::default()
+    }
+}
+
+#[test]
+fn repeats_are_answered_from_the_cache() {
+    let dir = std::env::temp_dir().join(format!("synth-seed-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    cache::set_dir(Some(&dir)).unwrap();
+    let config =
+        json::parse(r#"{"profiles": {"m": {"type": "mock", "replies": ["one", "two", "three"]}}}"#)
+            .unwrap();
+    backend::configure(Config::from_json(&config).unwrap());
+
+    assert_eq!(chat::chat("hi", &seeded(7)).unwrap(), "one");
+    assert_eq!(chat::chat("hi", &seeded(7)).unwrap(), "one");
+    assert_eq!(chat::chat("hi", &seeded(8)).unwrap(), "two");
+    assert_eq!(chat::chat("hi", &ChatOptions::default()).unwrap(), "three");
+
+    // Recorded replies survive the in-memory cache.
+    cache::clear();
+    assert_eq!(chat::chat("hi", &seeded(7)).unwrap(), "one");
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+    cache::set_dir(None).unwrap();
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn ollama_receives_the_seed() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let server = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if line.trim().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    length = value.trim().parse().unwrap();
+                }
+            }
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        let reply = r#"{"response": "seeded"}"#;
+        write!(
+            reader.get_mut(),
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{reply}",
+            reply.len()
+        )
+        .unwrap();
+        String::from_utf8(body).unwrap()
+    });
+    let profile = backend::Profile::from_json(
+        "local",
+        &json::parse(&format!(
+            r#"{{"type": "ollama", "model": "llama3", "url": "{url}"}}"#
+        ))
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(backend::send(&profile, "hi", Some(42)).unwrap(), "seeded");
+    let body = json::parse(&server.join().unwrap()).unwrap();
+    assert_eq!(
+        body.get("options").and_then(|o| o.get("seed")),
+        Some(&json::Value::from(42.0))
+    );
+
+    // Larger seeds cannot reach Ollama exactly, so they are refused.
+    let big = backend::send(&profile, "hi", Some(chat::MAX_NUMBER_SEED + 1)).unwrap_err();
+    assert!(big.to_string().contains("2^53"), "{big}");
+}
+
+#[test]
+fn seeds_round_trip_exactly() {
+    let options = seeded(u64::MAX);
+    let text = options.to_json().to_string();
+    assert!(text.contains(r#""seed":"18446744073709551615""#), "{text}");
+    let parsed = ChatOptions::from_json(&json::parse(&text).unwrap());
+    assert_eq!(parsed.seed, Some(u64::MAX));
+
+    let number = |text: &str| ChatOptions::from_json(&json::parse(text).unwrap()).seed;
+    assert_eq!(number(r#"{"seed": 42}"#), Some(42));
+    assert_eq!(number(r#"{"seed": 9007199254740992}"#), Some(1 << 53));
+    // Already rounded by the time it is a double.
+    assert_eq!(number(r#"{"seed": 9007199254740993000}"#), None);
+    assert_eq!(number(r#"{"seed": 1.5}"#), None);
+}
@@ -0,0 +1,69 @@
+//! Sentence and paragraph boundaries, and their offsets in UTF-16.
+
+use synth_core::chunk::chunk_text;
+use synth_core::json::Value;
+use synth_core::segment::{segment, segments_to_json, utf16_offsets, Mode};
+
+fn texts(text: &str, mode: Mode) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    segment(text, mode)
+        .into_iter()
+        .map(|r| chars[r].iter().collect())
+        .collect()
+}
+
+#[test]
+fn sentences_end_at_terminators_but_not_abbreviations() {
+    let text = "Dr. Smith signed, e.g. today. Was it \"final?\" Yes!  See 4.2 below.";
+    assert_eq!(
+        texts(text, Mode::Sentence),
+        [
+            "Dr. Smith signed, e.g. today.",
+            "Was it \"final?\"",
+            "Yes!",
+            "See 4.2 below."
+        ]
+    );
+    assert_eq!(
+        texts("署名した。次へ。", Mode::Sentence),
+        ["署名した。", "次へ。"]
+    );
+    assert_eq!(
+        texts("No full stop\n\nNext block.", Mode::Sentence),
+        ["No full stop", "Next block."]
+    );
+}
+
+#[test]
+fn paragraphs_split_at_blank_lines() {
+    let text = "  First line\nsame paragraph.\n \n\nSecond.\n";
+    assert_eq!(
+        texts(text, Mode::Paragraph),
+        ["First line\nsame paragraph.", "Second."]
+    );
+}
+
+#[test]
+fn offsets_are_given_in_utf16_too() {
+    let text = "🙂 Hi there. Bye.";
+    assert_eq!(utf16_offsets("a🙂b"), [0, 1, 3, 4]);
+    let json = segments_to_json(text, &segment(text, Mode::Sentence));
+    let field = |i: usize, key: &str| {
+        json.as_array().unwrap()[i]
+            .get(key)
+            .and_then(Value::as_f64)
+            .unwrap() as usize
+    };
+    // The emoji is one character but two UTF-16 code units.
+    assert_eq!((field(0, "start"), field(0, "end")), (0, 11));
+    assert_eq!((field(0, "start_utf16"), field(0, "end_utf16")), (0, 12));
+    assert_eq!((field(1, "start"), field(1, "start_utf16")), (12, 13));
+    assert_eq!(field(1, "end_utf16"), text.encode_utf16().count());
+
+    let chunks = chunk_text("🙂🙂 abc", 3);
+    let ranges: Vec<(usize, usize, usize, usize)> = chunks
+        .iter()
+        .map(|c| (c.start, c.end, c.start_utf16, c.end_utf16))
+        .collect();
+    assert_eq!(ranges, [(0, 3, 0, 5), (3, 6, 5, 8)]);
+}
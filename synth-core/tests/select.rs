@@ -0,0 +1,87 @@
+//! Selecting a section, a heading range, lines or pages of a document.
+
+use std::path::Path;
+
+use synth_core::extract::extract_text;
+use synth_core::json;
+use synth_core::select::{select, Selection};
+
+const TEXT: &str = "\
+# Agreement
+Intro.
+## 7. Termination
+Ninety days.
+### Notice
+In writing.
+## 8. Law
+Delaware.
+";
+
+fn selection(value: &str) -> Selection {
+    Selection::from_json(&json::parse(value).unwrap()).unwrap()
+}
+
+#[test]
+fn a_section_runs_to_the_next_heading_at_its_level() {
+    let chosen = select(TEXT, &selection(r#"{"section": "7"}"#)).unwrap();
+    assert_eq!(
+        chosen,
+        "## 7. Termination\nNinety days.\n### Notice\nIn writing.\n"
+    );
+    let notice = select(TEXT, &selection(r#"{"section": "NOTICE"}"#)).unwrap();
+    assert_eq!(notice, "### Notice\nIn writing.\n");
+    let err = select(TEXT, &selection(r#"{"section": "Annex"}"#)).unwrap_err();
+    assert_eq!(err.to_string(), "parse error: no heading 'annex'");
+}
+
+#[test]
+fn heading_ranges_and_line_ranges() {
+    let range = select(
+        TEXT,
+        &selection(r#"{"from_heading": "Notice", "to_heading": "8. Law"}"#),
+    )
+    .unwrap();
+    assert_eq!(range, "### Notice\nIn writing.\n");
+    let rest = select(TEXT, &selection(r#"{"from_heading": "8"}"#)).unwrap();
+    assert_eq!(rest, "## 8. Law\nDelaware.\n");
+    let lines = select(TEXT, &selection(r#"{"lines": [2, 3]}"#)).unwrap();
+    assert_eq!(lines, "Intro.\n## 7. Termination\n");
+    let tail = select(TEXT, &selection(r#"{"lines": [8]}"#)).unwrap();
+    assert_eq!(tail, "Delaware.\n");
+
+    let none = Selection::from_json(&json::parse("{}").unwrap()).unwrap_err();
+    assert_eq!(none.kind(), "parse");
+}
+
+#[test]
+fn page_ranges_of_a_pdf() {
+    let pdf = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/corpus/columns.pdf");
+    let text = extract_text(Path::new(pdf)).unwrap();
+    let second = select(&text, &selection(r#"{"pages": 2}"#)).unwrap();
+    assert!(second.starts_with("Supply remains the main risk"));
+    assert!(!second.contains("Market Outlook"));
+    let first = select(&text, &selection(r#"{"pages": [1, 1]}"#)).unwrap();
+    assert!(first.starts_with("Market Outlook"));
+    assert!(!first.contains("Supply"));
+    let both = select(&text, &selection(r#"{"pages": [1, 9]}"#)).unwrap();
+    assert_eq!(both, text);
+    assert_eq!(
+        select(&text, &selection(r#"{"pages": [3, 4]}"#))
+            .unwrap_err()
+            .kind(),
+        "invalid_argument"
+    );
+    // Text without page breaks is one page.
+    assert_eq!(select(TEXT, &selection(r#"{"pages": [1]}"#)).unwrap(), TEXT);
+
+    for bad in [
+        r#"{"pages": 0}"#,
+        r#"{"pages": [0, 2]}"#,
+        r#"{"pages": [3, 2]}"#,
+        r#"{"pages": [1.5]}"#,
+        r#"{"pages": "1-2"}"#,
+    ] {
+        let err = Selection::from_json(&json::parse(bad).unwrap()).unwrap_err();
+        assert_eq!(err.kind(), "invalid_argument", "{bad}");
+    }
+}
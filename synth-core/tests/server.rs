@@ -0,0 +1,105 @@
+//! `synth-server` end to end, on an ephemeral port with a mock backend.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::process::{Child, Command, Stdio};
+
+struct Server {
+    child: Child,
+    addr: String,
+}
+
+impl Server {
+    fn start() -> Server {
+        let config = std::env::temp_dir().join(format!("synth-server-{}.json", std::process::id()));
+        std::fs::write(
+            &config,
+            r#"{"profiles": {"mock": {"type": "mock", "default": "echo: {prompt}"}}}"#,
+        )
+        .unwrap();
+        let mut child = Command::new(env!("CARGO_BIN_EXE_synth-server"))
+            .args(["--addr", "127.0.0.1:0", "--backends"])
+            .arg(&config)
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut line = String::new();
+        BufReader::new(child.stderr.take().unwrap())
+            .read_line(&mut line)
+            .unwrap();
+        let addr = line.trim().rsplit("http://").next().unwrap().to_string();
+        Server { child, addr }
+    }
+
+    fn request(&self, head: &str, body: &str) -> String {
+        self.send(&format!(
+            "{head}\r\nHost: test\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        ))
+    }
+
+    fn send(&self, raw: &str) -> String {
+        let mut stream = TcpStream::connect(&self.addr).unwrap();
+        stream.write_all(raw.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[test]
+fn serves_extraction_and_chat() {
+    let server = Server::start();
+
+    let health = server.request("GET /health HTTP/1.1", "");
+    assert!(health.starts_with("HTTP/1.1 200"), "{health}");
+
+    let extract = server.request("POST /extract?name=data.csv HTTP/1.1", "a,b\n1,2\n");
+    assert!(
+        extract.contains(r#""text":"| a | b |\n| --- | --- |\n| 1 | 2 |\n""#),
+        "{extract}"
+    );
+
+    let chat = server.request(
+        "POST /chat HTTP/1.1\r\nContent-Type: application/json\r\nAccept: text/event-stream",
+        r#"{"prompt": "hello"}"#,
+    );
+    assert!(
+        chat.contains("event: reply\ndata: echo: hello\n\nevent: done"),
+        "{chat}"
+    );
+
+    let tagged = server.request(
+        "POST /chat HTTP/1.1\r\nContent-Type: application/json\r\nX-Synth-Tenant: acme\r\nX-Synth-User: ann",
+        r#"{"prompt": "hi"}"#,
+    );
+    assert!(tagged.starts_with("HTTP/1.1 200"), "{tagged}");
+    let usage = server.request("GET /usage?tenant_id=acme HTTP/1.1", "");
+    assert!(
+        usage.contains(r#"{"usage":[{"tenant_id":"acme","user_id":"ann","calls":1,"failures":0,"#),
+        "{usage}"
+    );
+
+    let missing = server.request("POST /ask?name=a.txt HTTP/1.1", "text");
+    assert!(missing.starts_with("HTTP/1.1 400"), "{missing}");
+}
+
+#[test]
+fn rejects_chunks_past_the_body_limit() {
+    let server = Server::start();
+    // A chunk size that would overflow the running body length.
+    let huge = server.send(
+        "POST /extract?name=a.txt HTTP/1.1\r\nHost: test\r\nTransfer-Encoding: chunked\r\n\r\n\
+         2\r\nhi\r\nffffffffffffffff\r\n",
+    );
+    assert!(huge.starts_with("HTTP/1.1 413"), "{huge}");
+    let health = server.request("GET /health HTTP/1.1", "");
+    assert!(health.starts_with("HTTP/1.1 200"), "{health}");
+}
@@ -0,0 +1,88 @@
+//! Chat sessions: sending, regenerating the last reply and branching.
+
+use synth_core::error::Error;
+use synth_core::session::{Role, Session};
+
+fn replies<'a>(
+    texts: &'a [&'a str],
+    prompts: &'a mut Vec<String>,
+) -> impl FnMut(&str) -> synth_core::error::Result<String> + 'a {
+    let mut next = texts.iter();
+    move |prompt| {
+        prompts.push(prompt.to_string());
+        Ok(format!(" {} ", next.next().unwrap()))
+    }
+}
+
+#[test]
+fn regenerate_replaces_the_last_reply() {
+    let mut prompts = Vec::new();
+    let mut session = Session::default();
+    let mut chat = replies(&["Hello.", "Net 30.", "Thirty days."], &mut prompts);
+    assert_eq!(session.send("Hi", &mut chat).unwrap(), "Hello.");
+    assert_eq!(session.send("Terms?", &mut chat).unwrap(), "Net 30.");
+    assert_eq!(session.regenerate(&mut chat).unwrap(), "Thirty days.");
+    drop(chat);
+    let texts: Vec<&str> = session.turns.iter().map(|t| t.text.as_str()).collect();
+    assert_eq!(texts, ["Hi", "Hello.", "Terms?", "Thirty days."]);
+    // The regenerated prompt is the one that produced the replaced reply.
+    assert_eq!(prompts[2], prompts[1]);
+    assert!(prompts[1].ends_with("User: Hi\n\nAssistant: Hello.\n\nUser: Terms?\n\nAssistant:"));
+    assert!(session.tokens_used > 0);
+
+    // A failing backend leaves the reply in place.
+    let err = session
+        .regenerate(|_| Err(Error::Backend("down".into())))
+        .unwrap_err();
+    assert_eq!(err.kind(), "backend");
+    assert_eq!(session.turns[3].text, "Thirty days.");
+    assert!(session
+        .send("More?", |_| Err(Error::Backend("down".into())))
+        .is_err());
+    assert_eq!(session.turns.len(), 4);
+}
+
+#[test]
+fn branches_keep_the_turns_before_the_cut() {
+    let mut session = Session::default();
+    session.send("Hi", |_| Ok("Hello.".into())).unwrap();
+    session.send("Terms?", |_| Ok("Net 30.".into())).unwrap();
+
+    let mut branch = session.branch(2).unwrap();
+    assert_eq!(branch.turns.len(), 2);
+    assert_eq!(branch.tokens_used, session.tokens_used);
+    branch.send("Price?", |_| Ok("$5.".into())).unwrap();
+    assert_eq!(branch.turns[2].text, "Price?");
+    assert_eq!(session.turns[2].text, "Terms?");
+    assert!(session.branch(5).is_none());
+
+    // A branch ending on a user turn has nothing to regenerate.
+    let mut asked = session.branch(1).unwrap();
+    assert_eq!(asked.turns[0].role, Role::User);
+    assert_eq!(
+        asked.regenerate(|_| Ok("x".into())).unwrap_err().kind(),
+        "parse"
+    );
+    let saved = Session::from_saved_json(&branch.to_saved_json()).unwrap();
+    assert_eq!(saved, branch);
+}
+
+#[test]
+fn handles_address_sessions_in_the_registry() {
+    use synth_core::session;
+
+    let handle = session::open(Session::default());
+    assert_ne!(handle, 0);
+    let mut first = session::get(handle).unwrap();
+    first.send("Hi", |_| Ok("Hello.".into())).unwrap();
+    let forked = session::open(first);
+    let branch = session::branch(forked, 1).unwrap();
+    assert_ne!(branch, forked);
+    assert_eq!(session::get(branch).unwrap().turns.len(), 1);
+    assert_eq!(session::branch(forked, 3).unwrap_err().kind(), "parse");
+    assert_eq!(session::regenerate(handle).unwrap_err().kind(), "parse");
+    for handle in [handle, forked, branch] {
+        assert!(session::close(handle));
+    }
+    assert!(session::get(handle).is_none());
+}
@@ -0,0 +1,64 @@
+//! Shutdown: jobs are cancelled, child processes killed and worker
+//! threads joined.
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use synth_core::backend::{self, Config};
+use synth_core::jobs::{self, State};
+use synth_core::json::{self, Value};
+use synth_core::shutdown;
+
+#[test]
+fn shutdown_stops_jobs_and_children() {
+    let dir = std::env::temp_dir().join(format!("synth-shutdown-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let started = dir.join("started");
+    let script = dir.join("kiro-cli");
+    fs::write(
+        &script,
+        format!("#!/bin/sh\ntouch '{}'\nexec sleep 30\n", started.display()),
+    )
+    .unwrap();
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+    let config = Value::object([(
+        "profiles",
+        Value::object([(
+            "slow",
+            Value::object([
+                ("type", Value::from("kiro")),
+                ("command", Value::from(script.display().to_string())),
+            ]),
+        )]),
+    )]);
+    backend::configure(Config::from_json(&config).unwrap());
+    let transcript = dir.join("meeting.txt");
+    fs::write(&transcript, "Ann: Ship on Friday.\nBob: Agreed.\n").unwrap();
+    let params = json::parse(&format!(
+        r#"{{"path": {:?}}}"#,
+        transcript.display().to_string()
+    ))
+    .unwrap();
+    let running = jobs::submit("generate_minutes", params.clone()).unwrap();
+    let queued = jobs::submit("generate_minutes", params).unwrap();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !started.exists() {
+        assert!(Instant::now() < deadline, "the backend never started");
+        thread::sleep(Duration::from_millis(20));
+    }
+
+    let began = Instant::now();
+    let report = shutdown::shutdown(Duration::from_secs(5));
+    assert!(began.elapsed() < Duration::from_secs(5));
+    assert_eq!(report.jobs_cancelled, 2);
+    assert_eq!(report.processes_killed, 1);
+    assert!(report.clean() && report.threads_joined >= 1, "{report:?}");
+    for id in [running, queued] {
+        assert_eq!(jobs::status(id).unwrap().state, State::Cancelled);
+    }
+    assert!(!shutdown::stopping());
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,37 @@
+//! Document statistics: counts, reading time and reading ease.
+
+use synth_core::json::Value;
+use synth_core::stats::document_stats;
+
+#[test]
+fn counts_and_reading_ease_of_a_short_text() {
+    let stats = document_stats("The cat sat. The dog ran.\n\nIt was late.");
+    assert_eq!((stats.words, stats.sentences, stats.paragraphs), (9, 3, 2));
+    assert_eq!(stats.characters, 39);
+    assert_eq!(stats.avg_sentence_words, 3.0);
+    // One syllable per word and three words per sentence.
+    let json = stats.to_json();
+    assert_eq!(
+        json.get("flesch_reading_ease").and_then(Value::as_f64),
+        Some(119.2)
+    );
+}
+
+#[test]
+fn long_words_read_harder_and_take_longer() {
+    let easy = document_stats("We go now. We can see it.");
+    let hard = document_stats(
+        "Notwithstanding contractual obligations, indemnification responsibilities \
+         automatically terminate immediately.",
+    );
+    assert!(hard.flesch_reading_ease < easy.flesch_reading_ease - 100.0);
+    let long = document_stats(&"word ".repeat(476));
+    assert_eq!(long.reading_minutes, 2.0);
+
+    let empty = document_stats("");
+    assert_eq!((empty.words, empty.sentences, empty.paragraphs), (0, 0, 0));
+    assert_eq!(
+        (empty.avg_sentence_words, empty.flesch_reading_ease),
+        (0.0, 0.0)
+    );
+}
@@ -0,0 +1,266 @@
+//! Persisted records in files, or in one SQLite database, compiled in with
+//! the `sqlite` feature or through the system `sqlite3`, and the stores
+//! kept through them.
+
+use std::fs;
+use std::path::PathBuf;
+#[cfg(not(feature = "sqlite"))]
+use std::process::Command;
+use std::sync::Mutex;
+use std::thread;
+
+use synth_core::chat::ChatOptions;
+use synth_core::error::Error;
+use synth_core::json;
+use synth_core::session::Session;
+use synth_core::store::{self, Config};
+use synth_core::usage::Tags;
+use synth_core::{memory, project};
+
+/// The store is configured for the whole process, so its tests take turns.
+static TURN: Mutex<()> = Mutex::new(());
+
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("synth-store-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Whether there is SQLite to test: compiled in, or an installed `sqlite3`.
+fn have_sqlite() -> bool {
+    #[cfg(feature = "sqlite")]
+    return true;
+    #[cfg(not(feature = "sqlite"))]
+    Command::new("sqlite3").arg("-version").output().is_ok()
+}
+
+/// `statement`'s first column, one row per line.
+#[cfg(feature = "sqlite")]
+fn sql(db: &std::path::Path, statement: &str) -> String {
+    let connection = rusqlite::Connection::open(db).unwrap();
+    let mut statement = connection.prepare(statement).unwrap();
+    let columns = statement.column_count();
+    let mut rows = statement.query([]).unwrap();
+    let mut lines = Vec::new();
+    while let Some(row) = rows.next().unwrap() {
+        if columns > 0 {
+            let value: rusqlite::types::Value = row.get(0).unwrap();
+            lines.push(match value {
+                rusqlite::types::Value::Integer(n) => n.to_string(),
+                rusqlite::types::Value::Text(text) => text,
+                other => format!("{other:?}"),
+            });
+        }
+    }
+    lines.join("\n")
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn sql(db: &std::path::Path, statement: &str) -> String {
+    let out = Command::new("sqlite3")
+        .arg(db)
+        .arg(statement)
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    String::from_utf8(out.stdout).unwrap().trim().to_string()
+}
+
+#[test]
+fn records_are_files_by_default() {
+    let _turn = TURN.lock().unwrap_or_else(|e| e.into_inner());
+    store::configure(None).unwrap();
+    let dir = scratch("files");
+    let path = dir.join("a.json");
+    assert_eq!(store::read(&path).unwrap(), None);
+    // Writers never share a temporary file, so the record is always one
+    // of the writes in full.
+    let writers: Vec<_> = (0..8)
+        .map(|i| {
+            let path = path.clone();
+            thread::spawn(move || {
+                for _ in 0..20 {
+                    store::write(&path, format!("writer {i} ").repeat(500).as_bytes()).unwrap();
+                }
+            })
+        })
+        .collect();
+    for writer in writers {
+        writer.join().unwrap();
+    }
+    let text = store::read_string(&path).unwrap().unwrap();
+    assert!((0..8).any(|i| text == format!("writer {i} ").repeat(500)));
+    assert_eq!(store::list(&dir).unwrap(), ["a.json"]);
+    assert!(store::exists(&path).unwrap());
+    assert!(store::remove(&path).unwrap());
+    assert!(!store::remove(&path).unwrap());
+    assert_eq!(store::schema_version().unwrap(), None);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn records_are_rows_of_a_migrated_database() {
+    let _turn = TURN.lock().unwrap_or_else(|e| e.into_inner());
+    if !have_sqlite() {
+        return;
+    }
+    let dir = scratch("db");
+    let db = dir.join("state/synth.db");
+    let config = Config::from_json(
+        &json::parse(&format!(
+            r#"{{"synth_db_path": {:?}}}"#,
+            db.display().to_string()
+        ))
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(config, Config::new(&db));
+    store::configure(Some(config.clone())).unwrap();
+    assert_eq!(store::schema_version().unwrap(), Some(1));
+    assert_eq!(sql(&db, "PRAGMA journal_mode;"), "wal");
+
+    // Any bytes, at any path, and no file is written.
+    let records = dir.join("it's here");
+    fs::create_dir_all(&records).unwrap();
+    let path = records.join("blob.bin");
+    let data: Vec<u8> = (0..=255)
+        .chain(b"'); DROP TABLE records; --".iter().copied())
+        .collect();
+    store::write(&path, &data).unwrap();
+    assert_eq!(store::read(&path).unwrap(), Some(data));
+    assert!(!path.exists());
+    assert!(store::exists(&path).unwrap());
+
+    // A record still in a file is read from it until written again.
+    fs::write(records.join("legacy.json"), "old").unwrap();
+    assert_eq!(store::list(&records).unwrap(), ["blob.bin", "legacy.json"]);
+    assert_eq!(
+        store::read_string(&records.join("legacy.json"))
+            .unwrap()
+            .unwrap(),
+        "old"
+    );
+    store::write(&records.join("legacy.json"), b"new").unwrap();
+    assert!(!records.join("legacy.json").exists());
+    assert_eq!(
+        store::read_string(&records.join("legacy.json"))
+            .unwrap()
+            .unwrap(),
+        "new"
+    );
+    assert!(store::remove(&path).unwrap());
+    assert_eq!(store::read(&path).unwrap(), None);
+
+    // Writers in parallel each wait for the database's lock.
+    let writers: Vec<_> = (0..8)
+        .map(|i| {
+            let path = records.join(format!("{i}.txt"));
+            thread::spawn(move || store::write(&path, format!("record {i}").as_bytes()))
+        })
+        .collect();
+    for writer in writers {
+        writer.join().unwrap().unwrap();
+    }
+    assert_eq!(store::list(&records).unwrap().len(), 9);
+    assert_eq!(
+        store::read_string(&records.join("7.txt")).unwrap().unwrap(),
+        "record 7"
+    );
+
+    // Stores keep their records there too.
+    let memories = dir.join("memories");
+    memory::init(&memories).unwrap();
+    memory::add("The fiscal year ends in March.", None, &Tags::default()).unwrap();
+    assert!(fs::read_dir(&memories).unwrap().next().is_none());
+    assert!(store::read_string(&memories.join("memories.json"))
+        .unwrap()
+        .unwrap()
+        .contains("fiscal year"));
+    let root = dir.join("projects");
+    let alpha = project::create(&root, "alpha").unwrap();
+    alpha
+        .save_session("kickoff", &Session::new(ChatOptions::default()))
+        .unwrap();
+    assert_eq!(alpha.sessions(), ["kickoff"]);
+    assert!(!root.join("alpha/project.json").exists());
+    assert!(project::create(&root, "alpha").is_err());
+    assert_eq!(project::list(&root).unwrap().len(), 1);
+    project::open(&root, "alpha")
+        .unwrap()
+        .load_session("kickoff")
+        .unwrap();
+    project::delete(&root, "alpha").unwrap();
+    assert!(project::list(&root).unwrap().is_empty());
+    assert_eq!(
+        sql(
+            &db,
+            "SELECT count(*) FROM records WHERE path LIKE '%alpha%';"
+        ),
+        "0"
+    );
+
+    // Configured again, the database is already at the latest version.
+    store::configure(None).unwrap();
+    assert_eq!(store::read(&records.join("7.txt")).unwrap(), None);
+    store::configure(Some(config)).unwrap();
+    assert_eq!(
+        store::read_string(&records.join("7.txt")).unwrap().unwrap(),
+        "record 7"
+    );
+    store::configure(None).unwrap();
+
+    // One from a newer build is refused.
+    let newer = dir.join("newer.db");
+    sql(&newer, "PRAGMA user_version = 99;");
+    let err = store::configure(Some(Config::new(&newer))).unwrap_err();
+    assert!(matches!(err, Error::Unsupported(_)), "{err}");
+    assert_eq!(store::schema_version().unwrap(), None);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Without the `sqlite` feature a database is the `sqlite3` on `PATH`.
+#[cfg(not(feature = "sqlite"))]
+#[test]
+fn a_database_needs_sqlite3() {
+    let _turn = TURN.lock().unwrap_or_else(|e| e.into_inner());
+    let dir = scratch("missing");
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    std::env::set_var("PATH", &dir);
+    let err = store::configure(Some(Config::new(&dir.join("synth.db")))).unwrap_err();
+    std::env::set_var("PATH", path);
+    assert!(matches!(err, Error::Unsupported(_)), "{err}");
+    // Which program runs is not the host's to choose.
+    let config = Config::from_json(
+        &json::parse(r#"{"synth_db_path": "/tmp/synth.db", "command": "/bin/sh"}"#).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(config, Config::new(std::path::Path::new("/tmp/synth.db")));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+/// With it, SQLite is compiled in and no program is run.
+#[cfg(feature = "sqlite")]
+#[test]
+fn a_database_needs_no_sqlite3() {
+    let _turn = TURN.lock().unwrap_or_else(|e| e.into_inner());
+    let dir = scratch("embedded");
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    std::env::set_var("PATH", &dir);
+    let configured = store::configure(Some(Config::new(&dir.join("synth.db"))));
+    let written = store::write(&dir.join("a.txt"), b"kept");
+    std::env::set_var("PATH", path);
+    configured.unwrap();
+    written.unwrap();
+    assert_eq!(
+        store::read_string(&dir.join("a.txt")).unwrap().as_deref(),
+        Some("kept")
+    );
+    assert!(!dir.join("a.txt").exists());
+    store::configure(None).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,122 @@
+//! Structured data as prompt context: each format in each style, with and
+//! without a depth limit.
+
+use synth_core::structured::{
+    render_json, render_xml, render_yaml, StructuredOptions, StructuredStyle,
+};
+
+fn options(style: StructuredStyle, max_depth: Option<usize>) -> StructuredOptions {
+    StructuredOptions {
+        style,
+        max_depth,
+        max_chars: None,
+    }
+}
+
+const JSON: &str = r#"{"name": "Acme", "terms": {"days": 30, "late": [1.5, "monthly"]}}"#;
+
+const XML: &str = r#"<deal id="7"><name>Acme</name><terms><days>30</days></terms></deal>"#;
+
+const YAML: &str = "\
+# supplier terms
+name: Acme
+terms:
+  days: 30 # net
+  late:
+    - 1.5
+    - monthly
+contacts:
+- name: Ana
+  role: buyer
+- {name: Bo, role: legal}
+notes: |
+  Paid by wire.
+  No cheques.
+";
+
+#[test]
+fn json_in_each_style() {
+    let pretty = render_json(JSON, &options(StructuredStyle::Pretty, None)).unwrap();
+    assert!(
+        pretty.contains("  \"terms\": {\n    \"days\": 30,"),
+        "{pretty}"
+    );
+    let shallow = render_json(JSON, &options(StructuredStyle::Pretty, Some(1))).unwrap();
+    assert!(shallow.contains("\"terms\": {… 2 keys}"), "{shallow}");
+
+    let flat = render_json(JSON, &options(StructuredStyle::Flat, None)).unwrap();
+    assert_eq!(
+        flat,
+        "name: Acme\nterms.days: 30\nterms.late[0]: 1.5\nterms.late[1]: monthly\n"
+    );
+    let shallow = render_json(JSON, &options(StructuredStyle::Flat, Some(1))).unwrap();
+    assert_eq!(shallow, "name: Acme\nterms: {… 2 keys}\n");
+}
+
+#[test]
+fn xml_in_each_style() {
+    let pretty = render_xml(XML, &options(StructuredStyle::Pretty, None)).unwrap();
+    assert_eq!(
+        pretty,
+        "<deal id=\"7\">\n  <name>Acme</name>\n  <terms>\n    <days>30</days>\n  </terms>\n</deal>\n"
+    );
+    let shallow = render_xml(XML, &options(StructuredStyle::Pretty, Some(1))).unwrap();
+    assert!(shallow.contains("  <terms>…</terms>"), "{shallow}");
+
+    let flat = render_xml(XML, &options(StructuredStyle::Flat, None)).unwrap();
+    assert_eq!(flat, "deal/@id: 7\ndeal/name: Acme\ndeal/terms/days: 30\n");
+    let shallow = render_xml(XML, &options(StructuredStyle::Flat, Some(1))).unwrap();
+    assert!(shallow.contains("deal/terms: <… 1 elements>"), "{shallow}");
+}
+
+#[test]
+fn yaml_in_each_style() {
+    // Without a depth limit pretty YAML is the text itself.
+    let pretty = render_yaml(YAML, &options(StructuredStyle::Pretty, None)).unwrap();
+    assert_eq!(pretty, YAML);
+
+    let flat = render_yaml(YAML, &options(StructuredStyle::Flat, None)).unwrap();
+    assert_eq!(
+        flat,
+        "name: Acme\nterms.days: 30\nterms.late[0]: 1.5\nterms.late[1]: monthly\n\
+         contacts[0].name: Ana\ncontacts[0].role: buyer\ncontacts[1].name: Bo\n\
+         contacts[1].role: legal\nnotes: Paid by wire.\nNo cheques.\n\n"
+    );
+    let shallow = render_yaml(YAML, &options(StructuredStyle::Flat, Some(1))).unwrap();
+    assert_eq!(
+        shallow,
+        "name: Acme\nterms: {… 2 keys}\ncontacts: [… 2 items]\nnotes: Paid by wire.\nNo cheques.\n\n"
+    );
+
+    let limited = render_yaml(YAML, &options(StructuredStyle::Pretty, Some(2))).unwrap();
+    assert_eq!(
+        limited,
+        "name: Acme\nterms:\n  days: 30\n  late: [… 2 items]\ncontacts:\n  \
+         - {… 2 keys}\n  - {… 2 keys}\nnotes: \"Paid by wire.\\nNo cheques.\\n\"\n"
+    );
+    let deep = render_yaml(YAML, &options(StructuredStyle::Pretty, Some(8))).unwrap();
+    assert!(
+        deep.contains("contacts:\n  - name: Ana\n    role: buyer\n  - name: Bo\n"),
+        "{deep}"
+    );
+}
+
+#[test]
+fn yaml_that_cannot_be_restructured_is_refused() {
+    let flat = options(StructuredStyle::Flat, None);
+    for text in [
+        "base: &base {a: 1}\nother: *base\n",
+        "when: !!timestamp 2024-01-01\n",
+        "a: 1\n---\nb: 2\n",
+    ] {
+        let err = render_yaml(text, &flat).unwrap_err();
+        assert_eq!(err.kind(), "invalid_argument", "{text}");
+    }
+    // Passing it through needs no parsing.
+    let pretty = options(StructuredStyle::Pretty, None);
+    assert!(render_yaml("a: &x 1\n", &pretty).is_ok());
+    assert_eq!(
+        render_yaml("a: 1\n  b: 2\n", &flat).unwrap_err().kind(),
+        "parse"
+    );
+}
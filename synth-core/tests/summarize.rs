@@ -0,0 +1,58 @@
+//! Summaries: short documents at once, long ones part by part and
+//! combined.
+
+use synth_core::json;
+use synth_core::summarize::{summarize, SummarizeOptions};
+
+#[test]
+fn a_short_document_is_summarized_at_once() {
+    let mut prompts = Vec::new();
+    let summary = summarize(
+        "The fee is due in May.",
+        &SummarizeOptions::default(),
+        |prompt| {
+            prompts.push(prompt.to_string());
+            Ok("  A fee is due.\n".into())
+        },
+        |_| {},
+    )
+    .unwrap();
+    assert_eq!(summary, "A fee is due.");
+    assert_eq!(prompts.len(), 1);
+    assert!(prompts[0].ends_with("Document:\nThe fee is due in May."));
+}
+
+#[test]
+fn a_long_document_is_summarized_in_parts_then_combined() {
+    let text = "The renewal terms were discussed at length today.\n".repeat(50);
+    let options = SummarizeOptions::from_json(
+        &json::parse(r#"{"max_chunk_chars": 1200, "instructions": "Use two bullet points."}"#)
+            .unwrap(),
+    );
+    let mut prompts = Vec::new();
+    let summary = summarize(
+        &text,
+        &options,
+        |prompt| {
+            prompts.push(prompt.to_string());
+            Ok(format!("Summary {}.", prompts.len()))
+        },
+        |_| {},
+    )
+    .unwrap();
+    // Three parts, then the combination.
+    assert_eq!(prompts.len(), 4);
+    assert!(prompts[0].contains("(part 1 of 3)"));
+    assert!(!prompts[0].contains("bullet"));
+    assert!(prompts[3].contains("Part 1:\nSummary 1.\n\nPart 2:\nSummary 2."));
+    assert!(prompts[3].contains("Use two bullet points."));
+    assert_eq!(summary, "Summary 4.");
+
+    let failed = summarize(
+        &text,
+        &options,
+        |_| Err(synth_core::error::Error::Backend("down".into())),
+        |_| {},
+    );
+    assert_eq!(failed.unwrap_err().kind(), "backend");
+}
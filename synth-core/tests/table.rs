@@ -0,0 +1,69 @@
+//! CSV/TSV rendering: header detection, ragged rows and row limits.
+
+use synth_core::table::{render_table, TableOptions, TableStyle, MAX_FIELD_BYTES};
+
+fn render(csv: &str, options: &TableOptions) -> String {
+    render_table(csv.as_bytes(), options).unwrap()
+}
+
+#[test]
+fn header_only_input_renders_an_empty_table() {
+    let options = TableOptions::default();
+    assert_eq!(
+        render("name,amount\n", &options),
+        "| name | amount |\n| --- | --- |\n"
+    );
+    let explicit = TableOptions {
+        header: Some(true),
+        ..TableOptions::default()
+    };
+    assert_eq!(
+        render("name,amount\n", &explicit),
+        "| name | amount |\n| --- | --- |\n"
+    );
+    assert_eq!(render("", &options), "");
+}
+
+#[test]
+fn rows_are_padded_to_the_widest() {
+    let csv = "name,amount\nrent,100\nfood,20,weekly\n";
+    assert_eq!(
+        render(csv, &TableOptions::default()),
+        "| name | amount | column 3 |\n\
+         | --- | --- | --- |\n\
+         | rent | 100 |  |\n\
+         | food | 20 | weekly |\n"
+    );
+    let records = TableOptions {
+        style: TableStyle::Records,
+        max_rows: Some(1),
+        ..TableOptions::default()
+    };
+    assert_eq!(
+        render(csv, &records),
+        "name: rent\namount: 100\n\n(1 more rows omitted)\n"
+    );
+}
+
+#[test]
+fn a_stray_quote_is_a_parse_error() {
+    let options = TableOptions::default();
+    // Quoted fields may still span lines.
+    assert_eq!(
+        render("name,note\nAna,\"two\nlines\"\n", &options),
+        "| name | note |\n| --- | --- |\n| Ana | two lines |\n"
+    );
+    let stray = render_table("name,note\nAna,\"open\nBo,x\n".as_bytes(), &options).unwrap_err();
+    assert_eq!(stray.kind(), "parse");
+    assert!(stray.to_string().contains("line 2"), "{stray}");
+
+    // A field cannot grow without bound before the end is reached.
+    let mut huge = String::from("a,b\n1,\"");
+    let line = format!("{}\n", "x".repeat(1000));
+    while huge.len() <= MAX_FIELD_BYTES + line.len() {
+        huge.push_str(&line);
+    }
+    huge.push_str("\"\n");
+    let err = render_table(huge.as_bytes(), &options).unwrap_err();
+    assert_eq!(err.kind(), "parse");
+}
@@ -0,0 +1,67 @@
+//! Telemetry: events reach the exporter only once one is set, and carry no
+//! content.
+
+use std::fs;
+use std::sync::{Arc, Mutex};
+
+use synth_core::backend::{self, Config};
+use synth_core::extract::extract_text;
+use synth_core::json::{self, Value};
+use synth_core::{chat, telemetry};
+
+#[test]
+fn operations_are_exported_without_their_content() {
+    let dir = std::env::temp_dir().join(format!("synth-telemetry-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let doc = dir.join("secret-plan.txt");
+    fs::write(&doc, "The merger closes Friday.").unwrap();
+    let profiles =
+        json::parse(r#"{"profiles": {"m": {"type": "mock", "default": "ok"}}}"#).unwrap();
+    backend::configure(Config::from_json(&profiles).unwrap());
+
+    let events: Arc<Mutex<Vec<Value>>> = Arc::default();
+    // Nothing is recorded before an exporter is set.
+    extract_text(&doc).unwrap();
+    let sink = Arc::clone(&events);
+    telemetry::set_exporter(Some(Arc::new(move |event| {
+        sink.lock().unwrap().push(event.to_json())
+    })));
+    extract_text(&doc).unwrap();
+    chat::kiro_chat("What closes Friday?").unwrap();
+    assert!(extract_text(&dir.join("missing.txt")).is_err());
+    telemetry::set_exporter(None);
+    chat::kiro_chat("again").unwrap();
+
+    let events = events.lock().unwrap();
+    let summary: Vec<(&str, bool, Option<&str>)> = events
+        .iter()
+        .map(|e| {
+            (
+                e.get("operation").and_then(Value::as_str).unwrap(),
+                e.get("ok").and_then(Value::as_bool).unwrap(),
+                e.get("error").and_then(Value::as_str),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("extract", true, None),
+            ("chat", true, None),
+            ("extract", false, Some("io")),
+        ]
+    );
+    let exported = format!("{events:?}");
+    for private in ["secret-plan", "merger", "Friday"] {
+        assert!(!exported.contains(private), "{exported}");
+    }
+    assert!(
+        events[0]
+            .get("duration_ms")
+            .and_then(Value::as_f64)
+            .unwrap()
+            >= 0.0
+    );
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,58 @@
+//! Mail-merge: placeholders filled per record, one file each.
+
+use std::fs;
+
+use synth_core::json;
+use synth_core::template::{fill, generate_documents};
+
+#[test]
+fn placeholders_take_nested_fields_and_report_missing_ones() {
+    let record = json::parse(r#"{"name": "Acme", "deal": {"days": 30}, "note": null}"#).unwrap();
+    let filled = fill(
+        "Dear {{ name }}, net {{deal.days}}. {{note}}{{ fax }}{{fax}}",
+        &record,
+    );
+    assert_eq!(filled.text, "Dear Acme, net 30. ");
+    assert_eq!(filled.missing, ["note", "fax"]);
+    // An unclosed placeholder is left as written.
+    assert_eq!(fill("{{name", &record).text, "{{name");
+}
+
+#[test]
+fn one_numbered_file_is_written_per_record() {
+    let dir = std::env::temp_dir().join(format!("synth-template-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let template = dir.join("offer.md");
+    fs::write(&template, "Offer for {{name}}").unwrap();
+    let records: Vec<_> = (1..=10)
+        .map(|i| json::parse(&format!(r#"{{"name": "client {i}"}}"#)).unwrap())
+        .chain([json::parse("{}").unwrap()])
+        .collect();
+
+    let mut progress = Vec::new();
+    let out = dir.join("out");
+    let generated = generate_documents(&template, &records, &out, |done, total| {
+        progress.push((done, total))
+    })
+    .unwrap();
+    assert_eq!(generated.written.len(), 11);
+    assert_eq!(generated.written[0], out.join("offer-01.md"));
+    assert_eq!(
+        fs::read_to_string(out.join("offer-10.md")).unwrap(),
+        "Offer for client 10"
+    );
+    assert_eq!(generated.missing, [(10, vec!["name".to_string()])]);
+    assert_eq!(progress.first(), Some(&(1, 11)));
+    assert_eq!(progress.last(), Some(&(11, 11)));
+
+    let binary = dir.join("offer.docx");
+    fs::write(&binary, "PK").unwrap();
+    assert_eq!(
+        generate_documents(&binary, &records, &out, |_, _| {})
+            .unwrap_err()
+            .kind(),
+        "unsupported"
+    );
+    let _ = fs::remove_dir_all(&dir);
+}
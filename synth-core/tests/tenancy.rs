@@ -0,0 +1,126 @@
+//! User and tenant tags keep memories, sessions, usage and audit records
+//! apart.
+
+use synth_core::backend::{self, Config};
+use synth_core::chat::{self, ChatOptions};
+use synth_core::json::{self, Value};
+use synth_core::usage::{self, Tags};
+use synth_core::{audit, memory, session};
+
+fn options(tenant: &str, user: &str) -> ChatOptions {
+    ChatOptions {
+        memory: true,
+        tenant_id: Some(tenant.into()),
+        user_id: Some(user.into()),
+        ..ChatOptions::default()
+    }
+}
+
+#[test]
+fn tags_separate_users() {
+    let config =
+        json::parse(r#"{"profiles": {"m": {"type": "mock", "default": "echo: {prompt}"}}}"#)
+            .unwrap();
+    backend::configure(Config::from_json(&config).unwrap());
+    let dir = std::env::temp_dir().join(format!("synth-tenancy-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    audit::configure(Some(
+        audit::Config::from_json(&Value::object([(
+            "dir",
+            Value::from(dir.display().to_string()),
+        )]))
+        .unwrap(),
+    ))
+    .unwrap();
+
+    let ann = options("acme", "ann");
+    let bob = options("acme", "bob");
+    memory::add("Ann's project codename is Bluebird", None, &ann.tags()).unwrap();
+    // The same fact may be stored once for each user.
+    memory::add("Ann's project codename is Bluebird", None, &bob.tags()).unwrap();
+    memory::add("The office is in Lisbon", None, &Tags::default()).unwrap();
+    memory::add("Ann's pager code is 4471", None, &ann.tags()).unwrap();
+
+    let reply = chat::chat("What is the pager code and office?", &ann).unwrap();
+    assert!(
+        reply.contains("4471") && reply.contains("Lisbon"),
+        "{reply}"
+    );
+    let reply = chat::chat("What is the pager code and office?", &bob).unwrap();
+    assert!(
+        !reply.contains("4471") && reply.contains("Lisbon"),
+        "{reply}"
+    );
+
+    let acme = Tags::new(Some("acme"), None);
+    let counted = usage::report(&acme);
+    assert_eq!(counted.len(), 2);
+    assert!(counted
+        .iter()
+        .all(|(_, u)| u.calls == 1 && u.prompt_tokens > 0));
+    let records = audit::query(&audit::Filter {
+        user_id: Some("bob".into()),
+        tenant_id: Some("acme".into()),
+        ..audit::Filter::default()
+    })
+    .unwrap();
+    assert_eq!(records.len(), 1);
+
+    let handle = session::create(bob.clone());
+    session::create(ChatOptions::default());
+    let listed = session::list(&Tags::new(None, Some("bob")));
+    let handles: Vec<f64> = listed
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|s| s.get("handle").and_then(Value::as_f64))
+        .collect();
+    assert_eq!(handles, [handle as f64]);
+
+    usage::reset(&Tags::new(None, Some("ann")));
+    assert_eq!(usage::report(&acme).len(), 1);
+    audit::configure(None).unwrap();
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn tenants_cannot_see_or_change_each_others_memories() {
+    let north = Tags::new(Some("north"), None);
+    let south = Tags::new(Some("south"), None);
+    let secret = memory::add("North's launch date is March 3", None, &north).unwrap();
+    memory::add("South's budget is frozen", None, &south).unwrap();
+    let shared = memory::add("Invoices are due in 30 days", None, &Tags::default()).unwrap();
+
+    let texts = |memories: Vec<memory::Memory>| -> Vec<String> {
+        memories.into_iter().map(|m| m.text).collect()
+    };
+    let seen = texts(memory::list(&south));
+    assert!(seen.contains(&"South's budget is frozen".to_string()));
+    assert!(seen.contains(&"Invoices are due in 30 days".to_string()));
+    assert!(
+        !seen.iter().any(|text| text.starts_with("North")),
+        "{seen:?}"
+    );
+    assert!(memory::search("launch date March", 0, &south)
+        .iter()
+        .all(|(m, _)| m.id != secret.id));
+    assert_eq!(
+        memory::search("launch date March", 0, &north)[0].0.id,
+        secret.id
+    );
+    // A caller without tags sees only what everyone may.
+    assert!(memory::list(&Tags::default())
+        .iter()
+        .all(|m| m.tags == Tags::default()));
+
+    assert!(!memory::update(secret.id, "changed", &south));
+    assert!(!memory::remove(secret.id, &south));
+    assert!(!memory::remove(shared.id, &south));
+    assert!(memory::update(
+        secret.id,
+        "North's launch date is March 4",
+        &north
+    ));
+    assert!(memory::remove(secret.id, &north));
+    assert!(memory::remove(shared.id, &Tags::default()));
+}
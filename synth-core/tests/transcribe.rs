@@ -0,0 +1,111 @@
+//! Audio transcription through `whisper-cli` with the `transcribe`
+//! feature, and audio files as extraction input.
+
+use synth_core::json;
+use synth_core::transcribe::Config;
+
+#[test]
+fn config_needs_a_model() {
+    let config = Config::from_json(&json::parse(r#"{"model": "base.bin"}"#).unwrap()).unwrap();
+    assert_eq!(config.command, "whisper-cli");
+    assert_eq!(config.ffmpeg, "ffmpeg");
+    assert_eq!(config.language, None);
+    assert!(Config::from_json(&json::parse(r#"{"language": "en"}"#).unwrap()).is_err());
+}
+
+#[cfg(feature = "transcribe")]
+#[test]
+fn whisper_cli_transcribes_and_ffmpeg_converts_the_rest() {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    use synth_core::error::Error;
+    use synth_core::{extract, transcribe};
+
+    let dir = std::env::temp_dir().join(format!("synth-transcribe-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let script = |name: &str, body: &str| {
+        let path = dir.join(name);
+        fs::write(&path, format!("#!/bin/sh\n{body}")).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        path.display().to_string()
+    };
+    // Stand-ins: whisper-cli prints a padded segment then its arguments;
+    // ffmpeg writes a WAV header to its last argument.
+    let whisper = script(
+        "whisper-cli",
+        "echo '  Welcome, everyone.  '\necho\necho \"$@\"\n",
+    );
+    let ffmpeg = script(
+        "ffmpeg",
+        "for arg; do out=$arg; done\nprintf 'RIFF\\0\\0\\0\\0WAVE' > \"$out\"\n",
+    );
+    let broken = script("broken-ffmpeg", "exit 1\n");
+    let model = dir.join("ggml-tiny.bin");
+    let wav = dir.join("standup.wav");
+    fs::write(&wav, b"RIFF\0\0\0\0WAVEfmt ").unwrap();
+    let m4a = dir.join("standup.m4a");
+    fs::write(&m4a, b"\0\0\0\x20ftypM4A ").unwrap();
+    let configure = |ffmpeg: &str| {
+        let config = json::parse(&format!(
+            r#"{{"model": {:?}, "command": {whisper:?}, "ffmpeg": {ffmpeg:?}, "language": "en"}}"#,
+            model.display().to_string()
+        ))
+        .unwrap();
+        transcribe::configure(Some(Config::from_json(&config).unwrap()));
+    };
+
+    transcribe::configure(None);
+    assert!(matches!(
+        transcribe::transcribe(&wav),
+        Err(Error::Unsupported(_))
+    ));
+    configure(&ffmpeg);
+    let err = transcribe::transcribe(&wav).unwrap_err();
+    assert!(err.to_string().contains("model not found"), "{err}");
+
+    fs::write(&model, b"ggml").unwrap();
+    let text = transcribe::transcribe(&wav).unwrap();
+    assert_eq!(
+        text,
+        format!(
+            "Welcome, everyone.\n-m {} -f {} --no-timestamps --no-prints -l en\n",
+            model.display(),
+            wav.display()
+        )
+    );
+    assert_eq!(extract::extract_text(&wav).unwrap(), text);
+
+    // m4a is not read by whisper itself, so it goes through a scratch WAV
+    // that is removed afterwards.
+    let text = transcribe::transcribe(&m4a).unwrap();
+    let scratch = text
+        .split(" -f ")
+        .nth(1)
+        .and_then(|rest| rest.split(' ').next())
+        .unwrap();
+    assert!(scratch.ends_with(".wav") && scratch.contains("synth-audio-"));
+    assert!(!std::path::Path::new(scratch).exists());
+
+    configure(&broken);
+    assert!(matches!(
+        transcribe::transcribe(&m4a),
+        Err(Error::Unsupported(_))
+    ));
+    transcribe::configure(None);
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[cfg(not(feature = "transcribe"))]
+#[test]
+fn audio_needs_the_transcribe_feature() {
+    use synth_core::error::Error;
+    use synth_core::extract;
+
+    let wav = std::env::temp_dir().join(format!("synth-transcribe-{}.wav", std::process::id()));
+    std::fs::write(&wav, b"RIFF\0\0\0\0WAVE").unwrap();
+    let err = extract::extract_text(&wav).unwrap_err();
+    assert!(matches!(err, Error::Unsupported(_)), "{err}");
+    let _ = std::fs::remove_file(&wav);
+}
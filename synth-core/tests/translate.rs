@@ -0,0 +1,104 @@
+//! Translation, chunk by chunk, keeping the document's paragraphs and
+//! the glossary's terminology.
+
+use synth_core::glossary::{self, Glossary};
+use synth_core::json;
+use synth_core::translate::{translate, TranslateOptions};
+
+#[test]
+fn chunks_are_translated_and_paragraphs_kept() {
+    let paragraph = "The supplier delivers the goods within thirty days of the order. "
+        .repeat(6)
+        .trim_end()
+        .to_string();
+    let text = format!("{paragraph}\n\n{paragraph}\n\n\n{paragraph}\n");
+    let options = TranslateOptions::from_json(
+        &json::parse(r#"{"target": "German", "source": "English", "max_chunk_chars": 500}"#)
+            .unwrap(),
+    )
+    .unwrap();
+    let mut prompts = Vec::new();
+    let translation = translate(
+        &text,
+        &options,
+        |prompt| {
+            prompts.push(prompt.to_string());
+            Ok(format!(" Teil {}. \n", prompts.len()))
+        },
+        |_| {},
+    )
+    .unwrap();
+    assert_eq!(prompts.len(), 3);
+    assert!(prompts[0].starts_with("Translate the text below from English into German."));
+    assert!(prompts[0].ends_with(&paragraph));
+    assert_eq!(translation, "Teil 1.\n\nTeil 2.\n\n\nTeil 3.\n");
+}
+
+#[test]
+fn a_target_language_is_required() {
+    let options = |text: &str| TranslateOptions::from_json(&json::parse(text).unwrap());
+    assert_eq!(options("{}").unwrap_err().kind(), "parse");
+    assert_eq!(options(r#"{"target": " "}"#).unwrap_err().kind(), "parse");
+    let options = options(r#"{"target": "fr", "backend": "local"}"#).unwrap();
+    assert_eq!(options.target, "fr");
+    assert_eq!(options.chat.backend.as_deref(), Some("local"));
+}
+
+#[test]
+fn each_part_is_held_to_the_glossary_terms_it_contains() {
+    let entries = json::parse(
+        r#"[{"term": "purchase order", "use": "Bestellung", "avoid": ["Kaufauftrag"]},
+            {"term": "invoice", "use": "Rechnung"}]"#,
+    )
+    .unwrap();
+    glossary::define("translate-de", Glossary::from_json(&entries).unwrap());
+    let options = TranslateOptions::from_json(
+        &json::parse(r#"{"target": "German", "glossary": "translate-de", "max_chunk_chars": 500}"#)
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(options.glossary.as_deref(), Some("translate-de"));
+    // The pipeline checks the glossary, not each chat call.
+    assert_eq!(options.chat.glossary, None);
+
+    let first = "Send the purchase order today. ".repeat(12);
+    let second = "Pay the invoice within thirty days. ".repeat(12);
+    let text = format!("{}\n\n{}", first.trim_end(), second.trim_end());
+    let mut prompts = Vec::new();
+    let translation = translate(
+        &text,
+        &options,
+        |prompt| {
+            prompts.push(prompt.to_string());
+            Ok(match prompts.len() {
+                1 => "Senden Sie den Kaufauftrag heute.".into(),
+                2 => "Senden Sie die Bestellung heute.".into(),
+                _ => "Zahlen Sie die Rechnung.".into(),
+            })
+        },
+        |_| {},
+    )
+    .unwrap();
+    assert_eq!(
+        translation,
+        "Senden Sie die Bestellung heute.\n\nZahlen Sie die Rechnung."
+    );
+    assert_eq!(prompts.len(), 3);
+    // Each part gets the entries for its own terms only.
+    assert!(prompts[0].contains("\"purchase order\""));
+    assert!(!prompts[0].contains("Rechnung"));
+    assert!(prompts[1].contains("use \"Bestellung\" instead of \"Kaufauftrag\""));
+    assert!(prompts[2].contains("Rechnung"));
+    assert!(!prompts[2].contains("Bestellung"));
+
+    // A reply that keeps breaking it fails the translation.
+    let err = translate(&text, &options, |_| Ok("Kaufauftrag".into()), |_| {}).unwrap_err();
+    assert_eq!(err.kind(), "terminology");
+
+    let unknown = TranslateOptions {
+        glossary: Some("missing".into()),
+        ..TranslateOptions::new("German")
+    };
+    assert!(translate(&text, &unknown, |_| Ok(String::new()), |_| {}).is_err());
+    glossary::remove("translate-de");
+}
@@ -0,0 +1,131 @@
+//! Webhook signing, retries and job events, against a local listener.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use synth_core::backend::Secret;
+use synth_core::json::{self, Value};
+use synth_core::{jobs, webhook};
+
+struct Delivery {
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl Delivery {
+    fn header(&self, name: &str) -> &str {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map_or("", |(_, value)| value.as_str())
+    }
+}
+
+/// Answer each connection with the next status; report what was received.
+fn listen(statuses: Vec<u16>) -> (String, mpsc::Receiver<Delivery>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for status in statuses {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut headers = Vec::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    headers.push((name.trim().to_string(), value.trim().to_string()));
+                }
+            }
+            let delivery = Delivery {
+                headers,
+                body: String::new(),
+            };
+            let length: usize = delivery.header("content-length").parse().unwrap_or(0);
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            write!(
+                reader.get_mut(),
+                "HTTP/1.1 {status} X\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+            )
+            .unwrap();
+            let _ = sender.send(Delivery {
+                body: String::from_utf8(body).unwrap(),
+                ..delivery
+            });
+        }
+    });
+    (url, receiver)
+}
+
+#[test]
+fn signature_is_hmac_sha256_of_timestamp_and_body() {
+    assert_eq!(
+        webhook::signature(&"k".repeat(100), 1_700_000_000, r#"{"a":1}"#),
+        "sha256=e0b419ce90349e7bf07569fb4c3e646db7042eb8c074a060f9d00f9df36890d1"
+    );
+}
+
+#[test]
+fn retries_server_errors_and_signs_each_attempt() {
+    let (url, received) = listen(vec![503, 200]);
+    let hook = webhook::Webhook {
+        secret: Some(Secret::Literal("s3cret".into())),
+        backoff: Duration::from_millis(10),
+        ..webhook::Webhook::new(&url)
+    };
+    let data = Value::object([("id", Value::from(7.0))]);
+    assert_eq!(webhook::deliver(&hook, "job.done", &data).unwrap(), 200);
+
+    let first = received.recv().unwrap();
+    let second = received.recv().unwrap();
+    assert_eq!(first.body, second.body);
+    assert_eq!(
+        first.header("X-Synth-Delivery"),
+        second.header("X-Synth-Delivery")
+    );
+    assert_eq!(second.header("X-Synth-Event"), "job.done");
+    let timestamp: u64 = second.header("X-Synth-Timestamp").parse().unwrap();
+    assert_eq!(
+        second.header("X-Synth-Signature"),
+        webhook::signature("s3cret", timestamp, &second.body)
+    );
+    let body = json::parse(&second.body).unwrap();
+    assert_eq!(body.get("data"), Some(&data));
+
+    // Client errors are final.
+    let (url, _received) = listen(vec![400]);
+    assert!(webhook::deliver(&webhook::Webhook::new(&url), "job.done", &data).is_err());
+}
+
+#[test]
+fn finished_jobs_are_announced() {
+    let (url, received) = listen(vec![200]);
+    webhook::configure(Some(webhook::Webhook {
+        events: vec!["job".into()],
+        ..webhook::Webhook::new(&url)
+    }));
+    let path = std::env::temp_dir().join(format!("synth-webhook-{}.txt", std::process::id()));
+    std::fs::write(&path, "hello").unwrap();
+    let id = jobs::submit(
+        "extract_text",
+        Value::object([("path", Value::from(path.display().to_string()))]),
+    )
+    .unwrap();
+    let delivery = received.recv_timeout(Duration::from_secs(10)).unwrap();
+    webhook::configure(None);
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(delivery.header("X-Synth-Event"), "job.done");
+    let data = json::parse(&delivery.body).unwrap();
+    let job = data.get("data").unwrap();
+    assert_eq!(job.get("id").and_then(Value::as_f64), Some(id as f64));
+    assert_eq!(job.get("state").and_then(Value::as_str), Some("done"));
+}
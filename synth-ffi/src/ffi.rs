@@ -0,0 +1,179 @@
+//! C string plumbing shared by the exported functions.
+//!
+//! All pointer handling lives here. The helpers that read a pointer are
+//! `unsafe fn`s whose safety rests on the contract in `synth_core.h`:
+//! string arguments are null or NUL-terminated, buffers are null or hold
+//! `len` readable bytes, and both stay valid for the duration of the call.
+//!
+//! Every entry point runs its body in [`ffi_guard`], since a panic that
+//! unwinds out of an `extern "C"` function aborts the host app.
+
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+
+use crate::paths;
+
+thread_local! {
+    static LAST_PANIC: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// What an entry point returns when it fails.
+pub(crate) trait Failure {
+    fn failure() -> Self;
+}
+
+impl Failure for *mut c_char {
+    fn failure() -> Self {
+        std::ptr::null_mut()
+    }
+}
+
+impl Failure for bool {
+    fn failure() -> Self {
+        false
+    }
+}
+
+impl Failure for u64 {
+    fn failure() -> Self {
+        0
+    }
+}
+
+impl Failure for () {
+    fn failure() -> Self {}
+}
+
+/// Run an entry point's body, returning its failure value (null, false
+/// or 0) if it panics. The panic's message is kept for [`last_panic`].
+pub(crate) fn ffi_guard<T: Failure>(body: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+        LAST_PANIC.with(|last| *last.borrow_mut() = Some(message));
+        T::failure()
+    })
+}
+
+/// The message of the last panic [`ffi_guard`] caught on this thread.
+pub(crate) fn last_panic() -> Option<String> {
+    LAST_PANIC.with(|last| last.borrow().clone())
+}
+
+/// Borrow a C string argument as UTF-8. Returns `None` for null or invalid input.
+///
+/// # Safety
+///
+/// `ptr` is null or points to a NUL-terminated string that stays valid and
+/// unmodified for `'a`.
+pub(crate) unsafe fn str_arg<'a>(ptr: *const c_char) -> Option<&'a str> {
+    if ptr.is_null() {
+        return None;
+    }
+    // SAFETY: upheld by the caller.
+    unsafe { CStr::from_ptr(ptr) }.to_str().ok()
+}
+
+/// Read a C string argument as a path. Unlike [`str_arg`], on Unix the
+/// bytes need not be UTF-8.
+///
+/// # Safety
+///
+/// `ptr` is null or points to a NUL-terminated string valid for the call.
+pub(crate) unsafe fn path_arg(ptr: *const c_char) -> Option<PathBuf> {
+    if ptr.is_null() {
+        return None;
+    }
+    // SAFETY: upheld by the caller.
+    paths::from_bytes(unsafe { CStr::from_ptr(ptr) }.to_bytes())
+}
+
+/// Borrow a byte buffer argument. Returns `None` for null input.
+///
+/// # Safety
+///
+/// `ptr` is null or points to `len` readable bytes that stay valid and
+/// unmodified for `'a`.
+pub(crate) unsafe fn bytes_arg<'a>(ptr: *const u8, len: usize) -> Option<&'a [u8]> {
+    if ptr.is_null() {
+        return None;
+    }
+    // SAFETY: upheld by the caller.
+    Some(unsafe { std::slice::from_raw_parts(ptr, len) })
+}
+
+/// Read the whole file behind a descriptor the host owns, from the start
+/// and without moving its offset or closing it.
+#[cfg(unix)]
+pub(crate) fn read_fd(fd: std::os::raw::c_int) -> std::io::Result<Vec<u8>> {
+    use std::io::Read;
+    use std::os::fd::BorrowedFd;
+    use std::os::unix::fs::FileExt;
+
+    if fd < 0 {
+        return Err(std::io::ErrorKind::InvalidInput.into());
+    }
+    // SAFETY: the caller passes an open descriptor that stays open for the
+    // duration of this call; it is duplicated, never closed, here.
+    let file = std::fs::File::from(unsafe { BorrowedFd::borrow_raw(fd) }.try_clone_to_owned()?);
+    let mut bytes = Vec::new();
+    let mut chunk = vec![0u8; 64 * 1024];
+    loop {
+        match file.read_at(&mut chunk, bytes.len() as u64) {
+            Ok(0) => return Ok(bytes),
+            Ok(n) => bytes.extend_from_slice(&chunk[..n]),
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            // Pipes and sockets cannot seek; read what is left instead.
+            Err(e) if bytes.is_empty() && e.kind() == std::io::ErrorKind::NotSeekable => {
+                (&file).read_to_end(&mut bytes)?;
+                return Ok(bytes);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Copy a C string returned by a host callback. Invalid UTF-8 is replaced.
+///
+/// # Safety
+///
+/// `ptr` is null or points to a NUL-terminated string valid for the call.
+pub(crate) unsafe fn copy_c_string(ptr: *const c_char) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+    // SAFETY: upheld by the caller.
+    Some(
+        unsafe { CStr::from_ptr(ptr) }
+            .to_string_lossy()
+            .into_owned(),
+    )
+}
+
+/// Hand a string to the caller, who must release it with `free_string`.
+/// Returns null if the string contains an interior NUL.
+pub(crate) fn into_c_string(s: String) -> *mut c_char {
+    CString::new(s)
+        .map(CString::into_raw)
+        .unwrap_or(std::ptr::null_mut())
+}
+
+/// Reclaim a string previously returned by `into_c_string`.
+///
+/// # Safety
+///
+/// `ptr` is null or came from [`into_c_string`] and has not been released.
+pub(crate) unsafe fn release_c_string(ptr: *mut c_char) {
+    if !ptr.is_null() {
+        // SAFETY: upheld by the caller.
+        unsafe {
+            drop(CString::from_raw(ptr));
+        }
+    }
+}
@@ -0,0 +1,2334 @@
+//! The C ABI for the Swift app: thin wrappers that convert arguments,
+//! call into `synth-core` and hand results back as C values.
+//!
+//! Every pointer is read or written through the helpers in `ffi`. Calling
+//! them is `unsafe`: each call relies on the header's contract that
+//! pointer arguments are null or valid for the duration of the call.
+//! Each body runs in `ffi_guard`, so a panic fails the call instead of
+//! aborting the host.
+
+// The entry points are called from C, which cannot see `unsafe`; their
+// pointer contract is the header's, and each read is an `unsafe` call.
+#![allow(clippy::not_unsafe_ptr_arg_deref)]
+
+use std::ffi::c_void;
+use std::os::raw::c_char;
+use std::time::Duration;
+
+mod ffi;
+mod paths;
+mod progress;
+
+use ffi::{
+    bytes_arg, copy_c_string, ffi_guard, into_c_string, path_arg, release_c_string, str_arg,
+};
+use synth_core::json::Value;
+use synth_core::{
+    ask, audit, backend, cache, chat, classify, code, compare, concordance, dedup, docx, docx_text,
+    draft, embeddings, encryption, error, extract, glossary, hooks, ingest, injection, jobs, json,
+    keywords, live, memory, minutes, models, normalize, ocr, outline, pdf, pdf_markup, pdf_text,
+    policy, pptx, prefetch, project, reanchor, registry, segment, select, selftest, session,
+    shutdown, stats, store, structured, summarize, table, telemetry, template, transcribe,
+    translate, usage, webhook,
+};
+
+/// Progress callback: `(completed, total, user_data)`.
+pub type ProgressCallback = extern "C" fn(u32, u32, *mut c_void);
+
+/// Stage progress callback: `(stage, completed, total, percent, item,
+/// user_data)`. `item` may be null; both strings are only valid during the
+/// call.
+pub type StageCallback = extern "C" fn(*const c_char, u32, u32, f64, *const c_char, *mut c_void);
+
+/// The message of the last panic caught in a call on this thread, which
+/// then failed as it does on error (null, false or 0), or null if none
+/// has panicked.
+#[no_mangle]
+pub extern "C" fn synth_last_panic() -> *mut c_char {
+    ffi_guard(|| ffi::last_panic().map_or(std::ptr::null_mut(), into_c_string))
+}
+
+/// Free a string allocated by Rust
+#[no_mangle]
+pub extern "C" fn free_string(s: *mut c_char) {
+    ffi_guard(|| {
+        unsafe { release_c_string(s) };
+    })
+}
+
+/// Send a prompt to kiro-cli and get the response
+#[no_mangle]
+pub extern "C" fn kiro_chat(prompt: *const c_char) -> *mut c_char {
+    ffi_guard(|| {
+        let prompt_str = match unsafe { str_arg(prompt) } {
+            Some(s) => s,
+            None => return std::ptr::null_mut(),
+        };
+
+        match chat::kiro_chat(prompt_str) {
+            Ok(text) => into_c_string(text),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Send a prompt through a chosen backend profile. `options_json` (may be
+/// null) is `{"backend": "fast-local", "stop": ["END"], "max_chars": 2000,
+/// "strip_fences": true, "memory": true, "seed": 42, "glossary":
+/// "contracts-de", "user_id": "...", "tenant_id": "..."}`; without a
+/// backend the default profile answers.
+/// With `memory`, relevant long-term memories the caller's tags allow are
+/// added to the prompt. The tags also go to the audit log and
+/// [`synth_usage`]. A `seed` is passed to backends that take one (give
+/// seeds above 2^53 as a decimal string), and repeats of a seeded request get the cached reply (see
+/// [`synth_reply_cache_init`]). A `glossary` given to
+/// [`synth_glossary_define`] has its relevant terms added to the prompt; a
+/// reply that breaks it is re-prompted once, then the call fails.
+/// The reply is cut at the first stop sequence, stripped of code fences and
+/// truncated, in that order. Returns the reply, or null on error.
+#[no_mangle]
+pub extern "C" fn synth_chat(prompt: *const c_char, options_json: *const c_char) -> *mut c_char {
+    ffi_guard(|| {
+        let Some(prompt) = (unsafe { str_arg(prompt) }) else {
+            return std::ptr::null_mut();
+        };
+        let options = match unsafe { str_arg(options_json) }.map(json::parse) {
+            None => chat::ChatOptions::default(),
+            Some(Ok(value)) => chat::ChatOptions::from_json(&value),
+            Some(Err(_)) => return std::ptr::null_mut(),
+        };
+        match chat::chat(prompt, &options) {
+            Ok(text) => into_c_string(text),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Answer `question` about the document at `path` from the document alone.
+/// A document too long for one prompt is answered from its passages most
+/// relevant to the question, found through the index [`synth_prefetch`]
+/// builds when the document was opened (or built now). `options_json` is
+/// as for [`synth_chat`]. Returns the answer, or null on error.
+#[no_mangle]
+pub extern "C" fn synth_ask(
+    path: *const c_char,
+    question: *const c_char,
+    options_json: *const c_char,
+) -> *mut c_char {
+    ffi_guard(|| {
+        let (Some(path), Some(question)) =
+            (unsafe { path_arg(path) }, unsafe { str_arg(question) })
+        else {
+            return std::ptr::null_mut();
+        };
+        let options = match unsafe { str_arg(options_json) }.map(json::parse) {
+            None => chat::ChatOptions::default(),
+            Some(Ok(value)) => chat::ChatOptions::from_json(&value),
+            Some(Err(_)) => return std::ptr::null_mut(),
+        };
+        let answer = extract::extract_text(&path)
+            .and_then(|text| chat::chat(&chat::ask_prompt(&text, question), &options));
+        match answer {
+            Ok(text) => into_c_string(text),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Configure named backend profiles, replacing any earlier configuration:
+/// `{"default": "smart", "profiles": {"smart": {"type": "kiro"},
+/// "fast-local": {"type": "ollama", "model": "llama3", "url":
+/// "http://localhost:11434", "timeout_secs": 60}}, "fallback": ["smart",
+/// "fast-local"]}`. Ollama profiles also accept `"api_key"`,
+/// `"api_key_env"` or `"api_key_secret"` (sent as a bearer token, and
+/// only to a localhost server and proxy, as requests are plain HTTP),
+/// `"headers": {...}` and `"proxy": "http://host:port"`. For host tests, a
+/// `{"type": "mock"}` profile answers from `"rules": [{"contains": "...",
+/// "reply": "..."}]`, then `"replies": [...]` in turn, then `"default"`
+/// (or the same keys in a `"fixture"` JSON file). When the selected profile
+/// errors or times out, the `fallback` profiles are tried in order.
+/// Returns false if the configuration is invalid, leaving the previous one
+/// in place.
+#[no_mangle]
+pub extern "C" fn synth_configure_backends(config_json: *const c_char) -> bool {
+    ffi_guard(|| {
+        let config = unsafe { str_arg(config_json) }
+            .and_then(|s| json::parse(s).ok())
+            .and_then(|value| backend::Config::from_json(&value).ok());
+        match config {
+            Some(config) => {
+                backend::configure(config);
+                true
+            }
+            None => false,
+        }
+    })
+}
+
+/// Telemetry exporter: receives one event as JSON, valid only during the
+/// call.
+pub type EventCallback = extern "C" fn(*const c_char, *mut c_void);
+
+/// Opt in to anonymous operational events: `{"operation": "chat" |
+/// "extract" | "job.<kind>", "duration_ms": 12.5, "ok": false, "error":
+/// "backend"}`. No paths, prompts or content are included. Pass a null
+/// `exporter` to opt out again. The exporter may be called from any
+/// thread.
+#[no_mangle]
+pub extern "C" fn synth_set_telemetry(exporter: Option<EventCallback>, user_data: *mut c_void) {
+    ffi_guard(|| {
+        let Some(exporter) = exporter else {
+            telemetry::set_exporter(None);
+            return;
+        };
+        // Stored as an address so the closure is Send; the host owns user_data.
+        let user_data = user_data as usize;
+        telemetry::set_exporter(Some(std::sync::Arc::new(
+            move |event: &telemetry::Event| {
+                if let Ok(json) = std::ffi::CString::new(event.to_json().to_string()) {
+                    exporter(json.as_ptr(), user_data as *mut c_void);
+                }
+            },
+        )));
+    })
+}
+
+/// Secret provider: receives a secret name (a profile's `api_key_secret`)
+/// and returns its value, or null if unknown.
+pub type SecretCallback = extern "C" fn(*const c_char, *mut c_void) -> *const c_char;
+
+/// Let HTTP backend profiles fetch API keys from the host (e.g. the
+/// Keychain) instead of storing them in config. `release` (may be null)
+/// frees returned strings once copied. Pass a null `provider` to remove it.
+/// The provider may be called from any thread.
+#[no_mangle]
+pub extern "C" fn synth_set_secret_provider(
+    provider: Option<SecretCallback>,
+    release: Option<ReleaseCallback>,
+    user_data: *mut c_void,
+) {
+    ffi_guard(|| {
+        let Some(provider) = provider else {
+            backend::set_secret_provider(None);
+            return;
+        };
+        // Stored as an address so the closure is Send; the host owns user_data.
+        let user_data = user_data as usize;
+        backend::set_secret_provider(Some(std::sync::Arc::new(move |name: &str| {
+            let name = std::ffi::CString::new(name).ok()?;
+            let output = provider(name.as_ptr(), user_data as *mut c_void);
+            let secret = unsafe { copy_c_string(output) };
+            if let (Some(release), false) = (release, output.is_null()) {
+                release(output, user_data as *mut c_void);
+            }
+            secret
+        })));
+    })
+}
+
+/// Name of the backend profile that answered the calling thread's most
+/// recent chat request (after any fallback), or null if none has.
+#[no_mangle]
+pub extern "C" fn synth_last_backend() -> *mut c_char {
+    ffi_guard(|| match backend::last_answered() {
+        Some(name) => into_c_string(name),
+        None => std::ptr::null_mut(),
+    })
+}
+
+/// Send a prompt and require a JSON reply matching `schema_json` (a JSON
+/// Schema subset: type, properties, required, items, enum, ...). Invalid
+/// replies are re-prompted with the violations up to `max_retries` times.
+/// Returns JSON: `{"ok": true, "value": ...}` or `{"ok": false, "error":
+/// "schema" | "backend" | "io", "message": "...", "violations": [...]}`;
+/// null only for invalid arguments or a panic.
+#[no_mangle]
+pub extern "C" fn kiro_chat_json(
+    prompt: *const c_char,
+    schema_json: *const c_char,
+    max_retries: u32,
+) -> *mut c_char {
+    ffi_guard(|| {
+        let (Some(prompt), Some(schema)) = (
+            unsafe { str_arg(prompt) },
+            unsafe { str_arg(schema_json) }.and_then(|s| json::parse(s).ok()),
+        ) else {
+            return std::ptr::null_mut();
+        };
+        let reply = match chat::chat_json(prompt, &schema, max_retries as usize, chat::kiro_chat) {
+            Ok(value) => Value::object([("ok", Value::from(true)), ("value", value)]),
+            Err(err) => {
+                let violations = match &err {
+                    error::Error::Schema(violations) => violations.clone(),
+                    _ => Vec::new(),
+                };
+                Value::object([
+                    ("ok", Value::from(false)),
+                    ("error", Value::from(err.kind())),
+                    ("message", Value::from(err.to_string())),
+                    (
+                        "violations",
+                        Value::Array(violations.into_iter().map(Value::from).collect()),
+                    ),
+                ])
+            }
+        };
+        into_c_string(reply.to_string())
+    })
+}
+
+/// Guardrail hook: receives the prompt or reply and returns a replacement,
+/// or null to leave it unchanged.
+pub type HookCallback = extern "C" fn(*const c_char, *mut c_void) -> *const c_char;
+
+/// Called with each non-null string a hook returned, once it has been copied.
+pub type ReleaseCallback = extern "C" fn(*const c_char, *mut c_void);
+
+/// Register a guardrail hook that runs on every chat backend call: `stage`
+/// "pre" sees the prompt before it is sent, "post" the reply. `release`
+/// (may be null) frees the hook's returned strings. Hooks may be called
+/// from any thread. Returns the hook id, or 0 on invalid input.
+#[no_mangle]
+pub extern "C" fn synth_register_hook(
+    stage: *const c_char,
+    hook: Option<HookCallback>,
+    release: Option<ReleaseCallback>,
+    user_data: *mut c_void,
+) -> u64 {
+    ffi_guard(|| {
+        let (Some(stage), Some(hook)) = (
+            unsafe { str_arg(stage) }.and_then(hooks::Stage::from_name),
+            hook,
+        ) else {
+            return 0;
+        };
+        // Stored as an address so the closure is Send; the host owns user_data.
+        let user_data = user_data as usize;
+        hooks::register(
+            stage,
+            std::sync::Arc::new(move |text: &str| {
+                let Ok(input) = std::ffi::CString::new(text.replace('\0', "")) else {
+                    return text.to_string();
+                };
+                let output = hook(input.as_ptr(), user_data as *mut c_void);
+                let replaced = unsafe { copy_c_string(output) };
+                if let (Some(release), false) = (release, output.is_null()) {
+                    release(output, user_data as *mut c_void);
+                }
+                replaced.unwrap_or_else(|| text.to_string())
+            }),
+        )
+    })
+}
+
+/// Remove a hook registered with `synth_register_hook`. Returns false if
+/// the id is unknown.
+#[no_mangle]
+pub extern "C" fn synth_unregister_hook(id: u64) -> bool {
+    ffi_guard(|| hooks::unregister(id))
+}
+
+/// Return the heading outline of a markdown or text file as JSON:
+/// `[{"level": 1, "text": "Intro", "offset": 0, "offset_utf16": 0, "page":
+/// 1, "children": [...]}]`. Offsets are character offsets into the file,
+/// and in UTF-16 code units for `NSString` ranges. Pages count
+/// form-feed page breaks from 1, and are null in text without any.
+/// Returns null on error.
+#[no_mangle]
+pub extern "C" fn extract_outline(path: *const c_char) -> *mut c_char {
+    ffi_guard(|| {
+        let path = match unsafe { path_arg(path) } {
+            Some(p) => p,
+            None => return std::ptr::null_mut(),
+        };
+        match policy::read_file(&path) {
+            Ok(bytes) => {
+                let text = String::from_utf8_lossy(&bytes);
+                let headings = outline::outline(&text);
+                let json = Value::Array(headings.iter().map(outline::Heading::to_json).collect());
+                into_c_string(json.to_string())
+            }
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Extract text from a supported file (.txt, .md, .csv, .tsv, .json, .xml,
+/// .yaml, .html, .docx, .pptx, .pdf, images via OCR, audio with the `transcribe` feature, or source code). Tables are rendered as Markdown, structured data pretty-printed. Returns null on error or unsupported type.
+#[no_mangle]
+pub extern "C" fn extract_text(path: *const c_char) -> *mut c_char {
+    ffi_guard(|| {
+        let path = match unsafe { path_arg(path) } {
+            Some(p) => p,
+            None => return std::ptr::null_mut(),
+        };
+        match extract::extract_text(&path) {
+            Ok(text) => into_c_string(text),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Start warming up for a document the user just opened: it is extracted
+/// in the background, so a later [`extract_text`] of the unchanged file is
+/// answered from memory, a document too long for one prompt is chunked
+/// and embedded for [`synth_ask`], and the default backend loads its
+/// model. Returns
+/// at once; false only if the path is null or the read policy forbids it.
+#[no_mangle]
+pub extern "C" fn synth_prefetch(path: *const c_char) -> bool {
+    ffi_guard(|| unsafe { path_arg(path) }.is_some_and(|path| prefetch::prefetch(&path).is_ok()))
+}
+
+/// Extract text as [`extract_text`] does, calling `on_delta` (may be null)
+/// with each pass as JSON, valid only during the call: for files over 64
+/// KiB first `{"pass": "preview", "text": "..."}` from the start of the
+/// file, then `{"pass": "text", "text": "..."}` with the full text,
+/// `{"pass": "outline", "outline": [...]}` and `{"pass": "stats", "stats":
+/// {...}}`. Returns the full text, or null on error (after any preview).
+#[no_mangle]
+pub extern "C" fn extract_text_live(
+    path: *const c_char,
+    on_delta: Option<EventCallback>,
+    user_data: *mut c_void,
+) -> *mut c_char {
+    ffi_guard(|| {
+        let Some(path) = (unsafe { path_arg(path) }) else {
+            return std::ptr::null_mut();
+        };
+        let result = live::extract_live(&path, |delta| {
+            if let (Some(callback), Ok(json)) = (
+                on_delta,
+                std::ffi::CString::new(delta.to_json().to_string()),
+            ) {
+                callback(json.as_ptr(), user_data);
+            }
+        });
+        match result {
+            Ok(text) => into_c_string(text),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Extract text from a file the host already opened, e.g. through a
+/// security-scoped URL in a sandboxed app. `fd` is read from the start and
+/// left open; `name` (may be null) is the file name or extension used to
+/// pick the format, and without it the contents are treated as plain text.
+/// Returns null on error, an unsupported type, or on non-Unix platforms.
+#[no_mangle]
+pub extern "C" fn extract_text_fd(fd: std::os::raw::c_int, name: *const c_char) -> *mut c_char {
+    ffi_guard(|| {
+        let format = match unsafe { path_arg(name) } {
+            Some(name) => {
+                // A bare extension ("csv") is accepted as well as a file name.
+                let name = match name.extension() {
+                    Some(_) => name,
+                    None => std::path::Path::new("file").with_extension(name),
+                };
+                match extract::Format::from_path(&name) {
+                    Some(format) => format,
+                    None => return std::ptr::null_mut(),
+                }
+            }
+            None => extract::Format::Text,
+        };
+        #[cfg(unix)]
+        let result = policy::check_descriptor(fd)
+            .and_then(|()| ffi::read_fd(fd).map_err(error::Error::from))
+            .and_then(|bytes| extract::extract_from_bytes_fallible(&bytes, format));
+        #[cfg(not(unix))]
+        let result: error::Result<String> = {
+            let _ = (fd, format);
+            Err(error::Error::Unsupported("file descriptors".into()))
+        };
+        match result {
+            Ok(text) => into_c_string(text),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Fetch an http(s) URL and extract its text. Web pages are reduced to
+/// their article (navigation, sidebars and comments dropped) with the title
+/// as a heading; other responses are extracted by their content type.
+/// Returns null on error or an unsupported type.
+#[no_mangle]
+pub extern "C" fn extract_url(url: *const c_char) -> *mut c_char {
+    ffi_guard(|| {
+        let Some(url) = (unsafe { str_arg(url) }) else {
+            return std::ptr::null_mut();
+        };
+        match ingest::extract_url(url) {
+            Ok(text) => into_c_string(text),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Save pasted text (e.g. the clipboard, or the result of [`extract_url`])
+/// as a Markdown document under `title` and return its path, for use with
+/// anything that takes document paths. `title` may be null. The same title
+/// and text always give the same file. Returns null on error or empty text.
+#[no_mangle]
+pub extern "C" fn ingest_text(text: *const c_char, title: *const c_char) -> *mut c_char {
+    ffi_guard(|| {
+        let Some(text) = (unsafe { str_arg(text) }) else {
+            return std::ptr::null_mut();
+        };
+        match ingest::ingest_text(text, unsafe { str_arg(title) }.unwrap_or("")) {
+            Ok(path) => into_c_string(path.display().to_string()),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Like [`extract_text`], but the path is given as `len` raw bytes (no NUL
+/// terminator needed), for paths that are not valid UTF-8.
+#[no_mangle]
+pub extern "C" fn extract_text_bytes(path: *const u8, len: usize) -> *mut c_char {
+    ffi_guard(|| {
+        let Some(path) = unsafe { bytes_arg(path, len) }.and_then(paths::from_bytes) else {
+            return std::ptr::null_mut();
+        };
+        match extract::extract_text(&path) {
+            Ok(text) => into_c_string(text),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Render a CSV/TSV file with the delimiter and header row auto-detected.
+/// `style` is "markdown" (pipe table) or "records" ("column: value" lines).
+/// `max_rows` caps the rendered data rows (0 = no limit); omitted rows are
+/// counted in a trailing note. Returns null on error.
+#[no_mangle]
+pub extern "C" fn extract_table(
+    path: *const c_char,
+    style: *const c_char,
+    max_rows: u32,
+) -> *mut c_char {
+    ffi_guard(|| {
+        let (path, style) = match (
+            unsafe { path_arg(path) },
+            unsafe { str_arg(style) }.and_then(table::TableStyle::from_name),
+        ) {
+            (Some(p), Some(s)) => (p, s),
+            _ => return std::ptr::null_mut(),
+        };
+        let options = table::TableOptions {
+            style,
+            max_rows: (max_rows > 0).then_some(max_rows as usize),
+            ..table::TableOptions::default()
+        };
+        match extract::extract_table(&path, &options) {
+            Ok(text) => into_c_string(text),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Render a JSON, XML or YAML file as prompt context.
+/// `style` is "pretty" (indented) or "flat" (`path.to.key: value` per leaf).
+/// Containers deeper than `max_depth` are summarized and output is cut at
+/// `max_chars` characters (0 = no limit for either). Pretty YAML without a
+/// depth limit is passed through as written. Returns null on error,
+/// malformed input, or YAML using anchors, aliases, tags or several
+/// documents when it has to be restructured.
+#[no_mangle]
+pub extern "C" fn extract_structured(
+    path: *const c_char,
+    style: *const c_char,
+    max_depth: u32,
+    max_chars: u32,
+) -> *mut c_char {
+    ffi_guard(|| {
+        let (path, style) = match (
+            unsafe { path_arg(path) },
+            unsafe { str_arg(style) }.and_then(structured::StructuredStyle::from_name),
+        ) {
+            (Some(p), Some(s)) => (p, s),
+            _ => return std::ptr::null_mut(),
+        };
+        let options = structured::StructuredOptions {
+            style,
+            max_depth: (max_depth > 0).then_some(max_depth as usize),
+            max_chars: (max_chars > 0).then_some(max_chars as usize),
+        };
+        match extract::extract_structured(&path, &options) {
+            Ok(text) => into_c_string(text),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// The slides of a .pptx presentation in the order shown, as JSON:
+/// `[{"number": 1, "title": "Intro", "body": "...", "notes": "..."}]`.
+/// `content` is "all", "body" (title and body only) or "notes" (speaker
+/// notes only). A slide without a title has `"title": null`. Returns null
+/// on error or an unknown `content`.
+#[no_mangle]
+pub extern "C" fn extract_slides(path: *const c_char, content: *const c_char) -> *mut c_char {
+    ffi_guard(|| {
+        let (path, content) = match (
+            unsafe { path_arg(path) },
+            unsafe { str_arg(content) }.and_then(pptx::SlideContent::from_name),
+        ) {
+            (Some(p), Some(c)) => (p, c),
+            _ => return std::ptr::null_mut(),
+        };
+        match pptx::slides(&path) {
+            Ok(slides) => into_c_string(pptx::slides_to_json(&slides, content).to_string()),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Set the whisper.cpp model and tools audio is transcribed with, or clear
+/// them when `config_json` is null. Config: `{"model":
+/// "/path/ggml-base.en.bin", "command": "whisper-cli", "ffmpeg": "ffmpeg",
+/// "language": "en", "timeout_secs": 600}`; only `model` is required.
+/// Returns false on malformed config.
+#[no_mangle]
+pub extern "C" fn synth_transcribe_configure(config_json: *const c_char) -> bool {
+    ffi_guard(|| {
+        if config_json.is_null() {
+            transcribe::configure(None);
+            return true;
+        }
+        unsafe { str_arg(config_json) }
+            .and_then(|s| json::parse(s).ok())
+            .and_then(|value| transcribe::Config::from_json(&value).ok())
+            .map(|config| transcribe::configure(Some(config)))
+            .is_some()
+    })
+}
+
+/// Set the `tesseract` command and languages images are read with, or
+/// restore the defaults when `config_json` is null. Config: `{"command":
+/// "tesseract", "language": "eng+deu", "timeout_secs": 120}`. Returns false
+/// on malformed config.
+#[no_mangle]
+pub extern "C" fn synth_ocr_configure(config_json: *const c_char) -> bool {
+    ffi_guard(|| {
+        if config_json.is_null() {
+            ocr::configure(None);
+            return true;
+        }
+        unsafe { str_arg(config_json) }
+            .and_then(|s| json::parse(s).ok())
+            .and_then(|value| ocr::Config::from_json(&value).ok())
+            .map(|config| ocr::configure(Some(config)))
+            .is_some()
+    })
+}
+
+/// Load the ONNX embedding model `embed` runs, or unload it when
+/// `config_json` is null. Config: `{"models_dir": "...", "model":
+/// "minilm", "vocab": "minilm-vocab"}` naming installed models, or
+/// `{"model_path": "...", "vocab_path": "..."}`, with `"max_tokens": 128`.
+/// Returns false on malformed config, a model that fails its checksum or
+/// does not load, or without the `embeddings` feature.
+#[no_mangle]
+pub extern "C" fn synth_embeddings_configure(config_json: *const c_char) -> bool {
+    ffi_guard(|| {
+        if config_json.is_null() {
+            return embeddings::configure(None).is_ok();
+        }
+        unsafe { str_arg(config_json) }
+            .and_then(|s| json::parse(s).ok())
+            .and_then(|value| embeddings::Config::from_json(&value).ok())
+            .is_some_and(|config| embeddings::configure(Some(config)).is_ok())
+    })
+}
+
+/// Embed a JSON array of texts with the configured model. Returns a JSON
+/// array of unit-length vectors, one per text, whose dot products are
+/// cosine similarities; null on error or when no model is configured.
+#[no_mangle]
+pub extern "C" fn embed(texts_json: *const c_char) -> *mut c_char {
+    ffi_guard(|| {
+        let Some(value) = unsafe { str_arg(texts_json) }.and_then(|s| json::parse(s).ok()) else {
+            return std::ptr::null_mut();
+        };
+        let Some(texts) = value
+            .as_array()
+            .and_then(|items| items.iter().map(Value::as_str).collect::<Option<Vec<_>>>())
+        else {
+            return std::ptr::null_mut();
+        };
+        match embeddings::embed(&texts) {
+            Ok(vectors) => into_c_string(
+                Value::Array(
+                    vectors
+                        .iter()
+                        .map(|v| Value::Array(v.iter().map(|&x| Value::from(x as f64)).collect()))
+                        .collect(),
+                )
+                .to_string(),
+            ),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Transcribe a recording (.wav, .mp3, .m4a, ...), one line per segment.
+/// [`extract_text`] does the same for audio files. Returns null on error,
+/// when no model is configured, or without the `transcribe` feature.
+#[no_mangle]
+pub extern "C" fn transcribe(path: *const c_char) -> *mut c_char {
+    ffi_guard(|| {
+        let path = match unsafe { path_arg(path) } {
+            Some(p) => p,
+            None => return std::ptr::null_mut(),
+        };
+        match transcribe::transcribe(&path) {
+            Ok(text) => into_c_string(text),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Split a source file into declaration-level chunks (functions, types,
+/// impl blocks, loose statements), with the language detected from the
+/// extension. Returns JSON:
+/// `{"language": "rust", "chunks": [{"kind": "fn", "name": "main",
+/// "start_line": 1, "end_line": 3, "text": "..."}]}`.
+/// Chunks over `max_lines` lines are split further (0 = no limit).
+/// Returns null on error or an unrecognized language.
+#[no_mangle]
+pub extern "C" fn chunk_code(path: *const c_char, max_lines: u32) -> *mut c_char {
+    ffi_guard(|| {
+        let path = match unsafe { path_arg(path) } {
+            Some(p) => p,
+            None => return std::ptr::null_mut(),
+        };
+        let language = match code::Language::from_path(&path) {
+            Some(l) => l,
+            None => return std::ptr::null_mut(),
+        };
+        match policy::read_file(&path) {
+            Ok(bytes) => {
+                let source = String::from_utf8_lossy(&bytes);
+                let limit = (max_lines > 0).then_some(max_lines as usize);
+                let chunks = code::chunk_code(&source, language, limit);
+                let json = Value::object([
+                    ("language", Value::from(language.name())),
+                    (
+                        "chunks",
+                        Value::Array(chunks.iter().map(code::CodeChunk::to_json).collect()),
+                    ),
+                ]);
+                into_c_string(json.to_string())
+            }
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Edit the .docx at `path` and save it to `out_path` (which may be
+/// `path`). `edits_json` is an array applied in order: `{"op": "append",
+/// "text": "..."}`, `{"op": "insert_at_bookmark", "bookmark": "...",
+/// "text": "..."}`, `{"op": "replace_section", "heading": "...", "text":
+/// "..."}` or, as a Word tracked change, `{"op": "suggest", "find": "...",
+/// "replace": "...", "author": "Synth", "date": "2026-01-31T09:00:00Z"}`,
+/// or `{"op": "highlight", "start": 120, "end": 180, "color": "yellow"}`
+/// to highlight where characters of [`extract_text`]'s output came from.
+/// Text is markdown, as [`draft_in_style`] returns; inside a paragraph it
+/// is inserted as plain text. Returns false on error, e.g. a missing
+/// bookmark, heading or text to replace, in which case nothing is
+/// written.
+#[no_mangle]
+pub extern "C" fn synth_docx_edit(
+    path: *const c_char,
+    edits_json: *const c_char,
+    out_path: *const c_char,
+) -> bool {
+    ffi_guard(|| {
+        let (Some(path), Some(edits), Some(out)) =
+            (unsafe { (path_arg(path), str_arg(edits_json), path_arg(out_path)) })
+        else {
+            return false;
+        };
+        let edits = match json::parse(edits) {
+            Ok(Value::Array(items)) => items
+                .iter()
+                .map(docx::Edit::from_json)
+                .collect::<Result<Vec<_>, _>>(),
+            _ => return false,
+        };
+        edits.is_ok_and(|edits| docx::edit(&path, &edits, &out).is_ok())
+    })
+}
+
+/// The text of the .docx at `path`, text boxes and SmartArt included after
+/// the paragraph they are anchored in, what Word does not show of it and
+/// its form, as JSON: `{"text": "...", "hidden": [{"kind": "vanish" |
+/// "white_text" | "collapsed", "paragraph": 3, "text": "..."}], "form":
+/// {"client_name": "Acme Ltd", "consent": true, "notes": null},
+/// "bookmarks": [{"name": "fees", "paragraph": 2, "text": "..."}],
+/// "references": [{"kind": "ref" | "page_ref", "bookmark": "_Ref100",
+/// "paragraph": 4, "text": "...", "target": "..." | null}], "fields":
+/// [{"code": "DATE", "instruction": "DATE \\@ \"d MMMM yyyy\"",
+/// "paragraph": 5, "result": "...", "value": "..." | null}]}`. The form
+/// maps each content control (by tag, else title) and legacy form field
+/// (by name) to its text, its checked state, or null when not filled in.
+/// A reference's `text` is what it last showed and `target` the text of
+/// its bookmark, null when the bookmark is missing. `options_json` (may be
+/// null) is `{"hidden": false, "collapsed": true, "fields": "cached",
+/// "today": "2026-10-14", "math": "latex"}`: whether to keep hidden and
+/// white-on-white text, and what is folded under collapsed headings, in
+/// `text`; whether fields show the result Word saved or, with
+/// `"evaluate"`, the value of DATE (as of `today`, by default the current
+/// UTC date), SEQ, AUTONUM and FILENAME fields; and whether equations are
+/// shown as LaTeX (`$\frac{a}{b}$`), in a `"linear"` form (`a/b`) or
+/// `"omit"`ted. Hidden text is reported either way. Null on error,
+/// including malformed options.
+#[no_mangle]
+pub extern "C" fn extract_docx(path: *const c_char, options_json: *const c_char) -> *mut c_char {
+    ffi_guard(|| {
+        let Some(path) = (unsafe { path_arg(path) }) else {
+            return std::ptr::null_mut();
+        };
+        let options = match unsafe { str_arg(options_json) }.map(json::parse) {
+            None => Ok(docx_text::Options::default()),
+            Some(Ok(value)) => docx_text::Options::from_json(&value),
+            Some(Err(err)) => Err(err),
+        };
+        match options.and_then(|options| docx_text::extract(&path, &options)) {
+            Ok(extraction) => into_c_string(extraction.to_json().to_string()),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Where characters `start..end` of [`extract_text`]'s output for the .docx
+/// at `path` came from, as JSON: `[{"paragraph": 3, "run": 1, "start": 0,
+/// "end": 12, "text": "..."}]`, one entry per run, with paragraphs counted
+/// in reading order and runs within their paragraph, both from 0, and
+/// `start`/`end` characters of the run's text. Null on error.
+#[no_mangle]
+pub extern "C" fn docx_locate(path: *const c_char, start: usize, end: usize) -> *mut c_char {
+    ffi_guard(|| {
+        let Some(path) = (unsafe { path_arg(path) }) else {
+            return std::ptr::null_mut();
+        };
+        match docx_text::locate(&path, start..end) {
+            Ok(locations) => into_c_string(docx_text::locations_to_json(&locations).to_string()),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// The text of the .pdf at `path`, its form and its annotations, as JSON:
+/// `{"text": "...", "pages": 3, "form": {"applicant.name": "Jane Doe",
+/// "consent": true, "contact": "Email", "phone": null}, "annotations":
+/// [{"kind": "highlight", "page": 1, "author": "Dana", "quote": "...",
+/// "comment": "..."}]}`, pages separated by a form feed. The form maps
+/// each AcroForm field, by its full name, to its text, its checked state,
+/// the chosen radio button, or null when not filled in. Annotations are
+/// highlights, underlines, strike-outs and squiggles with the text they
+/// mark, sticky notes and text boxes; the author may be null.
+/// `options_json` (may be null) is
+/// `{"layout_aware": true}`: read multi-column pages column by column,
+/// leave out running headers, footers and page numbers, and join lines
+/// into paragraphs and hyphenated words; with false, lines come in the
+/// order they are drawn. Null on error, including malformed options and
+/// encrypted files.
+#[no_mangle]
+pub extern "C" fn extract_pdf(path: *const c_char, options_json: *const c_char) -> *mut c_char {
+    ffi_guard(|| {
+        let Some(path) = (unsafe { path_arg(path) }) else {
+            return std::ptr::null_mut();
+        };
+        let options = match unsafe { str_arg(options_json) }.map(json::parse) {
+            None => Ok(pdf_text::Options::default()),
+            Some(Ok(value)) => pdf_text::Options::from_json(&value),
+            Some(Err(err)) => Err(err),
+        };
+        match options.and_then(|options| pdf_text::extract(&path, &options)) {
+            Ok(extraction) => into_c_string(extraction.to_json().to_string()),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Add highlights and notes to the .pdf at `path` and save it to
+/// `out_path` (which may be `path`), appended as an incremental update.
+/// `marks_json` is an array: `{"kind": "highlight", "quote": "...",
+/// "page": 2}` over the first place the quote is drawn (page optional),
+/// or `{"kind": "note", "page": 1, "rect": [72, 700, 92, 720]}` at a
+/// rectangle in the page's user space, either with `"comment"`,
+/// `"author"` (default "Synth") and `"color"` ("yellow", "green", "cyan",
+/// "magenta", "blue" or "red"). Returns false on error, e.g. a quote not
+/// found, a damaged or encrypted file, in which case nothing is written.
+#[no_mangle]
+pub extern "C" fn synth_pdf_annotate(
+    path: *const c_char,
+    marks_json: *const c_char,
+    out_path: *const c_char,
+) -> bool {
+    ffi_guard(|| {
+        let (Some(path), Some(marks), Some(out)) =
+            (unsafe { (path_arg(path), str_arg(marks_json), path_arg(out_path)) })
+        else {
+            return false;
+        };
+        let marks = match json::parse(marks) {
+            Ok(Value::Array(items)) => items
+                .iter()
+                .map(pdf_markup::Mark::from_json)
+                .collect::<Result<Vec<_>, _>>(),
+            _ => return false,
+        };
+        marks.is_ok_and(|marks| pdf_markup::annotate(&path, &marks, &out).is_ok())
+    })
+}
+
+/// Fill a .txt/.md template's `{{field}}` placeholders once per record and
+/// write each result to `out_dir` as `<stem>-<n>.<ext>`. `records_json` is
+/// a JSON array of objects. `progress` (may be null) is called after every
+/// file with `user_data` passed through. Returns JSON:
+/// `{"written": ["..."], "missing": [{"index": 0, "fields": ["name"]}]}`,
+/// or null on error.
+#[no_mangle]
+pub extern "C" fn generate_documents(
+    template_path: *const c_char,
+    records_json: *const c_char,
+    out_dir: *const c_char,
+    progress: Option<ProgressCallback>,
+    user_data: *mut c_void,
+) -> *mut c_char {
+    ffi_guard(|| {
+        let (template_path, records_json, out_dir) = match unsafe {
+            (
+                path_arg(template_path),
+                str_arg(records_json),
+                path_arg(out_dir),
+            )
+        } {
+            (Some(t), Some(r), Some(o)) => (t, r, o),
+            _ => return std::ptr::null_mut(),
+        };
+        let records = match json::parse(records_json) {
+            Ok(Value::Array(items)) => items,
+            _ => return std::ptr::null_mut(),
+        };
+        let result =
+            template::generate_documents(&template_path, &records, &out_dir, |done, total| {
+                if let Some(callback) = progress {
+                    callback(done as u32, total as u32, user_data);
+                }
+            });
+        match result {
+            Ok(generated) => into_c_string(generated.to_json().to_string()),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Export text (e.g. a summary or report) as a Letter-size PDF at
+/// `out_path`. Markdown `#` headings are set in bold. Returns false on error.
+#[no_mangle]
+pub extern "C" fn write_pdf(content: *const c_char, out_path: *const c_char) -> bool {
+    ffi_guard(|| match unsafe { (str_arg(content), path_arg(out_path)) } {
+        (Some(content), Some(out_path)) => pdf::write_pdf(content, &out_path).is_ok(),
+        _ => false,
+    })
+}
+
+/// Turn a meeting transcript (.txt/.md, `Name: text` lines) into minutes
+/// via the chat backend: attendees, summary, decisions and action items.
+/// `options_json` (may be null) is `{"format": "json" | "markdown",
+/// "max_chunk_chars": 12000, "backend": "profile"}`. `progress` (may be null) reports the
+/// chunking and generating stages. Returns JSON or Markdown, or null on
+/// error. Audio input is not supported.
+#[no_mangle]
+pub extern "C" fn generate_minutes(
+    transcript_path: *const c_char,
+    options_json: *const c_char,
+    progress: Option<StageCallback>,
+    user_data: *mut c_void,
+) -> *mut c_char {
+    ffi_guard(|| {
+        let path = match unsafe { path_arg(transcript_path) } {
+            Some(p) => p,
+            None => return std::ptr::null_mut(),
+        };
+        let options = match unsafe { str_arg(options_json) }.map(json::parse) {
+            None => Ok(minutes::MinutesOptions::default()),
+            Some(Ok(value)) => minutes::MinutesOptions::from_json(&value),
+            Some(Err(err)) => Err(err),
+        };
+        let result = options.and_then(|options| {
+            let transcript = extract::extract_text(&path)?;
+            let minutes = minutes::generate_minutes(
+                &transcript,
+                &options,
+                |prompt| chat::chat(prompt, &options.chat),
+                |report| progress::notify(progress, user_data, report),
+            )?;
+            Ok(match options.format {
+                minutes::MinutesFormat::Json => minutes.to_json().to_string(),
+                minutes::MinutesFormat::Markdown => minutes.to_markdown(),
+            })
+        });
+        match result {
+            Ok(text) => into_c_string(text),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Summarize a document of any length via the chat backend, in parts when
+/// it is longer than `max_chunk_chars`. `options_json` (may be null) is
+/// `{"max_chunk_chars": 12000, "instructions": "three bullet points",
+/// "backend": "profile"}`. `progress` (may be null) reports the
+/// extracting, chunking and generating stages. Returns the summary, or
+/// null on error.
+#[no_mangle]
+pub extern "C" fn summarize_document(
+    path: *const c_char,
+    options_json: *const c_char,
+    progress: Option<StageCallback>,
+    user_data: *mut c_void,
+) -> *mut c_char {
+    ffi_guard(|| {
+        let Some(path) = (unsafe { path_arg(path) }) else {
+            return std::ptr::null_mut();
+        };
+        let options = match unsafe { str_arg(options_json) }.map(json::parse) {
+            None => summarize::SummarizeOptions::default(),
+            Some(Ok(value)) => summarize::SummarizeOptions::from_json(&value),
+            Some(Err(_)) => return std::ptr::null_mut(),
+        };
+        let result = summarize::summarize_document(
+            &path,
+            &options,
+            |prompt| chat::chat(prompt, &options.chat),
+            |report| progress::notify(progress, user_data, report),
+        );
+        match result {
+            Ok(text) => into_c_string(text),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Translate a document of any length via the chat backend, part by part.
+/// `options_json` is `{"target": "German", "source": "English",
+/// "max_chunk_chars": 6000, "glossary": "contracts-de", "backend":
+/// "profile"}`; only `target` is required. With a `glossary` given to
+/// [`synth_glossary_define`], each part's prompt gets the entries for the
+/// terms in it and a translation that breaks them is re-prompted once,
+/// then the call fails. `progress` (may be null) reports the extracting,
+/// chunking and generating stages. Returns the translation, or null on
+/// error.
+#[no_mangle]
+pub extern "C" fn translate_document(
+    path: *const c_char,
+    options_json: *const c_char,
+    progress: Option<StageCallback>,
+    user_data: *mut c_void,
+) -> *mut c_char {
+    ffi_guard(|| {
+        let Some(path) = (unsafe { path_arg(path) }) else {
+            return std::ptr::null_mut();
+        };
+        let Some(options) = unsafe { str_arg(options_json) }
+            .and_then(|s| json::parse(s).ok())
+            .and_then(|value| translate::TranslateOptions::from_json(&value).ok())
+        else {
+            return std::ptr::null_mut();
+        };
+        let result = translate::translate_document(
+            &path,
+            &options,
+            |prompt| chat::chat(prompt, &options.chat),
+            |report| progress::notify(progress, user_data, report),
+        );
+        match result {
+            Ok(text) => into_c_string(text),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Index a document for [`synth_ask`] now, as [`synth_prefetch`] does in
+/// the background: extract it, chunk it and embed each chunk with the
+/// configured embedding model. `progress` (may be null) reports the
+/// extracting, chunking and embedding stages. Returns false on error.
+#[no_mangle]
+pub extern "C" fn index_document(
+    path: *const c_char,
+    progress: Option<StageCallback>,
+    user_data: *mut c_void,
+) -> bool {
+    ffi_guard(|| {
+        unsafe { path_arg(path) }.is_some_and(|path| {
+            ask::index_document(&path, |report| {
+                progress::notify(progress, user_data, report)
+            })
+            .is_ok()
+        })
+    })
+}
+
+/// Concatenate the .docx files in `paths_json` (a JSON array of paths)
+/// into `out_path`, each starting a new section with its own page layout.
+/// The first document's styles, headers and footers are kept; the others
+/// bring the styles it lacks, their images and hyperlinks. Returns false
+/// on error, including documents whose body uses charts or embedded
+/// objects.
+#[no_mangle]
+pub extern "C" fn merge_documents(paths_json: *const c_char, out_path: *const c_char) -> bool {
+    ffi_guard(|| {
+        let (Some(paths), Some(out)) = (unsafe { (str_arg(paths_json), path_arg(out_path)) })
+        else {
+            return false;
+        };
+        path_list(paths).is_some_and(|paths| docx::merge(&paths, &out).is_ok())
+    })
+}
+
+/// Draft new text following `instruction` (e.g. "a limitation of
+/// liability clause capped at fees paid") in the tone and formatting of
+/// the document at `path`. `options_json` (may be null) takes the same
+/// keys as [`synth_chat`]. Returns JSON: `{"text": "...", "paragraphs":
+/// [{"style": "Heading2" | "ListBullet" | "Normal", "text": "...", "runs":
+/// [{"text": "...", "bold": true, "italic": false}]}], "wordml":
+/// "<w:p>...</w:p>"}`, where `wordml` is ready to insert into the body of a
+/// .docx. Null on error.
+#[no_mangle]
+pub extern "C" fn draft_in_style(
+    path: *const c_char,
+    instruction: *const c_char,
+    options_json: *const c_char,
+) -> *mut c_char {
+    ffi_guard(|| {
+        let (Some(path), Some(instruction)) = (unsafe { (path_arg(path), str_arg(instruction)) })
+        else {
+            return std::ptr::null_mut();
+        };
+        let options = match unsafe { str_arg(options_json) }.map(json::parse) {
+            None => chat::ChatOptions::default(),
+            Some(Ok(value)) => chat::ChatOptions::from_json(&value),
+            Some(Err(_)) => return std::ptr::null_mut(),
+        };
+        match draft::draft_in_style(&path, instruction, |prompt| chat::chat(prompt, &options)) {
+            Ok(draft) => into_c_string(draft.to_json().to_string()),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Compare several documents through the chat backend. `paths_json` is a
+/// JSON array of file paths; `criteria` is a JSON array of column names or
+/// a free-text description (e.g. "key commercial terms"). Returns JSON:
+/// `{"columns": [...], "rows": [{"document": "...", "values": [...]}],
+/// "markdown": "| Document | ... |"}`, or null on error. `progress` (may
+/// be null) reports each document extracted and the generating stage.
+#[no_mangle]
+pub extern "C" fn compare_corpus(
+    paths_json: *const c_char,
+    criteria: *const c_char,
+    progress: Option<StageCallback>,
+    user_data: *mut c_void,
+) -> *mut c_char {
+    ffi_guard(|| {
+        let (paths_json, criteria) = match unsafe { (str_arg(paths_json), str_arg(criteria)) } {
+            (Some(p), Some(c)) => (p, c),
+            _ => return std::ptr::null_mut(),
+        };
+        let paths = match path_list(paths_json) {
+            Some(paths) => paths,
+            None => return std::ptr::null_mut(),
+        };
+        let result = compare::compare_corpus(&paths, criteria, chat::kiro_chat, |report| {
+            progress::notify(progress, user_data, report)
+        });
+        match result {
+            Ok(comparison) => into_c_string(comparison.to_json().to_string()),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Classify a document against caller-defined labels. `labels_json` is an
+/// array of names or `{"label": "...", "description": "..."}` objects.
+/// `method` is "backend" (ask the chat backend) or "lexical" (local
+/// term-overlap similarity, no backend call). Returns JSON, best first:
+/// `[{"label": "invoice", "confidence": 0.91}]`, or null on error.
+#[no_mangle]
+pub extern "C" fn classify_document(
+    path: *const c_char,
+    labels_json: *const c_char,
+    method: *const c_char,
+) -> *mut c_char {
+    ffi_guard(|| {
+        let (path, labels_json, method) = match (
+            unsafe { path_arg(path) },
+            unsafe { str_arg(labels_json) },
+            unsafe { str_arg(method) }.and_then(classify::Method::from_name),
+        ) {
+            (Some(p), Some(l), Some(m)) => (p, l, m),
+            _ => return std::ptr::null_mut(),
+        };
+        let result = json::parse(labels_json)
+            .and_then(|value| classify::Label::list_from_json(&value))
+            .and_then(|labels| {
+                let text = extract::extract_text(&path)?;
+                match method {
+                    classify::Method::Lexical => Ok(classify::classify_lexical(&text, &labels)),
+                    classify::Method::Backend => {
+                        classify::classify_with_backend(&text, &labels, chat::kiro_chat)
+                    }
+                }
+            });
+        match result {
+            Ok(ranking) => into_c_string(classify::ranking_to_json(&ranking).to_string()),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Find exact and near-duplicate documents. `paths_json` is a JSON array of
+/// file paths; `threshold` is the minimum estimated similarity (0.0-1.0,
+/// e.g. 0.8) for two documents to share a cluster. Returns JSON:
+/// `{"clusters": [{"documents": [{"path": "...", "similarity": 0.93}],
+/// "exact": false}], "skipped": ["..."]}`, or null on invalid input.
+#[no_mangle]
+pub extern "C" fn find_duplicates(paths_json: *const c_char, threshold: f64) -> *mut c_char {
+    ffi_guard(
+        || match unsafe { str_arg(paths_json) }.and_then(path_list) {
+            Some(paths) => into_c_string(
+                dedup::find_duplicates(&paths, threshold)
+                    .to_json()
+                    .to_string(),
+            ),
+            None => std::ptr::null_mut(),
+        },
+    )
+}
+
+/// Suggest keyphrases for the document at `path`, computed locally with
+/// RAKE. `top_n` caps the result (0 = all). Returns JSON, best first:
+/// `[{"keyword": "release schedule", "score": 8.5}]`, or null on error.
+#[no_mangle]
+pub extern "C" fn extract_keywords(path: *const c_char, top_n: u32) -> *mut c_char {
+    ffi_guard(|| {
+        let Some(path) = (unsafe { path_arg(path) }) else {
+            return std::ptr::null_mut();
+        };
+        match extract::extract_text(&path) {
+            Ok(text) => into_c_string(
+                keywords::keywords_to_json(&keywords::extract_keywords(&text, top_n as usize))
+                    .to_string(),
+            ),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Every occurrence of `term` (a word or phrase, matched whole-word and
+/// case-insensitively) in the document at `path`, with `window` words of
+/// context each side. Returns JSON in document order: `[{"start": 120,
+/// "end": 131, "line": 4, "column": 9, "before": "...", "match":
+/// "Termination", "after": "..."}]` (character offsets), or null on
+/// error.
+#[no_mangle]
+pub extern "C" fn concordance(
+    path: *const c_char,
+    term: *const c_char,
+    window: u32,
+) -> *mut c_char {
+    ffi_guard(|| {
+        let (Some(path), Some(term)) = (unsafe { (path_arg(path), str_arg(term)) }) else {
+            return std::ptr::null_mut();
+        };
+        match extract::extract_text(&path) {
+            Ok(text) => into_c_string(
+                concordance::occurrences_to_json(&concordance::concordance(
+                    &text,
+                    term,
+                    window as usize,
+                ))
+                .to_string(),
+            ),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Word frequency table for the document at `path`, most frequent first.
+/// With `content_only`, stopwords and numbers are left out; `top_n` caps
+/// the list (0 = all). Returns JSON: `{"total_words": 1200, "distinct":
+/// 410, "words": [{"word": "party", "count": 40, "per_thousand": 33.3}]}`,
+/// or null on error.
+#[no_mangle]
+pub extern "C" fn word_frequencies(
+    path: *const c_char,
+    content_only: bool,
+    top_n: u32,
+) -> *mut c_char {
+    ffi_guard(|| {
+        let Some(path) = (unsafe { path_arg(path) }) else {
+            return std::ptr::null_mut();
+        };
+        match extract::extract_text(&path) {
+            Ok(text) => {
+                let frequencies =
+                    concordance::word_frequencies(&text, content_only, top_n as usize);
+                into_c_string(concordance::frequencies_to_json(&text, &frequencies).to_string())
+            }
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Word, sentence and paragraph counts, reading time, average sentence
+/// length and Flesch reading ease for the document at `path`. Returns
+/// JSON: `{"characters": 0, "words": 0, "sentences": 0, "paragraphs": 0,
+/// "reading_minutes": 0.0, "avg_sentence_words": 0.0,
+/// "flesch_reading_ease": 0.0}`, or null on error.
+#[no_mangle]
+pub extern "C" fn document_stats(path: *const c_char) -> *mut c_char {
+    ffi_guard(|| {
+        let Some(path) = (unsafe { path_arg(path) }) else {
+            return std::ptr::null_mut();
+        };
+        match extract::extract_text(&path) {
+            Ok(text) => into_c_string(stats::document_stats(&text).to_json().to_string()),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Clean up `text` for one consumer: `profile` is "display" (faithful),
+/// "llm" (normalized for prompts), "search" (lowercased words, accents
+/// folded) or a name given to [`synth_define_normalization`]. Returns null
+/// on an unknown profile.
+#[no_mangle]
+pub extern "C" fn normalize_text(text: *const c_char, profile: *const c_char) -> *mut c_char {
+    ffi_guard(|| match unsafe { (str_arg(text), str_arg(profile)) } {
+        (Some(text), Some(name)) => match normalize::profile(name) {
+            Ok(profile) => into_c_string(profile.apply(text)),
+            Err(_) => std::ptr::null_mut(),
+        },
+        _ => std::ptr::null_mut(),
+    })
+}
+
+/// Extract a file once and normalize it for several consumers.
+/// `profiles_json` is an array of profile names, e.g. `["display", "llm",
+/// "search"]`. Returns `{"display": "...", "llm": "...", "search": "..."}`,
+/// or null on error.
+#[no_mangle]
+pub extern "C" fn extract_text_normalized(
+    path: *const c_char,
+    profiles_json: *const c_char,
+) -> *mut c_char {
+    ffi_guard(|| {
+        let (Some(path), Some(profiles)) = (unsafe { (path_arg(path), str_arg(profiles_json)) })
+        else {
+            return std::ptr::null_mut();
+        };
+        let Ok(Value::Array(names)) = json::parse(profiles) else {
+            return std::ptr::null_mut();
+        };
+        let names: Vec<&str> = names.iter().filter_map(Value::as_str).collect();
+        let result =
+            extract::extract_text(&path).and_then(|text| normalize::normalize_all(&text, &names));
+        match result {
+            Ok(texts) => into_c_string(
+                Value::Object(
+                    texts
+                        .into_iter()
+                        .map(|(name, text)| (name, Value::from(text)))
+                        .collect(),
+                )
+                .to_string(),
+            ),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Add a normalization profile: `{"name": "titles", "base": "llm",
+/// "lowercase": true}` takes the steps of `base` and switches the named
+/// ones on or off (see `synth_core::normalize::Profile`). Returns false on
+/// invalid JSON, an unknown step or a built-in name.
+#[no_mangle]
+pub extern "C" fn synth_define_normalization(profile_json: *const c_char) -> bool {
+    ffi_guard(|| {
+        unsafe { str_arg(profile_json) }
+            .and_then(|s| json::parse(s).ok())
+            .and_then(|value| normalize::Profile::from_json(&value).ok())
+            .is_some_and(|profile| normalize::define(profile).is_ok())
+    })
+}
+
+/// Define or replace a named glossary for `"glossary"` in chat options:
+/// `[{"term": "purchase order", "use": "Bestellung", "definition": "...",
+/// "avoid": ["Kaufauftrag"]}]`. Returns false on invalid JSON or an entry
+/// without a term.
+#[no_mangle]
+pub extern "C" fn synth_glossary_define(name: *const c_char, glossary_json: *const c_char) -> bool {
+    ffi_guard(|| {
+        let Some(name) = (unsafe { str_arg(name) }) else {
+            return false;
+        };
+        match unsafe { str_arg(glossary_json) }
+            .and_then(|s| json::parse(s).ok())
+            .and_then(|value| glossary::Glossary::from_json(&value).ok())
+        {
+            Some(entries) => {
+                glossary::define(name, entries);
+                true
+            }
+            None => false,
+        }
+    })
+}
+
+/// Forget a glossary. Returns whether one was defined under `name`.
+#[no_mangle]
+pub extern "C" fn synth_glossary_remove(name: *const c_char) -> bool {
+    ffi_guard(|| unsafe { str_arg(name) }.is_some_and(glossary::remove))
+}
+
+/// Check text a host produced from `source` against a defined glossary.
+/// Returns a JSON array of violations (empty when it complies), or null
+/// for an unknown glossary.
+#[no_mangle]
+pub extern "C" fn synth_glossary_check(
+    name: *const c_char,
+    source: *const c_char,
+    output: *const c_char,
+) -> *mut c_char {
+    ffi_guard(|| {
+        let (Some(name), Some(source), Some(output)) =
+            (unsafe { (str_arg(name), str_arg(source), str_arg(output)) })
+        else {
+            return std::ptr::null_mut();
+        };
+        match glossary::glossary(name) {
+            Ok(entries) => {
+                let violations = glossary::check(&entries, source, output);
+                into_c_string(
+                    Value::Array(violations.into_iter().map(Value::from).collect()).to_string(),
+                )
+            }
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Split `text` into sentences or paragraphs (`mode` is "sentence" or
+/// "paragraph"), using the same boundaries as the internal chunker.
+/// Offsets are in Unicode scalar values, and in UTF-16 code units for
+/// `NSString` ranges; surrounding whitespace is not part of a segment.
+/// Returns JSON: `[{"start": 0, "end": 12, "start_utf16": 0, "end_utf16":
+/// 12, "text": "..."}]`, or null on invalid input.
+#[no_mangle]
+pub extern "C" fn segment_text(text: *const c_char, mode: *const c_char) -> *mut c_char {
+    ffi_guard(|| {
+        let (Some(text), Some(mode)) = (
+            unsafe { str_arg(text) },
+            unsafe { str_arg(mode) }.and_then(segment::Mode::from_name),
+        ) else {
+            return std::ptr::null_mut();
+        };
+        into_c_string(segment::segments_to_json(text, &segment::segment(text, mode)).to_string())
+    })
+}
+
+/// Re-locate annotations made against `old_text` in the current version of
+/// the document at `new_path`. `annotations_json` is an array of
+/// `{"id": ..., "quote": "...", "start": 10, "end": 24}` with char offsets.
+/// Returns JSON in the same order: `[{"id": ..., "status": "unchanged" |
+/// "moved" | "fuzzy" | "orphaned", "score": 0.92, "start": 12, "end": 26,
+/// "quote": "..."}]` (no range when orphaned), or null on error.
+#[no_mangle]
+pub extern "C" fn reanchor_annotations(
+    old_text: *const c_char,
+    new_path: *const c_char,
+    annotations_json: *const c_char,
+) -> *mut c_char {
+    ffi_guard(|| {
+        let (Some(old_text), Some(new_path), Some(annotations)) = (
+            unsafe { str_arg(old_text) },
+            unsafe { path_arg(new_path) },
+            unsafe { str_arg(annotations_json) }
+                .and_then(|s| json::parse(s).ok())
+                .and_then(|v| reanchor::Annotation::list_from_json(&v).ok()),
+        ) else {
+            return std::ptr::null_mut();
+        };
+        match extract::extract_text(&new_path) {
+            Ok(new_text) => {
+                let anchors = reanchor::reanchor(old_text, &new_text, &annotations);
+                into_c_string(reanchor::anchors_to_json(&anchors, &new_text).to_string())
+            }
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Scan text for likely prompt-injection content: instruction-like phrases
+/// ("ignore previous instructions"), zero-width and bidi-control
+/// characters, and markup that hides text. When `neutralize` is true the
+/// result also carries a cleaned copy safe to paste into a prompt.
+/// Returns JSON: `{"findings": [{"kind": "instruction", "start": 0,
+/// "end": 28, "excerpt": "..."}], "text": "..."}`, or null on invalid input.
+#[no_mangle]
+pub extern "C" fn scan_injection(text: *const c_char, neutralize: bool) -> *mut c_char {
+    ffi_guard(|| {
+        let Some(text) = (unsafe { str_arg(text) }) else {
+            return std::ptr::null_mut();
+        };
+        let findings = injection::scan(text);
+        let mut pairs = vec![("findings", injection::findings_to_json(text, &findings))];
+        if neutralize {
+            pairs.push(("text", Value::from(injection::neutralize(text, &findings))));
+        }
+        into_c_string(Value::object(pairs).to_string())
+    })
+}
+
+/// Extract only part of a document, so large files need not cross the FFI
+/// whole. `options_json` is one of `{"section": "7"}` (a heading's section
+/// with its subsections, matched by text or numbering prefix),
+/// `{"from_heading": "A", "to_heading": "B"}` (`to_heading` optional),
+/// `{"lines": [10, 40]}` or `{"pages": [3, 5]}` (PDF pages, from 1).
+/// Returns the selected text, or null on error, including when no heading
+/// matches or the range is past the last page.
+#[no_mangle]
+pub extern "C" fn extract_selection(
+    path: *const c_char,
+    options_json: *const c_char,
+) -> *mut c_char {
+    ffi_guard(|| {
+        let (Some(path), Some(selection)) = (
+            unsafe { path_arg(path) },
+            unsafe { str_arg(options_json) }
+                .and_then(|s| json::parse(s).ok())
+                .and_then(|v| select::Selection::from_json(&v).ok()),
+        ) else {
+            return std::ptr::null_mut();
+        };
+        match extract::extract_text(&path).and_then(|text| select::select(&text, &selection)) {
+            Ok(text) => into_c_string(text),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Open a chat session and return its handle, or 0 on invalid options.
+/// `options_json` (may be null) takes the same keys as [`synth_chat`];
+/// sessions recall long-term memories unless `"memory": false`.
+#[no_mangle]
+pub extern "C" fn synth_session_new(options_json: *const c_char) -> u64 {
+    ffi_guard(|| {
+        let value = match unsafe { str_arg(options_json) }.map(json::parse) {
+            None => Value::Null,
+            Some(Ok(value)) => value,
+            Some(Err(_)) => return 0,
+        };
+        let mut options = chat::ChatOptions::from_json(&value);
+        options.memory = value.get("memory").and_then(Value::as_bool) != Some(false);
+        session::create(options)
+    })
+}
+
+/// Send a user message in a session and return the reply, or null on error
+/// (the message is then not added to the history).
+#[no_mangle]
+pub extern "C" fn synth_session_send(handle: u64, message: *const c_char) -> *mut c_char {
+    ffi_guard(|| {
+        let Some(message) = (unsafe { str_arg(message) }) else {
+            return std::ptr::null_mut();
+        };
+        match session::send(handle, message) {
+            Ok(reply) => into_c_string(reply),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Replace the session's last reply with a new one ("try again") and
+/// return it, or null on error or if the session has no reply yet.
+#[no_mangle]
+pub extern "C" fn synth_session_regenerate(handle: u64) -> *mut c_char {
+    ffi_guard(|| match session::regenerate(handle) {
+        Ok(reply) => into_c_string(reply),
+        Err(_) => std::ptr::null_mut(),
+    })
+}
+
+/// Open a new session holding the first `turns` turns of `handle` (an
+/// index from [`synth_session_history`]) and return its handle, or 0 on
+/// error. To edit an earlier message, branch at that message and send the
+/// edited text.
+#[no_mangle]
+pub extern "C" fn synth_session_branch(handle: u64, turns: u32) -> u64 {
+    ffi_guard(|| session::branch(handle, turns as usize).unwrap_or(0))
+}
+
+/// The session's turns as JSON: `[{"role": "user" | "assistant", "text":
+/// "..."}]`, or null if the handle is unknown.
+#[no_mangle]
+pub extern "C" fn synth_session_history(handle: u64) -> *mut c_char {
+    ffi_guard(|| match session::history(handle) {
+        Some(turns) => into_c_string(turns.to_string()),
+        None => std::ptr::null_mut(),
+    })
+}
+
+/// Open sessions as JSON: `[{"handle": 1, "tenant_id": "...", "user_id":
+/// "...", "turns": 4}]`. `filter_json` (may be null) is `{"tenant_id":
+/// "...", "user_id": "..."}`; only sessions whose options carry the given
+/// tags are listed. Returns null if the filter is not JSON.
+#[no_mangle]
+pub extern "C" fn synth_session_list(filter_json: *const c_char) -> *mut c_char {
+    ffi_guard(|| match unsafe { tags_arg(filter_json) } {
+        Some(filter) => into_c_string(session::list(&filter).to_string()),
+        None => std::ptr::null_mut(),
+    })
+}
+
+/// `{"tenant_id": ..., "user_id": ...}` JSON, or no tags for null.
+///
+/// # Safety
+///
+/// As for [`str_arg`].
+unsafe fn tags_arg(filter_json: *const c_char) -> Option<usage::Tags> {
+    match unsafe { str_arg(filter_json) }.map(json::parse) {
+        None => Some(usage::Tags::default()),
+        Some(Ok(value)) => Some(usage::Tags::from_json(&value)),
+        Some(Err(_)) => None,
+    }
+}
+
+/// Discard a session. Returns false if the handle is unknown.
+#[no_mangle]
+pub extern "C" fn synth_session_close(handle: u64) -> bool {
+    ffi_guard(|| session::close(handle))
+}
+
+/// Create a project at `<root_dir>/<name>` and return its handle, or 0 on
+/// error (including when it already exists).
+#[no_mangle]
+pub extern "C" fn synth_project_create(root_dir: *const c_char, name: *const c_char) -> u64 {
+    ffi_guard(|| {
+        let (Some(root), Some(name)) = (unsafe { (path_arg(root_dir), str_arg(name)) }) else {
+            return 0;
+        };
+        project::create(&root, name).map_or(0, project::insert)
+    })
+}
+
+/// Open the project at `<root_dir>/<name>` and return its handle, or 0 on
+/// error.
+#[no_mangle]
+pub extern "C" fn synth_project_open(root_dir: *const c_char, name: *const c_char) -> u64 {
+    ffi_guard(|| {
+        let (Some(root), Some(name)) = (unsafe { (path_arg(root_dir), str_arg(name)) }) else {
+            return 0;
+        };
+        project::open(&root, name).map_or(0, project::insert)
+    })
+}
+
+/// Projects under `root_dir` as JSON: `[{"name": "...", "dir": "...",
+/// "created": 1700000000, "documents": ["..."], "sessions": ["..."]}]`, or
+/// null on error.
+#[no_mangle]
+pub extern "C" fn synth_project_list(root_dir: *const c_char) -> *mut c_char {
+    ffi_guard(|| {
+        let Some(root) = (unsafe { path_arg(root_dir) }) else {
+            return std::ptr::null_mut();
+        };
+        match project::list(&root) {
+            Ok(projects) => into_c_string(
+                Value::Array(projects.iter().map(project::Project::to_json).collect()).to_string(),
+            ),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Delete the project at `<root_dir>/<name>` with its caches and sessions,
+/// closing any open handles on it. Returns false on error.
+#[no_mangle]
+pub extern "C" fn synth_project_delete(root_dir: *const c_char, name: *const c_char) -> bool {
+    ffi_guard(|| match unsafe { (path_arg(root_dir), str_arg(name)) } {
+        (Some(root), Some(name)) => project::delete(&root, name).is_ok(),
+        _ => false,
+    })
+}
+
+/// Release a project handle. Returns false if the handle is unknown.
+#[no_mangle]
+pub extern "C" fn synth_project_close(handle: u64) -> bool {
+    ffi_guard(|| project::close(handle))
+}
+
+/// The project's details, in the format of [`synth_project_list`], or null
+/// if the handle is unknown.
+#[no_mangle]
+pub extern "C" fn synth_project_info(handle: u64) -> *mut c_char {
+    ffi_guard(|| match project::get(handle) {
+        Some(project) => into_c_string(project.to_json().to_string()),
+        None => std::ptr::null_mut(),
+    })
+}
+
+/// Add a document path to the project. Returns false on error or if it is
+/// already there.
+#[no_mangle]
+pub extern "C" fn synth_project_add_document(handle: u64, path: *const c_char) -> bool {
+    ffi_guard(|| {
+        unsafe { path_arg(path) }.is_some_and(|path| {
+            project::with(handle, |project| project.add_document(&path)).unwrap_or(false)
+        })
+    })
+}
+
+/// Remove a document path and its cached text from the project. Returns
+/// false on error or if it was not there.
+#[no_mangle]
+pub extern "C" fn synth_project_remove_document(handle: u64, path: *const c_char) -> bool {
+    ffi_guard(|| {
+        unsafe { path_arg(path) }.is_some_and(|path| {
+            project::with(handle, |project| project.remove_document(&path)).unwrap_or(false)
+        })
+    })
+}
+
+/// Like `extract_text`, but served from the project's cache while the file
+/// is unchanged. Returns null on error.
+#[no_mangle]
+pub extern "C" fn synth_project_extract(handle: u64, path: *const c_char) -> *mut c_char {
+    ffi_guard(|| {
+        let (Some(project), Some(path)) = (project::get(handle), unsafe { path_arg(path) }) else {
+            return std::ptr::null_mut();
+        };
+        match project.extract(&path) {
+            Ok(text) => into_c_string(text),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Save a chat session in the project under `name`, replacing any session
+/// saved with that name. Returns false on error.
+#[no_mangle]
+pub extern "C" fn synth_project_save_session(
+    handle: u64,
+    name: *const c_char,
+    session_handle: u64,
+) -> bool {
+    ffi_guard(|| {
+        let (Some(project), Some(name), Some(saved)) = (
+            project::get(handle),
+            unsafe { str_arg(name) },
+            session::get(session_handle),
+        ) else {
+            return false;
+        };
+        project.save_session(name, &saved).is_ok()
+    })
+}
+
+/// Open a session saved in the project and return its session handle, or
+/// 0 on error.
+#[no_mangle]
+pub extern "C" fn synth_project_load_session(handle: u64, name: *const c_char) -> u64 {
+    ffi_guard(|| {
+        let (Some(project), Some(name)) = (project::get(handle), unsafe { str_arg(name) }) else {
+            return 0;
+        };
+        project.load_session(name).map_or(0, session::open)
+    })
+}
+
+/// Write the project's manifest (name and document paths) and saved
+/// sessions to a single tar archive at `archive_path`, for backup or moving
+/// to another machine. Document paths are stored relative to the project
+/// directory. Returns false on error.
+#[no_mangle]
+pub extern "C" fn synth_project_export(handle: u64, archive_path: *const c_char) -> bool {
+    ffi_guard(
+        || match (project::get(handle), unsafe { path_arg(archive_path) }) {
+            (Some(project), Some(path)) => project.export(&path).is_ok(),
+            _ => false,
+        },
+    )
+}
+
+/// Unpack an archive from [`synth_project_export`] under `root_dir` and
+/// return a handle on the imported project, or 0 on error (including when
+/// a project with its name already exists). Document paths are resolved
+/// against the new project directory.
+#[no_mangle]
+pub extern "C" fn synth_project_import(
+    archive_path: *const c_char,
+    root_dir: *const c_char,
+) -> u64 {
+    ffi_guard(|| {
+        let (Some(archive), Some(root)) = (unsafe { (path_arg(archive_path), path_arg(root_dir)) })
+        else {
+            return 0;
+        };
+        project::import(&archive, &root).map_or(0, project::insert)
+    })
+}
+
+/// Start audit logging of every chat call to `<dir>/audit.jsonl`, or stop
+/// it when `config_json` is null. Config: `{"dir": "...", "content":
+/// "full" | "hash", "max_bytes": 10485760, "keep": 5}`; with `"hash"` only
+/// SHA-256 hashes of prompts and replies are stored. Returns false on
+/// error.
+#[no_mangle]
+pub extern "C" fn synth_audit_configure(config_json: *const c_char) -> bool {
+    ffi_guard(|| {
+        if config_json.is_null() {
+            return audit::configure(None).is_ok();
+        }
+        unsafe { str_arg(config_json) }
+            .and_then(|s| json::parse(s).ok())
+            .and_then(|value| audit::Config::from_json(&value).ok())
+            .is_some_and(|config| audit::configure(Some(config)).is_ok())
+    })
+}
+
+/// Audit records as a JSON array, oldest first: `[{"time": 1700000000.5,
+/// "backend": "...", "model": "...", "user_id": "...", "tenant_id": "...",
+/// "duration_ms": 0, "ok": true, "prompt": "...", "response": "..."}]`
+/// (failed calls carry `"error"` and `"message"` instead of a response).
+/// `filter_json` (may be null) is `{"since": ..., "until": ..., "backend":
+/// "...", "user_id": "...", "tenant_id": "...", "limit": 100}`. Returns
+/// null on error or when logging is off.
+#[no_mangle]
+pub extern "C" fn synth_audit_query(filter_json: *const c_char) -> *mut c_char {
+    ffi_guard(|| {
+        let filter = match unsafe { str_arg(filter_json) }.map(json::parse) {
+            None => audit::Filter::default(),
+            Some(Ok(value)) => audit::Filter::from_json(&value),
+            Some(Err(_)) => return std::ptr::null_mut(),
+        };
+        match audit::query(&filter) {
+            Ok(records) => into_c_string(Value::Array(records).to_string()),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Chat usage per tenant and user as JSON: `[{"tenant_id": "...",
+/// "user_id": "...", "calls": 12, "failures": 1, "prompt_tokens": 5200,
+/// "reply_tokens": 900, "last_call": 1700000000}]`, counted since the
+/// process started from the tags in each call's options. `filter_json` (may
+/// be null) is `{"tenant_id": "...", "user_id": "..."}`. Returns null if
+/// the filter is not JSON.
+#[no_mangle]
+pub extern "C" fn synth_usage(filter_json: *const c_char) -> *mut c_char {
+    ffi_guard(|| match unsafe { tags_arg(filter_json) } {
+        Some(filter) => into_c_string(usage::report_json(&filter).to_string()),
+        None => std::ptr::null_mut(),
+    })
+}
+
+/// Zero the usage counters `filter_json` matches (all of them for null).
+/// Returns false if the filter is not JSON.
+#[no_mangle]
+pub extern "C" fn synth_usage_reset(filter_json: *const c_char) -> bool {
+    ffi_guard(|| {
+        unsafe { tags_arg(filter_json) }
+            .map(|filter| usage::reset(&filter))
+            .is_some()
+    })
+}
+
+/// Also store the replies to seeded chat calls under `cache_dir`, reusing
+/// those already there; null keeps them in memory only. Returns false on
+/// error.
+#[no_mangle]
+pub extern "C" fn synth_reply_cache_init(cache_dir: *const c_char) -> bool {
+    ffi_guard(|| cache::set_dir(unsafe { path_arg(cache_dir) }.as_deref()).is_ok())
+}
+
+/// Forget the cached replies held in memory; files under the cache
+/// directory are kept.
+#[no_mangle]
+pub extern "C" fn synth_reply_cache_clear() {
+    ffi_guard(|| {
+        cache::clear();
+    })
+}
+
+/// Keep sessions, metadata, caches, memories, the registry and job state
+/// in one SQLite database (compiled in with the `sqlite` feature, through
+/// the `sqlite3` command otherwise) instead of JSON files, migrating its
+/// schema first; null goes back to files. Config: `{"synth_db_path":
+/// "/path/synth.db", "busy_timeout_secs": 10}`. Records written before keep being read from
+/// their files until written again. Returns false on malformed config, a
+/// missing `sqlite3`, or a database from a newer version.
+#[no_mangle]
+pub extern "C" fn synth_db_configure(config_json: *const c_char) -> bool {
+    ffi_guard(|| {
+        if config_json.is_null() {
+            return store::configure(None).is_ok();
+        }
+        unsafe { str_arg(config_json) }
+            .and_then(|s| json::parse(s).ok())
+            .and_then(|value| store::Config::from_json(&value).ok())
+            .is_some_and(|config| store::configure(Some(config)).is_ok())
+    })
+}
+
+/// Encrypt everything persisted from now on with AES-256-GCM, or write
+/// it plain again when `config_json` is null. Config: `{"key": "<64 hex
+/// digits>"}` for a key the host keeps, or `{"passphrase": "...", "salt":
+/// "...", "iterations": 600000}` to derive one with PBKDF2-HMAC-SHA256.
+/// Each record is bound to its name, and plain records are refused once a
+/// key is set: seal those written before with
+/// [`synth_encryption_seal_existing`]. Returns false on malformed config,
+/// or when built without the `encryption` feature.
+#[no_mangle]
+pub extern "C" fn synth_encryption_configure(config_json: *const c_char) -> bool {
+    ffi_guard(|| {
+        if config_json.is_null() {
+            return encryption::configure(None).is_ok();
+        }
+        unsafe { str_arg(config_json) }
+            .and_then(|s| json::parse(s).ok())
+            .and_then(|value| encryption::Config::from_json(&value).ok())
+            .is_some_and(|config| encryption::configure(Some(config)).is_ok())
+    })
+}
+
+/// Seal, once, what was written plain before the key was configured: the
+/// records in the database and in the directories of `dirs_json` (a JSON
+/// array of paths holding only records, such as the cache, jobs, memories
+/// and projects directories), and the lines of the configured audit log.
+/// Returns false if no key is configured or a record could not be sealed.
+#[no_mangle]
+pub extern "C" fn synth_encryption_seal_existing(dirs_json: *const c_char) -> bool {
+    ffi_guard(|| {
+        let Some(dirs) = unsafe { str_arg(dirs_json) }.and_then(path_list) else {
+            return false;
+        };
+        let dirs: Vec<_> = dirs.iter().map(|dir| dir.as_path()).collect();
+        store::seal_existing(&dirs).is_ok() && audit::seal_existing().is_ok()
+    })
+}
+
+/// How long [`synth_shutdown`] waits for background threads.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Call before the host quits: cancels queued and running jobs, kills the
+/// `kiro-cli`, `llama-cli` and `curl` processes still running, waits up to
+/// five seconds for background threads (job worker, prefetches, webhook
+/// deliveries) and drops in-memory caches. Everything persisted is
+/// already on disk. Returns false if some thread was still running at the
+/// deadline.
+#[no_mangle]
+pub extern "C" fn synth_shutdown() -> bool {
+    ffi_guard(|| shutdown::shutdown(SHUTDOWN_TIMEOUT).clean())
+}
+
+/// Persist the document registry in `registry_dir` and load the versions
+/// recorded there. Without it the registry is kept for this run only.
+/// Returns false on error.
+#[no_mangle]
+pub extern "C" fn synth_registry_init(registry_dir: *const c_char) -> bool {
+    ffi_guard(|| match unsafe { path_arg(registry_dir) } {
+        Some(dir) => registry::init(&dir).is_ok(),
+        None => false,
+    })
+}
+
+/// Record an ingested file by its SHA-256. Returns JSON: `{"change":
+/// "new" | "unchanged" | "modified" | "moved", "version": {"document": 1,
+/// "version": 2, "path": "...", "sha256": "...", "size": 1024,
+/// "ingested": 1700000000}, "previous": {...} | null}`. Modified and moved
+/// files keep their `document` id with the next `version`, so indexes
+/// keyed by it can be rebuilt. Null on error.
+#[no_mangle]
+pub extern "C" fn synth_registry_record(path: *const c_char) -> *mut c_char {
+    ffi_guard(
+        || match unsafe { path_arg(path) }.map(|path| registry::record(&path)) {
+            Some(Ok(recorded)) => into_c_string(recorded.to_json().to_string()),
+            _ => std::ptr::null_mut(),
+        },
+    )
+}
+
+/// Compare a file with the registry without recording it. Returns
+/// `{"change": "...", "previous": {...} | null}`, where `previous` is the
+/// registered version it would follow, or null on error.
+#[no_mangle]
+pub extern "C" fn synth_registry_check(path: *const c_char) -> *mut c_char {
+    ffi_guard(
+        || match unsafe { path_arg(path) }.map(|path| registry::check(&path)) {
+            Some(Ok((change, previous))) => into_c_string(
+                Value::object([
+                    ("change", Value::from(change.name())),
+                    (
+                        "previous",
+                        previous
+                            .as_ref()
+                            .map_or(Value::Null, registry::Version::to_json),
+                    ),
+                ])
+                .to_string(),
+            ),
+            _ => std::ptr::null_mut(),
+        },
+    )
+}
+
+/// Every version of the document now at `path`, oldest first, as a JSON
+/// array (empty if it is not registered); null on invalid arguments.
+#[no_mangle]
+pub extern "C" fn synth_registry_history(path: *const c_char) -> *mut c_char {
+    ffi_guard(|| match unsafe { path_arg(path) } {
+        Some(path) => {
+            into_c_string(registry::versions_to_json(&registry::history(&path)).to_string())
+        }
+        None => std::ptr::null_mut(),
+    })
+}
+
+/// The latest version of every registered document, as a JSON array.
+#[no_mangle]
+pub extern "C" fn synth_registry_list() -> *mut c_char {
+    ffi_guard(|| into_c_string(registry::versions_to_json(&registry::documents()).to_string()))
+}
+
+/// Drop the document at `path` and all its versions. Returns false if
+/// none is registered there.
+#[no_mangle]
+pub extern "C" fn synth_registry_forget(path: *const c_char) -> bool {
+    ffi_guard(|| unsafe { path_arg(path) }.is_some_and(|path| registry::forget(&path)))
+}
+
+/// Persist long-term memories in `memory_dir` and load those saved there.
+/// Without it memories are kept for this run only. Returns false on error.
+#[no_mangle]
+pub extern "C" fn synth_memory_init(memory_dir: *const c_char) -> bool {
+    ffi_guard(|| match unsafe { path_arg(memory_dir) } {
+        Some(dir) => memory::init(&dir).is_ok(),
+        None => false,
+    })
+}
+
+/// Remember a fact, recalled for every caller, and return its id, or 0 if
+/// it is empty or already known. `source` may be null.
+#[no_mangle]
+pub extern "C" fn synth_memory_add(text: *const c_char, source: *const c_char) -> u64 {
+    ffi_guard(|| {
+        match unsafe { str_arg(text) }
+            .and_then(|text| memory::add(text, unsafe { str_arg(source) }, &usage::Tags::default()))
+        {
+            Some(memory) => memory.id,
+            None => 0,
+        }
+    })
+}
+
+/// Ask the backend for the facts worth remembering in `text` (a
+/// conversation or document) and store the new ones. `source` and
+/// `options_json` (as for [`synth_chat`]) may be null; the options'
+/// `user_id` and `tenant_id` limit who may recall the facts. Returns the
+/// added memories as JSON, or null on error.
+#[no_mangle]
+pub extern "C" fn synth_memory_extract(
+    text: *const c_char,
+    source: *const c_char,
+    options_json: *const c_char,
+) -> *mut c_char {
+    ffi_guard(|| {
+        let Some(text) = (unsafe { str_arg(text) }) else {
+            return std::ptr::null_mut();
+        };
+        let options = match unsafe { str_arg(options_json) }.map(json::parse) {
+            None => chat::ChatOptions::default(),
+            Some(Ok(value)) => chat::ChatOptions::from_json(&value),
+            Some(Err(_)) => return std::ptr::null_mut(),
+        };
+        let tags = options.tags();
+        match memory::extract(text, unsafe { str_arg(source) }, &tags, |prompt| {
+            chat::chat(prompt, &options)
+        }) {
+            Ok(added) => into_c_string(memory::memories_to_json(&added).to_string()),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Run the built-in invariant checks (strip_ansi, chunking) over random
+/// inputs and return `{"ok": true, "cases": 512, "seed": "...",
+/// "failures": [{"property": "...", "input": "...", "message": "..."}]}`.
+/// The seed varies per call; pass it to `selftest::run` to reproduce.
+#[no_mangle]
+pub extern "C" fn synth_self_test() -> *mut c_char {
+    ffi_guard(|| {
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(1, |d| d.as_nanos() as u64);
+        let cases = 512;
+        let failures = selftest::run(cases, seed);
+        into_c_string(selftest::report_json(cases, seed, &failures).to_string())
+    })
+}
+
+/// The caller's tags for the memory calls: `{"tenant_id": "...",
+/// "user_id": "..."}`, or `{}` for a caller without tags. Required, so
+/// no call sees every tenant's memories by default.
+///
+/// # Safety
+///
+/// As for [`str_arg`].
+unsafe fn scope_arg(scope_json: *const c_char) -> Option<usage::Tags> {
+    let value = json::parse(unsafe { str_arg(scope_json) }?).ok()?;
+    matches!(value, Value::Object(_)).then(|| usage::Tags::from_json(&value))
+}
+
+/// The memories a caller with the tags in `scope_json` may recall, as
+/// JSON: `[{"id": 1, "text": "...", "source": "..." | null, "created":
+/// 1700000000}]`, with `"tenant_id"` and `"user_id"` on those stored for
+/// particular callers. `scope_json` is `{"tenant_id": "...", "user_id":
+/// "..."}`, or `{}` for untagged memories only. Returns null if it is
+/// missing or not a JSON object.
+#[no_mangle]
+pub extern "C" fn synth_memory_list(scope_json: *const c_char) -> *mut c_char {
+    ffi_guard(|| match unsafe { scope_arg(scope_json) } {
+        Some(scope) => into_c_string(memory::memories_to_json(&memory::list(&scope)).to_string()),
+        None => std::ptr::null_mut(),
+    })
+}
+
+/// Up to `limit` memories most similar to `query` (0 for no limit) that
+/// the caller with the tags in `scope_json` (as for [`synth_memory_list`])
+/// may recall, as the list JSON with a `"score"` on each, or null on
+/// invalid input.
+#[no_mangle]
+pub extern "C" fn synth_memory_search(
+    query: *const c_char,
+    limit: u32,
+    scope_json: *const c_char,
+) -> *mut c_char {
+    ffi_guard(|| {
+        let (Some(query), Some(scope)) = (unsafe { (str_arg(query), scope_arg(scope_json)) })
+        else {
+            return std::ptr::null_mut();
+        };
+        let results = memory::search(query, limit as usize, &scope)
+            .into_iter()
+            .map(|(memory, score)| {
+                let mut value = memory.to_json();
+                if let Value::Object(pairs) = &mut value {
+                    pairs.push(("score".into(), Value::from(score)));
+                }
+                value
+            })
+            .collect();
+        into_c_string(Value::Array(results).to_string())
+    })
+}
+
+/// Replace a memory's text. `scope_json` (as for [`synth_memory_list`])
+/// must carry exactly the tags the memory was stored with. Returns false
+/// if the id is unknown in that scope.
+#[no_mangle]
+pub extern "C" fn synth_memory_update(
+    id: u64,
+    text: *const c_char,
+    scope_json: *const c_char,
+) -> bool {
+    ffi_guard(|| match unsafe { (str_arg(text), scope_arg(scope_json)) } {
+        (Some(text), Some(scope)) => memory::update(id, text, &scope),
+        _ => false,
+    })
+}
+
+/// Forget a memory. `scope_json` (as for [`synth_memory_list`]) must carry
+/// exactly the tags the memory was stored with. Returns false if the id is
+/// unknown in that scope.
+#[no_mangle]
+pub extern "C" fn synth_memory_delete(id: u64, scope_json: *const c_char) -> bool {
+    ffi_guard(|| unsafe { scope_arg(scope_json) }.is_some_and(|scope| memory::remove(id, &scope)))
+}
+
+/// Persist background jobs under `state_dir` and re-queue any that were
+/// unfinished when the app last exited. Call once at startup, before
+/// submitting jobs; without it jobs live in memory only. Returns false on
+/// error.
+#[no_mangle]
+pub extern "C" fn synth_jobs_init(state_dir: *const c_char) -> bool {
+    ffi_guard(|| match unsafe { path_arg(state_dir) } {
+        Some(dir) => jobs::init(&dir).is_ok(),
+        None => false,
+    })
+}
+
+/// Queue a background job and return its id, or 0 on invalid input.
+/// `kind` is "extract_text", "generate_minutes", "compare_corpus",
+/// "classify_document", "find_duplicates", "generate_documents",
+/// "summarize" ([`summarize_document`]) or "translate"
+/// ([`translate_document`]); `params_json` holds that function's
+/// arguments, e.g. `{"path": "...", "options": {"format": "markdown"}}`.
+#[no_mangle]
+pub extern "C" fn synth_job_submit(kind: *const c_char, params_json: *const c_char) -> u64 {
+    ffi_guard(|| {
+        let (Some(kind), Some(params)) = (
+            unsafe { str_arg(kind) },
+            unsafe { str_arg(params_json) }.and_then(|s| json::parse(s).ok()),
+        ) else {
+            return 0;
+        };
+        jobs::submit(kind, params).unwrap_or(0)
+    })
+}
+
+/// Current status of a job as JSON: `{"id": 1, "kind": "...", "state":
+/// "queued" | "running" | "done" | "failed" | "cancelled", "progress":
+/// {"completed": 0, "total": 0}, "error": "..."}`, or null if unknown.
+#[no_mangle]
+pub extern "C" fn synth_job_status(id: u64) -> *mut c_char {
+    ffi_guard(|| match jobs::status(id) {
+        Some(job) => into_c_string(job.status_json().to_string()),
+        None => std::ptr::null_mut(),
+    })
+}
+
+/// Cancel a queued or running job. Returns false if the job is unknown or
+/// already finished.
+#[no_mangle]
+pub extern "C" fn synth_job_cancel(id: u64) -> bool {
+    ffi_guard(|| jobs::cancel(id))
+}
+
+/// The output of a finished job (what the synchronous function would have
+/// returned), or null if the job is unknown or not done.
+#[no_mangle]
+pub extern "C" fn synth_job_result(id: u64) -> *mut c_char {
+    ffi_guard(|| match jobs::result(id) {
+        Some(result) => into_c_string(result),
+        None => std::ptr::null_mut(),
+    })
+}
+
+/// Put a policy in force for the rest of the process: a `.toml` or JSON
+/// file restricting readable directories, per-session token spend and
+/// backend profiles (see `synth_core::policy`). Returns false if the file
+/// is invalid or a policy is already in force, e.g. a managed one.
+#[no_mangle]
+pub extern "C" fn synth_policy_install(path: *const c_char) -> bool {
+    ffi_guard(|| {
+        unsafe { path_arg(path) }
+            .and_then(|path| policy::Policy::from_file(&path).ok())
+            .is_some_and(|loaded| policy::install(loaded).is_ok())
+    })
+}
+
+/// The policy in force as JSON (`{"source", "read": {"allow", "deny"},
+/// "session": {"max_tokens"}, "backends": {"deny"}}`), or null if none.
+#[no_mangle]
+pub extern "C" fn synth_policy_current() -> *mut c_char {
+    ffi_guard(|| match policy::current() {
+        Some(policy) => into_c_string(policy.to_json().to_string()),
+        None => std::ptr::null_mut(),
+    })
+}
+
+/// POST job completions (`job.done`, `job.failed`, `job.cancelled`) to a
+/// webhook, or stop when `config_json` is null. Config: `{"url":
+/// "https://...", "secret_env": "NAME", "events": ["job"], "retries": 3,
+/// "backoff_ms": 1000, "timeout_secs": 10}`; the secret, also accepted as
+/// `"secret"` or `"secret_host"` (asked of the secret provider), signs each
+/// delivery with HMAC-SHA256. Returns false on an invalid config.
+#[no_mangle]
+pub extern "C" fn synth_set_webhook(config_json: *const c_char) -> bool {
+    ffi_guard(|| {
+        if config_json.is_null() {
+            webhook::configure(None);
+            return true;
+        }
+        unsafe { str_arg(config_json) }
+            .and_then(|s| json::parse(s).ok())
+            .and_then(|value| webhook::Webhook::from_json(&value).ok())
+            .map(|hook| webhook::configure(Some(hook)))
+            .is_some()
+    })
+}
+
+/// List models installed in `models_dir` as JSON: `[{"name": "...",
+/// "file": "...", "url": "...", "sha256": "...", "size": 0, "path": "...",
+/// "present": true}]`, or null on error.
+#[no_mangle]
+pub extern "C" fn synth_models_list(models_dir: *const c_char) -> *mut c_char {
+    ffi_guard(
+        || match unsafe { path_arg(models_dir) }.map(|dir| models::list(&dir)) {
+            Some(Ok(list)) => into_c_string(list.to_string()),
+            _ => std::ptr::null_mut(),
+        },
+    )
+}
+
+/// Download a model into `models_dir`, verify its SHA-256 and record it.
+/// `model_json` is `{"name": "...", "url": "https://...", "sha256": "...",
+/// "file": "model.gguf", "size": 123}` (`file` and `size` optional).
+/// `progress` (may be null) receives bytes downloaded and expected bytes
+/// (0 when unknown), clamped to 32 bits. Blocks until done; returns the
+/// installed path, or null on error or hash mismatch.
+#[no_mangle]
+pub extern "C" fn synth_models_download(
+    models_dir: *const c_char,
+    model_json: *const c_char,
+    progress: Option<ProgressCallback>,
+    user_data: *mut c_void,
+) -> *mut c_char {
+    ffi_guard(|| {
+        let (Some(dir), Some(model)) = (
+            unsafe { path_arg(models_dir) },
+            unsafe { str_arg(model_json) }
+                .and_then(|s| json::parse(s).ok())
+                .and_then(|v| models::Model::from_json(&v).ok()),
+        ) else {
+            return std::ptr::null_mut();
+        };
+        let clamp = |n: u64| n.min(u32::MAX as u64) as u32;
+        let result = models::download(&dir, &model, |done, total| {
+            if let Some(callback) = progress {
+                callback(clamp(done), clamp(total), user_data);
+            }
+        });
+        match result {
+            Ok(path) => into_c_string(path.display().to_string()),
+            Err(_) => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Delete an installed model. Returns false if it is unknown or the
+/// deletion failed.
+#[no_mangle]
+pub extern "C" fn synth_models_remove(models_dir: *const c_char, name: *const c_char) -> bool {
+    ffi_guard(|| match unsafe { (path_arg(models_dir), str_arg(name)) } {
+        (Some(dir), Some(name)) => models::remove(&dir, name).unwrap_or(false),
+        _ => false,
+    })
+}
+
+/// Parse a JSON array of path strings.
+fn path_list(paths_json: &str) -> Option<Vec<std::path::PathBuf>> {
+    json::parse(paths_json)
+        .ok()?
+        .as_array()?
+        .iter()
+        .map(|v| v.as_str().map(|s| paths::long(s.into())))
+        .collect()
+}
@@ -0,0 +1,60 @@
+//! Paths received from the host, as raw bytes rather than UTF-8 text.
+//!
+//! On Unix any byte string is a valid path. Elsewhere paths must be UTF-8,
+//! and on Windows long absolute paths get the `\\?\` prefix so they are
+//! not limited to 260 characters.
+
+use std::path::PathBuf;
+
+/// The path spelled by `bytes`, or `None` if this platform cannot
+/// represent it.
+pub fn from_bytes(bytes: &[u8]) -> Option<PathBuf> {
+    if bytes.is_empty() {
+        return None;
+    }
+    #[cfg(unix)]
+    let path = {
+        use std::os::unix::ffi::OsStrExt;
+        PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
+    };
+    #[cfg(not(unix))]
+    let path = PathBuf::from(std::str::from_utf8(bytes).ok()?);
+    Some(long(path))
+}
+
+/// Spell an absolute path over the legacy `MAX_PATH` limit in verbatim form
+/// (`\\?\C:\...` or `\\?\UNC\server\share\...`). Short and relative paths
+/// are returned unchanged.
+#[cfg(windows)]
+pub fn long(path: PathBuf) -> PathBuf {
+    const MAX_PATH: usize = 260;
+    let text = path.to_string_lossy().replace('/', "\\");
+    if text.len() < MAX_PATH || text.starts_with(r"\\?\") || !path.is_absolute() {
+        return path;
+    }
+    // Verbatim paths are not normalized, so separators must already be
+    // backslashes and `.`/`..` must be resolved.
+    let (prefix, rest) = match text.strip_prefix(r"\\") {
+        Some(unc) => (r"\\?\UNC\", unc),
+        None => (r"\\?\", text.as_str()),
+    };
+    let mut parts: Vec<&str> = Vec::new();
+    for part in rest.split('\\') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                if parts.len() > 1 {
+                    parts.pop();
+                }
+            }
+            part => parts.push(part),
+        }
+    }
+    PathBuf::from(format!("{prefix}{}", parts.join("\\")))
+}
+
+/// Paths have no length limit to work around here.
+#[cfg(not(windows))]
+pub fn long(path: PathBuf) -> PathBuf {
+    path
+}
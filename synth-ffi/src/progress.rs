@@ -0,0 +1,26 @@
+//! Forwarding pipeline progress to host callbacks.
+
+use std::ffi::{c_void, CString};
+
+use synth_core::progress::Progress;
+
+use crate::StageCallback;
+
+/// Forward a report to an optional host callback.
+pub(crate) fn notify(callback: Option<StageCallback>, user_data: *mut c_void, progress: &Progress) {
+    let Some(callback) = callback else {
+        return;
+    };
+    let stage = CString::new(progress.stage.name()).unwrap_or_default();
+    let item = progress
+        .item
+        .and_then(|item| CString::new(item.replace('\0', "")).ok());
+    callback(
+        stage.as_ptr(),
+        progress.completed as u32,
+        progress.total as u32,
+        progress.percent,
+        item.as_ref().map_or(std::ptr::null(), |item| item.as_ptr()),
+        user_data,
+    );
+}
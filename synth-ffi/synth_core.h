@@ -0,0 +1,141 @@
+#ifndef SYNTH_CORE_H
+#define SYNTH_CORE_H
+
+#include <stdbool.h>
+#include <stddef.h>
+
+typedef void (*synth_progress_cb)(unsigned int completed, unsigned int total, void* user_data);
+/* stage is "extracting", "chunking", "generating" or "writing"; item may be NULL. */
+typedef void (*synth_stage_cb)(const char* stage, unsigned int completed, unsigned int total,
+                               double percent, const char* item, void* user_data);
+/* Return a replacement string, or NULL to leave the text unchanged. */
+typedef const char* (*synth_hook_cb)(const char* text, void* user_data);
+typedef void (*synth_release_cb)(const char* text, void* user_data);
+
+char* kiro_chat(const char* prompt);
+char* synth_chat(const char* prompt, const char* options_json);
+char* synth_ask(const char* path, const char* question, const char* options_json);
+bool synth_configure_backends(const char* config_json);
+typedef void (*synth_event_cb)(const char* event_json, void* user_data);
+void synth_set_telemetry(synth_event_cb exporter, void* user_data);
+typedef const char* (*synth_secret_cb)(const char* name, void* user_data);
+void synth_set_secret_provider(synth_secret_cb provider, synth_release_cb release, void* user_data);
+char* synth_last_backend(void);
+char* synth_last_panic(void);
+char* kiro_chat_json(const char* prompt, const char* schema_json, unsigned int max_retries);
+unsigned long long synth_register_hook(const char* stage, synth_hook_cb hook, synth_release_cb release, void* user_data);
+bool synth_unregister_hook(unsigned long long id);
+char* extract_outline(const char* path);
+char* extract_text(const char* path);
+bool synth_prefetch(const char* path);
+char* extract_text_live(const char* path, synth_event_cb on_delta, void* user_data);
+char* extract_text_bytes(const unsigned char* path, size_t len);
+char* extract_text_fd(int fd, const char* name);
+char* extract_url(const char* url);
+char* ingest_text(const char* text, const char* title);
+char* extract_table(const char* path, const char* style, unsigned int max_rows);
+char* extract_structured(const char* path, const char* style, unsigned int max_depth, unsigned int max_chars);
+char* extract_slides(const char* path, const char* content);
+bool synth_ocr_configure(const char* config_json);
+bool synth_transcribe_configure(const char* config_json);
+char* transcribe(const char* path);
+bool synth_embeddings_configure(const char* config_json);
+char* embed(const char* texts_json);
+char* chunk_code(const char* path, unsigned int max_lines);
+char* generate_documents(const char* template_path, const char* records_json, const char* out_dir,
+                         synth_progress_cb progress, void* user_data);
+bool write_pdf(const char* content, const char* out_path);
+char* generate_minutes(const char* transcript_path, const char* options_json,
+                       synth_stage_cb progress, void* user_data);
+char* summarize_document(const char* path, const char* options_json,
+                         synth_stage_cb progress, void* user_data);
+char* translate_document(const char* path, const char* options_json,
+                         synth_stage_cb progress, void* user_data);
+bool index_document(const char* path, synth_stage_cb progress, void* user_data);
+bool synth_docx_edit(const char* path, const char* edits_json, const char* out_path);
+char* extract_docx(const char* path, const char* options_json);
+char* docx_locate(const char* path, size_t start, size_t end);
+char* extract_pdf(const char* path, const char* options_json);
+bool synth_pdf_annotate(const char* path, const char* marks_json, const char* out_path);
+bool merge_documents(const char* paths_json, const char* out_path);
+char* draft_in_style(const char* path, const char* instruction, const char* options_json);
+char* compare_corpus(const char* paths_json, const char* criteria,
+                     synth_stage_cb progress, void* user_data);
+char* classify_document(const char* path, const char* labels_json, const char* method);
+char* find_duplicates(const char* paths_json, double threshold);
+char* extract_keywords(const char* path, unsigned int top_n);
+char* document_stats(const char* path);
+char* concordance(const char* path, const char* term, unsigned int window);
+char* word_frequencies(const char* path, bool content_only, unsigned int top_n);
+char* segment_text(const char* text, const char* mode);
+char* normalize_text(const char* text, const char* profile);
+char* extract_text_normalized(const char* path, const char* profiles_json);
+bool synth_define_normalization(const char* profile_json);
+bool synth_glossary_define(const char* name, const char* glossary_json);
+bool synth_glossary_remove(const char* name);
+char* synth_glossary_check(const char* name, const char* source, const char* output);
+char* reanchor_annotations(const char* old_text, const char* new_path, const char* annotations_json);
+char* scan_injection(const char* text, bool neutralize);
+char* extract_selection(const char* path, const char* options_json);
+unsigned long long synth_session_new(const char* options_json);
+char* synth_session_send(unsigned long long handle, const char* message);
+char* synth_session_regenerate(unsigned long long handle);
+unsigned long long synth_session_branch(unsigned long long handle, unsigned int turns);
+char* synth_session_history(unsigned long long handle);
+char* synth_session_list(const char* filter_json);
+bool synth_session_close(unsigned long long handle);
+unsigned long long synth_project_create(const char* root_dir, const char* name);
+unsigned long long synth_project_open(const char* root_dir, const char* name);
+char* synth_project_list(const char* root_dir);
+bool synth_project_delete(const char* root_dir, const char* name);
+bool synth_project_close(unsigned long long handle);
+char* synth_project_info(unsigned long long handle);
+bool synth_project_add_document(unsigned long long handle, const char* path);
+bool synth_project_remove_document(unsigned long long handle, const char* path);
+char* synth_project_extract(unsigned long long handle, const char* path);
+bool synth_project_save_session(unsigned long long handle, const char* name, unsigned long long session_handle);
+unsigned long long synth_project_load_session(unsigned long long handle, const char* name);
+bool synth_project_export(unsigned long long handle, const char* archive_path);
+unsigned long long synth_project_import(const char* archive_path, const char* root_dir);
+bool synth_audit_configure(const char* config_json);
+char* synth_audit_query(const char* filter_json);
+char* synth_usage(const char* filter_json);
+bool synth_usage_reset(const char* filter_json);
+
+char* synth_self_test(void);
+bool synth_shutdown(void);
+
+bool synth_reply_cache_init(const char* cache_dir);
+void synth_reply_cache_clear(void);
+bool synth_db_configure(const char* config_json);
+bool synth_encryption_configure(const char* config_json);
+bool synth_encryption_seal_existing(const char* dirs_json);
+
+bool synth_registry_init(const char* registry_dir);
+char* synth_registry_record(const char* path);
+char* synth_registry_check(const char* path);
+char* synth_registry_history(const char* path);
+char* synth_registry_list(void);
+bool synth_registry_forget(const char* path);
+bool synth_memory_init(const char* memory_dir);
+unsigned long long synth_memory_add(const char* text, const char* source);
+char* synth_memory_extract(const char* text, const char* source, const char* options_json);
+char* synth_memory_list(const char* scope_json);
+char* synth_memory_search(const char* query, unsigned int limit, const char* scope_json);
+bool synth_memory_update(unsigned long long id, const char* text, const char* scope_json);
+bool synth_memory_delete(unsigned long long id, const char* scope_json);
+bool synth_jobs_init(const char* state_dir);
+unsigned long long synth_job_submit(const char* kind, const char* params_json);
+char* synth_job_status(unsigned long long id);
+bool synth_job_cancel(unsigned long long id);
+char* synth_job_result(unsigned long long id);
+bool synth_set_webhook(const char* config_json);
+bool synth_policy_install(const char* path);
+char* synth_policy_current(void);
+char* synth_models_list(const char* models_dir);
+char* synth_models_download(const char* models_dir, const char* model_json,
+                            synth_progress_cb progress, void* user_data);
+bool synth_models_remove(const char* models_dir, const char* name);
+void free_string(char* s);
+
+#endif
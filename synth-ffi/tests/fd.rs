@@ -0,0 +1,49 @@
+//! Extracting from a descriptor the host opened: read from the start, and
+//! left open where it was.
+
+use std::ffi::{CStr, CString};
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::os::fd::AsRawFd;
+
+use synth_ffi::{extract_text_fd, free_string};
+
+fn take(ptr: *mut std::os::raw::c_char) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+    let text = unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_string();
+    free_string(ptr);
+    Some(text)
+}
+
+#[test]
+fn descriptors_are_read_whole_and_left_open() {
+    let dir = std::env::temp_dir().join(format!("synth-fd-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("sheet.bin");
+    fs::write(&path, "name,days\nAcme,30\n").unwrap();
+    let mut file = File::open(&path).unwrap();
+    file.seek(SeekFrom::Start(5)).unwrap();
+    let fd = file.as_raw_fd();
+
+    // Without a name the contents are plain text.
+    let plain = take(extract_text_fd(fd, std::ptr::null())).unwrap();
+    assert!(plain.starts_with("name,days"), "{plain}");
+    // A file name or bare extension picks the format.
+    for name in ["sheet.csv", "csv"] {
+        let name = CString::new(name).unwrap();
+        let table = take(extract_text_fd(fd, name.as_ptr())).unwrap();
+        assert!(table.contains("| Acme | 30 |"), "{table}");
+    }
+    let unknown = CString::new("sheet.xyz").unwrap();
+    assert_eq!(take(extract_text_fd(fd, unknown.as_ptr())), None);
+
+    // The host's descriptor keeps its offset and stays open.
+    let mut rest = String::new();
+    file.read_to_string(&mut rest).unwrap();
+    assert_eq!(rest, "days\nAcme,30\n");
+    assert_eq!(take(extract_text_fd(-1, std::ptr::null())), None);
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,45 @@
+//! Panics inside an entry point fail the call instead of unwinding into
+//! the host.
+
+use std::ffi::{CStr, CString};
+use std::sync::Arc;
+
+use synth_core::hooks::{self, Stage};
+use synth_ffi::{free_string, synth_chat, synth_configure_backends, synth_last_panic};
+
+fn take(ptr: *mut std::os::raw::c_char) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+    let text = unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_string();
+    free_string(ptr);
+    Some(text)
+}
+
+#[test]
+fn a_panicking_call_returns_null_and_keeps_the_message() {
+    let config =
+        CString::new(r#"{"profiles": {"m": {"type": "mock", "default": "fine"}}}"#).unwrap();
+    assert!(synth_configure_backends(config.as_ptr()));
+    let hook = hooks::register(
+        Stage::Pre,
+        Arc::new(|prompt: &str| {
+            if prompt.contains("EXPLODE") {
+                panic!("hook exploded");
+            }
+            prompt.to_string()
+        }),
+    );
+    assert_eq!(take(synth_last_panic()), None);
+
+    let prompt = CString::new("EXPLODE").unwrap();
+    assert_eq!(take(synth_chat(prompt.as_ptr(), std::ptr::null())), None);
+    assert_eq!(take(synth_last_panic()).as_deref(), Some("hook exploded"));
+
+    let prompt = CString::new("hello").unwrap();
+    assert_eq!(
+        take(synth_chat(prompt.as_ptr(), std::ptr::null())).as_deref(),
+        Some("fine")
+    );
+    hooks::unregister(hook);
+}
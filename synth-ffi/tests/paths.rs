@@ -0,0 +1,45 @@
+//! Paths that are not UTF-8 reach the file system unchanged.
+
+use std::ffi::{CStr, CString, OsStr};
+use std::fs;
+use std::os::unix::ffi::OsStrExt;
+
+use synth_ffi::{extract_text, extract_text_bytes, free_string};
+
+fn take(ptr: *mut std::os::raw::c_char) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+    let text = unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_string();
+    free_string(ptr);
+    Some(text)
+}
+
+#[test]
+fn non_utf8_paths_are_read_as_bytes() {
+    let dir = std::env::temp_dir().join(format!("synth-paths-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    // "notes-é.txt" with the é in Latin-1, as some older file systems hold it.
+    let mut name = b"notes-".to_vec();
+    name.push(0xe9);
+    name.extend_from_slice(b".txt");
+    let path = dir.join(OsStr::from_bytes(&name));
+    fs::write(&path, "Latin-1 name.").unwrap();
+    let bytes = path.as_os_str().as_bytes();
+    assert!(std::str::from_utf8(bytes).is_err());
+
+    let text = take(extract_text_bytes(bytes.as_ptr(), bytes.len()));
+    assert_eq!(text.as_deref().map(str::trim), Some("Latin-1 name."));
+    let c_path = CString::new(bytes).unwrap();
+    let text = take(extract_text(c_path.as_ptr()));
+    assert_eq!(text.as_deref().map(str::trim), Some("Latin-1 name."));
+
+    // The length bounds the path; no NUL terminator is read.
+    let padded = [bytes, b"junk"].concat();
+    let text = take(extract_text_bytes(padded.as_ptr(), bytes.len()));
+    assert_eq!(text.as_deref().map(str::trim), Some("Latin-1 name."));
+    assert_eq!(take(extract_text_bytes(padded.as_ptr(), 0)), None);
+    assert_eq!(take(extract_text_bytes(std::ptr::null(), 4)), None);
+    let _ = fs::remove_dir_all(&dir);
+}